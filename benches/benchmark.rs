@@ -1,4 +1,9 @@
-use arloader::{bundle::DataItem, error::Error, status::Status, Arweave};
+use arloader::{
+    bundle::{DataItem, SignatureType},
+    error::Error,
+    status::Status,
+    Arweave,
+};
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 use rand::Rng;
 use rayon::prelude::*;
@@ -31,7 +36,9 @@ fn create_data_items(data: Vec<Vec<u8>>) -> Vec<(DataItem, Status)> {
     .unwrap();
     data.into_par_iter()
         .map(|d| {
-            let data_item = arweave.create_data_item(d, Vec::new(), false).unwrap();
+            let data_item = arweave
+                .create_data_item(d, Vec::new(), false, true, SignatureType::Arweave)
+                .unwrap();
             (
                 arweave.sign_data_item(data_item).unwrap(),
                 Status {
@@ -82,7 +89,13 @@ fn benchmarks(c: &mut Criterion) {
             |b, _| {
                 b.iter(|| {
                     let data_item = arweave
-                        .create_data_item(data.clone(), Vec::new(), false)
+                        .create_data_item(
+                            data.clone(),
+                            Vec::new(),
+                            false,
+                            true,
+                            SignatureType::Arweave,
+                        )
                         .unwrap();
                     let _ = arweave.sign_data_item(data_item);
                 })
@@ -112,7 +125,19 @@ fn benchmarks(c: &mut Criterion) {
                 )
             },
         );
-        let (bundle, _) = black_box(arweave.create_bundle_from_data_items(data_items).unwrap());
+        group.bench_with_input(
+            BenchmarkId::new("serialize_data_items", file_size * num_files),
+            &file_size,
+            |b, _| {
+                b.iter(|| {
+                    data_items
+                        .iter()
+                        .map(|(d, _)| d.serialize().unwrap())
+                        .collect::<Vec<Vec<u8>>>()
+                })
+            },
+        );
+        let (bundle, _, _) = black_box(arweave.create_bundle_from_data_items(data_items).unwrap());
         group.bench_with_input(
             BenchmarkId::new("create_transaction_from_bundle", file_size * num_files),
             &file_size,
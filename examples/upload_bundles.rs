@@ -50,6 +50,7 @@ async fn main() -> CommandResult {
             REWARD_MULTIPLIER,
             output_format,
             BUFFER,
+            None,
         )
         .await?;
     } else {
@@ -62,6 +63,7 @@ async fn main() -> CommandResult {
             output_format,
             BUFFER,
             sol_keypair_path.unwrap(),
+            None,
         )
         .await?;
     }
@@ -73,7 +75,7 @@ async fn main() -> CommandResult {
         duration
     );
 
-    command_update_bundle_statuses(&arweave, log_dir, output_format, 10).await?;
+    command_update_bundle_statuses(&arweave, log_dir, output_format, 10, None).await?;
     Ok(())
 }
 
@@ -50,6 +50,7 @@ async fn main() -> CommandResult {
             REWARD_MULTIPLIER,
             output_format,
             BUFFER,
+            None,
         )
         .await?;
     } else {
@@ -63,6 +64,7 @@ async fn main() -> CommandResult {
             output_format,
             BUFFER,
             sol_keypair_path.unwrap(),
+            None,
         )
         .await?;
     }
@@ -46,6 +46,8 @@ async fn main() -> CommandResult {
             5,
             sol_keypair_path,
             true,
+            false,
+            false,
         )
         .await?;
     }
@@ -0,0 +1,361 @@
+//! Audits of the transactions posted by a wallet, read from the GraphQL endpoint of a gateway,
+//! with rewards summed by the upload run or client that posted them.
+
+use crate::{error::Error, WINSTONS_PER_AR};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+/// Transactions requested per GraphQL page.
+pub const AUDIT_PAGE_SIZE: usize = 100;
+
+/// Tag that transactions are grouped by, falling back to `User-Agent` if they don't have it.
+pub const RUN_ID_TAG: &str = "Arloader-Run-Id";
+
+/// Start of an audit, `--since`, parsed from either a date, e.g., `2024-01-01`, taken as midnight
+/// UTC, or an RFC 3339 time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AuditSince(pub DateTime<Utc>);
+
+impl FromStr for AuditSince {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(midnight) = NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .ok()
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+        {
+            return Ok(Self(Utc.from_utc_datetime(&midnight)));
+        }
+        DateTime::parse_from_rfc3339(s)
+            .map(|t| Self(t.with_timezone(&Utc)))
+            .map_err(|_| Error::InvalidAuditSince(s.to_string()))
+    }
+}
+
+/// Transaction posted by the audited wallet.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AuditTransaction {
+    pub id: String,
+    pub reward: u64,
+    /// Unix time of the block the transaction was mined in, or `None` while it's pending.
+    pub timestamp: Option<i64>,
+    /// Value of the [`RUN_ID_TAG`] or `User-Agent` tag, or empty if it has neither.
+    pub group: String,
+}
+
+/// Count and summed reward of the transactions in an audit with the same group.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AuditGroup {
+    pub group: String,
+    pub transactions: usize,
+    pub reward: u64,
+}
+
+/// Page of GraphQL results, newest transactions first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuditPage {
+    pub transactions: Vec<AuditTransaction>,
+    /// Cursor to request the next page after, or `None` if this is the last page.
+    pub next_cursor: Option<String>,
+}
+
+impl AuditPage {
+    /// Parses the response to an [`audit_query`], or returns `None` if it isn't one, e.g., if it
+    /// has `errors` instead of `data`.
+    pub fn from_response(response: &Value) -> Option<Self> {
+        let connection = &response["data"]["transactions"];
+        let edges = connection["edges"].as_array()?;
+        let mut transactions = Vec::with_capacity(edges.len());
+        for edge in edges {
+            let node = &edge["node"];
+            let tag = |name: &str| {
+                node["tags"]
+                    .as_array()?
+                    .iter()
+                    .find_map(|t| (t["name"].as_str()? == name).then(|| t["value"].as_str())?)
+            };
+            transactions.push(AuditTransaction {
+                id: node["id"].as_str()?.to_string(),
+                reward: node["fee"]["winston"].as_str()?.parse().ok()?,
+                timestamp: node["block"]["timestamp"].as_i64(),
+                group: tag(RUN_ID_TAG)
+                    .or_else(|| tag("User-Agent"))
+                    .unwrap_or_default()
+                    .to_string(),
+            });
+        }
+        // A page without edges has no cursor to continue after, so it's the last one even if
+        // the gateway says there are more.
+        let next_cursor = match (
+            connection["pageInfo"]["hasNextPage"].as_bool()?,
+            edges.last(),
+        ) {
+            (true, Some(edge)) => Some(edge["cursor"].as_str()?.to_string()),
+            _ => None,
+        };
+        Some(Self {
+            transactions,
+            next_cursor,
+        })
+    }
+}
+
+/// GraphQL request for a page of the transactions owned by `address`, newest first, starting
+/// after `cursor` if provided. Data items in bundles are left out with `bundledIn: null`, since
+/// their rewards are paid by the bundles they're in.
+pub fn audit_query(address: &str, cursor: Option<&str>) -> Value {
+    json!({
+        "query": "query($owners: [String!], $first: Int, $after: String) { \
+            transactions(owners: $owners, first: $first, after: $after, sort: HEIGHT_DESC, \
+            bundledIn: null) { \
+            pageInfo { hasNextPage } \
+            edges { cursor node { id fee { winston } block { timestamp } tags { name value } } } } }",
+        "variables": {
+            "owners": [address],
+            "first": AUDIT_PAGE_SIZE,
+            "after": cursor,
+        }
+    })
+}
+
+/// Transactions posted by a wallet since a time, see [`crate::Arweave::audit_wallet`].
+/// Displayed as a table of groups, and written as csv by transaction with
+/// [`WalletAudit::to_csv`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct WalletAudit {
+    pub address: String,
+    pub since: DateTime<Utc>,
+    pub transactions: Vec<AuditTransaction>,
+}
+
+impl WalletAudit {
+    pub fn new(address: &str, since: DateTime<Utc>) -> Self {
+        Self {
+            address: address.to_string(),
+            since,
+            transactions: Vec::new(),
+        }
+    }
+
+    /// Adds the transactions of `page` mined since [`WalletAudit::since`], or still pending.
+    /// Returns whether later pages may have more, which is the case until a page reaches
+    /// transactions mined before then.
+    pub fn add_page(&mut self, page: AuditPage) -> bool {
+        let since = self.since.timestamp();
+        let mut more = page.next_cursor.is_some();
+        for transaction in page.transactions {
+            match transaction.timestamp {
+                Some(timestamp) if timestamp < since => more = false,
+                _ => self.transactions.push(transaction),
+            }
+        }
+        more
+    }
+
+    pub fn reward(&self) -> u64 {
+        self.transactions.iter().map(|t| t.reward).sum()
+    }
+
+    /// Transactions grouped by [`AuditTransaction::group`], sorted by group.
+    pub fn groups(&self) -> Vec<AuditGroup> {
+        let mut groups = BTreeMap::<&str, (usize, u64)>::new();
+        for transaction in &self.transactions {
+            let group = groups.entry(&transaction.group).or_default();
+            group.0 += 1;
+            group.1 += transaction.reward;
+        }
+        groups
+            .into_iter()
+            .map(|(group, (transactions, reward))| AuditGroup {
+                group: group.to_string(),
+                transactions,
+                reward,
+            })
+            .collect()
+    }
+
+    /// Summary with the groups and transactions of the audit.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "address": self.address,
+            "since": self.since.to_rfc3339(),
+            "count": self.transactions.len(),
+            "reward": self.reward(),
+            "groups": self.groups(),
+            "transactions": self.transactions,
+        })
+    }
+
+    /// One line per transaction with a header, `id,timestamp,group,reward`, timestamps empty
+    /// for pending transactions and rewards in winstons.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("id,timestamp,group,reward\n");
+        for transaction in &self.transactions {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                transaction.id,
+                transaction
+                    .timestamp
+                    .map(|t| t.to_string())
+                    .unwrap_or_default(),
+                csv_field(&transaction.group),
+                transaction.reward
+            ));
+        }
+        csv
+    }
+}
+
+impl fmt::Display for WalletAudit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Transactions posted by {} since {}:\n",
+            self.address,
+            self.since.to_rfc3339()
+        )?;
+        writeln!(
+            f,
+            " {:<48} {:>12} {:>20}",
+            "group", "transactions", "reward (AR)"
+        )?;
+        writeln!(f, "{:-<83}", "")?;
+        for group in self.groups() {
+            let name = match group.group.as_str() {
+                "" => "(untagged)",
+                name => name,
+            };
+            writeln!(
+                f,
+                " {:<48} {:>12} {:>20.12}",
+                name,
+                group.transactions,
+                group.reward as f64 / WINSTONS_PER_AR as f64
+            )?;
+        }
+        writeln!(f, "{:-<83}", "")?;
+        writeln!(
+            f,
+            " {:<48} {:>12} {:>20.12}",
+            "total",
+            self.transactions.len(),
+            self.reward() as f64 / WINSTONS_PER_AR as f64
+        )
+    }
+}
+
+/// Quotes `value` if it has commas, quotes or line breaks, doubling any quotes.
+fn csv_field(value: &str) -> String {
+    if value.contains(|c| matches!(c, ',' | '"' | '\n' | '\r')) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{audit_query, AuditGroup, AuditPage, AuditSince, WalletAudit};
+    use crate::error::Error;
+    use chrono::{DateTime, Utc};
+    use matches::assert_matches;
+    use serde_json::{json, Value};
+    use std::str::FromStr;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::<Utc>::from_str(s).unwrap()
+    }
+
+    fn fixture_page(i: usize) -> AuditPage {
+        let response: Value = serde_json::from_str(
+            &std::fs::read_to_string(format!("tests/fixtures/graphql_audit_page{}.json", i))
+                .unwrap(),
+        )
+        .unwrap();
+        AuditPage::from_response(&response).unwrap()
+    }
+
+    #[test]
+    fn test_audit_since() -> Result<(), Error> {
+        assert_eq!(
+            AuditSince::from_str("2024-01-01")?.0,
+            utc("2024-01-01T00:00:00Z")
+        );
+        assert_eq!(
+            AuditSince::from_str("2024-01-01T12:00:00+02:00")?.0,
+            utc("2024-01-01T10:00:00Z")
+        );
+        assert_matches!(
+            AuditSince::from_str("01/01/2024"),
+            Err(Error::InvalidAuditSince(s)) if s == "01/01/2024"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_wallet_audit() -> Result<(), Error> {
+        let query = audit_query("address", Some("cursor"));
+        assert_eq!(
+            query["variables"],
+            json!({"owners": ["address"], "first": 100, "after": "cursor"})
+        );
+        assert!(query["query"].as_str().unwrap().contains("bundledIn: null"));
+
+        let page0 = fixture_page(0);
+        assert_eq!(page0.next_cursor.as_deref(), Some("cursor2"));
+        let page1 = fixture_page(1);
+        assert_eq!(page1.next_cursor.as_deref(), Some("cursor5"));
+
+        // The last transaction of the second page was mined before the start of the audit.
+        let mut audit = WalletAudit::new("address", utc("2024-01-01T00:00:00Z"));
+        assert!(audit.add_page(page0));
+        assert!(!audit.add_page(page1));
+        assert_eq!(audit.transactions.len(), 5);
+        assert_eq!(audit.reward(), 1_500_000_000_000);
+        assert_eq!(
+            audit.groups(),
+            vec![
+                AuditGroup {
+                    group: "".to_string(),
+                    transactions: 1,
+                    reward: 100_000_000_000,
+                },
+                AuditGroup {
+                    group: "arloader/0.1.63".to_string(),
+                    transactions: 1,
+                    reward: 200_000_000_000,
+                },
+                AuditGroup {
+                    group: "run-a".to_string(),
+                    transactions: 2,
+                    reward: 700_000_000_000,
+                },
+                AuditGroup {
+                    group: "run-b".to_string(),
+                    transactions: 1,
+                    reward: 500_000_000_000,
+                },
+            ]
+        );
+        assert_eq!(audit.to_json()["reward"], 1_500_000_000_000_u64);
+
+        let csv = audit.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0], "id,timestamp,group,reward");
+        assert_eq!(lines[1], "tx0,,run-a,300000000000");
+        assert_eq!(lines[2], "tx1,1704153600,run-a,400000000000");
+
+        assert!(AuditPage::from_response(&json!({"errors": [{"message": "bad"}]})).is_none());
+
+        // An empty page ends the audit, even if it says there are more.
+        let empty = AuditPage::from_response(&json!({
+            "data": {"transactions": {"pageInfo": {"hasNextPage": true}, "edges": []}}
+        }))
+        .unwrap();
+        assert_eq!(empty.next_cursor, None);
+        assert!(!WalletAudit::new("address", utc("2024-01-01T00:00:00Z")).add_page(empty));
+        Ok(())
+    }
+}
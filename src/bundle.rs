@@ -1,29 +1,388 @@
 //! Data structure and functionality to create, serialize and deserialize [`DataItem`]s.
 
+use crate::crypto::Hasher;
 use crate::error::Error;
-use crate::transaction::{Base64, DeepHashItem, Tag, ToItems};
+use crate::status::Status;
+use crate::transaction::{Base64, DeepHashItem, FromUtf8Strs, Tag, ToItems};
 use avro_rs::Schema;
 use bytes::BufMut;
+use once_cell::sync::Lazy;
+use ring::rand::{SecureRandom, SystemRandom};
 use serde::{Deserialize, Serialize};
-use std::io::Write;
-
-/// Returns [`avro_rs::Schema`] for [`DataItem`] [`Tag`]s.
-pub fn get_tags_schema() -> Schema {
-    let schema = r#"
-        {
-            "type": "array",
-            "items": {
-                "type": "record",
-                "name": "tag",
-                "fields": [
-                    {"name": "name", "type": "string"},
-                    {"name": "value", "type": "string"}
-                ]
-            }
+use std::fs as fsSync;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use tokio::{
+    fs,
+    io::{AsyncReadExt, AsyncSeekExt},
+};
+
+const TAGS_SCHEMA_STR: &str = r#"
+    {
+        "type": "array",
+        "items": {
+            "type": "record",
+            "name": "tag",
+            "fields": [
+                {"name": "name", "type": "string"},
+                {"name": "value", "type": "string"}
+            ]
+        }
+    }
+"#;
+
+static TAGS_SCHEMA: Lazy<Schema> = Lazy::new(|| Schema::parse_str(TAGS_SCHEMA_STR).unwrap());
+
+/// Returns [`avro_rs::Schema`] for [`DataItem`] [`Tag`]s. The schema is parsed once and cached.
+pub fn get_tags_schema() -> &'static Schema {
+    &TAGS_SCHEMA
+}
+
+/// Maximum number of [`Tag`]s on a [`DataItem`] per ANS-104.
+pub const MAX_TAGS: usize = 128;
+
+/// Maximum length in bytes of a [`DataItem`] tag name per ANS-104.
+pub const MAX_TAG_NAME_LEN: usize = 1024;
+
+/// Maximum length in bytes of a [`DataItem`] tag value per ANS-104.
+pub const MAX_TAG_VALUE_LEN: usize = 3072;
+
+/// Returns [`Error::TagLimitExceeded`] if the name or value of `tag` is longer than ANS-104
+/// allows.
+pub fn check_tag(tag: &Tag<String>) -> Result<(), Error> {
+    let exceeded = |field: &str, len: usize, max: usize| {
+        Error::TagLimitExceeded(format!(
+            "tag {} of {} is {} bytes, the maximum is {}",
+            field,
+            tag_excerpt(&tag.name),
+            len,
+            max
+        ))
+    };
+    if tag.name.len() > MAX_TAG_NAME_LEN {
+        return Err(exceeded("name", tag.name.len(), MAX_TAG_NAME_LEN));
+    }
+    if tag.value.len() > MAX_TAG_VALUE_LEN {
+        return Err(exceeded("value", tag.value.len(), MAX_TAG_VALUE_LEN));
+    }
+    Ok(())
+}
+
+/// Checks each of `tags` with [`check_tag`] and that there are no more than [`MAX_TAGS`].
+pub fn check_tags(tags: &[Tag<String>]) -> Result<(), Error> {
+    if tags.len() > MAX_TAGS {
+        return Err(Error::TagLimitExceeded(format!(
+            "{} tags, the maximum is {}",
+            tags.len(),
+            MAX_TAGS
+        )));
+    }
+    tags.iter().try_for_each(check_tag)
+}
+
+/// First characters of a tag name for error messages, since names can be long.
+fn tag_excerpt(name: &str) -> String {
+    const EXCERPT_LEN: usize = 32;
+    if name.chars().count() > EXCERPT_LEN {
+        format!(
+            "\"{}...\"",
+            name.chars().take(EXCERPT_LEN).collect::<String>()
+        )
+    } else {
+        format!("\"{}\"", name)
+    }
+}
+
+/// Avro encodes [`DataItem`] [`Tag`]s.
+pub fn encode_tags(tags: &[Tag<String>]) -> Result<Vec<u8>, Error> {
+    let value = avro_rs::to_value(tags)?;
+    let tags_bytes = avro_rs::to_avro_datum(get_tags_schema(), value)?;
+    Ok(tags_bytes)
+}
+
+/// Returns the leading [`Tag`]s shared by all of the provided tag sets.
+pub fn shared_tags_prefix<'a, I>(mut tag_sets: I) -> Vec<Tag<String>>
+where
+    I: Iterator<Item = &'a Vec<Tag<String>>>,
+{
+    let first = match tag_sets.next() {
+        Some(first) => first,
+        None => return Vec::new(),
+    };
+    let shared_len = tag_sets.fold(first.len(), |len, tags| {
+        first
+            .iter()
+            .zip(tags.iter())
+            .take(len)
+            .take_while(|(a, b)| a == b)
+            .count()
+    });
+    first[..shared_len].to_vec()
+}
+
+/// Pre-encodes a set of [`Tag`]s shared by a group of [`DataItem`]s so that only the tags
+/// unique to each item have to be encoded. Produces the same bytes as [`encode_tags`].
+pub struct TagsEncoder {
+    shared_len: usize,
+    shared_bytes: Vec<u8>,
+}
+
+impl TagsEncoder {
+    pub fn new(shared_tags: &[Tag<String>]) -> Self {
+        let mut shared_bytes = Vec::new();
+        shared_tags
+            .iter()
+            .for_each(|t| encode_tag_record(t, &mut shared_bytes));
+        Self {
+            shared_len: shared_tags.len(),
+            shared_bytes,
+        }
+    }
+
+    /// Number of shared tags that each item's tags are expected to start with.
+    pub fn shared_len(&self) -> usize {
+        self.shared_len
+    }
+
+    /// Returns the encoded bytes for the shared tags followed by `item_tags`.
+    pub fn encode(&self, item_tags: &[Tag<String>]) -> Vec<u8> {
+        let number_of_tags = self.shared_len + item_tags.len();
+        if number_of_tags == 0 {
+            return vec![0];
         }
-    "#;
+        let mut buf = Vec::with_capacity(self.shared_bytes.len() + 64 * item_tags.len() + 11);
+        encode_long(number_of_tags as i64, &mut buf);
+        buf.extend_from_slice(&self.shared_bytes);
+        item_tags
+            .iter()
+            .for_each(|t| encode_tag_record(t, &mut buf));
+        buf.push(0);
+        buf
+    }
+}
 
-    Schema::parse_str(schema).unwrap()
+/// Zig-zag varint encoding used by avro for longs.
+fn encode_long(n: i64, buf: &mut Vec<u8>) {
+    let mut z = ((n << 1) ^ (n >> 63)) as u64;
+    while z > 0x7F {
+        buf.push((z & 0x7F) as u8 | 0x80);
+        z >>= 7;
+    }
+    buf.push(z as u8);
+}
+
+/// Number of bytes [`encode_long`] writes for `n`.
+fn encoded_long_len(n: i64) -> usize {
+    let mut z = ((n << 1) ^ (n >> 63)) as u64;
+    let mut len = 1;
+    while z > 0x7F {
+        z >>= 7;
+        len += 1;
+    }
+    len
+}
+
+/// Number of tag bytes written by [`DataItem::serialize`], computed without encoding them.
+pub fn serialized_tags_len(tags: &[Tag<String>]) -> usize {
+    if tags.is_empty() {
+        return 0;
+    }
+    let records_len: usize = tags
+        .iter()
+        .map(|t| {
+            encoded_long_len(t.name.len() as i64)
+                + t.name.len()
+                + encoded_long_len(t.value.len() as i64)
+                + t.value.len()
+        })
+        .sum();
+    encoded_long_len(tags.len() as i64) + records_len + 1
+}
+
+fn encode_tag_record(tag: &Tag<String>, buf: &mut Vec<u8>) {
+    for s in [&tag.name, &tag.value] {
+        encode_long(s.len() as i64, buf);
+        buf.extend_from_slice(s.as_bytes());
+    }
+}
+
+/// Length of the bundle header: number of data items followed by 24 bytes of padding.
+pub const BUNDLE_HEADER_LEN: usize = 32;
+
+/// Length of each data item header in a bundle: size of the item followed by its id.
+pub const BUNDLE_ITEM_HEADER_LEN: usize = 64;
+
+/// Length of [`DataItem`] signatures made with Arweave keypairs.
+pub const SIGNATURE_LEN: usize = 512;
+
+/// Length of [`DataItem`] owners signed with Arweave keypairs, the modulus of the signing key.
+pub const OWNER_LEN: usize = 512;
+
+/// ANS-104 signature types supported for [`DataItem`]s, which determine the lengths of their
+/// signature and owner fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureType {
+    /// RSA-PSS signature by an Arweave keypair, with the modulus of the key as owner.
+    Arweave = 1,
+    /// Ed25519 signature by a Solana keypair, with the public key as owner.
+    Ed25519 = 2,
+}
+
+impl SignatureType {
+    pub fn signature_len(&self) -> usize {
+        match self {
+            SignatureType::Arweave => SIGNATURE_LEN,
+            SignatureType::Ed25519 => 64,
+        }
+    }
+
+    pub fn owner_len(&self) -> usize {
+        match self {
+            SignatureType::Arweave => OWNER_LEN,
+            SignatureType::Ed25519 => 32,
+        }
+    }
+}
+
+impl TryFrom<u16> for SignatureType {
+    type Error = Error;
+    fn try_from(signature_type: u16) -> Result<Self, Self::Error> {
+        match signature_type {
+            1 => Ok(SignatureType::Arweave),
+            2 => Ok(SignatureType::Ed25519),
+            _ => Err(Error::UnsupportedSignatureType(signature_type)),
+        }
+    }
+}
+
+/// Breakdown of a serialized bundle into file data and ANS-104 overhead, returned by
+/// [`crate::Arweave::create_bundle_from_data_items`].
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub struct BundleBreakdown {
+    /// Bytes of data item data, i.e. the files included in the bundle.
+    pub data_bytes: u64,
+    /// Bytes of avro encoded tags across all data items.
+    pub tags_bytes: u64,
+    /// Bytes of the serialized bundle that aren't data item data: bundle and item headers, owners,
+    /// signatures, targets, anchors and tags.
+    pub overhead_bytes: u64,
+}
+
+/// Anchor derived from `data`, the 32 byte BLAKE3 hash of it, so that identical content always
+/// gets the same anchor. Used by [`crate::Arweave::create_data_item`] when
+/// [`crate::Arweave::anchor_from_hash`] is set.
+pub fn anchor_from_hash(data: &[u8]) -> Base64 {
+    Base64(blake3::hash(data).as_bytes().to_vec())
+}
+
+/// Placeholders that can be used in the values of [`crate::Arweave::bundle_tags`], resolved
+/// from a [`BundleSummary`].
+pub const BUNDLE_TAG_PLACEHOLDERS: [&str; 5] =
+    ["count", "bytes", "items_hash", "first_path", "last_path"];
+
+/// Summary of the files in a bundle, used to resolve templated bundle tags with
+/// [`resolve_bundle_tags`].
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct BundleSummary {
+    /// Number of data items.
+    pub count: usize,
+    /// Bytes of data item data.
+    pub bytes: u64,
+    /// BLAKE3 hash of the concatenated data item ids, in bundle order.
+    pub items_hash: Base64,
+    pub first_path: String,
+    pub last_path: String,
+}
+
+impl BundleSummary {
+    pub fn new(data_items: &[(DataItem, Status)]) -> Self {
+        let mut hasher = blake3::Hasher::new();
+        data_items.iter().for_each(|(d, _)| {
+            hasher.update(&d.id.0);
+        });
+        let path = |item: Option<&(DataItem, Status)>| {
+            item.and_then(|(_, s)| s.file_path.as_ref())
+                .map(|p| p.display().to_string())
+                .unwrap_or_default()
+        };
+        Self {
+            count: data_items.len(),
+            bytes: data_items.iter().map(|(d, _)| d.data.0.len() as u64).sum(),
+            items_hash: Base64(hasher.finalize().as_bytes().to_vec()),
+            first_path: path(data_items.first()),
+            last_path: path(data_items.last()),
+        }
+    }
+
+    fn placeholder_value(&self, placeholder: &str) -> Option<String> {
+        match placeholder {
+            "count" => Some(self.count.to_string()),
+            "bytes" => Some(self.bytes.to_string()),
+            "items_hash" => Some(self.items_hash.to_string()),
+            "first_path" => Some(self.first_path.clone()),
+            "last_path" => Some(self.last_path.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Replaces `{<PLACEHOLDER>}`s in `template` with the values returned by `value`, returning
+/// [`Error::InvalidBundleTag`] for unknown or unclosed placeholders. `{{` and `}}` are replaced
+/// with literal braces, and a `}` that doesn't close a placeholder is kept as it is.
+fn fill_template<F>(template: &str, value: F) -> Result<String, Error>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find(|c| c == '{' || c == '}') {
+        filled.push_str(&rest[..start]);
+        let (brace, after) = rest[start..].split_at(1);
+        if after.starts_with(brace) {
+            filled.push_str(brace);
+            rest = &after[1..];
+        } else if brace == "}" {
+            filled.push_str(brace);
+            rest = after;
+        } else {
+            let end = after
+                .find('}')
+                .ok_or_else(|| Error::InvalidBundleTag(template.to_string()))?;
+            let placeholder = &after[..end];
+            filled.push_str(
+                &value(placeholder)
+                    .ok_or_else(|| Error::InvalidBundleTag(placeholder.to_string()))?,
+            );
+            rest = &after[end + 1..];
+        }
+    }
+    filled.push_str(rest);
+    Ok(filled)
+}
+
+/// Checks that `template` only uses [`BUNDLE_TAG_PLACEHOLDERS`].
+pub fn check_bundle_tag_template(template: &str) -> Result<(), Error> {
+    fill_template(template, |p| {
+        BUNDLE_TAG_PLACEHOLDERS.contains(&p).then(String::new)
+    })
+    .map(|_| ())
+}
+
+/// Resolves the placeholders in the names and values of `templates` from `summary`.
+pub fn resolve_bundle_tags(
+    templates: &[Tag<String>],
+    summary: &BundleSummary,
+) -> Result<Vec<Tag<Base64>>, Error> {
+    templates
+        .iter()
+        .map(|t| {
+            let value = |p: &str| summary.placeholder_value(p);
+            Tag::<Base64>::from_utf8_strs(
+                &fill_template(&t.name, value)?,
+                &fill_template(&t.value, value)?,
+            )
+        })
+        .collect()
 }
 
 /// Primary structure for [`DataItem`]s included in bundles.
@@ -56,7 +415,45 @@ impl Default for DataItem {
 
 impl DataItem {
     pub fn serialize(&self) -> Result<Vec<u8>, Error> {
-        if self.signature.0.len() != 512 {
+        self.serialize_with_tags_bytes(&encode_tags(&self.tags)?)
+    }
+
+    /// Number of bytes returned by [`DataItem::serialize`], computed without serializing.
+    ///
+    /// The signature is counted at the length of its [`SignatureType`], so this can be used for
+    /// planning before signing as long as `owner` has been set.
+    pub fn serialized_len(&self) -> usize {
+        let signature_len = SignatureType::try_from(self.signature_type)
+            .map_or(SIGNATURE_LEN, |t| t.signature_len());
+        2 + signature_len
+            + self.owner.0.len()
+            + 1
+            + self.target.0.len()
+            + 1
+            + self.anchor.0.len()
+            + 16
+            + serialized_tags_len(&self.tags)
+            + self.data.0.len()
+    }
+
+    /// Returns [`Error::UnsupportedKeySize`] if `owner` doesn't have the length of the
+    /// [`SignatureType`], e.g., when signed with an Arweave key that isn't 4096 bits, which would
+    /// otherwise produce a data item that can't be deserialized.
+    pub fn check_owner_len(&self) -> Result<SignatureType, Error> {
+        let signature_type = SignatureType::try_from(self.signature_type)?;
+        if self.owner.0.len() != signature_type.owner_len() {
+            return Err(Error::UnsupportedKeySize {
+                modulus_len: self.owner.0.len(),
+            });
+        }
+        Ok(signature_type)
+    }
+
+    /// Same as [`DataItem::serialize`] with tags already encoded, see [`TagsEncoder`].
+    pub fn serialize_with_tags_bytes(&self, tags_bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        check_tags(&self.tags)?;
+        let signature_type = self.check_owner_len()?;
+        if self.signature.0.len() != signature_type.signature_len() {
             return Err(Error::UnsignedTransaction);
         }
         let mut buf = Vec::new().writer();
@@ -79,14 +476,11 @@ impl DataItem {
 
         if self.tags.len() > 0 {
             let number_of_tags = self.tags.len() as u64;
-            let schema = get_tags_schema();
-            let value = avro_rs::to_value(&self.tags)?;
-            let tags_bytes = avro_rs::to_avro_datum(&schema, value)?;
             let number_of_tag_bytes = tags_bytes.len() as u64;
 
             buf.write(&number_of_tags.to_le_bytes())?;
             buf.write(&number_of_tag_bytes.to_le_bytes())?;
-            buf.write(&tags_bytes)?;
+            buf.write(tags_bytes)?;
         } else {
             buf.write(&[0; 16])?;
         };
@@ -95,63 +489,53 @@ impl DataItem {
 
         Ok(buf.into_inner())
     }
+
+    /// Parses a serialized data item, returning [`Error::InvalidDataItem`] instead of panicking
+    /// if `bytes_vec` is truncated or malformed.
     pub fn deserialize(bytes_vec: Vec<u8>) -> Result<Self, Error> {
-        let mut iter = bytes_vec.into_iter();
-        let mut data_item = DataItem::default();
+        Self::from_slice(&bytes_vec)
+    }
 
-        let result = [(); 2].map(|_| iter.next().unwrap());
-        data_item.signature_type = u16::from_le_bytes(result);
-        if data_item.signature_type != 1 {
-            println!("invalid signature_type");
-            return Err(Error::InvalidDataItem);
-        }
+    /// Same as [`DataItem::deserialize`] without taking ownership of the bytes, so that each field
+    /// is copied out of `bytes` only once.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, Error> {
+        Self::from_reader(Reader::new(bytes, 0))
+    }
 
-        for _ in 0..512 {
-            data_item.signature.0.push(iter.next().unwrap());
-        }
+    fn from_reader(mut reader: Reader) -> Result<Self, Error> {
+        let mut data_item = DataItem::default();
 
-        for _ in 0..512 {
-            data_item.owner.0.push(iter.next().unwrap());
-        }
+        let signature_type_error = reader.invalid();
+        data_item.signature_type = u16::from_le_bytes(reader.take_array()?);
+        let signature_type =
+            SignatureType::try_from(data_item.signature_type).map_err(|_| signature_type_error)?;
 
-        if iter.next().unwrap() == 1 {
-            for _ in 0..32 {
-                data_item.target.0.push(iter.next().unwrap());
-            }
-        }
+        data_item.signature.0 = reader.take_exact(signature_type.signature_len())?.to_vec();
+        data_item.owner.0 = reader.take_exact(signature_type.owner_len())?.to_vec();
+        data_item.target.0 = reader.take_optional()?;
+        data_item.anchor.0 = reader.take_optional()?;
 
-        if iter.next().unwrap() == 1 {
-            for _ in 0..32 {
-                data_item.anchor.0.push(iter.next().unwrap());
-            }
-        }
-
-        let number_of_tags = u64::from_le_bytes([(); 8].map(|_| iter.next().unwrap()));
-        let number_of_tag_bytes =
-            u64::from_le_bytes([(); 8].map(|_| iter.next().unwrap())) as usize;
+        let number_of_tags = u64::from_le_bytes(reader.take_array()?);
+        let tag_bytes_error = reader.invalid();
+        let number_of_tag_bytes = u64::from_le_bytes(reader.take_array()?) as usize;
         if number_of_tag_bytes > 2048 {
-            return Err(Error::InvalidDataItem);
+            return Err(tag_bytes_error);
         }
+        let tags_error = reader.invalid();
+        let mut tags_bytes = reader.take_exact(number_of_tag_bytes)?;
 
         data_item.tags = if number_of_tags > 0 {
-            let schema = get_tags_schema();
-            let mut reader = Vec::<u8>::with_capacity(number_of_tag_bytes);
-
-            for _ in 0..number_of_tag_bytes {
-                reader.push(iter.next().unwrap());
-            }
-
-            let value = avro_rs::from_avro_datum::<&[u8]>(&schema, &mut &*reader, None)?;
-            let tags: Vec<Tag<String>> = avro_rs::from_value(&value)?;
-            if tags.len() != number_of_tags as usize {
-                return Err(Error::InvalidDataItem);
-            }
-            tags
+            let tags: Option<Vec<Tag<String>>> =
+                avro_rs::from_avro_datum(get_tags_schema(), &mut tags_bytes, None)
+                    .ok()
+                    .and_then(|value| avro_rs::from_value(&value).ok())
+                    .filter(|tags: &Vec<Tag<String>>| tags.len() == number_of_tags as usize);
+            tags.ok_or(tags_error)?
         } else {
             Vec::<Tag<String>>::new()
         };
 
-        data_item.data.0 = iter.collect();
+        data_item.data.0 = reader.take_rest().to_vec();
 
         Ok(data_item)
     }
@@ -159,23 +543,330 @@ impl DataItem {
     /// Header is 64 bytes with first 32 for the size of the bytes le. Second
     /// 32 is id, which is the hashed signature.
     pub fn to_bundle_item(&self) -> Result<(Vec<u8>, Vec<u8>), Error> {
-        let binary = self.serialize()?;
-        let binary_len = binary.len();
-        let mut header = Vec::<u8>::with_capacity(64);
+        self.to_bundle_item_with_tags_bytes(&encode_tags(&self.tags)?)
+    }
+
+    /// Same as [`DataItem::to_bundle_item`] with tags already encoded, see [`TagsEncoder`].
+    pub fn to_bundle_item_with_tags_bytes(
+        &self,
+        tags_bytes: &[u8],
+    ) -> Result<(Vec<u8>, Vec<u8>), Error> {
+        let binary = self.serialize_with_tags_bytes(tags_bytes)?;
+        Ok((self.bundle_item_header(binary.len()), binary))
+    }
 
+    /// Bundle item header for the data item serialized into `binary_len` bytes.
+    pub fn bundle_item_header(&self, binary_len: usize) -> Vec<u8> {
+        let mut header = Vec::<u8>::with_capacity(BUNDLE_ITEM_HEADER_LEN);
         header.extend((binary_len as u64).to_le_bytes());
         header.extend(&[0u8; 24]);
         header.extend(&self.id.0);
+        header
+    }
+}
+
+/// Number of bytes [`write_bundle`] writes for `data_items`.
+pub fn bundle_len(data_items: &[(DataItem, Vec<u8>)]) -> usize {
+    BUNDLE_HEADER_LEN
+        + data_items
+            .iter()
+            .map(|(d, _)| BUNDLE_ITEM_HEADER_LEN + d.serialized_len())
+            .sum::<usize>()
+}
+
+/// Writes data items, paired with their encoded tags, to `writer` as a bundle per ANS-104. All of
+/// the headers are written first, computed from [`DataItem::serialized_len`], so that each data
+/// item can then be serialized and written in turn, and dropped once it has been.
+pub fn write_bundle<W: Write>(
+    data_items: Vec<(DataItem, Vec<u8>)>,
+    writer: &mut W,
+) -> Result<(), Error> {
+    writer.write_all(&(data_items.len() as u64).to_le_bytes())?;
+    writer.write_all(&[0u8; 24])?;
+    for (data_item, _) in &data_items {
+        writer.write_all(&data_item.bundle_item_header(data_item.serialized_len()))?;
+    }
+    for (data_item, tags_bytes) in data_items {
+        let binary = data_item.serialize_with_tags_bytes(&tags_bytes)?;
+        if binary.len() != data_item.serialized_len() {
+            return Err(Error::InvalidDataLength);
+        }
+        writer.write_all(&binary)?;
+    }
+    Ok(())
+}
+
+/// Serialized bundle written to a file by [`crate::Arweave::create_bundle_file_from_data_items`]
+/// so that it doesn't have to be held in memory while it is posted. Also used for files uploaded
+/// by [`crate::Arweave::create_transaction_from_file_path_streamed`].
+#[derive(Debug, PartialEq)]
+pub struct BundleFile {
+    pub path: PathBuf,
+    pub len: u64,
+    /// Removes the file when the handle is dropped, set for temporary bundle files.
+    pub remove_on_drop: bool,
+}
+
+impl BundleFile {
+    /// Creates a new file for a bundle of `len` bytes in the system temporary directory, returning
+    /// the handle and the file to write the bundle to.
+    pub fn create_temp(len: u64) -> Result<(Self, fsSync::File), Error> {
+        let mut rand_bytes = [0u8; 8];
+        SystemRandom::new().fill(&mut rand_bytes)?;
+        let path = std::env::temp_dir().join(format!(
+            "arloader-bundle-{}.bin",
+            base64::encode_config(rand_bytes, base64::URL_SAFE_NO_PAD)
+        ));
+        let file = fsSync::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        Ok((
+            Self {
+                path,
+                len,
+                remove_on_drop: true,
+            },
+            file,
+        ))
+    }
+
+    /// Handle for an existing file at `path`, which is left in place when the handle is dropped.
+    pub fn open(path: PathBuf) -> Result<Self, Error> {
+        let len = fsSync::metadata(&path)?.len();
+        Ok(Self {
+            path,
+            len,
+            remove_on_drop: false,
+        })
+    }
+
+    /// Reads bytes `start..end` of the bundle.
+    pub async fn read_range(&self, start: usize, end: usize) -> Result<Vec<u8>, Error> {
+        let mut file = fs::File::open(&self.path).await?;
+        file.seek(SeekFrom::Start(start as u64)).await?;
+        let mut buf = vec![0; end - start];
+        file.read_exact(&mut buf).await?;
+        Ok(buf)
+    }
+
+    /// Same as [`BundleFile::read_range`] without blocking on a runtime.
+    pub fn read_range_sync(&self, start: usize, end: usize) -> Result<Vec<u8>, Error> {
+        let mut file = fsSync::File::open(&self.path)?;
+        file.seek(SeekFrom::Start(start as u64))?;
+        let mut buf = vec![0; end - start];
+        file.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Reads the whole bundle.
+    pub async fn read_all(&self) -> Result<Vec<u8>, Error> {
+        Ok(fs::read(&self.path).await?)
+    }
+}
+
+impl Drop for BundleFile {
+    fn drop(&mut self) {
+        if self.remove_on_drop {
+            fsSync::remove_file(&self.path).ok();
+        }
+    }
+}
 
-        Ok((header, binary))
+/// Collection of [`DataItem`]s serialized together as a bundle.
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Bundle {
+    pub data_items: Vec<DataItem>,
+}
+
+impl Bundle {
+    /// Serializes bundle header, data item headers and data items per ANS-104.
+    pub fn serialize(&self) -> Result<Vec<u8>, Error> {
+        let items = self
+            .data_items
+            .iter()
+            .map(|d| d.to_bundle_item())
+            .collect::<Result<Vec<(Vec<u8>, Vec<u8>)>, Error>>()?;
+
+        let mut binary = Vec::with_capacity(self.serialized_len());
+        binary.extend((self.data_items.len() as u64).to_le_bytes());
+        binary.extend([0u8; 24]);
+        items.iter().for_each(|(header, _)| binary.extend(header));
+        items.into_iter().for_each(|(_, item)| binary.extend(item));
+        Ok(binary)
+    }
+
+    /// Number of bytes returned by [`Bundle::serialize`], computed without serializing.
+    pub fn serialized_len(&self) -> usize {
+        BUNDLE_HEADER_LEN
+            + self
+                .data_items
+                .iter()
+                .map(|d| BUNDLE_ITEM_HEADER_LEN + d.serialized_len())
+                .sum::<usize>()
+    }
+}
+
+/// Deserializes a bundle into its [`DataItem`]s, verifying each signature against the data item
+/// owner according to its [`SignatureType`], so no keypair is needed, and that the id in the
+/// bundle header is the hash of the signature, see [`verify_data_item`].
+pub fn deserialize_bundle(bundle: Vec<u8>, hasher: &Hasher) -> Result<Vec<DataItem>, Error> {
+    let mut data_items = Vec::new();
+    deserialize_bundle_streaming(&bundle, hasher, |data_item| {
+        data_items.push(data_item);
+        Ok(())
+    })?;
+    Ok(data_items)
+}
+
+/// Same as [`deserialize_bundle`], handing each data item to `f` as soon as it's verified instead
+/// of collecting them, so that only one data item is in memory at a time besides the bundle.
+/// Stops at the first data item that fails to deserialize or verify, or that `f` returns an error
+/// for.
+pub fn deserialize_bundle_streaming<F>(
+    bundle: &[u8],
+    hasher: &Hasher,
+    mut f: F,
+) -> Result<(), Error>
+where
+    F: FnMut(DataItem) -> Result<(), Error>,
+{
+    for (id, item) in split_bundle_items(bundle)? {
+        let mut data_item = DataItem::from_reader(item)?;
+        verify_data_item(&data_item, &id, hasher)?;
+        data_item.id.0 = id;
+        f(data_item)?;
+    }
+    Ok(())
+}
+
+/// Splits a bundle into the id from each data item header and the bytes of the data item, without
+/// deserializing or verifying the data items.
+pub fn split_bundle(bundle: &[u8]) -> Result<Vec<(Vec<u8>, &[u8])>, Error> {
+    Ok(split_bundle_items(bundle)?
+        .into_iter()
+        .map(|(id, item)| (id, item.bytes))
+        .collect())
+}
+
+/// Same as [`split_bundle`], with each data item in a [`Reader`] that keeps track of its offset
+/// in the bundle for errors.
+fn split_bundle_items(bundle: &[u8]) -> Result<Vec<(Vec<u8>, Reader)>, Error> {
+    let mut reader = Reader::new(bundle, 0);
+    let number_of_data_items = u64::from_le_bytes(reader.take_array()?) as usize;
+    reader.take_exact(BUNDLE_HEADER_LEN - 8)?;
+
+    // Each header takes 64 bytes, so a count larger than that is corrupt and shouldn't be used to
+    // allocate.
+    if number_of_data_items > reader.bytes.len() / BUNDLE_ITEM_HEADER_LEN {
+        return Err(Error::InvalidDataItem { offset: 0 });
+    }
+
+    // Parse headers.
+    let mut headers = Vec::<(usize, Vec<u8>)>::with_capacity(number_of_data_items);
+    for _ in 0..number_of_data_items {
+        let bytes_len = u64::from_le_bytes(reader.take_array()?) as usize;
+        reader.take_exact(24)?;
+        headers.push((bytes_len, reader.take_exact(32)?.to_vec()));
+    }
+
+    headers
+        .into_iter()
+        .map(|(bytes_len, id)| {
+            let offset = reader.offset;
+            Ok((id, Reader::new(reader.take_exact(bytes_len)?, offset)))
+        })
+        .collect()
+}
+
+/// Deserializes a data item signed elsewhere, e.g., with arbundles, verifying its signature
+/// against its owner according to its [`SignatureType`] and setting its id to the hash of the
+/// signature.
+pub fn deserialize_signed_data_item(bytes: &[u8], hasher: &Hasher) -> Result<DataItem, Error> {
+    let mut data_item = DataItem::from_slice(bytes)?;
+    verify_signature(&data_item, hasher)?;
+    data_item.id.0 = hasher.hash_sha256(&data_item.signature.0)?.to_vec();
+    Ok(data_item)
+}
+
+/// Verifies the signature of a data item against its owner and that `id`, from the bundle
+/// header, is the hash of the signature.
+pub fn verify_data_item(data_item: &DataItem, id: &[u8], hasher: &Hasher) -> Result<(), Error> {
+    verify_signature(data_item, hasher)?;
+    if hasher.hash_sha256(&data_item.signature.0)?.as_slice() != id {
+        return Err(Error::InvalidDataItemId);
+    }
+    Ok(())
+}
+
+fn verify_signature(data_item: &DataItem, hasher: &Hasher) -> Result<(), Error> {
+    let deep_hash = hasher.deep_hash(data_item.to_deep_hash_item()?)?;
+    hasher.verify_signature(
+        SignatureType::try_from(data_item.signature_type)?,
+        &data_item.owner.0,
+        &data_item.signature.0,
+        &deep_hash,
+    )
+}
+
+/// Cursor over a serialized bundle or data item, returning [`Error::InvalidDataItem`] with the
+/// offset in the bundle of the field that couldn't be read instead of panicking.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8], offset: usize) -> Self {
+        Self { bytes, offset }
+    }
+
+    fn invalid(&self) -> Error {
+        Error::InvalidDataItem {
+            offset: self.offset,
+        }
+    }
+
+    /// Splits the first `n` bytes off, returning [`Error::InvalidDataItem`] if there aren't
+    /// enough of them.
+    fn take_exact(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if self.bytes.len() < n {
+            return Err(self.invalid());
+        }
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        self.offset += n;
+        Ok(taken)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.take_exact(N)?);
+        Ok(array)
+    }
+
+    /// Reads a presence flag followed by 32 bytes if the flag is set, as used for target and
+    /// anchor.
+    fn take_optional(&mut self) -> Result<Vec<u8>, Error> {
+        let error = self.invalid();
+        match self.take_exact(1)?[0] {
+            0 => Ok(Vec::new()),
+            1 => Ok(self.take_exact(32)?.to_vec()),
+            _ => Err(error),
+        }
+    }
+
+    fn take_rest(&mut self) -> &'a [u8] {
+        let rest = self.bytes;
+        self.offset += rest.len();
+        self.bytes = &[];
+        rest
     }
 }
 
 impl<'a> ToItems<'a, DataItem> for DataItem {
     fn to_deep_hash_item(&'a self) -> Result<DeepHashItem, Error> {
-        let schema = get_tags_schema();
-        let value = avro_rs::to_value(&self.tags)?;
-        let tags_bytes = avro_rs::to_avro_datum(&schema, value)?;
+        let tags_bytes = encode_tags(&self.tags)?;
 
         let children: Vec<DeepHashItem> = vec![
             "dataitem".as_bytes(),
@@ -197,17 +888,36 @@ impl<'a> ToItems<'a, DataItem> for DataItem {
 
 #[cfg(test)]
 mod tests {
-    use super::DataItem;
+    use super::{
+        anchor_from_hash, check_bundle_tag_template, check_tags, deserialize_bundle, encode_tags,
+        resolve_bundle_tags, shared_tags_prefix, verify_data_item, Bundle, BundleSummary, DataItem,
+        SignatureType, TagsEncoder, BUNDLE_HEADER_LEN, BUNDLE_ITEM_HEADER_LEN, MAX_TAGS,
+        MAX_TAG_NAME_LEN, MAX_TAG_VALUE_LEN,
+    };
     use crate::{
+        crypto::Hasher,
+        error::Error,
         status::Status,
         transaction::{Base64, FromUtf8Strs, Tag, ToItems},
         Arweave,
     };
+    use matches::assert_matches;
+    use rand::Rng;
+    use solana_sdk::signer::{keypair, Signer};
     use std::path::PathBuf;
     use std::str::FromStr;
+    use std::time::Instant;
     use tokio::fs;
     use url::Url;
 
+    /// Status for the `i`th data item of a test bundle, which needs a file path for the manifest.
+    fn test_status(i: usize) -> Status {
+        Status {
+            file_path: Some(PathBuf::from(format!("{}.json", i))),
+            ..Status::default()
+        }
+    }
+
     async fn get_test_data_item() -> DataItem {
         let arweave = Arweave::from_keypair_path(
             PathBuf::from("tests/fixtures/test_key0.json"),
@@ -271,6 +981,383 @@ mod tests {
         assert_eq!(data_item, de_data_item)
     }
 
+    #[tokio::test]
+    async fn test_anchor_from_hash() -> Result<(), Error> {
+        let mut arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key0.json"),
+            Url::from_str("http://url.com").unwrap(),
+        )
+        .await?;
+        let data = b"tasty".to_vec();
+        let anchor = anchor_from_hash(&data);
+        assert_eq!(anchor.0, blake3::hash(&data).as_bytes().to_vec());
+        assert_eq!(anchor.0.len(), 32);
+
+        let data_item = arweave.create_data_item(
+            data.clone(),
+            Vec::new(),
+            false,
+            false,
+            SignatureType::Arweave,
+        )?;
+        assert!(data_item.anchor.0.is_empty());
+
+        arweave.anchor_from_hash = true;
+        let data_items = (0..2)
+            .map(|_| {
+                arweave.sign_data_item(arweave.create_data_item(
+                    data.clone(),
+                    Vec::new(),
+                    false,
+                    true,
+                    SignatureType::Arweave,
+                )?)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        assert_eq!(data_items[0].anchor, anchor);
+        assert_eq!(data_items[0].anchor, data_items[1].anchor);
+        assert_ne!(
+            data_items[0].anchor,
+            arweave
+                .create_data_item(
+                    b"salty".to_vec(),
+                    Vec::new(),
+                    false,
+                    true,
+                    SignatureType::Arweave
+                )?
+                .anchor
+        );
+
+        let data_item = DataItem::deserialize(data_items[0].serialize()?)?;
+        assert_eq!(data_item.anchor, anchor);
+        verify_data_item(&data_item, &data_items[0].id.0, &arweave.crypto)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_random_anchor() -> Result<(), Error> {
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key0.json"),
+            Url::from_str("http://url.com").unwrap(),
+        )
+        .await?;
+        let data = b"tasty".to_vec();
+
+        let data_items = (0..2)
+            .map(|_| {
+                arweave.sign_data_item(arweave.create_data_item(
+                    data.clone(),
+                    Vec::new(),
+                    false,
+                    true,
+                    SignatureType::Arweave,
+                )?)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        assert_eq!(data_items[0].anchor.0.len(), 32);
+        assert_ne!(data_items[0].anchor, data_items[1].anchor);
+        assert_ne!(data_items[0].id, data_items[1].id);
+
+        let data_item = DataItem::deserialize(data_items[0].serialize()?)?;
+        assert_eq!(data_item.anchor, data_items[0].anchor);
+        verify_data_item(&data_item, &data_items[0].id.0, &arweave.crypto)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ed25519_data_item() -> Result<(), Error> {
+        let mut arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key0.json"),
+            Url::from_str("http://url.com").unwrap(),
+        )
+        .await?;
+        let create = |arweave: &Arweave, signature_type| {
+            arweave.create_data_item(b"hello".to_vec(), Vec::new(), true, true, signature_type)
+        };
+        assert_matches!(
+            arweave.sign_data_item(create(&arweave, SignatureType::Ed25519)?),
+            Err(Error::SolanaKeypairNotProvided)
+        );
+
+        let sol_keypair = keypair::read_keypair_file("tests/fixtures/solana_test.json")?;
+        let pubkey = sol_keypair.pubkey().to_bytes().to_vec();
+        arweave.sol_keypair = Some(sol_keypair);
+        let ed25519 = arweave.sign_data_item(create(&arweave, SignatureType::Ed25519)?)?;
+        assert_eq!(ed25519.signature_type, 2);
+        assert_eq!(ed25519.owner.0, pubkey);
+        assert_eq!(ed25519.signature.0.len(), 64);
+        assert_eq!(ed25519.serialized_len(), ed25519.serialize()?.len());
+
+        let data_item = DataItem::deserialize(ed25519.serialize()?)?;
+        assert_eq!(data_item.owner, ed25519.owner);
+        verify_data_item(&data_item, &ed25519.id.0, &arweave.crypto)?;
+
+        let arweave_item = arweave.sign_data_item(create(&arweave, SignatureType::Arweave)?)?;
+        let (bundle, _, _) = arweave.create_bundle_from_data_items(vec![
+            (ed25519.clone(), test_status(0)),
+            (arweave_item.clone(), test_status(1)),
+        ])?;
+        let data_items = deserialize_bundle(bundle.clone(), &Hasher::new())?;
+        assert_eq!(data_items[0].id, ed25519.id);
+        assert_eq!(data_items[1].id, arweave_item.id);
+
+        let mut bad_signature = bundle;
+        let signature_start = BUNDLE_HEADER_LEN + 2 * BUNDLE_ITEM_HEADER_LEN + 2;
+        bad_signature[signature_start] ^= 1;
+        assert!(deserialize_bundle(bad_signature, &Hasher::new()).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_key_size() -> Result<(), Error> {
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key_2048.json"),
+            Url::from_str("http://url.com").unwrap(),
+        )
+        .await?;
+        let data_item = arweave.create_data_item(
+            b"hello".to_vec(),
+            Vec::new(),
+            true,
+            true,
+            SignatureType::Arweave,
+        )?;
+        assert_matches!(
+            arweave.sign_data_item(data_item.clone()),
+            Err(Error::UnsupportedKeySize { modulus_len: 256 })
+        );
+
+        // Items signed elsewhere with a 2048 bit key can't be serialized or bundled either.
+        let signature = arweave.crypto.sign(b"message")?;
+        assert_eq!(signature.len(), 256);
+        let data_item = DataItem {
+            owner: arweave.crypto.keypair_modulus()?,
+            signature: Base64(signature),
+            ..data_item
+        };
+        assert_matches!(
+            data_item.serialize(),
+            Err(Error::UnsupportedKeySize { modulus_len: 256 })
+        );
+        assert_matches!(
+            arweave.create_bundle_from_data_items(vec![(data_item, test_status(0))]),
+            Err(Error::UnsupportedKeySize { modulus_len: 256 })
+        );
+
+        // 4096 bit keys round trip through a bundle.
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key0.json"),
+            Url::from_str("http://url.com").unwrap(),
+        )
+        .await?;
+        let data_item = arweave.sign_data_item(arweave.create_data_item(
+            b"hello".to_vec(),
+            Vec::new(),
+            true,
+            true,
+            SignatureType::Arweave,
+        )?)?;
+        assert_eq!(data_item.owner.0.len(), 512);
+        let (bundle, _, _) =
+            arweave.create_bundle_from_data_items(vec![(data_item.clone(), test_status(0))])?;
+        assert_eq!(deserialize_bundle(bundle, &Hasher::new())?, vec![data_item]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tag_limits() -> Result<(), Error> {
+        let name = "n".repeat(MAX_TAG_NAME_LEN);
+        let value = "v".repeat(MAX_TAG_VALUE_LEN);
+        assert!(Tag::<String>::from_utf8_strs(&name, &value).is_ok());
+        assert_matches!(
+            Tag::<String>::from_utf8_strs(&format!("{}n", name), "value"),
+            Err(Error::TagLimitExceeded(m)) if m.contains("tag name of \"nnn") && m.contains("...")
+        );
+        assert_matches!(
+            Tag::<String>::from_utf8_strs("Name", &format!("{}v", value)),
+            Err(Error::TagLimitExceeded(m)) if m.contains("tag value of \"Name\" is 3073 bytes")
+        );
+
+        // Room is left for the User-Agent and Content-Type tags.
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key0.json"),
+            Url::from_str("http://url.com").unwrap(),
+        )
+        .await?;
+        let tags = |n: usize| {
+            (0..n)
+                .map(|i| Tag::<String>::from_utf8_strs("Index", &i.to_string()))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let data_item = arweave.create_data_item(
+            b"hello".to_vec(),
+            tags(MAX_TAGS - 2)?,
+            true,
+            true,
+            SignatureType::Arweave,
+        )?;
+        assert_eq!(data_item.tags.len(), MAX_TAGS);
+        assert!(check_tags(&data_item.tags).is_ok());
+        assert_matches!(
+            arweave.create_data_item(
+                b"hello".to_vec(),
+                tags(MAX_TAGS - 1)?,
+                true,
+                true,
+                SignatureType::Arweave,
+            ),
+            Err(Error::TagLimitExceeded(m)) if m == "129 tags, the maximum is 128"
+        );
+
+        // Tags added to an item after it was created are checked when it is serialized.
+        let data_item = DataItem {
+            tags: tags(MAX_TAGS + 1)?,
+            ..get_test_data_item().await
+        };
+        assert_matches!(data_item.serialize(), Err(Error::TagLimitExceeded(_)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_deserialize_truncated_data_item() {
+        let mut data_item = get_test_data_item().await;
+        data_item.target = Base64(vec![3; 32]);
+        let bytes = data_item.serialize().unwrap();
+
+        // Signature type, signature, owner, target and anchor with their presence flags, tag
+        // counts and tags, followed by data.
+        let tags_len = encode_tags(&data_item.tags).unwrap().len();
+        let boundaries = [
+            2,
+            514,
+            1026,
+            1027,
+            1059,
+            1060,
+            1092,
+            1100,
+            1108,
+            1108 + tags_len,
+        ];
+        let data_start = boundaries[boundaries.len() - 1];
+        assert_eq!(bytes.len(), data_start + data_item.data.0.len());
+
+        for boundary in boundaries {
+            for len in [boundary - 1, boundary, boundary + 1] {
+                let result = DataItem::deserialize(bytes[..len].to_vec());
+                if len < data_start {
+                    assert_matches!(result, Err(Error::InvalidDataItem { .. }));
+                } else {
+                    assert_eq!(result.unwrap().data.0, bytes[data_start..len].to_vec());
+                }
+            }
+        }
+        (0..data_start).for_each(|len| {
+            assert_matches!(
+                DataItem::deserialize(bytes[..len].to_vec()),
+                Err(Error::InvalidDataItem { offset }) if offset <= len
+            )
+        });
+
+        let mut bad_flag = bytes.clone();
+        bad_flag[1026] = 2;
+        assert_matches!(
+            DataItem::deserialize(bad_flag),
+            Err(Error::InvalidDataItem { offset: 1026 })
+        );
+
+        let mut bad_tags = bytes;
+        bad_tags[1092] += 1;
+        assert_matches!(
+            DataItem::deserialize(bad_tags),
+            Err(Error::InvalidDataItem { offset: 1108 })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deserialize_bundle_randomly_truncated() -> Result<(), Error> {
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key0.json"),
+            Url::from_str("http://url.com").unwrap(),
+        )
+        .await?;
+        let data_items = vec![
+            (
+                arweave.sign_data_item(get_test_data_item().await)?,
+                test_status(0),
+            ),
+            (
+                arweave.sign_data_item(get_test_data_item().await)?,
+                test_status(1),
+            ),
+        ];
+        let (bundle, _, _) = arweave.create_bundle_from_data_items(data_items)?;
+        let hasher = Hasher::new();
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..200 {
+            let len = rng.gen_range(0..bundle.len());
+            assert!(deserialize_bundle(bundle[..len].to_vec(), &hasher).is_err());
+
+            // Corrupted bytes may still parse, but must not panic.
+            let mut corrupted = bundle.clone();
+            let i = rng.gen_range(0..bundle.len());
+            corrupted[i] = rng.gen();
+            let _ = deserialize_bundle(corrupted, &hasher);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_deserialize_corrupted_bundle() -> Result<(), Error> {
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key0.json"),
+            Url::from_str("http://url.com").unwrap(),
+        )
+        .await?;
+        let first = arweave.sign_data_item(get_test_data_item().await)?;
+        let second = arweave.sign_data_item(get_test_data_item().await)?;
+        let first_start = BUNDLE_HEADER_LEN + 2 * BUNDLE_ITEM_HEADER_LEN;
+        let second_start = first_start + first.serialize()?.len();
+        let (bundle, _, _) = arweave.create_bundle_from_data_items(vec![
+            (first, test_status(0)),
+            (second, test_status(1)),
+        ])?;
+        let hasher = Hasher::new();
+
+        // Truncated headers are reported at the field that runs short.
+        for len in 0..first_start {
+            assert_matches!(
+                deserialize_bundle(bundle[..len].to_vec(), &hasher),
+                Err(Error::InvalidDataItem { offset }) if offset <= len
+            );
+        }
+
+        let mut bad_signature_type = bundle.clone();
+        bad_signature_type[second_start] = 3;
+        assert_matches!(
+            deserialize_bundle(bad_signature_type, &hasher),
+            Err(Error::InvalidDataItem { offset }) if offset == second_start
+        );
+
+        let mut bad_count = bundle.clone();
+        bad_count[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_matches!(
+            deserialize_bundle(bad_count, &hasher),
+            Err(Error::InvalidDataItem { offset: 0 })
+        );
+
+        let mut bad_len = bundle;
+        bad_len[BUNDLE_HEADER_LEN..BUNDLE_HEADER_LEN + 8]
+            .copy_from_slice(&(u32::MAX as u64).to_le_bytes());
+        assert_matches!(
+            deserialize_bundle(bad_len, &hasher),
+            Err(Error::InvalidDataItem { offset }) if offset == first_start
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_data_item_to_json() {
         let data_item = get_test_data_item().await;
@@ -303,6 +1390,36 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_deserialize_bundle_without_keypair() -> Result<(), Error> {
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key0.json"),
+            Url::from_str("http://url.com").unwrap(),
+        )
+        .await?;
+        let data_item = arweave.sign_data_item(get_test_data_item().await)?;
+        let (bundle, _, _) =
+            arweave.create_bundle_from_data_items(vec![(data_item.clone(), test_status(0))])?;
+
+        let data_items = deserialize_bundle(bundle.clone(), &Hasher::new())?;
+        assert_eq!(data_items, vec![data_item]);
+
+        // Changing the data invalidates the signature.
+        let mut tampered = bundle.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(deserialize_bundle(tampered, &Hasher::new()).is_err());
+
+        // Changing the id in the header leaves the signature valid but no longer matching it.
+        let mut tampered = bundle;
+        tampered[BUNDLE_HEADER_LEN + BUNDLE_ITEM_HEADER_LEN - 1] ^= 1;
+        assert_matches!(
+            deserialize_bundle(tampered, &Hasher::new()),
+            Err(Error::InvalidDataItemId)
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_data_items_to_bundle() {
         let arweave = Arweave::from_keypair_path(
@@ -321,7 +1438,7 @@ mod tests {
 
         let data_item_ser = data_item.serialize().unwrap();
 
-        let (bundle, manifest_object) = arweave
+        let (bundle, manifest_object, _) = arweave
             .create_bundle_from_data_items(vec![
                 (data_item.clone(), status.clone()),
                 (data_item, status),
@@ -412,4 +1529,180 @@ mod tests {
             1u16
         );
     }
+
+    #[tokio::test]
+    async fn test_tags_encoder_matches_avro() {
+        let data_item = get_test_data_item().await;
+        let shared_tags = vec![
+            Tag::<String>::from_utf8_strs("App-Name", "arloader").unwrap(),
+            Tag::<String>::from_utf8_strs("User-Agent", "arloader/0.0.0").unwrap(),
+        ];
+
+        let items: Vec<DataItem> = (0..1_000)
+            .map(|i| {
+                let mut tags = shared_tags.clone();
+                tags.push(Tag::<String>::from_utf8_strs("Content-Type", "image/png").unwrap());
+                tags.push(Tag::<String>::from_utf8_strs("Index", &i.to_string()).unwrap());
+                DataItem {
+                    tags,
+                    ..data_item.clone()
+                }
+            })
+            .collect();
+
+        let prefix = shared_tags_prefix(items.iter().map(|d| &d.tags));
+        assert_eq!(prefix, shared_tags);
+
+        let start = Instant::now();
+        let avro_bytes: Vec<Vec<u8>> = items.iter().map(|d| d.serialize().unwrap()).collect();
+        let avro_duration = start.elapsed();
+
+        let start = Instant::now();
+        let encoder = TagsEncoder::new(&prefix);
+        let encoded_bytes: Vec<Vec<u8>> = items
+            .iter()
+            .map(|d| {
+                let tags_bytes = encoder.encode(&d.tags[encoder.shared_len()..]);
+                d.serialize_with_tags_bytes(&tags_bytes).unwrap()
+            })
+            .collect();
+        let encoded_duration = start.elapsed();
+
+        println!(
+            "Time elapsed to serialize 1,000 data items: {} µs with avro, {} µs with shared tags",
+            avro_duration.as_micros(),
+            encoded_duration.as_micros()
+        );
+        assert_eq!(avro_bytes, encoded_bytes);
+
+        // Empty and long tag sets encode the same as well.
+        let long_value = "v".repeat(300);
+        let long_tags = vec![Tag::<String>::from_utf8_strs("Long", &long_value).unwrap()];
+        assert_eq!(TagsEncoder::new(&[]).encode(&[]), encode_tags(&[]).unwrap());
+        assert_eq!(
+            TagsEncoder::new(&[]).encode(&long_tags),
+            encode_tags(&long_tags).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_serialized_len() {
+        let data_item = get_test_data_item().await;
+        let target = Base64(vec![1; 32]);
+        let mut data_items = Vec::new();
+
+        for has_target in [false, true] {
+            for has_anchor in [false, true] {
+                for has_tags in [false, true] {
+                    for has_data in [false, true] {
+                        let data_item = DataItem {
+                            target: if has_target {
+                                target.clone()
+                            } else {
+                                Base64(vec![])
+                            },
+                            anchor: if has_anchor {
+                                data_item.anchor.clone()
+                            } else {
+                                Base64(vec![])
+                            },
+                            tags: if has_tags {
+                                data_item.tags.clone()
+                            } else {
+                                Vec::new()
+                            },
+                            data: if has_data {
+                                data_item.data.clone()
+                            } else {
+                                Base64(vec![])
+                            },
+                            ..data_item.clone()
+                        };
+                        assert_eq!(
+                            data_item.serialized_len(),
+                            data_item.serialize().unwrap().len()
+                        );
+                        data_items.push(data_item);
+                    }
+                }
+            }
+        }
+
+        let bundle = Bundle { data_items };
+        assert_eq!(bundle.serialized_len(), bundle.serialize().unwrap().len());
+    }
+
+    #[test]
+    fn test_resolve_bundle_tags() -> Result<(), Error> {
+        let data_items: Vec<(DataItem, Status)> = ["assets/000.png", "assets/036.png"]
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                (
+                    DataItem {
+                        id: Base64(vec![i as u8; 32]),
+                        data: Base64(vec![0; 10 * (i + 1)]),
+                        ..DataItem::default()
+                    },
+                    Status {
+                        file_path: Some(PathBuf::from(p)),
+                        ..Status::default()
+                    },
+                )
+            })
+            .collect();
+        let summary = BundleSummary::new(&data_items);
+        let items_hash = Base64(
+            blake3::hash(&[vec![0; 32], vec![1; 32]].concat())
+                .as_bytes()
+                .to_vec(),
+        );
+        assert_eq!(
+            summary,
+            BundleSummary {
+                count: 2,
+                bytes: 30,
+                items_hash: items_hash.clone(),
+                first_path: "assets/000.png".to_string(),
+                last_path: "assets/036.png".to_string(),
+            }
+        );
+
+        let templates = vec![
+            Tag::<String>::from_utf8_strs("File-Count", "{count}")?,
+            Tag::<String>::from_utf8_strs("Content-Hash", "{items_hash}")?,
+            Tag::<String>::from_utf8_strs("Paths", "{first_path}..{last_path} ({bytes} bytes)")?,
+            Tag::<String>::from_utf8_strs("Plain", "value")?,
+            Tag::<String>::from_utf8_strs("Escaped", "{{count}}: {{{count}}} }")?,
+        ];
+        let tags = resolve_bundle_tags(&templates, &summary)?;
+        let values: Vec<String> = tags
+            .iter()
+            .map(|t| t.value.to_utf8_string().unwrap())
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                "2".to_string(),
+                items_hash.to_string(),
+                "assets/000.png..assets/036.png (30 bytes)".to_string(),
+                "value".to_string(),
+                "{count}: {2} }".to_string(),
+            ]
+        );
+        assert_eq!(tags[0].name.to_utf8_string()?, "File-Count");
+
+        assert!(check_bundle_tag_template("{count} of {bytes}").is_ok());
+        assert!(check_bundle_tag_template("{{size}}").is_ok());
+        assert_matches!(
+            check_bundle_tag_template("{size}"),
+            Err(Error::InvalidBundleTag(p)) if p == "size"
+        );
+        assert_matches!(
+            check_bundle_tag_template("{count"),
+            Err(Error::InvalidBundleTag(_))
+        );
+        assert_eq!(BundleSummary::new(&[]).first_path, "");
+        Ok(())
+    }
 }
@@ -0,0 +1,60 @@
+//! Functionality for checking balances with bundler nodes.
+
+use crate::{endpoints::Endpoints, error::Error};
+use serde_json::Value;
+use url::Url;
+
+/// Returns the prepaid balance in winstons of `address` with the bundler node at `base_url`.
+pub async fn get_balance(base_url: &Url, address: &str) -> Result<u64, Error> {
+    let url = Endpoints::account_balance(base_url, address)?;
+    let resp = reqwest::get(url).await?;
+    if !resp.status().is_success() {
+        return Err(Error::ArweaveNetworkError(resp.status()));
+    }
+    let result: Value = resp.json().await?;
+
+    // Balances may be returned as either strings or numbers.
+    match &result["balance"] {
+        Value::String(s) => s.parse::<u64>().map_err(|_| Error::InvalidBundlerResponse),
+        Value::Number(n) => n.as_u64().ok_or(Error::InvalidBundlerResponse),
+        _ => Err(Error::InvalidBundlerResponse),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::get_balance;
+    use crate::{error::Error, utils::mock_server};
+    use matches::assert_matches;
+
+    const ADDRESS: &str = "7eV1qae4qVNqsNChg3Scdi-DpOLJPCogct4ixoq1WNg";
+
+    fn mock_bundler(balance: &'static str) -> url::Url {
+        let (url, _) = mock_server::serve(move |path| match path {
+            p if p.starts_with("/account/balance/") => ("200 OK", balance.to_string()),
+            _ => ("404 Not Found", String::new()),
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_get_balance() -> Result<(), Error> {
+        let (url, requests) =
+            mock_server::serve(|_| ("200 OK", r#"{"balance": "2500"}"#.to_string()));
+        assert_eq!(get_balance(&url, ADDRESS).await?, 2500);
+        assert_eq!(
+            requests.recv().unwrap().path,
+            format!("/account/balance/{}", ADDRESS)
+        );
+
+        let url = mock_bundler(r#"{"balance": 2500}"#);
+        assert_eq!(get_balance(&url, ADDRESS).await?, 2500);
+
+        let url = mock_bundler(r#"{"error": "not found"}"#);
+        assert_matches!(
+            get_balance(&url, ADDRESS).await,
+            Err(Error::InvalidBundlerResponse)
+        );
+        Ok(())
+    }
+}
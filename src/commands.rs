@@ -1,1043 +1,4906 @@
 //! Functions for Cli commands comprised of library functions.
 
 use crate::{
+    bundle::BUNDLE_HEADER_LEN,
+    bundler, check_local_files_stream,
+    crypto::{address_from_modulus, write_keypair, Provider},
     error::Error,
     file_stem_is_valid_txid,
-    solana::{FLOOR, RATE, SOLANA_MAIN_URL, SOL_AR_BASE_URL},
-    status::{OutputFormat, StatusCode},
-    transaction::{Base64, Tag},
+    merkle::chunks_len,
+    nft_stage_error,
+    plan::UploadPlan,
+    queue::{UploadQueue, QUEUE_STATUS_DIR},
+    solana::{
+        load_keypair, SigResponse, FLOOR, RATE, SOLANA_MAIN_URL, SOL_AR_BASE_URL, SOL_TX_FEE,
+    },
+    status::{
+        merge_duplicate_statuses, parse_status, sort_statuses, to_status_json, BundleStatus,
+        Filterable, LocalFileState, MergedStatuses, Metrics, OutputFormat, OutputHeader, Receipt,
+        ReceiptTransaction, RecordPrinter, RecordedSize, SortKey, Sortable, Status, StatusCode,
+        StatusUpdate, SummaryAccumulator, SystemClock, UploadCheck, UploadProgress,
+    },
+    transaction::{Base64, FromUtf8Strs, RewardBreakdown, Tag, Transaction},
     update_bundle_statuses_stream, update_statuses_stream, upload_bundles_stream,
     upload_bundles_stream_with_sol, upload_files_stream, upload_files_with_sol_stream, Arweave,
-    PathsChunk, BLOCK_SIZE, WINSTONS_PER_AR,
+    CancelToken, FailedUpdate, FailedUpload, FilesLink, ImageLink, MetadataBackup, NetworkParams,
+    NftUploadStage, PathsChunk, RetryPolicy, BYTES_PER_KB, CHUNKS_BUFFER_FACTOR, SUPERSEDED_DIR,
+    WINSTONS_PER_AR,
 };
 
+use chrono::{DateTime, Utc};
 use futures::{
-    future::{try_join, try_join_all},
-    StreamExt,
+    future::{join, try_join},
+    stream, Future, Stream, StreamExt,
 };
 use glob::glob;
 use num_traits::cast::ToPrimitive;
-use solana_sdk::signer::keypair;
-use std::{path::PathBuf, str::FromStr};
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::atomic::Ordering,
+};
+use sysinfo::{System, SystemExt};
 use tokio::{
     fs,
-    time::{sleep, Duration},
+    io::AsyncWriteExt,
+    task::JoinHandle,
+    time::{sleep, timeout_at, Duration, Instant},
 };
 use url::Url;
 
 pub type CommandResult = Result<(), Error>;
 
-/// Gets cost of uploading a list of files.
-pub async fn command_files(paths: Option<Vec<PathBuf>>) -> CommandResult {
-    println!("{:?}", paths);
-    Ok(())
-}
-
-/// Gets cost of uploading a list of files.
-pub async fn command_get_cost<IP>(
-    arweave: &Arweave,
-    paths_iter: IP,
-    reward_mult: f32,
-    with_sol: bool,
-    bundle_size: u64,
-    no_bundle: bool,
-) -> CommandResult
-where
-    IP: Iterator<Item = PathBuf> + Send + Sync,
-{
-    let (base, incremental) = arweave.get_price_terms(reward_mult).await?;
-    let (_, usd_per_ar, usd_per_sol) = arweave.get_price(&1).await?;
+/// Exit code used when a command only partially completes, e.g. when `--max-runtime` is reached.
+pub const PARTIAL_FAILURE_EXIT_CODE: u8 = 2;
 
-    let units = match with_sol {
-        true => "lamports",
-        false => "winstons",
-    };
+/// File in the log dir that [`command_verify_uploads`] writes failed checks to.
+pub const VERIFY_FAILURES_FILE: &str = "verify_failures.json";
 
-    let (num_trans, num_files, cost, bytes) = if no_bundle {
-        paths_iter.fold((0, 0, 0, 0), |(n_t, n_f, c, b), p| {
-            let data_len = p.metadata().unwrap().len();
-            (
-                n_t + 1,
-                n_f + 1,
-                c + {
-                    let blocks_len = data_len / BLOCK_SIZE + (data_len % BLOCK_SIZE != 0) as u64;
-                    match with_sol {
-                        true => {
-                            std::cmp::max((base + incremental * (blocks_len - 1)) / RATE, FLOOR)
-                                + 5000
-                        }
-                        false => base + incremental * (blocks_len - 1),
-                    }
-                },
-                b + data_len,
-            )
-        })
-    } else {
-        let path_chunks = arweave.chunk_file_paths(paths_iter, bundle_size)?;
-        path_chunks.iter().fold(
-            (0, 0, 0, 0),
-            |(n_t, n_f, c, b), PathsChunk(paths, data_len)| {
-                (
-                    n_t + 1,
-                    n_f + paths.len(),
-                    c + {
-                        let blocks_len =
-                            data_len / BLOCK_SIZE + (data_len % BLOCK_SIZE != 0) as u64;
-                        match with_sol {
-                            true => {
-                                std::cmp::max((base + incremental * (blocks_len - 1)) / RATE, FLOOR)
-                                    + 5000
-                            }
-                            false => base + incremental * (blocks_len - 1),
-                        }
-                    },
-                    b + data_len,
-                )
-            },
-        )
-    };
+/// Time allowed for uploads in flight to finish after `--max-runtime` is reached.
+pub const MAX_RUNTIME_GRACE: Duration = Duration::from_secs(30);
 
-    // get usd cost based on calculated cost
-    let usd_cost = match with_sol {
-        true => (&cost * &usd_per_sol).to_f32().unwrap() / 1e11_f32,
-        false => (&cost * &usd_per_ar).to_f32().unwrap() / 1e14_f32,
-    };
+/// Time between status checks while [`command_upload_one`] waits for the upload to be pending.
+pub const UPLOAD_ONE_POLL_INTERVAL: Duration = Duration::from_secs(2);
 
-    println!(
-        "The price to upload {} files with {} total bytes in {} transaction(s) is {} {} (${:.4}).",
-        num_files, bytes, num_trans, cost, units, usd_cost
-    );
+/// Number of status checks [`command_upload_one`] makes before giving up on waiting.
+pub const UPLOAD_ONE_MAX_POLLS: u32 = 30;
 
-    Ok(())
+/// Deadline set with `--max-runtime`. The task that cancels the command's [`CancelToken`] when
+/// the runtime is up is aborted when the deadline is dropped, so that it doesn't outlive the
+/// command.
+#[derive(Default)]
+struct Deadline {
+    /// Time after which uploads still in flight are abandoned.
+    at: Option<Instant>,
+    cancel_task: Option<JoinHandle<()>>,
 }
 
-/// Displays pending transaction count every second for one minute.
-pub async fn command_get_pending_count(arweave: &Arweave) -> CommandResult {
-    println!(" {}\n{:-<84}", "pending tx", "");
+impl Deadline {
+    /// Runs `future` to completion, or returns `None` if uploads in flight are abandoned first.
+    async fn before<F: Future>(&self, future: F) -> Option<F::Output> {
+        match self.at {
+            Some(at) => timeout_at(at, future).await.ok(),
+            None => Some(future.await),
+        }
+    }
 
-    let mut counter = 0;
-    while counter < 60 {
-        sleep(Duration::from_secs(1)).await;
-        let count = arweave.get_pending_count().await?;
-        println!(
-            "{:>5} {} {}",
-            count,
-            124u8 as char,
-            std::iter::repeat('\u{25A5}')
-                .take(count / 50 + 1)
-                .collect::<String>()
-        );
-        counter += 1;
+    fn abandoned(&self) -> bool {
+        self.at.map(|at| Instant::now() >= at).unwrap_or(false)
     }
-    Ok(())
 }
 
-/// Gets status from the network for the provided transaction id.
-pub async fn command_get_status(
-    arweave: &Arweave,
-    id: &str,
-    output_format: &OutputFormat,
-) -> CommandResult {
-    let id = Base64::from_str(id)?;
-    let status = arweave.get_status(&id).await?;
-    println!(
-        "{}",
-        status
-            .header_string(output_format)
-            .split_at(32)
-            .1
-            .split_at(132)
-            .0
-    );
-    print!("{}", output_format.formatted_string(&status).split_at(32).1);
-    Ok(())
+impl Drop for Deadline {
+    fn drop(&mut self) {
+        if let Some(task) = &self.cancel_task {
+            task.abort();
+        }
+    }
 }
 
-/// Retrieves transaction from the network.
-pub async fn command_get_transaction(arweave: &Arweave, id: &str) -> CommandResult {
-    let id = Base64::from_str(id)?;
-    let transaction = arweave.get_transaction(&id).await?;
-    println!("Fetched transaction {}", transaction.id);
-    Ok(())
+/// Spawns a task that cancels `token` once `max_runtime` seconds have elapsed, returning the
+/// deadline after which uploads still in flight are abandoned.
+fn arm_deadline(token: &CancelToken, max_runtime: Option<u64>) -> Deadline {
+    match max_runtime {
+        Some(secs) => {
+            let token = token.clone();
+            let runtime = Duration::from_secs(secs);
+            Deadline {
+                at: Some(Instant::now() + runtime + MAX_RUNTIME_GRACE),
+                cancel_task: Some(tokio::spawn(async move {
+                    sleep(runtime).await;
+                    token.cancel();
+                })),
+            }
+        }
+        None => Deadline::default(),
+    }
 }
 
-/// Lists transaction statuses, filtered by statuses and max confirmations if provided.
-pub async fn command_list_statuses<IP>(
-    arweave: &Arweave,
-    paths_iter: IP,
-    log_dir: &str,
-    statuses: Option<Vec<StatusCode>>,
-    max_confirms: Option<u64>,
-    output_format: &OutputFormat,
-) -> CommandResult
+/// Waits for the next item from `stream`, returning `None` if `deadline` passes first.
+async fn next_before_deadline<S>(stream: &mut S, deadline: &Deadline) -> Option<S::Item>
 where
-    IP: Iterator<Item = PathBuf> + Send + Sync,
+    S: Stream + Unpin,
 {
-    let log_dir_str = log_dir;
-    let log_dir = PathBuf::from(log_dir_str);
+    deadline.before(stream.next()).await.flatten()
+}
 
-    let all_statuses = arweave.read_statuses(paths_iter, log_dir).await;
-    if let Ok(all_statuses) = all_statuses {
-        let mut counter = 0;
-        for status in arweave
-            .filter_statuses(all_statuses, statuses, max_confirms)?
-            .iter()
-        {
-            if counter == 0 {
-                println!("{}", status.header_string(&output_format));
-            }
-            print!("{}", output_format.formatted_string(status));
-            counter += 1;
-        }
-        if counter == 0 {
-            println!("Didn't find any matching statuses.");
-        } else {
-            println!("Found {} files matching filter criteria.", counter);
-        }
-    } else {
-        println!(
-            "Didn't find statuses for one or more file paths in {}.",
-            log_dir_str
-        );
-    }
-    Ok(())
+/// Files of `paths` that weren't uploaded or reported as failed, either because they weren't
+/// started before `--max-runtime` was reached or because they were still in flight when uploads
+/// were abandoned.
+fn unfinished_paths(paths: &[PathBuf], finished: &HashSet<PathBuf>) -> Vec<PathBuf> {
+    paths
+        .iter()
+        .filter(|p| !finished.contains(*p))
+        .cloned()
+        .collect()
 }
 
-/// Lists transaction statuses, filtered by statuses and max confirmations if provided.
-pub async fn command_list_bundle_statuses(
-    arweave: &Arweave,
-    log_dir: &str,
-    statuses: Option<Vec<StatusCode>>,
-    max_confirms: Option<u64>,
+/// Lists files that weren't uploaded because `--max-runtime` was reached, returning an error so
+/// that the command exits with the partial failure exit code.
+fn check_max_runtime(
+    token: &CancelToken,
+    deadline: &Deadline,
+    unfinished: Vec<PathBuf>,
     output_format: &OutputFormat,
 ) -> CommandResult {
-    let mut counter = 0;
-    let all_statuses = arweave.read_bundle_statuses(log_dir).await?;
+    let abandoned = deadline.abandoned();
+    if !token.is_cancelled() || (unfinished.is_empty() && !abandoned) {
+        return Ok(());
+    }
 
-    for status in arweave
-        .filter_statuses(all_statuses, statuses, max_confirms)?
+    print_note(
+        output_format,
+        &format!(
+            "\nReached --max-runtime before finishing {} files:",
+            unfinished.len()
+        ),
+    );
+    unfinished
         .iter()
-    {
-        if counter == 0 {
-            println!("{}", status.header_string(&output_format));
-        }
-        print!("{}", output_format.formatted_string(status));
-        counter += 1;
+        .for_each(|p| print_note(output_format, &format!("{}", p.display())));
+    if abandoned {
+        print_note(
+            output_format,
+            "Uploads still in progress after the grace period were abandoned.",
+        );
     }
-    if counter == 0 {
-        println!("Didn't find any matching statuses.");
-    } else {
-        println!("Found {} files matching filter criteria.", counter);
+    Err(Error::MaxRuntimeExceeded(unfinished.len()))
+}
+
+/// Multiple of the bundle bytes in flight allowed for serialization and request buffers when
+/// estimating the memory used by uploads.
+pub const MEMORY_SAFETY_FACTOR: f64 = 2.5;
+
+/// Source of the memory available for uploads.
+pub trait MemoryProbe {
+    /// Returns available memory in bytes, or `None` if it can't be determined.
+    fn available_memory(&self) -> Option<u64>;
+}
+
+/// [`MemoryProbe`] backed by the operating system.
+pub struct SystemMemory;
+
+impl MemoryProbe for SystemMemory {
+    fn available_memory(&self) -> Option<u64> {
+        let mut system = System::new();
+        system.refresh_memory();
+        // Reported in kilobytes.
+        match system.available_memory() {
+            0 => None,
+            kb => Some(kb * 1024),
+        }
     }
-    Ok(())
 }
 
-/// Prints a count of transactions by status.
-pub async fn command_status_report<IP>(
-    arweave: &Arweave,
-    paths_iter: IP,
-    log_dir: &str,
-) -> CommandResult
-where
-    IP: Iterator<Item = PathBuf> + Send + Sync,
-{
-    let log_dir = PathBuf::from(log_dir);
-    let summary = arweave.status_summary(paths_iter, log_dir).await?;
-    println!("{}", summary);
-    Ok(())
+/// Returns the estimated peak memory in bytes used by uploading `buffer` bundles of
+/// `bundle_size` bytes concurrently.
+pub fn estimate_upload_memory(bundle_size: u64, buffer: usize) -> u64 {
+    (bundle_size as f64 * buffer as f64 * MEMORY_SAFETY_FACTOR) as u64
 }
 
-/// Updates bundle statuses for provided files in provided directory.
-pub async fn command_update_bundle_statuses(
-    arweave: &Arweave,
-    log_dir: PathBuf,
-    output_format: &OutputFormat,
+/// Checks that the estimated memory needed for bundle uploads is available, only printing a
+/// warning if `ignore` is set.
+pub fn check_upload_memory<M: MemoryProbe>(
+    probe: &M,
+    bundle_size: u64,
     buffer: usize,
+    ignore: bool,
 ) -> CommandResult {
-    let paths_iter = glob(&format!("{}*.json", log_dir.display().to_string()))?
-        .filter_map(Result::ok)
-        .filter(|p| file_stem_is_valid_txid(p));
-
-    let mut stream = update_bundle_statuses_stream(arweave, paths_iter, buffer);
-    let mut counter = 0;
-    while let Some(Ok(status)) = stream.next().await {
-        if counter == 0 {
-            println!("{}", status.header_string(&output_format));
+    let required = estimate_upload_memory(bundle_size, buffer);
+    match probe.available_memory() {
+        Some(available) if required > available => {
+            let error = Error::InsufficientMemory {
+                required,
+                available,
+            };
+            if ignore {
+                eprintln!("Warning: {}.", error);
+                Ok(())
+            } else {
+                Err(error)
+            }
         }
-        print!("{}", output_format.formatted_string(&status));
-        counter += 1;
-    }
-    if counter == 0 {
-        println!(
-            "The <LOG_DIR> you provided, {}, didn't have any statuses in it.",
-            log_dir.display().to_string()
-        );
-    } else {
-        println!("Updated {} statuses.", counter);
+        _ => Ok(()),
     }
-
-    Ok(())
 }
 
-/// Updates NFT metadata files from a manifest file.
-pub async fn command_update_metadata<IP>(
-    arweave: &Arweave,
-    paths_iter: IP,
-    manifest_path: PathBuf,
-    link_file: bool,
-    update_image: bool,
-    update_animation_url: bool,
-) -> CommandResult
-where
-    IP: Iterator<Item = PathBuf> + Send + Sync,
-{
-    let paths_vec = paths_iter.collect::<Vec<PathBuf>>();
-    let num_paths: usize = paths_vec.len();
+/// Number of failed file paths above which they are written to [`FAILED_PATHS_FILE`] in the log
+/// directory instead of being printed.
+pub const MAX_PRINTED_FAILED_PATHS: usize = 20;
 
-    arweave
-        .update_metadata(
-            paths_vec.into_iter(),
-            manifest_path,
-            link_file,
-            update_image,
-            update_animation_url,
-        )
-        .await?;
+/// Name of the file failed file paths are written to.
+pub const FAILED_PATHS_FILE: &str = "failed_paths.txt";
 
-    println!("Successfully updated {} metadata files.", num_paths);
-    Ok(())
+/// Name of the file the paths of files whose statuses failed to update are written to, separate
+/// from [`FAILED_PATHS_FILE`] so that it doesn't replace the list of files to upload again.
+pub const FAILED_UPDATES_FILE: &str = "failed_updates.txt";
+
+/// Prints the bytes saved by [`Arweave::gzip_requests`] with verbose output.
+fn print_gzip_savings(arweave: &Arweave, output_format: &OutputFormat) {
+    if !arweave.gzip_requests || !matches!(output_format, OutputFormat::DisplayVerbose) {
+        return;
+    }
+    if arweave.gzip_stats.rejected() {
+        println!("Gateway doesn't accept gzip encoded requests, sent them uncompressed.");
+    }
+    println!(
+        "Gzip encoding saved {} KB.",
+        arweave.gzip_stats.bytes_saved() / 1000
+    );
 }
 
-/// Updates statuses for uploaded nfts.
-pub async fn command_update_nft_statuses(
-    arweave: &Arweave,
-    log_dir: &str,
-    output_format: &OutputFormat,
-    buffer: usize,
-) -> CommandResult {
-    let log_dir = PathBuf::from(log_dir);
-    let log_dir_assets = log_dir.join("assets/");
-    let log_dir_metadata = log_dir.join("metadata/");
-    let asset_manifest_txid = get_manifest_id_from_log_dir(&log_dir_assets);
-    let metadata_manifest_txid = get_manifest_id_from_log_dir(&log_dir_metadata);
-
-    println!("\n\nUpdating asset bundle statuses...\n");
-    command_update_bundle_statuses(&arweave, log_dir_assets, output_format, buffer).await?;
-    println!("\n\nUpdating metadata bundle statuses...\n");
-    command_update_bundle_statuses(&arweave, log_dir_metadata, output_format, buffer).await?;
-    println!("\n\nUpdating asset manifest status...\n");
-    command_get_status(&arweave, &asset_manifest_txid, output_format).await?;
-    println!("\n\nUpdating metadata manifest status...\n");
-    command_get_status(&arweave, &metadata_manifest_txid, output_format).await?;
-    Ok(())
+/// Prints a message that isn't part of the records of a command, to stderr with json output
+/// formats so that stdout can be parsed as json.
+pub fn print_note(output_format: &OutputFormat, note: &str) {
+    if output_format.is_json() {
+        eprintln!("{}", note);
+    } else {
+        println!("{}", note);
+    }
 }
 
-/// Updates statuses for provided files in provided directory.
-pub async fn command_update_statuses<IP>(
-    arweave: &Arweave,
-    paths_iter: IP,
-    log_dir: PathBuf,
+/// Lists file paths that failed to upload, writing them to [`FAILED_PATHS_FILE`] in `log_dir`
+/// instead if there are more than [`MAX_PRINTED_FAILED_PATHS`]. Returns the path of the file if
+/// one was written.
+async fn report_failed_paths(
+    failed_paths: &[PathBuf],
+    log_dir: Option<&Path>,
     output_format: &OutputFormat,
-    buffer: usize,
-) -> CommandResult
-where
-    IP: Iterator<Item = PathBuf> + Send + Sync,
-{
-    let log_dir = PathBuf::from(log_dir);
-
-    let mut stream = update_statuses_stream(arweave, paths_iter, log_dir.clone(), buffer);
-    let mut counter = 0;
-    while let Some(Ok(status)) = stream.next().await {
-        if counter == 0 {
-            println!("{}", status.header_string(output_format));
-        }
-        print!("{}", output_format.formatted_string(&status));
-        counter += 1;
+) -> Result<Option<PathBuf>, Error> {
+    if failed_paths.is_empty() {
+        return Ok(None);
     }
-    if counter == 0 {
-        println!("The <GLOB> and <LOG_DIR> combination you provided didn't return any statuses.");
-    } else {
-        println!("Updated {} statuses.", counter);
+    let paths_string: String = failed_paths
+        .iter()
+        .map(|p| format!("{}\n", p.display()))
+        .collect();
+    match log_dir {
+        Some(log_dir) if failed_paths.len() > MAX_PRINTED_FAILED_PATHS => {
+            let file_path = log_dir.join(FAILED_PATHS_FILE);
+            fs::write(&file_path, paths_string).await?;
+            print_note(
+                output_format,
+                &format!(
+                    "\n{} files failed to upload. Their paths were written to {}.",
+                    failed_paths.len(),
+                    file_path.display()
+                ),
+            );
+            Ok(Some(file_path))
+        }
+        _ => {
+            print_note(
+                output_format,
+                &format!(
+                    "\n{} files failed to upload:\n{}",
+                    failed_paths.len(),
+                    paths_string
+                ),
+            );
+            Ok(None)
+        }
     }
+}
 
-    Ok(())
+/// Writes `receipt` to `log_dir` as [`Receipt::file_name`] once the run has finished, whether or
+/// not it finished with an error, numbering the file if another run already wrote a receipt with
+/// the same name. Returns the path of the receipt.
+async fn write_receipt(
+    arweave: &Arweave,
+    receipt: &mut Receipt,
+    log_dir: &Path,
+    output_format: &OutputFormat,
+) -> Result<PathBuf, Error> {
+    receipt.finish();
+    let json = to_status_json(receipt, arweave.pretty_status)?;
+    let mut n = 0;
+    let (file_path, mut file) = loop {
+        let file_path = log_dir.join(receipt.file_name(n));
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&file_path)
+            .await
+        {
+            Ok(file) => break (file_path, file),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => n += 1,
+            Err(e) => return Err(e.into()),
+        }
+    };
+    file.write_all(json.as_bytes()).await?;
+    print_note(
+        output_format,
+        &format!("Wrote receipt to {}.", file_path.display()),
+    );
+    Ok(file_path)
 }
 
-/// Uploads files to Arweave.
-pub async fn command_upload<IP>(
+/// Verifies serialized data items signed by other tools, printing the id of each, and bundles
+/// and posts them if `post`. Nothing is posted if any of them fails to verify.
+pub async fn command_bundle_items<IP>(
     arweave: &Arweave,
     paths_iter: IP,
+    post: bool,
     log_dir: Option<PathBuf>,
-    tags: Option<Vec<Tag<Base64>>>,
     reward_mult: f32,
     output_format: &OutputFormat,
     buffer: usize,
+    retry_policy: RetryPolicy,
+    skip_balance_check: bool,
 ) -> CommandResult
 where
-    IP: Iterator<Item = PathBuf> + Send + Sync,
+    IP: Iterator<Item = PathBuf> + Send,
 {
-    let price_terms = arweave.get_price_terms(reward_mult).await?;
+    let mut paths = Vec::new();
+    let mut items = Vec::new();
+    let mut ids = HashSet::new();
+    let mut failed = 0;
+    for path in paths_iter {
+        let bytes = fs::read(&path).await?;
+        let result = arweave
+            .deserialize_signed_data_item(&bytes)
+            .and_then(|data_item| match ids.insert(data_item.id.to_string()) {
+                true => Ok(data_item),
+                false => Err(Error::DuplicateDataItem(data_item.id.to_string())),
+            });
+        match result {
+            Ok(data_item) => print_note(
+                output_format,
+                &format!(" {:<43}  {}", data_item.id.to_string(), path.display()),
+            ),
+            Err(e) => {
+                print_note(
+                    output_format,
+                    &format!(" {:<43}  {} ({})", "FAIL", path.display(), e),
+                );
+                failed += 1;
+            }
+        }
+        paths.push(path);
+        items.push(bytes);
+    }
 
-    let mut stream = upload_files_stream(
-        arweave,
-        paths_iter,
-        tags,
-        log_dir.clone(),
-        None,
-        price_terms,
-        buffer,
+    if paths.is_empty() {
+        print_note(output_format, "<FILE_PATHS> didn't match any files.");
+        return Ok(());
+    }
+    if failed > 0 {
+        return Err(Error::BundleVerificationFailed(failed));
+    }
+    print_note(
+        output_format,
+        &format!("\nVerified {} data items.", items.len()),
     );
-
-    let mut counter = 0;
-    while let Some(result) = stream.next().await {
-        match result {
-            Ok(status) => {
-                if counter == 0 {
-                    if let Some(log_dir) = &log_dir {
-                        println!("Logging statuses to {}", &log_dir.display());
-                    }
-                    println!("{}", status.header_string(&output_format));
-                }
-                print!("{}", output_format.formatted_string(&status));
-                counter += 1;
-            }
-            Err(e) => println!("{:#?}", e),
-        }
+    if !post {
+        return Ok(());
     }
 
-    if counter == 0 {
-        println!("<FILE_PATHS> didn't match any files.");
-    } else {
-        println!(
-            "Uploaded {} files. Run `arloader update-status {} --file-paths <FILE_PATHS>` to confirm transaction(s).",
-            counter,
-            &log_dir.unwrap_or(PathBuf::from("")).display(),
-        );
+    let price_terms = arweave.get_price_terms(reward_mult).await?;
+    if !skip_balance_check {
+        let data_size = items.iter().map(|bytes| bytes.len() as u64).sum();
+        arweave
+            .check_bundles_wallet_balance(&[PathsChunk(paths.clone(), data_size)], price_terms)
+            .await?;
     }
+    let log_dir = if let Some(log_dir) = log_dir {
+        ensure_log_dir(&log_dir).await?;
+        log_dir
+    } else {
+        arweave
+            .create_log_dir(paths[0].parent().unwrap_or_else(|| Path::new(".")))
+            .await?
+    };
+    let status = arweave
+        .post_bundle_from_serialized_items(
+            items,
+            Some(log_dir.clone()),
+            price_terms,
+            buffer * CHUNKS_BUFFER_FACTOR,
+            retry_policy,
+        )
+        .await?;
+    fs::write(
+        log_dir.join(status.id.to_string()).with_extension("json"),
+        to_status_json(&status, arweave.pretty_status)?,
+    )
+    .await?;
 
+    let header = BundleStatus::header_string(output_format);
+    if !header.is_empty() {
+        print_note(output_format, &format!("\n{}", header));
+    }
+    print!("{}", output_format.formatted_string(&status));
+    print_note(output_format, &format!("\nPosted {} data items in bundle {}. Run `arloader update-status {}` to update its status.",
+        status.number_of_files,
+        status.id,
+        log_dir.display()));
     Ok(())
 }
 
-/// Uploads bundles created from provided glob to Arweave.
-pub async fn command_upload_bundles(
+/// Prints the prepaid balance of a wallet with a bundler node.
+pub async fn command_bundler_balance(
     arweave: &Arweave,
-    path_chunks: Vec<PathsChunk>,
-    log_dir: Option<PathBuf>,
-    tags: Option<Vec<Tag<String>>>,
-    reward_mult: f32,
-    output_format: &OutputFormat,
-    buffer: usize,
+    bundler_url: &Url,
+    wallet_address: Option<String>,
 ) -> CommandResult {
-    if path_chunks.len() == 0 {
-        println!("<FILE_PATHS> didn't match any files.");
-        return Ok(());
-    } else {
-        let tags = tags.unwrap_or(Vec::new());
-        let price_terms = arweave.get_price_terms(reward_mult).await?;
-        let log_dir = if let Some(log_dir) = log_dir {
-            log_dir
-        } else {
-            let parent_dir = path_chunks[0].0[0].parent().unwrap();
-            arweave.create_log_dir(parent_dir).await?
-        };
-
-        let (num_files, data_size) = path_chunks
-            .iter()
-            .fold((0, 0), |(f, d), c| (f + c.0.len(), d + c.1));
-
-        println!(
-            "Uploading {} files with {} KB of data in {} bundle transactions...\n",
-            num_files,
-            data_size / 1_000,
-            path_chunks.len(),
-        );
+    let wallet_address = match wallet_address {
+        Some(wallet_address) => wallet_address,
+        None => arweave.crypto.wallet_address()?.to_string(),
+    };
+    let balance = bundler::get_balance(bundler_url, &wallet_address).await?;
+    println!(
+        "Bundler balance for {} at {} is {} {} ({:.6} AR).",
+        wallet_address,
+        bundler_url,
+        balance,
+        arweave.units,
+        balance as f64 / WINSTONS_PER_AR as f64
+    );
+    Ok(())
+}
 
-        let mut stream = upload_bundles_stream(arweave, path_chunks, tags, price_terms, buffer);
+/// Gets cost of uploading a list of files.
+#[deprecated(since = "0.1.64", note = "use `command_get_cost` instead")]
+pub async fn command_files(paths: Option<Vec<PathBuf>>) -> CommandResult {
+    println!("{:?}", paths);
+    Ok(())
+}
 
-        let mut counter = 0;
-        let mut number_of_files = 0;
-        let mut data_size = 0;
+/// Estimated cost of a single transaction, for a file if uploading without bundling and for a
+/// bundle of files otherwise.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct CostEstimate {
+    pub paths: Vec<PathBuf>,
+    /// Size of the data of the transaction, which for a bundle includes the headers and tags
+    /// of its data items.
+    pub bytes: u64,
+    /// Bytes of file data in the transaction.
+    #[serde(default)]
+    pub data_bytes: u64,
+    pub blocks: u64,
+    /// Number of chunks the data is uploaded in.
+    pub chunks: u64,
+    /// Reward in winstons, before conversion to lamports if paying with SOL.
+    pub reward: RewardBreakdown,
+    /// Cost in winstons, or in lamports if paying with SOL.
+    pub cost: u64,
+    pub usd: f64,
+}
 
-        while let Some(result) = stream.next().await {
-            match result {
-                Ok(status) => {
-                    number_of_files += status.number_of_files;
-                    data_size += status.data_size;
-                    if counter == 0 {
-                        println!("{}", status.header_string(&output_format));
-                    }
-                    print!("{}", output_format.formatted_string(&status));
-                    fs::write(
-                        log_dir.join(status.id.to_string()).with_extension("json"),
-                        serde_json::to_string(&status)?,
-                    )
-                    .await?;
-                    counter += 1;
-                }
-                Err(e) => println!("{:#?}", e),
-            }
+impl CostEstimate {
+    /// Estimates the cost of uploading `paths` with `bytes` of data in a single transaction, all
+    /// of which is file data. `usd_per_unit` is the price in USD of a winston, or of a lamport if
+    /// `with_sol`.
+    pub fn new(
+        paths: Vec<PathBuf>,
+        bytes: u64,
+        price_terms: (u64, u64),
+        usd_per_unit: f64,
+        with_sol: bool,
+        network_params: &NetworkParams,
+    ) -> Self {
+        let reward = network_params.reward_breakdown(bytes, price_terms);
+        let cost = match with_sol {
+            true => std::cmp::max(reward.total / RATE, FLOOR) + SOL_TX_FEE,
+            false => reward.total,
+        };
+        Self {
+            paths,
+            bytes,
+            data_bytes: bytes,
+            blocks: reward.blocks,
+            chunks: chunks_len(bytes),
+            reward,
+            cost,
+            usd: cost as f64 * usd_per_unit,
         }
+    }
 
-        println!(
-            "\nUploaded {} KB in {} files in {} bundle transactions. Run `arloader update-status {}` to update statuses.",
-            data_size / 1000,
-            number_of_files,
-            counter,
-            log_dir.display().to_string()
-        );
+    /// Totals of `estimates`, with no paths. The base and incremental winstons of the reward are
+    /// the price terms the estimates share.
+    pub fn total(estimates: &[CostEstimate]) -> Self {
+        estimates.iter().fold(Self::default(), |t, e| Self {
+            paths: Vec::new(),
+            bytes: t.bytes + e.bytes,
+            data_bytes: t.data_bytes + e.data_bytes,
+            blocks: t.blocks + e.blocks,
+            chunks: t.chunks + e.chunks,
+            reward: RewardBreakdown {
+                base: e.reward.base,
+                incremental: e.reward.incremental,
+                blocks: t.reward.blocks + e.reward.blocks,
+                total: t.reward.total + e.reward.total,
+            },
+            cost: t.cost + e.cost,
+            usd: t.usd + e.usd,
+        })
+    }
+
+    /// Blocks, chunks and reward breakdown of the estimate, for display.
+    pub fn breakdown(&self) -> String {
+        format!(
+            "{} block(s) in {} chunk(s), at {} winstons for the first block and {} for each additional block of a transaction, for a reward of {} winstons.",
+            self.blocks, self.chunks, self.reward.base, self.reward.incremental, self.reward.total
+        )
     }
-    Ok(())
 }
 
-/// Uploads bundles created from provided glob to Arweave, paying with SOL.
-pub async fn command_upload_bundles_with_sol(
+/// Estimates the cost of each transaction needed to upload files, one per file if `no_bundle` and
+/// one per bundle of up to `bundle_size` bytes otherwise, priced by the size of the serialized
+/// bundle with the [`Arweave::data_item_overhead`] of each file.
+pub fn cost_estimates<IP>(
     arweave: &Arweave,
-    path_chunks: Vec<PathsChunk>,
-    log_dir: Option<PathBuf>,
-    tags: Option<Vec<Tag<String>>>,
-    reward_mult: f32,
-    output_format: &OutputFormat,
-    buffer: usize,
-    sol_keypair_path: PathBuf,
-) -> CommandResult {
-    if path_chunks.len() == 0 {
-        println!("<FILE_PATHS> didn't match any files.");
-        return Ok(());
+    paths_iter: IP,
+    price_terms: (u64, u64),
+    usd_per_unit: f64,
+    with_sol: bool,
+    bundle_size: u64,
+    no_bundle: bool,
+) -> Result<Vec<CostEstimate>, Error>
+where
+    IP: Iterator<Item = PathBuf> + Send + Sync,
+{
+    if no_bundle {
+        paths_iter
+            .map(|p| -> Result<CostEstimate, Error> {
+                let data_len = p.metadata()?.len();
+                Ok(CostEstimate::new(
+                    vec![p],
+                    data_len,
+                    price_terms,
+                    usd_per_unit,
+                    with_sol,
+                    &arweave.network_params,
+                ))
+            })
+            .collect()
     } else {
-        let tags = tags.unwrap_or(Vec::new());
-        let price_terms = arweave.get_price_terms(reward_mult).await?;
-        let log_dir = if let Some(log_dir) = log_dir {
-            log_dir
-        } else {
-            let parent_dir = &path_chunks[0].0[0].parent().unwrap();
-            arweave.create_log_dir(parent_dir).await?
-        };
-        let solana_url = SOLANA_MAIN_URL.parse::<Url>()?;
-        let sol_ar_url = SOL_AR_BASE_URL.parse::<Url>()?.join("sol")?;
-        let from_keypair = keypair::read_keypair_file(sol_keypair_path)?;
+        let item_overhead = arweave.data_item_overhead()? as u64;
+        Ok(arweave
+            .chunk_file_paths(paths_iter, bundle_size)?
+            .into_iter()
+            .map(|PathsChunk(paths, data_len)| {
+                let bundle_len =
+                    BUNDLE_HEADER_LEN as u64 + data_len + paths.len() as u64 * item_overhead;
+                CostEstimate {
+                    data_bytes: data_len,
+                    ..CostEstimate::new(
+                        paths,
+                        bundle_len,
+                        price_terms,
+                        usd_per_unit,
+                        with_sol,
+                        &arweave.network_params,
+                    )
+                }
+            })
+            .collect())
+    }
+}
 
-        let (num_files, data_size) = path_chunks
-            .iter()
-            .fold((0, 0), |(f, d), c| (f + c.0.len(), d + c.1));
+/// Json output of [`command_get_cost`]: the estimate for each transaction and their total.
+pub fn cost_estimates_json(estimates: &[CostEstimate], with_sol: bool) -> Value {
+    json!({
+        "units": if with_sol { "lamports" } else { "winstons" },
+        "transactions": estimates,
+        "total": CostEstimate::total(estimates),
+    })
+}
 
-        println!(
-            "Uploading {} files with {} KB of data in {} bundle transactions...\n",
-            num_files,
-            data_size / 1_000,
-            path_chunks.len(),
-        );
+/// Units of cost estimates and the price in USD of one of them, a winston, or a lamport if
+/// `with_sol`.
+async fn price_units(arweave: &Arweave, with_sol: bool) -> Result<(&'static str, f64), Error> {
+    let (_, usd_per_ar, usd_per_sol) = arweave.get_price(&1).await?;
 
-        let mut stream = upload_bundles_stream_with_sol(
-            arweave,
-            path_chunks,
-            tags,
-            price_terms,
-            buffer,
-            solana_url,
-            sol_ar_url,
-            &from_keypair,
-        );
+    // Oracle prices are in cents per AR or SOL.
+    Ok(match with_sol {
+        true => ("lamports", usd_per_sol.to_f64().unwrap() / 1e11),
+        false => ("winstons", usd_per_ar.to_f64().unwrap() / 1e14),
+    })
+}
 
-        let mut counter = 0;
-        let mut number_of_files = 0;
-        let mut data_size = 0;
-        while let Some(result) = stream.next().await {
-            match result {
-                Ok(status) => {
-                    number_of_files += status.number_of_files;
-                    data_size += status.data_size;
-                    if counter == 0 {
-                        println!("{}", status.header_string(&output_format));
-                    }
-                    print!("{}", output_format.formatted_string(&status));
-                    fs::write(
-                        log_dir.join(status.id.to_string()).with_extension("json"),
-                        serde_json::to_string(&status)?,
-                    )
-                    .await?;
-                    counter += 1;
-                }
-                Err(e) => println!("{:#?}", e),
-            }
-        }
+/// Rewards recorded in statuses compared with the rewards for the same data at current prices,
+/// both in winstons.
+#[derive(Serialize, Debug, Default, PartialEq, Clone, Copy)]
+pub struct RewardComparison {
+    pub paid: u64,
+    pub current: u64,
+}
 
-        println!(
-            "\nUploaded {} KB in {} files in {} bundle transaction(s). Run `arloader update-status {}` to update statuses.",
-            data_size / 1000,
-            number_of_files,
-            counter,
-            log_dir.display().to_string()
-        );
+impl RewardComparison {
+    pub fn new(
+        sizes: &[RecordedSize],
+        price_terms: (u64, u64),
+        network_params: &NetworkParams,
+    ) -> Self {
+        sizes.iter().fold(Self::default(), |c, s| Self {
+            paid: c.paid + s.reward,
+            current: c.current
+                + network_params.transaction_reward(s.transaction_size(), price_terms),
+        })
+    }
+
+    /// Percentage change from the paid rewards to current prices, `None` if nothing was paid.
+    pub fn change(&self) -> Option<f64> {
+        (self.paid > 0).then(|| (self.current as f64 / self.paid as f64 - 1.0) * 100.0)
     }
-    Ok(())
 }
 
-/// Re-uploads files from status and max confirmations criteria.
-pub async fn command_reupload<IP>(
+/// Gets cost of uploading a list of files.
+pub async fn command_get_cost<IP>(
     arweave: &Arweave,
-    log_dir: PathBuf,
     paths_iter: IP,
-    tags: Option<Vec<Tag<Base64>>>,
     reward_mult: f32,
-    statuses: Option<Vec<StatusCode>>,
-    max_confirms: Option<u64>,
+    with_sol: bool,
+    bundle_size: u64,
+    no_bundle: bool,
     output_format: &OutputFormat,
-    buffer: usize,
-    sol_keypair_path: Option<PathBuf>,
 ) -> CommandResult
 where
     IP: Iterator<Item = PathBuf> + Send + Sync,
 {
-    let paths_vec: Vec<PathBuf> = paths_iter.collect();
-    let all_statuses = arweave
-        .read_statuses(paths_vec.clone().into_iter(), log_dir.clone())
-        .await?;
-    let all_statuses_copy = all_statuses.clone();
+    let price_terms = arweave.get_price_terms(reward_mult).await?;
+    let (units, usd_per_unit) = price_units(arweave, with_sol).await?;
 
-    let missing_paths_iter = paths_vec
-        .clone()
-        .into_iter()
-        .filter(|p| !all_statuses.iter().any(|s| s.file_path.as_ref() == Some(p)));
+    let estimates = cost_estimates(
+        arweave,
+        paths_iter,
+        price_terms,
+        usd_per_unit,
+        with_sol,
+        bundle_size,
+        no_bundle,
+    )?;
 
-    let filtered_paths_iter = arweave
-        .filter_statuses(all_statuses_copy, statuses, max_confirms)?
-        .into_iter()
-        .filter_map(|f| f.file_path);
+    match output_format {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&cost_estimates_json(&estimates, with_sol))?
+        ),
+        OutputFormat::JsonCompact | OutputFormat::JsonLines => {
+            println!("{}", cost_estimates_json(&estimates, with_sol))
+        }
+        _ => {
+            let total = CostEstimate::total(&estimates);
+            println!(
+                "The price to upload {} files with {} total bytes in {} transaction(s) is {} {} (${:.4}).",
+                estimates.iter().map(|e| e.paths.len()).sum::<usize>(),
+                total.data_bytes,
+                estimates.len(),
+                total.cost,
+                units,
+                total.usd
+            );
+            println!("{}", total.breakdown());
+        }
+    }
 
-    let paths_iter = missing_paths_iter.chain(filtered_paths_iter);
+    Ok(())
+}
 
-    if let Some(sol_keypair_path) = sol_keypair_path {
-        command_upload_with_sol(
-            arweave,
-            paths_iter,
-            Some(log_dir),
-            tags,
-            reward_mult,
-            output_format,
-            buffer,
-            sol_keypair_path,
-        )
-        .await
-    } else {
-        command_upload(
-            arweave,
-            paths_iter,
-            Some(log_dir),
-            tags,
-            reward_mult,
-            output_format,
-            buffer,
-        )
-        .await
+/// Gets cost of uploading the data recorded in the statuses in `log_dir` again, comparing it with
+/// the rewards paid when it was uploaded.
+pub async fn command_estimate_from_log_dir(
+    arweave: &Arweave,
+    log_dir: &str,
+    reward_mult: f32,
+    with_sol: bool,
+    output_format: &OutputFormat,
+) -> CommandResult {
+    let sizes = arweave.sizes_from_log_dir(log_dir).await?;
+    if sizes.is_empty() {
+        println!("No statuses with recorded sizes found in {}.", log_dir);
+        return Ok(());
+    }
+    let price_terms = arweave.get_price_terms(reward_mult).await?;
+    let (units, usd_per_unit) = price_units(arweave, with_sol).await?;
+
+    let estimates: Vec<CostEstimate> = sizes
+        .iter()
+        .map(|s| CostEstimate {
+            data_bytes: s.data_size,
+            ..CostEstimate::new(
+                s.file_paths.clone(),
+                s.transaction_size(),
+                price_terms,
+                usd_per_unit,
+                with_sol,
+                &arweave.network_params,
+            )
+        })
+        .collect();
+    let comparison = RewardComparison::new(&sizes, price_terms, &arweave.network_params);
+
+    let mut json = cost_estimates_json(&estimates, with_sol);
+    json["rewards"] = json!(comparison);
+    match output_format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&json)?),
+        OutputFormat::JsonCompact | OutputFormat::JsonLines => println!("{}", json),
+        _ => {
+            let total = CostEstimate::total(&estimates);
+            println!(
+                "The price to upload {} files with {} total bytes in {} transaction(s) again is {} {} (${:.4}).",
+                estimates.iter().map(|e| e.paths.len()).sum::<usize>(),
+                total.data_bytes,
+                estimates.len(),
+                total.cost,
+                units,
+                total.usd
+            );
+            println!("{}", total.breakdown());
+            println!(
+                "Rewards of {} winstons were paid, compared to {} winstons at current prices{}.",
+                comparison.paid,
+                comparison.current,
+                comparison
+                    .change()
+                    .map(|c| format!(" ({:+.1}%)", c))
+                    .unwrap_or_default()
+            );
+        }
     }
+
+    Ok(())
 }
 
-/// Re-uploads files from status and max confirmations criteria.
-///
-/// Includes any file paths not present in bundle statuses. Collects file paths from bundle
-/// statuses to be re-uploaded based on filter criteria, removes existing bundle statuses files,
-/// creates and uploads new bundle transactions, writes new bundles statuses.
-pub async fn command_reupload_bundles<IP>(
+/// Chunks the files in `paths_iter` into bundles of up to `bundle_size` bytes and writes the
+/// resulting [`UploadPlan`] to `out`, or prints it if not provided, to be uploaded later with
+/// `arloader upload --plan`.
+pub async fn command_plan<IP>(
     arweave: &Arweave,
     paths_iter: IP,
-    log_dir: PathBuf,
-    tags: Option<Vec<Tag<String>>>,
     bundle_size: u64,
     reward_mult: f32,
-    statuses: Option<Vec<StatusCode>>,
-    max_confirms: Option<u64>,
-    output_format: OutputFormat,
-    buffer: usize,
-    sol_keypair_path: Option<PathBuf>,
+    out: Option<&Path>,
 ) -> CommandResult
 where
-    IP: Iterator<Item = PathBuf> + Send + Sync,
+    IP: Iterator<Item = PathBuf> + Send,
 {
-    let all_statuses = arweave
-        .read_bundle_statuses(&log_dir.display().to_string())
-        .await?;
+    let price_terms = arweave.get_price_terms(reward_mult).await?;
+    let path_chunks = arweave.chunk_file_paths(paths_iter, bundle_size)?;
+    let plan = UploadPlan::new(
+        &path_chunks,
+        bundle_size,
+        price_terms,
+        &arweave.network_params,
+    )
+    .await?;
 
-    let all_paths_map =
-        all_statuses
-            .clone()
-            .into_iter()
-            .fold(serde_json::Map::new(), |mut m, mut s| {
-                m.append(s.file_paths.as_object_mut().unwrap());
-                m
-            });
+    match out {
+        Some(out) => {
+            fs::write(out, plan.to_string()).await?;
+            println!(
+                "Planned {} files with {} KB of data in {} bundle transactions for an estimated {} winstons. Wrote plan to {}.",
+                plan.num_files(),
+                plan.data_size() / BYTES_PER_KB,
+                plan.bundles.len(),
+                plan.reward(),
+                out.display()
+            );
+        }
+        None => print!("{}", plan),
+    }
+    Ok(())
+}
 
-    let missing_paths_iter =
-        paths_iter.filter(|p| !all_paths_map.contains_key(&p.display().to_string()));
+/// Adds the files in `paths_iter` to the queue in `queue_dir`, creating it if it doesn't exist.
+/// Paths are canonicalized, so that the queue can be run from any directory and files added
+/// by different paths are only queued once.
+pub async fn command_queue_add<IP>(queue_dir: &Path, paths_iter: IP) -> CommandResult
+where
+    IP: Iterator<Item = PathBuf>,
+{
+    let mut queue = UploadQueue::open(queue_dir).await?;
+    let paths = paths_iter
+        .map(|p| p.canonicalize())
+        .collect::<Result<Vec<PathBuf>, _>>()?;
+    let added = queue.add(paths.into_iter()).await?;
+    println!(
+        "Added {} files to the queue in {}, {} files are pending. Run `arloader queue run --queue-dir {}` to upload them.",
+        added,
+        queue_dir.display(),
+        queue.counts().pending,
+        queue_dir.display()
+    );
+    Ok(())
+}
 
-    let filtered_statuses = arweave.filter_statuses(all_statuses, statuses, max_confirms)?;
-    let mut bundle_status_paths = Vec::new();
+/// Makes the failed files of the queue in `queue_dir` pending again.
+pub async fn command_queue_retry_failed(queue_dir: &Path) -> CommandResult {
+    let mut queue = UploadQueue::open(queue_dir).await?;
+    let retried = queue.retry_failed().await?;
+    queue.compact().await?;
+    println!(
+        "Queued {} failed files to upload again in {}.",
+        retried,
+        queue_dir.display()
+    );
+    Ok(())
+}
 
-    let filtered_paths_map =
-        filtered_statuses
-            .clone()
-            .into_iter()
-            .fold(serde_json::Map::new(), |mut m, mut s| {
-                bundle_status_paths.push(log_dir.join(s.id.to_string()).with_extension("json"));
-                m.append(s.file_paths.as_object_mut().unwrap());
-                m
-            });
+/// Uploads the pending files of the queue in `queue_dir` in bundles of up to `bundle_size`
+/// bytes, writing bundle statuses to its [`QUEUE_STATUS_DIR`] sub directory. Bundles are posted
+/// one at a time and recorded in the queue journal with their id before they're posted and as
+/// soon as they post or fail. A run that was interrupted while posting a bundle first checks
+/// whether it was posted, see [`resume_queue_posting`], so that its files aren't uploaded twice.
+/// Once `max_runtime` seconds have passed, no more bundles are started, and the files that are
+/// left stay pending in the queue for the next run. Returns the number of bundles posted.
+pub async fn command_queue_run(
+    arweave: &Arweave,
+    queue_dir: &Path,
+    tags: Option<Vec<Tag<String>>>,
+    bundle_size: u64,
+    reward_mult: f32,
+    output_format: &OutputFormat,
+    buffer: usize,
+    retry_policy: RetryPolicy,
+    max_runtime: Option<u64>,
+) -> Result<usize, Error> {
+    let mut queue = UploadQueue::open(queue_dir).await?;
+    let log_dir = queue_dir.join(QUEUE_STATUS_DIR);
+    ensure_log_dir(&log_dir).await?;
+    let resumed = resume_queue_posting(arweave, &mut queue, &log_dir).await?;
+    if resumed > 0 {
+        print_note(
+            output_format,
+            &format!("Found {} bundles posted by an interrupted run.", resumed),
+        );
+    }
 
-    let filtered_paths_iter = filtered_paths_map.iter().map(|(k, _)| PathBuf::from(k));
+    let (paths, missing): (Vec<PathBuf>, Vec<PathBuf>) =
+        queue.pending().into_iter().partition(|p| p.is_file());
+    if !missing.is_empty() {
+        print_note(
+            output_format,
+            &format!("{} queued files not found.", missing.len()),
+        );
+        queue.mark_failed(&missing, "file not found").await?;
+    }
+    if paths.is_empty() {
+        print_note(
+            output_format,
+            &format!("No pending files in the queue in {}.", queue_dir.display()),
+        );
+        return Ok(0);
+    }
 
-    let paths_iter = missing_paths_iter.chain(filtered_paths_iter);
-    let path_chunks = arweave.chunk_file_paths(paths_iter, bundle_size)?;
+    let tags = tags.unwrap_or_default();
+    let path_chunks = arweave.chunk_file_paths(paths.into_iter(), bundle_size)?;
+    let price_terms = arweave.get_price_terms(reward_mult).await?;
+    arweave
+        .check_bundles_wallet_balance(&path_chunks, price_terms)
+        .await?;
 
-    try_join_all(bundle_status_paths.iter().map(fs::remove_file)).await?;
+    print_note(
+        output_format,
+        &format!(
+            "Uploading {} pending files in {} bundle transactions from the queue in {}...\n",
+            path_chunks.iter().map(|c| c.0.len()).sum::<usize>(),
+            path_chunks.len(),
+            queue_dir.display()
+        ),
+    );
 
-    if let Some(sol_keypair_path) = sol_keypair_path {
-        command_upload_bundles_with_sol(
-            &arweave,
-            path_chunks,
-            Some(log_dir),
-            tags,
-            reward_mult,
-            &output_format,
-            buffer,
-            sol_keypair_path,
-        )
-        .await
-    } else {
-        command_upload_bundles(
-            &arweave,
-            path_chunks,
-            Some(log_dir),
-            tags,
-            reward_mult,
-            &output_format,
-            buffer,
-        )
-        .await
+    let token = CancelToken::default();
+    let deadline = arm_deadline(&token, max_runtime);
+    let mut counter = 0;
+    let mut unfinished = Vec::new();
+    let mut printer = RecordPrinter::new::<BundleStatus>(output_format);
+    let mut summary = SummaryAccumulator::default();
+    let mut path_chunks = path_chunks.into_iter();
+    while let Some(chunk) = path_chunks.next() {
+        if token.is_cancelled() {
+            unfinished.extend(chunk.0);
+            break;
+        }
+        let file_paths = chunk.0.clone();
+        let mut posting_path = None;
+        let posting = async {
+            let data_items = arweave
+                .create_data_items_from_file_paths(chunk.0, tags.clone())
+                .await?;
+            let (signed_transaction, status) = arweave
+                .sign_bundle_transaction_from_data_items(data_items, chunk.1, price_terms)
+                .await?;
+
+            // The status is written before the bundle is journaled as posting, with no chunks
+            // posted if it's posted in chunks, so that a resumed run can tell if it was posted.
+            let status_path = log_dir.join(status.id.to_string()).with_extension("json");
+            let posting_status = BundleStatus {
+                upload_progress: arweave
+                    .network_params
+                    .posts_chunks(status.data_size)
+                    .then(|| UploadProgress {
+                        chunks_posted: 0,
+                        chunks_total: signed_transaction.chunks.len(),
+                    }),
+                ..status.clone()
+            };
+            fs::write(
+                &status_path,
+                to_status_json(&posting_status, arweave.pretty_status)?,
+            )
+            .await?;
+            posting_path = Some(status_path);
+            queue.mark_posting(&file_paths, &status.id).await?;
+
+            arweave
+                .post_signed_bundle_transaction(
+                    signed_transaction,
+                    status,
+                    Some(log_dir.clone()),
+                    buffer * CHUNKS_BUFFER_FACTOR,
+                    retry_policy,
+                )
+                .await
+        };
+        let posted = deadline.before(posting).await;
+        match posted {
+            // The bundle stays journaled as posting, so the next run checks whether it posted.
+            None => {
+                unfinished.extend(file_paths);
+                break;
+            }
+            Some(Ok(status)) => {
+                queue.mark_done(&file_paths, &status.id).await?;
+                summary.add(&status);
+                print!("{}", printer.record_string(&status));
+                let status_path = log_dir.join(status.id.to_string()).with_extension("json");
+                if let Some(path) = posting_path.filter(|p| *p != status_path) {
+                    fs::remove_file(path).await.ok();
+                }
+                fs::write(status_path, to_status_json(&status, arweave.pretty_status)?).await?;
+                counter += 1;
+            }
+            Some(Err(e)) => {
+                print_note(
+                    output_format,
+                    &format!(
+                        "Bundle of {} files failed to upload: {}",
+                        file_paths.len(),
+                        e
+                    ),
+                );
+                if let Some(posting_path) = posting_path {
+                    fs::remove_file(posting_path).await.ok();
+                }
+                queue.mark_failed(&file_paths, &e.to_string()).await?;
+            }
+        }
     }
+    unfinished.extend(path_chunks.flat_map(|c| c.0));
+
+    if counter > 0 {
+        print!(
+            "{}",
+            printer.footer_string(summary.footer_string(output_format))
+        );
+    }
+    print!("{}", printer.finish_string());
+    queue.compact().await?;
+    let counts = queue.counts();
+    print_note(output_format, &format!("\nUploaded {} files in {} bundle transactions, {} files failed. Run `arloader queue retry-failed --queue-dir {}` to queue failed files again and `arloader update-status {}` to update statuses.",
+        summary.number_of_files,
+        counter,
+        counts.failed,
+        queue_dir.display(),
+        log_dir.display()));
+    check_max_runtime(&token, &deadline, unfinished, output_format)?;
+    Ok(counter)
 }
 
-/// Uploads folder of nft assets and metadata, updating metadata with links to uploaded assets.
-pub async fn command_upload_nfts<IP>(
+/// Resolves the bundles that the queue journal records as posting, left by a run that was
+/// interrupted while posting them. The files of a bundle the gateway knows about are marked
+/// done, unless the status in `log_dir` shows that some of its chunks weren't posted, and the
+/// files of any other bundle are made pending to be uploaded again. Returns the number of
+/// bundles that were posted.
+async fn resume_queue_posting(
+    arweave: &Arweave,
+    queue: &mut UploadQueue,
+    log_dir: &Path,
+) -> Result<usize, Error> {
+    let mut resumed = 0;
+    for (bundle_id, paths) in queue.posting() {
+        let id = Base64::from_str(&bundle_id)?;
+        let status_path = log_dir.join(&bundle_id).with_extension("json");
+        let status = arweave.read_bundle_status(status_path.clone()).await.ok();
+        let chunks_left = status.as_ref().map_or(false, |s| s.is_posting());
+        let posted = !chunks_left && arweave.get_status(&id).await?.status != StatusCode::NotFound;
+
+        if !posted {
+            fs::remove_file(&status_path).await.ok();
+            queue.mark_pending(&paths).await?;
+            continue;
+        }
+        if let Some(mut status) = status {
+            status.upload_progress = None;
+            fs::write(
+                &status_path,
+                to_status_json(&status, arweave.pretty_status)?,
+            )
+            .await?;
+        }
+        queue.mark_done(&paths, &id).await?;
+        resumed += 1;
+    }
+    Ok(resumed)
+}
+
+/// Prints the number of pending, done and failed files in the queue in `queue_dir`, along with
+/// the errors of failed files.
+pub async fn command_queue_status(queue_dir: &Path, output_format: &OutputFormat) -> CommandResult {
+    let queue = UploadQueue::open(queue_dir).await?;
+    match output_format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&queue.to_json())?),
+        OutputFormat::JsonCompact | OutputFormat::JsonLines => println!("{}", queue.to_json()),
+        _ => print!("{}", queue),
+    }
+    Ok(())
+}
+
+/// Posts the transactions paid for with SOL that were saved to `log_dir` because they failed to
+/// post, without paying for them again.
+pub async fn command_repost(arweave: &Arweave, log_dir: &Path, buffer: usize) -> CommandResult {
+    let paths = arweave.paid_transaction_paths(log_dir)?;
+    if paths.is_empty() {
+        println!("No unposted transactions found in {}.", log_dir.display());
+        return Ok(());
+    }
+
+    let mut failed = 0;
+    for path in paths {
+        match arweave
+            .repost_paid_transaction(&path, log_dir, buffer * CHUNKS_BUFFER_FACTOR)
+            .await
+        {
+            Ok((id, _)) => println!("Posted {}.", id),
+            Err(e) => {
+                println!("Failed to post {}: {}", path.display(), e);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(Error::RepostFailed(failed));
+    }
+    println!(
+        "Run `arloader update-status {}` to update statuses.",
+        log_dir.display()
+    );
+    Ok(())
+}
+
+/// Displays pending transaction count every second for one minute.
+pub async fn command_get_pending_count(arweave: &Arweave) -> CommandResult {
+    println!(" {}\n{:-<84}", "pending tx", "");
+
+    let mut counter = 0;
+    while counter < 60 {
+        sleep(Duration::from_secs(1)).await;
+        let count = arweave.get_pending_count().await?;
+        println!(
+            "{:>5} {} {}",
+            count,
+            124u8 as char,
+            std::iter::repeat('\u{25A5}')
+                .take(count / 50 + 1)
+                .collect::<String>()
+        );
+        counter += 1;
+    }
+    Ok(())
+}
+
+/// Prints current network height, block hash and counts of blocks and peers.
+pub async fn command_network_info(
+    arweave: &Arweave,
+    output_format: &OutputFormat,
+) -> CommandResult {
+    let info = arweave.get_network_info().await?;
+    match output_format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&info)?),
+        OutputFormat::JsonCompact | OutputFormat::JsonLines => {
+            println!("{}", serde_json::to_string(&info)?)
+        }
+        _ => {
+            println!("{:<15} {}", "height:", info.height);
+            println!("{:<15} {}", "current:", info.current);
+            println!("{:<15} {}", "blocks:", info.blocks);
+            println!("{:<15} {}", "peers:", info.peers);
+        }
+    }
+    Ok(())
+}
+
+/// Gets status from the network for the provided transaction id.
+pub async fn command_get_status(
+    arweave: &Arweave,
+    id: &str,
+    output_format: &OutputFormat,
+) -> CommandResult {
+    let id = Base64::from_str(id)?;
+    let status = arweave.get_status(&id).await?;
+    let header = Status::single_header_string(output_format);
+    if !header.is_empty() {
+        println!("{}", header);
+    }
+    print!("{}", status.single_formatted_string(output_format));
+    Ok(())
+}
+
+/// Retrieves transaction from the network.
+pub async fn command_get_transaction(arweave: &Arweave, id: &str) -> CommandResult {
+    let id = Base64::from_str(id)?;
+    let transaction = arweave.get_transaction(&id).await?;
+    println!("Fetched transaction {}", transaction.id);
+    println!("Owner: {}", address_from_modulus(&transaction.owner));
+    Ok(())
+}
+
+/// Filters statuses, then sorts them if `sort_by` is provided and keeps the first `limit`.
+fn filter_sort_limit<S: Sortable>(
+    arweave: &Arweave,
+    all_statuses: Vec<S>,
+    statuses: Option<Vec<StatusCode>>,
+    max_confirms: Option<u64>,
+    sort_by: Option<SortKey>,
+    desc: bool,
+    limit: Option<usize>,
+) -> Result<Vec<S>, Error> {
+    let mut filtered = arweave.filter_statuses(all_statuses, statuses, max_confirms)?;
+    if let Some(sort_by) = sort_by {
+        sort_statuses(&mut filtered, sort_by, desc)?;
+    }
+    if let Some(limit) = limit {
+        filtered.truncate(limit);
+    }
+    Ok(filtered)
+}
+
+/// Lists transaction statuses, filtered by statuses and max confirmations if provided, and
+/// sorted by `sort_by`, up to `limit` of them.
+pub async fn command_list_statuses<IP>(
     arweave: &Arweave,
     paths_iter: IP,
-    log_dir: Option<PathBuf>,
-    bundle_size: u64,
-    reward_mult: f32,
+    log_dir: &str,
+    statuses: Option<Vec<StatusCode>>,
+    max_confirms: Option<u64>,
+    sort_by: Option<SortKey>,
+    desc: bool,
+    limit: Option<usize>,
     output_format: &OutputFormat,
-    buffer: usize,
-    sol_keypair_path: Option<PathBuf>,
-    link_file: bool,
 ) -> CommandResult
 where
     IP: Iterator<Item = PathBuf> + Send + Sync,
 {
-    let paths_vec: Vec<PathBuf> = paths_iter.collect();
-    let path_chunks = arweave.chunk_file_paths(paths_vec.clone().into_iter(), bundle_size)?;
-    let metadata_paths_iter = paths_vec
-        .clone()
-        .into_iter()
-        .map(|p| p.with_extension("json"));
-    let metadata_path_chunks = arweave.chunk_file_paths(metadata_paths_iter, bundle_size)?;
+    let log_dir_str = log_dir;
+    let log_dir = PathBuf::from(log_dir_str);
 
-    let log_dir = if let Some(log_dir) = log_dir {
-        log_dir
+    let all_statuses = arweave.read_statuses(paths_iter, log_dir).await;
+    if let Ok(all_statuses) = all_statuses {
+        let mut counter = 0;
+        let mut printer = RecordPrinter::new::<Status>(output_format);
+        for status in filter_sort_limit(
+            arweave,
+            all_statuses,
+            statuses,
+            max_confirms,
+            sort_by,
+            desc,
+            limit,
+        )?
+        .iter()
+        {
+            print!("{}", printer.record_string(status));
+            counter += 1;
+        }
+        print!("{}", printer.finish_string());
+        if counter == 0 {
+            print_note(output_format, "Didn't find any matching statuses.");
+        } else {
+            print_note(
+                output_format,
+                &format!("Found {} files matching filter criteria.", counter),
+            );
+        }
     } else {
-        let parent_dir = path_chunks[0].0[0].parent().unwrap();
-        arweave.create_log_dir(parent_dir).await?
-    };
+        print_note(
+            output_format,
+            &format!(
+                "Didn't find statuses for one or more file paths in {}.",
+                log_dir_str
+            ),
+        );
+    }
+    Ok(())
+}
+
+/// Lists bundle statuses, filtered by statuses and max confirmations if provided, and sorted by
+/// `sort_by`, up to `limit` of them.
+pub async fn command_list_bundle_statuses(
+    arweave: &Arweave,
+    log_dir: &str,
+    statuses: Option<Vec<StatusCode>>,
+    max_confirms: Option<u64>,
+    sort_by: Option<SortKey>,
+    desc: bool,
+    limit: Option<usize>,
+    output_format: &OutputFormat,
+) -> CommandResult {
+    let mut counter = 0;
+    let mut printer = RecordPrinter::new::<BundleStatus>(output_format);
+    let mut summary = SummaryAccumulator::default();
+    let all_statuses = arweave.read_bundle_statuses(log_dir).await?;
+
+    for status in filter_sort_limit(
+        arweave,
+        all_statuses,
+        statuses,
+        max_confirms,
+        sort_by,
+        desc,
+        limit,
+    )?
+    .iter()
+    {
+        print!("{}", printer.record_string(status));
+        summary.add(status);
+        counter += 1;
+    }
+    if counter > 0 {
+        print!(
+            "{}",
+            printer.footer_string(summary.footer_string(output_format))
+        );
+    }
+    print!("{}", printer.finish_string());
+    if counter == 0 {
+        print_note(output_format, "Didn't find any matching statuses.");
+    } else {
+        print_note(
+            output_format,
+            &format!("Found {} files matching filter criteria.", counter),
+        );
+    }
+    Ok(())
+}
+
+/// Prints the files in `paths_iter` that don't appear in any status in `log_dir`.
+pub async fn command_missing<IP>(
+    arweave: &Arweave,
+    paths_iter: IP,
+    log_dir: PathBuf,
+    output_format: &OutputFormat,
+) -> CommandResult
+where
+    IP: Iterator<Item = PathBuf> + Send + Sync,
+{
+    let missing = arweave.paths_not_in_log_dir(paths_iter, log_dir).await?;
+    match output_format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&missing)?),
+        OutputFormat::JsonCompact | OutputFormat::JsonLines => {
+            println!("{}", serde_json::to_string(&missing)?)
+        }
+        _ => {
+            for path in &missing {
+                println!("{}", path.display());
+            }
+            println!("\nFound {} files without statuses.", missing.len());
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the files referenced by the statuses in `log_dir` still exist locally with the
+/// sizes and hashes recorded when they were uploaded, printing files that don't as they're
+/// checked, followed by counts of each [`LocalFileState`].
+pub async fn command_check_local(
+    arweave: &Arweave,
+    log_dir: &str,
+    output_format: &OutputFormat,
+) -> CommandResult {
+    let files = arweave.read_recorded_files(log_dir).await?;
+    if files.is_empty() {
+        println!("Didn't find any statuses with file paths in {}.", log_dir);
+        return Ok(());
+    }
+
+    let mut results: BTreeMap<LocalFileState, Vec<PathBuf>> = BTreeMap::new();
+    let mut stream = check_local_files_stream(files);
+    while let Some((file, state)) = stream.next().await {
+        match (output_format, state) {
+            (OutputFormat::DisplayVerbose, _) => {
+                println!(" {:<10}  {}", state, file.file_path.display())
+            }
+            (OutputFormat::Display, LocalFileState::Modified | LocalFileState::Missing) => {
+                println!(" {:<10}  {}", state, file.file_path.display())
+            }
+            _ => {}
+        }
+        results.entry(state).or_default().push(file.file_path);
+    }
+    for paths in results.values_mut() {
+        paths.sort();
+    }
+
+    let count = |state| results.get(&state).map_or(0, Vec::len);
+    let paths = |state| results.get(&state).cloned().unwrap_or_default();
+    let json = json!({
+        "ok": count(LocalFileState::Ok),
+        "modified": paths(LocalFileState::Modified),
+        "missing": paths(LocalFileState::Missing),
+        "unverified": paths(LocalFileState::Unverified),
+    });
+    match output_format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&json)?),
+        OutputFormat::JsonCompact | OutputFormat::JsonLines => println!("{}", json),
+        _ => {
+            println!(
+                "\nChecked {} files: {} ok, {} modified, {} missing, {} unverified.",
+                results.values().map(Vec::len).sum::<usize>(),
+                count(LocalFileState::Ok),
+                count(LocalFileState::Modified),
+                count(LocalFileState::Missing),
+                count(LocalFileState::Unverified),
+            );
+            if count(LocalFileState::Unverified) > 0 {
+                println!("Unverified files were uploaded before hashes were recorded in statuses.");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Verifies the data items in the bundle for the status at `path`, printing OK or FAIL for each
+/// of them followed by a summary. Returns an error if any failed.
+pub async fn command_verify_bundle_status(
+    arweave: &Arweave,
+    path: PathBuf,
+    output_format: &OutputFormat,
+) -> CommandResult {
+    let data = fs::read_to_string(&path).await?;
+    let status: BundleStatus = parse_status(&data, &path)?;
+    let checks = arweave.verify_bundle_status(&status).await?;
+    let failed = checks.iter().filter(|c| !c.is_ok()).count();
+
+    match output_format {
+        OutputFormat::Json | OutputFormat::JsonCompact | OutputFormat::JsonLines => {
+            let json = json!({
+                "id": status.id.to_string(),
+                "ok": checks.len() - failed,
+                "failed": failed,
+                "items": checks
+                    .iter()
+                    .map(|c| json!({
+                        "id": c.id.to_string(),
+                        "file_path": c.file_path,
+                        "ok": c.is_ok(),
+                        "failures": c.failures(),
+                    }))
+                    .collect::<Vec<_>>(),
+            });
+            if matches!(output_format, OutputFormat::Json) {
+                println!("{}", serde_json::to_string_pretty(&json)?);
+            } else {
+                println!("{}", json);
+            }
+        }
+        _ => {
+            println!(" {:<43}  {:<4}  {}", "id", "", "file path");
+            println!("{:-<1$}", "", 80);
+            for check in &checks {
+                let file_path = check
+                    .file_path
+                    .as_ref()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_default();
+                if check.is_ok() {
+                    println!(" {:<43}  {:<4}  {}", check.id.to_string(), "OK", file_path);
+                } else {
+                    println!(
+                        " {:<43}  {:<4}  {} ({})",
+                        check.id.to_string(),
+                        "FAIL",
+                        file_path,
+                        check.failures().join(", ")
+                    );
+                }
+            }
+            println!(
+                "\nVerified {} items in bundle {}: {} ok, {} failed.",
+                checks.len(),
+                status.id,
+                checks.len() - failed,
+                failed
+            );
+        }
+    }
+
+    if failed > 0 {
+        return Err(Error::BundleVerificationFailed(failed));
+    }
+    Ok(())
+}
+
+/// Downloads the data uploaded for each of `paths_iter` with the ids recorded in `log_dir` and
+/// compares it with the local file, printing PASS or FAIL for each followed by a summary. Failed
+/// checks are written to [`VERIFY_FAILURES_FILE`] in `log_dir` and an error is returned.
+pub async fn command_verify_uploads<IP>(
+    arweave: &Arweave,
+    paths_iter: IP,
+    log_dir: &str,
+    buffer: usize,
+    output_format: &OutputFormat,
+) -> CommandResult
+where
+    IP: Iterator<Item = PathBuf>,
+{
+    let mut recorded: HashMap<PathBuf, _> = arweave
+        .read_recorded_files(log_dir)
+        .await?
+        .into_iter()
+        .map(|file| (file.file_path.clone(), file))
+        .collect();
+
+    let checks = paths_iter.map(|file_path| {
+        let file = recorded.remove(&file_path);
+        async move {
+            match file {
+                Some(file) => arweave.verify_upload(&file).await,
+                None => UploadCheck {
+                    file_path,
+                    id: None,
+                    failure: Some("no status found".to_string()),
+                },
+            }
+        }
+    });
+    let checks: Vec<UploadCheck> = stream::iter(checks)
+        .buffered(buffer)
+        .inspect(|check| match output_format {
+            OutputFormat::Json | OutputFormat::JsonCompact | OutputFormat::JsonLines => {}
+            OutputFormat::DisplayQuiet if check.is_ok() => {}
+            _ => println!(
+                " {:<4}  {:<43}  {}{}",
+                if check.is_ok() { "PASS" } else { "FAIL" },
+                check
+                    .id
+                    .as_ref()
+                    .map(|id| id.to_string())
+                    .unwrap_or_default(),
+                check.file_path.display(),
+                check
+                    .failure
+                    .as_ref()
+                    .map(|f| format!(" ({})", f))
+                    .unwrap_or_default()
+            ),
+        })
+        .collect()
+        .await;
+
+    let failures: Vec<&UploadCheck> = checks.iter().filter(|c| !c.is_ok()).collect();
+    let failures_path = PathBuf::from(log_dir).join(VERIFY_FAILURES_FILE);
+    if failures.is_empty() {
+        if failures_path.exists() {
+            fs::remove_file(&failures_path).await?;
+        }
+    } else {
+        fs::write(&failures_path, serde_json::to_string_pretty(&failures)?).await?;
+    }
+
+    match output_format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&checks)?),
+        OutputFormat::JsonCompact | OutputFormat::JsonLines => {
+            println!("{}", serde_json::to_string(&checks)?)
+        }
+        _ => {
+            println!(
+                "\nVerified {} uploads: {} passed, {} failed.",
+                checks.len(),
+                checks.len() - failures.len(),
+                failures.len()
+            );
+            if !failures.is_empty() {
+                println!("Failed uploads written to {}.", failures_path.display());
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        return Err(Error::UploadVerificationFailed(failures.len()));
+    }
+    Ok(())
+}
+
+/// Prints the deep hash of the unsigned transaction, or data item if `as_data_item`, that
+/// uploading `file_path` with `tags` would sign and the tree it's computed over, writing the
+/// unsigned transaction or data item as json to `out` if provided. See
+/// [`Arweave::deep_hash_preview`].
+pub async fn command_debug_deep_hash(
+    arweave: &Arweave,
+    file_path: PathBuf,
+    tags: Vec<Tag<String>>,
+    as_data_item: bool,
+    reward_mult: f32,
+    out: Option<&Path>,
+) -> CommandResult {
+    // Data items don't have a reward.
+    let price_terms = if as_data_item {
+        (0, 0)
+    } else {
+        arweave.get_price_terms(reward_mult).await?
+    };
+    let preview = arweave
+        .deep_hash_preview(file_path, tags, as_data_item, price_terms)
+        .await?;
+
+    println!("deep hash: {}", preview.deep_hash_hex());
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&preview.deep_hash_item.to_json())?
+    );
+    if let Some(out) = out {
+        fs::write(out, serde_json::to_string_pretty(&preview.unsigned)?).await?;
+        println!("Wrote unsigned item to {}.", out.display());
+    }
+    Ok(())
+}
+
+/// Merges bundle statuses in `log_dir` that include the same set of files, keeping the one
+/// furthest along and deleting the others. Only prints what would be merged unless `apply` is true.
+pub async fn command_dedupe_statuses(
+    arweave: &Arweave,
+    log_dir: &str,
+    apply: bool,
+) -> CommandResult {
+    let merged = merge_duplicate_statuses(arweave.read_bundle_statuses(log_dir).await?);
+    if merged.is_empty() {
+        println!("Didn't find any duplicate statuses in {}.", log_dir);
+        return Ok(());
+    }
+
+    let mut removed_count = 0;
+    for MergedStatuses { mut kept, removed } in merged {
+        println!(
+            "Keeping {} ({}) for {} files, removing {}.",
+            kept.id,
+            kept.status,
+            kept.number_of_files,
+            removed
+                .iter()
+                .map(|s| format!("{} ({})", s.id, s.status))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        removed_count += removed.len();
+        if apply {
+            kept.touch(&SystemClock);
+            fs::write(
+                format!("{}{}.json", log_dir, kept.id),
+                to_status_json(&kept, arweave.pretty_status)?,
+            )
+            .await?;
+            for status in removed {
+                fs::remove_file(format!("{}{}.json", log_dir, status.id)).await?;
+            }
+        }
+    }
+
+    if apply {
+        println!("Removed {} duplicate statuses.", removed_count);
+    } else {
+        println!(
+            "Found {} duplicate statuses. Run again with --apply to merge them.",
+            removed_count
+        );
+    }
+    Ok(())
+}
+
+/// Prints a count of transactions by status.
+pub async fn command_status_report<IP>(
+    arweave: &Arweave,
+    paths_iter: IP,
+    log_dir: &str,
+) -> CommandResult
+where
+    IP: Iterator<Item = PathBuf> + Send + Sync,
+{
+    let log_dir = PathBuf::from(log_dir);
+    let summary = arweave.status_summary(paths_iter, log_dir).await?;
+    println!("{}", summary);
+    Ok(())
+}
+
+/// Prints a count of bundles by status with their number of files and size.
+pub async fn command_bundle_status_report(
+    arweave: &Arweave,
+    log_dir: &str,
+    output_format: &OutputFormat,
+) -> CommandResult {
+    let summary = arweave
+        .bundle_status_summary(log_dir, output_format)
+        .await?;
+    println!("{}", summary);
+    Ok(())
+}
+
+/// Updates bundle statuses for provided files in provided directory, skipping bundles already
+/// confirmed with at least `min_confirms` confirmations if provided. Statuses that fail to update
+/// are reported and the rest are still updated, after which the paths of the files uploaded in the
+/// bundles that failed are written to [`FAILED_UPDATES_FILE`] in `log_dir` and an error is
+/// returned.
+///
+/// If `index_hint_url` is provided, posts index hints for bundles that weren't previously
+/// confirmed and now are. Failures to post hints are reported, but don't cause the command to fail.
+pub async fn command_update_bundle_statuses(
+    arweave: &Arweave,
+    log_dir: PathBuf,
+    output_format: &OutputFormat,
+    buffer: usize,
+    index_hint_url: Option<Url>,
+    min_confirms: Option<u64>,
+) -> CommandResult {
+    let mut printer = RecordPrinter::new::<BundleStatus>(output_format);
+    let updated = update_bundle_statuses(
+        arweave,
+        log_dir,
+        output_format,
+        buffer,
+        index_hint_url,
+        min_confirms,
+        &mut printer,
+    )
+    .await;
+    print!("{}", printer.finish_string());
+    updated
+}
+
+/// Same as [`command_update_bundle_statuses`], printing the records with `printer` without
+/// finishing it, so that they can be followed by others.
+async fn update_bundle_statuses(
+    arweave: &Arweave,
+    log_dir: PathBuf,
+    output_format: &OutputFormat,
+    buffer: usize,
+    index_hint_url: Option<Url>,
+    min_confirms: Option<u64>,
+    printer: &mut RecordPrinter,
+) -> CommandResult {
+    let previous_statuses: HashMap<String, StatusCode> = if index_hint_url.is_some() {
+        arweave
+            .read_bundle_statuses(&log_dir.display().to_string())
+            .await?
+            .into_iter()
+            .map(|s| (s.id.to_string(), s.status))
+            .collect()
+    } else {
+        HashMap::new()
+    };
+    let paths_iter = glob(&format!("{}*.json", log_dir.display().to_string()))?
+        .filter_map(Result::ok)
+        .filter(|p| file_stem_is_valid_txid(p));
+
+    let is_confirmed = |s: &BundleStatus| min_confirms.map_or(false, |n| s.is_confirmed_with(n));
+    let mut stream =
+        update_bundle_statuses_stream(arweave, paths_iter, buffer, Some(&is_confirmed));
+    let mut counter = 0;
+    let mut skipped = 0;
+    let mut failed_paths = Vec::new();
+    let mut summary = SummaryAccumulator::default();
+    while let Some(result) = stream.next().await {
+        let status = match result {
+            Ok(StatusUpdate::Updated(status)) => status,
+            Ok(StatusUpdate::Skipped(_)) => {
+                skipped += 1;
+                continue;
+            }
+            Err(e) => {
+                failed_paths.extend(report_failed_update(e, output_format));
+                continue;
+            }
+        };
+        print!("{}", printer.record_string(&status));
+        summary.add(&status);
+        counter += 1;
+        if let Some(url) = &index_hint_url {
+            let previous = previous_statuses.get(&status.id.to_string());
+            send_index_hint(arweave, &status, previous, url).await;
+        }
+    }
+    if counter > 0 {
+        print!(
+            "{}",
+            printer.footer_string(summary.footer_string(output_format))
+        );
+    }
+    if counter == 0 && skipped == 0 && failed_paths.is_empty() {
+        print_note(
+            output_format,
+            &format!(
+                "The <LOG_DIR> you provided, {}, didn't have any statuses in it.",
+                log_dir.display().to_string()
+            ),
+        );
+    } else {
+        print_updated(counter, &failed_paths, output_format);
+    }
+    print_skipped_updates(skipped, min_confirms, output_format);
+
+    let failed_files = bundle_file_paths(&failed_paths).await;
+    write_failed_updates(failed_paths.len(), &failed_files, &log_dir, output_format).await
+}
+
+/// Paths of the files uploaded in the bundles whose status files are at `status_paths`, or the id
+/// of a bundle if its status can't be read.
+async fn bundle_file_paths(status_paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut file_paths = Vec::new();
+    for status_path in status_paths {
+        let status = match fs::read_to_string(status_path).await {
+            Ok(data) => parse_status::<BundleStatus>(&data, status_path).ok(),
+            Err(_) => None,
+        };
+        match status.as_ref().and_then(|s| s.file_paths.as_object()) {
+            Some(paths) => file_paths.extend(paths.keys().map(PathBuf::from)),
+            None => file_paths.push(PathBuf::from(status_path.file_stem().unwrap_or_default())),
+        }
+    }
+    file_paths
+}
+
+/// Prints the error of a status that failed to update with its path, returning the path unless
+/// the status was only skipped for having been written by a newer version.
+fn report_failed_update(failed: FailedUpdate, output_format: &OutputFormat) -> Option<PathBuf> {
+    match failed.error {
+        e @ Error::UnsupportedStatusVersion { .. } => {
+            print_note(output_format, &e.to_string());
+            None
+        }
+        e => {
+            print_note(
+                output_format,
+                &format!("Failed to update {}: {}", failed.file_path.display(), e),
+            );
+            Some(failed.file_path)
+        }
+    }
+}
+
+/// Writes the paths of the files of `failed` statuses that failed to update to
+/// [`FAILED_UPDATES_FILE`] in `log_dir`, returning an error with the number of statuses if there
+/// were any.
+async fn write_failed_updates(
+    failed: usize,
+    file_paths: &[PathBuf],
+    log_dir: &Path,
+    output_format: &OutputFormat,
+) -> CommandResult {
+    if failed == 0 {
+        return Ok(());
+    }
+    let file_path = log_dir.join(FAILED_UPDATES_FILE);
+    let paths_string: String = file_paths
+        .iter()
+        .map(|p| format!("{}\n", p.display()))
+        .collect();
+    fs::write(&file_path, paths_string).await?;
+    print_note(
+        output_format,
+        &format!(
+            "Paths of the files whose statuses failed to update were written to {}.",
+            file_path.display()
+        ),
+    );
+    Err(Error::StatusUpdatesFailed(failed))
+}
+
+/// Prints the number of statuses updated, along with the number that failed if any did.
+fn print_updated(counter: usize, failed_paths: &[PathBuf], output_format: &OutputFormat) {
+    if failed_paths.is_empty() {
+        print_note(output_format, &format!("Updated {} statuses.", counter));
+    } else {
+        print_note(
+            output_format,
+            &format!(
+                "Updated {} statuses, failed {}.",
+                counter,
+                failed_paths.len()
+            ),
+        );
+    }
+}
+
+/// Prints how many statuses weren't updated because they were already confirmed.
+fn print_skipped_updates(skipped: usize, min_confirms: Option<u64>, output_format: &OutputFormat) {
+    if let (true, Some(min_confirms)) = (skipped > 0, min_confirms) {
+        print_note(
+            output_format,
+            &format!(
+                "Skipped {} statuses already confirmed with at least {} confirmations.",
+                skipped, min_confirms
+            ),
+        );
+    }
+}
+
+/// Posts an index hint if `status` has just been confirmed, returning whether the hint was
+/// accepted. Failures are printed instead of returned so that they don't affect the outcome of
+/// the command.
+async fn send_index_hint(
+    arweave: &Arweave,
+    status: &BundleStatus,
+    previous: Option<&StatusCode>,
+    url: &Url,
+) -> bool {
+    if status.status != StatusCode::Confirmed || previous == Some(&StatusCode::Confirmed) {
+        return false;
+    }
+    match arweave.post_bundle_index_hint(status, url).await {
+        Ok(()) => true,
+        Err(e) => {
+            println!("Failed to send index hint for bundle {}: {}", status.id, e);
+            false
+        }
+    }
+}
+
+/// Updates NFT metadata files from a manifest file.
+pub async fn command_update_metadata<IP>(
+    arweave: &Arweave,
+    paths_iter: IP,
+    manifest_path: PathBuf,
+    image_link: ImageLink,
+    files_link: FilesLink,
+    update_image: bool,
+    update_animation_url: bool,
+) -> CommandResult
+where
+    IP: Iterator<Item = PathBuf> + Send + Sync,
+{
+    let paths_vec = paths_iter.collect::<Vec<PathBuf>>();
+    let num_paths: usize = paths_vec.len();
+
+    arweave
+        .update_metadata(
+            paths_vec.into_iter(),
+            manifest_path,
+            image_link,
+            files_link,
+            update_image,
+            update_animation_url,
+        )
+        .await?;
+
+    println!("Successfully updated {} metadata files.", num_paths);
+    Ok(())
+}
+
+/// Updates statuses for uploaded nfts. Stages that didn't run, leaving their sub directory or
+/// manifest missing from `log_dir`, are skipped with a note. If bundle statuses of either stage
+/// fail to update, the rest are still updated and an error with the total number that failed is
+/// returned. In json output formats, stdout only has the records of both stages and manifests.
+pub async fn command_update_nft_statuses(
+    arweave: &Arweave,
+    log_dir: &str,
+    output_format: &OutputFormat,
+    buffer: usize,
+) -> CommandResult {
+    let log_dir = PathBuf::from(log_dir);
+    let stages = [
+        ("asset", log_dir.join("assets/")),
+        ("metadata", log_dir.join("metadata/")),
+    ];
+
+    // With `Json` and `JsonCompact`, the records of both stages and the manifests are elements
+    // of one array. Otherwise each stage is printed as a table of its own.
+    let one_array = matches!(
+        output_format,
+        OutputFormat::Json | OutputFormat::JsonCompact
+    );
+    let mut printer = RecordPrinter::new::<BundleStatus>(output_format);
+    let mut failed = 0;
+    let updated = async {
+        for (stage, stage_log_dir) in &stages {
+            if !stage_log_dir.is_dir() {
+                print_note(
+                    output_format,
+                    &format!(
+                        "\n\nSkipping {} bundle statuses, {} doesn't exist.",
+                        stage,
+                        stage_log_dir.display()
+                    ),
+                );
+                continue;
+            }
+            print_note(
+                output_format,
+                &format!("\n\nUpdating {} bundle statuses...\n", stage),
+            );
+            if !one_array {
+                printer = RecordPrinter::new::<BundleStatus>(output_format);
+            }
+            match update_bundle_statuses(
+                arweave,
+                stage_log_dir.clone(),
+                output_format,
+                buffer,
+                None,
+                None,
+                &mut printer,
+            )
+            .await
+            {
+                Err(Error::StatusUpdatesFailed(count)) => failed += count,
+                result => result?,
+            }
+        }
+        for (stage, stage_log_dir) in &stages {
+            match get_manifest_id_from_log_dir(stage_log_dir) {
+                Some(manifest_txid) => {
+                    print_note(
+                        output_format,
+                        &format!("\n\nUpdating {} manifest status...\n", stage),
+                    );
+                    if output_format.is_json() {
+                        let status = arweave
+                            .get_status(&Base64::from_str(&manifest_txid)?)
+                            .await?;
+                        print!("{}", printer.record_string(&status));
+                    } else {
+                        command_get_status(arweave, &manifest_txid, output_format).await?;
+                    }
+                }
+                None => print_note(
+                    output_format,
+                    &format!(
+                        "\n\nSkipping {} manifest status, no manifest found in {}.",
+                        stage,
+                        stage_log_dir.display()
+                    ),
+                ),
+            }
+        }
+        Ok::<(), Error>(())
+    }
+    .await;
+    print!("{}", printer.finish_string());
+    updated?;
+    if failed > 0 {
+        return Err(Error::StatusUpdatesFailed(failed));
+    }
+    Ok(())
+}
+
+/// Updates statuses for provided files in provided directory, skipping transactions already
+/// confirmed with at least `min_confirms` confirmations if provided. The paths of the files whose
+/// statuses failed to update are written to [`FAILED_UPDATES_FILE`] in `log_dir` and an error is
+/// returned.
+pub async fn command_update_statuses<IP>(
+    arweave: &Arweave,
+    paths_iter: IP,
+    log_dir: PathBuf,
+    output_format: &OutputFormat,
+    buffer: usize,
+    min_confirms: Option<u64>,
+) -> CommandResult
+where
+    IP: Iterator<Item = PathBuf> + Send + Sync,
+{
+    let log_dir = PathBuf::from(log_dir);
+
+    let is_confirmed = |s: &Status| min_confirms.map_or(false, |n| s.is_confirmed_with(n));
+    let mut stream = update_statuses_stream(
+        arweave,
+        paths_iter,
+        log_dir.clone(),
+        buffer,
+        Some(&is_confirmed),
+    );
+    let mut counter = 0;
+    let mut skipped = 0;
+    let mut failed_paths = Vec::new();
+    let mut printer = RecordPrinter::new::<Status>(output_format);
+    while let Some(result) = stream.next().await {
+        let status = match result {
+            Ok(StatusUpdate::Updated(status)) => status,
+            Ok(StatusUpdate::Skipped(_)) => {
+                skipped += 1;
+                continue;
+            }
+            Err(e) => {
+                failed_paths.extend(report_failed_update(e, output_format));
+                continue;
+            }
+        };
+        print!("{}", printer.record_string(&status));
+        counter += 1;
+    }
+    print!("{}", printer.finish_string());
+    if counter == 0 && skipped == 0 && failed_paths.is_empty() {
+        print_note(
+            output_format,
+            "The <GLOB> and <LOG_DIR> combination you provided didn't return any statuses.",
+        );
+    } else {
+        print_updated(counter, &failed_paths, output_format);
+    }
+    print_skipped_updates(skipped, min_confirms, output_format);
+
+    write_failed_updates(failed_paths.len(), &failed_paths, &log_dir, output_format).await
+}
+
+/// Creates `log_dir` if it doesn't exist yet, failing if the path is a file.
+async fn ensure_log_dir(log_dir: &Path) -> CommandResult {
+    if log_dir.is_file() {
+        return Err(Error::LogDirIsFile(log_dir.to_path_buf()));
+    }
+    fs::create_dir_all(log_dir).await?;
+    Ok(())
+}
+
+/// Uploads files to Arweave.
+pub async fn command_upload<IP>(
+    arweave: &Arweave,
+    paths_iter: IP,
+    log_dir: Option<PathBuf>,
+    tags: Option<Vec<Tag<Base64>>>,
+    reward_mult: f32,
+    output_format: &OutputFormat,
+    buffer: usize,
+    max_runtime: Option<u64>,
+) -> Result<Receipt, Error>
+where
+    IP: Iterator<Item = PathBuf> + Send + Sync,
+{
+    let paths_vec: Vec<PathBuf> = paths_iter.collect();
+    if let Some(log_dir) = &log_dir {
+        ensure_log_dir(log_dir).await?;
+    }
+    let token = CancelToken::default();
+    let deadline = arm_deadline(&token, max_runtime);
+    let price_terms = arweave.get_price_terms(reward_mult).await?;
+    if arweave.tip.is_some() {
+        arweave
+            .check_wallet_balance(&paths_vec, price_terms)
+            .await?;
+    }
+
+    let mut stream = upload_files_stream(
+        arweave,
+        token.wrap(paths_vec.clone().into_iter()),
+        tags,
+        log_dir.clone(),
+        None,
+        price_terms,
+        buffer,
+    );
+
+    let mut counter = 0;
+    let mut failed_paths = Vec::new();
+    let mut finished = HashSet::new();
+    let mut printer = RecordPrinter::new::<Status>(output_format);
+    let mut receipt = Receipt::default();
+    let uploaded = async {
+        while let Some(result) = next_before_deadline(&mut stream, &deadline).await {
+            match result {
+                Ok(status) => {
+                    if counter == 0 {
+                        if let Some(log_dir) = &log_dir {
+                            print_note(
+                                output_format,
+                                &format!("Logging statuses to {}", &log_dir.display()),
+                            );
+                        }
+                    }
+                    print!("{}", printer.record_string(&status));
+                    receipt.add_status(&status);
+                    finished.extend(status.file_path);
+                    counter += 1;
+                }
+                Err(e) => {
+                    print_note(output_format, &format!("{:#?}", e.error));
+                    receipt.add_failure(&e);
+                    finished.extend(e.file_paths.iter().cloned());
+                    failed_paths.extend(e.file_paths);
+                }
+            }
+        }
+        print!("{}", printer.finish_string());
+
+        if counter == 0 {
+            print_note(output_format, "<FILE_PATHS> didn't match any files.");
+        } else {
+            print_note(output_format, &format!("Uploaded {} files. Run `arloader update-status {} --file-paths <FILE_PATHS>` to confirm transaction(s).",
+                counter,
+                log_dir.as_deref().unwrap_or(Path::new("")).display()));
+        }
+
+        print_gzip_savings(arweave, output_format);
+        report_failed_paths(&failed_paths, log_dir.as_deref(), output_format).await?;
+        Ok::<(), Error>(())
+    }
+    .await;
+    if let Err(error) = &uploaded {
+        receipt.add_error(error);
+    }
+    if let Some(log_dir) = &log_dir {
+        write_receipt(arweave, &mut receipt, log_dir, output_format).await?;
+    }
+    uploaded?;
+
+    let unfinished = unfinished_paths(&paths_vec, &finished);
+    check_max_runtime(&token, &deadline, unfinished, output_format)?;
+    Ok(receipt)
+}
+
+/// Uploads bundles created from provided glob to Arweave. Returns the receipt of the run, which is
+/// also written to the log dir.
+pub async fn command_upload_bundles(
+    arweave: &Arweave,
+    path_chunks: Vec<PathsChunk>,
+    log_dir: Option<PathBuf>,
+    tags: Option<Vec<Tag<String>>>,
+    reward_mult: f32,
+    output_format: &OutputFormat,
+    buffer: usize,
+    retry_policy: RetryPolicy,
+    skip_balance_check: bool,
+    max_runtime: Option<u64>,
+    bundle_manifest: bool,
+) -> Result<Receipt, Error> {
+    if path_chunks.len() == 0 {
+        print_note(output_format, "<FILE_PATHS> didn't match any files.");
+        return Ok(Receipt::default());
+    } else {
+        let tags = tags.unwrap_or(Vec::new());
+        let price_terms = arweave.get_price_terms(reward_mult).await?;
+        if !skip_balance_check {
+            arweave
+                .check_bundles_wallet_balance(&path_chunks, price_terms)
+                .await?;
+        }
+        let log_dir = if let Some(log_dir) = log_dir {
+            ensure_log_dir(&log_dir).await?;
+            log_dir
+        } else {
+            let parent_dir = path_chunks[0].0[0].parent().unwrap();
+            arweave.create_log_dir(parent_dir).await?
+        };
+
+        let (num_files, data_size) = path_chunks
+            .iter()
+            .fold((0, 0), |(f, d), c| (f + c.0.len(), d + c.1));
+
+        print_note(
+            output_format,
+            &format!(
+                "Uploading {} files with {} KB of data in {} bundle transactions...\n",
+                num_files,
+                data_size / BYTES_PER_KB,
+                path_chunks.len()
+            ),
+        );
+
+        // With --bundle-manifest, the last chunk is held back until the others are uploaded, so
+        // that the manifest of every file can be bundled with it.
+        let mut streamed_chunks = path_chunks.clone();
+        let mut last_chunk = match bundle_manifest {
+            true => streamed_chunks.pop(),
+            false => None,
+        };
+        let token = CancelToken::default();
+        let deadline = arm_deadline(&token, max_runtime);
+        let mut stream = upload_bundles_stream(
+            arweave,
+            token.wrap(streamed_chunks.into_iter()),
+            tags.clone(),
+            Some(log_dir.clone()),
+            price_terms,
+            buffer,
+            retry_policy,
+        );
+
+        let mut counter = 0;
+        let mut failed_paths = Vec::new();
+        let mut finished = HashSet::new();
+        let mut statuses = Vec::new();
+        let mut manifest_id_bundled = None;
+        let mut printer = RecordPrinter::new::<BundleStatus>(output_format);
+        let mut receipt = Receipt::default();
+        let mut summary = SummaryAccumulator::default();
+        let mut metrics = Metrics::start(
+            arweave.post_retries.load(Ordering::Relaxed),
+            &arweave.connection_stats,
+        );
+
+        let uploaded = async {
+            loop {
+                let result = match next_before_deadline(&mut stream, &deadline).await {
+                    Some(result) => result,
+                    None if token.is_cancelled() => break,
+                    None => match last_chunk.take() {
+                        Some(chunk) => {
+                            let file_paths = chunk.0.clone();
+                            let chunks_buffer = buffer * CHUNKS_BUFFER_FACTOR;
+                            let posted = if can_bundle_manifest(&failed_paths, output_format) {
+                                arweave
+                                    .post_manifest_bundle(
+                                        chunk,
+                                        tags.clone(),
+                                        statuses.clone(),
+                                        log_dir.clone(),
+                                        price_terms,
+                                        chunks_buffer,
+                                        retry_policy,
+                                    )
+                                    .await
+                                    .map(|(manifest_id, status)| {
+                                        manifest_id_bundled = Some(manifest_id);
+                                        status
+                                    })
+                            } else {
+                                arweave
+                                    .post_bundle_transaction_from_file_paths(
+                                        chunk,
+                                        tags.clone(),
+                                        Some(log_dir.clone()),
+                                        price_terms,
+                                        chunks_buffer,
+                                        retry_policy,
+                                    )
+                                    .await
+                            };
+                            posted.map_err(|error| FailedUpload { error, file_paths })
+                        }
+                        None => break,
+                    },
+                };
+                match result {
+                    Ok(status) => {
+                        summary.add(&status);
+                        metrics.add(&status);
+                        print!("{}", printer.record_string(&status));
+                        fs::write(
+                            log_dir.join(status.id.to_string()).with_extension("json"),
+                            to_status_json(&status, arweave.pretty_status)?,
+                        )
+                        .await?;
+                        receipt.add_bundle(&status);
+                        finished.extend(status.file_path_set().into_iter().map(PathBuf::from));
+                        statuses.push(status);
+                        counter += 1;
+                    }
+                    Err(e) => {
+                        print_note(
+                            output_format,
+                            &format!(
+                                "Bundle of {} files failed to upload: {}",
+                                e.file_paths.len(),
+                                e.error
+                            ),
+                        );
+                        receipt.add_failure(&e);
+                        finished.extend(e.file_paths.iter().cloned());
+                        failed_paths.extend(e.file_paths);
+                    }
+                }
+            }
+
+            if counter > 0 {
+                print!(
+                    "{}",
+                    printer.footer_string(summary.footer_string(output_format))
+                );
+                metrics.finish(
+                    arweave.post_retries.load(Ordering::Relaxed),
+                    &arweave.connection_stats,
+                );
+                print!(
+                    "{}",
+                    printer.footer_string(metrics.metrics_string(output_format))
+                );
+            }
+            print!("{}", printer.finish_string());
+            print_note(output_format, &format!("\nUploaded {} KB in {} files in {} bundle transactions. Run `arloader update-status {}` to update statuses.",
+                summary.data_size / 1000,
+                summary.number_of_files,
+                counter,
+                log_dir.display().to_string()));
+
+            print_gzip_savings(arweave, output_format);
+            if let (Some(manifest_id), Some(status)) = (&manifest_id_bundled, statuses.last()) {
+                print_manifest_bundle(manifest_id, status, &log_dir, output_format);
+            }
+            report_failed_paths(&failed_paths, Some(log_dir.as_path()), output_format).await?;
+            Ok::<(), Error>(())
+        }
+        .await;
+        if let Err(error) = &uploaded {
+            receipt.add_error(error);
+        }
+        write_receipt(arweave, &mut receipt, &log_dir, output_format).await?;
+        uploaded?;
+
+        let paths_vec: Vec<PathBuf> = path_chunks.iter().flat_map(|c| c.0.clone()).collect();
+        let unfinished = unfinished_paths(&paths_vec, &finished);
+        check_max_runtime(&token, &deadline, unfinished, output_format)?;
+        Ok(receipt)
+    }
+}
+
+/// Whether a manifest can be bundled with the last bundle of an upload, which is only the case if
+/// all of the others were uploaded, so that it isn't missing any files. Prints why not otherwise.
+fn can_bundle_manifest(failed_paths: &[PathBuf], output_format: &OutputFormat) -> bool {
+    let can_bundle = failed_paths.is_empty();
+    if !can_bundle {
+        print_note(output_format, "\nSkipped bundling the manifest since not all files were uploaded. Run `arloader upload-manifest` once they have been.");
+    }
+    can_bundle
+}
+
+/// Prints the id of a manifest bundled with the last bundle of an upload and where it was
+/// written.
+fn print_manifest_bundle(
+    manifest_id: &Base64,
+    status: &BundleStatus,
+    log_dir: &Path,
+    output_format: &OutputFormat,
+) {
+    print_note(
+        output_format,
+        &format!(
+            "\nUploaded manifest {} in bundle {} and wrote to {}.",
+            manifest_id,
+            status.id,
+            log_dir
+                .join(format!("manifest_{}.json", manifest_id))
+                .display()
+        ),
+    );
+}
+
+/// Uploads bundles created from provided glob to Arweave, paying with SOL. Returns the receipt of
+/// the run, which is also written to the log dir.
+pub async fn command_upload_bundles_with_sol(
+    arweave: &Arweave,
+    path_chunks: Vec<PathsChunk>,
+    log_dir: Option<PathBuf>,
+    tags: Option<Vec<Tag<String>>>,
+    reward_mult: f32,
+    output_format: &OutputFormat,
+    buffer: usize,
+    retry_policy: RetryPolicy,
+    skip_balance_check: bool,
+    sol_keypair_path: PathBuf,
+    max_runtime: Option<u64>,
+    bundle_manifest: bool,
+) -> Result<Receipt, Error> {
+    if path_chunks.len() == 0 {
+        print_note(output_format, "<FILE_PATHS> didn't match any files.");
+        return Ok(Receipt::default());
+    } else {
+        let tags = tags.unwrap_or(Vec::new());
+        let price_terms = arweave.get_price_terms(reward_mult).await?;
+        let solana_url = SOLANA_MAIN_URL.parse::<Url>()?;
+        let sol_ar_url = SOL_AR_BASE_URL.parse::<Url>()?.join("sol")?;
+        let from_keypair = load_keypair(sol_keypair_path)?;
+        if !skip_balance_check {
+            arweave
+                .check_bundles_sol_balance(
+                    &path_chunks,
+                    price_terms,
+                    solana_url.clone(),
+                    &from_keypair,
+                )
+                .await?;
+        }
+        let log_dir = if let Some(log_dir) = log_dir {
+            ensure_log_dir(&log_dir).await?;
+            log_dir
+        } else {
+            let parent_dir = &path_chunks[0].0[0].parent().unwrap();
+            arweave.create_log_dir(parent_dir).await?
+        };
+
+        let (num_files, data_size) = path_chunks
+            .iter()
+            .fold((0, 0), |(f, d), c| (f + c.0.len(), d + c.1));
+
+        print_note(
+            output_format,
+            &format!(
+                "Uploading {} files with {} KB of data in {} bundle transactions...\n",
+                num_files,
+                data_size / BYTES_PER_KB,
+                path_chunks.len()
+            ),
+        );
+
+        // With --bundle-manifest, the last chunk is held back until the others are uploaded, so
+        // that the manifest of every file can be bundled with it.
+        let mut streamed_chunks = path_chunks.clone();
+        let mut last_chunk = match bundle_manifest {
+            true => streamed_chunks.pop(),
+            false => None,
+        };
+        let token = CancelToken::default();
+        let deadline = arm_deadline(&token, max_runtime);
+        let mut stream = upload_bundles_stream_with_sol(
+            arweave,
+            token.wrap(streamed_chunks.into_iter()),
+            tags.clone(),
+            Some(log_dir.clone()),
+            price_terms,
+            buffer,
+            retry_policy,
+            solana_url.clone(),
+            sol_ar_url.clone(),
+            &from_keypair,
+        );
+
+        let mut counter = 0;
+        let mut failed_paths = Vec::new();
+        let mut finished = HashSet::new();
+        let mut statuses = Vec::new();
+        let mut manifest_id_bundled = None;
+        let mut printer = RecordPrinter::new::<BundleStatus>(output_format);
+        let mut receipt = Receipt::default();
+        let mut summary = SummaryAccumulator::default();
+        let mut metrics = Metrics::start(
+            arweave.post_retries.load(Ordering::Relaxed),
+            &arweave.connection_stats,
+        );
+        let uploaded = async {
+            loop {
+                let result = match next_before_deadline(&mut stream, &deadline).await {
+                    Some(result) => result,
+                    None if token.is_cancelled() => break,
+                    None => match last_chunk.take() {
+                        Some(chunk) => {
+                            let file_paths = chunk.0.clone();
+                            let chunks_buffer = buffer * CHUNKS_BUFFER_FACTOR;
+                            let posted = if can_bundle_manifest(&failed_paths, output_format) {
+                                arweave
+                                    .post_manifest_bundle_with_sol(
+                                        chunk,
+                                        tags.clone(),
+                                        statuses.clone(),
+                                        log_dir.clone(),
+                                        price_terms,
+                                        chunks_buffer,
+                                        retry_policy,
+                                        solana_url.clone(),
+                                        sol_ar_url.clone(),
+                                        &from_keypair,
+                                    )
+                                    .await
+                                    .map(|(manifest_id, status)| {
+                                        manifest_id_bundled = Some(manifest_id);
+                                        status
+                                    })
+                            } else {
+                                arweave
+                                    .post_bundle_transaction_from_file_paths_with_sol(
+                                        chunk,
+                                        tags.clone(),
+                                        Some(log_dir.clone()),
+                                        price_terms,
+                                        chunks_buffer,
+                                        retry_policy,
+                                        solana_url.clone(),
+                                        sol_ar_url.clone(),
+                                        &from_keypair,
+                                    )
+                                    .await
+                            };
+                            posted.map_err(|error| FailedUpload { error, file_paths })
+                        }
+                        None => break,
+                    },
+                };
+                match result {
+                    Ok(status) => {
+                        summary.add(&status);
+                        metrics.add(&status);
+                        print!("{}", printer.record_string(&status));
+                        fs::write(
+                            log_dir.join(status.id.to_string()).with_extension("json"),
+                            to_status_json(&status, arweave.pretty_status)?,
+                        )
+                        .await?;
+                        receipt.add_bundle(&status);
+                        finished.extend(status.file_path_set().into_iter().map(PathBuf::from));
+                        statuses.push(status);
+                        counter += 1;
+                    }
+                    Err(e) => {
+                        print_note(
+                            output_format,
+                            &format!(
+                                "Bundle of {} files failed to upload: {}",
+                                e.file_paths.len(),
+                                e.error
+                            ),
+                        );
+                        receipt.add_failure(&e);
+                        finished.extend(e.file_paths.iter().cloned());
+                        failed_paths.extend(e.file_paths);
+                    }
+                }
+            }
+
+            if counter > 0 {
+                print!(
+                    "{}",
+                    printer.footer_string(summary.footer_string(output_format))
+                );
+                metrics.finish(
+                    arweave.post_retries.load(Ordering::Relaxed),
+                    &arweave.connection_stats,
+                );
+                print!(
+                    "{}",
+                    printer.footer_string(metrics.metrics_string(output_format))
+                );
+            }
+            print!("{}", printer.finish_string());
+            print_note(output_format, &format!("\nUploaded {} KB in {} files in {} bundle transaction(s). Run `arloader update-status {}` to update statuses.",
+                summary.data_size / 1000,
+                summary.number_of_files,
+                counter,
+                log_dir.display().to_string()));
+
+            print_gzip_savings(arweave, output_format);
+            if let (Some(manifest_id), Some(status)) = (&manifest_id_bundled, statuses.last()) {
+                print_manifest_bundle(manifest_id, status, &log_dir, output_format);
+            }
+            report_failed_paths(&failed_paths, Some(log_dir.as_path()), output_format).await?;
+            Ok::<(), Error>(())
+        }
+        .await;
+        if let Err(error) = &uploaded {
+            receipt.add_error(error);
+        }
+        write_receipt(arweave, &mut receipt, &log_dir, output_format).await?;
+        uploaded?;
+
+        let paths_vec: Vec<PathBuf> = path_chunks.iter().flat_map(|c| c.0.clone()).collect();
+        let unfinished = unfinished_paths(&paths_vec, &finished);
+        check_max_runtime(&token, &deadline, unfinished, output_format)?;
+        Ok(receipt)
+    }
+}
+
+/// Reads the paths of the files that failed verification from a file written by
+/// [`command_verify_uploads`].
+pub async fn read_verify_failures(failures_path: &Path) -> Result<Vec<PathBuf>, Error> {
+    let failures: Vec<UploadCheck> =
+        serde_json::from_str(&fs::read_to_string(failures_path).await?)?;
+    Ok(failures.into_iter().map(|f| f.file_path).collect())
+}
+
+/// Re-uploads files from status and max confirmations criteria, or if `verify_failures` is
+/// provided, the files listed in it by [`command_verify_uploads`] regardless of their statuses.
+pub async fn command_reupload<IP>(
+    arweave: &Arweave,
+    log_dir: PathBuf,
+    paths_iter: IP,
+    tags: Option<Vec<Tag<Base64>>>,
+    reward_mult: f32,
+    statuses: Option<Vec<StatusCode>>,
+    max_confirms: Option<u64>,
+    output_format: &OutputFormat,
+    buffer: usize,
+    sol_keypair_path: Option<PathBuf>,
+    verify_failures: Option<PathBuf>,
+    max_runtime: Option<u64>,
+) -> CommandResult
+where
+    IP: Iterator<Item = PathBuf> + Send + Sync,
+{
+    let paths_vec: Vec<PathBuf> = if let Some(failures_path) = verify_failures {
+        read_verify_failures(&failures_path).await?
+    } else {
+        let paths_vec: Vec<PathBuf> = paths_iter.collect();
+        let all_statuses = arweave
+            .read_statuses(paths_vec.clone().into_iter(), log_dir.clone())
+            .await?;
+        let missing_paths_iter = arweave
+            .paths_not_in_log_dir(paths_vec.into_iter(), log_dir.clone())
+            .await?
+            .into_iter();
+
+        let filtered_paths_iter = arweave
+            .filter_statuses(all_statuses, statuses, max_confirms)?
+            .into_iter()
+            .filter_map(|f| f.file_path);
+
+        missing_paths_iter.chain(filtered_paths_iter).collect()
+    };
+    let paths_iter = paths_vec.into_iter();
+
+    if let Some(sol_keypair_path) = sol_keypair_path {
+        command_upload_with_sol(
+            arweave,
+            paths_iter,
+            Some(log_dir),
+            tags,
+            reward_mult,
+            output_format,
+            buffer,
+            sol_keypair_path,
+            max_runtime,
+        )
+        .await
+        .map(|_| ())
+    } else {
+        command_upload(
+            arweave,
+            paths_iter,
+            Some(log_dir),
+            tags,
+            reward_mult,
+            output_format,
+            buffer,
+            max_runtime,
+        )
+        .await
+        .map(|_| ())
+    }
+}
+
+/// Re-uploads files from status and max confirmations criteria.
+///
+/// Includes any file paths not present in bundle statuses. Collects file paths from bundle
+/// statuses to be re-uploaded based on filter criteria, removes existing bundle statuses files,
+/// creates and uploads new bundle transactions, writes new bundles statuses.
+///
+/// If `verify_failures` is provided, the files listed in it by [`command_verify_uploads`] are
+/// uploaded in new bundles instead, keeping the statuses they were uploaded in since the rest of
+/// their files passed.
+pub async fn command_reupload_bundles<IP>(
+    arweave: &Arweave,
+    paths_iter: IP,
+    log_dir: PathBuf,
+    tags: Option<Vec<Tag<String>>>,
+    bundle_size: u64,
+    reward_mult: f32,
+    statuses: Option<Vec<StatusCode>>,
+    max_confirms: Option<u64>,
+    output_format: OutputFormat,
+    buffer: usize,
+    sol_keypair_path: Option<PathBuf>,
+    purge_superseded: bool,
+    verify_failures: Option<PathBuf>,
+    max_runtime: Option<u64>,
+) -> CommandResult
+where
+    IP: Iterator<Item = PathBuf> + Send + Sync,
+{
+    let (paths_vec, filtered_statuses) = if let Some(failures_path) = verify_failures {
+        (read_verify_failures(&failures_path).await?, Vec::new())
+    } else {
+        let all_statuses = arweave
+            .read_bundle_statuses(&log_dir.display().to_string())
+            .await?;
+
+        let missing_paths_iter = arweave
+            .paths_not_in_log_dir(paths_iter, log_dir.clone())
+            .await?
+            .into_iter();
+
+        let filtered_statuses = arweave.filter_statuses(all_statuses, statuses, max_confirms)?;
+
+        let filtered_paths_map =
+            filtered_statuses
+                .clone()
+                .into_iter()
+                .fold(serde_json::Map::new(), |mut m, mut s| {
+                    m.append(s.file_paths.as_object_mut().unwrap());
+                    m
+                });
+
+        let filtered_paths_iter = filtered_paths_map.iter().map(|(k, _)| PathBuf::from(k));
+
+        (
+            missing_paths_iter.chain(filtered_paths_iter).collect(),
+            filtered_statuses,
+        )
+    };
+    let path_chunks = arweave.chunk_file_paths(paths_vec.into_iter(), bundle_size)?;
+
+    // The statuses being replaced are kept until their replacements post, see
+    // `Arweave::supersede_bundle_statuses`, so that nothing is lost if the upload fails. Only
+    // statuses that weren't there before the upload count as replacements.
+    let earlier_ids: HashSet<Base64> = arweave
+        .read_bundle_statuses(&log_dir.join("").display().to_string())
+        .await?
+        .into_iter()
+        .map(|s| s.id)
+        .collect();
+    let uploaded = if let Some(sol_keypair_path) = sol_keypair_path {
+        command_upload_bundles_with_sol(
+            &arweave,
+            path_chunks,
+            Some(log_dir.clone()),
+            tags,
+            reward_mult,
+            &output_format,
+            buffer,
+            RetryPolicy::default(),
+            false,
+            sol_keypair_path,
+            max_runtime,
+            false,
+        )
+        .await
+    } else {
+        command_upload_bundles(
+            &arweave,
+            path_chunks,
+            Some(log_dir.clone()),
+            tags,
+            reward_mult,
+            &output_format,
+            buffer,
+            RetryPolicy::default(),
+            false,
+            max_runtime,
+            false,
+        )
+        .await
+    };
+
+    let superseded = arweave
+        .supersede_bundle_statuses(&filtered_statuses, &earlier_ids, &log_dir)
+        .await?;
+    if !superseded.is_empty() {
+        println!(
+            "\nMoved {} replaced bundle statuses to {}.",
+            superseded.len(),
+            log_dir.join(SUPERSEDED_DIR).display()
+        );
+    }
+    if purge_superseded {
+        let purged = arweave.purge_superseded_statuses(&log_dir).await?;
+        println!("Deleted {} superseded bundle statuses.", purged);
+    }
+    uploaded.map(|_| ())
+}
+
+/// Uploads folder of nft assets and metadata, updating metadata with links to uploaded assets.
+/// `max_runtime` covers both the assets and the metadata, which get whatever time is left after
+/// the assets are uploaded.
+pub async fn command_upload_nfts<IP>(
+    arweave: &Arweave,
+    paths_iter: IP,
+    log_dir: Option<PathBuf>,
+    bundle_size: u64,
+    reward_mult: f32,
+    output_format: &OutputFormat,
+    buffer: usize,
+    sol_keypair_path: Option<PathBuf>,
+    link_file: bool,
+    no_bundle: bool,
+    skip_balance_check: bool,
+    truncate_names: bool,
+    max_runtime: Option<u64>,
+) -> CommandResult
+where
+    IP: Iterator<Item = PathBuf> + Send + Sync,
+{
+    let started = Instant::now();
+    let remaining_runtime =
+        || max_runtime.map(|secs| secs.saturating_sub(started.elapsed().as_secs()));
+    let paths_vec: Vec<PathBuf> = paths_iter.collect();
+    let metadata_paths_vec: Vec<PathBuf> =
+        paths_vec.iter().map(|p| p.with_extension("json")).collect();
+
+    let log_dir = if let Some(log_dir) = log_dir {
+        ensure_log_dir(&log_dir).await?;
+        log_dir
+    } else {
+        let parent_dir = paths_vec[0].parent().unwrap();
+        arweave.create_log_dir(parent_dir).await?
+    };
+
+    let log_dir_assets = log_dir.join("assets/");
+    let log_dir_metadata = log_dir.join("metadata/");
+
+    try_join(
+        fs::create_dir_all(&log_dir_assets),
+        fs::create_dir_all(&log_dir_metadata),
+    )
+    .await?;
+
+    // Upload images
+    println!("\n\nUploading assets...\n");
+    let mut receipt = upload_nft_files(
+        &arweave,
+        paths_vec.clone(),
+        &log_dir_assets,
+        bundle_size,
+        reward_mult,
+        output_format,
+        buffer,
+        sol_keypair_path.clone(),
+        no_bundle,
+        skip_balance_check,
+        None,
+        remaining_runtime(),
+    )
+    .await?;
+    let uploaded = async {
+        if !no_bundle && receipt.transactions.is_empty() {
+            return Err(Error::NoBundlesUploaded(log_dir_assets));
+        }
+
+        // Upload manifest and update metadata with links to uploaded images while it's posted.
+        // Assumes simple case of single image file - updates image link accordingly.
+        println!("\n\nUploading manifest for images and updating metadata with links from it...\n");
+        let (manifest_transaction, sig_response, asset_manifest_path) = write_nft_manifest(
+            &arweave,
+            paths_vec.clone(),
+            &log_dir_assets,
+            reward_mult,
+            sol_keypair_path.clone(),
+            no_bundle,
+        )
+        .await
+        .map_err(nft_stage_error(NftUploadStage::AssetManifest, &paths_vec))?;
+        let (image_link, files_link) = if link_file {
+            (ImageLink::File, FilesLink::Both)
+        } else {
+            (ImageLink::Id, FilesLink::Id)
+        };
+        // Metadata is put back if the manifest isn't posted.
+        let paid = sig_response.is_some();
+        let backup = MetadataBackup::read(&metadata_paths_vec).await;
+        let (posted, updated) = join(
+            arweave.post_written_manifest(
+                &manifest_transaction,
+                sig_response,
+                &asset_manifest_path,
+            ),
+            command_update_metadata(
+                &arweave,
+                paths_vec.clone().into_iter(),
+                asset_manifest_path.clone(),
+                image_link,
+                files_link,
+                true,
+                false,
+            ),
+        )
+        .await;
+        backup
+            .restore_unless_posted(&posted, paid)
+            .await
+            .map_err(nft_stage_error(
+                NftUploadStage::MetadataLinks,
+                &metadata_paths_vec,
+            ))?;
+        posted.map_err(nft_stage_error(NftUploadStage::AssetManifest, &paths_vec))?;
+        updated.map_err(nft_stage_error(
+            NftUploadStage::MetadataLinks,
+            &metadata_paths_vec,
+        ))?;
+        println!(
+            "Uploaded manifest for images and wrote to {}.",
+            asset_manifest_path.display()
+        );
+        receipt.add(manifest_receipt_transaction(
+            &manifest_transaction,
+            &asset_manifest_path,
+        ));
+
+        // Upload metadata.
+        println!("\n\nUploading updated metadata files...\n");
+        let metadata_receipt = upload_nft_files(
+            &arweave,
+            metadata_paths_vec.clone(),
+            &log_dir_metadata,
+            bundle_size,
+            reward_mult,
+            output_format,
+            buffer,
+            sol_keypair_path.clone(),
+            no_bundle,
+            skip_balance_check,
+            Some(mime_guess::mime::APPLICATION_JSON.as_ref()),
+            remaining_runtime(),
+        )
+        .await?;
+        if !no_bundle && metadata_receipt.transactions.is_empty() {
+            return Err(Error::NoBundlesUploaded(log_dir_metadata));
+        }
+        receipt.merge(metadata_receipt);
+
+        // Upload manifest for metadata and write metaplex items while it's posted.
+        println!("\n\nUploading manifest for metadata...\n");
+        let (manifest_transaction, sig_response, metadata_manifest_path) = write_nft_manifest(
+            &arweave,
+            metadata_paths_vec.clone(),
+            &log_dir_metadata,
+            reward_mult,
+            sol_keypair_path,
+            no_bundle,
+        )
+        .await
+        .map_err(nft_stage_error(
+            NftUploadStage::MetadataManifest,
+            &metadata_paths_vec,
+        ))?;
+        let (posted, written) = join(
+            arweave.post_written_manifest(
+                &manifest_transaction,
+                sig_response,
+                &metadata_manifest_path,
+            ),
+            command_write_metaplex_items(
+                &arweave,
+                metadata_paths_vec.clone().into_iter(),
+                metadata_manifest_path.clone(),
+                false,
+                truncate_names,
+                false,
+            ),
+        )
+        .await;
+        posted.map_err(nft_stage_error(
+            NftUploadStage::MetadataManifest,
+            &metadata_paths_vec,
+        ))?;
+        written.map_err(nft_stage_error(
+            NftUploadStage::MetaplexItems,
+            &metadata_paths_vec,
+        ))?;
+        receipt.add(manifest_receipt_transaction(
+            &manifest_transaction,
+            &metadata_manifest_path,
+        ));
+        Ok::<PathBuf, Error>(metadata_manifest_path)
+    }
+    .await;
+    if let Err(error) = &uploaded {
+        receipt.add_error(error);
+    }
+    write_receipt(arweave, &mut receipt, &log_dir, output_format).await?;
+    let metadata_manifest_path = uploaded?;
+
+    println!(
+        "\n\nUpload complete! Links to your uploaded metadata files can be found in `{}`",
+        metadata_manifest_path
+            .display()
+            .to_string()
+            .replace("manifest_", "metaplex_items_")
+    );
+
+    println!(
+        "Run `arloader update-nft-status {}` to confirm all transactions.",
+        log_dir.display().to_string()
+    );
+    Ok(())
+}
+
+/// Uploads files for [`command_upload_nfts`], either in bundles or as individual transactions,
+/// returning the receipt of the upload.
+async fn upload_nft_files(
+    arweave: &Arweave,
+    paths_vec: Vec<PathBuf>,
+    log_dir: &Path,
+    bundle_size: u64,
+    reward_mult: f32,
+    output_format: &OutputFormat,
+    buffer: usize,
+    sol_keypair_path: Option<PathBuf>,
+    no_bundle: bool,
+    skip_balance_check: bool,
+    content_type_override: Option<&str>,
+    max_runtime: Option<u64>,
+) -> Result<Receipt, Error> {
+    let tags = |c: &str| Tag::<String>::from_utf8_strs("Content-Type", c).map(|t| vec![t]);
+    let b64_tags = |c: &str| Tag::<Base64>::from_utf8_strs("Content-Type", c).map(|t| vec![t]);
+    match (no_bundle, sol_keypair_path) {
+        (true, Some(sol_keypair_path)) => {
+            command_upload_with_sol(
+                arweave,
+                paths_vec.into_iter(),
+                Some(log_dir.to_path_buf()),
+                content_type_override.map(b64_tags).transpose()?,
+                reward_mult,
+                output_format,
+                buffer,
+                sol_keypair_path,
+                max_runtime,
+            )
+            .await
+        }
+        (true, None) => {
+            command_upload(
+                arweave,
+                paths_vec.into_iter(),
+                Some(log_dir.to_path_buf()),
+                content_type_override.map(b64_tags).transpose()?,
+                reward_mult,
+                output_format,
+                buffer,
+                max_runtime,
+            )
+            .await
+        }
+        (false, Some(sol_keypair_path)) => {
+            let path_chunks = arweave.chunk_file_paths(paths_vec.into_iter(), bundle_size)?;
+            command_upload_bundles_with_sol(
+                arweave,
+                path_chunks,
+                Some(log_dir.to_path_buf()),
+                content_type_override.map(tags).transpose()?,
+                reward_mult,
+                output_format,
+                buffer,
+                RetryPolicy::default(),
+                skip_balance_check,
+                sol_keypair_path,
+                max_runtime,
+                false,
+            )
+            .await
+        }
+        (false, None) => {
+            let path_chunks = arweave.chunk_file_paths(paths_vec.into_iter(), bundle_size)?;
+            command_upload_bundles(
+                arweave,
+                path_chunks,
+                Some(log_dir.to_path_buf()),
+                content_type_override.map(tags).transpose()?,
+                reward_mult,
+                output_format,
+                buffer,
+                RetryPolicy::default(),
+                skip_balance_check,
+                max_runtime,
+                false,
+            )
+            .await
+        }
+    }
+}
+
+/// Receipt entry for a manifest posted by [`command_upload_nfts`].
+fn manifest_receipt_transaction(
+    transaction: &Transaction,
+    manifest_path: &Path,
+) -> ReceiptTransaction {
+    ReceiptTransaction {
+        id: transaction.id.clone(),
+        file_paths: vec![manifest_path.to_path_buf()],
+        data_size: transaction.data_size,
+        reward: transaction.reward,
+        lamports: None,
+    }
+}
+
+/// Signs a manifest for the files uploaded to `log_dir` by [`upload_nft_files`] and writes it to
+/// `log_dir`, returning the signed transaction, the [`SigResponse`] if it was paid for with SOL and
+/// the path of the manifest file so that it can be posted with [`Arweave::post_written_manifest`]
+/// alongside the next step.
+async fn write_nft_manifest(
+    arweave: &Arweave,
+    paths_vec: Vec<PathBuf>,
+    log_dir: &Path,
+    reward_mult: f32,
+    sol_keypair_path: Option<PathBuf>,
+    no_bundle: bool,
+) -> Result<(Transaction, Option<SigResponse>, PathBuf), Error> {
+    let solana_url = SOLANA_MAIN_URL.parse::<Url>()?;
+    let sol_ar_url = SOL_AR_BASE_URL.parse::<Url>()?.join("sol")?;
+    let from_keypair = sol_keypair_path.map(load_keypair).transpose()?;
+
+    let price_terms = arweave.get_price_terms(reward_mult).await?;
+    let manifest = if no_bundle {
+        let statuses = arweave
+            .read_statuses(paths_vec.into_iter(), log_dir.to_path_buf())
+            .await?;
+        arweave.create_manifest(statuses, None)?
+    } else {
+        let statuses = arweave
+            .read_bundle_statuses(&log_dir.display().to_string())
+            .await?;
+        arweave.create_manifest_from_bundle_statuses(statuses, None)?
+    };
+    let (signed_transaction, sig_response) = arweave
+        .sign_manifest(
+            &manifest,
+            price_terms,
+            solana_url,
+            sol_ar_url,
+            from_keypair.as_ref(),
+        )
+        .await?;
+    let manifest_path = arweave
+        .write_manifest(
+            manifest,
+            signed_transaction.id.to_string(),
+            log_dir.to_path_buf(),
+        )
+        .await?;
+    Ok((signed_transaction, sig_response, manifest_path))
+}
+
+/// Creates and uploads manifest from directory of bundle statuses, or of individual file
+/// statuses if `no_bundle`, with `index_path` as its index if provided.
+pub async fn command_upload_manifest(
+    arweave: &Arweave,
+    log_dir: &str,
+    reward_mult: f32,
+    sol_keypair_path: Option<String>,
+    index_path: Option<&str>,
+    no_bundle: bool,
+) -> CommandResult {
+    let solana_url = SOLANA_MAIN_URL.parse::<Url>()?;
+    let sol_ar_url = SOL_AR_BASE_URL.parse::<Url>()?.join("sol")?;
+    let from_keypair = sol_keypair_path.map(load_keypair).transpose()?;
+
+    let price_terms = arweave.get_price_terms(reward_mult).await?;
+    let output = if no_bundle {
+        arweave
+            .upload_manifest_from_log_dir(
+                log_dir,
+                price_terms,
+                solana_url,
+                sol_ar_url,
+                from_keypair,
+                index_path,
+            )
+            .await?
+    } else {
+        arweave
+            .upload_manifest_from_bundle_log_dir(
+                log_dir,
+                price_terms,
+                solana_url,
+                sol_ar_url,
+                from_keypair,
+                index_path,
+            )
+            .await?
+    };
+
+    println!("{}", output);
+    Ok(())
+}
+
+/// Uploads files to Arweave, paying with SOL.
+pub async fn command_upload_with_sol<IP>(
+    arweave: &Arweave,
+    paths_iter: IP,
+    log_dir: Option<PathBuf>,
+    tags: Option<Vec<Tag<Base64>>>,
+    reward_mult: f32,
+    output_format: &OutputFormat,
+    buffer: usize,
+    sol_keypair_path: PathBuf,
+    max_runtime: Option<u64>,
+) -> Result<Receipt, Error>
+where
+    IP: Iterator<Item = PathBuf> + Send + Sync,
+{
+    let paths_vec: Vec<PathBuf> = paths_iter.collect();
+    if let Some(log_dir) = &log_dir {
+        ensure_log_dir(log_dir).await?;
+    }
+    let token = CancelToken::default();
+    let deadline = arm_deadline(&token, max_runtime);
+    let solana_url = SOLANA_MAIN_URL.parse::<Url>()?;
+    let sol_ar_url = SOL_AR_BASE_URL.parse::<Url>()?.join("sol")?;
+    let from_keypair = load_keypair(sol_keypair_path)?;
+
+    let price_terms = arweave.get_price_terms(reward_mult).await?;
+
+    let mut stream = upload_files_with_sol_stream(
+        arweave,
+        token.wrap(paths_vec.clone().into_iter()),
+        tags,
+        log_dir.clone(),
+        None,
+        price_terms,
+        solana_url,
+        sol_ar_url,
+        &from_keypair,
+        buffer,
+    );
+
+    let mut counter = 0;
+    let mut failed_paths = Vec::new();
+    let mut finished = HashSet::new();
+    let mut printer = RecordPrinter::new::<Status>(output_format);
+    let mut receipt = Receipt::default();
+    let uploaded = async {
+        while let Some(result) = next_before_deadline(&mut stream, &deadline).await {
+            match result {
+                Ok(status) => {
+                    if counter == 0 {
+                        if let Some(log_dir) = &log_dir {
+                            print_note(
+                                output_format,
+                                &format!("Logging statuses to {}", &log_dir.display()),
+                            );
+                        }
+                    }
+                    print!("{}", printer.record_string(&status));
+                    receipt.add_status(&status);
+                    finished.extend(status.file_path);
+                    counter += 1;
+                }
+                Err(e) => {
+                    print_note(output_format, &format!("{:#?}", e.error));
+                    receipt.add_failure(&e);
+                    finished.extend(e.file_paths.iter().cloned());
+                    failed_paths.extend(e.file_paths);
+                }
+            }
+        }
+        print!("{}", printer.finish_string());
+
+        if counter == 0 {
+            print_note(output_format, "<FILE_PATHS> didn't match any files.");
+        } else {
+            print_note(output_format, &format!("Uploaded {} files. Run `arloader update-status {} --file-paths <FILE_PATHS>` to confirm transaction(s).",
+                counter,
+                log_dir.as_deref().unwrap_or(Path::new("")).display()));
+        }
+
+        print_gzip_savings(arweave, output_format);
+        report_failed_paths(&failed_paths, log_dir.as_deref(), output_format).await?;
+        Ok::<(), Error>(())
+    }
+    .await;
+    if let Err(error) = &uploaded {
+        receipt.add_error(error);
+    }
+    if let Some(log_dir) = &log_dir {
+        write_receipt(arweave, &mut receipt, log_dir, output_format).await?;
+    }
+    uploaded?;
+
+    let unfinished = unfinished_paths(&paths_vec, &finished);
+    check_max_runtime(&token, &deadline, unfinished, output_format)?;
+    Ok(receipt)
+}
+
+/// Uploads a single file, as the only data item in a bundle or as a transaction if `no_bundle`,
+/// paying with SOL if `sol_keypair_path` is provided. The transaction includes
+/// [`Arweave::tip`] if set, which is paid for in SOL along with the reward when paying with SOL.
+/// Waits until the transaction is at least pending and prints a link to the file.
+pub async fn command_upload_one(
+    arweave: &Arweave,
+    file_path: PathBuf,
+    log_dir: Option<PathBuf>,
+    tags: Vec<Tag<String>>,
+    reward_mult: f32,
+    no_bundle: bool,
+    sol_keypair_path: Option<PathBuf>,
+) -> CommandResult {
+    if let Some(log_dir) = &log_dir {
+        ensure_log_dir(log_dir).await?;
+    }
+    let price_terms = arweave.get_price_terms(reward_mult).await?;
+    let sol = match sol_keypair_path {
+        Some(sol_keypair_path) => Some((
+            SOLANA_MAIN_URL.parse::<Url>()?,
+            SOL_AR_BASE_URL.parse::<Url>()?.join("sol")?,
+            load_keypair(sol_keypair_path)?,
+        )),
+        None => None,
+    };
+    if arweave.tip.is_some() && sol.is_none() {
+        arweave
+            .check_wallet_balance(&[file_path.clone()], price_terms)
+            .await?;
+    }
+
+    let (tx_id, file_id) = if no_bundle {
+        let tags = tags
+            .iter()
+            .map(|t| Tag::<Base64>::from_utf8_strs(&t.name, &t.value))
+            .collect::<Result<Vec<_>, _>>()?;
+        let status = match &sol {
+            Some((solana_url, sol_ar_url, keypair)) => {
+                arweave
+                    .upload_file_from_path_with_sol(
+                        file_path,
+                        log_dir,
+                        Some(tags),
+                        None,
+                        price_terms,
+                        solana_url.clone(),
+                        sol_ar_url.clone(),
+                        keypair,
+                    )
+                    .await?
+            }
+            None => {
+                arweave
+                    .upload_file_from_path(file_path, log_dir, Some(tags), None, price_terms)
+                    .await?
+            }
+        };
+        (status.id.clone(), status.id)
+    } else {
+        let data_size = fs::metadata(&file_path).await?.len();
+        let paths_chunk = PathsChunk(vec![file_path.clone()], data_size);
+        let status = match &sol {
+            Some((solana_url, sol_ar_url, keypair)) => {
+                arweave
+                    .post_bundle_transaction_from_file_paths_with_sol(
+                        paths_chunk,
+                        tags,
+                        log_dir.clone(),
+                        price_terms,
+                        CHUNKS_BUFFER_FACTOR,
+                        RetryPolicy::default(),
+                        solana_url.clone(),
+                        sol_ar_url.clone(),
+                        keypair,
+                    )
+                    .await?
+            }
+            None => {
+                arweave
+                    .post_bundle_transaction_from_file_paths(
+                        paths_chunk,
+                        tags,
+                        log_dir.clone(),
+                        price_terms,
+                        CHUNKS_BUFFER_FACTOR,
+                        RetryPolicy::default(),
+                    )
+                    .await?
+            }
+        };
+        if let Some(log_dir) = &log_dir {
+            fs::write(
+                log_dir.join(status.id.to_string()).with_extension("json"),
+                to_status_json(&status, arweave.pretty_status)?,
+            )
+            .await?;
+        }
+        let item_id = status
+            .file_paths
+            .as_object()
+            .and_then(|paths| paths.values().next())
+            .and_then(|entry| entry["id"].as_str())
+            .map(Base64::from_str)
+            .transpose()?
+            .unwrap_or_else(|| status.id.clone());
+        (status.id, item_id)
+    };
+
+    // The upload has already been posted, so errors checking on it, e.g., from a gateway that's
+    // briefly unavailable, are retried like statuses that aren't pending yet.
+    let mut polls = 0;
+    loop {
+        let error = match arweave.get_status(&tx_id).await {
+            Ok(status) if matches!(status.status, StatusCode::Pending | StatusCode::Confirmed) => {
+                break
+            }
+            Ok(_) => None,
+            Err(error) => Some(error),
+        };
+        polls += 1;
+        if polls == UPLOAD_ONE_MAX_POLLS {
+            let reason = error.map(|e| format!(" ({})", e)).unwrap_or_default();
+            println!(
+                "Transaction {} isn't pending yet{}, run `arloader get-status {}` to check on it.",
+                tx_id, reason, tx_id
+            );
+            break;
+        }
+        sleep(UPLOAD_ONE_POLL_INTERVAL).await;
+    }
+    println!("https://arweave.net/{}", file_id);
+    Ok(())
+}
+
+/// Prints the transactions posted by a wallet since `since` with their rewards summed by group,
+/// as csv by transaction if `csv`.
+pub async fn command_audit(
+    arweave: &Arweave,
+    wallet_address: Option<String>,
+    since: DateTime<Utc>,
+    csv: bool,
+    output_format: &OutputFormat,
+) -> CommandResult {
+    let audit = arweave.audit_wallet(wallet_address, since).await?;
+    match (csv, output_format) {
+        (true, _) => print!("{}", audit.to_csv()),
+        (false, OutputFormat::Json) => {
+            println!("{}", serde_json::to_string_pretty(&audit.to_json())?)
+        }
+        (false, OutputFormat::JsonCompact | OutputFormat::JsonLines) => {
+            println!("{}", audit.to_json())
+        }
+        (false, _) => print!("{}", audit),
+    }
+    Ok(())
+}
+
+/// Gets balance for provided wallet address.
+pub async fn command_wallet_balance(
+    arweave: &Arweave,
+    wallet_address: Option<String>,
+) -> CommandResult {
+    let mb = u64::pow(1024, 2);
+    let result = tokio::join!(
+        arweave.get_wallet_balance(wallet_address),
+        arweave.get_price(&mb)
+    );
+    let balance = result.0?;
+    let (winstons_per_kb, usd_per_ar, _) = result.1?;
+
+    let balance_usd = &balance.to_f32().unwrap() / &WINSTONS_PER_AR.to_f32().unwrap()
+        * &usd_per_ar.to_f32().unwrap()
+        / 100_f32;
+
+    let usd_per_kb = (&winstons_per_kb * &usd_per_ar).to_f32().unwrap() / 1e14_f32;
+
+    println!(
+            "Wallet balance is {} {units} (${balance_usd:.2} at ${ar_price:.2} USD per AR). At the current price of {price} {units} per MB (${usd_price:.4}), you can upload {max} MB of data.",
+            &balance,
+            units = arweave.units,
+            max = &balance / &winstons_per_kb,
+            price = &winstons_per_kb,
+            balance_usd = balance_usd,
+            ar_price = &usd_per_ar.to_f32().unwrap()
+            / 100_f32,
+            usd_price = usd_per_kb
+    );
+    Ok(())
+}
+
+/// Generates a new wallet, writes it to `output` and prints its address.
+pub async fn command_wallet_generate(output: &Path) -> CommandResult {
+    let (provider, jwk) = Provider::generate(&mut OsRng)?;
+    write_keypair(output, &jwk)?;
+    println!(
+        "Wrote new wallet {} to {}. Keep a backup of it, there is no way to recover a lost keypair.",
+        provider.wallet_address()?,
+        output.display()
+    );
+    Ok(())
+}
+
+/// Writes metaplex link items used to create NFTs with candy machine program.
+pub async fn command_write_metaplex_items<IP>(
+    arweave: &Arweave,
+    paths_iter: IP,
+    manifest_path: PathBuf,
+    link_file: bool,
+    truncate_names: bool,
+    ignore_missing: bool,
+) -> CommandResult
+where
+    IP: Iterator<Item = PathBuf> + Send + Sync,
+{
+    let metaplex_items_path = arweave
+        .write_metaplex_items(
+            paths_iter,
+            manifest_path,
+            link_file,
+            truncate_names,
+            ignore_missing,
+        )
+        .await?;
+
+    println!(
+        "Successfully wrote metaplex items for metadata files to {}.",
+        metaplex_items_path.display().to_string()
+    );
+    Ok(())
+}
+
+/// Gets manifest transaction id from first manifest file in a log directory, or `None` if it
+/// doesn't have one, e.g., because it doesn't exist.
+pub fn get_manifest_id_from_log_dir(log_dir: &Path) -> Option<String> {
+    glob(&format!("{}manifest_*.json", log_dir.display()))
+        .ok()?
+        .filter_map(Result::ok)
+        .find_map(|p| {
+            let txid = p.file_stem()?.to_str()?.strip_prefix("manifest_")?;
+            match Base64::from_str(txid) {
+                Ok(id) if id.0.len() == 32 => Some(txid.to_string()),
+                _ => None,
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        arm_deadline, bundle_file_paths, check_max_runtime, check_upload_memory,
+        command_bundle_items, command_dedupe_statuses, command_list_bundle_statuses,
+        command_queue_add, command_queue_run, command_reupload_bundles,
+        command_update_bundle_statuses, command_update_nft_statuses, command_upload_bundles,
+        command_verify_uploads, cost_estimates, cost_estimates_json, ensure_log_dir,
+        estimate_upload_memory, get_manifest_id_from_log_dir, next_before_deadline,
+        report_failed_paths, send_index_hint, unfinished_paths, CostEstimate, Deadline,
+        MemoryProbe, RewardComparison, FAILED_PATHS_FILE, FAILED_UPDATES_FILE,
+        MAX_PRINTED_FAILED_PATHS, VERIFY_FAILURES_FILE,
+    };
+    use crate::{
+        bundle::{SignatureType, BUNDLE_HEADER_LEN},
+        error::Error,
+        merkle::chunks_len,
+        queue::UploadQueue,
+        status::{
+            to_status_json, BundleStatus, Metrics, OutputFormat, RawStatus, Receipt, RecordedSize,
+            Status, StatusCode, UploadCheck,
+        },
+        transaction::{Base64, FromUtf8Strs, Tag},
+        upload_bundles_stream,
+        utils::{
+            mock_server::{self, MockRequest},
+            TempDir,
+        },
+        Arweave, CancelToken, NetworkParams, PathsChunk, RetryPolicy, BLOCK_SIZE, SUPERSEDED_DIR,
+    };
+    use futures::{stream, StreamExt};
+    use matches::assert_matches;
+    use serde_json::{json, Value};
+    use std::{
+        collections::HashSet,
+        path::PathBuf,
+        str::FromStr,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            mpsc,
+        },
+    };
+    use tokio::{
+        fs,
+        time::{sleep, Duration, Instant},
+    };
+    use url::Url;
+
+    /// Mock index hint endpoint, returning its url and a receiver of requests made to it.
+    fn mock_endpoint(response_status: &'static str) -> (Url, mpsc::Receiver<MockRequest>) {
+        let (base_url, requests) = mock_server::serve(move |_| (response_status, String::new()));
+        (base_url.join("hints").unwrap(), requests)
+    }
+
+    #[test]
+    fn test_reward_comparison() {
+        let size = |data_size, reward| RecordedSize {
+            id: Base64(vec![1; 32]),
+            file_paths: Vec::new(),
+            data_size,
+            overhead_bytes: 0,
+            reward,
+        };
+        let network_params = NetworkParams::default();
+        let comparison = RewardComparison::new(
+            &[size(300_000, 500), size(1000, 100)],
+            (100, 10),
+            &network_params,
+        );
+        assert_eq!(
+            comparison,
+            RewardComparison {
+                paid: 600,
+                current: 210
+            }
+        );
+        assert!((comparison.change().unwrap() + 65.0).abs() < 1e-9);
+
+        // The overhead of a bundle is priced along with its data.
+        let bundle = RecordedSize {
+            overhead_bytes: 1,
+            ..size(BLOCK_SIZE, 100)
+        };
+        assert_eq!(
+            RewardComparison::new(&[bundle], (100, 10), &network_params).current,
+            110
+        );
+        assert_eq!(
+            RewardComparison::new(&[], (100, 10), &network_params).change(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cost_estimates_json() -> Result<(), Error> {
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key0.json"),
+            Url::from_str("http://url.com").unwrap(),
+        )
+        .await?;
+        let paths: Vec<PathBuf> = glob::glob("tests/fixtures/[0-4].png")?
+            .filter_map(Result::ok)
+            .collect();
+        let bytes: u64 = paths.iter().map(|p| p.metadata().unwrap().len()).sum();
+
+        for (no_bundle, with_sol) in [(true, false), (true, true), (false, false)] {
+            let estimates = cost_estimates(
+                &arweave,
+                paths.clone().into_iter(),
+                (1_000_000, 100_000),
+                1e-12,
+                with_sol,
+                10_000_000,
+                no_bundle,
+            )?;
+            let output: Value =
+                serde_json::from_str(&cost_estimates_json(&estimates, with_sol).to_string())?;
+            let transactions: Vec<CostEstimate> =
+                serde_json::from_value(output["transactions"].clone())?;
+            let total: CostEstimate = serde_json::from_value(output["total"].clone())?;
+
+            assert_eq!(transactions.len(), if no_bundle { 5 } else { 1 });
+            assert_eq!(
+                transactions.iter().map(|t| t.paths.len()).sum::<usize>(),
+                paths.len()
+            );
+            assert_eq!(
+                transactions.iter().map(|t| t.data_bytes).sum::<u64>(),
+                bytes
+            );
+            assert_eq!(total.data_bytes, bytes);
+            let overhead = if no_bundle {
+                0
+            } else {
+                BUNDLE_HEADER_LEN as u64 + paths.len() as u64 * arweave.data_item_overhead()? as u64
+            };
+            assert_eq!(total.bytes, bytes + overhead);
+            assert_eq!(transactions.iter().map(|t| t.cost).sum::<u64>(), total.cost);
+            for t in transactions.iter() {
+                assert_eq!(t.blocks, arweave.network_params.blocks_len(t.bytes));
+                assert_eq!(t.chunks, chunks_len(t.bytes));
+                assert_eq!(
+                    t.reward,
+                    arweave
+                        .network_params
+                        .reward_breakdown(t.bytes, (1_000_000, 100_000))
+                );
+                if !with_sol {
+                    assert_eq!(t.cost, t.reward.total);
+                }
+            }
+            assert_eq!(
+                transactions.iter().map(|t| t.chunks).sum::<u64>(),
+                total.chunks
+            );
+            assert_eq!(
+                transactions.iter().map(|t| t.reward.total).sum::<u64>(),
+                total.reward.total
+            );
+            assert_eq!(total.reward.blocks, total.blocks);
+            assert_eq!(
+                (total.reward.base, total.reward.incremental),
+                (1_000_000, 100_000)
+            );
+            assert!(output["total"]["reward"]["incremental"].is_u64());
+            let usd: f64 = transactions.iter().map(|t| t.usd).sum();
+            assert!((usd - total.usd).abs() < 1e-9);
+            assert_eq!(
+                output["units"],
+                if with_sol { "lamports" } else { "winstons" }
+            );
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bundle_items() -> Result<(), Error> {
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let gateway = mock_server::MockGateway::serve();
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key0.json"),
+            gateway.url.clone(),
+        )
+        .await?;
+        let mut paths = Vec::new();
+        for (i, data) in [b"hello", b"world"].into_iter().enumerate() {
+            let data_item = arweave.sign_data_item(arweave.create_data_item(
+                data.to_vec(),
+                vec![Tag::<String>::from_utf8_strs(
+                    "File-Name",
+                    &format!("{}.txt", i),
+                )?],
+                true,
+                false,
+                SignatureType::Arweave,
+            )?)?;
+            let path = temp_dir.0.join(format!("{}.bin", i));
+            fs::write(&path, data_item.serialize()?).await?;
+            paths.push(path);
+        }
+        let duplicate = temp_dir.0.join("copy.bin");
+        fs::copy(&paths[0], &duplicate).await?;
+
+        let bundle_items = |paths: Vec<PathBuf>, skip_balance_check| {
+            command_bundle_items(
+                &arweave,
+                paths.into_iter(),
+                true,
+                Some(temp_dir.0.join("status")),
+                1.0,
+                &OutputFormat::DisplayQuiet,
+                1,
+                RetryPolicy::with_retries(0),
+                skip_balance_check,
+            )
+        };
+
+        let mut with_duplicate = paths.clone();
+        with_duplicate.push(duplicate);
+        assert_matches!(
+            bundle_items(with_duplicate, false).await,
+            Err(Error::BundleVerificationFailed(1))
+        );
+        assert!(gateway.posted_transactions().is_empty());
+
+        gateway.set_balance(0);
+        assert_matches!(
+            bundle_items(paths.clone(), false).await,
+            Err(Error::InsufficientBalance { balance: 0, .. })
+        );
+        assert!(gateway.posted_transactions().is_empty());
+        assert!(!temp_dir.0.join("status").exists());
+
+        bundle_items(paths, true).await?;
+        assert_eq!(gateway.posted_transactions().len(), 1);
+        Ok(())
+    }
+
+    fn confirmed_bundle_status() -> BundleStatus {
+        BundleStatus {
+            id: Base64(vec![1; 32]),
+            status: StatusCode::Confirmed,
+            file_paths: json!({
+                "0.png": {"id": Base64(vec![2; 32]).to_string(), "content_type": "image/png"},
+                "1.png": {"id": Base64(vec![3; 32]).to_string(), "content_type": "image/png"},
+            }),
+            number_of_files: 2,
+            ..BundleStatus::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_command_verify_uploads() -> Result<(), Error> {
+        let (file_id, item_id) = (Base64(vec![1; 32]), Base64(vec![2; 32]));
+        let (file_path, item_path) = (
+            PathBuf::from("tests/fixtures/0.json"),
+            PathBuf::from("tests/fixtures/1.json"),
+        );
+        let responses = [
+            (format!("/{}", file_id), "tests/fixtures/0.json"),
+            (format!("/{}", item_id), "tests/fixtures/2.json"),
+        ];
+        let (base_url, _requests) =
+            mock_server::serve(
+                move |path| match responses.iter().find(|(p, _)| p == path) {
+                    Some((_, fixture)) => ("200 OK", std::fs::read_to_string(fixture).unwrap()),
+                    None => ("404 Not Found", String::new()),
+                },
+            );
+        let arweave = Arweave {
+            base_url,
+            ..Arweave::default()
+        };
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let log_dir = format!("{}/", temp_dir.0.display());
+
+        arweave
+            .write_status(
+                Status {
+                    id: file_id.clone(),
+                    file_path: Some(file_path.clone()),
+                    ..Status::default()
+                },
+                temp_dir.0.clone(),
+                None,
+            )
+            .await?;
+        let bundle_status = BundleStatus {
+            id: Base64(vec![3; 32]),
+            file_paths: json!({ item_path.to_str().unwrap(): { "id": item_id.to_string() } }),
+            ..BundleStatus::default()
+        };
+        fs::write(
+            format!("{}{}.json", log_dir, bundle_status.id),
+            to_status_json(&bundle_status, false)?,
+        )
+        .await?;
+
+        // The data item is served with the wrong data and 2.json was never uploaded.
+        let paths = vec![
+            file_path.clone(),
+            item_path.clone(),
+            PathBuf::from("tests/fixtures/2.json"),
+        ];
+        let result = command_verify_uploads(
+            &arweave,
+            paths.into_iter(),
+            &log_dir,
+            2,
+            &OutputFormat::Display,
+        )
+        .await;
+        assert_matches!(result, Err(Error::UploadVerificationFailed(2)));
+
+        let failures_path = temp_dir.0.join(VERIFY_FAILURES_FILE);
+        let failures: Vec<UploadCheck> =
+            serde_json::from_str(&fs::read_to_string(&failures_path).await?)?;
+        assert_eq!(
+            failures
+                .iter()
+                .map(|c| (c.file_path.clone(), c.id.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (item_path, Some(item_id)),
+                (PathBuf::from("tests/fixtures/2.json"), None)
+            ]
+        );
+
+        command_verify_uploads(
+            &arweave,
+            vec![file_path].into_iter(),
+            &log_dir,
+            2,
+            &OutputFormat::Display,
+        )
+        .await?;
+        assert!(!failures_path.exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reupload_bundles_keeps_replaced_statuses() -> Result<(), Error> {
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let log_dir = PathBuf::from(format!("{}/", temp_dir.0.display()));
+        let gateway = mock_server::MockGateway::serve();
+        let mut arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key0.json"),
+            gateway.url.clone(),
+        )
+        .await?;
+        let file_paths = vec![
+            PathBuf::from("tests/fixtures/0.png"),
+            PathBuf::from("tests/fixtures/1.png"),
+        ];
+        let replaced = BundleStatus {
+            id: Base64(vec![9; 32]),
+            status: StatusCode::NotFound,
+            file_paths: json!({
+                "tests/fixtures/0.png": {"id": Base64(vec![2; 32]).to_string(), "content_type": "image/png"},
+                "tests/fixtures/1.png": {"id": Base64(vec![3; 32]).to_string(), "content_type": "image/png"},
+            }),
+            number_of_files: 2,
+            ..BundleStatus::default()
+        };
+        let status_path = log_dir.join(format!("{}.json", replaced.id));
+        fs::write(&status_path, to_status_json(&replaced, false)?).await?;
+        // An older status for the same files isn't taken for a replacement.
+        let older = BundleStatus {
+            id: Base64(vec![8; 32]),
+            status: StatusCode::Confirmed,
+            ..replaced.clone()
+        };
+        let older_path = log_dir.join(format!("{}.json", older.id));
+        fs::write(&older_path, to_status_json(&older, false)?).await?;
+
+        // The replacement bundle fails to post, leaving the status as it was.
+        gateway.fail_next("/tx", "400 Bad Request", 100);
+        command_reupload_bundles(
+            &arweave,
+            file_paths.clone().into_iter(),
+            log_dir.clone(),
+            None,
+            10_000_000,
+            1.0,
+            Some(vec![StatusCode::NotFound]),
+            None,
+            OutputFormat::Display,
+            1,
+            None,
+            false,
+            None,
+            None,
+        )
+        .await?;
+        let status = arweave.read_bundle_status(status_path.clone()).await?;
+        assert_eq!(status.id, replaced.id);
+        assert_eq!(status.status, StatusCode::NotFound);
+        assert!(!log_dir.join(SUPERSEDED_DIR).exists());
+        fs::remove_file(&older_path).await?;
+
+        let gateway = mock_server::MockGateway::serve();
+        arweave.base_url = gateway.url.clone();
+        command_reupload_bundles(
+            &arweave,
+            file_paths.clone().into_iter(),
+            log_dir.clone(),
+            None,
+            10_000_000,
+            1.0,
+            Some(vec![StatusCode::NotFound]),
+            None,
+            OutputFormat::Display,
+            1,
+            None,
+            false,
+            None,
+            None,
+        )
+        .await?;
+        let statuses = arweave
+            .read_bundle_statuses(&log_dir.display().to_string())
+            .await?;
+        assert_eq!(statuses.len(), 1);
+        assert_ne!(statuses[0].id, replaced.id);
+        assert_eq!(statuses[0].number_of_files, 2);
+        assert!(!status_path.exists());
+        let superseded_path = log_dir
+            .join(SUPERSEDED_DIR)
+            .join(format!("{}.json", replaced.id));
+        assert_eq!(
+            arweave
+                .read_bundle_status(superseded_path.clone())
+                .await?
+                .id,
+            replaced.id
+        );
+
+        assert_eq!(arweave.purge_superseded_statuses(&log_dir).await?, 1);
+        assert!(!superseded_path.exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reupload_bundles_from_verify_failures() -> Result<(), Error> {
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let log_dir = PathBuf::from(format!("{}/", temp_dir.0.display()));
+        let gateway = mock_server::MockGateway::serve();
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key0.json"),
+            gateway.url.clone(),
+        )
+        .await?;
+        let verified = BundleStatus {
+            id: Base64(vec![9; 32]),
+            status: StatusCode::Confirmed,
+            file_paths: json!({
+                "tests/fixtures/0.png": {"id": Base64(vec![2; 32]).to_string(), "content_type": "image/png"},
+                "tests/fixtures/1.png": {"id": Base64(vec![3; 32]).to_string(), "content_type": "image/png"},
+            }),
+            number_of_files: 2,
+            ..BundleStatus::default()
+        };
+        let status_path = log_dir.join(format!("{}.json", verified.id));
+        fs::write(&status_path, to_status_json(&verified, false)?).await?;
+        let failures_path = log_dir.join(VERIFY_FAILURES_FILE);
+        let failures = vec![UploadCheck {
+            file_path: PathBuf::from("tests/fixtures/0.png"),
+            id: Some(Base64(vec![2; 32])),
+            failure: Some("data doesn't match".to_string()),
+        }];
+        fs::write(&failures_path, serde_json::to_string_pretty(&failures)?).await?;
+
+        command_reupload_bundles(
+            &arweave,
+            Vec::new().into_iter(),
+            log_dir.clone(),
+            None,
+            10_000_000,
+            1.0,
+            None,
+            None,
+            OutputFormat::Display,
+            1,
+            None,
+            false,
+            Some(failures_path),
+            None,
+        )
+        .await?;
+        let statuses = arweave
+            .read_bundle_statuses(&log_dir.display().to_string())
+            .await?;
+        assert_eq!(statuses.len(), 2);
+        assert!(status_path.exists());
+        let reuploaded = statuses.iter().find(|s| s.id != verified.id).unwrap();
+        assert_eq!(reuploaded.number_of_files, 1);
+        assert!(reuploaded.file_paths.get("tests/fixtures/0.png").is_some());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_upload_bundles_writes_receipt() -> Result<(), Error> {
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let log_dir = PathBuf::from(format!("{}/", temp_dir.0.display()));
+        let gateway = mock_server::MockGateway::serve();
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key0.json"),
+            gateway.url.clone(),
+        )
+        .await?;
+        let mut path_chunks = Vec::new();
+        for i in 0..2 {
+            let path = PathBuf::from(format!("tests/fixtures/{}.png", i));
+            let data_size = fs::metadata(&path).await?.len();
+            path_chunks.push(PathsChunk(vec![path], data_size));
+        }
+
+        let receipt = command_upload_bundles(
+            &arweave,
+            path_chunks.clone(),
+            Some(log_dir.clone()),
+            None,
+            1.0,
+            &OutputFormat::Display,
+            1,
+            RetryPolicy::default(),
+            true,
+            None,
+            false,
+        )
+        .await?;
+        let written: Receipt =
+            serde_json::from_str(&fs::read_to_string(log_dir.join(receipt.file_name(0))).await?)?;
+        assert_eq!(written, receipt);
+        assert_eq!(written.number_of_files, 2);
+        assert_eq!(written.data_size, path_chunks[0].1 + path_chunks[1].1);
+        assert_eq!(written.lamports, None);
+        assert!(written.failures.is_empty());
+
+        let posted = gateway.posted_transactions();
+        assert_eq!(written.transactions.len(), 2);
+        for (transaction, chunk) in written.transactions.iter().zip(&path_chunks) {
+            assert_eq!(transaction.file_paths, chunk.0);
+            assert!(posted.iter().any(|t| t["id"] == transaction.id.to_string()));
+        }
+        assert_eq!(
+            written.reward,
+            written.transactions.iter().map(|t| t.reward).sum::<u64>()
+        );
+
+        let mut receipt = receipt;
+        let mut written_paths = Vec::new();
+        for _ in 0..3 {
+            let path =
+                write_receipt(&arweave, &mut receipt, &log_dir, &OutputFormat::Display).await?;
+            assert!(path.exists());
+            written_paths.push(path);
+        }
+        written_paths.sort();
+        written_paths.dedup();
+        assert_eq!(written_paths.len(), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_queue_run_resumes_without_reposting() -> Result<(), Error> {
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let queue_dir = temp_dir.0.join("queue");
+        let gateway = mock_server::MockGateway::serve();
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key0.json"),
+            gateway.url.clone(),
+        )
+        .await?;
+        let paths = (0..5).map(|i| PathBuf::from(format!("tests/fixtures/{}.png", i)));
+        command_queue_add(&queue_dir, paths).await?;
+        assert!(UploadQueue::open(&queue_dir)
+            .await?
+            .pending()
+            .iter()
+            .all(|p| p.is_absolute()));
+
+        // Drops the run while the gateway is responding to the post of the first bundle, after
+        // it has accepted it, as if the process was killed before the post was recorded.
+        gateway.delay_next("/tx", Duration::from_secs(5));
+        {
+            let interrupted = command_queue_run(
+                &arweave,
+                &queue_dir,
+                None,
+                1,
+                1.0,
+                &OutputFormat::Display,
+                1,
+                RetryPolicy::with_retries(0),
+                None,
+            );
+            tokio::pin!(interrupted);
+            tokio::select! {
+                biased;
+                _ = async {
+                    while gateway.posted_transactions().is_empty() {
+                        tokio::task::yield_now().await;
+                    }
+                } => (),
+                _ = &mut interrupted => panic!("run finished before it was interrupted"),
+            }
+        }
+        let queue = UploadQueue::open(&queue_dir).await?;
+        assert_eq!(queue.counts().done, 0);
+        assert_eq!(queue.posting().len(), 1);
+        assert_eq!(gateway.posted_transactions().len(), 1);
+
+        // A bundle that was journaled as posting but never made it to the gateway is uploaded
+        // again.
+        let mut queue = UploadQueue::open(&queue_dir).await?;
+        let unposted = PathBuf::from("tests/fixtures/5.png").canonicalize()?;
+        queue.add(vec![unposted.clone()].into_iter()).await?;
+        queue
+            .mark_posting(&[unposted], &Base64(vec![9; 32]))
+            .await?;
+
+        let posted = command_queue_run(
+            &arweave,
+            &queue_dir,
+            None,
+            1,
+            1.0,
+            &OutputFormat::Display,
+            1,
+            RetryPolicy::with_retries(0),
+            None,
+        )
+        .await?;
+        assert_eq!(posted, 5);
+
+        // Every file was uploaded in exactly one posted bundle.
+        let queue = UploadQueue::open(&queue_dir).await?;
+        assert_eq!(queue.counts().done, 6);
+        let mut bundle_ids: Vec<String> = queue
+            .items()
+            .iter()
+            .map(|i| i.bundle_id.clone().unwrap())
+            .collect();
+        let mut posted_ids: Vec<String> = gateway
+            .posted_transactions()
+            .iter()
+            .map(|t| t["id"].as_str().unwrap().to_string())
+            .collect();
+        bundle_ids.sort();
+        posted_ids.sort();
+        assert_eq!(bundle_ids, posted_ids);
+
+        let posted = command_queue_run(
+            &arweave,
+            &queue_dir,
+            None,
+            1,
+            1.0,
+            &OutputFormat::Display,
+            1,
+            RetryPolicy::with_retries(0),
+            None,
+        )
+        .await?;
+        assert_eq!(posted, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_dedupe_statuses() -> Result<(), Error> {
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let log_dir = format!("{}/", temp_dir.0.display());
+        let arweave = Arweave::default();
+
+        let confirmed = confirmed_bundle_status();
+        let not_found = BundleStatus {
+            id: Base64(vec![4; 32]),
+            status: StatusCode::NotFound,
+            ..confirmed_bundle_status()
+        };
+        for status in [&confirmed, &not_found] {
+            fs::write(
+                format!("{}{}.json", log_dir, status.id),
+                to_status_json(status, false)?,
+            )
+            .await?;
+        }
+
+        // Dry run by default.
+        command_dedupe_statuses(&arweave, &log_dir, false).await?;
+        assert_eq!(arweave.read_bundle_statuses(&log_dir).await?.len(), 2);
+
+        command_dedupe_statuses(&arweave, &log_dir, true).await?;
+        let statuses = arweave.read_bundle_statuses(&log_dir).await?;
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0].id, confirmed.id);
+        assert_eq!(statuses[0].previous.len(), 1);
+        assert_eq!(statuses[0].previous[0].id, not_found.id);
+        assert_eq!(statuses[0].previous[0].status, StatusCode::NotFound);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bundle_status_summary() -> Result<(), Error> {
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let log_dir = format!("{}/", temp_dir.0.display());
+        let arweave = Arweave::default();
+
+        let confirmed = BundleStatus {
+            data_size: 3_000,
+            ..confirmed_bundle_status()
+        };
+        let not_found = |i: u8| BundleStatus {
+            id: Base64(vec![i; 32]),
+            status: StatusCode::NotFound,
+            number_of_files: 60,
+            data_size: 225_000,
+            ..confirmed_bundle_status()
+        };
+        for status in [confirmed, not_found(4), not_found(5)] {
+            fs::write(
+                format!("{}{}.json", log_dir, status.id),
+                to_status_json(&status, false)?,
+            )
+            .await?;
+        }
+
+        let table = arweave
+            .bundle_status_summary(&log_dir, &OutputFormat::Display)
+            .await?;
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 8);
+        assert_eq!(
+            lines[4].split_whitespace().collect::<Vec<_>>(),
+            ["NotFound", "2", "120", "450"]
+        );
+        assert_eq!(
+            lines[5].split_whitespace().collect::<Vec<_>>(),
+            ["Confirmed", "1", "2", "3"]
+        );
+        assert_eq!(
+            lines[7].split_whitespace().collect::<Vec<_>>(),
+            ["Total", "3", "122", "453"]
+        );
+
+        let summary: Value = serde_json::from_str(
+            &arweave
+                .bundle_status_summary(&log_dir, &OutputFormat::JsonCompact)
+                .await?,
+        )?;
+        assert_eq!(
+            summary["statuses"][2],
+            json!({"status": "NotFound", "bundles": 2, "files": 120, "data_size": 450_000})
+        );
+        assert_eq!(
+            summary["total"],
+            json!({"bundles": 3, "files": 122, "data_size": 453_000})
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_newer_status_versions_skipped() -> Result<(), Error> {
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let log_dir = format!("{}/", temp_dir.0.display());
+        let (base_url, _) = mock_server::serve(|_| ("404 Not Found", String::new()));
+        let arweave = Arweave {
+            base_url,
+            ..Arweave::default()
+        };
+
+        let current = BundleStatus {
+            id: Base64(vec![1; 32]),
+            ..BundleStatus::default()
+        };
+        let current_path = PathBuf::from(format!("{}{}.json", log_dir, current.id));
+        fs::write(&current_path, to_status_json(&current, false)?).await?;
+
+        let mut newer = serde_json::to_value(&BundleStatus {
+            id: Base64(vec![2; 32]),
+            ..BundleStatus::default()
+        })?;
+        newer["schema_version"] = json!(999);
+        newer["status"] = json!("Archived");
+        newer["storage_class"] = json!("cold");
+        let newer_path = PathBuf::from(format!("{}{}.json", log_dir, Base64(vec![2; 32])));
+        fs::write(&newer_path, newer.to_string()).await?;
+
+        let statuses = arweave.read_bundle_statuses(&log_dir).await?;
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0].id, current.id);
+
+        let output_format = OutputFormat::Display;
+        command_list_bundle_statuses(
+            &arweave,
+            &log_dir,
+            None,
+            None,
+            None,
+            false,
+            None,
+            &output_format,
+        )
+        .await?;
+
+        // A status that can't be read is reported as failed without stopping the other updates,
+        // while the newer one is only skipped.
+        let corrupt_path = PathBuf::from(format!("{}{}.json", log_dir, Base64(vec![3; 32])));
+        fs::write(&corrupt_path, "{").await?;
+        assert_matches!(
+            command_update_bundle_statuses(
+                &arweave,
+                PathBuf::from(&log_dir),
+                &output_format,
+                2,
+                None,
+                None,
+            )
+            .await,
+            Err(Error::StatusUpdatesFailed(1))
+        );
 
-    let log_dir_assets = log_dir.join("assets/");
-    let log_dir_metadata = log_dir.join("metadata/");
-    let log_dir_metadata_string = log_dir_metadata.display().to_string();
+        assert_eq!(
+            arweave.read_bundle_status(current_path).await?.status,
+            StatusCode::NotFound
+        );
+        assert_eq!(
+            serde_json::from_str::<Value>(&fs::read_to_string(newer_path).await?)?,
+            newer
+        );
+        assert_eq!(
+            fs::read_to_string(PathBuf::from(&log_dir).join(FAILED_UPDATES_FILE)).await?,
+            format!("{}\n", Base64(vec![3; 32]))
+        );
+        Ok(())
+    }
 
-    try_join(
-        fs::create_dir_all(&log_dir_assets),
-        fs::create_dir_all(&log_dir_metadata),
-    )
-    .await?;
+    #[tokio::test]
+    async fn test_update_statuses_skips_confirmed() -> Result<(), Error> {
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let log_dir = format!("{}/", temp_dir.0.display());
+        let (base_url, receiver) = mock_server::serve(|_| ("404 Not Found", String::new()));
+        let arweave = Arweave {
+            base_url,
+            ..Arweave::default()
+        };
 
-    // Upload images
-    println!("\n\nUploading assets...\n");
-    if let Some(sol_keypair_path) = sol_keypair_path.clone() {
-        command_upload_bundles_with_sol(
+        let confirmed = |id: u8, confirms: u64| BundleStatus {
+            id: Base64(vec![id; 32]),
+            raw_status: Some(RawStatus {
+                block_height: 1,
+                block_indep_hash: Base64(vec![1; 48]),
+                number_of_confirmations: confirms,
+                confirmations_source: Default::default(),
+            }),
+            ..confirmed_bundle_status()
+        };
+        let pending = BundleStatus {
+            id: Base64(vec![6; 32]),
+            status: StatusCode::Pending,
+            ..confirmed_bundle_status()
+        };
+        for status in [confirmed(4, 30), confirmed(5, 10), pending] {
+            fs::write(
+                format!("{}{}.json", log_dir, status.id),
+                to_status_json(&status, false)?,
+            )
+            .await?;
+        }
+
+        command_update_bundle_statuses(
             &arweave,
-            path_chunks,
-            Some(log_dir_assets.clone()),
+            PathBuf::from(&log_dir),
+            &OutputFormat::Display,
+            2,
             None,
-            reward_mult,
-            output_format,
-            buffer,
-            sol_keypair_path,
+            Some(25),
         )
         .await?;
-    } else {
-        command_upload_bundles(
+        let mut paths: Vec<String> = receiver.try_iter().map(|r| r.path).collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            [
+                format!("/tx/{}/status", Base64(vec![5; 32])),
+                format!("/tx/{}/status", Base64(vec![6; 32])),
+            ]
+        );
+        let skipped = arweave
+            .read_bundle_status(PathBuf::from(format!(
+                "{}{}.json",
+                log_dir,
+                Base64(vec![4; 32])
+            )))
+            .await?;
+        assert_eq!(skipped.status, StatusCode::Confirmed);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_update_statuses_continues_after_failure() -> Result<(), Error> {
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let log_dir = format!("{}/", temp_dir.0.display());
+        let (base_url, receiver) = mock_server::serve(|_| ("404 Not Found", String::new()));
+        let arweave = Arweave {
+            base_url,
+            ..Arweave::default()
+        };
+
+        for id in [7, 9] {
+            let status = BundleStatus {
+                id: Base64(vec![id; 32]),
+                ..BundleStatus::default()
+            };
+            fs::write(
+                format!("{}{}.json", log_dir, status.id),
+                to_status_json(&status, false)?,
+            )
+            .await?;
+        }
+        let corrupt_path = PathBuf::from(format!("{}{}.json", log_dir, Base64(vec![8; 32])));
+        fs::write(&corrupt_path, "{\"id\": ").await?;
+
+        let result = command_update_bundle_statuses(
             &arweave,
-            path_chunks,
-            Some(log_dir_assets.clone()),
+            PathBuf::from(&log_dir),
+            &OutputFormat::Display,
+            1,
             None,
-            reward_mult,
-            output_format,
-            buffer,
+            None,
+        )
+        .await;
+        assert_matches!(result, Err(Error::StatusUpdatesFailed(1)));
+        assert_eq!(receiver.try_iter().count(), 2);
+        // The bundle is recorded by its id, since its file paths can't be read.
+        assert_eq!(
+            fs::read_to_string(temp_dir.0.join(FAILED_UPDATES_FILE)).await?,
+            format!("{}\n", Base64(vec![8; 32]))
+        );
+        assert!(!temp_dir.0.join(FAILED_PATHS_FILE).exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bundle_file_paths() -> Result<(), Error> {
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let status = BundleStatus {
+            id: Base64(vec![7; 32]),
+            file_paths: json!({"a.png": {}, "b.png": {}}),
+            ..BundleStatus::default()
+        };
+        let status_path = temp_dir.0.join(format!("{}.json", status.id));
+        fs::write(&status_path, to_status_json(&status, false)?).await?;
+        let missing_path = temp_dir.0.join(format!("{}.json", Base64(vec![8; 32])));
+
+        assert_eq!(
+            bundle_file_paths(&[status_path, missing_path]).await,
+            vec![
+                PathBuf::from("a.png"),
+                PathBuf::from("b.png"),
+                PathBuf::from(Base64(vec![8; 32]).to_string())
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_update_nft_statuses_assets_only() -> Result<(), Error> {
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let log_dir = format!("{}/", temp_dir.0.display());
+        let log_dir_assets = temp_dir.0.join("assets/");
+        fs::create_dir(&log_dir_assets).await?;
+        let (base_url, receiver) = mock_server::serve(|_| ("404 Not Found", String::new()));
+        let arweave = Arweave {
+            base_url,
+            ..Arweave::default()
+        };
+
+        let status = BundleStatus {
+            id: Base64(vec![7; 32]),
+            ..BundleStatus::default()
+        };
+        fs::write(
+            log_dir_assets.join(format!("{}.json", status.id)),
+            to_status_json(&status, false)?,
+        )
+        .await?;
+        assert_eq!(get_manifest_id_from_log_dir(&log_dir_assets), None);
+        let manifest_id = Base64(vec![8; 32]).to_string();
+        fs::write(
+            log_dir_assets.join(format!("manifest_index_{}.json", manifest_id)),
+            "{}",
         )
         .await?;
+        fs::write(
+            log_dir_assets.join(format!("manifest_{}.json", manifest_id)),
+            "{}",
+        )
+        .await?;
+        assert_eq!(
+            get_manifest_id_from_log_dir(&log_dir_assets),
+            Some(manifest_id.clone())
+        );
+        assert_eq!(
+            get_manifest_id_from_log_dir(&temp_dir.0.join("metadata/")),
+            None
+        );
+
+        command_update_nft_statuses(&arweave, &log_dir, &OutputFormat::Display, 1).await?;
+        let mut paths: Vec<String> = receiver.try_iter().map(|r| r.path).collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            [
+                format!("/tx/{}/status", status.id),
+                format!("/tx/{}/status", manifest_id),
+            ]
+        );
+        Ok(())
     }
 
-    // Upload manifest
-    println!("\n\nUploading manifest for images...\n");
-    command_upload_manifest(
-        &arweave,
-        &log_dir_assets.display().to_string(),
-        reward_mult,
-        sol_keypair_path.clone().map(|s| s.display().to_string()),
-    )
-    .await?;
+    #[tokio::test]
+    async fn test_send_index_hint() {
+        let arweave = Arweave::default();
+        let status = confirmed_bundle_status();
+        let (url, receiver) = mock_endpoint("200 OK");
 
-    let asset_manifest_path = glob(&format!(
-        "{}manifest*.json",
-        &log_dir_assets.display().to_string()
-    ))
-    .unwrap()
-    .filter_map(Result::ok)
-    .nth(0)
-    .unwrap();
-
-    // Update metadata with links to uploaded images.
-    // Assumes simple case of single image file - updates image link accordingly.
-    println!("\n\nUpdating metadata with links from manifest...\n");
-    command_update_metadata(
-        &arweave,
-        paths_vec.clone().into_iter(),
-        asset_manifest_path,
-        link_file,
-        true,
-        false,
-    )
-    .await?;
+        // Already confirmed bundles and bundles that aren't confirmed yet don't get hints.
+        assert!(!send_index_hint(&arweave, &status, Some(&StatusCode::Confirmed), &url).await);
+        let pending = BundleStatus {
+            status: StatusCode::Pending,
+            ..status.clone()
+        };
+        assert!(!send_index_hint(&arweave, &pending, None, &url).await);
 
-    // Upload metadata.
-    println!("\n\nUploading updated metadata files...\n");
-    if let Some(sol_keypair_path) = sol_keypair_path.clone() {
-        command_upload_bundles_with_sol(
+        assert!(send_index_hint(&arweave, &status, Some(&StatusCode::Pending), &url).await);
+        let request = receiver.recv().unwrap();
+        assert_eq!(
+            (request.method.as_str(), request.path.as_str()),
+            ("POST", "/hints")
+        );
+        assert_eq!(
+            serde_json::from_slice::<Value>(&request.body).unwrap(),
+            json!({
+                "bundle_id": Base64(vec![1; 32]).to_string(),
+                "item_ids": [Base64(vec![2; 32]).to_string(), Base64(vec![3; 32]).to_string()],
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_index_hint_failure_not_fatal() {
+        let arweave = Arweave::default();
+        let status = confirmed_bundle_status();
+
+        let (url, receiver) = mock_endpoint("500 Internal Server Error");
+        assert_matches!(
+            arweave.post_bundle_index_hint(&status, &url).await,
+            Err(Error::ArweaveNetworkError(_))
+        );
+        assert!(receiver.recv().is_ok());
+
+        let (url, _) = mock_endpoint("500 Internal Server Error");
+        assert!(!send_index_hint(&arweave, &status, None, &url).await);
+
+        // Unreachable endpoint.
+        let url = Url::from_str("http://127.0.0.1:1/hints").unwrap();
+        assert!(!send_index_hint(&arweave, &status, None, &url).await);
+    }
+
+    #[tokio::test]
+    async fn test_failed_chunk_keeps_paths() -> Result<(), Error> {
+        let (base_url, _) = mock_server::serve(|path| match path {
+            "/tx_anchor" => ("200 OK", "LCwsLCwsLA".to_string()),
+            p if p.starts_with("/price/") => ("200 OK", "0".to_string()),
+            "/tx" => ("200 OK", String::new()),
+            _ => ("404 Not Found", String::new()),
+        });
+        let arweave =
+            Arweave::from_keypair_path(PathBuf::from("tests/fixtures/test_key0.json"), base_url)
+                .await?;
+
+        let failed_chunk = vec![
+            PathBuf::from("tests/fixtures/1.png"),
+            PathBuf::from("tests/fixtures/missing.png"),
+        ];
+        let path_chunks = vec![
+            PathsChunk(vec![PathBuf::from("tests/fixtures/0.png")], 0),
+            PathsChunk(failed_chunk.clone(), 0),
+        ];
+        let results: Vec<_> = upload_bundles_stream(
             &arweave,
-            metadata_path_chunks,
-            Some(log_dir_metadata.clone()),
+            path_chunks,
+            Vec::new(),
             None,
-            reward_mult,
-            output_format,
-            buffer,
-            sol_keypair_path,
+            (0, 0),
+            1,
+            RetryPolicy::default(),
         )
-        .await?;
-    } else {
-        command_upload_bundles(
+        .collect()
+        .await;
+
+        assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1);
+        let failed: Vec<PathBuf> = results
+            .into_iter()
+            .filter_map(Result::err)
+            .flat_map(|e| e.file_paths)
+            .collect();
+        assert_eq!(failed, failed_chunk);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_upload_metrics() -> Result<(), Error> {
+        let tx_posts = AtomicUsize::new(0);
+        let (base_url, requests) = mock_server::serve(move |path| match path {
+            "/tx_anchor" => ("200 OK", "LCwsLCwsLA".to_string()),
+            p if p.starts_with("/price/") => ("200 OK", "0".to_string()),
+            "/tx" if tx_posts.fetch_add(1, Ordering::SeqCst) == 0 => {
+                ("500 Internal Server Error", String::new())
+            }
+            "/tx" => ("200 OK", String::new()),
+            _ => ("404 Not Found", String::new()),
+        });
+        let arweave =
+            Arweave::from_keypair_path(PathBuf::from("tests/fixtures/test_key0.json"), base_url)
+                .await?;
+        let path_chunks = vec![
+            PathsChunk(vec![PathBuf::from("tests/fixtures/0.png")], 0),
+            PathsChunk(
+                vec![
+                    PathBuf::from("tests/fixtures/1.png"),
+                    PathBuf::from("tests/fixtures/2.png"),
+                ],
+                0,
+            ),
+        ];
+
+        let mut metrics = Metrics::start(
+            arweave.post_retries.load(Ordering::Relaxed),
+            &arweave.connection_stats,
+        );
+        let statuses: Vec<BundleStatus> = upload_bundles_stream(
             &arweave,
-            metadata_path_chunks,
-            Some(log_dir_metadata.clone()),
+            path_chunks,
+            Vec::new(),
             None,
-            reward_mult,
-            output_format,
-            buffer,
+            (0, 0),
+            1,
+            RetryPolicy::default(),
         )
-        .await?;
-    }
+        .map(|r| r.unwrap())
+        .collect()
+        .await;
+        statuses.iter().for_each(|s| metrics.add(s));
+        metrics.finish(
+            arweave.post_retries.load(Ordering::Relaxed),
+            &arweave.connection_stats,
+        );
 
-    println!("\n\nUploading manifest for metadata...\n");
-    command_upload_manifest(
-        &arweave,
-        &log_dir_metadata_string,
-        reward_mult,
-        sol_keypair_path.map(|s| s.display().to_string()),
-    )
-    .await?;
-    let metadata_manifest_path = glob(&format!("{}manifest*.json", &log_dir_metadata_string))
-        .unwrap()
-        .filter_map(Result::ok)
-        .nth(0)
-        .unwrap();
+        let mut files_bytes = 0;
+        for i in 0..3 {
+            files_bytes += fs::metadata(format!("tests/fixtures/{}.png", i))
+                .await?
+                .len();
+        }
+        assert_eq!((metrics.bundles, metrics.items, metrics.retries), (2, 3, 1));
+        assert!(metrics.bytes_posted > files_bytes);
+        assert_eq!(
+            metrics.bytes_posted,
+            statuses.iter().map(|s| s.post_metrics.bytes).sum::<u64>()
+        );
 
-    command_write_metaplex_items(
-        &arweave,
-        paths_vec
-            .clone()
-            .into_iter()
-            .map(|p| p.with_extension("json")),
-        metadata_manifest_path.clone(),
-        false,
-    )
-    .await?;
+        // The retry sleeps for a second while posting the first bundle.
+        assert!(metrics.avg_post_latency_secs * 2.0 >= 1.0);
+        assert!(metrics.avg_post_latency_secs * 2.0 <= metrics.elapsed_secs);
+        let bytes_from_rate = metrics.mb_per_sec * 1_000_000.0 * metrics.elapsed_secs;
+        assert!((bytes_from_rate - metrics.bytes_posted as f64).abs() < 1.0);
+        assert!((metrics.bundles_per_min * metrics.elapsed_secs / 60.0 - 2.0).abs() < 1e-9);
+        assert!(metrics.connections_opened >= 1);
+        assert!(metrics.avg_connect_secs > 0.0);
+        assert_eq!(
+            metrics.connections_opened + metrics.connections_reused,
+            requests.try_iter().count() as u64
+        );
 
-    println!(
-        "\n\nUpload complete! Links to your uploaded metadata files can be found in `{}`",
-        metadata_manifest_path
-            .display()
-            .to_string()
-            .replace("manifest_", "metaplex_items_")
-    );
+        let json: Value =
+            serde_json::from_str(&metrics.metrics_string(&OutputFormat::JsonCompact))?;
+        assert_eq!(json["metrics"]["bytes_posted"], metrics.bytes_posted);
+        Ok(())
+    }
 
-    println!(
-        "Run `arloader update-nft-status {}` to confirm all transactions.",
-        log_dir.display().to_string()
-    );
-    Ok(())
-}
+    #[tokio::test]
+    async fn test_report_failed_paths() -> Result<(), Error> {
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let failed_paths: Vec<PathBuf> = (0..MAX_PRINTED_FAILED_PATHS + 1)
+            .map(|i| PathBuf::from(format!("{}.png", i)))
+            .collect();
 
-/// Creates and uploads manifest from directory of bundle statuses.
-pub async fn command_upload_manifest(
-    arweave: &Arweave,
-    log_dir: &str,
-    reward_mult: f32,
-    sol_keypair_path: Option<String>,
-) -> CommandResult {
-    let solana_url = SOLANA_MAIN_URL.parse::<Url>()?;
-    let sol_ar_url = SOL_AR_BASE_URL.parse::<Url>()?.join("sol")?;
-    let from_keypair = sol_keypair_path.map(|s| keypair::read_keypair_file(s).unwrap());
+        assert_eq!(
+            report_failed_paths(&[], Some(temp_dir.0.as_path()), &OutputFormat::Display).await?,
+            None
+        );
+        assert_eq!(
+            report_failed_paths(
+                &failed_paths[1..],
+                Some(temp_dir.0.as_path()),
+                &OutputFormat::Display
+            )
+            .await?,
+            None
+        );
+        assert_eq!(
+            report_failed_paths(&failed_paths, None, &OutputFormat::Display).await?,
+            None
+        );
 
-    let price_terms = arweave.get_price_terms(reward_mult).await?;
-    let output = arweave
-        .upload_manifest_from_bundle_log_dir(
-            log_dir,
-            price_terms,
-            solana_url,
-            sol_ar_url,
-            from_keypair,
+        let file_path = temp_dir.0.join(FAILED_PATHS_FILE);
+        assert_eq!(
+            report_failed_paths(
+                &failed_paths,
+                Some(temp_dir.0.as_path()),
+                &OutputFormat::Display
+            )
+            .await?,
+            Some(file_path.clone())
+        );
+        let written: Vec<PathBuf> = fs::read_to_string(file_path)
+            .await?
+            .lines()
+            .map(PathBuf::from)
+            .collect();
+        assert_eq!(written, failed_paths);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_max_runtime_partial_upload() {
+        let paths_vec: Vec<PathBuf> = (0..10)
+            .map(|i| PathBuf::from(format!("{}.png", i)))
+            .collect();
+        let token = CancelToken::default();
+        assert!(check_max_runtime(
+            &token,
+            &Deadline::default(),
+            paths_vec.clone(),
+            &OutputFormat::Display
         )
-        .await?;
+        .is_ok());
 
-    println!("{}", output);
-    Ok(())
-}
+        // Mock upload that takes 300 ms per file, uploading two files at a time.
+        let deadline = arm_deadline(&token, Some(1));
+        let mut stream = stream::iter(token.wrap(paths_vec.clone().into_iter()))
+            .map(|p| async move {
+                sleep(Duration::from_millis(300)).await;
+                p
+            })
+            .buffer_unordered(2);
 
-/// Uploads files to Arweave, paying with SOL.
-pub async fn command_upload_with_sol<IP>(
-    arweave: &Arweave,
-    paths_iter: IP,
-    log_dir: Option<PathBuf>,
-    tags: Option<Vec<Tag<Base64>>>,
-    reward_mult: f32,
-    output_format: &OutputFormat,
-    buffer: usize,
-    sol_keypair_path: PathBuf,
-) -> CommandResult
-where
-    IP: Iterator<Item = PathBuf> + Send + Sync,
-{
-    let solana_url = SOLANA_MAIN_URL.parse::<Url>()?;
-    let sol_ar_url = SOL_AR_BASE_URL.parse::<Url>()?.join("sol")?;
-    let from_keypair = keypair::read_keypair_file(sol_keypair_path)?;
+        let mut uploaded = Vec::new();
+        while let Some(p) = next_before_deadline(&mut stream, &deadline).await {
+            uploaded.push(p);
+        }
 
-    let price_terms = arweave.get_price_terms(reward_mult).await?;
+        assert!(token.is_cancelled());
+        assert!(uploaded.len() > 0 && uploaded.len() < paths_vec.len());
+        assert_eq!(uploaded.len(), token.started());
 
-    let mut stream = upload_files_with_sol_stream(
-        arweave,
-        paths_iter,
-        tags,
-        log_dir.clone(),
-        None,
-        price_terms,
-        solana_url,
-        sol_ar_url,
-        &from_keypair,
-        buffer,
-    );
+        let unfinished = unfinished_paths(&paths_vec, &uploaded.iter().cloned().collect());
+        assert_matches!(
+            check_max_runtime(&token, &deadline, unfinished, &OutputFormat::Display),
+            Err(Error::MaxRuntimeExceeded(n)) if n == paths_vec.len() - uploaded.len()
+        );
+    }
 
-    let mut counter = 0;
-    while let Some(result) = stream.next().await {
-        match result {
-            Ok(status) => {
-                if counter == 0 {
-                    if let Some(log_dir) = &log_dir {
-                        println!("Logging statuses to {}", &log_dir.display());
-                    }
-                    println!("{}", status.header_string(&output_format));
-                }
-                print!("{}", output_format.formatted_string(&status));
-                counter += 1;
-            }
-            Err(e) => println!("{:#?}", e),
+    #[tokio::test]
+    async fn test_max_runtime_abandons_uploads_in_flight() {
+        let paths_vec: Vec<PathBuf> = (0..4)
+            .map(|i| PathBuf::from(format!("{}.png", i)))
+            .collect();
+        let token = CancelToken::default();
+        let deadline = Deadline {
+            at: Some(Instant::now() + Duration::from_millis(500)),
+            cancel_task: None,
+        };
+
+        // The first file uploads, the rest are started but don't finish before the deadline.
+        let mut stream = stream::iter(token.wrap(paths_vec.clone().into_iter()))
+            .enumerate()
+            .map(|(i, p)| async move {
+                sleep(Duration::from_secs(if i == 0 { 0 } else { 60 })).await;
+                p
+            })
+            .buffer_unordered(4);
+        let mut uploaded = HashSet::new();
+        while let Some(p) = next_before_deadline(&mut stream, &deadline).await {
+            uploaded.insert(p);
         }
-    }
+        assert!(deadline.abandoned());
+        token.cancel();
 
-    if counter == 0 {
-        println!("<FILE_PATHS> didn't match any files.");
-    } else {
-        println!(
-            "Uploaded {} files. Run `arloader update-status {} --file-paths <FILE_PATHS>` to confirm transaction(s).",
-            counter,
-            &log_dir.unwrap_or(PathBuf::from("")).display(),
+        let unfinished = unfinished_paths(&paths_vec, &uploaded);
+        assert_eq!(unfinished, paths_vec[1..].to_vec());
+        assert_matches!(
+            check_max_runtime(&token, &deadline, unfinished, &OutputFormat::Display),
+            Err(Error::MaxRuntimeExceeded(3))
         );
     }
 
-    Ok(())
-}
+    #[tokio::test]
+    async fn test_deadline_aborts_cancel_task() {
+        let token = CancelToken::default();
+        let deadline = arm_deadline(&token, Some(1));
+        let task = deadline.cancel_task.as_ref().unwrap().abort_handle();
+        drop(deadline);
+        sleep(Duration::from_millis(10)).await;
+        assert!(task.is_finished());
+        assert!(!token.is_cancelled());
+    }
 
-/// Gets balance for provided wallet address.
-pub async fn command_wallet_balance(
-    arweave: &Arweave,
-    wallet_address: Option<String>,
-) -> CommandResult {
-    let mb = u64::pow(1024, 2);
-    let result = tokio::join!(
-        arweave.get_wallet_balance(wallet_address),
-        arweave.get_price(&mb)
-    );
-    let balance = result.0?;
-    let (winstons_per_kb, usd_per_ar, _) = result.1?;
+    struct FixedMemory(Option<u64>);
 
-    let balance_usd = &balance.to_f32().unwrap() / &WINSTONS_PER_AR.to_f32().unwrap()
-        * &usd_per_ar.to_f32().unwrap()
-        / 100_f32;
+    impl MemoryProbe for FixedMemory {
+        fn available_memory(&self) -> Option<u64> {
+            self.0
+        }
+    }
 
-    let usd_per_kb = (&winstons_per_kb * &usd_per_ar).to_f32().unwrap() / 1e14_f32;
+    #[test]
+    fn test_check_upload_memory() {
+        assert_eq!(estimate_upload_memory(200_000_000, 10), 5_000_000_000);
+        assert_eq!(estimate_upload_memory(10_000_000, 5), 125_000_000);
 
-    println!(
-            "Wallet balance is {} {units} (${balance_usd:.2} at ${ar_price:.2} USD per AR). At the current price of {price} {units} per MB (${usd_price:.4}), you can upload {max} MB of data.",
-            &balance,
-            units = arweave.units,
-            max = &balance / &winstons_per_kb,
-            price = &winstons_per_kb,
-            balance_usd = balance_usd,
-            ar_price = &usd_per_ar.to_f32().unwrap()
-            / 100_f32,
-            usd_price = usd_per_kb
-    );
-    Ok(())
-}
+        let probe = FixedMemory(Some(4_000_000_000));
+        assert!(check_upload_memory(&probe, 10_000_000, 5, false).is_ok());
+        assert_matches!(
+            check_upload_memory(&probe, 200_000_000, 10, false),
+            Err(Error::InsufficientMemory {
+                required: 5_000_000_000,
+                available: 4_000_000_000
+            })
+        );
+        assert!(check_upload_memory(&probe, 200_000_000, 10, true).is_ok());
+        assert!(check_upload_memory(&FixedMemory(None), 200_000_000, 10, false).is_ok());
+    }
 
-/// Writes metaplex link items used to create NFTs with candy machine program.
-pub async fn command_write_metaplex_items<IP>(
-    arweave: &Arweave,
-    paths_iter: IP,
-    manifest_path: PathBuf,
-    link_file: bool,
-) -> CommandResult
-where
-    IP: Iterator<Item = PathBuf> + Send + Sync,
-{
-    let metaplex_items_path = arweave
-        .write_metaplex_items(paths_iter, manifest_path, link_file)
-        .await?;
+    #[tokio::test]
+    async fn test_ensure_log_dir() -> Result<(), Error> {
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let log_dir = temp_dir.0.join("out/statuses");
+        ensure_log_dir(&log_dir).await?;
+        assert!(log_dir.is_dir());
+        ensure_log_dir(&log_dir).await?;
 
-    println!(
-        "Successfully wrote metaplex items for metadata files to {}.",
-        metaplex_items_path.display().to_string()
-    );
-    Ok(())
+        let file_path = temp_dir.0.join("file");
+        fs::write(&file_path, "").await?;
+        assert_matches!(
+            ensure_log_dir(&file_path).await,
+            Err(Error::LogDirIsFile(p)) if p == file_path
+        );
+        Ok(())
+    }
 }
-
-/// Gets manifest transaction id from first manifest file in a log directory.
-pub fn get_manifest_id_from_log_dir(log_dir: &PathBuf) -> String {
-    glob(&format!("{}manifest*.json", log_dir.display().to_string()))
-        .unwrap()
-        .filter_map(Result::ok)
-        .nth(0)
-        .unwrap()
-        .display()
-        .to_string()
-        .split(".")
-        .next()
-        .unwrap()
-        .split("manifest_")
-        .nth(1)
-        .unwrap()
-        .to_string()
-}
\ No newline at end of file
@@ -1,9 +1,11 @@
 //! Functionality for creating and verifying signatures and hashing.
 
 use crate::{
+    bundle::SignatureType,
     error::Error,
     transaction::{Base64, DeepHashItem},
 };
+use ::rand::{rngs::OsRng, CryptoRng, RngCore};
 use jsonwebkey::JsonWebKey;
 use log::debug;
 use ring::{
@@ -11,27 +13,231 @@ use ring::{
     rand::{self, SecureRandom},
     signature::{self, KeyPair, RsaKeyPair},
 };
+use rsa::{BigUint, PublicKeyParts, RsaPrivateKey};
+use serde_json::json;
+use solana_sdk::signer::{keypair::Keypair, Signer};
 use std::fs as fsSync;
-use std::path::PathBuf;
+use std::io::{ErrorKind, Write};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use tokio::fs;
 
-/// Struct for for crypto methods.
+/// Environment variable that can be set to a JWK json string to use in place of the default
+/// keypair.
+pub const AR_DEFAULT_KEYPAIR_JSON: &str = "AR_DEFAULT_KEYPAIR_JSON";
+
+/// Size in bits of generated keypairs, matching Arweave wallets.
+pub const KEYPAIR_BITS: usize = 4096;
+
+#[cfg(test)]
+thread_local! {
+    /// Number of signatures made on the current thread, used in tests to check when signing is
+    /// skipped.
+    pub(crate) static SIGN_COUNT: std::cell::Cell<usize> = std::cell::Cell::new(0);
+}
+
+/// Hashing functions, including deep hash, that don't require a keypair.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Hasher;
+
+impl Hasher {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn hash_sha256(&self, message: &[u8]) -> Result<[u8; 32], Error> {
+        let mut context = Context::new(&SHA256);
+        context.update(message);
+        let mut result: [u8; 32] = [0; 32];
+        result.copy_from_slice(context.finish().as_ref());
+        Ok(result)
+    }
+
+    fn hash_sha384(&self, message: &[u8]) -> Result<[u8; 48], Error> {
+        let mut context = Context::new(&SHA384);
+        context.update(message);
+        let mut result: [u8; 48] = [0; 48];
+        result.copy_from_slice(context.finish().as_ref());
+        Ok(result)
+    }
+
+    /// Returns a SHA256 hash of the the concatenated SHA256 hashes of a vector of messages.
+    pub fn hash_all_sha256(&self, messages: Vec<&[u8]>) -> Result<[u8; 32], Error> {
+        let hash: Vec<u8> = messages
+            .into_iter()
+            .map(|m| self.hash_sha256(m).unwrap())
+            .into_iter()
+            .flatten()
+            .collect();
+        let hash = self.hash_sha256(&hash)?;
+        Ok(hash)
+    }
+
+    /// Returns a SHA384 hash of the the concatenated SHA384 hashes of a vector messages.
+    fn hash_all_sha384(&self, messages: Vec<&[u8]>) -> Result<[u8; 48], Error> {
+        let hash: Vec<u8> = messages
+            .into_iter()
+            .map(|m| self.hash_sha384(m).unwrap())
+            .into_iter()
+            .flatten()
+            .collect();
+        let hash = self.hash_sha384(&hash)?;
+        Ok(hash)
+    }
+
+    /// Concatenates two `[u8; 48]` arrays, returning a `[u8; 96]` array.
+    fn concat_u8_48(&self, left: [u8; 48], right: [u8; 48]) -> Result<[u8; 96], Error> {
+        let mut iter = left.into_iter().chain(right);
+        let result = [(); 96].map(|_| iter.next().unwrap());
+        Ok(result)
+    }
+
+    /// Calculates data root of transaction in accordance with implementation in [arweave-js](https://github.com/ArweaveTeam/arweave-js/blob/master/src/common/lib/deepHash.ts).
+    /// [`DeepHashItem`] is a recursive Enum that allows the function to be applied to
+    /// nested [`Vec<u8>`] of arbitrary depth.
+    pub fn deep_hash(&self, deep_hash_item: DeepHashItem) -> Result<[u8; 48], Error> {
+        let hash = match deep_hash_item {
+            DeepHashItem::Blob(blob) => {
+                let blob_tag = format!("blob{}", blob.len());
+                self.hash_all_sha384(vec![blob_tag.as_bytes(), &blob])?
+            }
+            DeepHashItem::List(list) => {
+                let list_tag = format!("list{}", list.len());
+                let mut hash = self.hash_sha384(list_tag.as_bytes())?;
+
+                for child in list.into_iter() {
+                    let child_hash = self.deep_hash(child)?;
+                    hash = self.hash_sha384(&self.concat_u8_48(hash, child_hash)?)?;
+                }
+                hash
+            }
+        };
+        Ok(hash)
+    }
+
+    /// Verifies a signature against the public key with modulus `owner`, for example the owner of a
+    /// [`crate::bundle::DataItem`], using the public exponent of all Arweave keys.
+    pub fn verify_owner(
+        &self,
+        owner: &[u8],
+        signature: &[u8],
+        message: &[u8],
+    ) -> Result<(), Error> {
+        let public_key = signature::RsaPublicKeyComponents {
+            n: owner,
+            e: &[1, 0, 1][..],
+        };
+        public_key.verify(&signature::RSA_PSS_2048_8192_SHA256, message, signature)?;
+        Ok(())
+    }
+
+    /// Verifies a signature of a data item with `signature_type` against its `owner`, the modulus
+    /// for [`SignatureType::Arweave`] or the ed25519 public key for [`SignatureType::Ed25519`].
+    pub fn verify_signature(
+        &self,
+        signature_type: SignatureType,
+        owner: &[u8],
+        signature: &[u8],
+        message: &[u8],
+    ) -> Result<(), Error> {
+        match signature_type {
+            SignatureType::Arweave => self.verify_owner(owner, signature, message),
+            SignatureType::Ed25519 => {
+                signature::UnparsedPublicKey::new(&signature::ED25519, owner)
+                    .verify(message, signature)?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Signs `message` with a Solana `keypair`, returning the 64 byte ed25519 signature used for
+/// data items with [`SignatureType::Ed25519`].
+pub fn sign_ed25519(keypair: &Keypair, message: &[u8]) -> Vec<u8> {
+    #[cfg(test)]
+    SIGN_COUNT.with(|c| c.set(c.get() + 1));
+    keypair.sign_message(message).as_ref().to_vec()
+}
+
+/// Struct for for crypto methods. Hashing methods are provided by [`Hasher`], through
+/// [`Deref`].
 pub struct Provider {
     pub keypair: RsaKeyPair,
     pub sr: rand::SystemRandom,
+    pub hasher: Hasher,
+}
+
+impl Deref for Provider {
+    type Target = Hasher;
+    fn deref(&self) -> &Self::Target {
+        &self.hasher
+    }
 }
 
+/// Same as [`Provider::from_env`], panicking if `AR_DEFAULT_KEYPAIR_JSON` isn't a valid keypair.
 impl Default for Provider {
     fn default() -> Self {
-        let jwk_parsed: JsonWebKey = DEFAULT_KEYPAIR.parse().unwrap();
-        Self {
-            keypair: signature::RsaKeyPair::from_pkcs8(&jwk_parsed.key.as_ref().to_der()).unwrap(),
-            sr: rand::SystemRandom::new(),
-        }
+        Self::from_env().expect("invalid keypair in AR_DEFAULT_KEYPAIR_JSON")
     }
 }
 
 impl Provider {
+    /// Generates a new [`KEYPAIR_BITS`] keypair from `rng`, returned along with its JWK json
+    /// string, which has the same shape as Arweave wallet files and can be written with
+    /// [`write_keypair`].
+    pub fn generate<R: CryptoRng + RngCore>(rng: &mut R) -> Result<(Provider, String), Error> {
+        Self::generate_with_bits(rng, KEYPAIR_BITS)
+    }
+    fn generate_with_bits<R: CryptoRng + RngCore>(
+        rng: &mut R,
+        bits: usize,
+    ) -> Result<(Provider, String), Error> {
+        let jwk = keypair_jwk_from_rng(rng, bits)?;
+        Ok((Self::from_jwk_str(&jwk)?, jwk))
+    }
+    /// Parses a [`JsonWebKey`] json string and stores it as a [`signature::RsaKeyPair`].
+    pub fn from_jwk_str(jwk: &str) -> Result<Provider, Error> {
+        let jwk_parsed = jwk
+            .parse::<JsonWebKey>()
+            .map_err(|e| Error::InvalidKeypair(e.to_string()))?;
+        Ok(Self {
+            keypair: signature::RsaKeyPair::from_pkcs8(&jwk_parsed.key.as_ref().to_der())?,
+            sr: rand::SystemRandom::new(),
+            hasher: Hasher::new(),
+        })
+    }
+    /// Chooses a default keypair in order of precedence from `env_json`, typically the value of
+    /// `AR_DEFAULT_KEYPAIR_JSON`, the keypair at `machine_keypair_path`, generated and written
+    /// there on first use, and finally the keypair embedded in the crate.
+    pub fn from_default_sources(
+        env_json: Option<String>,
+        machine_keypair_path: Option<PathBuf>,
+    ) -> Result<Provider, Error> {
+        if let Some(jwk) = env_json {
+            Self::from_jwk_str(&jwk)
+        } else if let Some(path) = machine_keypair_path {
+            Self::from_jwk_str(&load_or_generate_keypair(&path, KEYPAIR_BITS)?)
+        } else {
+            Self::from_jwk_str(DEFAULT_KEYPAIR)
+        }
+    }
+    /// Uses the keypair in `AR_DEFAULT_KEYPAIR_JSON` if set, otherwise the embedded default
+    /// keypair, returning an error instead of panicking if the environment variable isn't a valid
+    /// keypair.
+    /// See [`Provider::from_default_sources`].
+    pub fn from_env() -> Result<Provider, Error> {
+        Self::from_default_sources(std::env::var(AR_DEFAULT_KEYPAIR_JSON).ok(), None)
+    }
+    /// Keypair used with `--ar-default-keypair`. Uses `AR_DEFAULT_KEYPAIR_JSON` if set, otherwise a
+    /// keypair unique to this machine stored at [`machine_keypair_path`], which is generated the
+    /// first time it is needed.
+    pub fn machine_default() -> Result<Provider, Error> {
+        Self::from_default_sources(
+            std::env::var(AR_DEFAULT_KEYPAIR_JSON).ok(),
+            machine_keypair_path(),
+        )
+    }
     /// Reads a [`JsonWebKey`] from a [`PathBuf`] and stores it as a [`signature::RsaKeyPair`] in
     /// the `keypair` property of [`Provider`] for future use in signing and funding transactions.
     pub async fn from_keypair_path(keypair_path: PathBuf) -> Result<Provider, Error> {
@@ -42,6 +248,7 @@ impl Provider {
         Ok(Self {
             keypair: signature::RsaKeyPair::from_pkcs8(&jwk_parsed.key.as_ref().to_der())?,
             sr: rand::SystemRandom::new(),
+            hasher: Hasher::new(),
         })
     }
     /// Sync version of [`Provider::from_keypair_path`].
@@ -52,6 +259,7 @@ impl Provider {
         Ok(Self {
             keypair: signature::RsaKeyPair::from_pkcs8(&jwk_parsed.key.as_ref().to_der())?,
             sr: rand::SystemRandom::new(),
+            hasher: Hasher::new(),
         })
     }
 
@@ -88,13 +296,12 @@ impl Provider {
     /// # }
     /// ```
     pub fn wallet_address(&self) -> Result<Base64, Error> {
-        let mut context = Context::new(&SHA256);
-        context.update(&self.keypair_modulus()?.0[..]);
-        let wallet_address = Base64(context.finish().as_ref().to_vec());
-        Ok(wallet_address)
+        Ok(address_from_modulus(&self.keypair_modulus()?))
     }
 
     pub fn sign(&self, message: &[u8]) -> Result<Vec<u8>, Error> {
+        #[cfg(test)]
+        SIGN_COUNT.with(|c| c.set(c.get() + 1));
         let rng = rand::SystemRandom::new();
         let mut signature = vec![0; self.keypair.public_modulus_len()];
         self.keypair
@@ -128,80 +335,114 @@ impl Provider {
         Ok(())
     }
 
-    pub fn hash_sha256(&self, message: &[u8]) -> Result<[u8; 32], Error> {
-        let mut context = Context::new(&SHA256);
-        context.update(message);
-        let mut result: [u8; 32] = [0; 32];
-        result.copy_from_slice(context.finish().as_ref());
-        Ok(result)
+    pub fn fill_rand(&self, dest: &mut [u8]) -> Result<(), Error> {
+        let rand_bytes = self.sr.fill(dest)?;
+        Ok(rand_bytes)
     }
+}
 
-    fn hash_sha384(&self, message: &[u8]) -> Result<[u8; 48], Error> {
-        let mut context = Context::new(&SHA384);
-        context.update(message);
-        let mut result: [u8; 48] = [0; 48];
-        result.copy_from_slice(context.finish().as_ref());
-        Ok(result)
-    }
+/// Path of the per machine default keypair, `arloader/default-keypair.json` in the user data
+/// directory, e.g. `$XDG_DATA_HOME` on Linux.
+pub fn machine_keypair_path() -> Option<PathBuf> {
+    dirs_next::data_dir().map(|d| d.join("arloader").join("default-keypair.json"))
+}
 
-    /// Returns a SHA256 hash of the the concatenated SHA256 hashes of a vector of messages.
-    pub fn hash_all_sha256(&self, messages: Vec<&[u8]>) -> Result<[u8; 32], Error> {
-        let hash: Vec<u8> = messages
-            .into_iter()
-            .map(|m| self.hash_sha256(m).unwrap())
-            .into_iter()
-            .flatten()
-            .collect();
-        let hash = self.hash_sha256(&hash)?;
-        Ok(hash)
-    }
+/// Calculates the wallet address for `owner`, the modulus of a public key as found in the `owner`
+/// field of transactions and data items, as the SHA256 hash of its bytes.
+pub fn address_from_modulus(owner: &Base64) -> Base64 {
+    let mut context = Context::new(&SHA256);
+    context.update(&owner.0);
+    Base64(context.finish().as_ref().to_vec())
+}
 
-    /// Returns a SHA384 hash of the the concatenated SHA384 hashes of a vector messages.
-    fn hash_all_sha384(&self, messages: Vec<&[u8]>) -> Result<[u8; 48], Error> {
-        let hash: Vec<u8> = messages
-            .into_iter()
-            .map(|m| self.hash_sha384(m).unwrap())
-            .into_iter()
-            .flatten()
-            .collect();
-        let hash = self.hash_sha384(&hash)?;
-        Ok(hash)
-    }
+/// Calculates the wallet address of a JWK json string from its modulus, `n`. Only the public part
+/// of the key is needed.
+pub fn address_from_jwk_str(jwk: &str) -> Result<Base64, Error> {
+    let jwk: serde_json::Value =
+        serde_json::from_str(jwk).map_err(|e| Error::InvalidKeypair(e.to_string()))?;
+    let modulus = jwk["n"]
+        .as_str()
+        .ok_or_else(|| Error::InvalidKeypair("missing modulus `n`".to_string()))?;
+    let modulus = Base64::from_str(modulus).map_err(|e| Error::InvalidKeypair(e.to_string()))?;
+    Ok(address_from_modulus(&modulus))
+}
 
-    /// Concatenates two `[u8; 48]` arrays, returning a `[u8; 96]` array.
-    fn concat_u8_48(&self, left: [u8; 48], right: [u8; 48]) -> Result<[u8; 96], Error> {
-        let mut iter = left.into_iter().chain(right);
-        let result = [(); 96].map(|_| iter.next().unwrap());
-        Ok(result)
+/// Generates a new RSA keypair of `bits` size, returned as a JWK json string.
+pub fn generate_keypair_jwk(bits: usize) -> Result<String, Error> {
+    keypair_jwk_from_rng(&mut OsRng, bits)
+}
+
+fn keypair_jwk_from_rng<R: CryptoRng + RngCore>(rng: &mut R, bits: usize) -> Result<String, Error> {
+    let key = RsaPrivateKey::new(rng, bits).map_err(|e| Error::KeyPairGeneration(e.to_string()))?;
+    let (mut p, mut q) = (&key.primes()[0], &key.primes()[1]);
+    if p < q {
+        std::mem::swap(&mut p, &mut q);
     }
+    let one = BigUint::from(1u32);
+    let two = BigUint::from(2u32);
+    let d = key.d();
+    let dp = d % &(p - &one);
+    let dq = d % &(q - &one);
+    let qi = q.modpow(&(p - &two), p);
 
-    /// Calculates data root of transaction in accordance with implementation in [arweave-js](https://github.com/ArweaveTeam/arweave-js/blob/master/src/common/lib/deepHash.ts).
-    /// [`DeepHashItem`] is a recursive Enum that allows the function to be applied to
-    /// nested [`Vec<u8>`] of arbitrary depth.
-    pub fn deep_hash(&self, deep_hash_item: DeepHashItem) -> Result<[u8; 48], Error> {
-        let hash = match deep_hash_item {
-            DeepHashItem::Blob(blob) => {
-                let blob_tag = format!("blob{}", blob.len());
-                self.hash_all_sha384(vec![blob_tag.as_bytes(), &blob])?
-            }
-            DeepHashItem::List(list) => {
-                let list_tag = format!("list{}", list.len());
-                let mut hash = self.hash_sha384(list_tag.as_bytes())?;
+    let encode = |n: &BigUint| base64::encode_config(n.to_bytes_be(), base64::URL_SAFE_NO_PAD);
+    let jwk = json!({
+        "kty": "RSA",
+        "n": encode(key.n()),
+        "e": encode(key.e()),
+        "d": encode(d),
+        "p": encode(p),
+        "q": encode(q),
+        "dp": encode(&dp),
+        "dq": encode(&dq),
+        "qi": encode(&qi),
+    });
+    Ok(jwk.to_string())
+}
 
-                for child in list.into_iter() {
-                    let child_hash = self.deep_hash(child)?;
-                    hash = self.hash_sha384(&self.concat_u8_48(hash, child_hash)?)?;
-                }
-                hash
-            }
-        };
-        Ok(hash)
+/// Reads the keypair at `path`, generating and writing a new one if it doesn't exist yet. If
+/// another process writes one first, that one is read and used instead.
+fn load_or_generate_keypair(path: &Path, bits: usize) -> Result<String, Error> {
+    match fsSync::read_to_string(path) {
+        Err(e) if e.kind() == ErrorKind::NotFound => {}
+        read => return Ok(read?),
+    }
+    let jwk = generate_keypair_jwk(bits)?;
+    match write_keypair(path, &jwk) {
+        Err(Error::IOError(e)) if e.kind() == ErrorKind::AlreadyExists => {
+            Ok(fsSync::read_to_string(path)?)
+        }
+        written => written.map(|_| jwk),
     }
+}
 
-    pub fn fill_rand(&self, dest: &mut [u8]) -> Result<(), Error> {
-        let rand_bytes = self.sr.fill(dest)?;
-        Ok(rand_bytes)
+/// Writes a JWK json string to a new file at `path`, readable only by the owner on unix, creating
+/// its parent directories. Fails if the file already exists rather than overwriting a wallet.
+///
+/// The keypair is written and synced to a temporary file next to `path` first, which is then
+/// linked to `path`, so that the file at `path` is never left partially written.
+pub fn write_keypair(path: &Path, jwk: &str) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+        fsSync::create_dir_all(parent)?;
     }
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(format!(".{:016x}.tmp", OsRng.next_u64()));
+    let temp_path = path.with_file_name(temp_name);
+
+    let mut options = fsSync::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(&temp_path)?;
+    let written = (|| {
+        file.write_all(jwk.as_bytes())?;
+        file.sync_all()?;
+        // Unlike a rename, linking fails if there already is a file at `path`.
+        fsSync::hard_link(&temp_path, path)
+    })();
+    drop(file);
+    let _ = fsSync::remove_file(&temp_path);
+    Ok(written?)
 }
 
 const DEFAULT_KEYPAIR: &str = r##"{
@@ -218,11 +459,16 @@ const DEFAULT_KEYPAIR: &str = r##"{
 
 #[cfg(test)]
 mod tests {
-    use super::Provider;
+    use super::{
+        address_from_jwk_str, address_from_modulus, load_or_generate_keypair, write_keypair,
+        Provider,
+    };
     use crate::{
+        utils::TempDir,
         Arweave, Error,
         {transaction::Transaction, ToItems},
     };
+    use ::rand::rngs::OsRng;
     use std::path::PathBuf;
     use std::str::FromStr;
     use url::Url;
@@ -255,11 +501,123 @@ mod tests {
     }
 
     #[test]
-    fn test_default_keypair() {
-        let provider = Provider::default();
+    fn test_default_keypair() -> Result<(), Error> {
+        for provider in [Provider::default(), Provider::from_env()?] {
+            assert_eq!(
+                provider.wallet_address()?.to_string(),
+                "jA6UzKJ1cIvL2vUIct7Qf90QhC5b1UttvwknaGGBtjI"
+            );
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_default_sources_precedence() -> Result<(), Error> {
+        let fixture_jwk = std::fs::read_to_string(
+            "tests/fixtures/arweave-key-7eV1qae4qVNqsNChg3Scdi-DpOLJPCogct4ixoq1WNg.json",
+        )?;
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let machine_path = temp_dir.0.join("default-keypair.json");
+
+        let provider = Provider::from_default_sources(None, None)?;
         assert_eq!(
-            provider.wallet_address().unwrap().to_string(),
+            provider.wallet_address()?.to_string(),
             "jA6UzKJ1cIvL2vUIct7Qf90QhC5b1UttvwknaGGBtjI"
         );
+
+        std::fs::write(&machine_path, &fixture_jwk)?;
+        let provider = Provider::from_default_sources(None, Some(machine_path.clone()))?;
+        assert_eq!(
+            provider.wallet_address()?.to_string(),
+            "7eV1qae4qVNqsNChg3Scdi-DpOLJPCogct4ixoq1WNg"
+        );
+
+        std::fs::remove_file(&machine_path)?;
+        let provider =
+            Provider::from_default_sources(Some(fixture_jwk), Some(machine_path.clone()))?;
+        assert_eq!(
+            provider.wallet_address()?.to_string(),
+            "7eV1qae4qVNqsNChg3Scdi-DpOLJPCogct4ixoq1WNg"
+        );
+        assert!(!machine_path.exists());
+
+        assert!(Provider::from_default_sources(Some("{}".to_string()), None).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_generated_keypair_persists() -> Result<(), Error> {
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let machine_path = temp_dir.0.join("arloader").join("default-keypair.json");
+
+        let first = Provider::from_jwk_str(&load_or_generate_keypair(&machine_path, 2048)?)?;
+        assert!(machine_path.exists());
+        let second = Provider::from_jwk_str(&load_or_generate_keypair(&machine_path, 2048)?)?;
+        assert_eq!(first.wallet_address()?, second.wallet_address()?);
+        // Only the keypair is left in the directory, without the temporary file it was written to.
+        assert_eq!(std::fs::read_dir(temp_dir.0.join("arloader"))?.count(), 1);
+
+        let message = b"hello, world";
+        let signature = first.sign(message)?;
+        second.verify(&signature, message)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_generate_round_trip() -> Result<(), Error> {
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let path = temp_dir.0.join("wallet.json");
+
+        let (generated, jwk) = Provider::generate_with_bits(&mut OsRng, 2048)?;
+        write_keypair(&path, &jwk)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path)?.permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        assert!(write_keypair(&path, &jwk).is_err());
+
+        let loaded = Provider::from_keypair_path(path).await?;
+        assert_eq!(generated.wallet_address()?, loaded.wallet_address()?);
+
+        let message = b"hello, world";
+        let signature = generated.sign(message)?;
+        loaded.verify(&signature, message)?;
+        let signature = loaded.sign(message)?;
+        generated.verify(&signature, message)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_address_from_modulus() -> Result<(), Error> {
+        let path = "tests/fixtures/arweave-key-7eV1qae4qVNqsNChg3Scdi-DpOLJPCogct4ixoq1WNg.json";
+        let expected = "7eV1qae4qVNqsNChg3Scdi-DpOLJPCogct4ixoq1WNg";
+        let jwk = std::fs::read_to_string(path)?;
+
+        let provider = Provider::from_jwk_str(&jwk)?;
+        assert_eq!(
+            address_from_modulus(&provider.keypair_modulus()?).to_string(),
+            expected
+        );
+        assert_eq!(address_from_jwk_str(&jwk)?.to_string(), expected);
+
+        // Only the public part of the key is needed.
+        let mut public: serde_json::Value = serde_json::from_str(&jwk)?;
+        let public = serde_json::json!({"kty": "RSA", "n": public["n"].take(), "e": "AQAB"});
+        assert_eq!(
+            address_from_jwk_str(&public.to_string())?.to_string(),
+            expected
+        );
+
+        assert!(matches!(
+            address_from_jwk_str(r#"{"kty": "RSA"}"#),
+            Err(Error::InvalidKeypair(_))
+        ));
+        assert!(matches!(
+            address_from_jwk_str("not json"),
+            Err(Error::InvalidKeypair(_))
+        ));
+        Ok(())
     }
 }
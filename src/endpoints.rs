@@ -0,0 +1,142 @@
+//! Urls of gateway and bundler node endpoints.
+
+use crate::{error::Error, transaction::Base64};
+use url::{ParseError, Url};
+
+/// Constructors for endpoint urls. Paths are appended to the path of the base url, so that a
+/// gateway mounted under a path prefix, e.g., `https://example.com/arweave`, works the same with
+/// or without a trailing slash.
+pub struct Endpoints;
+
+impl Endpoints {
+    /// `info`
+    pub fn info(base: &Url) -> Result<Url, Error> {
+        Self::join(base, &["info"])
+    }
+
+    /// `tx/pending`
+    pub fn pending(base: &Url) -> Result<Url, Error> {
+        Self::join(base, &["tx", "pending"])
+    }
+
+    /// `price/<BYTES>`
+    pub fn price(base: &Url, bytes: u64) -> Result<Url, Error> {
+        Self::join(base, &["price", &bytes.to_string()])
+    }
+
+    /// `tx`, where transactions are posted.
+    pub fn post_tx(base: &Url) -> Result<Url, Error> {
+        Self::join(base, &["tx"])
+    }
+
+    /// `tx/<ID>`
+    pub fn tx(base: &Url, id: &Base64) -> Result<Url, Error> {
+        Self::join(base, &["tx", &id.to_string()])
+    }
+
+    /// `tx/<ID>/status`
+    pub fn status(base: &Url, id: &Base64) -> Result<Url, Error> {
+        Self::join(base, &["tx", &id.to_string(), "status"])
+    }
+
+    /// `tx_anchor`
+    pub fn tx_anchor(base: &Url) -> Result<Url, Error> {
+        Self::join(base, &["tx_anchor"])
+    }
+
+    /// `<ID>`, the data of a transaction.
+    pub fn data(base: &Url, id: &Base64) -> Result<Url, Error> {
+        Self::join(base, &[&id.to_string()])
+    }
+
+    /// `chunk`
+    pub fn chunk(base: &Url) -> Result<Url, Error> {
+        Self::join(base, &["chunk"])
+    }
+
+    /// `graphql`
+    pub fn graphql(base: &Url) -> Result<Url, Error> {
+        Self::join(base, &["graphql"])
+    }
+
+    /// `wallet/<ADDRESS>/balance`
+    pub fn wallet_balance(base: &Url, address: &str) -> Result<Url, Error> {
+        Self::join(base, &["wallet", address, "balance"])
+    }
+
+    /// `account/balance/<ADDRESS>` of a bundler node.
+    pub fn account_balance(base: &Url, address: &str) -> Result<Url, Error> {
+        Self::join(base, &["account", "balance", address])
+    }
+
+    /// Appends `segments` to the path of `base`, keeping every segment of it.
+    fn join(base: &Url, segments: &[&str]) -> Result<Url, Error> {
+        let mut url = base.clone();
+        url.path_segments_mut()
+            .map_err(|_| ParseError::RelativeUrlWithCannotBeABaseBase)?
+            .pop_if_empty()
+            .extend(segments);
+        Ok(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Endpoints;
+    use crate::{error::Error, transaction::Base64};
+    use std::str::FromStr;
+    use url::Url;
+
+    #[test]
+    fn test_endpoints() -> Result<(), Error> {
+        let id = Base64::from_str("LCwsLCwsLA")?;
+        for (base, prefix) in [
+            ("https://arweave.net", "https://arweave.net/"),
+            ("https://arweave.net/", "https://arweave.net/"),
+            (
+                "http://localhost:1984/arweave",
+                "http://localhost:1984/arweave/",
+            ),
+            (
+                "http://localhost:1984/arweave/",
+                "http://localhost:1984/arweave/",
+            ),
+        ] {
+            let base = Url::from_str(base)?;
+            let urls = vec![
+                Endpoints::info(&base)?,
+                Endpoints::pending(&base)?,
+                Endpoints::price(&base, 1024)?,
+                Endpoints::post_tx(&base)?,
+                Endpoints::tx(&base, &id)?,
+                Endpoints::status(&base, &id)?,
+                Endpoints::tx_anchor(&base)?,
+                Endpoints::data(&base, &id)?,
+                Endpoints::chunk(&base)?,
+                Endpoints::graphql(&base)?,
+                Endpoints::wallet_balance(&base, "address")?,
+                Endpoints::account_balance(&base, "address")?,
+            ];
+            let paths = [
+                "info",
+                "tx/pending",
+                "price/1024",
+                "tx",
+                "tx/LCwsLCwsLA",
+                "tx/LCwsLCwsLA/status",
+                "tx_anchor",
+                "LCwsLCwsLA",
+                "chunk",
+                "graphql",
+                "wallet/address/balance",
+                "account/balance/address",
+            ];
+            for (url, path) in urls.iter().zip(paths) {
+                assert_eq!(url.as_str(), format!("{}{}", prefix, path));
+            }
+        }
+
+        assert!(Endpoints::info(&Url::from_str("data:text/plain,arweave")?).is_err());
+        Ok(())
+    }
+}
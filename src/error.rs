@@ -4,7 +4,7 @@ use glob;
 use reqwest;
 use ring::error::{KeyRejected, Unspecified};
 use serde_json;
-use std::string::FromUtf8Error;
+use std::{path::PathBuf, string::FromUtf8Error, time::Duration};
 use thiserror::Error;
 use url::ParseError;
 
@@ -15,6 +15,12 @@ pub enum Error {
     ArweaveGetPriceError(reqwest::Error),
     #[error("error posting arweave transaction: {0}")]
     ArweavePostError(reqwest::Error),
+    #[error("post rejected with {status}: {body}")]
+    ArweavePostRejected {
+        status: reqwest::StatusCode,
+        body: String,
+        retry_after: Option<Duration>,
+    },
     #[error("Arweave network error: {0}")]
     ArweaveNetworkError(reqwest::StatusCode),
     #[error("avro deserialize: {0}")]
@@ -23,61 +29,240 @@ pub enum Error {
     Base64Decode(#[from] DecodeError),
     #[error("bincode: {0}")]
     Bincode(#[from] Box<bincode::ErrorKind>),
+    #[error("{0} bundle items failed verification")]
+    BundleVerificationFailed(usize),
     #[error("unhandled boxed dyn error {0}")]
     BoxedDynStd(#[from] Box<dyn std::error::Error>),
+    #[error("data of transaction {0} doesn't match its data root")]
+    DataRootMismatch(String),
+    #[error("data item {0} is included more than once")]
+    DuplicateDataItem(String),
+    #[error(
+        "{} files not found in manifest: {}",
+        .0.len(),
+        .0.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", ")
+    )]
+    FilesNotInManifest(Vec<PathBuf>),
     #[error("formatting error")]
     FormatError(#[from] std::fmt::Error),
     #[error("from utf8: {0}")]
     FromUtf8(#[from] FromUtf8Error),
+    #[error("unexpected response from {endpoint}, the gateway may be having problems: {snippet}")]
+    GatewayUnexpectedResponse { endpoint: String, snippet: String },
     #[error("glob patters: {0}")]
     GlobPattern(#[from] glob::PatternError),
-    #[error("invalid bunlde item binary")]
-    InvalidDataItem,
+    #[error("invalid value for argument {name}: {value}")]
+    InvalidArgument { name: String, value: String },
+    #[error("invalid --since, expected a date (YYYY-MM-DD) or RFC 3339 time: {0}")]
+    InvalidAuditSince(String),
+    #[error("invalid bundler response")]
+    InvalidBundlerResponse,
+    #[error("invalid bundle tag placeholder: {0}")]
+    InvalidBundleTag(String),
+    #[error("fewer bytes written than the expected data length")]
+    InvalidDataLength,
+    #[error("invalid bundle item binary at byte {offset}")]
+    InvalidDataItem { offset: usize },
+    #[error("data item id doesn't match its signature")]
+    InvalidDataItemId,
     #[error("hashing failed")]
     InvalidHash,
+    #[error("invalid keypair: {0}")]
+    InvalidKeypair(String),
+    #[error("invalid --newer-than, expected RFC 3339 time or @last-run: {0}")]
+    InvalidNewerThan(String),
+    #[error("manifest {} isn't a json object of paths with ids", .0.display())]
+    InvalidManifest(PathBuf),
+    #[error("manifest entry for {0} doesn't have an id and links")]
+    InvalidManifestEntry(String),
+    #[error("manifest path needs url escaping, rename it or don't use --strict-paths: {0}")]
+    InvalidManifestKey(String),
     #[error("invalid proof")]
     InvalidProof,
+    #[error("invalid --files-link, expected id, file or both: {0}")]
+    InvalidFilesLink(String),
+    #[error("invalid --image-link, expected id or file: {0}")]
+    InvalidImageLink(String),
+    #[error("invalid transaction or data item id, expected 32 bytes: {0:?}")]
+    InvalidId(String),
+    #[error("invalid --sort-by: {0}")]
+    InvalidSortKey(String),
     #[error("invalid tags")]
     InvalidTags,
+    #[error("tags provided for {tags} files, but there are {paths} file paths")]
+    TagsLengthMismatch { paths: usize, tags: usize },
+    #[error("ANS-104 tag limit exceeded: {0}")]
+    TagLimitExceeded(String),
+    #[error("invalid --tip, expected <ADDRESS>:<AR>: {0}")]
+    InvalidTip(String),
+    #[error("insufficient wallet balance of {balance} winstons, {required} winstons required")]
+    InsufficientBalance { balance: u64, required: u64 },
+    #[error("reward of {reward} winstons doesn't cover the {required} winstons required for the serialized bundle")]
+    InsufficientReward { reward: u64, required: u64 },
+    #[error("uploads may use {required} bytes of memory with only {available} bytes available, lower --bundle-size or --buffer or pass --ignore-memory-check")]
+    InsufficientMemory { required: u64, available: u64 },
     #[error("insufficient sol funds")]
     InsufficientSolFunds,
+    #[error("insufficient sol wallet balance of {balance} lamports, {required} lamports required")]
+    InsufficientSolBalance { balance: u64, required: u64 },
     #[error("io: {0}")]
     IOError(#[from] std::io::Error),
+    #[error("keypair generation failed: {0}")]
+    KeyPairGeneration(String),
     #[error("keypair not provided")]
     KeyPairNotProvided,
     #[error("key rejected: {0}")]
     KeyRejected(#[from] KeyRejected),
+    #[error("log dir {} is a file", .0.display())]
+    LogDirIsFile(PathBuf),
+    #[error("directory \"{prefix}\" directly contains {count} files, more than the maximum of {max} paths per manifest, so it can't be split into manifests by directory")]
+    ManifestDirTooLarge {
+        prefix: String,
+        count: usize,
+        max: usize,
+    },
+    #[error("fallback path {0} is not one of the manifest paths")]
+    ManifestFallbackNotFound(String),
+    #[error("index path {0} is not one of the manifest paths")]
+    ManifestIndexNotFound(String),
     #[error("manifest not found")]
     ManifestNotFound,
+    #[error("manifest doesn't have a paths object")]
+    ManifestPathsMissing,
+    #[error("max runtime reached with {0} files not uploaded")]
+    MaxRuntimeExceeded(usize),
+    #[error(
+        "{} metadata files not found in manifest, or not matched to a single path in it, use --ignore-missing to skip them: {}",
+        .0.len(),
+        .0.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", ")
+    )]
+    MetadataNotInManifest(Vec<PathBuf>),
+    #[error("metadata {} doesn't have a name", .0.display())]
+    MetadataNameMissing(PathBuf),
+    #[error("metadata is not a json object")]
+    MetadataNotObject,
+    #[error("metaplex name is longer than 32 bytes, use --truncate-names to shorten it: {0}")]
+    MetaplexNameTooLong(String),
+    #[error("missing argument: {0}")]
+    MissingArgument(String),
     #[error("file path not provided")]
     MissingFilePath,
     #[error("missing trailing slash")]
     MissingTrailingSlash,
+    #[error("nft upload failed at {stage} with {} files not processed: {source}", .file_paths.len())]
+    NftUploadFailed {
+        stage: crate::NftUploadStage,
+        file_paths: Vec<PathBuf>,
+        source: Box<Error>,
+    },
+    #[error(
+        "nft upload failed at {stage}{}: {source}",
+        .image_id.as_ref().map(|id| format!(" after uploading image {}", id)).unwrap_or_default()
+    )]
+    NftPairUploadFailed {
+        stage: crate::NftUploadStage,
+        image_id: Option<crate::transaction::Base64>,
+        source: Box<Error>,
+    },
+    #[error("no bundles were uploaded to {}, check your wallet balance and run the command again", .0.display())]
+    NoBundlesUploaded(PathBuf),
     #[error("no bundle statuses found")]
     NoBundleStatusesFound,
     #[error("error getting oracle prices: {0}")]
     OracleGetPriceError(reqwest::Error),
+    #[error("post failed after paying with SOL, saved the transaction to {} to post it again with `arloader repost` without paying again: {source}", .path.display())]
+    PaidPostFailed { path: PathBuf, source: Box<Error> },
+    #[error("queue journal {} can't be parsed at line {line}", .path.display())]
+    QueueJournalCorrupt { path: PathBuf, line: usize },
+    #[error("read-only mode, refusing to post to the network")]
+    ReadOnlyMode,
+    #[error("{0} transactions failed to post, run `arloader repost` again to retry")]
+    RepostFailed(usize),
     #[error("reqwest: {0}")]
     Reqwest(#[from] reqwest::Error),
+    #[error("reward of {reward} winstons is below the network price of {price} winstons")]
+    RewardBelowPrice { reward: u64, price: u64 },
     #[error("ring unspecified: {0}")]
     RingUnspecified(#[from] Unspecified),
     #[error("serde json: {0}")]
     SerdeJson(#[from] serde_json::Error),
+    #[error("{0} statuses failed to update")]
+    StatusUpdatesFailed(usize),
     #[error("status code not ok")]
     StatusCodeNotOk,
     #[error("status not found")]
     StatusNotFound,
     #[error("solana hash parse {0}")]
     SolanaHashParse(#[from] solana_sdk::hash::ParseHashError),
+    #[error("can't load Solana keypair from {}: {reason}", .path.display())]
+    SolanaKeypair { path: PathBuf, reason: String },
+    #[error("data items with ed25519 signatures need a Solana keypair")]
+    SolanaKeypairNotProvided,
+    #[error("paying with SOL needs a log dir to save the transaction to in case posting it fails")]
+    SolanaLogDirNotProvided,
     #[error("solana network error")]
     SolanaNetworkError,
     #[error("solana hash parse {0}")]
     TokioJoinError(#[from] tokio::task::JoinError),
+    #[error("manifest has {count} paths, more than the maximum of {max}, pass --split-manifests to upload it as several manifests")]
+    TooManyManifestPaths { count: usize, max: usize },
+    #[error("unknown subcommand: {0}")]
+    UnknownSubcommand(String),
     #[error("transaction is not signed")]
     UnsignedTransaction,
+    #[error(
+        "unsupported key size for data items, the key modulus is {modulus_len} bytes and ANS-104 \
+        needs 4096 bit (512 byte) Arweave keys"
+    )]
+    UnsupportedKeySize { modulus_len: usize },
+    #[error("unsupported data item signature type: {0}")]
+    UnsupportedSignatureType(u16),
+    #[error(
+        "skipped {} written with status schema version {version}, upgrade arloader to read it",
+        .path.display()
+    )]
+    UnsupportedStatusVersion { path: PathBuf, version: u64 },
+    #[error("{} changed since the upload plan was made, make a new plan", .0.display())]
+    UploadPlanFileChanged(PathBuf),
+    #[error("{0} uploads failed verification")]
+    UploadVerificationFailed(usize),
     #[error("url parse error: {0}")]
     UrlParse(#[from] ParseError),
 }
 
+impl Error {
+    /// Whether a failed post might succeed if retried, i.e., it failed at the network level or
+    /// the gateway responded with 429 or 5xx.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::ArweavePostError(_) | Error::Reqwest(_) => true,
+            Error::ArweavePostRejected { status, .. } => {
+                status.is_server_error() || *status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            }
+            _ => false,
+        }
+    }
+
+    /// How long the gateway asked for requests to be paused with a `Retry-After` header.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Error::ArweavePostRejected { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Whether the gateway rejected a transaction because its anchor, `last_tx`, is no longer
+    /// valid.
+    pub fn is_stale_anchor(&self) -> bool {
+        match self {
+            Error::ArweavePostRejected { status, body, .. } => {
+                *status == reqwest::StatusCode::BAD_REQUEST
+                    && body.to_lowercase().contains("anchor")
+            }
+            _ => false,
+        }
+    }
+}
+
 unsafe impl Send for Error {}
 unsafe impl Sync for Error {}
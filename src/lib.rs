@@ -53,7 +53,7 @@
 //! It takes various [`Transaction`] or [`DataItem`] elements, including nested arrays of [`Tag`]s, and successively
 //! hashes and concatenates them together. Arloader assembles the required elements via the [`ToItems`] trait, which
 //! is implemented separately as [`Transaction::to_deep_hash_item`] and [`DataItem::to_deep_hash_item`] for each transaction
-//! format. [`crypto::Provider::deep_hash`] is Arloader's implementation of the deep hash algorithm.
+//! format. [`crypto::Hasher::deep_hash`] is Arloader's implementation of the deep hash algorithm.
 //!
 //! #### Higher Level Functions
 //! The functions for creating [`Transaction`]s and bundles of [`DataItem`]s are all consolidated on the [`Arweave`] struct.
@@ -71,52 +71,87 @@
 //! The functions for allowing payment to be made in SOL can be found in the [`solana`] module.
 
 use blake3;
-use chrono::Utc;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
 use futures::{
-    future::{try_join, try_join_all},
-    stream, Stream, StreamExt,
+    channel::mpsc,
+    future::{join, join_all, ready, try_join, try_join_all},
+    stream, Future, Stream, StreamExt, TryFutureExt,
 };
 use glob::glob;
 use infer;
-use log::debug;
+use log::{debug, warn};
 use num_bigint::BigUint;
 use rayon::prelude::*;
 use reqwest::{
     self,
-    header::{ACCEPT, CONTENT_TYPE},
-    Client, StatusCode as ResponseStatusCode,
+    header::{
+        HeaderMap, HeaderName, ACCEPT, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, RETRY_AFTER,
+    },
+    Client, RequestBuilder, StatusCode as ResponseStatusCode,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use solana_sdk::signer::keypair::Keypair;
+use solana_sdk::signer::{keypair::Keypair, Signer};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
     fmt::Write,
     path::{Path, PathBuf},
+    pin::Pin,
     str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
 };
 use tokio::{
     fs,
-    time::{sleep, Duration},
+    io::AsyncWriteExt,
+    time::{sleep, sleep_until, Duration, Instant},
 };
+use tower_layer::Layer;
+use tower_service::Service;
 use url::Url;
 
+pub mod audit;
 pub mod bundle;
+pub mod bundler;
 pub mod commands;
 pub mod crypto;
+pub mod endpoints;
 pub mod error;
 pub mod merkle;
+pub mod plan;
+pub mod queue;
 pub mod solana;
 pub mod status;
 pub mod transaction;
 pub mod utils;
 
-use bundle::DataItem;
+use audit::{audit_query, AuditPage, WalletAudit};
+use bundle::{
+    bundle_len, resolve_bundle_tags, shared_tags_prefix, split_bundle, verify_data_item,
+    write_bundle, BundleBreakdown, BundleFile, BundleSummary, DataItem, SignatureType, TagsEncoder,
+    BUNDLE_HEADER_LEN, BUNDLE_ITEM_HEADER_LEN,
+};
+use crypto::address_from_modulus;
+use endpoints::Endpoints;
 use error::Error;
-use merkle::{generate_data_root, generate_leaves, resolve_proofs};
-use solana::{create_sol_transaction, get_sol_ar_signature, SigResponse, FLOOR, RATE};
-use status::{BundleStatus, Filterable, Status, StatusCode};
-use transaction::{Base64, Chunk, FromUtf8Strs, Tag, ToItems, Transaction};
+use merkle::{chunks_and_proofs, generate_leaves, generate_leaves_from_reader, LeavesWriter, Node};
+use solana::{
+    get_or_create_sol_transaction, get_sol_ar_signature, get_sol_wallet_balance, payment_reference,
+    sol_transaction_failed, sol_tx_signature, SigResponse, FLOOR, RATE, SOLANA_MAIN_URL,
+    SOL_AR_BASE_URL, SOL_TX_FEE,
+};
+use status::{
+    parse_status, to_status_json, BundleItemCheck, BundleStatus, Filterable, LocalFileState,
+    OutputFormat, PostMetrics, RawStatus, RecordedFile, RecordedSize, Status, StatusCode,
+    StatusUpdate, SystemClock, UploadCheck, UploadProgress,
+};
+use transaction::{
+    Base64, Chunk, DeepHashItem, FromUtf8Strs, RewardBreakdown, Tag, Tip, ToItems, Transaction,
+};
 
 const VERSION: &'static str = env!("CARGO_PKG_VERSION");
 
@@ -129,6 +164,21 @@ pub const BLOCK_SIZE: u64 = 1024 * 256;
 /// Maximum data size to send to `tx/` endpoint. Sent to `chunk/` endpoint above this.
 pub const MAX_TX_DATA: u64 = 10_000_000;
 
+/// Maximum bundle size accepted by the cli `--bundle-size` argument = 200 MB.
+pub const MAX_BUNDLE_SIZE: u64 = 200 * BYTES_PER_MB;
+
+/// Bytes per MB in the cli `--bundle-size` and `--max-bundle-size` arguments.
+pub const BYTES_PER_MB: u64 = 1_000_000;
+
+/// Bytes per KB in data sizes printed by the cli.
+pub const BYTES_PER_KB: u64 = 1_000;
+
+/// Default maximum number of paths in a manifest, see [`Arweave::max_manifest_paths`].
+pub const MAX_MANIFEST_PATHS: usize = 100_000;
+
+/// `Content-Type` of `arweave/paths` manifests.
+pub const MANIFEST_CONTENT_TYPE: &str = "application/x.arweave-manifest+json";
+
 /// Multiplier applied to the buffer argument from the cli to determine the maximum number
 /// of simultaneous request to the `chunk/ endpoint`.
 pub const CHUNKS_BUFFER_FACTOR: usize = 20;
@@ -139,22 +189,188 @@ pub const CHUNKS_RETRIES: u16 = 10;
 /// Number of seconds to wait between retying to post a failed chunk.
 pub const CHUNKS_RETRY_SLEEP: u64 = 1;
 
+/// Number of times a failed bundle post is retried by default, see [`RetryPolicy`].
+pub const BUNDLE_POST_RETRIES: u32 = 3;
+
+/// Number of chunks posted between writes of interim [`BundleStatus`]es with upload progress.
+pub const PROGRESS_WRITE_INTERVAL: usize = 10;
+
+/// Number of seconds [`NetworkInfo`] is reused before being fetched from the network again.
+pub const NETWORK_INFO_TTL: u64 = 10;
+
+/// Number of seconds a fetched anchor is used as the `last_tx` of new transactions before a new
+/// one is fetched, well within the 50 blocks that anchors stay valid for.
+pub const TX_ANCHOR_TTL: u64 = 600;
+
+/// Number of seconds requests are paused after a 429 response without a `Retry-After` header.
+pub const RATE_LIMIT_COOLDOWN: u64 = 1;
+
+/// Sub directory of log dirs that [`PaidTransaction`]s are saved to until they're posted.
+pub const UNPOSTED_DIR: &str = "unposted";
+
+/// Sub directory of log dirs that bundle statuses replaced by `arloader reupload` are moved to,
+/// see [`Arweave::supersede_bundle_statuses`]. Statuses in it aren't read with the others.
+pub const SUPERSEDED_DIR: &str = "superseded";
+
+/// Number of seconds to wait for a connection to a gateway, see [`build_client`].
+pub const CONNECT_TIMEOUT: u64 = 30;
+
+/// Number of seconds between TCP keepalive probes of pooled connections, see [`build_client`].
+pub const TCP_KEEPALIVE: u64 = 60;
+
 //=========================
 // Streams
 //=========================
 
+/// Error from an upload stream along with the file paths that weren't uploaded because of it.
+#[derive(Debug)]
+pub struct FailedUpload {
+    pub error: Error,
+    pub file_paths: Vec<PathBuf>,
+}
+
+/// Error from a status update stream along with the path that wasn't updated because of it, the
+/// status file for bundles and the uploaded file otherwise.
+#[derive(Debug)]
+pub struct FailedUpdate {
+    pub error: Error,
+    pub file_path: PathBuf,
+}
+
+/// How far posting a transaction in chunks got, so that a retry can pick up from there.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct ChunksResume {
+    /// Whether the transaction header has been accepted.
+    header_posted: bool,
+    /// Index of the first chunk that hasn't been posted. Later chunks may have been posted too.
+    first_chunk: usize,
+}
+
+/// How failed bundle posts are retried. Only network errors and 429 or 5xx responses are retried,
+/// after waiting `base_delay` doubled for each previous retry, capped at `max_delay`, plus up to
+/// half as much again of random jitter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    pub retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn with_retries(retries: u32) -> Self {
+        Self {
+            retries,
+            ..Self::default()
+        }
+    }
+
+    /// Time to wait before retry number `retry`, starting from 0.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        backoff.mul_f64(1.0 + rand::random::<f64>() / 2.0)
+    }
+
+    /// Time to wait before retrying after `error`, the `Retry-After` duration sent by the gateway
+    /// if there was one, capped at `max_delay`, and otherwise [`RetryPolicy::delay`].
+    pub fn delay_for(&self, retry: u32, error: &Error) -> Duration {
+        match error.retry_after() {
+            Some(retry_after) => retry_after.min(self.max_delay),
+            None => self.delay(retry),
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: BUNDLE_POST_RETRIES,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Transaction paid for with SOL, saved to [`UNPOSTED_DIR`] before it's posted so that it can be
+/// posted again with [`Arweave::repost_paid_transaction`] without paying again if posting fails.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaidTransaction {
+    pub transaction: Transaction,
+    pub sig_response: SigResponse,
+    /// Written to the log dir once the transaction is posted.
+    pub status: PaidStatus,
+}
+
+/// Status of a [`PaidTransaction`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PaidStatus {
+    Bundle(BundleStatus),
+    File(Status),
+}
+
+/// Cooperative cancellation shared between a command and the streams it drives.
+///
+/// Iterators wrapped with [`CancelToken::wrap`] stop yielding items once the token is cancelled,
+/// so streams built from them don't start any new uploads while uploads already in flight are
+/// allowed to finish.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+    started: Arc<AtomicUsize>,
+}
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Number of items yielded by wrapped iterators, all of which were started before the
+    /// token was cancelled.
+    pub fn started(&self) -> usize {
+        self.started.load(Ordering::SeqCst)
+    }
+
+    pub fn wrap<I>(&self, iter: I) -> impl Iterator<Item = I::Item> + Send + Sync
+    where
+        I: Iterator + Send + Sync,
+    {
+        let token = self.clone();
+        iter.take_while(move |_| {
+            if token.is_cancelled() {
+                false
+            } else {
+                token.started.fetch_add(1, Ordering::SeqCst);
+                true
+            }
+        })
+    }
+}
+
 /// Uploads a stream of bundles from [`Vec<PathsChunk>`]s.
 ///
 /// Buffers bundles if using the tx/ endpoint, else buffers chunks with bundles processed serially.
-pub fn upload_bundles_stream<'a>(
+pub fn upload_bundles_stream<'a, IP>(
     arweave: &'a Arweave,
-    paths_chunks: Vec<PathsChunk>,
+    paths_chunks: IP,
     tags: Vec<Tag<String>>,
+    log_dir: Option<PathBuf>,
     price_terms: (u64, u64),
     buffer: usize,
-) -> impl Stream<Item = Result<BundleStatus, Error>> + 'a {
-    let bundle_size = paths_chunks[0].1;
-    let (bundles_buffer, chunks_buffer) = if bundle_size > MAX_TX_DATA {
+    retry_policy: RetryPolicy,
+) -> impl Stream<Item = Result<BundleStatus, FailedUpload>> + 'a
+where
+    IP: IntoIterator<Item = PathsChunk>,
+    IP::IntoIter: 'a,
+{
+    let mut paths_chunks = paths_chunks.into_iter().peekable();
+    let bundle_size = paths_chunks.peek().map(|c| c.1).unwrap_or(0);
+    let (bundles_buffer, chunks_buffer) = if arweave.network_params.posts_chunks(bundle_size) {
         (1, buffer * CHUNKS_BUFFER_FACTOR)
     } else {
         (buffer, 1)
@@ -162,43 +378,79 @@ pub fn upload_bundles_stream<'a>(
 
     stream::iter(paths_chunks)
         .map(move |p| {
-            arweave.post_bundle_transaction_from_file_paths(
-                p,
-                tags.clone(),
-                price_terms,
-                chunks_buffer,
-            )
+            let file_paths = p.0.clone();
+            arweave
+                .post_bundle_transaction_from_file_paths(
+                    p,
+                    tags.clone(),
+                    log_dir.clone(),
+                    price_terms,
+                    chunks_buffer,
+                    retry_policy,
+                )
+                .map_err(|error| FailedUpload { error, file_paths })
         })
         .buffer_unordered(bundles_buffer)
 }
 
-/// Queries network and updates locally stored [`BundleStatus`] structs.
+/// Predicate for statuses that the update streams yield as they were read instead of querying the
+/// network for them, e.g., statuses that are already confirmed, see
+/// [`Filterable::is_confirmed_with`].
+pub type SkipUpdate<'a, T> = Option<&'a (dyn Fn(&T) -> bool + Send + Sync)>;
+
+/// Queries network and updates locally stored [`BundleStatus`] structs, skipping those that
+/// `skip` returns true for. Statuses that can't be read or updated are yielded as
+/// [`FailedUpdate`]s with the path of the status file.
 pub fn update_bundle_statuses_stream<'a, IP>(
     arweave: &'a Arweave,
     paths_iter: IP,
     buffer: usize,
-) -> impl Stream<Item = Result<BundleStatus, Error>> + 'a
+    skip: SkipUpdate<'a, BundleStatus>,
+) -> impl Stream<Item = Result<StatusUpdate<BundleStatus>, FailedUpdate>> + 'a
 where
     IP: Iterator<Item = PathBuf> + Send + Sync + 'a,
 {
     stream::iter(paths_iter)
-        .map(move |p| arweave.update_bundle_status(p))
+        .map(move |p| async move {
+            let update = async {
+                let status = arweave.read_bundle_status(p.clone()).await?;
+                if skip.map_or(false, |skip| skip(&status)) {
+                    return Ok(StatusUpdate::Skipped(status));
+                }
+                arweave
+                    .refresh_bundle_status(status, p.clone())
+                    .await
+                    .map(StatusUpdate::Updated)
+            }
+            .await;
+            update.map_err(|error| FailedUpdate {
+                error,
+                file_path: p,
+            })
+        })
         .buffer_unordered(buffer)
 }
 
 /// Uploads a stream of bundles from [`Vec<PathsChunk>`]s, paying with SOL.
-pub fn upload_bundles_stream_with_sol<'a>(
+pub fn upload_bundles_stream_with_sol<'a, IP>(
     arweave: &'a Arweave,
-    paths_chunks: Vec<PathsChunk>,
+    paths_chunks: IP,
     tags: Vec<Tag<String>>,
+    log_dir: Option<PathBuf>,
     price_terms: (u64, u64),
     buffer: usize,
+    retry_policy: RetryPolicy,
     solana_url: Url,
     sol_ar_url: Url,
     from_keypair: &'a Keypair,
-) -> impl Stream<Item = Result<BundleStatus, Error>> + 'a {
-    let bundle_size = paths_chunks[0].1;
-    let (bundles_buffer, chunks_buffer) = if bundle_size > MAX_TX_DATA {
+) -> impl Stream<Item = Result<BundleStatus, FailedUpload>> + 'a
+where
+    IP: IntoIterator<Item = PathsChunk>,
+    IP::IntoIter: 'a,
+{
+    let mut paths_chunks = paths_chunks.into_iter().peekable();
+    let bundle_size = paths_chunks.peek().map(|c| c.1).unwrap_or(0);
+    let (bundles_buffer, chunks_buffer) = if arweave.network_params.posts_chunks(bundle_size) {
         (1, buffer * CHUNKS_BUFFER_FACTOR)
     } else {
         (buffer, 1)
@@ -206,15 +458,20 @@ pub fn upload_bundles_stream_with_sol<'a>(
 
     stream::iter(paths_chunks)
         .map(move |p| {
-            arweave.post_bundle_transaction_from_file_paths_with_sol(
-                p,
-                tags.clone(),
-                price_terms,
-                chunks_buffer,
-                solana_url.clone(),
-                sol_ar_url.clone(),
-                from_keypair,
-            )
+            let file_paths = p.0.clone();
+            arweave
+                .post_bundle_transaction_from_file_paths_with_sol(
+                    p,
+                    tags.clone(),
+                    log_dir.clone(),
+                    price_terms,
+                    chunks_buffer,
+                    retry_policy,
+                    solana_url.clone(),
+                    sol_ar_url.clone(),
+                    from_keypair,
+                )
+                .map_err(|error| FailedUpload { error, file_paths })
         })
         .buffer_unordered(bundles_buffer)
 }
@@ -225,11 +482,38 @@ pub fn upload_transaction_chunks_stream<'a>(
     signed_transaction: Transaction,
     buffer: usize,
 ) -> impl Stream<Item = Result<usize, Error>> + 'a {
-    let client = Client::new();
-    stream::iter(0..signed_transaction.chunks.len())
+    upload_chunks_from_stream(arweave, Arc::new(signed_transaction), 0, buffer, true)
+        .map(|(_, r)| r)
+}
+
+/// Serializes `signed_transaction` to the json body posted to the `tx` endpoint.
+fn transaction_body(signed_transaction: &Transaction) -> Result<Bytes, Error> {
+    Ok(Bytes::from(serde_json::to_vec(signed_transaction)?))
+}
+
+/// Uploads the chunks of `signed_transaction` from index `start`, yielding the index of each
+/// chunk with the result of posting it. Each chunk is only posted once unless `retry`, see
+/// [`Arweave::post_chunk_with_retries`].
+fn upload_chunks_from_stream<'a>(
+    arweave: &'a Arweave,
+    signed_transaction: Arc<Transaction>,
+    start: usize,
+    buffer: usize,
+    retry: bool,
+) -> impl Stream<Item = (usize, Result<usize, Error>)> + 'a {
+    let client = arweave.client.clone();
+    stream::iter(start..signed_transaction.chunks.len())
         .map(move |i| {
-            let chunk = signed_transaction.get_chunk(i).unwrap();
-            arweave.post_chunk_with_retries(chunk, client.clone())
+            let signed_transaction = signed_transaction.clone();
+            let client = client.clone();
+            async move {
+                let posted = match signed_transaction.read_chunk(i).await {
+                    Ok(chunk) if retry => arweave.post_chunk_with_retries(chunk, client).await,
+                    Ok(chunk) => arweave.post_chunk(&chunk, &client).await,
+                    Err(error) => Err(error),
+                };
+                (i, posted)
+            }
         })
         .buffer_unordered(buffer)
 }
@@ -243,19 +527,22 @@ pub fn upload_files_stream<'a, IP>(
     last_tx: Option<Base64>,
     price_terms: (u64, u64),
     buffer: usize,
-) -> impl Stream<Item = Result<Status, Error>> + 'a
+) -> impl Stream<Item = Result<Status, FailedUpload>> + 'a
 where
     IP: Iterator<Item = PathBuf> + Send + Sync + 'a,
 {
     stream::iter(paths_iter)
         .map(move |p| {
-            arweave.upload_file_from_path(
-                p,
-                log_dir.clone(),
-                tags.clone(),
-                last_tx.clone(),
-                price_terms,
-            )
+            let file_paths = vec![p.clone()];
+            arweave
+                .upload_file_from_path(
+                    p,
+                    log_dir.clone(),
+                    tags.clone(),
+                    last_tx.clone(),
+                    price_terms,
+                )
+                .map_err(|error| FailedUpload { error, file_paths })
         })
         .buffer_unordered(buffer)
 }
@@ -272,38 +559,211 @@ pub fn upload_files_with_sol_stream<'a, IP>(
     sol_ar_url: Url,
     from_keypair: &'a Keypair,
     buffer: usize,
-) -> impl Stream<Item = Result<Status, Error>> + 'a
+) -> impl Stream<Item = Result<Status, FailedUpload>> + 'a
 where
     IP: Iterator<Item = PathBuf> + Send + Sync + 'a,
 {
     stream::iter(paths_iter)
         .map(move |p| {
-            arweave.upload_file_from_path_with_sol(
-                p,
-                log_dir.clone(),
-                tags.clone(),
-                last_tx.clone(),
-                price_terms,
-                solana_url.clone(),
-                sol_ar_url.clone(),
-                from_keypair,
-            )
+            let file_paths = vec![p.clone()];
+            arweave
+                .upload_file_from_path_with_sol(
+                    p,
+                    log_dir.clone(),
+                    tags.clone(),
+                    last_tx.clone(),
+                    price_terms,
+                    solana_url.clone(),
+                    sol_ar_url.clone(),
+                    from_keypair,
+                )
+                .map_err(|error| FailedUpload { error, file_paths })
         })
         .buffer_unordered(buffer)
 }
 
-/// Queries network and updates locally stored [`Status`] structs.
+/// Error for a post the gateway didn't accept, including the body of the response, which usually
+/// explains why, e.g., insufficient funds or an invalid anchor.
+async fn post_rejected(resp: reqwest::Response) -> Error {
+    let status = resp.status();
+    let retry_after = retry_after(resp.headers());
+    let body = resp.text().await.unwrap_or_default().trim().to_string();
+    Error::ArweavePostRejected {
+        status,
+        body,
+        retry_after,
+    }
+}
+
+/// Builds a [`Client`] for [`Arweave::client`] that pools connections with TCP keepalive and
+/// decodes gzip encoded responses, failing requests that take longer than `timeout` if provided.
+/// Uploads of large transactions can take a while, so there's no timeout by default. HTTP/2 is
+/// negotiated with gateways that support it, so that requests share a single connection, and
+/// connections opened and the time taken to open them are recorded in `connection_stats`.
+pub fn build_client(
+    timeout: Option<Duration>,
+    connection_stats: &ConnectionStats,
+) -> Result<Client, Error> {
+    let mut builder = Client::builder()
+        .connect_timeout(Duration::from_secs(CONNECT_TIMEOUT))
+        .tcp_keepalive(Duration::from_secs(TCP_KEEPALIVE))
+        .connector_layer(ConnectionStatsLayer(connection_stats.clone()))
+        .gzip(true);
+    if let Some(timeout) = timeout {
+        builder = builder.timeout(timeout);
+    }
+    Ok(builder.build()?)
+}
+
+/// Number of characters of an unexpected gateway response included in
+/// [`Error::GatewayUnexpectedResponse`].
+const RESPONSE_SNIPPET_LEN: usize = 200;
+
+/// Parses the body of a gateway response with `parse`. Unsuccessful responses, html, e.g., the
+/// error page of a CDN in front of the gateway, and bodies that can't be parsed are returned as
+/// [`Error::GatewayUnexpectedResponse`] with the start of the body.
+async fn parse_gateway_response<T>(
+    resp: reqwest::Response,
+    parse: impl FnOnce(&str) -> Option<T>,
+) -> Result<T, Error> {
+    let endpoint = resp.url().to_string();
+    let success = resp.status().is_success();
+    let is_html = resp
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map_or(false, |v| v.to_lowercase().contains("html"));
+    let body = resp.text().await?;
+    match (success && !is_html).then(|| parse(&body)).flatten() {
+        Some(value) => Ok(value),
+        None => Err(Error::GatewayUnexpectedResponse {
+            endpoint,
+            snippet: body.trim().chars().take(RESPONSE_SNIPPET_LEN).collect(),
+        }),
+    }
+}
+
+/// Parses a `Retry-After` header, given either as a number of seconds or as an http date.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        date.with_timezone(&Utc)
+            .signed_duration_since(Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// Removes the [`PaidTransaction`] saved at `paid_path` once it has been posted, or points to it
+/// in the error if posting failed.
+async fn finish_paid_post(
+    posted: Result<(Base64, u64), Error>,
+    paid_path: Option<PathBuf>,
+) -> Result<(Base64, u64), Error> {
+    match (posted, paid_path) {
+        (Ok(posted), Some(path)) => {
+            fs::remove_file(path).await?;
+            Ok(posted)
+        }
+        (Err(error), Some(path)) => Err(Error::PaidPostFailed {
+            path,
+            source: Box::new(error),
+        }),
+        (posted, None) => posted,
+    }
+}
+
+/// Hash of the data root, data size and tags of `transaction`, which SOL payments for it are
+/// referenced by. Unlike its deep hash, it doesn't change if the transaction is created again
+/// with a new anchor or reward.
+fn payment_key(transaction: &Transaction) -> Vec<u8> {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&transaction.data_root.0);
+    hasher.update(&transaction.data_size.to_le_bytes());
+    for tag in &transaction.tags {
+        for bytes in [&tag.name.0, &tag.value.0] {
+            hasher.update(&(bytes.len() as u64).to_le_bytes());
+            hasher.update(bytes);
+        }
+    }
+    hasher.finalize().as_bytes().to_vec()
+}
+
+/// [`status::file_data_hash`] of `file_path`, hashed on a blocking thread.
+async fn file_data_hash(file_path: PathBuf) -> Result<String, Error> {
+    Ok(tokio::task::spawn_blocking(move || status::file_data_hash(&file_path)).await??)
+}
+
+/// Manifest entries of the files uploaded in bundles with `statuses`. Manifests bundled with
+/// [`Arweave::post_manifest_bundle`] and bundles with chunks left to post, see
+/// [`BundleStatus::is_posting`], are left out.
+fn bundle_statuses_paths(statuses: Vec<BundleStatus>) -> serde_json::Map<String, Value> {
+    statuses
+        .into_iter()
+        .filter(|s| !s.is_posting())
+        .fold(serde_json::Map::new(), |mut m, mut s| {
+            m.append(s.file_paths.as_object_mut().unwrap());
+            m
+        })
+        .into_iter()
+        .filter(|(_, p)| p["content_type"] != MANIFEST_CONTENT_TYPE)
+        .collect()
+}
+
+/// Checks [`RecordedFile`]s against local files, hashing them in parallel with rayon and
+/// returning a stream of results in the order they complete.
+pub fn check_local_files_stream(
+    files: Vec<RecordedFile>,
+) -> impl Stream<Item = (RecordedFile, LocalFileState)> {
+    let (sender, receiver) = mpsc::unbounded();
+    rayon::spawn(move || {
+        files.into_par_iter().for_each_with(sender, |sender, file| {
+            let state = file.check();
+            // The receiver is only gone if the stream was dropped.
+            let _ = sender.unbounded_send((file, state));
+        })
+    });
+    receiver
+}
+
+/// Queries network and updates locally stored [`Status`] structs, skipping those that `skip`
+/// returns true for. Statuses that can't be read or updated are yielded as [`FailedUpdate`]s
+/// with the path of the uploaded file.
 pub fn update_statuses_stream<'a, IP>(
     arweave: &'a Arweave,
     paths_iter: IP,
     log_dir: PathBuf,
     buffer: usize,
-) -> impl Stream<Item = Result<Status, Error>> + 'a
+    skip: SkipUpdate<'a, Status>,
+) -> impl Stream<Item = Result<StatusUpdate<Status>, FailedUpdate>> + 'a
 where
     IP: Iterator<Item = PathBuf> + Send + Sync + 'a,
 {
     stream::iter(paths_iter)
-        .map(move |p| arweave.update_status(p, log_dir.clone()))
+        .map(move |p| {
+            let log_dir = log_dir.clone();
+            async move {
+                let update = async {
+                    let status = arweave.read_status(p.clone(), log_dir.clone()).await?;
+                    if skip.map_or(false, |skip| skip(&status)) {
+                        return Ok(StatusUpdate::Skipped(status));
+                    }
+                    arweave
+                        .refresh_status(status, log_dir)
+                        .await
+                        .map(StatusUpdate::Updated)
+                }
+                .await;
+                update.map_err(|error| FailedUpdate {
+                    error,
+                    file_path: p,
+                })
+            }
+        })
         .buffer_unordered(buffer)
 }
 
@@ -322,1544 +782,8057 @@ pub struct OraclePricePair {
     pub usd: f32,
 }
 
-/// Tuple struct includes two elements: chunk of paths and aggregatge data size of paths.
-#[derive(Clone, Debug)]
-pub struct PathsChunk(Vec<PathBuf>, u64);
-
-/// Used in updating [`BundleStatus`]s to determine whether a file stem includes a valid transaction id.
-pub fn file_stem_is_valid_txid(file_path: &PathBuf) -> bool {
-    match Base64::from_str(file_path.file_stem().unwrap().to_str().unwrap()) {
-        Ok(txid) => match txid.0.len() {
-            32 => true,
-            _ => false,
-        },
-        Err(_) => false,
-    }
+/// Network info as reported by the `info` endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NetworkInfo {
+    pub height: u64,
+    pub current: String,
+    pub blocks: u64,
+    pub peers: u64,
 }
 
-//=========================
-// Arweave
-//=========================
+/// Most recently fetched [`NetworkInfo`] along with when it was fetched, shared between clones.
+#[derive(Clone, Debug, Default)]
+pub struct NetworkInfoCache(Arc<Mutex<Option<(Instant, NetworkInfo)>>>);
+
+/// Most recently fetched transaction anchor along with when it was fetched, shared between clones.
+/// Held while a new anchor is fetched, so that concurrent transactions wait for it rather than
+/// each fetching their own.
+#[derive(Clone, Debug, Default)]
+pub struct AnchorCache(Arc<tokio::sync::Mutex<Option<(Instant, Base64)>>>);
+
+/// Time until which requests are paused after the gateway responded with 429, shared between
+/// clones so that one rate limited request pauses all the others.
+#[derive(Clone, Debug, Default)]
+pub struct Cooldown(Arc<Mutex<Option<Instant>>>);
+
+impl Cooldown {
+    /// Pauses requests for `duration`, unless they're already paused for longer.
+    pub fn set(&self, duration: Duration) {
+        let until = Instant::now() + duration;
+        let mut current = self.0.lock().unwrap();
+        if current.map_or(true, |current| current < until) {
+            *current = Some(until);
+        }
+    }
 
-/// Struct with methods for interacting with the Arweave network.
-pub struct Arweave {
-    pub name: String,
-    pub units: String,
-    pub base_url: Url,
-    pub crypto: crypto::Provider,
-}
+    /// Waits until requests are no longer paused, including by pauses set while waiting.
+    pub async fn wait(&self) {
+        loop {
+            let until = match *self.0.lock().unwrap() {
+                Some(until) if until > Instant::now() => until,
+                _ => return,
+            };
+            sleep_until(until).await;
+        }
+    }
 
-impl Default for Arweave {
-    fn default() -> Self {
-        Self {
-            name: String::from("arweave"),
-            units: String::from("winstons"),
-            base_url: Url::from_str("https://arweave.net/").unwrap(),
-            crypto: crypto::Provider::default(),
+    /// Pauses requests if `resp` is a 429, for as long as its `Retry-After` header asks up to the
+    /// default [`RetryPolicy::max_delay`], or [`RATE_LIMIT_COOLDOWN`] seconds if it doesn't have
+    /// one.
+    fn observe(&self, resp: &reqwest::Response) {
+        if resp.status() == ResponseStatusCode::TOO_MANY_REQUESTS {
+            let duration = retry_after(resp.headers())
+                .map(|retry_after| retry_after.min(RetryPolicy::default().max_delay))
+                .unwrap_or_else(|| Duration::from_secs(RATE_LIMIT_COOLDOWN));
+            debug!("rate limited by {}, pausing for {:?}", resp.url(), duration);
+            self.set(duration);
         }
     }
 }
 
-impl Arweave {
-    pub async fn from_keypair_path(keypair_path: PathBuf, base_url: Url) -> Result<Arweave, Error> {
-        let crypto = crypto::Provider::from_keypair_path(keypair_path).await?;
-        let arweave = Arweave {
-            base_url,
-            crypto,
-            ..Default::default()
-        };
+/// Bytes saved by [`Arweave::gzip_requests`] and whether the gateway rejected gzip encoded
+/// requests, shared between clones.
+#[derive(Clone, Debug, Default)]
+pub struct GzipStats {
+    bytes_saved: Arc<AtomicU64>,
+    rejected: Arc<AtomicBool>,
+}
 
-        Ok(arweave)
+impl GzipStats {
+    pub fn bytes_saved(&self) -> u64 {
+        self.bytes_saved.load(Ordering::Relaxed)
     }
 
-    pub fn from_keypair_path_sync(keypair_path: PathBuf, base_url: Url) -> Result<Arweave, Error> {
-        let crypto = crypto::Provider::from_keypair_path_sync(keypair_path)?;
-        let arweave = Arweave {
-            base_url,
-            crypto,
-            ..Default::default()
-        };
-
-        Ok(arweave)
+    /// Set once the gateway responds with 415, after which requests are no longer compressed.
+    pub fn rejected(&self) -> bool {
+        self.rejected.load(Ordering::Relaxed)
     }
+}
 
-    //-------------------------
-    // Get Request
-    //-------------------------
+/// Connections opened by clients from [`build_client`] and requests made with them, shared
+/// between clones. Connections are counted by the client's connector, so they are counted
+/// whether the gateway is addressed by host name or by IP, along with the time taken to open
+/// them.
+#[derive(Clone, Debug, Default)]
+pub struct ConnectionStats {
+    opened: Arc<AtomicU64>,
+    requests: Arc<AtomicU64>,
+    connect_nanos: Arc<AtomicU64>,
+}
 
-    /// Get pending network transaction count.
-    pub async fn get_pending_count(&self) -> Result<usize, Error> {
-        let url = self.base_url.join("tx/pending")?;
-        let tx_ids: Vec<String> = reqwest::get(url).await?.json().await?;
-        Ok(tx_ids.len())
+impl ConnectionStats {
+    pub fn opened(&self) -> u64 {
+        self.opened.load(Ordering::Relaxed)
     }
 
-    /// Returns price of uploading data to the network in winstons and USD per AR and USD per SOL
-    /// as a BigUint with two decimals.
-    pub async fn get_price(&self, bytes: &u64) -> Result<(BigUint, BigUint, BigUint), Error> {
-        let url = self.base_url.join("price/")?.join(&bytes.to_string())?;
-        let winstons_per_bytes = reqwest::get(url)
-            .await
-            .map_err(|e| Error::ArweaveGetPriceError(e))?
-            .json::<u64>()
-            .await?;
-        let winstons_per_bytes = BigUint::from(winstons_per_bytes);
-
-        let oracle_url =
-            "https://api.coingecko.com/api/v3/simple/price?ids=arweave,solana&vs_currencies=usd";
-        let prices = reqwest::get(oracle_url)
-            .await
-            .map_err(|e| Error::OracleGetPriceError(e))?
-            .json::<OraclePrice>()
-            .await?;
-
-        let usd_per_ar: BigUint = BigUint::from((prices.arweave.usd * 100.0).floor() as u32);
-        let usd_per_sol: BigUint = BigUint::from((prices.solana.usd * 100.0).floor() as u32);
+    /// Total time taken to open connections, including looking up the host and the TCP and TLS
+    /// handshakes.
+    pub fn connect_time(&self) -> Duration {
+        Duration::from_nanos(self.connect_nanos.load(Ordering::Relaxed))
+    }
 
-        Ok((winstons_per_bytes, usd_per_ar, usd_per_sol))
+    pub fn requests(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
     }
 
-    /// Gets base and incremental prices for a 256 KB block of data.
-    pub async fn get_price_terms(&self, reward_mult: f32) -> Result<(u64, u64), Error> {
-        let (prices1, prices2) = try_join(
-            self.get_price(&(256 * 1024)),
-            self.get_price(&(256 * 1024 * 2)),
-        )
-        .await?;
-        let base = (prices1.0.to_u64_digits()[0] as f32 * reward_mult) as u64;
-        let incremental = (prices2.0.to_u64_digits()[0] as f32 * reward_mult) as u64 - &base;
-        Ok((base, incremental))
+    /// Requests made on a connection that was already open.
+    pub fn reused(&self) -> u64 {
+        self.requests().saturating_sub(self.opened())
     }
 
-    /// Gets transaction from the network.
-    pub async fn get_transaction(&self, id: &Base64) -> Result<Transaction, Error> {
-        let url = self.base_url.join("tx/")?.join(&id.to_string())?;
-        let resp = reqwest::get(url).await?.json::<Transaction>().await?;
-        Ok(resp)
+    fn observe(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
     }
 
-    /// Returns the balance of the wallet.
-    pub async fn get_wallet_balance(
-        &self,
-        wallet_address: Option<String>,
-    ) -> Result<BigUint, Error> {
-        let wallet_address = if let Some(wallet_address) = wallet_address {
-            wallet_address
-        } else {
-            self.crypto.wallet_address()?.to_string()
-        };
-        let url = self
-            .base_url
-            .join(&format!("wallet/{}/balance", &wallet_address))?;
-        let winstons = reqwest::get(url).await?.json::<u64>().await?;
-        Ok(BigUint::from(winstons))
+    fn observe_connect(&self, elapsed: Duration) {
+        self.opened.fetch_add(1, Ordering::Relaxed);
+        self.connect_nanos
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
     }
+}
 
-    //-------------------------
-    // Bundle
-    //-------------------------
+/// Wraps the connector of clients from [`build_client`] with [`ConnectionStatsConnector`], which
+/// is only called when a new connection is needed and not for pooled connections.
+#[derive(Clone)]
+struct ConnectionStatsLayer(ConnectionStats);
 
-    pub fn chunk_file_paths<IP>(
-        &self,
-        paths_iter: IP,
-        data_size: u64,
-    ) -> Result<Vec<PathsChunk>, Error>
-    where
-        IP: Iterator<Item = PathBuf> + Send,
-    {
-        let (mut paths_chunks, last_chunk, last_data_len) = paths_iter.fold(
-            (Vec::<PathsChunk>::new(), Vec::<PathBuf>::new(), 0u64),
-            |(mut ip, mut i, data_len), p| {
-                let p_len = p.metadata().unwrap().len();
-                if data_len + p_len > data_size {
-                    ip.push(PathsChunk(i, data_len));
-                    (ip, vec![p], p_len)
-                } else {
-                    i.push(p);
-                    (ip, i, data_len + p_len)
-                }
-            },
-        );
+impl<S> Layer<S> for ConnectionStatsLayer {
+    type Service = ConnectionStatsConnector<S>;
 
-        if last_chunk.len() > 0 {
-            paths_chunks.push(PathsChunk(last_chunk, last_data_len));
+    fn layer(&self, inner: S) -> Self::Service {
+        ConnectionStatsConnector {
+            inner,
+            stats: self.0.clone(),
         }
-
-        Ok(paths_chunks)
     }
+}
 
-    pub fn create_bundle_from_data_items(
-        &self,
-        data_items: Vec<(DataItem, Status)>,
-    ) -> Result<(Vec<u8>, Value), Error> {
-        let data_items_len = (data_items.len()) as u64;
-        let ((headers, binaries), statuses): ((Vec<Vec<u8>>, Vec<Vec<u8>>), Vec<Status>) =
-            data_items
-                .into_iter()
-                .map(|(d, s)| (d.to_bundle_item().unwrap(), s))
-                .unzip();
-
-        let manifest = self.create_manifest(statuses)?;
+/// Records each connection opened by `inner` in [`ConnectionStats`] with the time taken to
+/// open it.
+#[derive(Clone)]
+struct ConnectionStatsConnector<S> {
+    inner: S,
+    stats: ConnectionStats,
+}
 
-        let binary: Vec<_> = data_items_len
-            .to_le_bytes()
-            .into_par_iter()
-            .chain([0u8; 24].into_par_iter())
-            .chain(headers.into_par_iter().flatten())
-            .chain(binaries.into_par_iter().flatten())
-            .collect();
+impl<S, R> Service<R> for ConnectionStatsConnector<S>
+where
+    S: Service<R>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
 
-        Ok((binary, manifest))
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
     }
 
-    pub async fn create_bundle_transaction_from_file_paths(
-        &self,
-        paths_iter: Vec<PathBuf>,
-        tags: Vec<Tag<String>>,
-        price_terms: (u64, u64),
-    ) -> Result<(Transaction, Value), Error> {
-        let data_items = self
-            .create_data_items_from_file_paths(paths_iter, tags)
-            .await?;
+    fn call(&mut self, dst: R) -> Self::Future {
+        let stats = self.stats.clone();
+        let started = Instant::now();
+        let connecting = self.inner.call(dst);
+        Box::pin(async move {
+            let conn = connecting.await?;
+            stats.observe_connect(started.elapsed());
+            Ok(conn)
+        })
+    }
+}
 
-        let (bundle, manifest_object) = self.create_bundle_from_data_items(data_items)?;
-        let other_tags = Some(vec![
-            Tag::<Base64>::from_utf8_strs("Bundle-Format", "binary")?,
-            Tag::<Base64>::from_utf8_strs("Bundle-Version", "2.0.0")?,
-        ]);
+/// Tuple struct includes two elements: chunk of paths and aggregatge data size of paths.
+#[derive(Clone, Debug)]
+pub struct PathsChunk(Vec<PathBuf>, u64);
 
-        let transaction = self
-            .create_transaction(bundle, other_tags, None, price_terms, true)
-            .await?;
+/// Options for [`Arweave::upload_nft_collection`], defaulting to the same values as
+/// `arloader upload-nfts`.
+#[derive(Debug)]
+pub struct NftUploadOptions {
+    /// Directory statuses and manifests are written to, created next to the first asset if not
+    /// provided.
+    pub log_dir: Option<PathBuf>,
+    pub bundle_size: u64,
+    pub reward_mult: f32,
+    pub buffer: usize,
+    pub retry_policy: RetryPolicy,
+    /// Pays for all transactions with SOL from this keypair instead of with AR.
+    pub sol_keypair: Option<Keypair>,
+    pub solana_url: Url,
+    pub sol_ar_url: Url,
+    /// Links metadata to assets through their manifest paths instead of by id.
+    pub link_file: bool,
+    /// Uploads each file as its own transaction instead of in bundles.
+    pub no_bundle: bool,
+    pub truncate_names: bool,
+}
 
-        Ok((transaction, manifest_object))
+/// Options for [`Arweave::upload_nft_pair`], defaulting to the same values as
+/// [`NftUploadOptions`].
+#[derive(Debug)]
+pub struct NftPairOptions {
+    pub reward_mult: f32,
+    /// Pays for both transactions with SOL from this keypair instead of with AR.
+    pub sol_keypair: Option<Keypair>,
+    pub solana_url: Url,
+    pub sol_ar_url: Url,
+    /// Uploads the image and metadata each as its own transaction instead of in a bundle.
+    pub no_bundle: bool,
+    /// Multiplied by [`CHUNKS_BUFFER_FACTOR`] for the number of chunks posted concurrently.
+    pub buffer: usize,
+    /// Directory transactions paid for with SOL are saved to before they're posted, so that they
+    /// can be posted again with `arloader repost` if posting fails. Needed with `sol_keypair`.
+    pub log_dir: Option<PathBuf>,
+}
+
+impl Default for NftPairOptions {
+    fn default() -> Self {
+        Self {
+            reward_mult: 1.0,
+            sol_keypair: None,
+            solana_url: Url::from_str(SOLANA_MAIN_URL).unwrap(),
+            sol_ar_url: Url::from_str(SOL_AR_BASE_URL).unwrap().join("sol").unwrap(),
+            no_bundle: false,
+            buffer: 5,
+            log_dir: None,
+        }
     }
+}
 
-    // Create [`data_item::DataItem`] for bundle.
-    pub fn create_data_item(
-        &self,
-        data: Vec<u8>,
-        mut tags: Vec<Tag<String>>,
-        auto_content_tag: bool,
-    ) -> Result<DataItem, Error> {
-        tags.push(Tag::<String>::from_utf8_strs(
-            "User-Agent",
-            &format!("arloader/{}", VERSION),
-        )?);
+impl NftUploadOptions {
+    /// Link written to the `image` field of metadata, by file path if [`Self::link_file`].
+    pub fn image_link(&self) -> ImageLink {
+        if self.link_file {
+            ImageLink::File
+        } else {
+            ImageLink::Id
+        }
+    }
 
-        // Get content type from [magic numbers](https://developer.mozilla.org/en-US/docs/Web/HTTP/Basics_of_HTTP/MIME_types)
-        // and include additional tags if any.
-        if auto_content_tag {
-            let content_type = if let Some(kind) = infer::get(&data) {
-                kind.mime_type()
-            } else {
-                "application/octet-stream"
-            };
+    /// Links written to the `properties.files` array of metadata, both if [`Self::link_file`].
+    pub fn files_link(&self) -> FilesLink {
+        if self.link_file {
+            FilesLink::Both
+        } else {
+            FilesLink::Id
+        }
+    }
+}
 
-            tags.push(Tag::<String>::from_utf8_strs("Content-Type", content_type)?)
+impl Default for NftUploadOptions {
+    fn default() -> Self {
+        Self {
+            log_dir: None,
+            bundle_size: 100_000_000,
+            reward_mult: 1.0,
+            buffer: 5,
+            retry_policy: RetryPolicy::default(),
+            sol_keypair: None,
+            solana_url: Url::from_str(SOLANA_MAIN_URL).unwrap(),
+            sol_ar_url: Url::from_str(SOL_AR_BASE_URL).unwrap().join("sol").unwrap(),
+            link_file: false,
+            no_bundle: false,
+            truncate_names: false,
         }
+    }
+}
 
-        // let mut anchor = Base64(Vec::with_capacity(32));
-        // self.crypto.fill_rand(&mut anchor.0)?;
+/// Link written to the `image` field of NFT metadata by [`Arweave::update_metadata`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageLink {
+    /// `https://arweave.net/<ID>`
+    Id,
+    /// `https://arweave.net/<MANIFEST_ID>/<FILE_PATH>`
+    File,
+}
 
-        Ok(DataItem {
-            data: Base64(data),
-            tags,
-            // anchor,
-            ..DataItem::default()
-        })
+impl FromStr for ImageLink {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "id" => Ok(ImageLink::Id),
+            "file" => Ok(ImageLink::File),
+            _ => Err(Error::InvalidImageLink(s.to_string())),
+        }
     }
+}
 
-    pub async fn create_data_item_from_file_path(
-        &self,
-        file_path: PathBuf,
-        mut tags: Vec<Tag<String>>,
-    ) -> Result<(DataItem, Status), Error> {
-        let mut auto_content_tag = true;
-        let mut status_content_type = mime_guess::mime::OCTET_STREAM.to_string();
-
-        if let Some(content_type) = mime_guess::from_path(file_path.clone()).first() {
-            status_content_type = content_type.to_string();
-            auto_content_tag = false;
-            let content_tag: Tag<String> =
-                Tag::from_utf8_strs("Content-Type", &content_type.to_string())?;
-            tags.push(content_tag);
-        }
+/// Links written to the `properties.files` array of NFT metadata by [`Arweave::update_metadata`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilesLink {
+    Id,
+    File,
+    /// The id link followed by the file link.
+    Both,
+}
 
-        let data = fs::read(&file_path).await?;
-        let data_item = self.create_data_item(data, tags, auto_content_tag)?;
-        let data_item = self.sign_data_item(data_item)?;
+impl FromStr for FilesLink {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "id" => Ok(FilesLink::Id),
+            "file" => Ok(FilesLink::File),
+            "both" => Ok(FilesLink::Both),
+            _ => Err(Error::InvalidFilesLink(s.to_string())),
+        }
+    }
+}
 
-        let status = Status {
-            id: data_item.id.clone(),
-            file_path: Some(file_path),
-            content_type: status_content_type,
-            ..Status::default()
-        };
+/// Keys of a manifest written by [`Arweave::write_manifest`] normalized with [`utils::path_key`],
+/// for looking up files by paths that may be written differently from the keys.
+struct ManifestKeys<'a> {
+    /// `None` for paths that more than one key normalizes to.
+    keys: HashMap<PathBuf, Option<(&'a String, &'a Value)>>,
+}
 
-        Ok((data_item, status))
+impl<'a> ManifestKeys<'a> {
+    fn new(manifest: &'a serde_json::Map<String, Value>) -> Self {
+        let mut keys = HashMap::new();
+        for (key, path_object) in manifest {
+            keys.entry(utils::path_key(Path::new(key)))
+                .and_modify(|entry| *entry = None)
+                .or_insert(Some((key, path_object)));
+        }
+        Self { keys }
     }
 
-    pub async fn create_data_items_from_file_paths(
-        &self,
-        paths: Vec<PathBuf>,
-        tags: Vec<Tag<String>>,
-    ) -> Result<Vec<(DataItem, Status)>, Error> {
-        try_join_all(
-            paths
-                .into_iter()
-                .map(|p| self.create_data_item_from_file_path(p, tags.clone())),
-        )
-        .await
+    /// Returns the key and entry for `file_path` if exactly one key matches it, falling back to
+    /// the only key that ends with it, or that it ends with, if no key matches.
+    fn get(&self, file_path: &Path) -> Option<(&'a String, &'a Value)> {
+        let path = utils::path_key(file_path);
+        if let Some(entry) = self.keys.get(&path) {
+            return *entry;
+        }
+        let mut matches = self
+            .keys
+            .iter()
+            .filter(|(key, _)| key.ends_with(&path) || path.ends_with(key));
+        match (matches.next(), matches.next()) {
+            (Some((_, entry)), None) => *entry,
+            _ => None,
+        }
     }
+}
 
-    // Tested here instead of data_item to verify signature as well - crytpo on data_item.
-    pub fn deserialize_bundle(&self, bundle: Vec<u8>) -> Result<Vec<DataItem>, Error> {
-        let mut bundle_iter = bundle.into_iter();
-        let result = [(); 8].map(|_| bundle_iter.next().unwrap());
-        let number_of_data_items = u64::from_le_bytes(result) as usize;
-        (0..24).for_each(|_| {
-            bundle_iter.next().unwrap();
-        });
-
-        // Parse headers.
-        let mut bytes_lens = Vec::<u64>::with_capacity(number_of_data_items);
-        let mut ids = vec![Vec::<u8>::with_capacity(32); number_of_data_items];
-        (0..number_of_data_items).for_each(|i| {
-            let result = [(); 8].map(|_| bundle_iter.next().unwrap());
-            bytes_lens.push(u64::from_le_bytes(result));
-            (0..24).for_each(|_| {
-                bundle_iter.next().unwrap();
-            });
-            (0..32).for_each(|_| {
-                ids[i].push(bundle_iter.next().unwrap());
-            });
-        });
-
-        // Parse data_items - data_item verified during deserialization - signatures verified
-        // TODO: verify signature against data_item id.
-        let mut bytes_lens_iter = bytes_lens.into_iter();
-        let mut ids_iter = ids.into_iter();
-        let data_items: Result<Vec<DataItem>, _> = (0..number_of_data_items)
-            .map(|_| {
-                let bytes_len = bytes_lens_iter.next().unwrap() as usize;
-                let mut bytes_vec = Vec::<u8>::with_capacity(bytes_len);
-                (0..bytes_len).for_each(|_| bytes_vec.push(bundle_iter.next().unwrap()));
-                let mut data_item = DataItem::deserialize(bytes_vec)?;
-
-                let deep_hash = self
-                    .crypto
-                    .deep_hash(data_item.to_deep_hash_item()?)
-                    .unwrap();
-                self.crypto
-                    .verify(&data_item.signature.0, &deep_hash)
-                    .unwrap();
+/// Returns the link for the `image` field and the `properties.files` array for `file_path` in the
+/// manifest written by [`Arweave::write_manifest`], where `path_object` is its entry. Links
+/// through the manifest are taken from the entry, since they're relative to the directory the
+/// manifest was created from rather than the local path.
+pub fn metadata_links(
+    file_path: &str,
+    path_object: &Value,
+    image_link: ImageLink,
+    files_link: FilesLink,
+) -> Result<(String, Vec<Value>), Error> {
+    let id_link = format!(
+        "https://arweave.net/{}",
+        path_object["id"]
+            .as_str()
+            .ok_or_else(|| Error::InvalidManifestEntry(file_path.to_string()))?
+    );
+    let file_link = manifest_file_link(file_path, path_object)?;
+    let content_type = &path_object["files"][0]["type"];
+    let file = |uri: &str| json!({"uri": uri, "type": content_type});
+
+    let image = match image_link {
+        ImageLink::Id => id_link.clone(),
+        ImageLink::File => file_link.to_string(),
+    };
+    let files = match files_link {
+        FilesLink::Id => vec![file(&id_link)],
+        FilesLink::File => vec![file(file_link)],
+        FilesLink::Both => vec![file(&id_link), file(file_link)],
+    };
+    Ok((image, files))
+}
 
-                data_item.id.0 = ids_iter.next().unwrap();
+/// Paths object of `manifest`, keyed by file path, returning [`Error::ManifestPathsMissing`] if it
+/// doesn't have one.
+fn manifest_paths(manifest: &Value) -> Result<&serde_json::Map<String, Value>, Error> {
+    manifest["paths"]
+        .as_object()
+        .ok_or(Error::ManifestPathsMissing)
+}
 
-                Ok(data_item)
-            })
-            .collect();
+/// Link through the manifest to `file_path` recorded in its entry of a manifest written by
+/// [`Arweave::write_manifest`], see [`Arweave::manifest_links`].
+fn manifest_file_link<'a>(file_path: &str, path_object: &'a Value) -> Result<&'a str, Error> {
+    path_object["files"][1]["uri"]
+        .as_str()
+        .ok_or_else(|| Error::InvalidManifestEntry(file_path.to_string()))
+}
 
-        data_items
+/// Writes `image_link` and `animation_url_link` to `metadata`, if provided, and appends
+/// `files_array` to its `properties.files` array, returning [`Error::MetadataNotObject`] if
+/// `metadata` or its `properties` isn't a json object.
+pub fn link_metadata(
+    metadata: &mut Value,
+    mut files_array: Vec<Value>,
+    image_link: Option<String>,
+    animation_url_link: Option<String>,
+) -> Result<(), Error> {
+    let metadata = metadata.as_object_mut().ok_or(Error::MetadataNotObject)?;
+
+    if let Some(image_link) = image_link {
+        metadata.insert("image".to_string(), Value::String(image_link));
     }
 
-    pub async fn post_bundle_transaction_from_file_paths(
-        &self,
-        paths_chunk: PathsChunk,
-        tags: Vec<Tag<String>>,
-        price_terms: (u64, u64),
-        buffer: usize,
-    ) -> Result<BundleStatus, Error> {
-        let number_of_files = paths_chunk.0.len() as u64;
-        let data_items = self
-            .create_data_items_from_file_paths(paths_chunk.0, tags)
-            .await?;
+    if let Some(animation_url_link) = animation_url_link {
+        metadata.insert(
+            "animation_url".to_string(),
+            Value::String(animation_url_link),
+        );
+    }
 
-        let (bundle, manifest) = self.create_bundle_from_data_items(data_items)?;
-        let other_tags = Some(vec![
-            Tag::<Base64>::from_utf8_strs("Bundle-Format", "binary")?,
-            Tag::<Base64>::from_utf8_strs("Bundle-Version", "2.0.0")?,
-        ]);
+    let properties = metadata
+        .entry("properties")
+        .or_insert_with(|| json!({}))
+        .as_object_mut()
+        .ok_or(Error::MetadataNotObject)?;
 
-        let transaction = self
-            .create_transaction(bundle, other_tags, None, price_terms, true)
-            .await?;
+    match properties.get_mut("files").and_then(Value::as_array_mut) {
+        Some(files) => files.append(&mut files_array),
+        None => {
+            properties.insert("files".to_string(), Value::Array(files_array));
+        }
+    }
+    Ok(())
+}
 
-        let signed_transaction = self.sign_transaction(transaction)?;
+/// Steps of [`Arweave::upload_nft_collection`], in the order they're run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NftUploadStage {
+    Assets,
+    AssetManifest,
+    MetadataLinks,
+    Metadata,
+    MetadataManifest,
+    MetaplexItems,
+}
 
-        let (id, reward) = if paths_chunk.1 > MAX_TX_DATA {
-            self.post_transaction_chunks(signed_transaction, buffer)
-                .await?
-        } else {
-            self.post_transaction(&signed_transaction).await?
+impl std::fmt::Display for NftUploadStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let stage = match self {
+            NftUploadStage::Assets => "assets",
+            NftUploadStage::AssetManifest => "asset manifest",
+            NftUploadStage::MetadataLinks => "metadata links",
+            NftUploadStage::Metadata => "metadata",
+            NftUploadStage::MetadataManifest => "metadata manifest",
+            NftUploadStage::MetaplexItems => "metaplex items",
         };
+        write!(f, "{}", stage)
+    }
+}
 
-        let status = BundleStatus {
-            id,
-            reward,
-            number_of_files,
-            data_size: paths_chunk.1,
-            file_paths: manifest["paths"].clone(),
-            ..Default::default()
-        };
+/// Uploaded asset and metadata of a single token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NftToken {
+    pub asset_path: PathBuf,
+    pub image_id: Base64,
+    pub metadata_id: Base64,
+    /// Link written to the `image` field of the token's metadata.
+    pub image_link: String,
+    /// Link to the token's uploaded metadata, as written to the metaplex items file.
+    pub metadata_link: String,
+}
 
-        Ok(status)
+/// Returned by [`Arweave::upload_nft_collection`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct NftUploadResult {
+    pub log_dir: PathBuf,
+    pub asset_manifest_id: Base64,
+    pub metadata_manifest_id: Base64,
+    pub metaplex_items_path: PathBuf,
+    pub tokens: Vec<NftToken>,
+    /// Sum of the rewards in winstons of every transaction posted, including manifests.
+    pub reward: u64,
+}
+
+/// Wraps errors from a step of [`Arweave::upload_nft_collection`] other than uploading files,
+/// which leaves all of `file_paths` to be processed.
+pub(crate) fn nft_stage_error(
+    stage: NftUploadStage,
+    file_paths: &[PathBuf],
+) -> impl FnOnce(Error) -> Error {
+    let file_paths = file_paths.to_vec();
+    move |error| Error::NftUploadFailed {
+        stage,
+        file_paths,
+        source: Box::new(error),
     }
+}
 
-    pub async fn post_bundle_transaction_from_file_paths_with_sol(
-        &self,
-        paths_chunk: PathsChunk,
-        tags: Vec<Tag<String>>,
-        price_terms: (u64, u64),
-        chunks_buffer: usize,
-        solana_url: Url,
-        sol_ar_url: Url,
-        from_keypair: &Keypair,
-    ) -> Result<BundleStatus, Error> {
-        let number_of_files = paths_chunk.0.len() as u64;
-        let data_items = self
-            .create_data_items_from_file_paths(paths_chunk.0, tags)
-            .await?;
+/// Wraps an error from `stage` of [`Arweave::upload_nft_pair`] in [`Error::NftPairUploadFailed`].
+pub(crate) fn nft_pair_stage_error(
+    stage: NftUploadStage,
+    image_id: Option<&Base64>,
+) -> impl FnOnce(Error) -> Error {
+    let image_id = image_id.cloned();
+    move |error| Error::NftPairUploadFailed {
+        stage,
+        image_id,
+        source: Box::new(error),
+    }
+}
 
-        let (bundle, manifest) = self.create_bundle_from_data_items(data_items)?;
-        let other_tags = Some(vec![
-            Tag::<Base64>::from_utf8_strs("Bundle-Format", "binary")?,
-            Tag::<Base64>::from_utf8_strs("Bundle-Version", "2.0.0")?,
-        ]);
+/// Contents of metadata files before they're linked to a manifest that's posted at the same time,
+/// so that they can be put back if it isn't posted. Files that can't be read are left out.
+pub(crate) struct MetadataBackup(Vec<(PathBuf, Vec<u8>)>);
 
-        let transaction = self
-            .create_transaction(bundle, other_tags, None, price_terms, true)
-            .await?;
+impl MetadataBackup {
+    pub(crate) async fn read(paths: &[PathBuf]) -> Self {
+        let contents = join_all(paths.iter().map(fs::read)).await;
+        Self(
+            paths
+                .iter()
+                .cloned()
+                .zip(contents)
+                .filter_map(|(path, data)| Some((path, data.ok()?)))
+                .collect(),
+        )
+    }
 
-        let (signed_transaction, sig_response): (Transaction, SigResponse) = self
-            .sign_transaction_with_sol(transaction, solana_url, sol_ar_url, from_keypair)
-            .await?;
+    /// Puts back the metadata files, unless `manifest_posted` or the manifest was paid for with
+    /// SOL, `paid`, since it's then kept to be posted again and the links stay valid.
+    pub(crate) async fn restore_unless_posted<T>(
+        self,
+        manifest_posted: &Result<T, Error>,
+        paid: bool,
+    ) -> Result<(), Error> {
+        if manifest_posted.is_ok() || paid {
+            return Ok(());
+        }
+        try_join_all(self.0.iter().map(|(path, data)| fs::write(path, data))).await?;
+        Ok(())
+    }
+}
 
-        let (id, reward) = if paths_chunk.1 > MAX_TX_DATA {
-            self.post_transaction_chunks(signed_transaction, chunks_buffer)
-                .await?
-        } else {
-            self.post_transaction(&signed_transaction).await?
+/// Collects the results of an upload stream, passing each upload to `write` as it's yielded so
+/// that its status is kept even if others fail. Fails with the file paths of every failed upload,
+/// or upload that couldn't be written, attributed to `stage`.
+async fn collect_nft_uploads<S, T, F, Fut>(
+    mut stream: S,
+    stage: NftUploadStage,
+    mut write: F,
+) -> Result<Vec<T>, Error>
+where
+    S: Stream<Item = Result<T, FailedUpload>> + Unpin,
+    F: FnMut(&T) -> Fut,
+    Fut: Future<Output = Result<(), FailedUpload>>,
+{
+    let mut uploaded = Vec::new();
+    let mut failed: Option<FailedUpload> = None;
+    while let Some(result) = stream.next().await {
+        let result = match result {
+            Ok(item) => write(&item).await.map(|()| item),
+            Err(e) => Err(e),
         };
+        match result {
+            Ok(item) => uploaded.push(item),
+            Err(e) => {
+                if let Some(failed) = &mut failed {
+                    failed.file_paths.extend(e.file_paths);
+                } else {
+                    failed = Some(e);
+                }
+            }
+        }
+    }
+    match failed {
+        Some(FailedUpload { error, file_paths }) => Err(Error::NftUploadFailed {
+            stage,
+            file_paths,
+            source: Box::new(error),
+        }),
+        None => Ok(uploaded),
+    }
+}
 
-        let status = BundleStatus {
-            id,
-            reward,
-            number_of_files,
-            data_size: paths_chunk.1,
-            file_paths: manifest["paths"].clone(),
-            sol_sig: Some(sig_response),
-            ..Default::default()
-        };
+/// Number of [`BLOCK_SIZE`] blocks that `data_size` bytes are priced in, counting a partial block
+/// as a whole one.
+#[deprecated(since = "0.1.64", note = "use `NetworkParams::blocks_len` instead")]
+pub fn blocks_len(data_size: u64) -> u64 {
+    NetworkParams::default().blocks_len(data_size)
+}
 
-        Ok(status)
-    }
+/// Reward for a transaction of `data_size` bytes given `price_terms` of base winstons and
+/// incremental winstons per [`BLOCK_SIZE`].
+#[deprecated(
+    since = "0.1.64",
+    note = "use `NetworkParams::transaction_reward` instead"
+)]
+pub fn transaction_reward(data_size: u64, price_terms: (u64, u64)) -> u64 {
+    NetworkParams::default().transaction_reward(data_size, price_terms)
+}
 
-    pub fn sign_data_item(&self, mut data_item: DataItem) -> Result<DataItem, Error> {
-        data_item.owner = self.crypto.keypair_modulus()?;
-        let deep_hash_item = data_item.to_deep_hash_item()?;
-        let deep_hash = self.crypto.deep_hash(deep_hash_item)?;
-        let signature = self.crypto.sign(&deep_hash)?;
-        let id = self.crypto.hash_sha256(&signature)?;
+/// Network parameters that [`Arweave`] prices and posts transactions with, defaulting to
+/// [`BLOCK_SIZE`], [`MAX_TX_DATA`] and [`MAX_BUNDLE_SIZE`]. The gateway `info` endpoint doesn't
+/// report them, so they're overridden by setting [`Arweave::network_params`], which the cli does
+/// with `--block-size`, `--max-tx-body` and `--max-bundle-size`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NetworkParams {
+    /// Bytes of data priced per block.
+    pub block_size: u64,
+    /// Largest data posted whole to the `tx/` endpoint, larger data is posted in chunks.
+    pub max_tx_body: u64,
+    /// Largest bundle size in bytes.
+    pub max_bundle_size: u64,
+}
 
-        data_item.signature = Base64(signature);
-        data_item.id = Base64(id.to_vec());
-        Ok(data_item)
+impl Default for NetworkParams {
+    fn default() -> Self {
+        Self {
+            block_size: BLOCK_SIZE,
+            max_tx_body: MAX_TX_DATA,
+            max_bundle_size: MAX_BUNDLE_SIZE,
+        }
     }
+}
 
-    //-------------------------
-    // Transaction
-    //-------------------------
+impl NetworkParams {
+    /// Number of blocks that `data_size` bytes are priced in, counting a partial block as a
+    /// whole one.
+    pub fn blocks_len(&self, data_size: u64) -> u64 {
+        data_size / self.block_size + (data_size % self.block_size != 0) as u64
+    }
 
-    pub async fn create_transaction(
-        &self,
-        data: Vec<u8>,
-        other_tags: Option<Vec<Tag<Base64>>>,
-        last_tx: Option<Base64>,
-        price_terms: (u64, u64),
-        auto_content_tag: bool,
-    ) -> Result<Transaction, Error> {
-        let mut transaction = self.merklize(data)?;
-        transaction.owner = self.crypto.keypair_modulus()?;
+    /// Reward for a transaction of `data_size` bytes given `price_terms` of base winstons and
+    /// incremental winstons per block.
+    pub fn transaction_reward(&self, data_size: u64, price_terms: (u64, u64)) -> u64 {
+        price_terms.0 + price_terms.1 * self.blocks_len(data_size).saturating_sub(1)
+    }
 
-        let mut tags = vec![Tag::<Base64>::from_utf8_strs(
-            "User-Agent",
-            &format!("arloader/{}", VERSION),
-        )?];
+    /// [`NetworkParams::transaction_reward`] with the price terms and number of blocks it was
+    /// calculated from.
+    pub fn reward_breakdown(&self, data_size: u64, price_terms: (u64, u64)) -> RewardBreakdown {
+        RewardBreakdown {
+            base: price_terms.0,
+            incremental: price_terms.1,
+            blocks: self.blocks_len(data_size),
+            total: self.transaction_reward(data_size, price_terms),
+        }
+    }
 
-        // Get content type from [magic numbers](https://developer.mozilla.org/en-US/docs/Web/HTTP/Basics_of_HTTP/MIME_types)
-        // and include additional tags if any.
-        if auto_content_tag {
-            let content_type = if let Some(kind) = infer::get(&transaction.data.0) {
-                kind.mime_type()
-            } else {
-                "application/octet-stream"
-            };
+    /// Whether `data_size` bytes are posted in chunks rather than whole to the `tx/` endpoint.
+    pub fn posts_chunks(&self, data_size: u64) -> bool {
+        data_size > self.max_tx_body
+    }
+}
 
-            tags.push(Tag::<Base64>::from_utf8_strs("Content-Type", content_type)?)
-        }
+/// Unsigned transaction or data item with the deep hash it would be signed over, see
+/// [`Arweave::deep_hash_preview`].
+#[derive(Debug)]
+pub struct DeepHashPreview {
+    pub deep_hash: [u8; 48],
+    pub deep_hash_item: DeepHashItem,
+    /// Unsigned transaction or data item as json.
+    pub unsigned: Value,
+}
 
-        // Add other tags if provided.
-        if let Some(other_tags) = other_tags {
-            tags.extend(other_tags);
+impl DeepHashPreview {
+    pub fn deep_hash_hex(&self) -> String {
+        self.deep_hash
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+/// Collects results of reading statuses, printing to stderr and skipping those written by newer
+/// versions of arloader, see [`Error::UnsupportedStatusVersion`].
+pub fn skip_unsupported_statuses<T>(results: Vec<Result<T, Error>>) -> Result<Vec<T>, Error> {
+    results
+        .into_iter()
+        .filter_map(|result| match result {
+            Err(e @ Error::UnsupportedStatusVersion { .. }) => {
+                eprintln!("{}", e);
+                None
+            }
+            result => Some(result),
+        })
+        .collect()
+}
+
+/// Returns the content type of a file from its extension, falling back to `application/json` for
+/// files without an extension that contain a JSON object or array.
+pub fn content_type_from_path(file_path: &Path, data: &[u8]) -> Option<String> {
+    if let Some(content_type) = mime_guess::from_path(file_path).first() {
+        return Some(content_type.to_string());
+    }
+    match (file_path.extension(), serde_json::from_slice::<Value>(data)) {
+        (None, Ok(Value::Object(_) | Value::Array(_))) => {
+            Some(mime_guess::mime::APPLICATION_JSON.to_string())
         }
-        transaction.tags = tags;
+        _ => None,
+    }
+}
 
-        // Fetch and set last_tx if not provided (primarily for testing).
-        let last_tx = if let Some(last_tx) = last_tx {
-            last_tx
-        } else {
-            let resp = reqwest::get(self.base_url.join("tx_anchor")?).await?;
-            debug!("last_tx: {}", resp.status());
-            let last_tx_str = resp.text().await?;
-            Base64::from_str(&last_tx_str)?
-        };
-        transaction.last_tx = last_tx;
+/// Returns [`Error::InvalidId`] unless `id` is 32 bytes, the length of transaction and data item
+/// ids.
+fn check_id(id: &Base64) -> Result<(), Error> {
+    match id.0.len() {
+        32 => Ok(()),
+        _ => Err(Error::InvalidId(id.to_string())),
+    }
+}
 
-        let blocks_len =
-            transaction.data_size / BLOCK_SIZE + (transaction.data_size % BLOCK_SIZE != 0) as u64;
-        let reward = price_terms.0 + price_terms.1 * (blocks_len - 1);
-        transaction.reward = reward;
+/// Removes a `Content-Type` tag from the tags of data items, returning its value to use instead of
+/// the content type detected for each file. The tag is added back to each data item with the
+/// override as its value, after the other tags.
+fn take_content_type_override(tags: &mut Vec<Tag<String>>) -> Option<String> {
+    let content_type = tags
+        .iter()
+        .position(|t| t.name == "Content-Type")
+        .map(|i| tags.remove(i).value)?;
+    debug!(
+        "Content-Type tag {} overrides detected content types",
+        content_type
+    );
+    Some(content_type)
+}
 
-        Ok(transaction)
+/// Appends the `Content-Type` tag of a data item of the file at `file_path` to `tags`, using
+/// `content_type_override` if provided instead of detecting it from `data`. Returns the content
+/// type, or `None` if there isn't one and the data item needs an automatic content tag.
+fn push_data_item_content_type(
+    file_path: &Path,
+    data: &[u8],
+    tags: &mut Vec<Tag<String>>,
+    content_type_override: Option<&str>,
+) -> Result<Option<String>, Error> {
+    let content_type = content_type_override
+        .map(String::from)
+        .or_else(|| content_type_from_path(file_path, data));
+    if let Some(content_type) = &content_type {
+        tags.push(Tag::from_utf8_strs("Content-Type", content_type)?);
     }
+    Ok(content_type)
+}
 
-    pub async fn create_transaction_from_file_path(
-        &self,
-        file_path: PathBuf,
-        other_tags: Option<Vec<Tag<Base64>>>,
-        last_tx: Option<Base64>,
-        price_terms: (u64, u64),
-        auto_content_tag: bool,
-    ) -> Result<Transaction, Error> {
-        let data = fs::read(file_path).await?;
-        self.create_transaction(data, other_tags, last_tx, price_terms, auto_content_tag)
-            .await
+/// Tags of a transaction uploading the file at `file_path`. A `Content-Type` tag in
+/// `additional_tags` is kept where it is, otherwise one guessed from the file extension is
+/// appended. Returns the tags, the content type for the status of the file and whether the
+/// transaction needs an automatic content tag.
+fn file_transaction_tags(
+    file_path: &Path,
+    additional_tags: Option<Vec<Tag<Base64>>>,
+) -> Result<(Option<Vec<Tag<Base64>>>, String, bool), Error> {
+    let content_type_override = additional_tags
+        .iter()
+        .flatten()
+        .find(|t| t.name.to_utf8_string().ok().as_deref() == Some("Content-Type"))
+        .map(|t| t.value.to_utf8_string())
+        .transpose()?;
+    if let Some(content_type) = content_type_override {
+        return Ok((additional_tags, content_type, false));
+    }
+    match mime_guess::from_path(file_path).first() {
+        Some(content_type) => {
+            let content_tag = Tag::<Base64>::from_utf8_strs("Content-Type", content_type.as_ref())?;
+            let mut tags = additional_tags.unwrap_or_default();
+            tags.push(content_tag);
+            Ok((Some(tags), content_type.to_string(), false))
+        }
+        None => Ok((
+            additional_tags,
+            mime_guess::mime::OCTET_STREAM.to_string(),
+            true,
+        )),
     }
+}
 
-    pub fn merklize(&self, data: Vec<u8>) -> Result<Transaction, Error> {
-        let mut chunks = generate_leaves(data.clone(), &self.crypto)?;
-        let root = generate_data_root(chunks.clone(), &self.crypto)?;
-        let data_root = Base64(root.id.clone().into_iter().collect());
-        let mut proofs = resolve_proofs(root, None)?;
+/// Used in updating [`BundleStatus`]s to determine whether a file stem includes a valid transaction id.
+pub fn file_stem_is_valid_txid(file_path: &PathBuf) -> bool {
+    match Base64::from_str(file_path.file_stem().unwrap().to_str().unwrap()) {
+        Ok(txid) => match txid.0.len() {
+            32 => true,
+            _ => false,
+        },
+        Err(_) => false,
+    }
+}
 
-        // Discard the last chunk & proof if it's zero length.
-        let last_chunk = chunks.last().unwrap();
-        if last_chunk.max_byte_range == last_chunk.min_byte_range {
-            chunks.pop();
-            proofs.pop();
-        }
+//=========================
+// Arweave
+//=========================
 
-        Ok(Transaction {
-            format: 2,
-            data_size: data.len() as u64,
-            data: Base64(data),
-            data_root,
-            chunks,
-            proofs,
-            ..Default::default()
+/// Struct with methods for interacting with the Arweave network.
+pub struct Arweave {
+    pub name: String,
+    pub units: String,
+    pub base_url: Url,
+    pub crypto: crypto::Provider,
+    /// Writes status files as pretty printed json.
+    pub pretty_status: bool,
+    /// Directory in which signed data items are cached, so that they can be reused instead of
+    /// created and signed again if the same files are uploaded again.
+    pub item_cache: Option<PathBuf>,
+    /// Transfer added to each transaction created with [`Arweave::create_transaction`], used to
+    /// tip a wallet while uploading.
+    pub tip: Option<Tip>,
+    /// Refuses to post anything to the network, returning [`Error::ReadOnlyMode`] instead.
+    pub read_only: bool,
+    /// Rejects manifest paths that need url escaping instead of percent-encoding them, see
+    /// [`utils::manifest_key`].
+    pub strict_paths: bool,
+    /// Returns [`Error::InsufficientReward`] instead of raising the reward of bundle
+    /// transactions that don't cover their serialized size, see [`Arweave::check_bundle_reward`].
+    pub strict_reward: bool,
+    /// Reused by [`Arweave::get_network_info`] for [`NETWORK_INFO_TTL`] seconds.
+    pub network_info: NetworkInfoCache,
+    /// Reused by [`Arweave::cached_tx_anchor`] for [`TX_ANCHOR_TTL`] seconds.
+    pub tx_anchor: AnchorCache,
+    /// Count of retried transaction and chunk posts, shared between clones.
+    pub post_retries: Arc<AtomicUsize>,
+    /// Gzip encodes transaction and chunk request bodies, see [`GzipStats`].
+    pub gzip_requests: bool,
+    pub gzip_stats: GzipStats,
+    /// Pause that requests wait on after any of them is rate limited, see [`Cooldown`].
+    pub cooldown: Cooldown,
+    /// Sets the anchor of data items to a hash of their data, see [`bundle::anchor_from_hash`].
+    /// Standalone transactions aren't affected, since their `last_tx` has to be a recent block
+    /// or transaction.
+    pub anchor_from_hash: bool,
+    /// Tags added to each bundle transaction, with placeholders in their names and values
+    /// resolved from the files in the bundle, see [`bundle::resolve_bundle_tags`].
+    pub bundle_tags: Vec<Tag<String>>,
+    /// Client that all requests are made with, so that connections are reused, see
+    /// [`build_client`] and [`Arweave::with_client`].
+    pub client: Client,
+    /// Connections opened by [`Arweave::client`] and requests made with it. Connections of a
+    /// client passed to [`Arweave::with_client`] are only counted if it was built with these.
+    pub connection_stats: ConnectionStats,
+    /// Solana keypair that data items with [`SignatureType::Ed25519`] are signed with, see
+    /// [`Arweave::sign_data_item`].
+    pub sol_keypair: Option<Keypair>,
+    /// Block size and size limits that transactions are priced and posted with.
+    pub network_params: NetworkParams,
+    /// Directory that the paths of files in manifests are relative to, see
+    /// [`utils::relative_manifest_path`]. Defaults to the common parent of the files in each
+    /// manifest.
+    pub manifest_base_dir: Option<PathBuf>,
+    /// Path, relative to the manifest, of the file served for paths that aren't in manifests
+    /// created from here on, e.g., `404.html`, see [`Arweave::set_manifest_fallback`].
+    pub manifest_fallback: Option<String>,
+    /// Most paths a manifest can have, since gateways struggle to serve larger ones. Larger
+    /// manifests are an [`Error::TooManyManifestPaths`] unless [`Arweave::split_manifests`].
+    pub max_manifest_paths: usize,
+    /// Uploads manifests with more than [`Arweave::max_manifest_paths`] paths as several
+    /// manifests split by directory, see [`Arweave::upload_manifest`].
+    pub split_manifests: bool,
+}
+
+/// Same as [`Arweave::try_default`], panicking if the default keypair is invalid or the client
+/// can't be built.
+impl Default for Arweave {
+    fn default() -> Self {
+        Self::try_default().expect("failed to create default Arweave")
+    }
+}
+
+impl Arweave {
+    /// Signs with `crypto` and makes requests to `base_url`, with defaults for everything else,
+    /// returning an error if the client can't be built, see [`build_client`].
+    pub fn new(crypto: crypto::Provider, base_url: Url) -> Result<Arweave, Error> {
+        let connection_stats = ConnectionStats::default();
+        Ok(Self {
+            name: String::from("arweave"),
+            units: String::from("winstons"),
+            base_url,
+            crypto,
+            pretty_status: false,
+            item_cache: None,
+            tip: None,
+            read_only: false,
+            strict_paths: false,
+            strict_reward: false,
+            network_info: NetworkInfoCache::default(),
+            tx_anchor: AnchorCache::default(),
+            post_retries: Arc::new(AtomicUsize::new(0)),
+            gzip_requests: false,
+            gzip_stats: GzipStats::default(),
+            cooldown: Cooldown::default(),
+            anchor_from_hash: false,
+            bundle_tags: Vec::new(),
+            client: build_client(None, &connection_stats)?,
+            connection_stats,
+            sol_keypair: None,
+            network_params: NetworkParams::default(),
+            manifest_base_dir: None,
+            manifest_fallback: None,
+            max_manifest_paths: MAX_MANIFEST_PATHS,
+            split_manifests: false,
         })
     }
 
-    pub async fn post_chunk(&self, chunk: &Chunk, client: &Client) -> Result<usize, Error> {
-        let url = self.base_url.join("chunk")?;
-        // let client = reqwest::Client::new();
-
-        let resp = client
-            .post(url)
-            .json(&chunk)
-            .header(&ACCEPT, "application/json")
-            .header(&CONTENT_TYPE, "application/json")
-            .send()
-            .await
-            .map_err(|e| Error::ArweavePostError(e))?;
+    /// Uses the keypair from [`crypto::Provider::from_env`] and makes requests to
+    /// `https://arweave.net/`, returning an error instead of panicking like [`Arweave::default`].
+    pub fn try_default() -> Result<Arweave, Error> {
+        Self::try_default_with_url(Url::from_str("https://arweave.net/").unwrap())
+    }
 
-        match resp.status() {
-            reqwest::StatusCode::OK => Ok(chunk.offset),
-            _ => Err(Error::StatusCodeNotOk),
-        }
+    /// [`Arweave::try_default`] with requests made to `base_url`.
+    pub fn try_default_with_url(base_url: Url) -> Result<Arweave, Error> {
+        Self::new(crypto::Provider::from_env()?, base_url)
     }
 
-    pub async fn post_chunk_with_retries(
-        &self,
-        chunk: Chunk,
-        client: Client,
-    ) -> Result<usize, Error> {
-        let mut retries = 0;
-        let mut resp = self.post_chunk(&chunk, &client).await;
+    pub async fn from_keypair_path(keypair_path: PathBuf, base_url: Url) -> Result<Arweave, Error> {
+        let crypto = crypto::Provider::from_keypair_path(keypair_path).await?;
+        Self::new(crypto, base_url)
+    }
 
-        while retries < CHUNKS_RETRIES {
-            match resp {
-                Ok(offset) => return Ok(offset),
-                Err(e) => {
-                    log::debug!("post_chunk_with_retries: {:?}", e);
-                    sleep(Duration::from_secs(CHUNKS_RETRY_SLEEP)).await;
-                    retries += 1;
-                    resp = self.post_chunk(&chunk, &client).await;
-                }
-            }
-        }
-        resp
+    /// Uses the keypair from [`crypto::Provider::machine_default`], used with
+    /// `--ar-default-keypair`.
+    pub fn from_default_keypair(base_url: Url) -> Result<Arweave, Error> {
+        let crypto = crypto::Provider::machine_default()?;
+        Self::new(crypto, base_url)
     }
 
-    pub async fn post_transaction(
-        &self,
-        signed_transaction: &Transaction,
-    ) -> Result<(Base64, u64), Error> {
-        if signed_transaction.id.0.is_empty() {
-            return Err(error::Error::UnsignedTransaction.into());
+    /// Makes requests with `client` instead of the one from [`build_client`], e.g., to set a
+    /// timeout or go through a proxy.
+    pub fn with_client(self, client: Client) -> Arweave {
+        Arweave { client, ..self }
+    }
+
+    /// [`Arweave::default`] with requests made to `base_url`.
+    pub fn default_with_url(base_url: Url) -> Arweave {
+        Arweave {
+            base_url,
+            ..Default::default()
         }
+    }
 
-        let mut retries = 0;
-        let mut status = reqwest::StatusCode::NOT_FOUND;
-        let url = self.base_url.join("tx")?;
-        let client = reqwest::Client::new();
+    pub fn from_keypair_path_sync(keypair_path: PathBuf, base_url: Url) -> Result<Arweave, Error> {
+        let crypto = crypto::Provider::from_keypair_path_sync(keypair_path)?;
+        Self::new(crypto, base_url)
+    }
 
-        while (retries < CHUNKS_RETRIES) & (status != reqwest::StatusCode::OK) {
-            status = client
-                .post(url.clone())
-                .json(&signed_transaction)
-                .header(&ACCEPT, "application/json")
-                .header(&CONTENT_TYPE, "application/json")
-                .send()
-                .await?
-                .status();
-            if status == reqwest::StatusCode::OK {
-                return Ok((signed_transaction.id.clone(), signed_transaction.reward));
+    //-------------------------
+    // Get Request
+    //-------------------------
+
+    /// Gets current height, block hash and counts of blocks and peers from the network, reusing
+    /// the last response for [`NETWORK_INFO_TTL`] seconds. Requests go through
+    /// [`Arweave::client`] and are retried after network errors or 429 and 5xx responses as with
+    /// the default [`RetryPolicy`].
+    pub async fn get_network_info(&self) -> Result<NetworkInfo, Error> {
+        if let Some(info) = self.cached_network_info() {
+            return Ok(info);
+        }
+        let url = Endpoints::info(&self.base_url)?;
+        let retry_policy = RetryPolicy::default();
+        let mut retry = 0;
+        let resp = loop {
+            let error = match self.get(url.clone()).await {
+                Ok(resp) if resp.status().is_success() => break resp,
+                Ok(resp)
+                    if resp.status().is_server_error()
+                        || resp.status() == ResponseStatusCode::TOO_MANY_REQUESTS =>
+                {
+                    Error::ArweaveNetworkError(resp.status())
+                }
+                Ok(resp) => return Err(Error::ArweaveNetworkError(resp.status())),
+                Err(error) if error.is_retryable() => error,
+                Err(error) => return Err(error),
+            };
+            if retry >= retry_policy.retries {
+                return Err(error);
             }
-            log::debug!("post_transaction: {:?}", status);
-            sleep(Duration::from_secs(CHUNKS_RETRY_SLEEP)).await;
-            retries += 1;
+            debug!("get_network_info: {}", error);
+            sleep(retry_policy.delay_for(retry, &error)).await;
+            retry += 1;
+        };
+        let info: NetworkInfo = resp.json().await?;
+        *self.network_info.0.lock().unwrap() = Some((Instant::now(), info.clone()));
+        Ok(info)
+    }
+
+    fn cached_network_info(&self) -> Option<NetworkInfo> {
+        match &*self.network_info.0.lock().unwrap() {
+            Some((fetched, info)) if fetched.elapsed() < Duration::from_secs(NETWORK_INFO_TTL) => {
+                Some(info.clone())
+            }
+            _ => None,
         }
+    }
 
-        Err(Error::StatusCodeNotOk)
+    /// Get pending network transaction count.
+    pub async fn get_pending_count(&self) -> Result<usize, Error> {
+        let url = Endpoints::pending(&self.base_url)?;
+        let resp = self.send(self.client.get(url)).await?;
+        let tx_ids: Vec<String> =
+            parse_gateway_response(resp, |b| serde_json::from_str(b).ok()).await?;
+        Ok(tx_ids.len())
     }
 
-    pub async fn post_transaction_chunks(
-        &self,
-        signed_transaction: Transaction,
-        chunks_buffer: usize,
-    ) -> Result<(Base64, u64), Error> {
-        if signed_transaction.id.0.is_empty() {
-            return Err(error::Error::UnsignedTransaction.into());
-        }
+    /// Returns price of uploading data to the network in winstons and USD per AR and USD per SOL
+    /// as a BigUint with two decimals.
+    pub async fn get_price(&self, bytes: &u64) -> Result<(BigUint, BigUint, BigUint), Error> {
+        let winstons_per_bytes = BigUint::from(self.get_reward(*bytes).await?);
 
-        let transaction_with_no_data = signed_transaction.clone_with_no_data()?;
-        let (id, reward) = self.post_transaction(&transaction_with_no_data).await?;
+        let oracle_url =
+            "https://api.coingecko.com/api/v3/simple/price?ids=arweave,solana&vs_currencies=usd";
+        let prices = self
+            .send(self.client.get(oracle_url))
+            .await
+            .map_err(|e| Error::OracleGetPriceError(e))?
+            .json::<OraclePrice>()
+            .await?;
 
-        let results: Vec<Result<usize, Error>> =
-            upload_transaction_chunks_stream(&self, signed_transaction, chunks_buffer)
-                .collect()
-                .await;
+        let usd_per_ar: BigUint = BigUint::from((prices.arweave.usd * 100.0).floor() as u32);
+        let usd_per_sol: BigUint = BigUint::from((prices.solana.usd * 100.0).floor() as u32);
 
-        results.into_iter().collect::<Result<Vec<usize>, Error>>()?;
+        Ok((winstons_per_bytes, usd_per_ar, usd_per_sol))
+    }
 
-        Ok((id, reward))
+    /// Returns the network price in winstons of uploading `bytes` of data.
+    pub async fn get_reward(&self, bytes: u64) -> Result<u64, Error> {
+        let url = Endpoints::price(&self.base_url, bytes)?;
+        let resp = self
+            .send(self.client.get(url))
+            .await
+            .map_err(|e| Error::ArweaveGetPriceError(e))?;
+        parse_gateway_response(resp, |b| b.trim().parse::<u64>().ok()).await
     }
 
-    /// Gets deep hash, signs and sets signature and id.
-    pub fn sign_transaction(&self, mut transaction: Transaction) -> Result<Transaction, Error> {
-        let deep_hash_item = transaction.to_deep_hash_item()?;
-        let deep_hash = self.crypto.deep_hash(deep_hash_item)?;
-        let signature = self.crypto.sign(&deep_hash)?;
-        let id = self.crypto.hash_sha256(&signature)?;
-        transaction.signature = Base64(signature);
-        transaction.id = Base64(id.to_vec());
-        Ok(transaction)
+    /// Gets base and incremental prices for a block of [`NetworkParams::block_size`] bytes.
+    pub async fn get_price_terms(&self, reward_mult: f32) -> Result<(u64, u64), Error> {
+        let block_size = self.network_params.block_size;
+        let (reward1, reward2) =
+            try_join(self.get_reward(block_size), self.get_reward(block_size * 2)).await?;
+        let base = (reward1 as f32 * reward_mult) as u64;
+        let incremental = (reward2 as f32 * reward_mult) as u64 - base;
+        Ok((base, incremental))
     }
 
-    /// Signs transaction with sol_ar service.
-    pub async fn sign_transaction_with_sol(
-        &self,
-        mut transaction: Transaction,
-        solana_url: Url,
-        sol_ar_url: Url,
-        from_keypair: &Keypair,
-    ) -> Result<(Transaction, SigResponse), Error> {
-        let lamports = std::cmp::max(&transaction.reward / RATE, FLOOR);
+    /// Gets transaction from the network.
+    pub async fn get_transaction(&self, id: &Base64) -> Result<Transaction, Error> {
+        let url = Endpoints::tx(&self.base_url, id)?;
+        let resp = self.get(url).await?.json::<Transaction>().await?;
+        Ok(resp)
+    }
 
-        let mut sol_tx = create_sol_transaction(solana_url.clone(), from_keypair, lamports).await?;
-        let mut resp = get_sol_ar_signature(
-            sol_ar_url.clone(),
-            transaction.to_deep_hash_item()?,
-            sol_tx.clone(),
-        )
-        .await;
+    /// Gets an anchor to use as the `last_tx` of a new transaction.
+    pub async fn get_tx_anchor(&self) -> Result<Base64, Error> {
+        let resp = self.get(Endpoints::tx_anchor(&self.base_url)?).await?;
+        debug!("last_tx: {}", resp.status());
+        parse_gateway_response(resp, |b| Base64::from_str(b.trim()).ok()).await
+    }
 
-        let mut retries = 0;
-        while retries < CHUNKS_RETRIES {
-            match resp {
-                Ok(_) => {
-                    retries = CHUNKS_RETRIES;
-                }
-                Err(_) => {
-                    println!(
-                        "Retrying Solana transaction ({} of {})...",
-                        retries + 1,
-                        CHUNKS_RETRIES
-                    );
-                    retries += 1;
-                    sleep(Duration::from_millis(300)).await;
-                    sol_tx =
-                        create_sol_transaction(solana_url.clone(), from_keypair, lamports).await?;
-                    resp = get_sol_ar_signature(
-                        sol_ar_url.clone(),
-                        transaction.to_deep_hash_item()?,
-                        sol_tx.clone(),
-                    )
-                    .await;
-                }
+    /// Gets an anchor to use as the `last_tx` of a new transaction, reusing the last one fetched
+    /// for [`TX_ANCHOR_TTL`] seconds.
+    pub async fn cached_tx_anchor(&self) -> Result<Base64, Error> {
+        let mut cached = self.tx_anchor.0.lock().await;
+        match &*cached {
+            Some((fetched, anchor)) if fetched.elapsed() < Duration::from_secs(TX_ANCHOR_TTL) => {
+                Ok(anchor.clone())
+            }
+            _ => {
+                let anchor = self.get_tx_anchor().await?;
+                *cached = Some((Instant::now(), anchor.clone()));
+                Ok(anchor)
             }
-        }
-        if let Ok(sig_response) = resp {
-            let sig_response_copy = sig_response.clone();
-            transaction.signature = sig_response.ar_tx_sig;
-            transaction.id = sig_response.ar_tx_id;
-            transaction.owner = sig_response.ar_tx_owner;
-            Ok((transaction, sig_response_copy))
-        } else {
-            println!(
-                "There was a problem with the Solana network. Please try again later or use AR."
-            );
-            Err(Error::SolanaNetworkError)
         }
     }
 
-    pub async fn upload_file_from_path(
-        &self,
-        file_path: PathBuf,
-        log_dir: Option<PathBuf>,
-        mut additional_tags: Option<Vec<Tag<Base64>>>,
-        last_tx: Option<Base64>,
-        price_terms: (u64, u64),
-    ) -> Result<Status, Error> {
-        let mut auto_content_tag = true;
-        let mut status_content_type = mime_guess::mime::OCTET_STREAM.to_string();
-
-        if let Some(content_type) = mime_guess::from_path(file_path.clone()).first() {
-            status_content_type = content_type.to_string();
-            auto_content_tag = false;
-            let content_tag: Tag<Base64> =
-                Tag::from_utf8_strs("Content-Type", &content_type.to_string())?;
-            if let Some(mut tags) = additional_tags {
-                tags.push(content_tag);
-                additional_tags = Some(tags);
-            } else {
-                additional_tags = Some(vec![content_tag]);
-            }
+    /// Fetches a new anchor, replacing the one reused by [`Arweave::cached_tx_anchor`], e.g.,
+    /// after a post was rejected because its anchor was no longer valid.
+    pub async fn refresh_anchor(&self) -> Result<Base64, Error> {
+        let mut cached = self.tx_anchor.0.lock().await;
+        let anchor = self.get_tx_anchor().await?;
+        *cached = Some((Instant::now(), anchor.clone()));
+        Ok(anchor)
+    }
+
+    /// Returns the data of the transaction or data item with `id`, or [`Error::InvalidId`] if
+    /// `id` isn't 32 bytes, which would request some other path of the gateway instead.
+    pub async fn download_data(&self, id: &Base64) -> Result<Vec<u8>, Error> {
+        check_id(id)?;
+        let url = Endpoints::data(&self.base_url, id)?;
+        let resp = self.get(url).await?;
+        if !resp.status().is_success() {
+            return Err(Error::ArweaveNetworkError(resp.status()));
         }
+        Ok(resp.bytes().await?.to_vec())
+    }
 
-        let transaction = self
-            .create_transaction_from_file_path(
-                file_path.clone(),
-                additional_tags,
-                last_tx,
-                price_terms,
-                auto_content_tag,
-            )
-            .await?;
-        let signed_transaction = self.sign_transaction(transaction)?;
-        let (id, reward) = if signed_transaction.data.0.len() > MAX_TX_DATA as usize {
-            self.post_transaction_chunks(signed_transaction, 100)
-                .await?
+    /// Checks whether the data of transaction or data item `id` is available from the gateway
+    /// without downloading it, returning its content length and content type, or `None` if the
+    /// gateway responds with 404, including after following redirects. `id` is checked as with
+    /// [`Arweave::download_data`].
+    pub async fn head_data(&self, id: &Base64) -> Result<Option<(u64, String)>, Error> {
+        check_id(id)?;
+        let url = Endpoints::data(&self.base_url, id)?;
+        self.cooldown.wait().await;
+        let resp = self.send(self.client.head(url)).await?;
+        self.cooldown.observe(&resp);
+        match resp.status() {
+            ResponseStatusCode::NOT_FOUND => return Ok(None),
+            status if !status.is_success() => return Err(Error::ArweaveNetworkError(status)),
+            _ => (),
+        }
+        let header = |name: HeaderName| resp.headers().get(name).and_then(|v| v.to_str().ok());
+        let content_length = header(CONTENT_LENGTH)
+            .and_then(|v| v.parse::<u64>().ok())
+            .ok_or_else(|| Error::GatewayUnexpectedResponse {
+                endpoint: resp.url().to_string(),
+                snippet: "missing content-length".to_string(),
+            })?;
+        let content_type = header(CONTENT_TYPE).unwrap_or_default().to_string();
+        Ok(Some((content_length, content_type)))
+    }
+
+    /// Returns the balance of the wallet.
+    pub async fn get_wallet_balance(
+        &self,
+        wallet_address: Option<String>,
+    ) -> Result<BigUint, Error> {
+        let wallet_address = if let Some(wallet_address) = wallet_address {
+            wallet_address
         } else {
-            self.post_transaction(&signed_transaction).await?
+            self.crypto.wallet_address()?.to_string()
         };
+        let url = Endpoints::wallet_balance(&self.base_url, &wallet_address)?;
+        let resp = self.send(self.client.get(url)).await?;
+        let winstons = parse_gateway_response(resp, |b| b.trim().parse::<u64>().ok()).await?;
+        Ok(BigUint::from(winstons))
+    }
 
-        let status = Status {
-            id,
-            reward,
-            file_path: Some(file_path),
-            content_type: status_content_type,
-            ..Default::default()
+    /// Lists the transactions owned by `wallet_address`, or the wallet of the keypair if not
+    /// provided, that were mined since `since` or are still pending, requesting pages of
+    /// [`audit::AUDIT_PAGE_SIZE`] transactions from the GraphQL endpoint of the gateway, newest
+    /// first, until they reach older ones.
+    pub async fn audit_wallet(
+        &self,
+        wallet_address: Option<String>,
+        since: DateTime<Utc>,
+    ) -> Result<WalletAudit, Error> {
+        let wallet_address = if let Some(wallet_address) = wallet_address {
+            wallet_address
+        } else {
+            self.crypto.wallet_address()?.to_string()
         };
-
-        if let Some(log_dir) = log_dir {
-            self.write_status(status.clone(), log_dir, None).await?;
+        let url = Endpoints::graphql(&self.base_url)?;
+        let mut audit = WalletAudit::new(&wallet_address, since);
+        let mut cursor: Option<String> = None;
+        loop {
+            let query = audit_query(&wallet_address, cursor.as_deref());
+            let resp = self
+                .send(self.client.post(url.clone()).json(&query))
+                .await?;
+            let page = parse_gateway_response(resp, |b| {
+                AuditPage::from_response(&serde_json::from_str(b).ok()?)
+            })
+            .await?;
+            cursor = page.next_cursor.clone();
+            if !audit.add_page(page) {
+                break;
+            }
         }
-        Ok(status)
+        Ok(audit)
     }
 
-    pub async fn upload_file_from_path_with_sol(
+    /// Verifies that the wallet balance covers the rewards for uploading `paths` as whole
+    /// transactions plus the [`Arweave::tip`] included with each of them, returning the balance.
+    pub async fn check_wallet_balance(
         &self,
-        file_path: PathBuf,
-        log_dir: Option<PathBuf>,
-        mut additional_tags: Option<Vec<Tag<Base64>>>,
-        last_tx: Option<Base64>,
+        paths: &[PathBuf],
         price_terms: (u64, u64),
-        solana_url: Url,
-        sol_ar_url: Url,
-        from_keypair: &Keypair,
-    ) -> Result<Status, Error> {
-        let mut auto_content_tag = true;
-        let mut status_content_type = mime_guess::mime::OCTET_STREAM.to_string();
-
-        if let Some(content_type) = mime_guess::from_path(file_path.clone()).first() {
-            status_content_type = content_type.to_string();
-            auto_content_tag = false;
-            let content_tag: Tag<Base64> =
-                Tag::from_utf8_strs("Content-Type", &content_type.to_string())?;
-            if let Some(mut tags) = additional_tags {
-                tags.push(content_tag);
-                additional_tags = Some(tags);
-            } else {
-                additional_tags = Some(vec![content_tag]);
-            }
+    ) -> Result<u64, Error> {
+        let tip = self.tip.as_ref().map(|t| t.quantity).unwrap_or(0);
+        let mut required = 0;
+        for path in paths {
+            let data_size = fs::metadata(path).await?.len();
+            required += self
+                .network_params
+                .transaction_reward(data_size, price_terms)
+                + tip;
         }
+        self.check_balance(required).await
+    }
 
-        let transaction = self
-            .create_transaction_from_file_path(
-                file_path.clone(),
-                additional_tags,
-                last_tx,
-                price_terms,
-                auto_content_tag,
-            )
-            .await?;
-
-        let (signed_transaction, sig_response): (Transaction, SigResponse) = self
-            .sign_transaction_with_sol(transaction, solana_url, sol_ar_url, from_keypair)
-            .await?;
-
-        let (id, reward) = if signed_transaction.data.0.len() > MAX_TX_DATA as usize {
-            self.post_transaction_chunks(signed_transaction, 100)
-                .await?
-        } else {
-            self.post_transaction(&signed_transaction).await?
-        };
+    /// Verifies that the wallet balance covers the estimated rewards for uploading `path_chunks`
+    /// as bundles plus the [`Arweave::tip`] included with each of them, returning the balance. The
+    /// estimate is based on the sizes of the files, leaving out bundle headers and tags.
+    pub async fn check_bundles_wallet_balance(
+        &self,
+        path_chunks: &[PathsChunk],
+        price_terms: (u64, u64),
+    ) -> Result<u64, Error> {
+        let tip = self.tip.as_ref().map(|t| t.quantity).unwrap_or(0);
+        let required = path_chunks
+            .iter()
+            .map(|PathsChunk(_, data_size)| {
+                self.network_params
+                    .transaction_reward(*data_size, price_terms)
+                    + tip
+            })
+            .sum();
+        self.check_balance(required).await
+    }
 
-        let mut status = Status {
-            file_path: Some(file_path),
-            content_type: status_content_type,
-            id,
-            reward,
-            ..Default::default()
-        };
+    /// Like [`Arweave::check_bundles_wallet_balance`], but for paying with SOL from `keypair`,
+    /// returning the balance in lamports.
+    pub async fn check_bundles_sol_balance(
+        &self,
+        path_chunks: &[PathsChunk],
+        price_terms: (u64, u64),
+        solana_url: Url,
+        keypair: &Keypair,
+    ) -> Result<u64, Error> {
+        let required = path_chunks
+            .iter()
+            .map(|PathsChunk(_, data_size)| {
+                let winstons = self
+                    .network_params
+                    .transaction_reward(*data_size, price_terms);
+                std::cmp::max(winstons / RATE, FLOOR) + SOL_TX_FEE
+            })
+            .sum();
+        let balance = get_sol_wallet_balance(solana_url, keypair).await?;
+        if balance < required {
+            return Err(Error::InsufficientSolBalance { balance, required });
+        }
+        Ok(balance)
+    }
 
-        if let Some(log_dir) = log_dir {
-            status.sol_sig = Some(sig_response);
-            self.write_status(status.clone(), log_dir, None).await?;
+    async fn check_balance(&self, required: u64) -> Result<u64, Error> {
+        let balance = self.get_wallet_balance(None).await?;
+        let balance = u64::try_from(balance).unwrap_or(u64::MAX);
+        if balance < required {
+            return Err(Error::InsufficientBalance { balance, required });
         }
-        Ok(status)
+        Ok(balance)
     }
 
-    /// Uploads files from an iterator of paths.
-    ///
-    /// Optionally logs Status objects to `log_dir`, if provided and optionally adds tags to each
-    ///  transaction from an iterator of tags that must be the same size as the paths iterator.
-    pub async fn upload_files_from_paths<IP, IT>(
+    //-------------------------
+    // Bundle
+    //-------------------------
+
+    pub fn chunk_file_paths<IP>(
         &self,
         paths_iter: IP,
-        log_dir: Option<PathBuf>,
-        tags_iter: Option<IT>,
-        last_tx: Option<Base64>,
-        price_terms: (u64, u64),
-    ) -> Result<Vec<Status>, Error>
+        data_size: u64,
+    ) -> Result<Vec<PathsChunk>, Error>
     where
         IP: Iterator<Item = PathBuf> + Send,
-        IT: Iterator<Item = Option<Vec<Tag<Base64>>>> + Send,
     {
-        let statuses = if let Some(tags_iter) = tags_iter {
-            try_join_all(paths_iter.zip(tags_iter).map(|(p, t)| {
-                self.upload_file_from_path(p, log_dir.clone(), t, last_tx.clone(), price_terms)
-            }))
-        } else {
-            try_join_all(paths_iter.map(|p| {
-                self.upload_file_from_path(p, log_dir.clone(), None, last_tx.clone(), price_terms)
-            }))
+        let item_overhead = self.data_item_overhead()? as u64;
+        let bundle_header_len = BUNDLE_HEADER_LEN as u64;
+
+        let (mut paths_chunks, last_chunk, last_data_len, _) = paths_iter.fold(
+            (
+                Vec::<PathsChunk>::new(),
+                Vec::<PathBuf>::new(),
+                0u64,
+                bundle_header_len,
+            ),
+            |(mut ip, mut i, data_len, bundle_len), p| {
+                let p_len = p.metadata().unwrap().len();
+                let item_len = p_len + item_overhead;
+                if i.len() > 0 && bundle_len + item_len > data_size {
+                    ip.push(PathsChunk(i, data_len));
+                    (ip, vec![p], p_len, bundle_header_len + item_len)
+                } else {
+                    i.push(p);
+                    (ip, i, data_len + p_len, bundle_len + item_len)
+                }
+            },
+        );
+
+        if last_chunk.len() > 0 {
+            paths_chunks.push(PathsChunk(last_chunk, last_data_len));
         }
-        .await?;
-        Ok(statuses)
-    }
 
-    //-------------------------
-    // Status
-    //-------------------------
+        Ok(paths_chunks)
+    }
 
-    pub async fn create_log_dir(&self, parent_dir: &Path) -> Result<PathBuf, Error> {
-        let mut rand_bytes: [u8; 8] = [0; 8];
-        self.crypto.fill_rand(&mut rand_bytes)?;
-        let suffix = base64::encode_config(rand_bytes, base64::URL_SAFE_NO_PAD);
-        let log_dir = parent_dir.join(format!("arloader_{}", suffix));
-        fs::create_dir_all(&log_dir).await?;
-        Ok(log_dir)
+    /// Estimated number of bytes each file adds to a bundle in addition to its data: the bundle
+    /// item header plus the serialized [`DataItem`] fields with a 32 byte anchor and default
+    /// `User-Agent` and `Content-Type` tags.
+    pub fn data_item_overhead(&self) -> Result<usize, Error> {
+        let data_item = DataItem {
+            owner: self.crypto.keypair_modulus()?,
+            anchor: Base64(vec![0; 32]),
+            tags: vec![
+                Tag::<String>::from_utf8_strs("User-Agent", &format!("arloader/{}", VERSION))?,
+                Tag::<String>::from_utf8_strs("Content-Type", "application/octet-stream")?,
+            ],
+            ..DataItem::default()
+        };
+        Ok(BUNDLE_ITEM_HEADER_LEN + data_item.serialized_len())
     }
 
-    /// Filters saved Status objects by status and/or number of confirmations. Return
-    /// all statuses if no status codes or maximum confirmations are provided.
-    ///
-    /// If there is no raw status object and max_confirms is passed, it
-    /// assumes there are zero confirms. This is designed to be used to
-    /// determine whether all files have a confirmed status and to collect the
-    /// paths of the files that need to be re-uploaded.
-    pub fn filter_statuses<S>(
+    /// Serializes data items into a bundle, returning the bundle, a manifest of the included files
+    /// and a [`BundleBreakdown`] of how much of the bundle is file data.
+    pub fn create_bundle_from_data_items(
         &self,
-        all_statuses: Vec<S>,
-        statuses: Option<Vec<StatusCode>>,
-        max_confirms: Option<u64>,
-    ) -> Result<Vec<S>, Error>
-    where
-        S: Filterable,
-    {
-        // let all_statuses = self.read_statuses(paths_iter, log_dir).await?;
+        data_items: Vec<(DataItem, Status)>,
+    ) -> Result<(Vec<u8>, Value, BundleBreakdown), Error> {
+        let (data_items, manifest, mut breakdown) = self.prepare_bundle(data_items)?;
+        let mut binary = Vec::with_capacity(bundle_len(&data_items));
+        write_bundle(data_items, &mut binary)?;
+        breakdown.overhead_bytes = binary.len() as u64 - breakdown.data_bytes;
 
-        let filtered = if let Some(statuses) = statuses {
-            if let Some(max_confirms) = max_confirms {
-                all_statuses
-                    .into_iter()
-                    .filter(|s| {
-                        let s = s.get_filter_elements();
-                        let confirms = if let Some(raw_status) = &s.raw_status {
-                            raw_status.number_of_confirmations
-                        } else {
-                            0
-                        };
-                        (&statuses.iter().any(|c| c == s.status)) & (confirms <= max_confirms)
-                    })
-                    .collect()
-            } else {
-                all_statuses
-                    .into_iter()
-                    .filter(|s| {
-                        let s = s.get_filter_elements();
-                        statuses.iter().any(|c| c == s.status)
-                    })
-                    .collect()
-            }
-        } else {
-            if let Some(max_confirms) = max_confirms {
-                all_statuses
-                    .into_iter()
-                    .filter(|s| {
-                        let s = s.get_filter_elements();
-                        let confirms = if let Some(raw_status) = &s.raw_status {
-                            raw_status.number_of_confirmations
-                        } else {
-                            0
-                        };
-                        confirms <= max_confirms
-                    })
-                    .collect()
-            } else {
-                all_statuses
-            }
-        };
+        Ok((binary, manifest, breakdown))
+    }
 
-        Ok(filtered)
+    /// Same as [`Arweave::create_bundle_from_data_items`], except that the bundle is written to a
+    /// [`BundleFile`] in the system temporary directory, along with the merkle leaves computed as
+    /// it is written, so that only one serialized data item is held in memory at a time.
+    pub fn create_bundle_file_from_data_items(
+        &self,
+        data_items: Vec<(DataItem, Status)>,
+    ) -> Result<(BundleFile, Vec<Node>, Value, BundleBreakdown), Error> {
+        let (data_items, manifest, mut breakdown) = self.prepare_bundle(data_items)?;
+        let len = bundle_len(&data_items);
+        let (bundle_file, file) = BundleFile::create_temp(len as u64)?;
+        let mut writer = LeavesWriter::new(std::io::BufWriter::new(file), len, &self.crypto);
+        write_bundle(data_items, &mut writer)?;
+        let (_, leaves) = writer.finish()?;
+        breakdown.overhead_bytes = bundle_file.len - breakdown.data_bytes;
+
+        Ok((bundle_file, leaves, manifest, breakdown))
     }
 
-    /// Gets status from network.
-    pub async fn get_status(&self, id: &Base64) -> Result<Status, Error> {
-        let url = self.base_url.join(&format!("tx/{}/status", id))?;
-        let resp = reqwest::get(url).await?;
-        let mut status = Status {
-            id: id.clone(),
-            ..Status::default()
-        };
+    /// Encodes the tags of the data items for a bundle and creates the manifest of the included
+    /// files, returning the data items paired with their encoded tags.
+    fn prepare_bundle(
+        &self,
+        data_items: Vec<(DataItem, Status)>,
+    ) -> Result<(Vec<(DataItem, Vec<u8>)>, Value, BundleBreakdown), Error> {
+        let mut breakdown = BundleBreakdown::default();
+
+        // Encode tags shared by all of the data items once for the whole bundle.
+        let tags_encoder =
+            TagsEncoder::new(&shared_tags_prefix(data_items.iter().map(|(d, _)| &d.tags)));
+        let shared_len = tags_encoder.shared_len();
+
+        let (encoded_items, (statuses, owner_addresses)): (
+            Vec<(DataItem, Vec<u8>)>,
+            (Vec<Status>, Vec<String>),
+        ) = data_items
+            .into_iter()
+            .map(|(d, s)| {
+                let tags_bytes = tags_encoder.encode(&d.tags[shared_len..]);
+                breakdown.data_bytes += d.data.0.len() as u64;
+                breakdown.tags_bytes += tags_bytes.len() as u64;
+                let owner_address = address_from_modulus(&d.owner).to_string();
+                ((d, tags_bytes), (s, owner_address))
+            })
+            .unzip();
 
-        match resp.status() {
-            ResponseStatusCode::OK => {
-                let resp_string = resp.text().await?;
-                if &resp_string == &String::from("Pending") {
-                    status.status = StatusCode::Pending;
-                } else {
-                    status.raw_status = Some(serde_json::from_str(&resp_string)?);
-                    status.status = StatusCode::Confirmed;
-                }
-            }
-            ResponseStatusCode::ACCEPTED => {
-                status.status = StatusCode::Pending;
-            }
-            ResponseStatusCode::NOT_FOUND => {
-                status.status = StatusCode::NotFound;
-            }
-            other_status => {
-                return Err(Error::ArweaveNetworkError(other_status));
+        // Record the owner, size and hash of each data item alongside its id in the manifest
+        // entries.
+        let entries: Vec<(String, Option<u64>, Option<String>)> = statuses
+            .iter()
+            .map(|s| {
+                (
+                    s.file_path.as_ref().unwrap().to_str().unwrap().to_string(),
+                    s.data_size,
+                    s.data_hash.clone(),
+                )
+            })
+            .collect();
+        let mut manifest = self.create_manifest(statuses, None)?;
+        for ((p, data_size, data_hash), a) in entries.into_iter().zip(owner_addresses) {
+            let entry = &mut manifest["paths"][p.as_str()];
+            entry["owner_address"] = json!(a);
+            if let (Some(data_size), Some(data_hash)) = (data_size, data_hash) {
+                entry["data_size"] = json!(data_size);
+                entry["data_hash"] = json!(data_hash);
             }
         }
-        Ok(status)
-    }
-
-    pub async fn read_bundle_status(&self, file_path: PathBuf) -> Result<BundleStatus, Error> {
-        let data = fs::read_to_string(&file_path).await?;
-        let status = serde_json::from_str::<BundleStatus>(&data)?;
-        Ok(status)
-    }
 
-    // Reads statuses from a list of paths.
-    pub async fn read_bundle_statuses(&self, log_dir: &str) -> Result<Vec<BundleStatus>, Error> {
-        let paths_iter = glob(&format!("{}*.json", log_dir))?
-            .filter_map(Result::ok)
-            .filter(|p| file_stem_is_valid_txid(p));
-        try_join_all(paths_iter.map(|p| self.read_bundle_status(p))).await
+        Ok((encoded_items, manifest, breakdown))
     }
 
-    pub async fn status_summary<IP>(
+    pub async fn create_bundle_transaction_from_file_paths(
         &self,
-        paths_iter: IP,
-        log_dir: PathBuf,
-    ) -> Result<String, Error>
-    where
-        IP: Iterator<Item = PathBuf> + Send,
-    {
-        let statuses = self.read_statuses(paths_iter, log_dir).await?;
-        let status_counts: HashMap<StatusCode, u32> =
-            statuses
-                .into_iter()
-                .fold(HashMap::new(), |mut map, status| {
-                    *map.entry(status.status).or_insert(0) += 1;
-                    map
-                });
+        paths_iter: Vec<PathBuf>,
+        tags: Vec<Tag<String>>,
+        price_terms: (u64, u64),
+    ) -> Result<(Transaction, Value), Error> {
+        let data_items = self
+            .create_data_items_from_file_paths(paths_iter, tags)
+            .await?;
 
-        let mut total = 0;
-        let mut output = String::new();
-        writeln!(output, " {:<15}  {:>10}", "status", "count")?;
-        writeln!(output, "{:-<29}", "")?;
-        for k in vec![
-            StatusCode::Submitted,
-            StatusCode::Pending,
-            StatusCode::NotFound,
-            StatusCode::Confirmed,
-        ] {
-            let v = status_counts.get(&k).unwrap_or(&0);
-            writeln!(output, " {:<16} {:>10}", &k.to_string(), v)?;
-            total += v;
-        }
+        let (bundle, manifest_object, _) = self.create_bundle_from_data_items(data_items)?;
+        let other_tags = Some(vec![
+            Tag::<Base64>::from_utf8_strs("Bundle-Format", "binary")?,
+            Tag::<Base64>::from_utf8_strs("Bundle-Version", "2.0.0")?,
+        ]);
 
-        writeln!(output, "{:-<29}", "")?;
-        writeln!(output, " {:<15}  {:>10}", "Total", total)?;
+        let mut transaction = self
+            .create_transaction(bundle, other_tags, None, price_terms, true)
+            .await?;
+        self.check_bundle_reward(&mut transaction).await?;
 
-        Ok(output)
+        Ok((transaction, manifest_object))
     }
 
-    // Reads a status from file.
-    pub async fn read_status(&self, file_path: PathBuf, log_dir: PathBuf) -> Result<Status, Error> {
-        let file_path_hash = blake3::hash(file_path.to_str().unwrap().as_bytes());
+    /// Creates a [`DataItem`] for a bundle. Its anchor is a hash of `data` if
+    /// [`Arweave::anchor_from_hash`] is set, otherwise 32 random bytes if `random_anchor` is true,
+    /// so that items with identical data and tags don't get the same id, or empty.
+    /// `signature_type` determines the keypair [`Arweave::sign_data_item`] signs it with.
+    pub fn create_data_item(
+        &self,
+        data: Vec<u8>,
+        mut tags: Vec<Tag<String>>,
+        auto_content_tag: bool,
+        random_anchor: bool,
+        signature_type: SignatureType,
+    ) -> Result<DataItem, Error> {
+        tags.push(Tag::<String>::from_utf8_strs(
+            "User-Agent",
+            &format!("arloader/{}", VERSION),
+        )?);
 
-        let status_path = log_dir
-            .join(file_path_hash.to_string())
-            .with_extension("json");
+        // Get content type from [magic numbers](https://developer.mozilla.org/en-US/docs/Web/HTTP/Basics_of_HTTP/MIME_types)
+        // and include additional tags if any.
+        if auto_content_tag {
+            let content_type = if let Some(kind) = infer::get(&data) {
+                kind.mime_type()
+            } else {
+                "application/octet-stream"
+            };
 
-        if status_path.exists() {
-            let data = fs::read_to_string(status_path).await?;
-            let status: Status = serde_json::from_str(&data)?;
-            Ok(status)
-        } else {
-            Err(Error::StatusNotFound)
+            tags.push(Tag::<String>::from_utf8_strs("Content-Type", content_type)?)
         }
+
+        bundle::check_tags(&tags)?;
+
+        let anchor = if self.anchor_from_hash {
+            bundle::anchor_from_hash(&data)
+        } else if random_anchor {
+            self.random_anchor()?
+        } else {
+            Base64(Vec::with_capacity(32))
+        };
+
+        Ok(DataItem {
+            signature_type: signature_type as u16,
+            data: Base64(data),
+            tags,
+            anchor,
+            ..DataItem::default()
+        })
     }
 
-    // Reads statuses from a list of paths.
-    pub async fn read_statuses<IP>(
+    /// 32 random bytes to use as the anchor of a data item.
+    fn random_anchor(&self) -> Result<Base64, Error> {
+        let mut anchor = Base64(vec![0; 32]);
+        self.crypto.fill_rand(&mut anchor.0)?;
+        Ok(anchor)
+    }
+
+    /// Creates a data item from a file, using `content_type_override` as its `Content-Type` if
+    /// provided instead of detecting it from the file.
+    pub async fn create_data_item_from_file_path(
         &self,
-        paths_iter: IP,
-        log_dir: PathBuf,
-    ) -> Result<Vec<Status>, Error>
-    where
-        IP: Iterator<Item = PathBuf> + Send,
-    {
-        try_join_all(paths_iter.map(|p| self.read_status(p, log_dir.clone()))).await
+        file_path: PathBuf,
+        mut tags: Vec<Tag<String>>,
+        content_type_override: Option<&str>,
+    ) -> Result<(DataItem, Status), Error> {
+        let data = fs::read(&file_path).await?;
+        let content_type =
+            push_data_item_content_type(&file_path, &data, &mut tags, content_type_override)?;
+        let auto_content_tag = content_type.is_none();
+        let status_content_type =
+            content_type.unwrap_or_else(|| mime_guess::mime::OCTET_STREAM.to_string());
+
+        let data_size = data.len() as u64;
+        let data_hash = status::data_hash(&data);
+        let data_item = match &self.item_cache {
+            Some(cache_dir) => {
+                let key = self.item_cache_key(&file_path, &data, &tags, auto_content_tag)?;
+                match self.load_cached_item(cache_dir, &key).await? {
+                    Some(data_item) => data_item,
+                    None => {
+                        let data_item = self.create_data_item(
+                            data,
+                            tags,
+                            auto_content_tag,
+                            true,
+                            SignatureType::Arweave,
+                        )?;
+                        let data_item = self.sign_data_item(data_item)?;
+                        self.write_cached_item(cache_dir, &key, &data_item).await?;
+                        data_item
+                    }
+                }
+            }
+            None => {
+                let data_item = self.create_data_item(
+                    data,
+                    tags,
+                    auto_content_tag,
+                    true,
+                    SignatureType::Arweave,
+                )?;
+                self.sign_data_item(data_item)?
+            }
+        };
+
+        let status = Status {
+            id: data_item.id.clone(),
+            file_path: Some(file_path),
+            content_type: status_content_type,
+            data_size: Some(data_size),
+            data_hash: Some(data_hash),
+            ..Status::default()
+        };
+
+        Ok((data_item, status))
     }
 
-    pub async fn update_bundle_status(&self, file_path: PathBuf) -> Result<BundleStatus, Error> {
-        let data = fs::read_to_string(&file_path).await?;
-        let mut status: BundleStatus = serde_json::from_str(&data)?;
-        let trans_status = self.get_status(&status.id).await?;
-        status.last_modified = Utc::now();
-        status.status = trans_status.status;
-        status.raw_status = trans_status.raw_status;
-        fs::write(&file_path, serde_json::to_string(&status)?).await?;
-        Ok(status)
+    /// Key for a data item in the item cache, a BLAKE3 hash of everything that goes into creating
+    /// it, so that a change to the file, tags, keypair, anchor or arloader version results in a
+    /// new item.
+    fn item_cache_key(
+        &self,
+        file_path: &Path,
+        data: &[u8],
+        tags: &[Tag<String>],
+        auto_content_tag: bool,
+    ) -> Result<String, Error> {
+        let path_bytes = file_path.to_string_lossy();
+        let tags_bytes = bundle::encode_tags(tags)?;
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(VERSION.as_bytes());
+        hasher.update(&(path_bytes.len() as u64).to_le_bytes());
+        hasher.update(path_bytes.as_bytes());
+        hasher.update(&self.crypto.keypair_modulus()?.0);
+        hasher.update(&(tags_bytes.len() as u64).to_le_bytes());
+        hasher.update(&tags_bytes);
+        hasher.update(&[auto_content_tag as u8]);
+        hasher.update(&[self.anchor_from_hash as u8]);
+        hasher.update(data);
+        Ok(hasher.finalize().to_hex().to_string())
     }
 
-    pub async fn update_status(
+    /// Reads a signed data item from the item cache, returning `None` if it isn't there or
+    /// doesn't deserialize and verify, e.g., if it was left truncated, so that it gets signed and
+    /// written again.
+    async fn load_cached_item(
         &self,
-        file_path: PathBuf,
-        log_dir: PathBuf,
-    ) -> Result<Status, Error> {
-        let mut status = self.read_status(file_path, log_dir.clone()).await?;
-        let trans_status = self.get_status(&status.id).await?;
-        status.last_modified = Utc::now();
-        status.status = trans_status.status;
-        status.raw_status = trans_status.raw_status;
-        self.write_status(status.clone(), log_dir, None).await?;
-        Ok(status)
+        cache_dir: &Path,
+        key: &str,
+    ) -> Result<Option<DataItem>, Error> {
+        let bytes = match fs::read(cache_dir.join(key)).await {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(bundle::deserialize_signed_data_item(&bytes, &self.crypto).ok())
     }
 
-    pub async fn update_statuses<IP>(
+    /// Writes a signed data item to the item cache, writing to a temp file first and renaming it
+    /// so that an interrupted write never leaves a partial item under `key`.
+    async fn write_cached_item(
         &self,
-        paths_iter: IP,
-        log_dir: PathBuf,
-    ) -> Result<Vec<Status>, Error>
-    where
-        IP: Iterator<Item = PathBuf> + Send,
-    {
-        try_join_all(paths_iter.map(|p| self.update_status(p, log_dir.clone()))).await
+        cache_dir: &Path,
+        key: &str,
+        data_item: &DataItem,
+    ) -> Result<(), Error> {
+        fs::create_dir_all(cache_dir).await?;
+        let temp_path = cache_dir.join(format!("{}.{}.tmp", key, std::process::id()));
+        let mut file = fs::File::create(&temp_path).await?;
+        file.write_all(&data_item.serialize()?).await?;
+        file.sync_data().await?;
+        fs::rename(&temp_path, cache_dir.join(key)).await?;
+        Ok(())
     }
 
-    /// Writes Status Json to `log_dir` with file name based on BLAKE3 hash of `status.file_path`.
-    ///
-    /// This is done to facilitate checking the status of uploaded file and also means that only
-    /// one status object can exist for a given `file_path`. If for some reason you wanted to record
-    /// statuses for multiple uploads of the same file you can provide a different `log_dir` (or copy the
-    /// file to a different directory and upload from there).
-    pub async fn write_status(
+    /// Creates data items from files. A `Content-Type` tag in `tags` overrides the content type
+    /// detected for each file.
+    pub async fn create_data_items_from_file_paths(
         &self,
-        status: Status,
-        log_dir: PathBuf,
-        file_stem: Option<String>,
+        paths: Vec<PathBuf>,
+        mut tags: Vec<Tag<String>>,
+    ) -> Result<Vec<(DataItem, Status)>, Error> {
+        let content_type_override = take_content_type_override(&mut tags);
+        try_join_all(paths.into_iter().map(|p| {
+            self.create_data_item_from_file_path(p, tags.clone(), content_type_override.as_deref())
+        }))
+        .await
+    }
+
+    /// Deserializes a bundle, verifying data item signatures. See [`bundle::deserialize_bundle`].
+    pub fn deserialize_bundle(&self, bundle: Vec<u8>) -> Result<Vec<DataItem>, Error> {
+        bundle::deserialize_bundle(bundle, &self.crypto)
+    }
+
+    /// Deserializes a bundle one data item at a time, verifying each and handing it to `f`. See
+    /// [`bundle::deserialize_bundle_streaming`].
+    pub fn deserialize_bundle_streaming(
+        &self,
+        bundle: &[u8],
+        f: impl FnMut(DataItem) -> Result<(), Error>,
     ) -> Result<(), Error> {
-        let file_stem = if let Some(stem) = file_stem {
-            stem
-        } else {
-            if let Some(file_path) = &status.file_path {
-                if status.id.0.is_empty() {
-                    return Err(error::Error::UnsignedTransaction.into());
+        bundle::deserialize_bundle_streaming(bundle, &self.crypto, f)
+    }
+
+    /// Deserializes a data item signed elsewhere, verifying its signature. See
+    /// [`bundle::deserialize_signed_data_item`].
+    pub fn deserialize_signed_data_item(&self, bytes: &[u8]) -> Result<DataItem, Error> {
+        bundle::deserialize_signed_data_item(bytes, &self.crypto)
+    }
+
+    /// Bundles and posts serialized data items signed elsewhere, e.g., with arbundles or a
+    /// browser wallet, failing before anything is posted if any of them doesn't verify or is
+    /// included more than once, see [`Error::DuplicateDataItem`]. Items are
+    /// recorded in the bundle status under their `File-Name` tag, or their id if they don't have
+    /// one or another item has the same name, with their `Content-Type` tag as content type.
+    pub async fn post_bundle_from_serialized_items(
+        &self,
+        items: Vec<Vec<u8>>,
+        log_dir: Option<PathBuf>,
+        price_terms: (u64, u64),
+        buffer: usize,
+        retry_policy: RetryPolicy,
+    ) -> Result<BundleStatus, Error> {
+        let mut names = HashSet::new();
+        let mut ids = HashSet::new();
+        let mut data_size = 0;
+        let data_items = items
+            .iter()
+            .map(|bytes| {
+                let data_item = self.deserialize_signed_data_item(bytes)?;
+                if !ids.insert(data_item.id.to_string()) {
+                    return Err(Error::DuplicateDataItem(data_item.id.to_string()));
                 }
-                blake3::hash(file_path.to_str().unwrap().as_bytes()).to_string()
-            } else {
-                format!("txid_{}", status.id)
-            }
+                let tag = |name: &str| {
+                    data_item
+                        .tags
+                        .iter()
+                        .find(|t| t.name == name)
+                        .map(|t| t.value.clone())
+                };
+                let file_path = match tag("File-Name") {
+                    Some(name) if names.insert(name.clone()) => name,
+                    _ => data_item.id.to_string(),
+                };
+                let status = Status {
+                    id: data_item.id.clone(),
+                    file_path: Some(PathBuf::from(file_path)),
+                    content_type: tag("Content-Type")
+                        .unwrap_or_else(|| mime_guess::mime::OCTET_STREAM.to_string()),
+                    data_size: Some(data_item.data.0.len() as u64),
+                    data_hash: Some(status::data_hash(&data_item.data.0)),
+                    ..Status::default()
+                };
+                data_size += data_item.data.0.len() as u64;
+                Ok((data_item, status))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        self.post_bundle_transaction_from_data_items(
+            data_items,
+            data_size,
+            log_dir,
+            price_terms,
+            buffer,
+            retry_policy,
+        )
+        .await
+    }
+
+    /// Creates, signs and posts a bundle of the files in `paths_chunk`, retrying failed posts
+    /// according to `retry_policy`. The bundle is signed again with a new anchor if the gateway
+    /// rejects the anchor as stale.
+    pub async fn post_bundle_transaction_from_file_paths(
+        &self,
+        paths_chunk: PathsChunk,
+        tags: Vec<Tag<String>>,
+        log_dir: Option<PathBuf>,
+        price_terms: (u64, u64),
+        buffer: usize,
+        retry_policy: RetryPolicy,
+    ) -> Result<BundleStatus, Error> {
+        let data_items = self
+            .create_data_items_from_file_paths(paths_chunk.0, tags)
+            .await?;
+        self.post_bundle_transaction_from_data_items(
+            data_items,
+            paths_chunk.1,
+            log_dir,
+            price_terms,
+            buffer,
+            retry_policy,
+        )
+        .await
+    }
+
+    /// Same as [`Arweave::post_bundle_transaction_from_file_paths`] for signed data items, with
+    /// `data_size` bytes of data between them.
+    pub async fn post_bundle_transaction_from_data_items(
+        &self,
+        data_items: Vec<(DataItem, Status)>,
+        data_size: u64,
+        log_dir: Option<PathBuf>,
+        price_terms: (u64, u64),
+        buffer: usize,
+        retry_policy: RetryPolicy,
+    ) -> Result<BundleStatus, Error> {
+        let (signed_transaction, status) = self
+            .sign_bundle_transaction_from_data_items(data_items, data_size, price_terms)
+            .await?;
+        self.post_signed_bundle_transaction(
+            signed_transaction,
+            status,
+            log_dir,
+            buffer,
+            retry_policy,
+        )
+        .await
+    }
+
+    /// Creates and signs a bundle transaction of signed data items, with `data_size` bytes of
+    /// data between them, returning it with its status, to be posted with
+    /// [`Arweave::post_signed_bundle_transaction`].
+    pub async fn sign_bundle_transaction_from_data_items(
+        &self,
+        data_items: Vec<(DataItem, Status)>,
+        data_size: u64,
+        price_terms: (u64, u64),
+    ) -> Result<(Transaction, BundleStatus), Error> {
+        let number_of_files = data_items.len() as u64;
+        let bundle_tags = resolve_bundle_tags(&self.bundle_tags, &BundleSummary::new(&data_items))?;
+
+        let (bundle_file, leaves, manifest, breakdown) =
+            self.create_bundle_file_from_data_items(data_items)?;
+        let mut transaction = self
+            .create_bundle_file_transaction(bundle_file, leaves, bundle_tags, price_terms)
+            .await?;
+        self.check_bundle_reward(&mut transaction).await?;
+
+        let signed_transaction = self.sign_transaction(transaction)?;
+
+        let status = BundleStatus {
+            id: signed_transaction.id.clone(),
+            reward: signed_transaction.reward,
+            number_of_files,
+            data_size,
+            tags_bytes: breakdown.tags_bytes,
+            overhead_bytes: breakdown.overhead_bytes,
+            file_paths: manifest["paths"].clone(),
+            owner_address: address_from_modulus(&signed_transaction.owner).to_string(),
+            ..Default::default()
+        };
+        Ok((signed_transaction, status))
+    }
+
+    /// Posts a bundle transaction signed with
+    /// [`Arweave::sign_bundle_transaction_from_data_items`], retrying failed posts according to
+    /// `retry_policy`, and signing it again with a new anchor if the gateway rejects the anchor as
+    /// stale, in which case the id of the returned status differs from that of `status`.
+    pub async fn post_signed_bundle_transaction(
+        &self,
+        signed_transaction: Transaction,
+        mut status: BundleStatus,
+        log_dir: Option<PathBuf>,
+        buffer: usize,
+        retry_policy: RetryPolicy,
+    ) -> Result<BundleStatus, Error> {
+        let bytes = signed_transaction.data_size;
+        let started = Instant::now();
+        let (id, reward) = self
+            .post_bundle_with_retries(
+                signed_transaction,
+                buffer,
+                &mut status,
+                log_dir,
+                retry_policy,
+                true,
+            )
+            .await?;
+        status.id = id;
+        status.reward = reward;
+        status.post_metrics = PostMetrics {
+            bytes,
+            latency: started.elapsed(),
         };
 
+        Ok(status)
+    }
+
+    /// Creates, signs with SOL and posts a bundle of the files in `paths_chunk`, retrying failed
+    /// posts according to `retry_policy`. Posts rejected for a stale anchor aren't retried, since
+    /// signing again would require another SOL payment.
+    pub async fn post_bundle_transaction_from_file_paths_with_sol(
+        &self,
+        paths_chunk: PathsChunk,
+        tags: Vec<Tag<String>>,
+        log_dir: Option<PathBuf>,
+        price_terms: (u64, u64),
+        chunks_buffer: usize,
+        retry_policy: RetryPolicy,
+        solana_url: Url,
+        sol_ar_url: Url,
+        from_keypair: &Keypair,
+    ) -> Result<BundleStatus, Error> {
+        let data_items = self
+            .create_data_items_from_file_paths(paths_chunk.0, tags)
+            .await?;
+        self.post_bundle_transaction_from_data_items_with_sol(
+            data_items,
+            paths_chunk.1,
+            log_dir,
+            price_terms,
+            chunks_buffer,
+            retry_policy,
+            solana_url,
+            sol_ar_url,
+            from_keypair,
+        )
+        .await
+    }
+
+    /// Same as [`Arweave::post_bundle_transaction_from_file_paths_with_sol`] for signed data
+    /// items, with `data_size` bytes of data between them.
+    pub async fn post_bundle_transaction_from_data_items_with_sol(
+        &self,
+        data_items: Vec<(DataItem, Status)>,
+        data_size: u64,
+        log_dir: Option<PathBuf>,
+        price_terms: (u64, u64),
+        chunks_buffer: usize,
+        retry_policy: RetryPolicy,
+        solana_url: Url,
+        sol_ar_url: Url,
+        from_keypair: &Keypair,
+    ) -> Result<BundleStatus, Error> {
+        let number_of_files = data_items.len() as u64;
+        let bundle_tags = resolve_bundle_tags(&self.bundle_tags, &BundleSummary::new(&data_items))?;
+
+        let (bundle, manifest, breakdown) = self.create_bundle_from_data_items(data_items)?;
+        let mut other_tags = vec![
+            Tag::<Base64>::from_utf8_strs("Bundle-Format", "binary")?,
+            Tag::<Base64>::from_utf8_strs("Bundle-Version", "2.0.0")?,
+        ];
+        other_tags.extend(bundle_tags);
+
+        let mut transaction = self
+            .create_transaction(bundle, Some(other_tags), None, price_terms, true)
+            .await?;
+        self.check_bundle_reward(&mut transaction).await?;
+        self.check_before_sol_payment(&transaction).await?;
+
+        let (signed_transaction, sig_response): (Transaction, SigResponse) = self
+            .sign_transaction_with_sol(transaction, solana_url, sol_ar_url, from_keypair)
+            .await?;
+
+        let mut status = BundleStatus {
+            id: signed_transaction.id.clone(),
+            reward: signed_transaction.reward,
+            number_of_files,
+            data_size,
+            tags_bytes: breakdown.tags_bytes,
+            overhead_bytes: breakdown.overhead_bytes,
+            file_paths: manifest["paths"].clone(),
+            owner_address: address_from_modulus(&signed_transaction.owner).to_string(),
+            sol_sig: Some(sig_response.clone()),
+            ..Default::default()
+        };
+        let paid_path = self
+            .save_paid_transaction(
+                &signed_transaction,
+                sig_response,
+                PaidStatus::Bundle(status.clone()),
+                log_dir.as_deref(),
+            )
+            .await?;
+
+        let bytes = signed_transaction.data_size;
+        let started = Instant::now();
+        let posted = self
+            .post_bundle_with_retries(
+                signed_transaction,
+                chunks_buffer,
+                &mut status,
+                log_dir,
+                retry_policy,
+                false,
+            )
+            .await;
+        let (id, reward) = finish_paid_post(posted, paid_path).await?;
+        status.id = id;
+        status.reward = reward;
+        status.post_metrics = PostMetrics {
+            bytes,
+            latency: started.elapsed(),
+        };
+
+        Ok(status)
+    }
+
+    /// Posts a bundle, in chunks if it's larger than [`NetworkParams::max_tx_body`], retrying
+    /// according to `retry_policy`. Retries of a bundle posted in chunks pick up from the first
+    /// chunk that failed, without posting its header again once it has been accepted. If the
+    /// gateway rejects the anchor as stale and `resign` is true, the bundle is signed again with a
+    /// new anchor and `status.id` updated before retrying.
+    async fn post_bundle_with_retries(
+        &self,
+        mut signed_transaction: Transaction,
+        chunks_buffer: usize,
+        status: &mut BundleStatus,
+        log_dir: Option<PathBuf>,
+        retry_policy: RetryPolicy,
+        resign: bool,
+    ) -> Result<(Base64, u64), Error> {
+        let posts_chunks = self.network_params.posts_chunks(status.data_size);
+        if !posts_chunks {
+            signed_transaction.load_data().await?;
+        }
+        // Attempts share one copy of the bundle, and of its serialized body if posted whole,
+        // until it has to be signed again.
+        let mut signed_transaction = Arc::new(signed_transaction);
+        let mut body: Option<Bytes> = None;
+        let mut retry = 0;
+        let mut resume = ChunksResume::default();
+        loop {
+            let posted = if posts_chunks {
+                self.post_bundle_chunks(
+                    signed_transaction.clone(),
+                    chunks_buffer,
+                    status,
+                    log_dir.clone(),
+                    &mut resume,
+                )
+                .await
+            } else {
+                let body = match &body {
+                    Some(body) => body.clone(),
+                    None => body.insert(transaction_body(&signed_transaction)?).clone(),
+                };
+                self.try_post_transaction_body(&signed_transaction, body)
+                    .await
+            };
+            let error = match posted {
+                Ok(posted) => return Ok(posted),
+                Err(error) if retry >= retry_policy.retries => return Err(error),
+                Err(error) => error,
+            };
+
+            if resign && error.is_stale_anchor() {
+                // The chunks posted with the old anchor won't be resumed, so neither is their
+                // interim status.
+                if let Some(log_dir) = &log_dir {
+                    let status_path = log_dir.join(status.id.to_string()).with_extension("json");
+                    fs::remove_file(status_path).await.ok();
+                }
+                let mut transaction =
+                    Arc::try_unwrap(signed_transaction).unwrap_or_else(|shared| (*shared).clone());
+                transaction.last_tx = self.refresh_anchor().await?;
+                signed_transaction = Arc::new(self.sign_transaction(transaction)?);
+                status.id = signed_transaction.id.clone();
+                body = None;
+                resume = ChunksResume::default();
+            } else if !error.is_retryable() {
+                return Err(error);
+            }
+            debug!("post_bundle_with_retries: {}", error);
+            sleep(retry_policy.delay_for(retry, &error)).await;
+            retry += 1;
+            self.post_retries.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Posts the chunks of a bundle once each from where `resume` left off, see
+    /// [`Arweave::post_bundle_with_retries`], writing copies of `status` with the chunks posted so
+    /// far to `log_dir`, if provided, every [`PROGRESS_WRITE_INTERVAL`] chunks. The interim status
+    /// is left to be overwritten by the final status, and is kept if posting fails, recording
+    /// how far the upload got.
+    async fn post_bundle_chunks(
+        &self,
+        signed_transaction: Arc<Transaction>,
+        chunks_buffer: usize,
+        status: &BundleStatus,
+        log_dir: Option<PathBuf>,
+        resume: &mut ChunksResume,
+    ) -> Result<(Base64, u64), Error> {
+        let log_dir = match log_dir {
+            Some(log_dir) => log_dir,
+            None => {
+                return self
+                    .post_chunks_from(signed_transaction, chunks_buffer, None, resume, false)
+                    .await
+            }
+        };
+        let status_path = log_dir.join(status.id.to_string()).with_extension("json");
+
+        let (sender, mut receiver) = mpsc::unbounded();
+        let write_progress = async {
+            while let Some(progress) = receiver.next().await {
+                if progress.chunks_posted % PROGRESS_WRITE_INTERVAL == 0
+                    || progress.chunks_posted == progress.chunks_total
+                {
+                    let interim = BundleStatus {
+                        upload_progress: Some(progress),
+                        ..status.clone()
+                    };
+                    fs::write(&status_path, to_status_json(&interim, self.pretty_status)?).await?;
+                }
+            }
+            Ok::<(), Error>(())
+        };
+
+        let (posted, written) = join(
+            self.post_chunks_from(
+                signed_transaction,
+                chunks_buffer,
+                Some(sender),
+                resume,
+                false,
+            ),
+            write_progress,
+        )
+        .await;
+        written?;
+        posted
+    }
+
+    /// Signs `data_item` according to its [`SignatureType`], with [`Arweave::crypto`] or, for
+    /// [`SignatureType::Ed25519`], [`Arweave::sol_keypair`], setting the owner to the public key.
+    pub fn sign_data_item(&self, mut data_item: DataItem) -> Result<DataItem, Error> {
+        let signature_type = SignatureType::try_from(data_item.signature_type)?;
+        let sol_keypair = match signature_type {
+            SignatureType::Arweave => None,
+            SignatureType::Ed25519 => Some(
+                self.sol_keypair
+                    .as_ref()
+                    .ok_or(Error::SolanaKeypairNotProvided)?,
+            ),
+        };
+        data_item.owner = match sol_keypair {
+            Some(keypair) => Base64(keypair.pubkey().to_bytes().to_vec()),
+            None => self.crypto.keypair_modulus()?,
+        };
+        data_item.check_owner_len()?;
+        let deep_hash_item = data_item.to_deep_hash_item()?;
+        let deep_hash = self.crypto.deep_hash(deep_hash_item)?;
+        let signature = match sol_keypair {
+            Some(keypair) => crypto::sign_ed25519(keypair, &deep_hash),
+            None => self.crypto.sign(&deep_hash)?,
+        };
+        let id = self.crypto.hash_sha256(&signature)?;
+
+        data_item.signature = Base64(signature);
+        data_item.id = Base64(id.to_vec());
+        Ok(data_item)
+    }
+
+    //-------------------------
+    // Transaction
+    //-------------------------
+
+    pub async fn create_transaction(
+        &self,
+        data: Vec<u8>,
+        other_tags: Option<Vec<Tag<Base64>>>,
+        last_tx: Option<Base64>,
+        price_terms: (u64, u64),
+        auto_content_tag: bool,
+    ) -> Result<Transaction, Error> {
+        let transaction = self.merklize(data)?;
+        self.complete_transaction(
+            transaction,
+            other_tags,
+            last_tx,
+            price_terms,
+            auto_content_tag,
+        )
+        .await
+    }
+
+    /// Creates a bundle transaction whose data is read from a [`BundleFile`] written by
+    /// [`Arweave::create_bundle_file_from_data_items`] instead of being held in memory.
+    /// `bundle_tags` are added after the bundle format and version tags.
+    pub async fn create_bundle_file_transaction(
+        &self,
+        bundle_file: BundleFile,
+        leaves: Vec<Node>,
+        bundle_tags: Vec<Tag<Base64>>,
+        price_terms: (u64, u64),
+    ) -> Result<Transaction, Error> {
+        let mut transaction = self.merklize_leaves(leaves, bundle_file.len)?;
+        transaction.data_file = Some(Arc::new(bundle_file));
+        let mut other_tags = vec![
+            Tag::<Base64>::from_utf8_strs("Bundle-Format", "binary")?,
+            Tag::<Base64>::from_utf8_strs("Bundle-Version", "2.0.0")?,
+        ];
+        other_tags.extend(bundle_tags);
+        self.complete_transaction(transaction, Some(other_tags), None, price_terms, true)
+            .await
+    }
+
+    /// Sets the owner, tags, anchor, tip and reward of a merklized transaction.
+    async fn complete_transaction(
+        &self,
+        mut transaction: Transaction,
+        other_tags: Option<Vec<Tag<Base64>>>,
+        last_tx: Option<Base64>,
+        price_terms: (u64, u64),
+        auto_content_tag: bool,
+    ) -> Result<Transaction, Error> {
+        transaction.owner = self.crypto.keypair_modulus()?;
+
+        let mut tags = vec![Tag::<Base64>::from_utf8_strs(
+            "User-Agent",
+            &format!("arloader/{}", VERSION),
+        )?];
+
+        // Get content type from [magic numbers](https://developer.mozilla.org/en-US/docs/Web/HTTP/Basics_of_HTTP/MIME_types)
+        // and include additional tags if any.
+        if auto_content_tag {
+            let content_type = if let Some(kind) = infer::get(&transaction.data.0) {
+                kind.mime_type()
+            } else {
+                "application/octet-stream"
+            };
+
+            tags.push(Tag::<Base64>::from_utf8_strs("Content-Type", content_type)?)
+        }
+
+        // Add other tags if provided.
+        if let Some(other_tags) = other_tags {
+            tags.extend(other_tags);
+        }
+        transaction.tags = tags;
+
+        // Fetch and set last_tx if not provided (primarily for testing).
+        let last_tx = if let Some(last_tx) = last_tx {
+            last_tx
+        } else {
+            self.cached_tx_anchor().await?
+        };
+        transaction.last_tx = last_tx;
+
+        if let Some(tip) = &self.tip {
+            transaction.target = tip.target.clone();
+            transaction.quantity = tip.quantity;
+        }
+
+        transaction.reward = self
+            .network_params
+            .transaction_reward(transaction.data_size, price_terms);
+        transaction.price_terms = Some(price_terms);
+
+        Ok(transaction)
+    }
+
+    /// Makes sure the reward of a bundle transaction covers the network price of the serialized
+    /// bundle, including the headers and tags added to the files in it, since gateways drop
+    /// transactions that are underpaid and the price terms the reward was calculated from are
+    /// only an estimate. Raises the reward if it doesn't, unless [`Arweave::strict_reward`] is
+    /// set, in which case it returns [`Error::InsufficientReward`].
+    pub async fn check_bundle_reward(&self, transaction: &mut Transaction) -> Result<(), Error> {
+        let required = self.get_reward(transaction.data_size).await?;
+        if transaction.reward >= required {
+            return Ok(());
+        }
+        if self.strict_reward {
+            return Err(Error::InsufficientReward {
+                reward: transaction.reward,
+                required,
+            });
+        }
+        warn!(
+            "raising bundle reward from {} to {} winstons to cover {} bytes",
+            transaction.reward, required, transaction.data_size
+        );
+        transaction.reward = required;
+        Ok(())
+    }
+
+    pub async fn create_transaction_from_file_path(
+        &self,
+        file_path: PathBuf,
+        other_tags: Option<Vec<Tag<Base64>>>,
+        last_tx: Option<Base64>,
+        price_terms: (u64, u64),
+        auto_content_tag: bool,
+    ) -> Result<Transaction, Error> {
+        let data = fs::read(file_path).await?;
+        self.create_transaction(data, other_tags, last_tx, price_terms, auto_content_tag)
+            .await
+    }
+
+    /// Same as [`Arweave::create_transaction_from_file_path`] without reading the whole file into
+    /// memory. Leaves are hashed as the file is read a chunk at a time and the transaction is
+    /// returned without its data, with its chunks read back from the file as they're posted by
+    /// [`Arweave::post_transaction_chunks`].
+    pub async fn create_transaction_from_file_path_streamed(
+        &self,
+        file_path: PathBuf,
+        other_tags: Option<Vec<Tag<Base64>>>,
+        last_tx: Option<Base64>,
+        price_terms: (u64, u64),
+        auto_content_tag: bool,
+    ) -> Result<Transaction, Error> {
+        let data_file = BundleFile::open(file_path)?;
+        let data_len = data_file.len as usize;
+
+        let path = data_file.path.clone();
+        let hasher = self.crypto.hasher;
+        let leaves = tokio::task::spawn_blocking(move || -> Result<Vec<Node>, Error> {
+            generate_leaves_from_reader(std::fs::File::open(path)?, data_len, &hasher)
+        })
+        .await??;
+
+        // The content type is detected from the start of the file, since the transaction doesn't
+        // include its data.
+        let mut tags = Vec::new();
+        if auto_content_tag {
+            let head = data_file
+                .read_range(0, data_len.min(BLOCK_SIZE as usize))
+                .await?;
+            let content_type =
+                infer::get(&head).map_or("application/octet-stream", |k| k.mime_type());
+            tags.push(Tag::<Base64>::from_utf8_strs("Content-Type", content_type)?);
+        }
+        tags.extend(other_tags.unwrap_or_default());
+
+        let mut transaction = self.merklize_leaves(leaves, data_len as u64)?;
+        transaction.data_file = Some(Arc::new(data_file));
+        self.complete_transaction(transaction, Some(tags), last_tx, price_terms, false)
+            .await
+    }
+
+    pub fn merklize(&self, data: Vec<u8>) -> Result<Transaction, Error> {
+        let chunks = generate_leaves(data.clone(), &self.crypto)?;
+        let mut transaction = self.merklize_leaves(chunks, data.len() as u64)?;
+        transaction.data = Base64(data);
+        Ok(transaction)
+    }
+
+    /// Same as [`Arweave::merklize`] from leaves that have already been generated, e.g. by
+    /// [`LeavesWriter`], returning a transaction without its data.
+    pub fn merklize_leaves(&self, chunks: Vec<Node>, data_size: u64) -> Result<Transaction, Error> {
+        let (root, chunks, proofs) = chunks_and_proofs(chunks, &self.crypto)?;
+        let data_root = Base64(root.id.into_iter().collect());
+
+        Ok(Transaction {
+            format: 2,
+            data_size,
+            data_root,
+            chunks,
+            proofs,
+            ..Default::default()
+        })
+    }
+
+    /// Returns [`Error::ReadOnlyMode`] if [`Arweave::read_only`] is set. Checked before every
+    /// request that posts to the network.
+    pub fn check_writable(&self) -> Result<(), Error> {
+        if self.read_only {
+            Err(Error::ReadOnlyMode)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Gets `url` once any [`Cooldown`] has passed, starting a new one if the gateway responds
+    /// with 429.
+    async fn get(&self, url: Url) -> Result<reqwest::Response, Error> {
+        self.cooldown.wait().await;
+        let resp = self.send(self.client.get(url)).await?;
+        self.cooldown.observe(&resp);
+        Ok(resp)
+    }
+
+    /// Sends `request`, counting it in [`Arweave::connection_stats`].
+    async fn send(&self, request: RequestBuilder) -> Result<reqwest::Response, reqwest::Error> {
+        let (client, request) = request.build_split();
+        let request = request?;
+        self.connection_stats.observe();
+        client.execute(request).await
+    }
+
+    /// Posts `body` as json, gzip encoded if [`Arweave::gzip_requests`] is set. If the gateway
+    /// responds with 415, the request is sent again uncompressed and compression is turned off for
+    /// subsequent requests.
+    async fn post_json<T: Serialize>(
+        &self,
+        client: &Client,
+        url: Url,
+        body: &T,
+    ) -> Result<reqwest::Response, Error> {
+        let json = Bytes::from(serde_json::to_vec(body)?);
+        self.post_json_bytes(client, url, json).await
+    }
+
+    /// Posts `json`, already serialized, as with [`Arweave::post_json`].
+    async fn post_json_bytes(
+        &self,
+        client: &Client,
+        url: Url,
+        json: Bytes,
+    ) -> Result<reqwest::Response, Error> {
+        let request = || {
+            client
+                .post(url.clone())
+                .header(&ACCEPT, "application/json")
+                .header(&CONTENT_TYPE, "application/json")
+        };
+
+        self.cooldown.wait().await;
+        if self.gzip_requests && !self.gzip_stats.rejected() {
+            let gzipped = utils::gzip(&json)?;
+            let gzipped_len = gzipped.len();
+            let resp = self
+                .send(request().header(&CONTENT_ENCODING, "gzip").body(gzipped))
+                .await
+                .map_err(|e| Error::ArweavePostError(e))?;
+            if resp.status() != ResponseStatusCode::UNSUPPORTED_MEDIA_TYPE {
+                self.cooldown.observe(&resp);
+                self.gzip_stats.bytes_saved.fetch_add(
+                    json.len().saturating_sub(gzipped_len) as u64,
+                    Ordering::Relaxed,
+                );
+                return Ok(resp);
+            }
+            debug!("gzip encoded request rejected by {}", url);
+            self.gzip_stats.rejected.store(true, Ordering::Relaxed);
+        }
+
+        let resp = self
+            .send(request().body(json))
+            .await
+            .map_err(|e| Error::ArweavePostError(e))?;
+        self.cooldown.observe(&resp);
+        Ok(resp)
+    }
+
+    pub async fn post_chunk(&self, chunk: &Chunk, client: &Client) -> Result<usize, Error> {
+        self.check_writable()?;
+        let url = Endpoints::chunk(&self.base_url)?;
+
+        let resp = self.post_json(client, url, chunk).await?;
+
+        match resp.status() {
+            reqwest::StatusCode::OK | reqwest::StatusCode::ALREADY_REPORTED => Ok(chunk.offset),
+            _ => Err(post_rejected(resp).await),
+        }
+    }
+
+    /// Posts a chunk, retrying up to [`CHUNKS_RETRIES`] times if it fails with an error that
+    /// [`Error::is_retryable`].
+    pub async fn post_chunk_with_retries(
+        &self,
+        chunk: Chunk,
+        client: Client,
+    ) -> Result<usize, Error> {
+        self.check_writable()?;
+        let mut retries = 0;
+        let mut resp = self.post_chunk(&chunk, &client).await;
+
+        while retries < CHUNKS_RETRIES {
+            match resp {
+                Ok(offset) => return Ok(offset),
+                Err(e) if !e.is_retryable() => return Err(e),
+                Err(e) => {
+                    log::debug!("post_chunk_with_retries: {:?}", e);
+                    sleep(Duration::from_secs(CHUNKS_RETRY_SLEEP)).await;
+                    retries += 1;
+                    self.post_retries.fetch_add(1, Ordering::Relaxed);
+                    resp = self.post_chunk(&chunk, &client).await;
+                }
+            }
+        }
+        resp
+    }
+
+    pub async fn post_transaction(
+        &self,
+        signed_transaction: &Transaction,
+    ) -> Result<(Base64, u64), Error> {
+        self.check_writable()?;
+        if signed_transaction.id.0.is_empty() {
+            return Err(error::Error::UnsignedTransaction.into());
+        }
+
+        let mut retries = 0;
+        let mut error = Error::StatusCodeNotOk;
+
+        while retries < CHUNKS_RETRIES {
+            match self.try_post_transaction(signed_transaction).await {
+                // Posting the same transaction again won't help, but the next ones get a new
+                // anchor.
+                Err(e) if e.is_stale_anchor() => {
+                    self.refresh_anchor().await?;
+                    return Err(e);
+                }
+                Err(e @ Error::ArweavePostRejected { .. }) if e.is_retryable() => {
+                    error = e;
+                }
+                result => return result,
+            }
+            log::debug!("post_transaction: {}", error);
+            sleep(Duration::from_secs(CHUNKS_RETRY_SLEEP)).await;
+            retries += 1;
+            self.post_retries.fetch_add(1, Ordering::Relaxed);
+        }
+
+        Err(error)
+    }
+
+    /// Posts a transaction once, without retrying.
+    async fn try_post_transaction(
+        &self,
+        signed_transaction: &Transaction,
+    ) -> Result<(Base64, u64), Error> {
+        let body = transaction_body(signed_transaction)?;
+        self.try_post_transaction_body(signed_transaction, body)
+            .await
+    }
+
+    /// Posts `signed_transaction` once as `body`, its serialized json from [`transaction_body`],
+    /// which can be shared between attempts.
+    async fn try_post_transaction_body(
+        &self,
+        signed_transaction: &Transaction,
+        body: Bytes,
+    ) -> Result<(Base64, u64), Error> {
+        self.check_writable()?;
+        if signed_transaction.id.0.is_empty() {
+            return Err(error::Error::UnsignedTransaction.into());
+        }
+
+        let url = Endpoints::post_tx(&self.base_url)?;
+        let resp = self.post_json_bytes(&self.client, url, body).await?;
+        match resp.status() {
+            // Already accepted, e.g., if a previous post timed out after it was received.
+            reqwest::StatusCode::OK | reqwest::StatusCode::ALREADY_REPORTED => {
+                Ok((signed_transaction.id.clone(), signed_transaction.reward))
+            }
+            _ => Err(post_rejected(resp).await),
+        }
+    }
+
+    /// Posts the ids of the data items included in a bundle to `url` as an index hint, used by
+    /// some gateways to speed up unbundling.
+    pub async fn post_bundle_index_hint(
+        &self,
+        status: &BundleStatus,
+        url: &Url,
+    ) -> Result<(), Error> {
+        self.check_writable()?;
+        let item_ids: Vec<&str> = status
+            .file_paths
+            .as_object()
+            .map(|m| m.values().filter_map(|v| v["id"].as_str()).collect())
+            .unwrap_or_default();
+        let body = json!({
+            "bundle_id": status.id.to_string(),
+            "item_ids": item_ids,
+        });
+
+        let resp_status = self
+            .send(
+                self.client
+                    .post(url.clone())
+                    .json(&body)
+                    .header(&ACCEPT, "application/json")
+                    .header(&CONTENT_TYPE, "application/json"),
+            )
+            .await?
+            .status();
+
+        if resp_status.is_success() {
+            Ok(())
+        } else {
+            Err(Error::ArweaveNetworkError(resp_status))
+        }
+    }
+
+    /// Posts a transaction without its data followed by its chunks, sending the number of chunks
+    /// posted to `progress`, if provided, as each one completes.
+    pub async fn post_transaction_chunks(
+        &self,
+        signed_transaction: Transaction,
+        chunks_buffer: usize,
+        progress: Option<mpsc::UnboundedSender<UploadProgress>>,
+    ) -> Result<(Base64, u64), Error> {
+        self.post_chunks_from(
+            Arc::new(signed_transaction),
+            chunks_buffer,
+            progress,
+            &mut ChunksResume::default(),
+            true,
+        )
+        .await
+    }
+
+    /// Posts a transaction in chunks from where `resume` left off, posting its header first
+    /// unless it has already been accepted, and updates `resume` to the first chunk that failed.
+    /// The header and each chunk are posted once unless `retry`, with retries otherwise left to
+    /// the caller.
+    async fn post_chunks_from(
+        &self,
+        signed_transaction: Arc<Transaction>,
+        chunks_buffer: usize,
+        progress: Option<mpsc::UnboundedSender<UploadProgress>>,
+        resume: &mut ChunksResume,
+        retry: bool,
+    ) -> Result<(Base64, u64), Error> {
+        if signed_transaction.id.0.is_empty() {
+            return Err(error::Error::UnsignedTransaction.into());
+        }
+
+        let (id, reward) = (signed_transaction.id.clone(), signed_transaction.reward);
+        if !resume.header_posted {
+            let transaction_with_no_data = signed_transaction.clone_with_no_data()?;
+            if retry {
+                self.post_transaction(&transaction_with_no_data).await?;
+            } else {
+                self.try_post_transaction(&transaction_with_no_data).await?;
+            }
+            resume.header_posted = true;
+        }
+
+        let chunks_total = signed_transaction.chunks.len();
+        let mut upload_progress = UploadProgress {
+            chunks_posted: resume.first_chunk,
+            chunks_total,
+        };
+        let send_progress = |upload_progress| {
+            if let Some(progress) = &progress {
+                progress.unbounded_send(upload_progress).ok();
+            }
+        };
+        send_progress(upload_progress);
+
+        let results: Vec<(usize, Result<usize, Error>)> = upload_chunks_from_stream(
+            self,
+            signed_transaction,
+            resume.first_chunk,
+            chunks_buffer,
+            retry,
+        )
+        .inspect(|(_, result)| {
+            if result.is_ok() {
+                upload_progress.chunks_posted += 1;
+                send_progress(upload_progress);
+            }
+        })
+        .collect()
+        .await;
+
+        let first_failed = results
+            .into_iter()
+            .filter_map(|(i, result)| result.err().map(|error| (i, error)))
+            .min_by_key(|(i, _)| *i);
+        match first_failed {
+            Some((i, error)) => {
+                resume.first_chunk = i;
+                Err(error)
+            }
+            None => {
+                resume.first_chunk = chunks_total;
+                Ok((id, reward))
+            }
+        }
+    }
+
+    /// Gets deep hash, signs and sets signature and id.
+    pub fn sign_transaction(&self, mut transaction: Transaction) -> Result<Transaction, Error> {
+        let deep_hash_item = transaction.to_deep_hash_item()?;
+        let deep_hash = self.crypto.deep_hash(deep_hash_item)?;
+        let signature = self.crypto.sign(&deep_hash)?;
+        let id = self.crypto.hash_sha256(&signature)?;
+        transaction.signature = Base64(signature);
+        transaction.id = Base64(id.to_vec());
+        Ok(transaction)
+    }
+
+    /// Builds the unsigned transaction, or data item if `as_data_item`, that uploading `file_path`
+    /// with `tags` would sign, with the same tags and [`Arweave::tip`] but an empty anchor in place
+    /// of the one fetched from the gateway or generated at random, returning it with its deep
+    /// hash. Used to compare against hashes signed elsewhere, e.g., by the sol_ar service.
+    pub async fn deep_hash_preview(
+        &self,
+        file_path: PathBuf,
+        mut tags: Vec<Tag<String>>,
+        as_data_item: bool,
+        price_terms: (u64, u64),
+    ) -> Result<DeepHashPreview, Error> {
+        // Tags are built as when uploading, with a Content-Type tag overriding the detected
+        // content type.
+        let (deep_hash_item, unsigned) = if as_data_item {
+            let content_type_override = take_content_type_override(&mut tags);
+            let data = fs::read(&file_path).await?;
+            let content_type = push_data_item_content_type(
+                &file_path,
+                &data,
+                &mut tags,
+                content_type_override.as_deref(),
+            )?;
+            let mut data_item = self.create_data_item(
+                data,
+                tags,
+                content_type.is_none(),
+                false,
+                SignatureType::Arweave,
+            )?;
+            data_item.owner = self.crypto.keypair_modulus()?;
+            (
+                data_item.to_deep_hash_item()?,
+                serde_json::to_value(&data_item)?,
+            )
+        } else {
+            let tags = tags
+                .iter()
+                .map(|t| Tag::<Base64>::from_utf8_strs(&t.name, &t.value))
+                .collect::<Result<Vec<_>, _>>()?;
+            let (tags, _, auto_content_tag) = file_transaction_tags(&file_path, Some(tags))?;
+            let transaction = self
+                .create_transaction_from_file_path(
+                    file_path,
+                    tags,
+                    Some(Base64(Vec::new())),
+                    price_terms,
+                    auto_content_tag,
+                )
+                .await?;
+            (
+                transaction.to_deep_hash_item()?,
+                serde_json::to_value(&transaction)?,
+            )
+        };
+        Ok(DeepHashPreview {
+            deep_hash: self.crypto.deep_hash(deep_hash_item.clone())?,
+            deep_hash_item,
+            unsigned,
+        })
+    }
+
+    /// Signs transaction with sol_ar service, paying for its reward and any [`Arweave::tip`]
+    /// in its quantity, since both are sent from the wallet of the service.
+    ///
+    /// Payments are referenced by a hash of the data and tags, so an earlier payment for the same
+    /// data and tags is used instead of paying again, even if the transaction has a new anchor.
+    /// If the service doesn't respond, the same SOL transaction is sent again unless it failed,
+    /// since a transfer that is still pending would otherwise be paid twice.
+    pub async fn sign_transaction_with_sol(
+        &self,
+        mut transaction: Transaction,
+        solana_url: Url,
+        sol_ar_url: Url,
+        from_keypair: &Keypair,
+    ) -> Result<(Transaction, SigResponse), Error> {
+        self.check_writable()?;
+        let lamports = std::cmp::max((transaction.reward + transaction.quantity) / RATE, FLOOR);
+        let reference = payment_reference(&payment_key(&transaction));
+
+        let mut sol_tx =
+            get_or_create_sol_transaction(solana_url.clone(), from_keypair, lamports, &reference)
+                .await?;
+        let mut resp = get_sol_ar_signature(
+            sol_ar_url.clone(),
+            transaction.to_deep_hash_item()?,
+            sol_tx.clone(),
+        )
+        .await;
+
+        let mut retries = 0;
+        while retries < CHUNKS_RETRIES {
+            match resp {
+                Ok(_) => {
+                    retries = CHUNKS_RETRIES;
+                }
+                Err(_) => {
+                    eprintln!(
+                        "Retrying Solana transaction ({} of {})...",
+                        retries + 1,
+                        CHUNKS_RETRIES
+                    );
+                    retries += 1;
+                    sleep(Duration::from_millis(300)).await;
+                    if sol_transaction_failed(solana_url.clone(), &sol_tx_signature(&sol_tx)?)
+                        .await?
+                    {
+                        sol_tx = get_or_create_sol_transaction(
+                            solana_url.clone(),
+                            from_keypair,
+                            lamports,
+                            &reference,
+                        )
+                        .await?;
+                    }
+                    resp = get_sol_ar_signature(
+                        sol_ar_url.clone(),
+                        transaction.to_deep_hash_item()?,
+                        sol_tx.clone(),
+                    )
+                    .await;
+                }
+            }
+        }
+        if let Ok(sig_response) = resp {
+            let sig_response_copy = sig_response.clone();
+            transaction.signature = sig_response.ar_tx_sig;
+            transaction.id = sig_response.ar_tx_id;
+            transaction.owner = sig_response.ar_tx_owner;
+            Ok((transaction, sig_response_copy))
+        } else {
+            eprintln!(
+                "There was a problem with the Solana network. Please try again later or use AR."
+            );
+            Err(Error::SolanaNetworkError)
+        }
+    }
+
+    /// Checks that the gateway is reachable and that the reward of `transaction` covers the
+    /// current network price of its data before paying for it with SOL, since the payment isn't
+    /// refunded if the gateway rejects the transaction.
+    pub async fn check_before_sol_payment(&self, transaction: &Transaction) -> Result<(), Error> {
+        self.get_network_info().await?;
+        let price = self.get_reward(transaction.data_size).await?;
+        if transaction.reward < price {
+            return Err(Error::RewardBelowPrice {
+                reward: transaction.reward,
+                price,
+            });
+        }
+        Ok(())
+    }
+
+    /// Saves a signed transaction paid for with SOL to [`UNPOSTED_DIR`] in `log_dir`, returning
+    /// the path it was saved to, or `None` if there is no `log_dir`.
+    pub async fn save_paid_transaction(
+        &self,
+        transaction: &Transaction,
+        sig_response: SigResponse,
+        status: PaidStatus,
+        log_dir: Option<&Path>,
+    ) -> Result<Option<PathBuf>, Error> {
+        let dir = match log_dir {
+            Some(log_dir) => log_dir.join(UNPOSTED_DIR),
+            None => return Ok(None),
+        };
+        fs::create_dir_all(&dir).await?;
+        let path = dir.join(transaction.id.to_string()).with_extension("json");
+        let paid = PaidTransaction {
+            transaction: transaction.clone(),
+            sig_response,
+            status,
+        };
+        fs::write(&path, serde_json::to_string(&paid)?).await?;
+        Ok(Some(path))
+    }
+
+    /// Paths of the [`PaidTransaction`]s saved to `log_dir` that haven't been posted.
+    pub fn paid_transaction_paths(&self, log_dir: &Path) -> Result<Vec<PathBuf>, Error> {
+        let pattern = log_dir.join(UNPOSTED_DIR).join("*.json");
+        Ok(glob(&pattern.display().to_string())?
+            .filter_map(Result::ok)
+            .collect())
+    }
+
+    /// Posts the [`PaidTransaction`] saved at `path` again, writing its status to `log_dir` and
+    /// removing it once it has been posted.
+    pub async fn repost_paid_transaction(
+        &self,
+        path: &Path,
+        log_dir: &Path,
+        chunks_buffer: usize,
+    ) -> Result<(Base64, u64), Error> {
+        let paid: PaidTransaction = serde_json::from_slice(&fs::read(path).await?)?;
+        let mut transaction = paid.transaction;
+        // Transactions created from files are saved without their data, which is read back from
+        // the file. recompute_chunks checks that it hasn't changed since.
+        if let (
+            true,
+            PaidStatus::File(Status {
+                file_path: Some(file_path),
+                ..
+            }),
+        ) = (
+            transaction.data.0.is_empty() && transaction.data_size > 0,
+            &paid.status,
+        ) {
+            transaction.data_file = Some(Arc::new(BundleFile::open(file_path.clone())?));
+        }
+        // Chunks and proofs aren't saved, so they're calculated again from the data.
+        transaction.recompute_chunks(&self.crypto.hasher)?;
+        let (id, reward) = if self.network_params.posts_chunks(transaction.data_size) {
+            self.post_transaction_chunks(transaction, chunks_buffer, None)
+                .await?
+        } else {
+            transaction.load_data().await?;
+            self.post_transaction(&transaction).await?
+        };
+
+        match paid.status {
+            PaidStatus::Bundle(status) => {
+                fs::write(
+                    log_dir.join(status.id.to_string()).with_extension("json"),
+                    to_status_json(&status, self.pretty_status)?,
+                )
+                .await?
+            }
+            PaidStatus::File(status) => {
+                self.write_status(status, log_dir.to_path_buf(), None)
+                    .await?
+            }
+        }
+        fs::remove_file(path).await?;
+        Ok((id, reward))
+    }
+
+    pub async fn upload_file_from_path(
+        &self,
+        file_path: PathBuf,
+        log_dir: Option<PathBuf>,
+        additional_tags: Option<Vec<Tag<Base64>>>,
+        last_tx: Option<Base64>,
+        price_terms: (u64, u64),
+    ) -> Result<Status, Error> {
+        // A Content-Type tag in the additional tags overrides the one from the file extension.
+        let (additional_tags, status_content_type, auto_content_tag) =
+            file_transaction_tags(&file_path, additional_tags)?;
+
+        let transaction = self
+            .create_transaction_from_file_path_streamed(
+                file_path.clone(),
+                additional_tags,
+                last_tx,
+                price_terms,
+                auto_content_tag,
+            )
+            .await?;
+        let data_hash = file_data_hash(file_path.clone()).await?;
+        let mut signed_transaction = self.sign_transaction(transaction)?;
+        let data_size = signed_transaction.data_size;
+        let (id, reward) = if self.network_params.posts_chunks(data_size) {
+            self.post_transaction_chunks(signed_transaction, 100, None)
+                .await?
+        } else {
+            signed_transaction.load_data().await?;
+            self.post_transaction(&signed_transaction).await?
+        };
+
+        let status = Status {
+            id,
+            reward,
+            file_path: Some(file_path),
+            content_type: status_content_type,
+            tip: self.tip.clone(),
+            data_size: Some(data_size),
+            data_hash: Some(data_hash),
+            ..Default::default()
+        };
+
+        if let Some(log_dir) = log_dir {
+            self.write_status(status.clone(), log_dir, None).await?;
+        }
+        Ok(status)
+    }
+
+    pub async fn upload_file_from_path_with_sol(
+        &self,
+        file_path: PathBuf,
+        log_dir: Option<PathBuf>,
+        additional_tags: Option<Vec<Tag<Base64>>>,
+        last_tx: Option<Base64>,
+        price_terms: (u64, u64),
+        solana_url: Url,
+        sol_ar_url: Url,
+        from_keypair: &Keypair,
+    ) -> Result<Status, Error> {
+        // A Content-Type tag in the additional tags overrides the one from the file extension.
+        let (additional_tags, status_content_type, auto_content_tag) =
+            file_transaction_tags(&file_path, additional_tags)?;
+
+        let transaction = self
+            .create_transaction_from_file_path_streamed(
+                file_path.clone(),
+                additional_tags,
+                last_tx,
+                price_terms,
+                auto_content_tag,
+            )
+            .await?;
+        self.check_before_sol_payment(&transaction).await?;
+        let data_hash = file_data_hash(file_path.clone()).await?;
+
+        let (mut signed_transaction, sig_response): (Transaction, SigResponse) = self
+            .sign_transaction_with_sol(transaction, solana_url, sol_ar_url, from_keypair)
+            .await?;
+
+        let mut status = Status {
+            file_path: Some(file_path),
+            content_type: status_content_type,
+            id: signed_transaction.id.clone(),
+            reward: signed_transaction.reward,
+            data_size: Some(signed_transaction.data_size),
+            data_hash: Some(data_hash),
+            sol_sig: Some(sig_response.clone()),
+            ..Default::default()
+        };
+        let paid_path = self
+            .save_paid_transaction(
+                &signed_transaction,
+                sig_response,
+                PaidStatus::File(status.clone()),
+                log_dir.as_deref(),
+            )
+            .await?;
+
+        let posted = if self
+            .network_params
+            .posts_chunks(signed_transaction.data_size)
+        {
+            self.post_transaction_chunks(signed_transaction, 100, None)
+                .await
+        } else {
+            match signed_transaction.load_data().await {
+                Ok(()) => self.post_transaction(&signed_transaction).await,
+                Err(error) => Err(error),
+            }
+        };
+        let (id, reward) = finish_paid_post(posted, paid_path).await?;
+        status.id = id;
+        status.reward = reward;
+
+        if let Some(log_dir) = log_dir {
+            self.write_status(status.clone(), log_dir, None).await?;
+        }
+        Ok(status)
+    }
+
+    /// Uploads files from an iterator of paths.
+    ///
+    /// Optionally logs Status objects to `log_dir`, if provided and optionally adds tags to each
+    /// transaction from an iterator of tags that must be the same size as the paths iterator,
+    /// returning [`Error::TagsLengthMismatch`] before uploading anything if it isn't. Extra tags
+    /// are only detected if the iterator knows exactly how many it has left, so that infinite
+    /// iterators, like [`std::iter::repeat`], are also accepted to add the same tags to each file.
+    pub async fn upload_files_from_paths<IP, IT>(
+        &self,
+        paths_iter: IP,
+        log_dir: Option<PathBuf>,
+        tags_iter: Option<IT>,
+        last_tx: Option<Base64>,
+        price_terms: (u64, u64),
+    ) -> Result<Vec<Status>, Error>
+    where
+        IP: Iterator<Item = PathBuf> + Send,
+        IT: Iterator<Item = Option<Vec<Tag<Base64>>>> + Send,
+    {
+        let statuses = if let Some(mut tags_iter) = tags_iter {
+            let paths: Vec<PathBuf> = paths_iter.collect();
+            let paths_len = paths.len();
+            let uploads: Vec<(PathBuf, Option<Vec<Tag<Base64>>>)> =
+                paths.into_iter().zip(tags_iter.by_ref()).collect();
+            // Remaining tags aren't consumed, since they may never end, e.g. from iter::repeat.
+            let extra = match tags_iter.size_hint() {
+                (lower, Some(upper)) if lower == upper => lower,
+                _ => 0,
+            };
+            if uploads.len() != paths_len || extra > 0 {
+                return Err(Error::TagsLengthMismatch {
+                    paths: paths_len,
+                    tags: uploads.len() + extra,
+                });
+            }
+            try_join_all(uploads.into_iter().map(|(p, t)| {
+                self.upload_file_from_path(p, log_dir.clone(), t, last_tx.clone(), price_terms)
+            }))
+        } else {
+            try_join_all(paths_iter.map(|p| {
+                self.upload_file_from_path(p, log_dir.clone(), None, last_tx.clone(), price_terms)
+            }))
+        }
+        .await?;
+        Ok(statuses)
+    }
+
+    //-------------------------
+    // Status
+    //-------------------------
+
+    pub async fn create_log_dir(&self, parent_dir: &Path) -> Result<PathBuf, Error> {
+        let mut rand_bytes: [u8; 8] = [0; 8];
+        self.crypto.fill_rand(&mut rand_bytes)?;
+        let suffix = base64::encode_config(rand_bytes, base64::URL_SAFE_NO_PAD);
+        let log_dir = parent_dir.join(format!("arloader_{}", suffix));
+        fs::create_dir_all(&log_dir).await?;
+        Ok(log_dir)
+    }
+
+    /// Filters saved Status objects by status and/or number of confirmations. Return
+    /// all statuses if no status codes or maximum confirmations are provided.
+    ///
+    /// If there is no raw status object and max_confirms is passed, it
+    /// assumes there are zero confirms. This is designed to be used to
+    /// determine whether all files have a confirmed status and to collect the
+    /// paths of the files that need to be re-uploaded.
+    pub fn filter_statuses<S>(
+        &self,
+        all_statuses: Vec<S>,
+        statuses: Option<Vec<StatusCode>>,
+        max_confirms: Option<u64>,
+    ) -> Result<Vec<S>, Error>
+    where
+        S: Filterable,
+    {
+        // let all_statuses = self.read_statuses(paths_iter, log_dir).await?;
+
+        let filtered = if let Some(statuses) = statuses {
+            if let Some(max_confirms) = max_confirms {
+                all_statuses
+                    .into_iter()
+                    .filter(|s| {
+                        let s = s.get_filter_elements();
+                        let confirms = if let Some(raw_status) = &s.raw_status {
+                            raw_status.number_of_confirmations
+                        } else {
+                            0
+                        };
+                        (&statuses.iter().any(|c| c == s.status)) & (confirms <= max_confirms)
+                    })
+                    .collect()
+            } else {
+                all_statuses
+                    .into_iter()
+                    .filter(|s| {
+                        let s = s.get_filter_elements();
+                        statuses.iter().any(|c| c == s.status)
+                    })
+                    .collect()
+            }
+        } else {
+            if let Some(max_confirms) = max_confirms {
+                all_statuses
+                    .into_iter()
+                    .filter(|s| {
+                        let s = s.get_filter_elements();
+                        let confirms = if let Some(raw_status) = &s.raw_status {
+                            raw_status.number_of_confirmations
+                        } else {
+                            0
+                        };
+                        confirms <= max_confirms
+                    })
+                    .collect()
+            } else {
+                all_statuses
+            }
+        };
+
+        Ok(filtered)
+    }
+
+    /// Gets status from network.
+    pub async fn get_status(&self, id: &Base64) -> Result<Status, Error> {
+        let url = Endpoints::status(&self.base_url, id)?;
+        let resp = self.get(url).await?;
+        let mut status = Status {
+            id: id.clone(),
+            ..Status::default()
+        };
+
+        match resp.status() {
+            ResponseStatusCode::OK => {
+                let resp_string = resp.text().await?;
+                if &resp_string == &String::from("Pending") {
+                    status.status = StatusCode::Pending;
+                } else {
+                    let mut raw_status: RawStatus = serde_json::from_str(&resp_string)?;
+                    // Confirmations reported by the node can lag the network height.
+                    if let Ok(info) = self.get_network_info().await {
+                        raw_status.reconcile_confirmations(info.height);
+                    }
+                    status.raw_status = Some(raw_status);
+                    status.status = StatusCode::Confirmed;
+                }
+            }
+            ResponseStatusCode::ACCEPTED => {
+                status.status = StatusCode::Pending;
+            }
+            ResponseStatusCode::NOT_FOUND => {
+                status.status = StatusCode::NotFound;
+            }
+            other_status => {
+                return Err(Error::ArweaveNetworkError(other_status));
+            }
+        }
+        Ok(status)
+    }
+
+    pub async fn read_bundle_status(&self, file_path: PathBuf) -> Result<BundleStatus, Error> {
+        let data = fs::read_to_string(&file_path).await?;
+        parse_status(&data, &file_path)
+    }
+
+    // Reads statuses from a list of paths, skipping those written by newer versions.
+    pub async fn read_bundle_statuses(&self, log_dir: &str) -> Result<Vec<BundleStatus>, Error> {
+        let paths_iter = glob(&format!("{}*.json", log_dir))?
+            .filter_map(Result::ok)
+            .filter(|p| file_stem_is_valid_txid(p));
+        skip_unsupported_statuses(join_all(paths_iter.map(|p| self.read_bundle_status(p))).await)
+    }
+
+    /// Moves the statuses of `replaced` bundles in `log_dir` to [`SUPERSEDED_DIR`] once all of
+    /// their files are recorded in statuses of bundles that were posted since `earlier_ids` were
+    /// read, so that a status is only moved after its replacement bundles from the same run post,
+    /// not because of older statuses for the same files. Returns the ids of the bundles whose
+    /// statuses were moved.
+    pub async fn supersede_bundle_statuses(
+        &self,
+        replaced: &[BundleStatus],
+        earlier_ids: &HashSet<Base64>,
+        log_dir: &Path,
+    ) -> Result<Vec<Base64>, Error> {
+        let replacements: HashSet<String> = self
+            .read_bundle_statuses(&log_dir.join("").display().to_string())
+            .await?
+            .into_iter()
+            .filter(|s| !earlier_ids.contains(&s.id) && !s.is_posting())
+            .filter_map(|s| s.file_paths.as_object().cloned())
+            .flat_map(|paths| paths.into_iter().map(|(k, _)| k))
+            .collect();
+
+        let superseded_dir = log_dir.join(SUPERSEDED_DIR);
+        let mut superseded = Vec::new();
+        for status in replaced {
+            let file_paths = status.file_paths.as_object();
+            if !file_paths.map_or(false, |p| p.keys().all(|k| replacements.contains(k))) {
+                continue;
+            }
+            let file_name = format!("{}.json", status.id);
+            fs::create_dir_all(&superseded_dir).await?;
+            fs::rename(log_dir.join(&file_name), superseded_dir.join(&file_name)).await?;
+            superseded.push(status.id.clone());
+        }
+        Ok(superseded)
+    }
+
+    /// Deletes the statuses moved to [`SUPERSEDED_DIR`] in `log_dir`, returning how many were
+    /// deleted.
+    pub async fn purge_superseded_statuses(&self, log_dir: &Path) -> Result<usize, Error> {
+        let pattern = log_dir.join(SUPERSEDED_DIR).join("*.json");
+        let paths: Vec<PathBuf> = glob(&pattern.display().to_string())?
+            .filter_map(Result::ok)
+            .collect();
+        try_join_all(paths.iter().map(fs::remove_file)).await?;
+        Ok(paths.len())
+    }
+
+    /// Reads the files referenced by the [`Status`]es and [`BundleStatus`]es in `log_dir`, one
+    /// per file path, keeping the most recently modified status for files uploaded more than once.
+    /// Bundles with chunks left to post, see [`BundleStatus::is_posting`], are left out.
+    pub async fn read_recorded_files(&self, log_dir: &str) -> Result<Vec<RecordedFile>, Error> {
+        let mut files: BTreeMap<PathBuf, (DateTime<Utc>, RecordedFile)> = BTreeMap::new();
+        let mut insert = |last_modified, file: RecordedFile| match files.get(&file.file_path) {
+            Some((modified, _)) if *modified > last_modified => {}
+            _ => {
+                files.insert(file.file_path.clone(), (last_modified, file));
+            }
+        };
+
+        for status in self.read_bundle_statuses(log_dir).await? {
+            if status.is_posting() {
+                continue;
+            }
+            if let Some(file_paths) = status.file_paths.as_object() {
+                for (file_path, entry) in file_paths {
+                    insert(
+                        status.last_modified,
+                        RecordedFile::from_manifest_entry(file_path, entry),
+                    );
+                }
+            }
+        }
+
+        for status in self.read_file_statuses(log_dir).await? {
+            if let Some(file) = RecordedFile::from_status(&status) {
+                insert(status.last_modified, file);
+            }
+        }
+
+        Ok(files.into_values().map(|(_, file)| file).collect())
+    }
+
+    /// Reads all of the [`Status`]es of individual files in `log_dir`.
+    pub async fn read_file_statuses(&self, log_dir: &str) -> Result<Vec<Status>, Error> {
+        // Statuses of individual files are named with the BLAKE3 hash of their paths, which
+        // distinguishes them from manifests and metaplex items written to the same directory.
+        let paths_iter = glob(&format!("{}*.json", log_dir))?
+            .filter_map(Result::ok)
+            .filter(|p| {
+                p.file_stem().and_then(|s| s.to_str()).map_or(false, |s| {
+                    s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit())
+                })
+            });
+        skip_unsupported_statuses(
+            join_all(paths_iter.map(|p| async move {
+                let data = fs::read_to_string(&p).await?;
+                parse_status(&data, &p)
+            }))
+            .await,
+        )
+    }
+
+    /// Returns the data sizes and rewards recorded by the [`BundleStatus`]es and [`Status`]es in
+    /// `log_dir`, leaving out statuses written before sizes were recorded.
+    pub async fn sizes_from_log_dir(&self, log_dir: &str) -> Result<Vec<RecordedSize>, Error> {
+        let mut sizes: Vec<RecordedSize> = self
+            .read_bundle_statuses(log_dir)
+            .await?
+            .iter()
+            .map(RecordedSize::from_bundle_status)
+            .collect();
+        sizes.extend(
+            self.read_file_statuses(log_dir)
+                .await?
+                .iter()
+                .filter_map(RecordedSize::from_status),
+        );
+        Ok(sizes)
+    }
+
+    pub async fn status_summary<IP>(
+        &self,
+        paths_iter: IP,
+        log_dir: PathBuf,
+    ) -> Result<String, Error>
+    where
+        IP: Iterator<Item = PathBuf> + Send,
+    {
+        let statuses = self.read_statuses(paths_iter, log_dir).await?;
+        let status_counts: HashMap<StatusCode, u32> =
+            statuses
+                .into_iter()
+                .fold(HashMap::new(), |mut map, status| {
+                    *map.entry(status.status).or_insert(0) += 1;
+                    map
+                });
+
+        let mut total = 0;
+        let mut output = String::new();
+        writeln!(output, " {:<15}  {:>10}", "status", "count")?;
+        writeln!(output, "{:-<29}", "")?;
+        for k in vec![
+            StatusCode::Submitted,
+            StatusCode::Pending,
+            StatusCode::NotFound,
+            StatusCode::Confirmed,
+        ] {
+            let v = status_counts.get(&k).unwrap_or(&0);
+            writeln!(output, " {:<16} {:>10}", &k.to_string(), v)?;
+            total += v;
+        }
+
+        writeln!(output, "{:-<29}", "")?;
+        writeln!(output, " {:<15}  {:>10}", "Total", total)?;
+
+        Ok(output)
+    }
+
+    /// Counts the bundle statuses in `log_dir` by status, along with their number of files and
+    /// data size, as a table like [`Arweave::status_summary`] with files and KB columns, or as
+    /// json for the json output formats.
+    pub async fn bundle_status_summary(
+        &self,
+        log_dir: &str,
+        output_format: &OutputFormat,
+    ) -> Result<String, Error> {
+        let statuses = self.read_bundle_statuses(log_dir).await?;
+        let status_counts: HashMap<StatusCode, (u64, u64, u64)> =
+            statuses
+                .into_iter()
+                .fold(HashMap::new(), |mut map, status| {
+                    let counts = map.entry(status.status).or_insert((0, 0, 0));
+                    counts.0 += 1;
+                    counts.1 += status.number_of_files;
+                    counts.2 += status.data_size;
+                    map
+                });
+
+        let rows: Vec<(String, (u64, u64, u64))> = vec![
+            StatusCode::Submitted,
+            StatusCode::Pending,
+            StatusCode::NotFound,
+            StatusCode::Confirmed,
+        ]
+        .into_iter()
+        .map(|k| {
+            let counts = status_counts.get(&k).copied().unwrap_or_default();
+            (k.to_string(), counts)
+        })
+        .collect();
+        let total = rows
+            .iter()
+            .fold((0, 0, 0), |t, (_, c)| (t.0 + c.0, t.1 + c.1, t.2 + c.2));
+
+        let summary = || {
+            let statuses: Vec<Value> = rows
+                .iter()
+                .map(|(status, (bundles, files, data_size))| {
+                    json!({
+                        "status": status,
+                        "bundles": bundles,
+                        "files": files,
+                        "data_size": data_size,
+                    })
+                })
+                .collect();
+            json!({
+                "statuses": statuses,
+                "total": {"bundles": total.0, "files": total.1, "data_size": total.2},
+            })
+        };
+        match output_format {
+            OutputFormat::Json => return Ok(serde_json::to_string_pretty(&summary())?),
+            OutputFormat::JsonCompact | OutputFormat::JsonLines => return Ok(summary().to_string()),
+            _ => (),
+        }
+
+        let mut output = String::new();
+        writeln!(
+            output,
+            " {:<15}  {:>10} {:>10} {:>12}",
+            "status", "bundles", "files", "KB"
+        )?;
+        writeln!(output, "{:-<52}", "")?;
+        for (k, (bundles, files, data_size)) in &rows {
+            writeln!(
+                output,
+                " {:<16} {:>10} {:>10} {:>12}",
+                k,
+                bundles,
+                files,
+                data_size / 1000
+            )?;
+        }
+
+        writeln!(output, "{:-<52}", "")?;
+        writeln!(
+            output,
+            " {:<15}  {:>10} {:>10} {:>12}",
+            "Total",
+            total.0,
+            total.1,
+            total.2 / 1000
+        )?;
+
+        Ok(output)
+    }
+
+    // Reads a status from file.
+    pub async fn read_status(&self, file_path: PathBuf, log_dir: PathBuf) -> Result<Status, Error> {
+        let file_path_hash = blake3::hash(file_path.to_str().unwrap().as_bytes());
+
+        let status_path = log_dir
+            .join(file_path_hash.to_string())
+            .with_extension("json");
+
+        if status_path.exists() {
+            let data = fs::read_to_string(&status_path).await?;
+            parse_status(&data, &status_path)
+        } else {
+            Err(Error::StatusNotFound)
+        }
+    }
+
+    // Reads statuses from a list of paths, skipping those written by newer versions.
+    pub async fn read_statuses<IP>(
+        &self,
+        paths_iter: IP,
+        log_dir: PathBuf,
+    ) -> Result<Vec<Status>, Error>
+    where
+        IP: Iterator<Item = PathBuf> + Send,
+    {
+        skip_unsupported_statuses(
+            join_all(paths_iter.map(|p| self.read_status(p, log_dir.clone()))).await,
+        )
+    }
+
+    /// Returns the paths that have neither a [`Status`] nor an entry in the `file_paths` of a
+    /// [`BundleStatus`] in `log_dir`, i.e. files that were never uploaded. Files in bundles with
+    /// chunks left to post, see [`BundleStatus::is_posting`], aren't uploaded.
+    pub async fn paths_not_in_log_dir<IP>(
+        &self,
+        paths_iter: IP,
+        log_dir: PathBuf,
+    ) -> Result<Vec<PathBuf>, Error>
+    where
+        IP: Iterator<Item = PathBuf> + Send,
+    {
+        let bundled_paths: HashSet<String> = self
+            .read_bundle_statuses(&log_dir.join("").display().to_string())
+            .await?
+            .into_iter()
+            .filter(|s| !s.is_posting())
+            .filter_map(|s| {
+                s.file_paths
+                    .as_object()
+                    .map(|m| m.keys().cloned().collect::<Vec<String>>())
+            })
+            .flatten()
+            .collect();
+
+        let paths: Vec<PathBuf> = paths_iter
+            .filter(|p| !bundled_paths.contains(&p.display().to_string()))
+            .collect();
+        let results = join_all(
+            paths
+                .iter()
+                .map(|p| self.read_status(p.clone(), log_dir.clone())),
+        )
+        .await;
+
+        // Statuses written by newer versions still mean the file was uploaded.
+        paths
+            .into_iter()
+            .zip(results)
+            .filter_map(|(path, result)| match result {
+                Err(Error::StatusNotFound) => Some(Ok(path)),
+                Err(Error::UnsupportedStatusVersion { .. }) | Ok(_) => None,
+                Err(e) => Some(Err(e)),
+            })
+            .collect()
+    }
+
+    /// Fetches the bundle for `status` and checks each of its data items, returning a
+    /// [`BundleItemCheck`] for every id in the bundle or in [`BundleStatus::file_paths`]. Data
+    /// items are compared to the local files they were created from if those still exist.
+    pub async fn verify_bundle_status(
+        &self,
+        status: &BundleStatus,
+    ) -> Result<Vec<BundleItemCheck>, Error> {
+        let bundle = self.download_data(&status.id).await?;
+
+        let mut checks = Vec::<BundleItemCheck>::new();
+        let mut recorded = HashMap::<String, (usize, Option<RecordedFile>)>::new();
+        for (id, item_bytes) in split_bundle(&bundle)? {
+            let id = Base64(id);
+            if let Some((index, _)) = recorded.get(&id.to_string()) {
+                checks[*index].occurrences += 1;
+                continue;
+            }
+            let verified = DataItem::from_slice(item_bytes).and_then(|data_item| {
+                verify_data_item(&data_item, &id.0, &self.crypto)?;
+                Ok(data_item)
+            });
+            let (invalid, data) = match verified {
+                Ok(data_item) => (None, Some(data_item.data.0)),
+                Err(e) => (Some(e.to_string()), None),
+            };
+            let recorded_data = data.map(|data| RecordedFile {
+                file_path: PathBuf::new(),
+                id: id.clone(),
+                data_size: Some(data.len() as u64),
+                data_hash: Some(status::data_hash(&data)),
+            });
+            recorded.insert(id.to_string(), (checks.len(), recorded_data));
+            checks.push(BundleItemCheck {
+                id,
+                file_path: None,
+                occurrences: 1,
+                invalid,
+                local: None,
+            });
+        }
+
+        if let Some(file_paths) = status.file_paths.as_object() {
+            for (file_path, entry) in file_paths {
+                let id = entry["id"].as_str().unwrap_or_default();
+                match recorded.get(id) {
+                    Some((index, recorded_data)) => {
+                        let check = &mut checks[*index];
+                        check.file_path = Some(PathBuf::from(file_path));
+                        check.local = recorded_data
+                            .clone()
+                            .map(|recorded_data| {
+                                RecordedFile {
+                                    file_path: PathBuf::from(file_path),
+                                    ..recorded_data
+                                }
+                                .check()
+                            })
+                            .filter(|state| *state != LocalFileState::Missing);
+                    }
+                    None => checks.push(BundleItemCheck {
+                        id: Base64::from_str(id).unwrap_or_default(),
+                        file_path: Some(PathBuf::from(file_path)),
+                        occurrences: 0,
+                        invalid: None,
+                        local: None,
+                    }),
+                }
+            }
+        }
+        Ok(checks)
+    }
+
+    /// Downloads the data uploaded for `file` and compares its hash with the hash of the local
+    /// file. Data items are downloaded with the ids recorded in [`BundleStatus::file_paths`]. A
+    /// HEAD request is sent first, so that missing data or data of the wrong size fails without
+    /// being downloaded.
+    pub async fn verify_upload(&self, file: &RecordedFile) -> UploadCheck {
+        let head_failure = match (
+            self.head_data(&file.id).await,
+            fs::metadata(&file.file_path).await,
+        ) {
+            (Ok(None), _) => Some("not found on gateway".to_string()),
+            (Ok(Some((len, _))), Ok(metadata)) if len != metadata.len() => Some(format!(
+                "gateway has {} bytes, local file has {}",
+                len,
+                metadata.len()
+            )),
+            // Gateways that don't answer HEAD requests are checked by downloading.
+            _ => None,
+        };
+        if head_failure.is_some() {
+            return UploadCheck {
+                file_path: file.file_path.clone(),
+                id: Some(file.id.clone()),
+                failure: head_failure,
+            };
+        }
+        let failure = match (
+            self.download_data(&file.id).await,
+            fs::read(&file.file_path).await,
+        ) {
+            (Err(e), _) => Some(format!("download failed: {}", e)),
+            (_, Err(e)) => Some(format!("local file unreadable: {}", e)),
+            (Ok(data), Ok(local)) if data.len() != local.len() => Some(format!(
+                "downloaded {} bytes, local file has {}",
+                data.len(),
+                local.len()
+            )),
+            (Ok(data), Ok(local)) if status::data_hash(&data) != status::data_hash(&local) => {
+                Some("data differs from local file".to_string())
+            }
+            _ => None,
+        };
+        UploadCheck {
+            file_path: file.file_path.clone(),
+            id: Some(file.id.clone()),
+            failure,
+        }
+    }
+
+    pub async fn update_bundle_status(&self, file_path: PathBuf) -> Result<BundleStatus, Error> {
+        let status = self.read_bundle_status(file_path.clone()).await?;
+        self.refresh_bundle_status(status, file_path).await
+    }
+
+    /// Updates `status`, read from `file_path`, from the network and writes it back.
+    async fn refresh_bundle_status(
+        &self,
+        mut status: BundleStatus,
+        file_path: PathBuf,
+    ) -> Result<BundleStatus, Error> {
+        let trans_status = self.get_status(&status.id).await?;
+        status.touch(&SystemClock);
+        status.status = trans_status.status;
+        status.raw_status = trans_status.raw_status;
+        fs::write(&file_path, to_status_json(&status, self.pretty_status)?).await?;
+        Ok(status)
+    }
+
+    pub async fn update_status(
+        &self,
+        file_path: PathBuf,
+        log_dir: PathBuf,
+    ) -> Result<Status, Error> {
+        let status = self.read_status(file_path, log_dir.clone()).await?;
+        self.refresh_status(status, log_dir).await
+    }
+
+    /// Updates `status`, read from `log_dir`, from the network and writes it back.
+    async fn refresh_status(&self, mut status: Status, log_dir: PathBuf) -> Result<Status, Error> {
+        let trans_status = self.get_status(&status.id).await?;
+        status.touch(&SystemClock);
+        status.status = trans_status.status;
+        status.raw_status = trans_status.raw_status;
+        self.write_status(status.clone(), log_dir, None).await?;
+        Ok(status)
+    }
+
+    pub async fn update_statuses<IP>(
+        &self,
+        paths_iter: IP,
+        log_dir: PathBuf,
+    ) -> Result<Vec<Status>, Error>
+    where
+        IP: Iterator<Item = PathBuf> + Send,
+    {
+        skip_unsupported_statuses(
+            join_all(paths_iter.map(|p| self.update_status(p, log_dir.clone()))).await,
+        )
+    }
+
+    /// Writes Status Json to `log_dir` with file name based on BLAKE3 hash of `status.file_path`.
+    ///
+    /// This is done to facilitate checking the status of uploaded file and also means that only
+    /// one status object can exist for a given `file_path`. If for some reason you wanted to record
+    /// statuses for multiple uploads of the same file you can provide a different `log_dir` (or copy the
+    /// file to a different directory and upload from there).
+    pub async fn write_status(
+        &self,
+        status: Status,
+        log_dir: PathBuf,
+        file_stem: Option<String>,
+    ) -> Result<(), Error> {
+        let file_stem = if let Some(stem) = file_stem {
+            stem
+        } else {
+            if let Some(file_path) = &status.file_path {
+                if status.id.0.is_empty() {
+                    return Err(error::Error::UnsignedTransaction.into());
+                }
+                blake3::hash(file_path.to_str().unwrap().as_bytes()).to_string()
+            } else {
+                format!("txid_{}", status.id)
+            }
+        };
+
+        fs::write(
+            log_dir.join(file_stem).with_extension("json"),
+            to_status_json(&status, self.pretty_status)?,
+        )
+        .await?;
+        Ok(())
+    }
+
+    //-------------------------
+    // Manifest
+    //-------------------------
+
+    pub fn create_data_item_from_manifest(&self, manifest: Value) -> Result<DataItem, Error> {
+        let tags = vec![
+            Tag::<String>::from_utf8_strs("Content-Type", MANIFEST_CONTENT_TYPE)?,
+            Tag::<String>::from_utf8_strs("User-Agent", &format!("arloader/{}", VERSION))?,
+        ];
+
+        Ok(DataItem {
+            data: Base64(serde_json::to_string(&manifest)?.as_bytes().to_vec()),
+            tags,
+            anchor: self.random_anchor()?,
+            ..DataItem::default()
+        })
+    }
+
+    /// Creates an `arweave/paths` manifest of the files uploaded with `statuses`, served at
+    /// `index_path` when the manifest itself is requested if provided, see
+    /// [`Arweave::set_manifest_index`].
+    pub fn create_manifest(
+        &self,
+        statuses: Vec<Status>,
+        index_path: Option<&str>,
+    ) -> Result<Value, Error> {
+        let paths = statuses
+            .into_iter()
+            .fold(serde_json::Map::new(), |mut m, s| {
+                m.insert(
+                    s.file_path.unwrap().to_str().unwrap().to_string(),
+                    json!({"id": s.id.to_string(), "content_type": s.content_type}),
+                );
+                m
+            });
+
+        self.finish_manifest(paths, index_path)
+    }
+
+    /// Same as [`Arweave::create_manifest`] for files uploaded in bundles. Manifests bundled
+    /// with [`Arweave::post_manifest_bundle`] and bundles with chunks left to post, see
+    /// [`BundleStatus::is_posting`], are left out.
+    pub fn create_manifest_from_bundle_statuses(
+        &self,
+        statuses: Vec<BundleStatus>,
+        index_path: Option<&str>,
+    ) -> Result<Value, Error> {
+        self.finish_manifest(bundle_statuses_paths(statuses), index_path)
+    }
+
+    /// Wraps `paths` in a manifest with `index_path` as its index, returning
+    /// [`Error::TooManyManifestPaths`] if there are more than [`Arweave::max_manifest_paths`]
+    /// unless [`Arweave::split_manifests`] is set.
+    fn finish_manifest(
+        &self,
+        paths: serde_json::Map<String, Value>,
+        index_path: Option<&str>,
+    ) -> Result<Value, Error> {
+        if !self.split_manifests {
+            self.check_manifest_paths(paths.len())?;
+        }
+        let manifest = json!({
+            "manifest": "arweave/paths",
+            "version": "0.1.0",
+            "paths": Value::Object(paths)
+        });
+        let manifest = self.set_manifest_index(manifest, index_path)?;
+        self.set_manifest_fallback(manifest, self.manifest_fallback.as_deref())
+    }
+
+    fn check_manifest_paths(&self, count: usize) -> Result<(), Error> {
+        if count > self.max_manifest_paths {
+            return Err(Error::TooManyManifestPaths {
+                count,
+                max: self.max_manifest_paths,
+            });
+        }
+        Ok(())
+    }
+
+    /// Sets the `index` of `manifest` to `index_path`, e.g., `index.html`, returning
+    /// [`Error::ManifestIndexNotFound`] unless it's one of the paths of the manifest relative to
+    /// [`Arweave::manifest_base_dir`], the way they're requested through the manifest.
+    pub fn set_manifest_index(
+        &self,
+        mut manifest: Value,
+        index_path: Option<&str>,
+    ) -> Result<Value, Error> {
+        let index_path = match index_path {
+            Some(index_path) => utils::relative_manifest_path(index_path, Path::new("")),
+            None => return Ok(manifest),
+        };
+        let base_dir = self.manifest_base(&manifest)?;
+        let found = manifest_paths(&manifest)?
+            .keys()
+            .any(|k| utils::relative_manifest_path(k, &base_dir) == index_path);
+        if !found {
+            return Err(Error::ManifestIndexNotFound(index_path));
+        }
+        manifest["index"] = json!({ "path": index_path });
+        Ok(manifest)
+    }
+
+    /// Sets the `fallback` of `manifest` to the id of `fallback_path`, e.g., `404.html`, which
+    /// gateways serve for paths that aren't in the manifest, returning
+    /// [`Error::ManifestFallbackNotFound`] unless it's one of the paths of the manifest relative to
+    /// [`Arweave::manifest_base_dir`]. Fallbacks were added in version 0.2.0 of the manifest
+    /// format, so the version is set to it as well.
+    pub fn set_manifest_fallback(
+        &self,
+        mut manifest: Value,
+        fallback_path: Option<&str>,
+    ) -> Result<Value, Error> {
+        let fallback_path = match fallback_path {
+            Some(fallback_path) => utils::relative_manifest_path(fallback_path, Path::new("")),
+            None => return Ok(manifest),
+        };
+        let base_dir = self.manifest_base(&manifest)?;
+        let id = manifest_paths(&manifest)?
+            .iter()
+            .find(|(k, _)| utils::relative_manifest_path(k, &base_dir) == fallback_path)
+            .map(|(_, path_object)| path_object["id"].clone())
+            .ok_or_else(|| Error::ManifestFallbackNotFound(fallback_path.clone()))?;
+        manifest["version"] = json!("0.2.0");
+        manifest["fallback"] = json!({ "id": id });
+        Ok(manifest)
+    }
+
+    pub async fn create_transaction_from_manifest(
+        &self,
+        manifest: Value,
+        price_terms: (u64, u64),
+    ) -> Result<Transaction, Error> {
+        let tags = vec![Tag::<Base64>::from_utf8_strs(
+            "Content-Type",
+            MANIFEST_CONTENT_TYPE,
+        )?];
+
+        // let mut anchor = Base64(Vec::with_capacity(32));
+        // self.crypto.fill_rand(&mut anchor.0)?;
+
+        let data = serde_json::to_string(&manifest)?.as_bytes().to_vec();
+        let transaction = self
+            .create_transaction(data, Some(tags), None, price_terms, false)
+            .await?;
+
+        Ok(transaction)
+    }
+
+    pub async fn upload_manifest_from_bundle_log_dir(
+        &self,
+        log_dir: &str,
+        price_terms: (u64, u64),
+        solana_url: Url,
+        sol_ar_url: Url,
+        from_keypair: Option<Keypair>,
+        index_path: Option<&str>,
+    ) -> Result<String, Error> {
+        let paths: Vec<PathBuf> = glob(&format!("{}*.json", log_dir.clone()))?
+            .filter_map(Result::ok)
+            .collect();
+
+        let paths_len = paths.len();
+        if paths_len == 0 {
+            return Ok(format!("No bundle statuses found in {}", log_dir));
+        };
+
+        let statuses = self.read_bundle_statuses(log_dir).await?;
+
+        let manifest = self.create_manifest_from_bundle_statuses(statuses, index_path)?;
+        self.upload_manifest(
+            manifest,
+            log_dir,
+            price_terms,
+            solana_url,
+            sol_ar_url,
+            from_keypair,
+        )
+        .await
+    }
+
+    /// Creates and uploads a manifest from all of the [`Status`]es of files uploaded
+    /// individually, without bundling, in `log_dir`, see [`Arweave::read_file_statuses`]. The
+    /// consolidated manifest file has the same entries as for bundles.
+    pub async fn upload_manifest_from_log_dir(
+        &self,
+        log_dir: &str,
+        price_terms: (u64, u64),
+        solana_url: Url,
+        sol_ar_url: Url,
+        from_keypair: Option<Keypair>,
+        index_path: Option<&str>,
+    ) -> Result<String, Error> {
+        let statuses: Vec<Status> = self
+            .read_file_statuses(log_dir)
+            .await?
+            .into_iter()
+            .filter(|s| s.file_path.is_some())
+            .collect();
+        if statuses.is_empty() {
+            return Ok(format!("No statuses found in {}", log_dir));
+        };
+
+        let manifest = self.create_manifest(statuses, index_path)?;
+        self.upload_manifest(
+            manifest,
+            log_dir,
+            price_terms,
+            solana_url,
+            sol_ar_url,
+            from_keypair,
+        )
+        .await
+    }
+
+    /// Creates and uploads a manifest from the [`Status`]s of files uploaded individually,
+    /// without bundling, read from `log_dir`.
+    pub async fn upload_manifest_from_file_paths<IP>(
+        &self,
+        paths_iter: IP,
+        log_dir: &str,
+        price_terms: (u64, u64),
+        solana_url: Url,
+        sol_ar_url: Url,
+        from_keypair: Option<Keypair>,
+        index_path: Option<&str>,
+    ) -> Result<String, Error>
+    where
+        IP: Iterator<Item = PathBuf> + Send,
+    {
+        let statuses = self
+            .read_statuses(paths_iter, PathBuf::from(log_dir))
+            .await?;
+        if statuses.len() == 0 {
+            return Ok(format!("No statuses found in {}", log_dir));
+        };
+
+        let manifest = self.create_manifest(statuses, index_path)?;
+        self.upload_manifest(
+            manifest,
+            log_dir,
+            price_terms,
+            solana_url,
+            sol_ar_url,
+            from_keypair,
+        )
+        .await
+    }
+
+    /// Uploads a manifest, paying with SOL if `from_keypair` is provided, and writes the
+    /// consolidated manifest file to `log_dir`. Manifests with more than
+    /// [`Arweave::max_manifest_paths`] paths are uploaded as several manifests if
+    /// [`Arweave::split_manifests`] is set, see [`Arweave::upload_split_manifests`].
+    pub async fn upload_manifest(
+        &self,
+        manifest: Value,
+        log_dir: &str,
+        price_terms: (u64, u64),
+        solana_url: Url,
+        sol_ar_url: Url,
+        from_keypair: Option<Keypair>,
+    ) -> Result<String, Error> {
+        let num_files = manifest["paths"].as_object().unwrap().keys().len();
+        if self.split_manifests && num_files > self.max_manifest_paths {
+            return self
+                .upload_split_manifests(
+                    manifest,
+                    log_dir,
+                    price_terms,
+                    solana_url,
+                    sol_ar_url,
+                    from_keypair,
+                )
+                .await;
+        }
+
+        let (signed_transaction, sig_response) = self
+            .sign_manifest(
+                &manifest,
+                price_terms,
+                solana_url,
+                sol_ar_url,
+                from_keypair.as_ref(),
+            )
+            .await?;
+        let id = signed_transaction.id.clone();
+        let manifest_path = self
+            .write_manifest(manifest, id.to_string(), PathBuf::from(log_dir))
+            .await?;
+        self.post_written_manifest(&signed_transaction, sig_response, &manifest_path)
+            .await?;
+
+        Ok(format!("Uploaded manifest for {} files and wrote to {}manifest_{id}.json.\n\nRun `arloader get-status {id}` to confirm manifest transaction.",
+        num_files, log_dir, id=id.to_string()))
+    }
+
+    /// Uploads `manifest` as several manifests of at most [`Arweave::max_manifest_paths`] paths,
+    /// split by directory with [`utils::partition_manifest_paths`]. Paths keep the same relative
+    /// path in whichever manifest they're in. Writes a consolidated manifest file to `log_dir`
+    /// with links through the manifest each path is in, recorded as its `manifest_id`, and a
+    /// `manifest_index_<ID>.json` file mapping each directory prefix to the id of its manifest.
+    ///
+    /// Manifests are posted in order and both files are rewritten as each one posts, so if one
+    /// fails to post they're kept with the manifests that were posted before it. If paid for with
+    /// SOL, every manifest is saved to [`UNPOSTED_DIR`] before any is posted, and the files list
+    /// all of them, since those that fail to post can be posted again with
+    /// [`Arweave::repost_paid_transaction`].
+    pub async fn upload_split_manifests(
+        &self,
+        manifest: Value,
+        log_dir: &str,
+        price_terms: (u64, u64),
+        solana_url: Url,
+        sol_ar_url: Url,
+        from_keypair: Option<Keypair>,
+    ) -> Result<String, Error> {
+        let base_dir = self.manifest_base(&manifest)?;
+        let paths = manifest_paths(&manifest)?;
+        let keys: HashMap<String, &String> = paths
+            .keys()
+            .map(|k| (utils::relative_manifest_path(k, &base_dir), k))
+            .collect();
+        let partitions = utils::partition_manifest_paths(
+            keys.keys().cloned().collect(),
+            self.max_manifest_paths,
+        )?;
+
+        let mut signed_parts = Vec::with_capacity(partitions.len());
+        for partition in &partitions {
+            let mut part = manifest.clone();
+            part["paths"] = partition
+                .paths
+                .iter()
+                .map(|p| (keys[p].clone(), paths[keys[p]].clone()))
+                .collect();
+            let has_index = manifest["index"]["path"]
+                .as_str()
+                .map_or(false, |index_path| {
+                    partition.paths.iter().any(|p| p == index_path)
+                });
+            if !has_index {
+                part.as_object_mut().unwrap().remove("index");
+            }
+
+            let (signed_transaction, sig_response) = self
+                .sign_encoded_manifest(
+                    self.encode_manifest_keys_from(&part, &base_dir)?,
+                    price_terms,
+                    solana_url.clone(),
+                    sol_ar_url.clone(),
+                    from_keypair.as_ref(),
+                )
+                .await?;
+            let id = signed_transaction.id.to_string();
+            let mut part_links = self.manifest_links(&part, &id, &base_dir);
+            part_links
+                .values_mut()
+                .for_each(|links| links["manifest_id"] = json!(id));
+            signed_parts.push((signed_transaction, sig_response, part_links));
+        }
+
+        let ids: Vec<String> = signed_parts
+            .iter()
+            .map(|(t, _, _)| t.id.to_string())
+            .collect();
+        let log_dir = PathBuf::from(log_dir);
+        let manifest_path = log_dir
+            .join(format!("manifest_{}", ids[0]))
+            .with_extension("json");
+        let index_path = log_dir
+            .join(format!("manifest_index_{}", ids[0]))
+            .with_extension("json");
+        let write_links = |count: usize| {
+            let consolidated_paths: serde_json::Map<String, Value> = signed_parts[..count]
+                .iter()
+                .flat_map(|(_, _, part_links)| part_links.clone())
+                .collect();
+            let index = utils::manifest_prefix_index(&partitions[..count], &ids[..count]);
+            let manifest_path = &manifest_path;
+            let index_path = &index_path;
+            async move {
+                fs::write(manifest_path, serde_json::to_string(&consolidated_paths)?).await?;
+                fs::write(index_path, serde_json::to_string(&index)?).await?;
+                Ok::<(), Error>(())
+            }
+        };
+
+        let mut paid_paths = Vec::with_capacity(signed_parts.len());
+        for (signed_transaction, sig_response, _) in &signed_parts {
+            let paid_path = match sig_response {
+                Some(sig_response) => {
+                    let status = Status {
+                        id: signed_transaction.id.clone(),
+                        reward: signed_transaction.reward,
+                        content_type: MANIFEST_CONTENT_TYPE.to_string(),
+                        data_size: Some(signed_transaction.data.0.len() as u64),
+                        sol_sig: Some(sig_response.clone()),
+                        ..Status::default()
+                    };
+                    self.save_paid_transaction(
+                        signed_transaction,
+                        sig_response.clone(),
+                        PaidStatus::File(status),
+                        Some(&log_dir),
+                    )
+                    .await?
+                }
+                None => None,
+            };
+            paid_paths.push(paid_path);
+        }
+        let paid = paid_paths.iter().any(Option::is_some);
+        if paid {
+            write_links(signed_parts.len()).await?;
+        }
+        for (i, ((signed_transaction, _, _), paid_path)) in
+            signed_parts.iter().zip(paid_paths).enumerate()
+        {
+            let posted = self.post_transaction(signed_transaction).await;
+            finish_paid_post(posted, paid_path).await?;
+            if !paid {
+                write_links(i + 1).await?;
+            }
+        }
+
+        Ok(format!(
+            "Uploaded {} manifests for {} files and wrote links to {} and the manifest of each directory to {}.\n\nRun `arloader get-status <ID>` with each id in {} to confirm the manifest transactions.",
+            ids.len(),
+            paths.len(),
+            manifest_path.display(),
+            index_path.display(),
+            index_path.display()
+        ))
+    }
+
+    /// Signs and posts a manifest with encoded keys, paying with SOL if `from_keypair` is
+    /// provided, returning the id and reward of the manifest transaction.
+    pub async fn post_manifest(
+        &self,
+        manifest: &Value,
+        price_terms: (u64, u64),
+        solana_url: Url,
+        sol_ar_url: Url,
+        from_keypair: Option<&Keypair>,
+    ) -> Result<(Base64, u64), Error> {
+        let (signed_transaction, _) = self
+            .sign_manifest(manifest, price_terms, solana_url, sol_ar_url, from_keypair)
+            .await?;
+        self.post_transaction(&signed_transaction).await
+    }
+
+    /// Creates and signs a transaction for a manifest with encoded keys, paying with SOL if
+    /// `from_keypair` is provided, in which case the [`SigResponse`] of the payment is returned
+    /// with it. The id of the manifest is known once it's signed, so links through it can be
+    /// written before it's posted. Returns [`Error::TooManyManifestPaths`] if the manifest has
+    /// more than [`Arweave::max_manifest_paths`] paths.
+    pub async fn sign_manifest(
+        &self,
+        manifest: &Value,
+        price_terms: (u64, u64),
+        solana_url: Url,
+        sol_ar_url: Url,
+        from_keypair: Option<&Keypair>,
+    ) -> Result<(Transaction, Option<SigResponse>), Error> {
+        self.check_manifest_paths(manifest["paths"].as_object().unwrap().len())?;
+        self.sign_encoded_manifest(
+            self.encode_manifest_keys(manifest)?,
+            price_terms,
+            solana_url,
+            sol_ar_url,
+            from_keypair,
+        )
+        .await
+    }
+
+    async fn sign_encoded_manifest(
+        &self,
+        encoded: Value,
+        price_terms: (u64, u64),
+        solana_url: Url,
+        sol_ar_url: Url,
+        from_keypair: Option<&Keypair>,
+    ) -> Result<(Transaction, Option<SigResponse>), Error> {
+        let transaction = self
+            .create_transaction_from_manifest(encoded, price_terms)
+            .await?;
+
+        if let Some(from_keypair) = from_keypair {
+            let (signed_transaction, sig_response): (Transaction, SigResponse) = self
+                .sign_transaction_with_sol(transaction, solana_url, sol_ar_url, from_keypair)
+                .await?;
+            Ok((signed_transaction, Some(sig_response)))
+        } else {
+            Ok((self.sign_transaction(transaction)?, None))
+        }
+    }
+
+    /// Posts a manifest signed with [`Arweave::sign_manifest`] after it has been written to
+    /// `manifest_path` with [`Arweave::write_manifest`]. The manifest file is removed if posting
+    /// fails, so that manifest files are only left in log dirs for posted manifests, unless the
+    /// manifest was paid for with SOL. Then it's saved to [`UNPOSTED_DIR`] next to the manifest
+    /// file before posting, and both are kept if posting fails so that it can be posted again
+    /// with [`Arweave::repost_paid_transaction`].
+    pub async fn post_written_manifest(
+        &self,
+        signed_transaction: &Transaction,
+        sig_response: Option<SigResponse>,
+        manifest_path: &Path,
+    ) -> Result<(Base64, u64), Error> {
+        let paid_path = match sig_response {
+            Some(sig_response) => {
+                let status = Status {
+                    id: signed_transaction.id.clone(),
+                    reward: signed_transaction.reward,
+                    file_path: Some(manifest_path.to_path_buf()),
+                    content_type: MANIFEST_CONTENT_TYPE.to_string(),
+                    data_size: Some(signed_transaction.data.0.len() as u64),
+                    sol_sig: Some(sig_response.clone()),
+                    ..Status::default()
+                };
+                self.save_paid_transaction(
+                    signed_transaction,
+                    sig_response,
+                    PaidStatus::File(status),
+                    manifest_path.parent(),
+                )
+                .await?
+            }
+            None => None,
+        };
+        let posted = self.post_transaction(signed_transaction).await;
+        if posted.is_err() && paid_path.is_none() {
+            fs::remove_file(manifest_path).await.ok();
+        }
+        finish_paid_post(posted, paid_path).await
+    }
+
+    /// Signs a data item of `manifest` with its keys encoded, see
+    /// [`Arweave::encode_manifest_keys`], paired with a status recording the consolidated
+    /// manifest file that [`Arweave::write_manifest`] writes to `log_dir` for it, so that it can
+    /// be uploaded in a bundle.
+    pub fn create_manifest_data_item(
+        &self,
+        manifest: &Value,
+        log_dir: &Path,
+    ) -> Result<(DataItem, Status), Error> {
+        self.check_manifest_paths(manifest["paths"].as_object().unwrap().len())?;
+        let data_item =
+            self.create_data_item_from_manifest(self.encode_manifest_keys(manifest)?)?;
+        let data_item = self.sign_data_item(data_item)?;
+        let status = Status {
+            id: data_item.id.clone(),
+            file_path: Some(log_dir.join(format!("manifest_{}.json", data_item.id))),
+            content_type: MANIFEST_CONTENT_TYPE.to_string(),
+            ..Status::default()
+        };
+        Ok((data_item, status))
+    }
+
+    /// Signs data items of the files in `paths_chunk`, the last chunk of an upload, and a data
+    /// item of the manifest of those files and the files uploaded in `statuses`, built from the
+    /// ids of the signed items, see [`Arweave::create_manifest_data_item`]. Returns the data items
+    /// with the manifest last, the manifest and its id, and the bytes of data between them.
+    async fn create_manifest_bundle_items(
+        &self,
+        paths_chunk: PathsChunk,
+        tags: Vec<Tag<String>>,
+        statuses: Vec<BundleStatus>,
+        log_dir: &Path,
+    ) -> Result<(Vec<(DataItem, Status)>, Value, Base64, u64), Error> {
+        let mut data_items = self
+            .create_data_items_from_file_paths(paths_chunk.0, tags)
+            .await?;
+        let mut paths = bundle_statuses_paths(statuses);
+        for (_, status) in &data_items {
+            paths.insert(
+                status.file_path.as_ref().unwrap().display().to_string(),
+                json!({"id": status.id.to_string(), "content_type": status.content_type}),
+            );
+        }
+        let manifest = self.finish_manifest(paths, None)?;
+
+        let (data_item, status) = self.create_manifest_data_item(&manifest, log_dir)?;
+        let id = data_item.id.clone();
+        let data_size = paths_chunk.1 + data_item.data.0.len() as u64;
+        data_items.push((data_item, status));
+        Ok((data_items, manifest, id, data_size))
+    }
+
+    /// Posts the files in `paths_chunk`, the last chunk of an upload, in a bundle along with a
+    /// manifest of them and the files uploaded in `statuses`, see
+    /// [`Arweave::create_manifest_bundle_items`], instead of posting the manifest in a
+    /// transaction of its own, and writes the manifest to `log_dir` once posted. Returns the id
+    /// of the manifest and the status of the bundle.
+    pub async fn post_manifest_bundle(
+        &self,
+        paths_chunk: PathsChunk,
+        tags: Vec<Tag<String>>,
+        statuses: Vec<BundleStatus>,
+        log_dir: PathBuf,
+        price_terms: (u64, u64),
+        chunks_buffer: usize,
+        retry_policy: RetryPolicy,
+    ) -> Result<(Base64, BundleStatus), Error> {
+        let (data_items, manifest, id, data_size) = self
+            .create_manifest_bundle_items(paths_chunk, tags, statuses, &log_dir)
+            .await?;
+        let bundle_status = self
+            .post_bundle_transaction_from_data_items(
+                data_items,
+                data_size,
+                Some(log_dir.clone()),
+                price_terms,
+                chunks_buffer,
+                retry_policy,
+            )
+            .await?;
+        self.write_manifest(manifest, id.to_string(), log_dir)
+            .await?;
+        Ok((id, bundle_status))
+    }
+
+    /// Same as [`Arweave::post_manifest_bundle`], paying for the bundle with SOL.
+    pub async fn post_manifest_bundle_with_sol(
+        &self,
+        paths_chunk: PathsChunk,
+        tags: Vec<Tag<String>>,
+        statuses: Vec<BundleStatus>,
+        log_dir: PathBuf,
+        price_terms: (u64, u64),
+        chunks_buffer: usize,
+        retry_policy: RetryPolicy,
+        solana_url: Url,
+        sol_ar_url: Url,
+        from_keypair: &Keypair,
+    ) -> Result<(Base64, BundleStatus), Error> {
+        let (data_items, manifest, id, data_size) = self
+            .create_manifest_bundle_items(paths_chunk, tags, statuses, &log_dir)
+            .await?;
+        let bundle_status = self
+            .post_bundle_transaction_from_data_items_with_sol(
+                data_items,
+                data_size,
+                Some(log_dir.clone()),
+                price_terms,
+                chunks_buffer,
+                retry_policy,
+                solana_url,
+                sol_ar_url,
+                from_keypair,
+            )
+            .await?;
+        self.write_manifest(manifest, id.to_string(), log_dir)
+            .await?;
+        Ok((id, bundle_status))
+    }
+
+    /// Directory that the file paths keying `manifest` are made relative to,
+    /// [`Arweave::manifest_base_dir`] if set, or else their common parent.
+    fn manifest_base(&self, manifest: &Value) -> Result<PathBuf, Error> {
+        Ok(match &self.manifest_base_dir {
+            Some(base_dir) => base_dir.clone(),
+            None => utils::common_parent(manifest_paths(manifest)?.keys().map(|k| k.as_str())),
+        })
+    }
+
+    /// Returns a copy of `manifest` with its path keys made relative to
+    /// [`Arweave::manifest_base_dir`] and encoded with [`utils::manifest_key`], so that they
+    /// resolve when requested from a gateway. This is the manifest that gets posted, so each path
+    /// only keeps its `id`, leaving out what is recorded locally about the file.
+    pub fn encode_manifest_keys(&self, manifest: &Value) -> Result<Value, Error> {
+        self.encode_manifest_keys_from(manifest, &self.manifest_base(manifest)?)
+    }
+
+    fn encode_manifest_keys_from(&self, manifest: &Value, base_dir: &Path) -> Result<Value, Error> {
+        let mut encoded = manifest.clone();
+        let mut paths = serde_json::Map::new();
+        for (file_path, path_object) in manifest["paths"].as_object().unwrap() {
+            paths.insert(
+                utils::manifest_key(
+                    &utils::relative_manifest_path(file_path, base_dir),
+                    self.strict_paths,
+                )?,
+                json!({ "id": path_object["id"] }),
+            );
+        }
+        encoded["paths"] = Value::Object(paths);
+        if let Some(index_path) = manifest["index"]["path"].as_str() {
+            encoded["index"]["path"] = json!(utils::manifest_key(index_path, self.strict_paths)?);
+        }
+        Ok(encoded)
+    }
+
+    /// Writes the paths of an uploaded manifest to `log_dir`, keyed by file path, with links to
+    /// each file by id and through the manifest by its relative path, see
+    /// [`Arweave::encode_manifest_keys`], returning the path of the manifest file.
+    pub async fn write_manifest(
+        &self,
+        manifest: Value,
+        transaction_id: String,
+        log_dir: PathBuf,
+    ) -> Result<PathBuf, Error> {
+        let consolidated_paths =
+            self.manifest_links(&manifest, &transaction_id, &self.manifest_base(&manifest)?);
+        let manifest_path = log_dir
+            .join(format!("manifest_{}", transaction_id))
+            .with_extension("json");
+        fs::write(
+            &manifest_path,
+            serde_json::to_string(&json!(consolidated_paths))?,
+        )
+        .await?;
+        Ok(manifest_path)
+    }
+
+    /// Entries of the consolidated manifest file written by [`Arweave::write_manifest`].
+    fn manifest_links(
+        &self,
+        manifest: &Value,
+        transaction_id: &str,
+        base_dir: &Path,
+    ) -> serde_json::Map<String, Value> {
+        let mut consolidated_paths = serde_json::Map::new();
+        for (file_path, id_obj) in manifest["paths"].as_object().unwrap() {
+            let id = id_obj["id"].as_str().unwrap();
+            let content_type = id_obj["content_type"].as_str().unwrap();
+            let key =
+                utils::encode_manifest_key(&utils::relative_manifest_path(file_path, base_dir));
+            consolidated_paths.insert(
+                file_path.to_owned(),
+                json!({
+                    "id": id,
+                    "files": [
+                        {"uri": format!("https://arweave.net/{}", id), "type": content_type},
+                        {"uri": format!("https://arweave.net/{}/{}", transaction_id, key), "type": content_type}
+                    ]
+                }),
+            );
+        }
+        consolidated_paths
+    }
+
+    //-------------------------
+    // Metadata
+    //-------------------------
+
+    pub async fn read_metadata_file(&self, file_path: PathBuf) -> Result<Value, Error> {
+        let data = fs::read_to_string(file_path.clone()).await?;
+        let metadata: Value = serde_json::from_str(&data)?;
+        Ok(json!({"file_path": file_path.display().to_string(), "metadata": metadata}))
+    }
+
+    pub async fn update_metadata_file(
+        &self,
+        file_path: PathBuf,
+        files_array: Vec<Value>,
+        image_link: Option<String>,
+        animation_url_link: Option<String>,
+    ) -> Result<(), Error> {
+        let data = fs::read_to_string(file_path.clone()).await?;
+        let mut metadata: Value = serde_json::from_str(&data)?;
+        link_metadata(&mut metadata, files_array, image_link, animation_url_link)?;
+        fs::write(file_path, serde_json::to_string(&metadata)?).await?;
+        Ok(())
+    }
+
+    /// Adds links from the manifest at `manifest_path` to the metadata file of each of the
+    /// paths. Paths that aren't in the manifest are returned as [`Error::FilesNotInManifest`]
+    /// before any metadata is updated.
+    pub async fn update_metadata<IP>(
+        &self,
+        paths_iter: IP,
+        manifest_path: PathBuf,
+        image_link: ImageLink,
+        files_link: FilesLink,
+        update_image_link: bool,
+        update_animation_url_link: bool,
+    ) -> Result<(), Error>
+    where
+        IP: Iterator<Item = PathBuf> + Send,
+    {
+        if manifest_path.exists() {
+            let data = fs::read_to_string(manifest_path.clone()).await?;
+            let manifest: Value = serde_json::from_str(&data)?;
+            let manifest = manifest
+                .as_object()
+                .ok_or_else(|| Error::InvalidManifest(manifest_path.clone()))?;
+
+            let (paths, missing): (Vec<PathBuf>, Vec<PathBuf>) =
+                paths_iter.partition(|p| manifest.contains_key(&p.display().to_string()));
+            if !missing.is_empty() {
+                return Err(Error::FilesNotInManifest(missing));
+            }
+
+            let mut updates = Vec::new();
+            for p in paths {
+                let path_object = &manifest[&p.display().to_string()];
+
+                let (link, files_array) = metadata_links(
+                    &p.display().to_string(),
+                    path_object,
+                    image_link,
+                    files_link,
+                )?;
+
+                let image_link = if update_image_link {
+                    Some(link.clone())
+                } else {
+                    None
+                };
+
+                let animation_url_link = if update_animation_url_link {
+                    Some(link)
+                } else {
+                    None
+                };
+
+                updates.push(self.update_metadata_file(
+                    p.with_extension("json"),
+                    files_array,
+                    image_link,
+                    animation_url_link,
+                ));
+            }
+            try_join_all(updates).await?;
+            Ok(())
+        } else {
+            Err(Error::ManifestNotFound)
+        }
+    }
+
+    /// Writes metaplex link items for the files in a manifest, named from their metadata. Names
+    /// longer than [`utils::MAX_METAPLEX_NAME_LEN`] are an error unless `truncate_names`.
+    ///
+    /// Metadata files are matched to manifest keys with [`utils::path_key`], so paths don't have
+    /// to be written the same way as when the manifest was created. If none match, a single key
+    /// that ends with the path, or that the path ends with, is used, e.g., when running from
+    /// another directory. Files that still aren't found, or that match the same key as another
+    /// file, are returned as [`Error::MetadataNotInManifest`], or skipped if `ignore_missing`.
+    pub async fn write_metaplex_items<IP>(
+        &self,
+        paths_iter: IP,
+        manifest_path: PathBuf,
+        link_file: bool,
+        truncate_names: bool,
+        ignore_missing: bool,
+    ) -> Result<PathBuf, Error>
+    where
+        IP: Iterator<Item = PathBuf> + Send,
+    {
+        if manifest_path.exists() {
+            let invalid_manifest = || Error::InvalidManifest(manifest_path.clone());
+            let manifest_id = manifest_path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .ok_or_else(invalid_manifest)?
+                .replace("manifest_", "");
+            let data = fs::read_to_string(manifest_path.clone()).await?;
+            let manifest: Value = serde_json::from_str(&data)?;
+            let manifest_keys =
+                ManifestKeys::new(manifest.as_object().ok_or_else(invalid_manifest)?);
+
+            let paths: Vec<PathBuf> = paths_iter.collect();
+            let metadata =
+                try_join_all(paths.iter().map(|p| self.read_metadata_file(p.clone()))).await?;
+
+            let mut matched = Vec::new();
+            let mut missing = Vec::new();
+            for (file_path, meta) in paths.into_iter().zip(metadata) {
+                match manifest_keys.get(&file_path) {
+                    Some((key, path_object)) => matched.push((file_path, meta, key, path_object)),
+                    None => missing.push(file_path),
+                }
+            }
+            // A key matched by more than one file, e.g., through the suffix fallback, doesn't
+            // identify either of them.
+            let mut key_counts: HashMap<&String, usize> = HashMap::new();
+            for (_, _, key, _) in &matched {
+                *key_counts.entry(key).or_insert(0) += 1;
+            }
+            let (matched, shared): (Vec<_>, Vec<_>) = matched
+                .into_iter()
+                .partition(|(_, _, key, _)| key_counts[key] == 1);
+            missing.extend(shared.into_iter().map(|(file_path, ..)| file_path));
+
+            let mut items = serde_json::Map::new();
+            for (file_path, meta, key, path_object) in matched {
+                let name = utils::metaplex_name(
+                    meta["metadata"]["name"]
+                        .as_str()
+                        .ok_or_else(|| Error::MetadataNameMissing(file_path.clone()))?,
+                    truncate_names,
+                )?;
+                let id = path_object["id"].as_str().ok_or_else(invalid_manifest)?;
+                let link = if link_file {
+                    manifest_file_link(key, path_object)?.to_string()
+                } else {
+                    format!("https://arweave.net/{}", id)
+                };
+                items.insert(
+                    file_path
+                        .file_stem()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string(),
+                    json!({"name": name, "link": link, "onChain": false}),
+                );
+            }
+            if !missing.is_empty() {
+                if !ignore_missing {
+                    return Err(Error::MetadataNotInManifest(missing));
+                }
+                warn!(
+                    "skipped {} metadata files not found in manifest",
+                    missing.len()
+                );
+            }
+
+            let manifest_items_path =
+                manifest_path.with_file_name(format!("metaplex_items_{}.json", manifest_id));
+            fs::write(&manifest_items_path, serde_json::to_string(&json!(items))?).await?;
+            Ok(manifest_items_path)
+        } else {
+            Err(Error::ManifestNotFound)
+        }
+    }
+
+    //-------------------------
+    // NFTs
+    //-------------------------
+
+    /// Uploads a collection of NFT assets along with their metadata, which is expected in a json
+    /// file with the same stem next to each asset, without printing anything.
+    ///
+    /// Runs the same steps as `arloader upload-nfts`: uploads the assets and a manifest for them,
+    /// updates each metadata file with links to its asset, then uploads the metadata, a manifest
+    /// for it and writes metaplex items. Errors are returned as [`Error::NftUploadFailed`] with
+    /// the [`NftUploadStage`] that failed and the file paths that weren't processed in it, while
+    /// statuses of everything uploaded up to that point are kept in the log dir.
+    pub async fn upload_nft_collection(
+        &self,
+        assets: Vec<PathBuf>,
+        options: NftUploadOptions,
+    ) -> Result<NftUploadResult, Error> {
+        if assets.is_empty() {
+            return Err(Error::MissingFilePath);
+        }
+        let metadata_paths: Vec<PathBuf> =
+            assets.iter().map(|p| p.with_extension("json")).collect();
+
+        let log_dir = match &options.log_dir {
+            Some(log_dir) => log_dir.clone(),
+            None => self.create_log_dir(assets[0].parent().unwrap()).await?,
+        };
+        let log_dir_assets = log_dir.join("assets/");
+        let log_dir_metadata = log_dir.join("metadata/");
+        try_join(
+            fs::create_dir_all(&log_dir_assets),
+            fs::create_dir_all(&log_dir_metadata),
+        )
+        .await?;
+
+        let price_terms = self.get_price_terms(options.reward_mult).await?;
+
+        let (asset_manifest, mut reward) = self
+            .upload_nft_files(
+                assets.clone(),
+                &log_dir_assets,
+                &options,
+                price_terms,
+                NftUploadStage::Assets,
+                None,
+            )
+            .await?;
+        let (asset_manifest_transaction, asset_manifest_sig, asset_manifest_path) = self
+            .write_nft_manifest(&asset_manifest, &log_dir_assets, &options, price_terms)
+            .await
+            .map_err(nft_stage_error(NftUploadStage::AssetManifest, &assets))?;
+        let asset_manifest_id = asset_manifest_transaction.id.clone();
+
+        // Metadata only needs the id of the asset manifest, so it's updated while the manifest is
+        // posted, and put back if the manifest isn't.
+        let paid = asset_manifest_sig.is_some();
+        let backup = MetadataBackup::read(&metadata_paths).await;
+        let (posted, linked) = join(
+            self.post_written_manifest(
+                &asset_manifest_transaction,
+                asset_manifest_sig,
+                &asset_manifest_path,
+            ),
+            self.update_metadata(
+                assets.clone().into_iter(),
+                asset_manifest_path.clone(),
+                options.image_link(),
+                options.files_link(),
+                true,
+                false,
+            ),
+        )
+        .await;
+        backup
+            .restore_unless_posted(&posted, paid)
+            .await
+            .map_err(nft_stage_error(
+                NftUploadStage::MetadataLinks,
+                &metadata_paths,
+            ))?;
+        reward += posted
+            .map_err(nft_stage_error(NftUploadStage::AssetManifest, &assets))?
+            .1;
+        linked.map_err(nft_stage_error(
+            NftUploadStage::MetadataLinks,
+            &metadata_paths,
+        ))?;
+
+        let (metadata_manifest, metadata_reward) = self
+            .upload_nft_files(
+                metadata_paths.clone(),
+                &log_dir_metadata,
+                &options,
+                price_terms,
+                NftUploadStage::Metadata,
+                Some(mime_guess::mime::APPLICATION_JSON.as_ref()),
+            )
+            .await?;
+        reward += metadata_reward;
+        let (metadata_manifest_transaction, metadata_manifest_sig, metadata_manifest_path) = self
+            .write_nft_manifest(&metadata_manifest, &log_dir_metadata, &options, price_terms)
+            .await
+            .map_err(nft_stage_error(
+                NftUploadStage::MetadataManifest,
+                &metadata_paths,
+            ))?;
+        let metadata_manifest_id = metadata_manifest_transaction.id.clone();
+
+        let (posted, written) = join(
+            self.post_written_manifest(
+                &metadata_manifest_transaction,
+                metadata_manifest_sig,
+                &metadata_manifest_path,
+            ),
+            self.write_metaplex_items(
+                metadata_paths.clone().into_iter(),
+                metadata_manifest_path.clone(),
+                options.link_file,
+                options.truncate_names,
+                false,
+            ),
+        )
+        .await;
+        reward += posted
+            .map_err(nft_stage_error(
+                NftUploadStage::MetadataManifest,
+                &metadata_paths,
+            ))?
+            .1;
+        let metaplex_items_path = written.map_err(nft_stage_error(
+            NftUploadStage::MetaplexItems,
+            &metadata_paths,
+        ))?;
+
+        let link = |manifest_id: &Base64,
+                    manifest: &Value,
+                    path: &Path|
+         -> Result<(Base64, String), Error> {
+            let key = path.display().to_string();
+            let id = manifest["paths"][&key]["id"]
+                .as_str()
+                .ok_or_else(|| Error::InvalidManifestEntry(key.clone()))?;
+            let id = Base64::from_str(id)?;
+            let link = if options.link_file {
+                format!(
+                    "https://arweave.net/{}/{}",
+                    manifest_id,
+                    utils::encode_manifest_key(&utils::relative_manifest_path(
+                        &key,
+                        &self.manifest_base(manifest)?
+                    ))
+                )
+            } else {
+                format!("https://arweave.net/{}", id)
+            };
+            Ok((id, link))
+        };
+        let tokens = assets
+            .into_iter()
+            .zip(metadata_paths)
+            .map(|(asset_path, metadata_path)| {
+                let (image_id, image_link) =
+                    link(&asset_manifest_id, &asset_manifest, &asset_path)?;
+                let (metadata_id, metadata_link) =
+                    link(&metadata_manifest_id, &metadata_manifest, &metadata_path)?;
+                Ok(NftToken {
+                    asset_path,
+                    image_id,
+                    metadata_id,
+                    image_link,
+                    metadata_link,
+                })
+            })
+            .collect::<Result<Vec<NftToken>, Error>>()?;
+
+        Ok(NftUploadResult {
+            log_dir,
+            asset_manifest_id,
+            metadata_manifest_id,
+            metaplex_items_path,
+            tokens,
+            reward,
+        })
+    }
+
+    /// Uploads files for [`Arweave::upload_nft_collection`], writing their statuses to `log_dir`
+    /// and returning a manifest for them along with the total reward.
+    async fn upload_nft_files(
+        &self,
+        paths: Vec<PathBuf>,
+        log_dir: &Path,
+        options: &NftUploadOptions,
+        price_terms: (u64, u64),
+        stage: NftUploadStage,
+        content_type_override: Option<&str>,
+    ) -> Result<(Value, u64), Error> {
+        if options.no_bundle {
+            let log_dir = Some(log_dir.to_path_buf());
+            let tags = content_type_override
+                .map(|c| Tag::<Base64>::from_utf8_strs("Content-Type", c).map(|t| vec![t]))
+                .transpose()
+                .map_err(nft_stage_error(stage, &paths))?;
+            let statuses: Vec<Status> = match &options.sol_keypair {
+                Some(keypair) => {
+                    let stream = upload_files_with_sol_stream(
+                        self,
+                        paths.into_iter(),
+                        tags,
+                        log_dir,
+                        None,
+                        price_terms,
+                        options.solana_url.clone(),
+                        options.sol_ar_url.clone(),
+                        keypair,
+                        options.buffer,
+                    );
+                    collect_nft_uploads(stream, stage, |_| ready(Ok(()))).await?
+                }
+                None => {
+                    let stream = upload_files_stream(
+                        self,
+                        paths.into_iter(),
+                        tags,
+                        log_dir,
+                        None,
+                        price_terms,
+                        options.buffer,
+                    );
+                    collect_nft_uploads(stream, stage, |_| ready(Ok(()))).await?
+                }
+            };
+            let reward = statuses.iter().map(|s| s.reward).sum();
+            return Ok((self.create_manifest(statuses, None)?, reward));
+        }
+
+        let paths_chunks = self
+            .chunk_file_paths(paths.clone().into_iter(), options.bundle_size)
+            .map_err(nft_stage_error(stage, &paths))?;
+        let tags = content_type_override
+            .map(|c| Tag::<String>::from_utf8_strs("Content-Type", c).map(|t| vec![t]))
+            .transpose()
+            .map_err(nft_stage_error(stage, &paths))?
+            .unwrap_or_default();
+        let write_status = |status: &BundleStatus| {
+            let status_path = log_dir.join(status.id.to_string()).with_extension("json");
+            let status_json = to_status_json(status, self.pretty_status).map_err(Error::from);
+            let file_paths = status
+                .file_paths
+                .as_object()
+                .map(|paths| paths.keys().map(PathBuf::from).collect())
+                .unwrap_or_default();
+            async move {
+                let written = match status_json {
+                    Ok(status_json) => fs::write(status_path, status_json)
+                        .await
+                        .map_err(Error::from),
+                    Err(error) => Err(error),
+                };
+                written.map_err(|error| FailedUpload { error, file_paths })
+            }
+        };
+        let statuses: Vec<BundleStatus> = match &options.sol_keypair {
+            Some(keypair) => {
+                let stream = upload_bundles_stream_with_sol(
+                    self,
+                    paths_chunks,
+                    tags,
+                    Some(log_dir.to_path_buf()),
+                    price_terms,
+                    options.buffer,
+                    options.retry_policy,
+                    options.solana_url.clone(),
+                    options.sol_ar_url.clone(),
+                    keypair,
+                );
+                collect_nft_uploads(stream, stage, write_status).await?
+            }
+            None => {
+                let stream = upload_bundles_stream(
+                    self,
+                    paths_chunks,
+                    tags,
+                    Some(log_dir.to_path_buf()),
+                    price_terms,
+                    options.buffer,
+                    options.retry_policy,
+                );
+                collect_nft_uploads(stream, stage, write_status).await?
+            }
+        };
+        let reward = statuses.iter().map(|s| s.reward).sum();
+        Ok((
+            self.create_manifest_from_bundle_statuses(statuses, None)?,
+            reward,
+        ))
+    }
+
+    /// Uploads an NFT image from memory along with `metadata` linking to it, returning the ids of
+    /// the metadata and the image. The image link is written to the `image` field of the
+    /// metadata and appended to its `properties.files`, see [`link_metadata`], and the metadata
+    /// is uploaded as `application/json`. Errors are returned as
+    /// [`Error::NftPairUploadFailed`] with the [`NftUploadStage`] that failed and the id of the
+    /// image if it was already uploaded. If paying with SOL, transactions that fail to post
+    /// after being paid for are saved to [`NftPairOptions::log_dir`] to be posted again.
+    pub async fn upload_nft_pair(
+        &self,
+        image_bytes: Vec<u8>,
+        image_content_type: &str,
+        mut metadata: Value,
+        options: NftPairOptions,
+    ) -> Result<(Base64, Base64), Error> {
+        if options.sol_keypair.is_some() && options.log_dir.is_none() {
+            return Err(nft_pair_stage_error(NftUploadStage::Assets, None)(
+                Error::SolanaLogDirNotProvided,
+            ));
+        }
+        let price_terms = self
+            .get_price_terms(options.reward_mult)
+            .await
+            .map_err(nft_pair_stage_error(NftUploadStage::Assets, None))?;
+        let image_id = self
+            .post_nft_pair_data(image_bytes, image_content_type, price_terms, &options)
+            .await
+            .map_err(nft_pair_stage_error(NftUploadStage::Assets, None))?;
+
+        let image_link = format!("https://arweave.net/{}", image_id);
+        link_metadata(
+            &mut metadata,
+            vec![json!({"uri": image_link, "type": image_content_type})],
+            Some(image_link),
+            None,
+        )
+        .map_err(nft_pair_stage_error(
+            NftUploadStage::MetadataLinks,
+            Some(&image_id),
+        ))?;
+
+        let metadata_id = match serde_json::to_vec(&metadata) {
+            Ok(data) => {
+                self.post_nft_pair_data(
+                    data,
+                    mime_guess::mime::APPLICATION_JSON.as_ref(),
+                    price_terms,
+                    &options,
+                )
+                .await
+            }
+            Err(error) => Err(error.into()),
+        }
+        .map_err(nft_pair_stage_error(
+            NftUploadStage::Metadata,
+            Some(&image_id),
+        ))?;
+        Ok((metadata_id, image_id))
+    }
+
+    /// Posts `data` for [`Arweave::upload_nft_pair`] as the only data item in a bundle, or as a
+    /// transaction if [`NftPairOptions::no_bundle`], returning the id its data is requested by.
+    async fn post_nft_pair_data(
+        &self,
+        data: Vec<u8>,
+        content_type: &str,
+        price_terms: (u64, u64),
+        options: &NftPairOptions,
+    ) -> Result<Base64, Error> {
+        let (transaction, item_id, manifest) = if options.no_bundle {
+            let tags = vec![Tag::<Base64>::from_utf8_strs("Content-Type", content_type)?];
+            let transaction = self
+                .create_transaction(data, Some(tags), None, price_terms, false)
+                .await?;
+            (transaction, None, None)
+        } else {
+            let tags = vec![Tag::<String>::from_utf8_strs("Content-Type", content_type)?];
+            let data_item = self.sign_data_item(self.create_data_item(
+                data,
+                tags,
+                false,
+                true,
+                SignatureType::Arweave,
+            )?)?;
+            let item_id = data_item.id.clone();
+            // Bundles are described by a manifest keyed by file path, so the item is keyed by
+            // its id.
+            let status = Status {
+                id: item_id.clone(),
+                file_path: Some(PathBuf::from(item_id.to_string())),
+                content_type: content_type.to_string(),
+                ..Status::default()
+            };
+            let (bundle, manifest, _) =
+                self.create_bundle_from_data_items(vec![(data_item, status)])?;
+            let other_tags = Some(vec![
+                Tag::<Base64>::from_utf8_strs("Bundle-Format", "binary")?,
+                Tag::<Base64>::from_utf8_strs("Bundle-Version", "2.0.0")?,
+            ]);
+            let mut transaction = self
+                .create_transaction(bundle, other_tags, None, price_terms, true)
+                .await?;
+            self.check_bundle_reward(&mut transaction).await?;
+            (transaction, Some(item_id), Some(manifest))
+        };
+
+        let (signed_transaction, sig_response) = match &options.sol_keypair {
+            Some(keypair) => {
+                self.check_before_sol_payment(&transaction).await?;
+                let (signed_transaction, sig_response) = self
+                    .sign_transaction_with_sol(
+                        transaction,
+                        options.solana_url.clone(),
+                        options.sol_ar_url.clone(),
+                        keypair,
+                    )
+                    .await?;
+                (signed_transaction, Some(sig_response))
+            }
+            None => (self.sign_transaction(transaction)?, None),
+        };
+        let id = self
+            .post_nft_pair_transaction(
+                signed_transaction,
+                sig_response,
+                content_type,
+                manifest,
+                options.buffer * CHUNKS_BUFFER_FACTOR,
+                options.log_dir.as_deref(),
+            )
+            .await?;
+        Ok(item_id.unwrap_or(id))
+    }
+
+    /// Posts a transaction signed by [`Arweave::post_nft_pair_data`], with the `manifest` of its
+    /// bundle if it is one, posting up to `chunks_buffer` chunks at a time if it's posted in
+    /// chunks. If it was paid for with SOL, it's saved to `log_dir` first along with the status
+    /// written once it's posted, so that it can be posted again with
+    /// [`Arweave::repost_paid_transaction`] if posting fails.
+    async fn post_nft_pair_transaction(
+        &self,
+        signed_transaction: Transaction,
+        sig_response: Option<SigResponse>,
+        content_type: &str,
+        manifest: Option<Value>,
+        chunks_buffer: usize,
+        log_dir: Option<&Path>,
+    ) -> Result<Base64, Error> {
+        let paid_path = match sig_response {
+            Some(sig_response) => {
+                let status = match manifest {
+                    Some(manifest) => PaidStatus::Bundle(BundleStatus {
+                        id: signed_transaction.id.clone(),
+                        reward: signed_transaction.reward,
+                        number_of_files: 1,
+                        data_size: signed_transaction.data_size,
+                        file_paths: manifest["paths"].clone(),
+                        owner_address: address_from_modulus(&signed_transaction.owner).to_string(),
+                        sol_sig: Some(sig_response.clone()),
+                        ..Default::default()
+                    }),
+                    None => PaidStatus::File(Status {
+                        id: signed_transaction.id.clone(),
+                        reward: signed_transaction.reward,
+                        content_type: content_type.to_string(),
+                        sol_sig: Some(sig_response.clone()),
+                        ..Status::default()
+                    }),
+                };
+                self.save_paid_transaction(&signed_transaction, sig_response, status, log_dir)
+                    .await?
+            }
+            None => None,
+        };
+        let posted = if self
+            .network_params
+            .posts_chunks(signed_transaction.data.0.len() as u64)
+        {
+            self.post_transaction_chunks(signed_transaction, chunks_buffer, None)
+                .await
+        } else {
+            self.post_transaction(&signed_transaction).await
+        };
+        finish_paid_post(posted, paid_path).await.map(|(id, _)| id)
+    }
+
+    /// Signs and writes a manifest for [`Arweave::upload_nft_collection`], returning the signed
+    /// transaction, the [`SigResponse`] if it was paid for with SOL and the path of the manifest
+    /// file to post with [`Arweave::post_written_manifest`].
+    async fn write_nft_manifest(
+        &self,
+        manifest: &Value,
+        log_dir: &Path,
+        options: &NftUploadOptions,
+        price_terms: (u64, u64),
+    ) -> Result<(Transaction, Option<SigResponse>, PathBuf), Error> {
+        let (signed_transaction, sig_response) = self
+            .sign_manifest(
+                manifest,
+                price_terms,
+                options.solana_url.clone(),
+                options.sol_ar_url.clone(),
+                options.sol_keypair.as_ref(),
+            )
+            .await?;
+        let manifest_path = self
+            .write_manifest(
+                manifest.clone(),
+                signed_transaction.id.to_string(),
+                log_dir.to_path_buf(),
+            )
+            .await?;
+        Ok((signed_transaction, sig_response, manifest_path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        build_client,
+        bundle::{
+            serialized_tags_len, BundleFile, DataItem, SignatureType, BUNDLE_HEADER_LEN,
+            BUNDLE_ITEM_HEADER_LEN,
+        },
+        check_local_files_stream, collect_nft_uploads,
+        crypto::SIGN_COUNT,
+        error::Error,
+        finish_paid_post, metadata_links, retry_after,
+        solana::SigResponse,
+        status::{
+            to_status_json, ConfirmationsSource, LocalFileState, RecordedSize, UploadProgress,
+        },
+        transaction::{Base64, Chunk, FromUtf8Strs, Tag, Tip, ToItems, Transaction},
+        utils::{
+            mock_server::{self, MockRequest},
+            TempDir,
+        },
+        Arweave, BundleStatus, ConnectionStats, FailedUpload, FilesLink, ImageLink, NftPairOptions,
+        NftUploadOptions, NftUploadStage, PaidStatus, PaidTransaction, PathsChunk, RetryPolicy,
+        Status, StatusCode, BLOCK_SIZE, CHUNKS_BUFFER_FACTOR, MANIFEST_CONTENT_TYPE,
+        RESPONSE_SNIPPET_LEN, UNPOSTED_DIR,
+    };
+    use chrono::{DateTime, Utc};
+    use flate2::read::GzDecoder;
+    use futures::{
+        future::{ready, try_join_all},
+        stream, StreamExt,
+    };
+    use glob::glob;
+    use matches::assert_matches;
+    use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+    use serde_json::{json, Value};
+    use solana_sdk::signer::keypair::{read_keypair_file, Keypair};
+    use std::{
+        io::Read,
+        path::{Path, PathBuf},
+        str::FromStr,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        time::{Duration, Instant},
+    };
+    use tokio::fs;
+    use url::Url;
+
+    #[tokio::test]
+    async fn test_collect_nft_uploads() {
+        let failed = |path: &str| {
+            Err(FailedUpload {
+                error: Error::StatusCodeNotOk,
+                file_paths: vec![PathBuf::from(path)],
+            })
+        };
+
+        let mut written = Vec::new();
+        let uploaded = collect_nft_uploads(
+            stream::iter(vec![Ok(1), Ok(2)]),
+            NftUploadStage::Assets,
+            |i: &i32| {
+                written.push(*i);
+                ready(Ok(()))
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(uploaded, vec![1, 2]);
+        assert_eq!(written, vec![1, 2]);
+
+        // Uploads are written as they're yielded, and only the paths of failed uploads, or
+        // uploads that couldn't be written, are returned.
+        let mut written = Vec::new();
+        let results = vec![Ok(1), failed("0.json"), Ok(2), Ok(3)];
+        let error = collect_nft_uploads(
+            stream::iter(results),
+            NftUploadStage::Metadata,
+            |i: &i32| {
+                written.push(*i);
+                ready(match i {
+                    3 => failed("2.json").map(|_| ()),
+                    _ => Ok(()),
+                })
+            },
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(written, vec![1, 2, 3]);
+        assert_eq!(
+            error.to_string(),
+            "nft upload failed at metadata with 2 files not processed: status code not ok"
+        );
+        assert_matches!(
+            error,
+            Error::NftUploadFailed {
+                stage: NftUploadStage::Metadata,
+                file_paths,
+                source,
+            } if file_paths == vec![PathBuf::from("0.json"), PathBuf::from("2.json")]
+                && matches!(*source, Error::StatusCodeNotOk)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_upload_nft_collection_links_metadata_while_posting_manifest() -> Result<(), Error>
+    {
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        for i in 0..2 {
+            for ext in ["png", "json"] {
+                let file_name = format!("{}.{}", i, ext);
+                fs::copy(
+                    PathBuf::from("tests/fixtures").join(&file_name),
+                    temp_dir.0.join(&file_name),
+                )
+                .await?;
+            }
+        }
+        let assets: Vec<PathBuf> = (0..2)
+            .map(|i| temp_dir.0.join(format!("{}.png", i)))
+            .collect();
+        let log_dir = temp_dir.0.join("status/");
+
+        // The post of the asset manifest, the first one after its file is written, is delayed
+        // and records whether metadata was updated in the meantime.
+        let linked_during_post = Arc::new(Mutex::new(None));
+        let (base_url, _requests) = {
+            let linked_during_post = linked_during_post.clone();
+            let assets_log_dir = log_dir.join("assets");
+            let metadata_path = assets[0].with_extension("json");
+            let original = std::fs::read_to_string(&metadata_path).unwrap();
+            mock_server::serve(move |path| match path {
+                "/tx_anchor" => ("200 OK", "LCwsLCwsLA".to_string()),
+                p if p.starts_with("/price/") => ("200 OK", "1000".to_string()),
+                "/tx" => {
+                    let mut linked = linked_during_post.lock().unwrap();
+                    let manifest_written =
+                        glob(&format!("{}/manifest_*", assets_log_dir.display()))
+                            .unwrap()
+                            .next()
+                            .is_some();
+                    if linked.is_none() && manifest_written {
+                        std::thread::sleep(Duration::from_millis(500));
+                        *linked =
+                            Some(std::fs::read_to_string(&metadata_path).unwrap() != original);
+                    }
+                    ("200 OK", "OK".to_string())
+                }
+                _ => ("200 OK", "OK".to_string()),
+            })
+        };
+        let arweave =
+            Arweave::from_keypair_path(PathBuf::from("tests/fixtures/test_key0.json"), base_url)
+                .await?;
+
+        let started = Instant::now();
+        let result = arweave
+            .upload_nft_collection(
+                assets.clone(),
+                NftUploadOptions {
+                    log_dir: Some(log_dir.clone()),
+                    no_bundle: true,
+                    link_file: true,
+                    ..NftUploadOptions::default()
+                },
+            )
+            .await?;
+        println!("uploaded collection in {:?}", started.elapsed());
+
+        assert_eq!(*linked_during_post.lock().unwrap(), Some(true));
+        assert!(log_dir
+            .join(format!("assets/manifest_{}.json", result.asset_manifest_id))
+            .exists());
+        assert!(log_dir
+            .join(format!(
+                "metadata/manifest_{}.json",
+                result.metadata_manifest_id
+            ))
+            .exists());
+        assert!(result.metaplex_items_path.exists());
+        for token in &result.tokens {
+            let metadata: Value = serde_json::from_str(
+                &fs::read_to_string(token.asset_path.with_extension("json")).await?,
+            )?;
+            assert_eq!(metadata["image"], token.image_link);
+        }
+        // Links through the manifests are relative to the temp dir the files are in.
+        assert_eq!(
+            result.tokens[0].image_link,
+            format!("https://arweave.net/{}/0.png", result.asset_manifest_id)
+        );
+        assert_eq!(
+            result.tokens[0].metadata_link,
+            format!("https://arweave.net/{}/0.json", result.metadata_manifest_id)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_upload_nft_collection_restores_metadata_if_manifest_not_posted(
+    ) -> Result<(), Error> {
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        for ext in ["png", "json"] {
+            let file_name = format!("0.{}", ext);
+            fs::copy(
+                PathBuf::from("tests/fixtures").join(&file_name),
+                temp_dir.0.join(&file_name),
+            )
+            .await?;
+        }
+        let asset = temp_dir.0.join("0.png");
+        let metadata_path = asset.with_extension("json");
+        let original = fs::read_to_string(&metadata_path).await?;
+        let log_dir = temp_dir.0.join("status/");
+
+        // The asset manifest is rejected, once its file has been written.
+        let (base_url, _requests) = {
+            let assets_log_dir = log_dir.join("assets");
+            mock_server::serve(move |path| match path {
+                "/tx_anchor" => ("200 OK", "LCwsLCwsLA".to_string()),
+                p if p.starts_with("/price/") => ("200 OK", "1000".to_string()),
+                "/tx"
+                    if glob(&format!("{}/manifest_*", assets_log_dir.display()))
+                        .unwrap()
+                        .next()
+                        .is_some() =>
+                {
+                    ("400 Bad Request", String::new())
+                }
+                _ => ("200 OK", "OK".to_string()),
+            })
+        };
+        let arweave =
+            Arweave::from_keypair_path(PathBuf::from("tests/fixtures/test_key0.json"), base_url)
+                .await?;
+
+        let result = arweave
+            .upload_nft_collection(
+                vec![asset],
+                NftUploadOptions {
+                    log_dir: Some(log_dir.clone()),
+                    no_bundle: true,
+                    link_file: true,
+                    ..NftUploadOptions::default()
+                },
+            )
+            .await;
+        assert_matches!(
+            result,
+            Err(Error::NftUploadFailed {
+                stage: NftUploadStage::AssetManifest,
+                ..
+            })
+        );
+        assert_eq!(fs::read_to_string(&metadata_path).await?, original);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_post_written_manifest() -> Result<(), Error> {
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let (base_url, _requests) = mock_server::serve(|path| match path {
+            "/tx_anchor" => ("200 OK", "LCwsLCwsLA".to_string()),
+            _ => ("200 OK", "OK".to_string()),
+        });
+        let mut arweave =
+            Arweave::from_keypair_path(PathBuf::from("tests/fixtures/test_key0.json"), base_url)
+                .await?;
+        let manifest = arweave.create_manifest(
+            vec![Status {
+                id: Base64(vec![44; 32]),
+                file_path: Some(PathBuf::from("tests/fixtures/0.png")),
+                content_type: "image/png".to_string(),
+                ..Status::default()
+            }],
+            None,
+        )?;
+        let solana_url = Url::from_str("http://url.com").unwrap();
+
+        // A manifest that isn't posted doesn't leave its file behind.
+        for read_only in [true, false] {
+            arweave.read_only = read_only;
+            let (signed, sig_response) = arweave
+                .sign_manifest(
+                    &manifest,
+                    (0, 0),
+                    solana_url.clone(),
+                    solana_url.clone(),
+                    None,
+                )
+                .await?;
+            assert!(sig_response.is_none());
+            let path = arweave
+                .write_manifest(manifest.clone(), signed.id.to_string(), temp_dir.0.clone())
+                .await?;
+            assert!(path.exists());
+            let posted = arweave.post_written_manifest(&signed, None, &path).await;
+            if read_only {
+                assert_matches!(posted, Err(Error::ReadOnlyMode));
+                assert!(!path.exists());
+            } else {
+                assert_eq!(posted?.0, signed.id);
+                assert!(path.exists());
+            }
+        }
+
+        // Unless it was paid for with SOL, then the manifest file is kept along with the paid
+        // transaction, which can be posted again.
+        let (signed, _) = arweave
+            .sign_manifest(
+                &manifest,
+                (0, 0),
+                solana_url.clone(),
+                solana_url.clone(),
+                None,
+            )
+            .await?;
+        let sig_response = SigResponse {
+            ar_tx_sig: signed.signature.clone(),
+            ar_tx_id: signed.id.clone(),
+            ar_tx_owner: signed.owner.clone(),
+            sol_tx_sig: String::new(),
+            lamports: 0,
+        };
+        let path = arweave
+            .write_manifest(manifest.clone(), signed.id.to_string(), temp_dir.0.clone())
+            .await?;
+        arweave.read_only = true;
+        let error = arweave
+            .post_written_manifest(&signed, Some(sig_response), &path)
+            .await
+            .unwrap_err();
+        let paid_path = temp_dir
+            .0
+            .join(UNPOSTED_DIR)
+            .join(signed.id.to_string())
+            .with_extension("json");
+        assert_matches!(
+            &error,
+            Error::PaidPostFailed { path: p, source }
+                if p == &paid_path && matches!(**source, Error::ReadOnlyMode)
+        );
+        assert!(path.exists());
+        assert_eq!(
+            arweave.paid_transaction_paths(&temp_dir.0)?,
+            vec![paid_path.clone()]
+        );
+
+        arweave.read_only = false;
+        let (id, _) = arweave
+            .repost_paid_transaction(&paid_path, &temp_dir.0, 1)
+            .await?;
+        assert_eq!(id, signed.id);
+        assert!(arweave.paid_transaction_paths(&temp_dir.0)?.is_empty());
+        let status = arweave.read_status(path, temp_dir.0.clone()).await?;
+        assert_eq!(status.content_type, MANIFEST_CONTENT_TYPE);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_post_nft_pair_transaction_paid() -> Result<(), Error> {
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let (base_url, _requests) = mock_server::serve(|path| match path {
+            "/tx_anchor" => ("200 OK", "LCwsLCwsLA".to_string()),
+            _ => ("200 OK", "OK".to_string()),
+        });
+        let mut arweave =
+            Arweave::from_keypair_path(PathBuf::from("tests/fixtures/test_key0.json"), base_url)
+                .await?;
+
+        // Paying with SOL isn't started without somewhere to save the transaction.
+        let options = NftPairOptions {
+            sol_keypair: Some(Keypair::new()),
+            ..NftPairOptions::default()
+        };
+        assert_matches!(
+            arweave
+                .upload_nft_pair(vec![1; 10], "image/png", json!({}), options)
+                .await,
+            Err(Error::NftPairUploadFailed { source, .. })
+                if matches!(*source, Error::SolanaLogDirNotProvided)
+        );
+
+        let transaction = arweave
+            .create_transaction(vec![1; 10], None, None, (0, 0), false)
+            .await?;
+        let signed = arweave.sign_transaction(transaction)?;
+        let sig_response = SigResponse {
+            ar_tx_sig: signed.signature.clone(),
+            ar_tx_id: signed.id.clone(),
+            ar_tx_owner: signed.owner.clone(),
+            sol_tx_sig: String::new(),
+            lamports: 0,
+        };
+        arweave.read_only = true;
+        let error = arweave
+            .post_nft_pair_transaction(
+                signed.clone(),
+                Some(sig_response),
+                "image/png",
+                None,
+                CHUNKS_BUFFER_FACTOR,
+                Some(&temp_dir.0),
+            )
+            .await
+            .unwrap_err();
+        let paid_path = temp_dir
+            .0
+            .join(UNPOSTED_DIR)
+            .join(signed.id.to_string())
+            .with_extension("json");
+        assert_matches!(
+            &error,
+            Error::PaidPostFailed { path: p, source }
+                if p == &paid_path && matches!(**source, Error::ReadOnlyMode)
+        );
+
+        arweave.read_only = false;
+        let (id, _) = arweave
+            .repost_paid_transaction(&paid_path, &temp_dir.0, 1)
+            .await?;
+        assert_eq!(id, signed.id);
+        assert!(arweave.paid_transaction_paths(&temp_dir.0)?.is_empty());
+        let status: Status = serde_json::from_str(
+            &fs::read_to_string(temp_dir.0.join(format!("txid_{}.json", signed.id))).await?,
+        )?;
+        assert_eq!(status.content_type, "image/png");
+        assert!(status.sol_sig.is_some());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_item_cache_skips_signing() -> Result<(), Error> {
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let mut arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key0.json"),
+            Url::from_str("http://url.com").unwrap(),
+        )
+        .await?;
+        arweave.item_cache = Some(temp_dir.0.join("cache"));
+
+        let changed_path = temp_dir.0.join("changed.txt");
+        fs::write(&changed_path, "first").await?;
+        let paths = vec![
+            PathBuf::from("tests/fixtures/0.png"),
+            PathBuf::from("tests/fixtures/1.png"),
+            changed_path.clone(),
+        ];
+        let sign_count = || SIGN_COUNT.with(|c| c.get());
+
+        let start = sign_count();
+        let first = arweave
+            .create_data_items_from_file_paths(paths.clone(), Vec::new())
+            .await?;
+        assert_eq!(sign_count() - start, 3);
+
+        let start = sign_count();
+        let second = arweave
+            .create_data_items_from_file_paths(paths.clone(), Vec::new())
+            .await?;
+        assert_eq!(sign_count() - start, 0);
+        let data_items = |items: Vec<(DataItem, Status)>| -> Vec<DataItem> {
+            items.into_iter().map(|(d, _)| d).collect()
+        };
+        assert_eq!(data_items(first), data_items(second));
+
+        // Changed files and tags result in new data items.
+        fs::write(&changed_path, "second").await?;
+        let start = sign_count();
+        arweave
+            .create_data_items_from_file_paths(paths.clone(), Vec::new())
+            .await?;
+        assert_eq!(sign_count() - start, 1);
+
+        let tags = vec![Tag::<String>::from_utf8_strs("key", "value")?];
+        let start = sign_count();
+        arweave
+            .create_data_items_from_file_paths(paths.clone(), tags)
+            .await?;
+        assert_eq!(sign_count() - start, 3);
+
+        // Truncated items are signed again and replaced, without leaving temp files behind.
+        let cache_dir = temp_dir.0.join("cache");
+        let mut entries = fs::read_dir(&cache_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let bytes = fs::read(entry.path()).await?;
+            fs::write(entry.path(), &bytes[..bytes.len() / 2]).await?;
+        }
+        let start = sign_count();
+        let third = arweave
+            .create_data_items_from_file_paths(paths, Vec::new())
+            .await?;
+        assert_eq!(sign_count() - start, 3);
+        assert_eq!(data_items(third).len(), 3);
+        let mut entries = fs::read_dir(&cache_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            assert!(!entry.path().to_string_lossy().ends_with(".tmp"));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_transaction_with_tip() -> Result<(), Error> {
+        let mut arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key0.json"),
+            Url::from_str("http://url.com").unwrap(),
+        )
+        .await?;
+        let tip = Tip::from_str("7eV1qae4qVNqsNChg3Scdi-DpOLJPCogct4ixoq1WNg:0.25")?;
+        arweave.tip = Some(tip.clone());
+
+        let transaction = arweave
+            .create_transaction(vec![1; 100], None, Some(Base64(vec![])), (10, 1), true)
+            .await?;
+        assert_eq!(transaction.target, tip.target);
+        assert_eq!(transaction.quantity, 250_000_000_000);
+
+        // Signature covers the transfer.
+        let mut tampered = transaction.clone_with_no_data()?;
+        tampered.quantity += 1;
+        assert_ne!(
+            arweave.crypto.deep_hash(tampered.to_deep_hash_item()?)?,
+            arweave.crypto.deep_hash(transaction.to_deep_hash_item()?)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_retry_policy_delay() {
+        let policy = RetryPolicy {
+            retries: 5,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(5),
+        };
+        for (retry, backoff) in [(0, 1), (1, 2), (2, 4), (3, 5), (40, 5)] {
+            let delay = policy.delay(retry);
+            let backoff = Duration::from_secs(backoff);
+            assert!(
+                delay >= backoff && delay <= backoff.mul_f64(1.5),
+                "{:?}",
+                delay
+            );
+        }
+
+        // Retry-After is used as sent, up to the max delay.
+        let rejected = |retry_after| Error::ArweavePostRejected {
+            status: reqwest::StatusCode::TOO_MANY_REQUESTS,
+            body: String::new(),
+            retry_after,
+        };
+        for (retry_after, delay) in [(2, 2), (5, 5), (3600, 5)] {
+            assert_eq!(
+                policy.delay_for(0, &rejected(Some(Duration::from_secs(retry_after)))),
+                Duration::from_secs(delay)
+            );
+        }
+        let delay = policy.delay_for(2, &rejected(None));
+        assert!(delay >= Duration::from_secs(4) && delay <= Duration::from_secs(6));
+    }
+
+    #[tokio::test]
+    async fn test_post_bundle_retries() -> Result<(), Error> {
+        // Posts a bundle to a gateway responding to posts to /tx with `tx_responses` in turn,
+        // returning the result along with the number of anchors fetched and posts made.
+        let post_bundle = |tx_responses: Vec<(&'static str, &'static str)>| async move {
+            let anchors = Arc::new(AtomicUsize::new(0));
+            let posts = Arc::new(AtomicUsize::new(0));
+            let (anchors_count, posts_count) = (anchors.clone(), posts.clone());
+            let (base_url, _) = mock_server::serve(move |path| match path {
+                "/tx_anchor" => {
+                    let n = anchors.fetch_add(1, Ordering::SeqCst);
+                    ("200 OK", ["LCwsLCwsLA", "LSwsLCwsLA"][n.min(1)].to_string())
+                }
+                p if p.starts_with("/price/") => ("200 OK", "0".to_string()),
+                _ => {
+                    let n = posts.fetch_add(1, Ordering::SeqCst);
+                    let (status, body) = tx_responses[n.min(tx_responses.len() - 1)];
+                    (status, body.to_string())
+                }
+            });
+            let arweave = Arweave::from_keypair_path(
+                PathBuf::from("tests/fixtures/test_key0.json"),
+                base_url,
+            )
+            .await
+            .unwrap();
+            let retry_policy = RetryPolicy {
+                retries: 2,
+                base_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(1),
+            };
+            let result = arweave
+                .post_bundle_transaction_from_file_paths(
+                    PathsChunk(vec![PathBuf::from("tests/fixtures/0.png")], 0),
+                    Vec::new(),
+                    None,
+                    (0, 0),
+                    1,
+                    retry_policy,
+                )
+                .await;
+            (
+                result,
+                anchors_count.load(Ordering::SeqCst),
+                posts_count.load(Ordering::SeqCst),
+            )
+        };
+
+        let (result, anchors, posts) =
+            post_bundle(vec![("429 Too Many Requests", ""), ("200 OK", "")]).await;
+        assert!(result.is_ok());
+        assert_eq!((anchors, posts), (1, 2));
+
+        let (result, _, posts) = post_bundle(vec![("503 Service Unavailable", "")]).await;
+        assert_matches!(result, Err(Error::ArweavePostRejected { status, .. }) if status == 503);
+        assert_eq!(posts, 3);
+
+        let (result, _, posts) = post_bundle(vec![("400 Bad Request", "Invalid tags.")]).await;
+        assert_matches!(result, Err(Error::ArweavePostRejected { status, .. }) if status == 400);
+        assert_eq!(posts, 1);
+
+        let (result, anchors, posts) = post_bundle(vec![
+            ("400 Bad Request", "Invalid anchor (last_tx)."),
+            ("200 OK", ""),
+        ])
+        .await;
+        assert!(result.is_ok());
+        assert_eq!((anchors, posts), (2, 2));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_post_bundle_chunks_resumes() -> Result<(), Error> {
+        // Posts a bundle in chunks to a gateway that accepts the header with 208 and responds to
+        // the third chunk posted with `status`, returning the result and the requests made.
+        let post_bundle = |status: &'static str, log_dir: Option<PathBuf>| async move {
+            let chunks = Arc::new(AtomicUsize::new(0));
+            let (base_url, requests) =
+                mock_server::serve_requests(move |request| match request.path.as_str() {
+                    "/tx_anchor" => ("200 OK", Vec::new(), "LCwsLCwsLA".to_string()),
+                    p if p.starts_with("/price/") => ("200 OK", Vec::new(), "0".to_string()),
+                    "/tx" => ("208 Already Reported", Vec::new(), String::new()),
+                    _ if chunks.fetch_add(1, Ordering::SeqCst) == 2 => {
+                        (status, Vec::new(), String::new())
+                    }
+                    _ => ("200 OK", Vec::new(), String::new()),
+                });
+            let mut arweave = Arweave::from_keypair_path(
+                PathBuf::from("tests/fixtures/test_key0.json"),
+                base_url,
+            )
+            .await
+            .unwrap();
+            arweave.network_params.max_tx_body = 0;
+            let retry_policy = RetryPolicy {
+                retries: 2,
+                base_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(1),
+            };
+            let result = arweave
+                .post_bundle_transaction_from_file_paths(
+                    PathsChunk(vec![PathBuf::from("tests/fixtures/1mb.bin")], 0),
+                    Vec::new(),
+                    log_dir,
+                    (0, 0),
+                    1,
+                    retry_policy,
+                )
+                .await;
+            (result, requests.try_iter().collect::<Vec<MockRequest>>())
+        };
+        let offsets = |requests: &[MockRequest]| -> Vec<String> {
+            requests
+                .iter()
+                .filter(|r| r.path == "/chunk")
+                .map(|r| r.json()["offset"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        // The retry picks up from the failed chunk, without posting the header again.
+        let (result, requests) = post_bundle("503 Service Unavailable", None).await;
+        assert!(result.is_ok());
+        assert_eq!(requests.iter().filter(|r| r.path == "/tx").count(), 1);
+        let posted = offsets(&requests);
+        let chunks_total = posted.len() / 2 + 1;
+        assert!(chunks_total > 2);
+        assert_eq!(posted.len(), 2 * chunks_total - 2);
+        assert_eq!(posted[chunks_total..], posted[2..chunks_total]);
+
+        // Client errors aren't retried, leaving the interim status with how far posting got.
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let (result, requests) = post_bundle("400 Bad Request", Some(temp_dir.0.clone())).await;
+        assert_matches!(result, Err(Error::ArweavePostRejected { status, .. }) if status == 400);
+        assert_eq!(offsets(&requests).len(), chunks_total);
+        let statuses = Arweave::default()
+            .read_bundle_statuses(&temp_dir.0.join("").display().to_string())
+            .await?;
+        assert_eq!(statuses.len(), 1);
+        assert!(statuses[0].is_posting());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cached_tx_anchor() -> Result<(), Error> {
+        let anchors = Arc::new(AtomicUsize::new(0));
+        let (base_url, _) = {
+            let anchors = anchors.clone();
+            mock_server::serve(move |path| match path {
+                "/tx_anchor" => {
+                    let n = anchors.fetch_add(1, Ordering::SeqCst);
+                    ("200 OK", ["LCwsLCwsLA", "LSwsLCwsLA"][n.min(1)].to_string())
+                }
+                _ => ("400 Bad Request", "Invalid anchor (last_tx).".to_string()),
+            })
+        };
+        let arweave =
+            Arweave::from_keypair_path(PathBuf::from("tests/fixtures/test_key0.json"), base_url)
+                .await?;
+
+        let transactions = try_join_all(
+            (0..5).map(|_| arweave.create_transaction(vec![1; 10], None, None, (0, 0), true)),
+        )
+        .await?;
+        assert_eq!(anchors.load(Ordering::SeqCst), 1);
+        let first = Base64::from_str("LCwsLCwsLA")?;
+        assert!(transactions.iter().all(|t| t.last_tx == first));
+
+        // A post rejected for its anchor isn't retried and the next transaction gets a new one.
+        let signed_transaction = arweave.sign_transaction(transactions[0].clone())?;
+        assert_matches!(
+            arweave.post_transaction(&signed_transaction).await,
+            Err(e) if e.is_stale_anchor()
+        );
+        assert_eq!(anchors.load(Ordering::SeqCst), 2);
+        let transaction = arweave
+            .create_transaction(vec![1; 10], None, None, (0, 0), true)
+            .await?;
+        assert_eq!(transaction.last_tx, Base64::from_str("LSwsLCwsLA")?);
+        assert_eq!(anchors.load(Ordering::SeqCst), 2);
+
+        assert_eq!(
+            arweave.refresh_anchor().await?,
+            Base64::from_str("LSwsLCwsLA")?
+        );
+        assert_eq!(anchors.load(Ordering::SeqCst), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(RETRY_AFTER, HeaderValue::from_str(value).unwrap());
+            headers
+        };
+        assert_eq!(retry_after(&HeaderMap::new()), None);
+        assert_eq!(retry_after(&headers("120")), Some(Duration::from_secs(120)));
+        assert_eq!(retry_after(&headers("soon")), None);
+
+        let date = chrono::Utc::now() + chrono::Duration::seconds(30);
+        let delay = retry_after(&headers(
+            &date.format("%a, %d %b %Y %H:%M:%S GMT").to_string(),
+        ))
+        .unwrap();
+        assert!(delay > Duration::from_secs(28) && delay <= Duration::from_secs(30));
+        assert_eq!(
+            retry_after(&headers("Wed, 21 Oct 2015 07:28:00 GMT")),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_post_bundle_retry_after() -> Result<(), Error> {
+        let posts = Arc::new(AtomicUsize::new(0));
+        let (base_url, _) = mock_server::serve_with_headers(move |path| match path {
+            "/tx_anchor" => ("200 OK", Vec::new(), "LCwsLCwsLA".to_string()),
+            p if p.starts_with("/price/") => ("200 OK", Vec::new(), "0".to_string()),
+            _ if posts.fetch_add(1, Ordering::SeqCst) == 0 => (
+                "429 Too Many Requests",
+                vec![("retry-after", "1".to_string())],
+                String::new(),
+            ),
+            _ => ("200 OK", Vec::new(), String::new()),
+        });
+        let arweave =
+            Arweave::from_keypair_path(PathBuf::from("tests/fixtures/test_key0.json"), base_url)
+                .await?;
+        let retry_policy = RetryPolicy {
+            retries: 1,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(1),
+        };
+
+        let started = Instant::now();
+        arweave
+            .post_bundle_transaction_from_file_paths(
+                PathsChunk(vec![PathBuf::from("tests/fixtures/0.png")], 0),
+                Vec::new(),
+                None,
+                (0, 0),
+                1,
+                retry_policy,
+            )
+            .await?;
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cooldown_queues_requests() -> Result<(), Error> {
+        // Times at which the server received each request, the first of which is rate limited.
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let received_clone = received.clone();
+        let (base_url, _) = mock_server::serve_with_headers(move |_| {
+            let mut received = received_clone.lock().unwrap();
+            received.push(Instant::now());
+            if received.len() == 1 {
+                (
+                    "429 Too Many Requests",
+                    vec![("retry-after", "1".to_string())],
+                    String::new(),
+                )
+            } else {
+                ("404 Not Found", Vec::new(), String::new())
+            }
+        });
+        let arweave = Arweave {
+            base_url,
+            ..Arweave::default()
+        };
+        let id = Base64::from_str("LCwsLCwsLA")?;
+
+        let result = arweave.get_status(&id).await;
+        assert_matches!(result, Err(Error::ArweaveNetworkError(status)) if status == 429);
+
+        let statuses = futures::future::join_all((0..3).map(|_| arweave.get_status(&id))).await;
+        assert!(statuses
+            .iter()
+            .all(|s| matches!(s, Ok(s) if s.status == StatusCode::NotFound)));
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 4);
+        assert!(received[1..]
+            .iter()
+            .all(|t| t.duration_since(received[0]) >= Duration::from_secs(1)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_check_wallet_balance() -> Result<(), Error> {
+        let (base_url, _) = mock_server::serve(|_| ("200 OK", "500000000000".to_string()));
+        let mut arweave =
+            Arweave::from_keypair_path(PathBuf::from("tests/fixtures/test_key0.json"), base_url)
+                .await?;
+        let paths = vec![PathBuf::from("tests/fixtures/0.png")];
+
+        assert_eq!(
+            arweave.check_wallet_balance(&paths, (1000, 100)).await?,
+            500_000_000_000
+        );
+
+        arweave.tip = Some(Tip::from_str(
+            "7eV1qae4qVNqsNChg3Scdi-DpOLJPCogct4ixoq1WNg:0.5",
+        )?);
+        assert_matches!(
+            arweave.check_wallet_balance(&paths, (1000, 100)).await,
+            Err(Error::InsufficientBalance {
+                balance: 500_000_000_000,
+                ..
+            })
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_audit_wallet() -> Result<(), Error> {
+        let (base_url, requests) = mock_server::serve_requests(|request| {
+            let page = match request.json()["variables"]["after"].as_str() {
+                None => 0,
+                Some("cursor2") => 1,
+                Some(_) => return ("200 OK", Vec::new(), json!({"errors": []}).to_string()),
+            };
+            let body =
+                std::fs::read_to_string(format!("tests/fixtures/graphql_audit_page{}.json", page))
+                    .unwrap();
+            ("200 OK", Vec::new(), body)
+        });
+        let arweave =
+            Arweave::from_keypair_path(PathBuf::from("tests/fixtures/test_key0.json"), base_url)
+                .await?;
+        let since = DateTime::<Utc>::from_str("2024-01-01T00:00:00Z").unwrap();
+
+        // Stops after the second page, which reaches transactions older than `since`.
+        let audit = arweave.audit_wallet(None, since).await?;
+        let requests: Vec<_> = requests.try_iter().collect();
+        assert_eq!(requests.len(), 2);
+        assert!(requests.iter().all(|r| r.path == "/graphql"));
+        assert_eq!(
+            requests[0].json()["variables"]["owners"],
+            json!([arweave.crypto.wallet_address()?.to_string()])
+        );
+        assert_eq!(audit.transactions.len(), 5);
+        assert_eq!(audit.reward(), 1_500_000_000_000);
+
+        let since = DateTime::<Utc>::from_str("2023-01-01T00:00:00Z").unwrap();
+        assert_matches!(
+            arweave
+                .audit_wallet(Some("address".to_string()), since)
+                .await,
+            Err(Error::GatewayUnexpectedResponse { .. })
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_with_client() -> Result<(), Error> {
+        let (base_url, _) = mock_server::serve(|_| {
+            std::thread::sleep(Duration::from_secs(1));
+            ("200 OK", "[]".to_string())
+        });
+        let arweave = Arweave {
+            read_only: true,
+            ..Arweave::default_with_url(base_url)
+        }
+        .with_client(build_client(
+            Some(Duration::from_millis(100)),
+            &ConnectionStats::default(),
+        )?);
+        assert!(arweave.read_only);
+
+        assert_matches!(
+            arweave.get_pending_count().await,
+            Err(Error::Reqwest(e)) if e.is_timeout()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_and_try_default() -> Result<(), Error> {
+        let base_url = Url::from_str("http://url.com")?;
+        let crypto = crate::crypto::Provider::from_keypair_path_sync(PathBuf::from(
+            "tests/fixtures/test_key0.json",
+        ))?;
+        let address = crypto.wallet_address()?;
+        let arweave = Arweave::new(crypto, base_url.clone())?;
+        assert_eq!(arweave.base_url, base_url);
+        assert_eq!(arweave.crypto.wallet_address()?, address);
+
+        let arweave = Arweave::try_default()?;
+        assert_eq!(arweave.base_url.as_str(), "https://arweave.net/");
+        assert_eq!(
+            arweave.crypto.wallet_address()?,
+            Arweave::default().crypto.wallet_address()?
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_connection_stats() -> Result<(), Error> {
+        let (mut base_url, requests) = mock_server::serve(|_| ("200 OK", "[]".to_string()));
+        base_url.set_host(Some("localhost"))?;
+        let arweave = Arweave::default_with_url(base_url.clone());
+        for _ in 0..2 {
+            assert_eq!(arweave.get_pending_count().await?, 0);
+        }
+        let connections: Vec<usize> = requests.try_iter().map(|r| r.connection).collect();
+        assert_eq!(connections, vec![0, 0]);
+        let stats = &arweave.connection_stats;
+        assert_eq!(
+            (stats.opened(), stats.requests(), stats.reused()),
+            (1, 2, 1)
+        );
+
+        assert!(stats.connect_time() > Duration::ZERO);
+
+        // Gateways addressed by IP are counted the same way, since there's no host to look up
+        // but a connection is still opened.
+        base_url.set_host(Some("127.0.0.1"))?;
+        let arweave = Arweave::default_with_url(base_url);
+        for _ in 0..2 {
+            arweave.get_pending_count().await?;
+        }
+        let stats = &arweave.connection_stats;
+        assert_eq!(
+            (stats.opened(), stats.requests(), stats.reused()),
+            (1, 2, 1)
+        );
+        assert!(stats.connect_time() > Duration::ZERO);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_head_data() -> Result<(), Error> {
+        let (found, missing, moved, gone) = (
+            Base64(vec![1; 32]),
+            Base64(vec![2; 32]),
+            Base64(vec![3; 32]),
+            Base64(vec![4; 32]),
+        );
+        let (found_path, moved_path, gone_path) = (
+            format!("/{}", found),
+            format!("/{}", moved),
+            format!("/{}", gone),
+        );
+        let missing_path = format!("/{}", missing);
+        let (base_url, requests) = mock_server::serve_with_headers(move |path| {
+            if path == found_path {
+                (
+                    "200 OK",
+                    vec![("content-type", "image/png".to_string())],
+                    "x".repeat(1234),
+                )
+            } else if path == moved_path {
+                (
+                    "302 Found",
+                    vec![("location", found_path.clone())],
+                    String::new(),
+                )
+            } else if path == gone_path {
+                (
+                    "301 Moved Permanently",
+                    vec![("location", "/redirect".to_string())],
+                    String::new(),
+                )
+            } else if path == "/redirect" {
+                (
+                    "302 Found",
+                    vec![("location", missing_path.clone())],
+                    String::new(),
+                )
+            } else {
+                ("404 Not Found", Vec::new(), String::new())
+            }
+        });
+        let arweave = Arweave::default_with_url(base_url);
+
+        assert_eq!(
+            arweave.head_data(&found).await?,
+            Some((1234, "image/png".to_string()))
+        );
+        let request = requests.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(request.method, "HEAD");
+        assert_eq!(arweave.head_data(&missing).await?, None);
+        assert_eq!(
+            arweave.head_data(&moved).await?,
+            Some((1234, "image/png".to_string()))
+        );
+        assert_eq!(arweave.head_data(&gone).await?, None);
+
+        // Ids that aren't 32 bytes, e.g., empty ones that would request the root of the
+        // gateway, aren't requested.
+        for id in [Base64(Vec::new()), Base64(vec![1; 31])] {
+            assert_matches!(arweave.head_data(&id).await, Err(Error::InvalidId(_)));
+            assert_matches!(arweave.download_data(&id).await, Err(Error::InvalidId(_)));
+        }
+        assert!(requests.try_iter().all(|r| r.path != "/"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_gateway_unexpected_response() -> Result<(), Error> {
+        let page = format!(
+            "<!DOCTYPE html><html><head><title>502 Bad Gateway</title></head><body>{}</body></html>",
+            "error ".repeat(100)
+        );
+        let (base_url, _) = mock_server::serve_with_headers(move |path| match path {
+            "/price/1024" => ("200 OK", Vec::new(), "<html>1000</html>".to_string()),
+            "/tx_anchor" => ("502 Bad Gateway", Vec::new(), page.clone()),
+            _ => (
+                "200 OK",
+                vec![("content-type", "text/html; charset=UTF-8".to_string())],
+                page.clone(),
+            ),
+        });
+        let arweave =
+            Arweave::from_keypair_path(PathBuf::from("tests/fixtures/test_key0.json"), base_url)
+                .await?;
+
+        let assert_unexpected = |result: Result<(), Error>, path: &str| match result {
+            Err(Error::GatewayUnexpectedResponse { endpoint, snippet }) => {
+                assert!(endpoint.ends_with(path), "{} {}", endpoint, path);
+                assert!(snippet.starts_with('<'));
+                assert!(snippet.chars().count() <= RESPONSE_SNIPPET_LEN);
+            }
+            result => panic!("unexpected result for {}: {:?}", path, result),
+        };
+        assert_unexpected(arweave.get_reward(1024).await.map(|_| ()), "/price/1024");
+        assert_unexpected(arweave.get_reward(2048).await.map(|_| ()), "/price/2048");
+        assert_unexpected(arweave.get_tx_anchor().await.map(|_| ()), "/tx_anchor");
+        assert_unexpected(arweave.get_pending_count().await.map(|_| ()), "/tx/pending");
+        assert_unexpected(
+            arweave
+                .get_wallet_balance(Some("address".to_string()))
+                .await
+                .map(|_| ()),
+            "/wallet/address/balance",
+        );
+
+        let message = arweave.get_tx_anchor().await.unwrap_err().to_string();
+        assert!(message.contains("/tx_anchor"));
+        assert!(message.contains("502 Bad Gateway"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_check_bundles_balance() -> Result<(), Error> {
+        let (base_url, _) = mock_server::serve(|_| ("200 OK", "1000".to_string()));
+        let arweave =
+            Arweave::from_keypair_path(PathBuf::from("tests/fixtures/test_key0.json"), base_url)
+                .await?;
+        let path_chunks = vec![
+            PathsChunk(Vec::new(), 300_000),
+            PathsChunk(Vec::new(), 300_000),
+        ];
+
+        assert_eq!(
+            arweave
+                .check_bundles_wallet_balance(&path_chunks, (100, 10))
+                .await?,
+            1000
+        );
+        assert_matches!(
+            arweave
+                .check_bundles_wallet_balance(&path_chunks, (600, 0))
+                .await,
+            Err(Error::InsufficientBalance {
+                balance: 1000,
+                required: 1200
+            })
+        );
+
+        // Each bundle costs the minimum payment plus the fee when paying with SOL.
+        let (solana_url, _) =
+            mock_server::serve(|_| ("200 OK", json!({"result": {"value": 40_000}}).to_string()));
+        let keypair = Keypair::new();
+        assert_eq!(
+            arweave
+                .check_bundles_sol_balance(&path_chunks, (100, 10), solana_url.clone(), &keypair)
+                .await?,
+            40_000
+        );
+        assert_matches!(
+            arweave
+                .check_bundles_sol_balance(
+                    &[path_chunks.clone(), path_chunks].concat(),
+                    (100, 10),
+                    solana_url,
+                    &keypair
+                )
+                .await,
+            Err(Error::InsufficientSolBalance {
+                balance: 40_000,
+                required: 60_000
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_metadata_links() {
+        // The link through the manifest is taken from the entry, which is keyed by the local
+        // path but links by the path relative to the directory the manifest was created from.
+        let path_object = json!({
+            "id": "lUPT6MXFYtZgy5eNQ5BCbg1h8oPlgI43Sj8S1cNKV5A",
+            "files": [
+                {"uri": "https://arweave.net/lUPT6MXFYtZgy5eNQ5BCbg1h8oPlgI43Sj8S1cNKV5A", "type": "image/png"},
+                {"uri": "https://arweave.net/k5SQMAVPxhS-GAsbZbbTV9469qZj7oH-_SM3H45nTk/assets/0.png", "type": "image/png"}
+            ]
+        });
+        let id_link = "https://arweave.net/lUPT6MXFYtZgy5eNQ5BCbg1h8oPlgI43Sj8S1cNKV5A";
+        let file_link =
+            "https://arweave.net/k5SQMAVPxhS-GAsbZbbTV9469qZj7oH-_SM3H45nTk/assets/0.png";
+        let file = |uri: &str| json!({"uri": uri, "type": "image/png"});
+
+        for (image_link, image) in [(ImageLink::Id, id_link), (ImageLink::File, file_link)] {
+            for (files_link, files) in [
+                (FilesLink::Id, vec![file(id_link)]),
+                (FilesLink::File, vec![file(file_link)]),
+                (FilesLink::Both, vec![file(id_link), file(file_link)]),
+            ] {
+                assert_eq!(
+                    metadata_links(
+                        "/home/me/assets/0.png",
+                        &path_object,
+                        image_link,
+                        files_link
+                    )
+                    .unwrap(),
+                    (image.to_string(), files),
+                    "{:?} {:?}",
+                    image_link,
+                    files_link
+                );
+            }
+        }
+
+        assert_matches!(
+            metadata_links(
+                "0.png",
+                &json!({"id": "lUPT6MXFYtZgy5eNQ5BCbg1h8oPlgI43Sj8S1cNKV5A"}),
+                ImageLink::Id,
+                FilesLink::Id
+            ),
+            Err(Error::InvalidManifestEntry(path)) if path == "0.png"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_post_chunk_rejected() -> Result<(), Error> {
+        let (base_url, requests) =
+            mock_server::serve(|_| ("400 Bad Request", "invalid_proof\n".to_string()));
+        let arweave = Arweave {
+            base_url,
+            ..Arweave::default()
+        };
+
+        let error = arweave
+            .post_chunk(&Chunk::default(), &arweave.client)
+            .await
+            .unwrap_err();
+        assert_eq!(requests.recv().unwrap().path, "/chunk");
+        assert_eq!(
+            error.to_string(),
+            "post rejected with 400 Bad Request: invalid_proof"
+        );
+        assert_matches!(
+            error,
+            Error::ArweavePostRejected { status, body, .. }
+                if status == reqwest::StatusCode::BAD_REQUEST && body == "invalid_proof"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_network_info() -> Result<(), Error> {
+        let (base_url, requests) = mock_server::serve(|path| match path {
+            "/info" => (
+                "200 OK",
+                std::fs::read_to_string("tests/fixtures/network_info.json").unwrap(),
+            ),
+            _ => (
+                "200 OK",
+                std::fs::read_to_string("tests/fixtures/tx_status.json").unwrap(),
+            ),
+        });
+        let arweave = Arweave {
+            base_url,
+            ..Arweave::default()
+        };
+
+        let info = arweave.get_network_info().await?;
+        assert_eq!(
+            (info.height, info.blocks, info.peers),
+            (882140, 882141, 412)
+        );
+        assert_eq!(requests.recv().unwrap().path, "/info");
+
+        // Reuses cached info instead of requesting it again for each status.
+        let status = arweave.get_status(&Base64(vec![1; 32])).await?;
+        let raw_status = status.raw_status.unwrap();
+        assert_eq!(raw_status.number_of_confirmations, 10);
+        assert_eq!(raw_status.confirmations_source, ConfirmationsSource::Height);
+        assert!(requests.recv().unwrap().path.starts_with("/tx/"));
+        assert!(requests.try_recv().is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_network_info_retries() -> Result<(), Error> {
+        let count = AtomicUsize::new(0);
+        let (base_url, requests) =
+            mock_server::serve(move |_| match count.fetch_add(1, Ordering::SeqCst) {
+                0 => ("503 Service Unavailable", String::new()),
+                1 => (
+                    "200 OK",
+                    std::fs::read_to_string("tests/fixtures/network_info.json").unwrap(),
+                ),
+                _ => ("400 Bad Request", String::new()),
+            });
+        let arweave = Arweave {
+            base_url: base_url.clone(),
+            ..Arweave::default()
+        };
+
+        let info = arweave.get_network_info().await?;
+        assert_eq!(info.height, 882140);
+        assert_eq!(requests.recv().unwrap().path, "/info");
+        assert_eq!(requests.recv().unwrap().path, "/info");
+
+        // Client errors aren't retried.
+        let arweave = Arweave {
+            base_url,
+            ..Arweave::default()
+        };
+        assert_matches!(
+            arweave.get_network_info().await,
+            Err(Error::ArweaveNetworkError(status)) if status == reqwest::StatusCode::BAD_REQUEST
+        );
+        assert_eq!(requests.recv().unwrap().path, "/info");
+        assert!(requests.try_recv().is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_upload_files_tags_length() -> Result<(), Error> {
+        let (base_url, requests) = mock_server::serve(|path| match path {
+            "/tx_anchor" => ("200 OK", "LCwsLCwsLA".to_string()),
+            _ => ("200 OK", String::new()),
+        });
+        let arweave =
+            Arweave::from_keypair_path(PathBuf::from("tests/fixtures/test_key0.json"), base_url)
+                .await?;
+        let paths = || (0..3).map(|i| PathBuf::from(format!("tests/fixtures/{}.png", i)));
+        let tags = |n: usize| {
+            Some(
+                vec![Some(vec![Tag::<Base64>::from_utf8_strs("key", "value").unwrap()]); n]
+                    .into_iter(),
+            )
+        };
+
+        for (n, expected) in [(2, 2), (4, 4)] {
+            assert_matches!(
+                arweave
+                    .upload_files_from_paths(paths(), None, tags(n), None, (0, 0))
+                    .await,
+                Err(Error::TagsLengthMismatch { paths: 3, tags }) if tags == expected
+            );
+        }
+        // Nothing is uploaded if the lengths don't match.
+        assert!(requests.try_recv().is_err());
+
+        let statuses = arweave
+            .upload_files_from_paths(paths(), None, tags(3), None, (0, 0))
+            .await?;
+        assert_eq!(statuses.len(), 3);
+
+        let repeated = Some(std::iter::repeat(Some(vec![
+            Tag::<Base64>::from_utf8_strs("key", "value")?,
+        ])));
+        let statuses = arweave
+            .upload_files_from_paths(paths(), None, repeated.clone(), None, (0, 0))
+            .await?;
+        assert_eq!(statuses.len(), 3);
+
+        // Unbounded iterators without an infinite size hint aren't consumed to count extras.
+        let unbounded = repeated.map(|r| r.filter(|_| true));
+        let statuses = arweave
+            .upload_files_from_paths(paths(), None, unbounded, None, (0, 0))
+            .await?;
+        assert_eq!(statuses.len(), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_check_before_sol_payment() -> Result<(), Error> {
+        let (base_url, _requests) = mock_server::serve(|path| match path {
+            "/info" => (
+                "200 OK",
+                std::fs::read_to_string("tests/fixtures/network_info.json").unwrap(),
+            ),
+            _ => ("200 OK", "1000".to_string()),
+        });
+        let arweave = Arweave {
+            base_url,
+            ..Arweave::default()
+        };
+
+        let mut transaction = arweave
+            .create_transaction(vec![1; 100], None, Some(Base64(vec![])), (0, 0), true)
+            .await?;
+        transaction.reward = 999;
+        assert_matches!(
+            arweave.check_before_sol_payment(&transaction).await,
+            Err(Error::RewardBelowPrice {
+                reward: 999,
+                price: 1000
+            })
+        );
+        transaction.reward = 1000;
+        arweave.check_before_sol_payment(&transaction).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_repost_paid_transaction() -> Result<(), Error> {
+        let (base_url, requests) = mock_server::serve(|_| ("200 OK", "OK".to_string()));
+        let arweave =
+            Arweave::from_keypair_path(PathBuf::from("tests/fixtures/test_key0.json"), base_url)
+                .await?;
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let log_dir = temp_dir.0.clone();
+
+        let transaction = arweave
+            .create_transaction(vec![1; 100], None, Some(Base64(vec![])), (0, 0), true)
+            .await?;
+        let transaction = arweave.sign_transaction(transaction)?;
+        let sig_response = SigResponse {
+            ar_tx_sig: transaction.signature.clone(),
+            ar_tx_id: transaction.id.clone(),
+            ar_tx_owner: transaction.owner.clone(),
+            sol_tx_sig: String::new(),
+            lamports: 0,
+        };
+        let file_path = PathBuf::from("tests/fixtures/0.png");
+        let status = PaidStatus::File(Status {
+            file_path: Some(file_path.clone()),
+            id: transaction.id.clone(),
+            ..Status::default()
+        });
+
+        let path = arweave
+            .save_paid_transaction(&transaction, sig_response, status, Some(&log_dir))
+            .await?
+            .unwrap();
+        assert_eq!(
+            arweave.paid_transaction_paths(&log_dir)?,
+            vec![path.clone()]
+        );
+
+        // A failed post keeps the paid transaction and points to it.
+        let error = finish_paid_post(Err(Error::SolanaNetworkError), Some(path.clone()))
+            .await
+            .unwrap_err();
+        assert_matches!(&error, Error::PaidPostFailed { path: p, .. } if p == &path);
+        assert!(path.exists());
+
+        let (id, _) = arweave.repost_paid_transaction(&path, &log_dir, 1).await?;
+        assert_eq!(id, transaction.id);
+        assert_eq!(requests.recv().unwrap().path, "/tx");
+        assert!(arweave.paid_transaction_paths(&log_dir)?.is_empty());
+
+        let status = arweave.read_status(file_path, log_dir).await?;
+        assert_eq!(status.id, transaction.id);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_repost_paid_file_transaction() -> Result<(), Error> {
+        let (base_url, requests) = mock_server::serve(|_| ("200 OK", "OK".to_string()));
+        let arweave =
+            Arweave::from_keypair_path(PathBuf::from("tests/fixtures/test_key0.json"), base_url)
+                .await?;
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let log_dir = temp_dir.0.clone();
+        let file_path = temp_dir.0.join("data.bin");
+        fs::write(&file_path, vec![1; 100]).await?;
+
+        let transaction = arweave
+            .create_transaction_from_file_path_streamed(
+                file_path.clone(),
+                None,
+                Some(Base64(vec![])),
+                (0, 0),
+                false,
+            )
+            .await?;
+        let transaction = arweave.sign_transaction(transaction)?;
+        let sig_response = SigResponse {
+            ar_tx_sig: transaction.signature.clone(),
+            ar_tx_id: transaction.id.clone(),
+            ar_tx_owner: transaction.owner.clone(),
+            sol_tx_sig: String::new(),
+            lamports: 0,
+        };
+        let status = PaidStatus::File(Status {
+            file_path: Some(file_path.clone()),
+            id: transaction.id.clone(),
+            ..Status::default()
+        });
+        let path = arweave
+            .save_paid_transaction(&transaction, sig_response, status, Some(&log_dir))
+            .await?
+            .unwrap();
+
+        // The data is saved with the file path instead of in the transaction.
+        let saved: PaidTransaction = serde_json::from_slice(&fs::read(&path).await?)?;
+        assert!(saved.transaction.data.0.is_empty());
+
+        // Data that has changed since it was paid for isn't posted.
+        fs::write(&file_path, vec![2; 100]).await?;
+        assert_matches!(
+            arweave.repost_paid_transaction(&path, &log_dir, 1).await,
+            Err(Error::DataRootMismatch(_))
+        );
+        assert!(path.exists());
+
+        fs::write(&file_path, vec![1; 100]).await?;
+        let (id, _) = arweave.repost_paid_transaction(&path, &log_dir, 1).await?;
+        assert_eq!(id, transaction.id);
+        let posted: Value = serde_json::from_slice(&requests.recv().unwrap().body)?;
+        assert_eq!(posted["data"], Base64(vec![1; 100]).to_string());
+        assert!(arweave.paid_transaction_paths(&log_dir)?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_only_refuses_posts() -> Result<(), Error> {
+        let (base_url, requests) = mock_server::serve(|_| ("200 OK", "Pending".to_string()));
+        let mut arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key0.json"),
+            base_url.clone(),
+        )
+        .await?;
+        arweave.read_only = true;
+
+        let transaction = arweave
+            .create_transaction(vec![1; 100], None, Some(Base64(vec![])), (0, 0), true)
+            .await?;
+        let signed_transaction = arweave.sign_transaction(transaction)?;
+        let chunk = signed_transaction.get_chunk(0)?;
+        let client = arweave.client.clone();
+
+        assert_matches!(
+            arweave.post_transaction(&signed_transaction).await,
+            Err(Error::ReadOnlyMode)
+        );
+        assert_matches!(
+            arweave.post_chunk(&chunk, &client).await,
+            Err(Error::ReadOnlyMode)
+        );
+        assert_matches!(
+            arweave.post_chunk_with_retries(chunk, client).await,
+            Err(Error::ReadOnlyMode)
+        );
+        assert_matches!(
+            arweave
+                .post_bundle_index_hint(&BundleStatus::default(), &base_url)
+                .await,
+            Err(Error::ReadOnlyMode)
+        );
+
+        let id = signed_transaction.id.clone();
+        assert_matches!(
+            arweave
+                .sign_transaction_with_sol(
+                    signed_transaction,
+                    base_url.clone(),
+                    base_url.clone(),
+                    &Keypair::new(),
+                )
+                .await,
+            Err(Error::ReadOnlyMode)
+        );
+
+        // Queries still go through.
+        assert_eq!(arweave.get_status(&id).await?.status, StatusCode::Pending);
+        assert_eq!(requests.recv().unwrap().method, "GET");
+        assert!(requests.try_recv().is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_gzip_requests() -> Result<(), Error> {
+        let (base_url, requests) = mock_server::serve(|_| ("200 OK", String::new()));
+        let mut arweave =
+            Arweave::from_keypair_path(PathBuf::from("tests/fixtures/test_key0.json"), base_url)
+                .await?;
+        arweave.gzip_requests = true;
+        let transaction = arweave
+            .create_transaction(vec![1; 10_000], None, Some(Base64(vec![])), (0, 0), true)
+            .await?;
+        let signed_transaction = arweave.sign_transaction(transaction)?;
+        let chunk = signed_transaction.get_chunk(0)?;
+
+        arweave.post_transaction(&signed_transaction).await?;
+        arweave.post_chunk(&chunk, &arweave.client).await?;
+        let mut saved = 0;
+        for plain in [
+            serde_json::to_vec(&signed_transaction)?,
+            serde_json::to_vec(&chunk)?,
+        ] {
+            let request = requests.recv().unwrap();
+            assert_eq!(request.header("content-encoding"), Some("gzip"));
+            assert_eq!(request.header("content-type"), Some("application/json"));
+            let mut body = Vec::new();
+            GzDecoder::new(request.body.as_slice()).read_to_end(&mut body)?;
+            assert_eq!(body, plain);
+            assert!(request.body.len() < plain.len());
+            saved += plain.len() - request.body.len();
+        }
+        assert_eq!(arweave.gzip_stats.bytes_saved(), saved as u64);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_gzip_requests_fallback() -> Result<(), Error> {
+        let count = AtomicUsize::new(0);
+        let (base_url, requests) =
+            mock_server::serve(move |_| match count.fetch_add(1, Ordering::SeqCst) {
+                0 => ("415 Unsupported Media Type", String::new()),
+                _ => ("200 OK", String::new()),
+            });
+        let mut arweave =
+            Arweave::from_keypair_path(PathBuf::from("tests/fixtures/test_key0.json"), base_url)
+                .await?;
+        arweave.gzip_requests = true;
+        let transaction = arweave
+            .create_transaction(vec![1; 1000], None, Some(Base64(vec![])), (0, 0), true)
+            .await?;
+        let signed_transaction = arweave.sign_transaction(transaction)?;
+        let plain = serde_json::to_vec(&signed_transaction)?;
+
+        arweave.post_transaction(&signed_transaction).await?;
+        assert!(arweave.gzip_stats.rejected());
+        assert_eq!(
+            requests.recv().unwrap().header("content-encoding"),
+            Some("gzip")
+        );
+        let request = requests.recv().unwrap();
+        assert_eq!(request.header("content-encoding"), None);
+        assert_eq!(request.body, plain);
+
+        // Later requests aren't compressed at all.
+        arweave.post_transaction(&signed_transaction).await?;
+        assert_eq!(requests.recv().unwrap().header("content-encoding"), None);
+        assert_eq!(arweave.gzip_stats.bytes_saved(), 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cannot_post_unsigned_transaction() -> Result<(), Error> {
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from(
+                "tests/fixtures/arweave-key-7eV1qae4qVNqsNChg3Scdi-DpOLJPCogct4ixoq1WNg.json",
+            ),
+            Url::from_str("http://url.com").unwrap(),
+        )
+        .await?;
+
+        let file_path = PathBuf::from("tests/fixtures/0.png");
+        let last_tx = Base64::from_str("LCwsLCwsLA")?;
+        let other_tags = vec![Tag::<Base64>::from_utf8_strs("key2", "value2")?];
+        let transaction = arweave
+            .create_transaction_from_file_path(
+                file_path,
+                Some(other_tags),
+                Some(last_tx),
+                (0, 0),
+                true,
+            )
+            .await?;
+
+        let error = arweave.post_transaction(&transaction).await.unwrap_err();
+        assert_matches!(error, Error::UnsignedTransaction);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_write_read_status() -> Result<(), Error> {
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from(
+                "tests/fixtures/arweave-key-7eV1qae4qVNqsNChg3Scdi-DpOLJPCogct4ixoq1WNg.json",
+            ),
+            Url::from_str("http://url.com").unwrap(),
+        )
+        .await?;
+
+        let file_path = PathBuf::from("tests/fixtures/0.png");
+        let last_tx = Base64::from_str("LCwsLCwsLA")?;
+        let other_tags = vec![Tag::<Base64>::from_utf8_strs("key2", "value2")?];
+        let transaction = arweave
+            .create_transaction_from_file_path(
+                file_path.clone(),
+                Some(other_tags),
+                Some(last_tx),
+                (0, 0),
+                true,
+            )
+            .await?;
+
+        let signed_transaction = arweave.sign_transaction(transaction)?;
+
+        let status = Status {
+            id: signed_transaction.id.clone(),
+            reward: signed_transaction.reward,
+            file_path: Some(file_path.clone()),
+            ..Default::default()
+        };
+
+        let temp_log_dir = TempDir::from_str("./tests/").await?;
+        let log_dir = temp_log_dir.0.clone();
+
+        arweave
+            .write_status(status.clone(), log_dir.clone(), None)
+            .await?;
+
+        let read_status = arweave.read_status(file_path, log_dir).await?;
+
+        assert_eq!(status, read_status);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bundle_manifest_owner_address() -> Result<(), Error> {
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from(
+                "tests/fixtures/arweave-key-7eV1qae4qVNqsNChg3Scdi-DpOLJPCogct4ixoq1WNg.json",
+            ),
+            Url::from_str("http://url.com").unwrap(),
+        )
+        .await?;
+
+        let paths_iter = glob("tests/fixtures/[0-4].png")?
+            .filter_map(Result::ok)
+            .collect();
+        let data_items = arweave
+            .create_data_items_from_file_paths(paths_iter, Vec::new())
+            .await?;
+        let (_, manifest, _) = arweave.create_bundle_from_data_items(data_items)?;
+
+        let wallet_address = arweave.crypto.wallet_address()?.to_string();
+        let paths = manifest["paths"].as_object().unwrap();
+        assert_eq!(paths.len(), 5);
+        paths
+            .values()
+            .for_each(|v| assert_eq!(v["owner_address"], wallet_address));
+
+        // Only the ids of the files are posted.
+        let encoded = arweave.encode_manifest_keys(&manifest)?;
+        let encoded_paths = encoded["paths"].as_object().unwrap();
+        assert_eq!(encoded_paths.len(), 5);
+        for (path, entry) in encoded_paths {
+            assert_eq!(
+                entry,
+                &json!({ "id": paths[&format!("tests/fixtures/{}", path)]["id"] })
+            );
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_post_bundle_from_serialized_items() -> Result<(), Error> {
+        let gateway = mock_server::MockGateway::serve();
+        let mut arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key0.json"),
+            gateway.url.clone(),
+        )
+        .await?;
+        arweave.sol_keypair = Some(read_keypair_file("tests/fixtures/solana_test.json")?);
+
+        // Items as another tool would write them, one named and one without a File-Name tag.
+        let named = arweave.sign_data_item(arweave.create_data_item(
+            b"hello".to_vec(),
+            vec![Tag::<String>::from_utf8_strs("File-Name", "hello.txt")?],
+            true,
+            false,
+            SignatureType::Arweave,
+        )?)?;
+        let unnamed = arweave.sign_data_item(arweave.create_data_item(
+            b"{}".to_vec(),
+            vec![Tag::<String>::from_utf8_strs(
+                "Content-Type",
+                "application/json",
+            )?],
+            false,
+            false,
+            SignatureType::Ed25519,
+        )?)?;
+        let items = vec![named.serialize()?, unnamed.serialize()?];
+        for (bytes, data_item) in items.iter().zip([&named, &unnamed]) {
+            assert_eq!(
+                arweave.deserialize_signed_data_item(bytes)?.id,
+                data_item.id
+            );
+        }
+
+        let mut tampered = items.clone();
+        let last = tampered[1].len() - 1;
+        tampered[1][last] ^= 1;
+        assert!(arweave.deserialize_signed_data_item(&tampered[1]).is_err());
+        assert!(arweave
+            .post_bundle_from_serialized_items(tampered, None, (0, 0), 1, RetryPolicy::default())
+            .await
+            .is_err());
+        assert!(gateway.posted_transactions().is_empty());
+
+        let duplicated = vec![items[0].clone(), items[1].clone(), items[0].clone()];
+        assert_matches!(
+            arweave
+                .post_bundle_from_serialized_items(duplicated, None, (0, 0), 1, RetryPolicy::default())
+                .await,
+            Err(Error::DuplicateDataItem(id)) if id == named.id.to_string()
+        );
+        assert!(gateway.posted_transactions().is_empty());
+
+        let status = arweave
+            .post_bundle_from_serialized_items(items, None, (0, 0), 1, RetryPolicy::default())
+            .await?;
+        assert_eq!(status.number_of_files, 2);
+        assert_eq!(status.data_size, 7);
+        let paths = status.file_paths.as_object().unwrap();
+        assert_eq!(paths["hello.txt"]["id"], named.id.to_string());
+        assert_eq!(paths[&unnamed.id.to_string()]["id"], unnamed.id.to_string());
+        assert_eq!(
+            gateway.posted_transactions()[0]["id"],
+            status.id.to_string()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_check_bundle_reward() -> Result<(), Error> {
+        // The gateway charges 150 winstons for every block of data.
+        let (base_url, _) = mock_server::serve(|path| match path.strip_prefix("/price/") {
+            Some(bytes) => {
+                let blocks = NetworkParams::default().blocks_len(bytes.parse().unwrap());
+                ("200 OK", (150 * blocks).to_string())
+            }
+            None => ("200 OK", "LCwsLCwsLA".to_string()),
+        });
+        let mut arweave =
+            Arweave::from_keypair_path(PathBuf::from("tests/fixtures/test_key0.json"), base_url)
+                .await?;
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let file_size = BLOCK_SIZE / 2 - 100;
+        let mut paths = Vec::new();
+        for i in 0..2 {
+            let path = temp_dir.0.join(format!("{}.bin", i));
+            fs::write(&path, vec![i as u8; file_size as usize]).await?;
+            paths.push(path);
+        }
+        // Price terms quoted before the price went up.
+        let price_terms = (100, 100);
+
+        // The files fit in a single block, but not once they're serialized into a bundle, so
+        // the bundle is priced for two blocks.
+        assert_eq!(arweave.get_reward(2 * file_size).await?, 150);
+        arweave.strict_reward = true;
+        assert_matches!(
+            arweave
+                .create_bundle_transaction_from_file_paths(paths.clone(), Vec::new(), price_terms)
+                .await,
+            Err(Error::InsufficientReward {
+                reward: 200,
+                required: 300
+            })
+        );
+
+        arweave.strict_reward = false;
+        let (transaction, _) = arweave
+            .create_bundle_transaction_from_file_paths(paths, Vec::new(), price_terms)
+            .await?;
+        assert!(transaction.data.0.len() as u64 > BLOCK_SIZE);
+        assert_eq!(transaction.reward, 300);
+        let breakdown = transaction
+            .reward_breakdown(&arweave.network_params)
+            .unwrap();
+        assert_eq!((breakdown.blocks, breakdown.total), (2, 200));
+        assert_eq!(transaction.chunk_count(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sizes_from_log_dir() -> Result<(), Error> {
+        let arweave = Arweave::default();
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let log_dir = format!("{}/", temp_dir.0.display());
+
+        let bundle_status = BundleStatus {
+            id: Base64(vec![1; 32]),
+            file_paths: json!({"0.png": {}, "1.png": {}}),
+            number_of_files: 2,
+            data_size: 300_000,
+            tags_bytes: 100,
+            overhead_bytes: 2_000,
+            reward: 500,
+            ..BundleStatus::default()
+        };
+        fs::write(
+            format!("{}{}.json", log_dir, bundle_status.id),
+            to_status_json(&bundle_status, false)?,
+        )
+        .await?;
+        let status = Status {
+            id: Base64(vec![2; 32]),
+            file_path: Some(PathBuf::from("2.png")),
+            data_size: Some(1000),
+            reward: 100,
+            ..Status::default()
+        };
+        arweave
+            .write_status(status, temp_dir.0.clone(), None)
+            .await?;
+        // Written before sizes were recorded.
+        let status = Status {
+            id: Base64(vec![3; 32]),
+            file_path: Some(PathBuf::from("3.png")),
+            reward: 100,
+            ..Status::default()
+        };
+        arweave
+            .write_status(status, temp_dir.0.clone(), None)
+            .await?;
+
+        let sizes = arweave.sizes_from_log_dir(&log_dir).await?;
+        assert_eq!(
+            sizes,
+            vec![
+                RecordedSize {
+                    id: Base64(vec![1; 32]),
+                    file_paths: vec![PathBuf::from("0.png"), PathBuf::from("1.png")],
+                    data_size: 300_000,
+                    overhead_bytes: 2_000,
+                    reward: 500,
+                },
+                RecordedSize {
+                    id: Base64(vec![2; 32]),
+                    file_paths: vec![PathBuf::from("2.png")],
+                    data_size: 1000,
+                    overhead_bytes: 0,
+                    reward: 100,
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_check_local_files() -> Result<(), Error> {
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key0.json"),
+            Url::from_str("http://url.com").unwrap(),
+        )
+        .await?;
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let log_dir = format!("{}/", temp_dir.0.display());
+        let paths: Vec<PathBuf> = (0..5)
+            .map(|i| temp_dir.0.join(format!("{}.txt", i)))
+            .collect();
+        for (i, path) in paths.iter().enumerate() {
+            fs::write(path, format!("file {}", i)).await?;
+        }
+
+        // Files 0-2 in a bundle, 3 uploaded individually and 4 with a status from before hashes
+        // were recorded.
+        let data_items = arweave
+            .create_data_items_from_file_paths(paths[..3].to_vec(), Vec::new())
+            .await?;
+        let (_, manifest, _) = arweave.create_bundle_from_data_items(data_items)?;
+        let bundle_status = BundleStatus {
+            id: Base64(vec![1; 32]),
+            file_paths: manifest["paths"].clone(),
+            number_of_files: 3,
+            ..BundleStatus::default()
+        };
         fs::write(
-            log_dir.join(file_stem).with_extension("json"),
-            serde_json::to_string(&status)?,
+            format!("{}{}.json", log_dir, bundle_status.id),
+            to_status_json(&bundle_status, false)?,
         )
         .await?;
-        Ok(())
-    }
-
-    //-------------------------
-    // Manifest
-    //-------------------------
-
-    pub fn create_data_item_from_manifest(&self, manifest: Value) -> Result<DataItem, Error> {
-        let tags = vec![
-            Tag::<String>::from_utf8_strs("Content-Type", "application/x.arweave-manifest+json")?,
-            Tag::<String>::from_utf8_strs("User-Agent", &format!("arloader/{}", VERSION))?,
-        ];
-
-        // let mut anchor = Base64(Vec::with_capacity(32));
-        // self.crypto.fill_rand(&mut anchor.0)?;
+        let (_, status) = arweave
+            .create_data_item_from_file_path(paths[3].clone(), Vec::new(), None)
+            .await?;
+        assert_eq!(status.data_size, Some(6));
+        arweave
+            .write_status(status, temp_dir.0.clone(), None)
+            .await?;
+        let status = Status {
+            id: Base64(vec![2; 32]),
+            file_path: Some(paths[4].clone()),
+            ..Status::default()
+        };
+        arweave
+            .write_status(status, temp_dir.0.clone(), None)
+            .await?;
 
-        Ok(DataItem {
-            data: Base64(serde_json::to_string(&manifest)?.as_bytes().to_vec()),
-            tags,
-            // anchor,
-            ..DataItem::default()
-        })
+        // Same size with different contents, so only the hash catches it.
+        fs::write(&paths[1], "file 9").await?;
+        fs::remove_file(&paths[2]).await?;
+
+        let files = arweave.read_recorded_files(&log_dir).await?;
+        assert_eq!(files.len(), 5);
+        let mut results: Vec<(PathBuf, LocalFileState)> = check_local_files_stream(files)
+            .map(|(file, state)| (file.file_path, state))
+            .collect()
+            .await;
+        results.sort();
+        assert_eq!(
+            results,
+            vec![
+                (paths[0].clone(), LocalFileState::Ok),
+                (paths[1].clone(), LocalFileState::Modified),
+                (paths[2].clone(), LocalFileState::Missing),
+                (paths[3].clone(), LocalFileState::Ok),
+                (paths[4].clone(), LocalFileState::Unverified),
+            ]
+        );
+        Ok(())
     }
 
-    pub fn create_manifest(&self, statuses: Vec<Status>) -> Result<Value, Error> {
-        let paths = statuses
-            .into_iter()
-            .fold(serde_json::Map::new(), |mut m, s| {
-                m.insert(
-                    s.file_path.unwrap().to_str().unwrap().to_string(),
-                    json!({"id": s.id.to_string(), "content_type": s.content_type}),
-                );
-                m
-            });
+    #[tokio::test]
+    async fn test_json_content_type() -> Result<(), Error> {
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key0.json"),
+            Url::from_str("http://url.com").unwrap(),
+        )
+        .await?;
+        let content_types = |data_item: &DataItem| -> Vec<String> {
+            data_item
+                .tags
+                .iter()
+                .filter(|t| t.name == "Content-Type")
+                .map(|t| t.value.clone())
+                .collect()
+        };
 
-        let manifest = json!({
-            "manifest": "arweave/paths",
-            "version": "0.1.0",
-            "paths": Value::Object(paths)
-        });
+        let (data_item, status) = arweave
+            .create_data_item_from_file_path(PathBuf::from("tests/fixtures/metadata"), vec![], None)
+            .await?;
+        assert_eq!(content_types(&data_item), vec!["application/json"]);
+        assert_eq!(status.content_type, "application/json");
+
+        let (data_item, _) = arweave
+            .create_data_item_from_file_path(
+                PathBuf::from("tests/fixtures/0.png"),
+                vec![],
+                Some("application/json"),
+            )
+            .await?;
+        assert_eq!(content_types(&data_item), vec!["application/json"]);
 
-        Ok(manifest)
+        let tags = vec![Tag::<String>::from_utf8_strs(
+            "Content-Type",
+            "application/json",
+        )?];
+        let data_items = arweave
+            .create_data_items_from_file_paths(vec![PathBuf::from("tests/fixtures/0.png")], tags)
+            .await?;
+        assert_eq!(content_types(&data_items[0].0), vec!["application/json"]);
+        Ok(())
     }
 
-    pub fn create_manifest_from_bundle_statuses(
-        &self,
-        statuses: Vec<BundleStatus>,
-    ) -> Result<Value, Error> {
-        let paths = statuses
-            .into_iter()
-            .fold(serde_json::Map::new(), |mut m, mut s| {
-                m.append(s.file_paths.as_object_mut().unwrap());
-                m
-            });
+    #[tokio::test]
+    async fn test_paths_not_in_log_dir() -> Result<(), Error> {
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key0.json"),
+            Url::from_str("http://url.com").unwrap(),
+        )
+        .await?;
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let paths: Vec<PathBuf> = (0..5)
+            .map(|i| PathBuf::from(format!("tests/fixtures/{}.png", i)))
+            .collect();
 
-        let manifest = json!({
-            "manifest": "arweave/paths",
-            "version": "0.1.0",
-            "paths": Value::Object(paths)
-        });
+        // File 0 uploaded individually, file 1 in a bundle, file 3 in a bundle that didn't
+        // finish posting and file 4 in one whose chunks were all posted before the final status
+        // was written.
+        let status = Status {
+            id: Base64(vec![1; 32]),
+            file_path: Some(paths[0].clone()),
+            ..Status::default()
+        };
+        arweave
+            .write_status(status, temp_dir.0.clone(), None)
+            .await?;
+        let bundle_status = BundleStatus {
+            id: Base64(vec![2; 32]),
+            file_paths: json!({ paths[1].display().to_string(): {} }),
+            number_of_files: 1,
+            ..BundleStatus::default()
+        };
+        let interim_status = BundleStatus {
+            id: Base64(vec![3; 32]),
+            file_paths: json!({ paths[3].display().to_string(): {} }),
+            number_of_files: 1,
+            upload_progress: Some(UploadProgress {
+                chunks_posted: 10,
+                chunks_total: 20,
+            }),
+            ..BundleStatus::default()
+        };
+        let posted_status = BundleStatus {
+            id: Base64(vec![4; 32]),
+            file_paths: json!({ paths[4].display().to_string(): {} }),
+            number_of_files: 1,
+            upload_progress: Some(UploadProgress {
+                chunks_posted: 20,
+                chunks_total: 20,
+            }),
+            ..BundleStatus::default()
+        };
+        for status in [&bundle_status, &interim_status, &posted_status] {
+            fs::write(
+                temp_dir.0.join(format!("{}.json", status.id)),
+                to_status_json(status, false)?,
+            )
+            .await?;
+        }
 
-        Ok(manifest)
+        let missing = arweave
+            .paths_not_in_log_dir(paths.clone().into_iter(), temp_dir.0.clone())
+            .await?;
+        assert_eq!(missing, vec![paths[2].clone(), paths[3].clone()]);
+        Ok(())
     }
 
-    pub async fn create_transaction_from_manifest(
-        &self,
-        manifest: Value,
-        price_terms: (u64, u64),
-    ) -> Result<Transaction, Error> {
-        let tags = vec![Tag::<Base64>::from_utf8_strs(
-            "Content-Type",
-            "application/x.arweave-manifest+json",
-        )?];
-
-        // let mut anchor = Base64(Vec::with_capacity(32));
-        // self.crypto.fill_rand(&mut anchor.0)?;
+    #[tokio::test]
+    async fn test_manifest_keys_and_metaplex_names() -> Result<(), Error> {
+        let mut arweave = Arweave::default();
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let file_path = temp_dir.0.join("café #1.json");
+        let long_name = "Arloader Limited Edition Collection #1";
+        fs::write(&file_path, json!({ "name": long_name }).to_string()).await?;
+
+        let manifest = arweave.create_manifest(
+            vec![Status {
+                id: Base64(vec![44; 32]),
+                file_path: Some(file_path.clone()),
+                ..Status::default()
+            }],
+            None,
+        )?;
+        let encoded_path = "caf%C3%A9%20%231.json";
+        let encoded = arweave.encode_manifest_keys(&manifest)?;
+        assert!(encoded["paths"].get(encoded_path).is_some());
+
+        // Relative to the base dir if set, even if the file path is absolute.
+        let absolute = arweave.create_manifest(
+            vec![Status {
+                id: Base64(vec![44; 32]),
+                file_path: Some(std::env::current_dir()?.join(&file_path)),
+                ..Status::default()
+            }],
+            None,
+        )?;
+        arweave.manifest_base_dir = Some(std::env::current_dir()?.join("tests"));
+        let encoded = arweave.encode_manifest_keys(&absolute)?;
+        let temp_dir_name = temp_dir.0.file_name().unwrap().to_str().unwrap();
+        assert!(encoded["paths"]
+            .get(format!("{}/{}", temp_dir_name, encoded_path))
+            .is_some());
+        arweave.manifest_base_dir = None;
+
+        arweave.strict_paths = true;
+        assert_matches!(
+            arweave.encode_manifest_keys(&manifest),
+            Err(Error::InvalidManifestKey(_))
+        );
 
-        let data = serde_json::to_string(&manifest)?.as_bytes().to_vec();
-        let transaction = self
-            .create_transaction(data, Some(tags), None, price_terms, false)
+        // Local manifest stays keyed by file path, with encoded links.
+        let manifest_path = arweave
+            .write_manifest(manifest, "manifest-id".to_string(), temp_dir.0.clone())
             .await?;
-
-        Ok(transaction)
+        assert_eq!(manifest_path, temp_dir.0.join("manifest_manifest-id.json"));
+        let paths = [file_path.clone()];
+
+        assert_matches!(
+            arweave
+                .write_metaplex_items(
+                    paths.clone().into_iter(),
+                    manifest_path.clone(),
+                    true,
+                    false,
+                    false
+                )
+                .await,
+            Err(Error::MetaplexNameTooLong(_))
+        );
+        let items_path = arweave
+            .write_metaplex_items(paths.into_iter(), manifest_path, true, true, false)
+            .await?;
+        let items: Value = serde_json::from_str(&fs::read_to_string(items_path).await?)?;
+        let item = &items["café #1"];
+        assert_eq!(item["name"], long_name[..32]);
+        assert_eq!(
+            item["link"],
+            format!("https://arweave.net/manifest-id/{}", encoded_path)
+        );
+        Ok(())
     }
 
-    pub async fn upload_manifest_from_bundle_log_dir(
-        &self,
-        log_dir: &str,
-        price_terms: (u64, u64),
-        solana_url: Url,
-        sol_ar_url: Url,
-        from_keypair: Option<Keypair>,
-    ) -> Result<String, Error> {
-        let paths: Vec<PathBuf> = glob(&format!("{}*.json", log_dir.clone()))?
-            .filter_map(Result::ok)
+    #[tokio::test]
+    async fn test_manifest_index() -> Result<(), Error> {
+        let mut arweave = Arweave::default();
+        let statuses: Vec<Status> = ["site/index.html", "site/docs/page one.html"]
+            .iter()
+            .enumerate()
+            .map(|(i, p)| Status {
+                id: Base64(vec![i as u8; 32]),
+                file_path: Some(PathBuf::from(p)),
+                content_type: "text/html".to_string(),
+                ..Status::default()
+            })
             .collect();
 
-        let paths_len = paths.len();
-        if paths_len == 0 {
-            return Ok(format!("No bundle statuses found in {}", log_dir));
-        };
+        let manifest = arweave.create_manifest(statuses.clone(), None)?;
+        assert!(manifest.get("index").is_none());
 
-        let statuses = self.read_bundle_statuses(log_dir).await?;
+        let manifest = arweave.create_manifest(statuses.clone(), Some("index.html"))?;
+        assert_eq!(manifest["index"], json!({"path": "index.html"}));
+        let manifest = arweave.create_manifest(statuses.clone(), Some("./docs/page one.html"))?;
+        let encoded = arweave.encode_manifest_keys(&manifest)?;
+        assert_eq!(encoded["index"]["path"], "docs/page%20one.html");
+        assert!(encoded["paths"].get("docs/page%20one.html").is_some());
 
-        let manifest = self.create_manifest_from_bundle_statuses(statuses)?;
-        let num_files = manifest["paths"].as_object().unwrap().keys().len();
-        let transaction = self
-            .create_transaction_from_manifest(manifest.clone(), price_terms)
-            .await?;
+        assert_matches!(
+            arweave.create_manifest(statuses.clone(), Some("missing.html")),
+            Err(Error::ManifestIndexNotFound(p)) if p == "missing.html"
+        );
 
-        let signed_transaction = if let Some(from_keypair) = from_keypair {
-            let (signed_transaction, _): (Transaction, SigResponse) = self
-                .sign_transaction_with_sol(transaction, solana_url, sol_ar_url, &from_keypair)
-                .await?;
-            signed_transaction
-        } else {
-            self.sign_transaction(transaction)?
-        };
+        // Relative to the base dir if set.
+        arweave.manifest_base_dir = Some(PathBuf::from("."));
+        assert_matches!(
+            arweave.create_manifest(statuses.clone(), Some("index.html")),
+            Err(Error::ManifestIndexNotFound(_))
+        );
+        let manifest = arweave.create_manifest(statuses, Some("site/index.html"))?;
+        assert_eq!(manifest["index"]["path"], "site/index.html");
+        Ok(())
+    }
 
-        let (id, _) = self.post_transaction(&signed_transaction).await?;
+    #[test]
+    fn test_manifest_fallback() -> Result<(), Error> {
+        let mut arweave = Arweave::default();
+        let statuses: Vec<Status> = ["site/index.html", "site/404.html"]
+            .iter()
+            .enumerate()
+            .map(|(i, p)| Status {
+                id: Base64(vec![i as u8; 32]),
+                file_path: Some(PathBuf::from(p)),
+                content_type: "text/html".to_string(),
+                ..Status::default()
+            })
+            .collect();
 
-        self.write_manifest(manifest, id.to_string(), PathBuf::from(log_dir))
-            .await?;
+        let manifest = arweave.create_manifest(statuses.clone(), None)?;
+        assert_eq!(manifest["version"], "0.1.0");
+        assert!(manifest.get("fallback").is_none());
 
-        Ok(format!("Uploaded manifest for {} files and wrote to {}manifest_{id}.json.\n\nRun `arloader get-status {id}` to confirm manifest transaction.",
-        num_files, log_dir, id=id.to_string()))
+        arweave.manifest_fallback = Some("./404.html".to_string());
+        let manifest = arweave.create_manifest(statuses.clone(), Some("index.html"))?;
+        assert_eq!(manifest["version"], "0.2.0");
+        assert_eq!(
+            manifest["fallback"],
+            json!({ "id": Base64(vec![1; 32]).to_string() })
+        );
+        let encoded = arweave.encode_manifest_keys(&manifest)?;
+        assert_eq!(encoded["fallback"], manifest["fallback"]);
+        assert_eq!(encoded["index"]["path"], "index.html");
+
+        arweave.manifest_fallback = Some("missing.html".to_string());
+        assert_matches!(
+            arweave.create_manifest(statuses, None),
+            Err(Error::ManifestFallbackNotFound(p)) if p == "missing.html"
+        );
+        Ok(())
     }
 
-    pub async fn write_manifest(
-        &self,
-        manifest: Value,
-        transaction_id: String,
-        log_dir: PathBuf,
-    ) -> Result<(), Error> {
-        let mut consolidated_paths = serde_json::Map::new();
-        for (file_path, id_obj) in manifest["paths"].as_object().unwrap() {
-            let id = id_obj["id"].as_str().unwrap();
-            let content_type = id_obj["content_type"].as_str().unwrap();
-            consolidated_paths.insert(
-                file_path.to_owned(),
-                json!({
-                    "id": id,
-                    "files": [
-                        {"uri": format!("https://arweave.net/{}", id), "type": content_type},
-                        {"uri": format!("https://arweave.net/{}/{}", transaction_id, file_path), "type": content_type}
-                    ]
-                }),
-            );
-        }
-        fs::write(
-            log_dir
-                .join(format!("manifest_{}", transaction_id))
-                .with_extension("json"),
-            serde_json::to_string(&json!(consolidated_paths))?,
+    #[tokio::test]
+    async fn test_upload_split_manifests() -> Result<(), Error> {
+        let gateway = mock_server::MockGateway::serve();
+        let mut arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key0.json"),
+            gateway.url.clone(),
         )
         .await?;
-        Ok(())
-    }
+        arweave.max_manifest_paths = 3;
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let statuses: Vec<Status> = [
+            "index.html",
+            "a/1.html",
+            "a/2.html",
+            "a/3.html",
+            "b/1.html",
+            "b/c/1.html",
+            "b/c/2.html",
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, p)| Status {
+            id: Base64(vec![i as u8; 32]),
+            file_path: Some(PathBuf::from("site").join(p)),
+            content_type: "text/html".to_string(),
+            ..Status::default()
+        })
+        .collect();
 
-    //-------------------------
-    // Metadata
-    //-------------------------
+        assert_matches!(
+            arweave.create_manifest(statuses.clone(), None),
+            Err(Error::TooManyManifestPaths { count: 7, max: 3 })
+        );
 
-    pub async fn read_metadata_file(&self, file_path: PathBuf) -> Result<Value, Error> {
-        let data = fs::read_to_string(file_path.clone()).await?;
-        let metadata: Value = serde_json::from_str(&data)?;
-        Ok(json!({"file_path": file_path.display().to_string(), "metadata": metadata}))
-    }
+        arweave.split_manifests = true;
+        let manifest = arweave.create_manifest(statuses, Some("index.html"))?;
+        assert_matches!(
+            arweave
+                .sign_manifest(
+                    &manifest,
+                    (0, 0),
+                    gateway.url.clone(),
+                    gateway.url.clone(),
+                    None
+                )
+                .await,
+            Err(Error::TooManyManifestPaths { .. })
+        );
+        arweave
+            .upload_manifest(
+                manifest,
+                &format!("{}/", temp_dir.0.display()),
+                (0, 0),
+                gateway.url.clone(),
+                gateway.url.clone(),
+                None,
+            )
+            .await?;
 
-    pub async fn update_metadata_file(
-        &self,
-        file_path: PathBuf,
-        mut files_array: Vec<Value>,
-        image_link: Option<String>,
-        animation_url_link: Option<String>,
-    ) -> Result<(), Error> {
-        let data = fs::read_to_string(file_path.clone()).await?;
-        let mut metadata: Value = serde_json::from_str(&data)?;
-        let metadata = metadata.as_object_mut().unwrap();
+        // The top level file, `a/` and `b/` each go in a manifest of their own, keeping their
+        // paths relative to `site`.
+        let posted: Vec<Value> = gateway
+            .posted_transactions()
+            .iter()
+            .map(|t| {
+                let data = Base64::from_str(t["data"].as_str().unwrap()).unwrap();
+                serde_json::from_slice(&data.0).unwrap()
+            })
+            .collect();
+        let ids: Vec<String> = gateway
+            .posted_transactions()
+            .iter()
+            .map(|t| t["id"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(ids.len(), 3);
+        let keys = |manifest: &Value| -> Vec<String> {
+            manifest["paths"]
+                .as_object()
+                .unwrap()
+                .keys()
+                .cloned()
+                .collect()
+        };
+        assert_eq!(keys(&posted[0]), vec!["index.html"]);
+        assert_eq!(posted[0]["index"], json!({"path": "index.html"}));
+        assert_eq!(keys(&posted[1]), vec!["a/1.html", "a/2.html", "a/3.html"]);
+        assert_eq!(
+            keys(&posted[2]),
+            vec!["b/1.html", "b/c/1.html", "b/c/2.html"]
+        );
+        assert!(posted[1].get("index").is_none());
+
+        let index: Value = serde_json::from_str(
+            &fs::read_to_string(temp_dir.0.join(format!("manifest_index_{}.json", ids[0]))).await?,
+        )?;
+        assert_eq!(index, json!({"": ids[0], "a/": ids[1], "b/": ids[2]}));
+
+        // Links go through the manifest each path is in.
+        let links: Value = serde_json::from_str(
+            &fs::read_to_string(temp_dir.0.join(format!("manifest_{}.json", ids[0]))).await?,
+        )?;
+        let entry = &links["site/b/c/1.html"];
+        assert_eq!(entry["manifest_id"], ids[2]);
+        assert_eq!(
+            entry["files"][1]["uri"],
+            format!("https://arweave.net/{}/b/c/1.html", ids[2])
+        );
+        assert_eq!(links["site/index.html"]["manifest_id"], ids[0]);
+        Ok(())
+    }
 
-        if let Some(image_link) = image_link {
-            metadata.insert("image".to_string(), Value::String(image_link));
-        }
+    #[tokio::test]
+    async fn test_upload_split_manifests_keeps_posted_links() -> Result<(), Error> {
+        // The second manifest is rejected without retrying.
+        let posts = Arc::new(AtomicUsize::new(0));
+        let (base_url, requests) = mock_server::serve(move |path| match path {
+            "/tx_anchor" => ("200 OK", "LCwsLCwsLA".to_string()),
+            _ => match posts.fetch_add(1, Ordering::SeqCst) {
+                1 => ("400 Bad Request", "Invalid anchor".to_string()),
+                _ => ("200 OK", "OK".to_string()),
+            },
+        });
+        let mut arweave =
+            Arweave::from_keypair_path(PathBuf::from("tests/fixtures/test_key0.json"), base_url)
+                .await?;
+        arweave.max_manifest_paths = 3;
+        arweave.split_manifests = true;
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let statuses: Vec<Status> = ["index.html", "a/1.html", "a/2.html", "b/1.html"]
+            .iter()
+            .enumerate()
+            .map(|(i, p)| Status {
+                id: Base64(vec![i as u8; 32]),
+                file_path: Some(PathBuf::from("site").join(p)),
+                content_type: "text/html".to_string(),
+                ..Status::default()
+            })
+            .collect();
+        let manifest = arweave.create_manifest(statuses, None)?;
+        let url = Url::from_str("http://url.com").unwrap();
 
-        if let Some(animation_url_link) = animation_url_link {
-            metadata.insert(
-                "animation_url".to_string(),
-                Value::String(animation_url_link),
-            );
-        }
+        let error = arweave
+            .upload_manifest(
+                manifest,
+                &format!("{}/", temp_dir.0.display()),
+                (0, 0),
+                url.clone(),
+                url,
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert!(error.is_stale_anchor());
+
+        // The files are kept with the links of the manifest that was posted.
+        let ids: Vec<String> = requests
+            .try_iter()
+            .filter(|r| r.path == "/tx")
+            .map(|r| r.json()["id"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(ids.len(), 2);
+        let index: Value = serde_json::from_str(
+            &fs::read_to_string(temp_dir.0.join(format!("manifest_index_{}.json", ids[0]))).await?,
+        )?;
+        // The top level file and `a/` fit in the first manifest, `b/` is in the second.
+        assert_eq!(index, json!({"": ids[0], "a/": ids[0]}));
+        let links: Value = serde_json::from_str(
+            &fs::read_to_string(temp_dir.0.join(format!("manifest_{}.json", ids[0]))).await?,
+        )?;
+        let mut keys: Vec<&String> = links.as_object().unwrap().keys().collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            vec!["site/a/1.html", "site/a/2.html", "site/index.html"]
+        );
+        Ok(())
+    }
 
-        let properties = if let Some(properties) = metadata.get_mut("properties") {
-            properties.as_object_mut().unwrap()
-        } else {
-            metadata.insert("properties".to_string(), json!({}));
-            metadata["properties"].as_object_mut().unwrap()
-        };
+    #[tokio::test]
+    async fn test_upload_manifest_from_log_dir() -> Result<(), Error> {
+        let gateway = mock_server::MockGateway::serve();
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key0.json"),
+            gateway.url.clone(),
+        )
+        .await?;
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let log_dir = format!("{}/", temp_dir.0.display());
+        assert_eq!(
+            arweave
+                .upload_manifest_from_log_dir(
+                    &log_dir,
+                    (0, 0),
+                    gateway.url.clone(),
+                    gateway.url.clone(),
+                    None,
+                    None
+                )
+                .await?,
+            format!("No statuses found in {}", log_dir)
+        );
 
-        if let Some(files) = properties.get_mut("files") {
-            files.as_array_mut().unwrap().append(&mut files_array);
-        } else {
-            properties.insert("files".to_string(), Value::Array(files_array));
+        for (i, p) in ["index.html", "a/1.html"].iter().enumerate() {
+            let status = Status {
+                id: Base64(vec![i as u8; 32]),
+                file_path: Some(PathBuf::from("site").join(p)),
+                content_type: "text/html".to_string(),
+                ..Status::default()
+            };
+            arweave
+                .write_status(status, temp_dir.0.clone(), None)
+                .await?;
         }
+        arweave
+            .upload_manifest_from_log_dir(
+                &log_dir,
+                (0, 0),
+                gateway.url.clone(),
+                gateway.url.clone(),
+                None,
+                Some("index.html"),
+            )
+            .await?;
 
-        fs::write(file_path, serde_json::to_string(&json!(metadata))?).await?;
+        let posted = gateway.posted_transactions();
+        assert_eq!(posted.len(), 1);
+        let id = posted[0]["id"].as_str().unwrap();
+        let data = Base64::from_str(posted[0]["data"].as_str().unwrap())?;
+        let manifest: Value = serde_json::from_slice(&data.0)?;
+        assert_eq!(manifest["index"], json!({"path": "index.html"}));
+        assert_eq!(
+            manifest["paths"]["a/1.html"]["id"],
+            Base64(vec![1; 32]).to_string()
+        );
+
+        let links: Value = serde_json::from_str(
+            &fs::read_to_string(temp_dir.0.join(format!("manifest_{}.json", id))).await?,
+        )?;
+        let entry = &links["site/a/1.html"];
+        assert_eq!(entry["id"], Base64(vec![1; 32]).to_string());
+        assert_eq!(
+            entry["files"][1]["uri"],
+            format!("https://arweave.net/{}/a/1.html", id)
+        );
         Ok(())
     }
 
-    pub async fn update_metadata<IP>(
-        &self,
-        paths_iter: IP,
-        manifest_path: PathBuf,
-        image_link_file: bool,
-        update_image_link: bool,
-        update_animation_url_link: bool,
-    ) -> Result<(), Error>
-    where
-        IP: Iterator<Item = PathBuf> + Send,
-    {
-        if manifest_path.exists() {
-            let manifest_id = manifest_path
-                .file_stem()
-                .unwrap()
-                .to_str()
-                .unwrap()
-                .replace("manifest_", "");
-            let data = fs::read_to_string(manifest_path.clone()).await?;
-            let mut manifest: Value = serde_json::from_str(&data)?;
-            let manifest = manifest.as_object_mut().unwrap();
+    #[tokio::test]
+    async fn test_write_metaplex_items_path_keys() -> Result<(), Error> {
+        let arweave = Arweave::default();
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let file_paths: Vec<PathBuf> = (0..3)
+            .map(|i| temp_dir.0.join(format!("{}.json", i)))
+            .collect();
+        for (i, file_path) in file_paths.iter().enumerate() {
+            fs::write(file_path, json!({ "name": format!("#{}", i) }).to_string()).await?;
+        }
 
-            try_join_all(paths_iter.map(|p| {
-                let path_object = manifest.get(&p.display().to_string()).unwrap();
+        // File 0 keyed by `./tests/<TEMP_DIR>/0.json`, as from `arloader upload ./tests/...`, and
+        // file 1 by `elsewhere/tests/<TEMP_DIR>/1.json`, as if uploaded from another directory.
+        let elsewhere_key = Path::new("elsewhere").join(file_paths[1].strip_prefix(".").unwrap());
+        let manifest = arweave.create_manifest(
+            [file_paths[0].clone(), elsewhere_key.clone()]
+                .into_iter()
+                .enumerate()
+                .map(|(i, p)| Status {
+                    id: Base64(vec![i as u8; 32]),
+                    file_path: Some(p),
+                    ..Status::default()
+                })
+                .collect(),
+            None,
+        )?;
+        let manifest_path = arweave
+            .write_manifest(manifest, "manifest-id".to_string(), temp_dir.0.clone())
+            .await?;
 
-                let link = if image_link_file {
-                    format!(
-                        "https://arweave.net/{}/{}",
-                        manifest_id,
-                        &p.display().to_string()
-                    )
-                } else {
-                    format!(
-                        "https://arweave.net/{}",
-                        path_object["id"].as_str().unwrap()
-                    )
-                };
+        // Matched through the absolute path and the only key that ends with the path.
+        let paths = vec![
+            std::env::current_dir()?.join(&file_paths[0]),
+            file_paths[1].clone(),
+        ];
+        let items_path = arweave
+            .write_metaplex_items(
+                paths.clone().into_iter(),
+                manifest_path.clone(),
+                true,
+                false,
+                false,
+            )
+            .await?;
+        let items: Value = serde_json::from_str(&fs::read_to_string(&items_path).await?)?;
+        assert_eq!(items["0"]["name"], "#0");
+        assert_eq!(
+            items["1"]["link"],
+            format!(
+                "https://arweave.net/manifest-id/{}",
+                elsewhere_key.display()
+            )
+        );
 
-                let image_link = if update_image_link {
-                    Some(link.clone())
-                } else {
-                    None
-                };
+        // Links through the manifest are relative to the directory it was created from, the
+        // temp dir when every key is in it.
+        let manifest = arweave.create_manifest(
+            file_paths[..2]
+                .iter()
+                .enumerate()
+                .map(|(i, p)| Status {
+                    id: Base64(vec![i as u8; 32]),
+                    file_path: Some(p.clone()),
+                    ..Status::default()
+                })
+                .collect(),
+            None,
+        )?;
+        let relative_manifest_path = arweave
+            .write_manifest(manifest, "relative-id".to_string(), temp_dir.0.clone())
+            .await?;
+        let items_path = arweave
+            .write_metaplex_items(
+                file_paths[..2].to_vec().into_iter(),
+                relative_manifest_path,
+                true,
+                false,
+                false,
+            )
+            .await?;
+        let items: Value = serde_json::from_str(&fs::read_to_string(&items_path).await?)?;
+        assert_eq!(items["1"]["link"], "https://arweave.net/relative-id/1.json");
 
-                let animation_url_link = if update_animation_url_link {
-                    Some(link)
-                } else {
-                    None
-                };
+        // Files that match the same key don't identify it.
+        let shared = vec![
+            file_paths[1].clone(),
+            std::env::current_dir()?.join(&file_paths[1]),
+        ];
+        assert_matches!(
+            arweave
+                .write_metaplex_items(
+                    shared.clone().into_iter(),
+                    manifest_path.clone(),
+                    false,
+                    false,
+                    false,
+                )
+                .await,
+            Err(Error::MetadataNotInManifest(missing)) if missing == shared
+        );
 
-                let files_array = if image_link_file {
-                    path_object["files"].as_array().unwrap().clone()
-                } else {
-                    vec![path_object["files"]
-                        .as_array()
-                        .unwrap()
-                        .get(0)
-                        .unwrap()
-                        .clone()]
-                };
-                self.update_metadata_file(
-                    p.with_extension("json"),
-                    files_array,
-                    image_link,
-                    animation_url_link,
+        // Metadata without a name is an error instead of a panic.
+        fs::write(&file_paths[0], "{}").await?;
+        assert_matches!(
+            arweave
+                .write_metaplex_items(
+                    vec![file_paths[0].clone()].into_iter(),
+                    manifest_path.clone(),
+                    false,
+                    false,
+                    false,
                 )
-            }))
+                .await,
+            Err(Error::MetadataNameMissing(path)) if path == file_paths[0]
+        );
+        fs::write(&file_paths[0], json!({ "name": "#0" }).to_string()).await?;
+
+        let mut paths = paths;
+        paths.push(file_paths[2].clone());
+        assert_matches!(
+            arweave
+                .write_metaplex_items(
+                    paths.clone().into_iter(),
+                    manifest_path.clone(),
+                    false,
+                    false,
+                    false,
+                )
+                .await,
+            Err(Error::MetadataNotInManifest(missing)) if missing == vec![file_paths[2].clone()]
+        );
+        let items_path = arweave
+            .write_metaplex_items(paths.into_iter(), manifest_path, false, false, true)
             .await?;
-            Ok(())
-        } else {
-            Err(Error::ManifestNotFound)
-        }
+        let items: Value = serde_json::from_str(&fs::read_to_string(&items_path).await?)?;
+        assert_eq!(items.as_object().unwrap().len(), 2);
+        assert_eq!(
+            items["1"]["link"],
+            "https://arweave.net/AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE"
+        );
+        Ok(())
     }
 
-    pub async fn write_metaplex_items<IP>(
-        &self,
-        paths_iter: IP,
-        manifest_path: PathBuf,
-        link_file: bool,
-    ) -> Result<PathBuf, Error>
-    where
-        IP: Iterator<Item = PathBuf> + Send,
-    {
-        if manifest_path.exists() {
-            let manifest_id = manifest_path
-                .file_stem()
-                .unwrap()
-                .to_str()
-                .unwrap()
-                .replace("manifest_", "");
-            let data = fs::read_to_string(manifest_path.clone()).await?;
-            let mut manifest: Value = serde_json::from_str(&data)?;
-            let manifest = manifest.as_object_mut().unwrap();
-
-            let metadata = try_join_all(paths_iter.map(|p| self.read_metadata_file(p))).await?;
-
-            let items = metadata.iter().fold(serde_json::Map::new(), |mut m, meta| {
-                let name = meta["metadata"]["name"].as_str().unwrap();
-                let file_path = meta["file_path"].as_str().unwrap();
-                let id = manifest
-                    .get(file_path)
-                    .unwrap()
-                    .get("id")
-                    .unwrap()
-                    .as_str()
-                    .unwrap();
-                let link = if link_file {
-                    format!("https://arweave.net/{}/{}", manifest_id, file_path)
-                } else {
-                    format!("https://arweave.net/{}", id)
-                };
-                m.insert(
-                    PathBuf::from(file_path)
-                        .file_stem()
-                        .unwrap()
-                        .to_str()
-                        .unwrap()
-                        .to_string(),
-                    json!({"name": name, "link": link, "onChain": false}),
-                );
-                m
-            });
-
-            let manifest_items_path = manifest_path
-                .parent()
-                .unwrap()
-                .to_path_buf()
-                .join(format!("metaplex_items_{}", manifest_id))
-                .with_extension("json");
-            fs::write(&manifest_items_path, serde_json::to_string(&json!(items))?).await?;
-            Ok(manifest_items_path)
-        } else {
-            Err(Error::ManifestNotFound)
+    #[tokio::test]
+    async fn test_update_metadata_missing_files() -> Result<(), Error> {
+        let arweave = Arweave::default();
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let file_paths: Vec<PathBuf> = (0..2)
+            .map(|i| temp_dir.0.join(format!("{}.png", i)))
+            .collect();
+        for (i, file_path) in file_paths.iter().enumerate() {
+            fs::write(file_path, vec![i as u8; 10]).await?;
+            fs::write(
+                file_path.with_extension("json"),
+                json!({ "name": format!("#{}", i) }).to_string(),
+            )
+            .await?;
         }
-    }
-}
+        let manifest = arweave.create_manifest(
+            vec![Status {
+                id: Base64(vec![0; 32]),
+                file_path: Some(file_paths[0].clone()),
+                ..Status::default()
+            }],
+            None,
+        )?;
+        let manifest_path = arweave
+            .write_manifest(manifest, "manifest-id".to_string(), temp_dir.0.clone())
+            .await?;
+        let update_metadata = |paths: Vec<PathBuf>| {
+            arweave.update_metadata(
+                paths.into_iter(),
+                manifest_path.clone(),
+                ImageLink::Id,
+                FilesLink::Both,
+                true,
+                false,
+            )
+        };
 
-#[cfg(test)]
-mod tests {
-    use crate::{
-        error::Error,
-        transaction::{Base64, FromUtf8Strs, Tag},
-        utils::TempDir,
-        Arweave, Status,
-    };
-    use futures::future::try_join_all;
-    use glob::glob;
-    use matches::assert_matches;
-    use std::{path::PathBuf, str::FromStr, time::Instant};
-    use tokio::fs;
-    use url::Url;
+        // Nothing is updated if any of the files isn't in the manifest.
+        assert_matches!(
+            update_metadata(file_paths.clone()).await,
+            Err(Error::FilesNotInManifest(missing)) if missing == vec![file_paths[1].clone()]
+        );
+        let metadata = fs::read_to_string(file_paths[0].with_extension("json")).await?;
+        assert_eq!(
+            serde_json::from_str::<Value>(&metadata)?,
+            json!({"name": "#0"})
+        );
+
+        update_metadata(file_paths[..1].to_vec()).await?;
+        let metadata: Value =
+            serde_json::from_str(&fs::read_to_string(file_paths[0].with_extension("json")).await?)?;
+        assert_eq!(
+            metadata["image"],
+            format!("https://arweave.net/{}", Base64(vec![0; 32]))
+        );
+        assert_eq!(metadata["properties"]["files"].as_array().unwrap().len(), 2);
+        Ok(())
+    }
 
     #[tokio::test]
-    async fn test_cannot_post_unsigned_transaction() -> Result<(), Error> {
+    async fn test_bundle_breakdown() -> Result<(), Error> {
         let arweave = Arweave::from_keypair_path(
-            PathBuf::from(
-                "tests/fixtures/arweave-key-7eV1qae4qVNqsNChg3Scdi-DpOLJPCogct4ixoq1WNg.json",
-            ),
+            PathBuf::from("tests/fixtures/test_key0.json"),
             Url::from_str("http://url.com").unwrap(),
         )
         .await?;
 
-        let file_path = PathBuf::from("tests/fixtures/0.png");
-        let last_tx = Base64::from_str("LCwsLCwsLA")?;
-        let other_tags = vec![Tag::<Base64>::from_utf8_strs("key2", "value2")?];
+        let paths_iter = glob("tests/fixtures/[0-4].png")?
+            .filter_map(Result::ok)
+            .collect();
+        let data_items = arweave
+            .create_data_items_from_file_paths(paths_iter, Vec::new())
+            .await?;
+
+        let data_bytes: usize = data_items.iter().map(|(d, _)| d.data.0.len()).sum();
+        let tags_bytes: usize = data_items
+            .iter()
+            .map(|(d, _)| serialized_tags_len(&d.tags))
+            .sum();
+        let overhead_bytes = BUNDLE_HEADER_LEN
+            + data_items
+                .iter()
+                .map(|(d, _)| BUNDLE_ITEM_HEADER_LEN + d.serialized_len() - d.data.0.len())
+                .sum::<usize>();
+
+        let (bundle, _, breakdown) = arweave.create_bundle_from_data_items(data_items)?;
+        assert_eq!(breakdown.data_bytes, data_bytes as u64);
+        assert_eq!(breakdown.tags_bytes, tags_bytes as u64);
+        assert_eq!(breakdown.overhead_bytes, overhead_bytes as u64);
+        assert_eq!(
+            breakdown.data_bytes + breakdown.overhead_bytes,
+            bundle.len() as u64
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bundle_file() -> Result<(), Error> {
+        let (base_url, _) = mock_server::serve(|_| ("200 OK", "LCwsLCwsLA".to_string()));
+        let arweave =
+            Arweave::from_keypair_path(PathBuf::from("tests/fixtures/test_key0.json"), base_url)
+                .await?;
+        let paths = ["1mb.bin", "0.png", "1.png"]
+            .iter()
+            .map(|p| PathBuf::from("tests/fixtures").join(p))
+            .collect();
+        let data_items = arweave
+            .create_data_items_from_file_paths(paths, Vec::new())
+            .await?;
+
+        let (bundle, manifest, breakdown) =
+            arweave.create_bundle_from_data_items(data_items.clone())?;
+        let (bundle_file, leaves, file_manifest, file_breakdown) =
+            arweave.create_bundle_file_from_data_items(data_items)?;
+        assert_eq!(fs::read(&bundle_file.path).await?, bundle);
+        assert_eq!(bundle_file.len, bundle.len() as u64);
+        assert_eq!((file_manifest, file_breakdown), (manifest, breakdown));
+
+        // Chunks are read from the file, matching the transaction created with the data.
+        let in_memory = arweave.merklize(bundle)?;
+        let path = bundle_file.path.clone();
         let transaction = arweave
-            .create_transaction_from_file_path(
-                file_path,
-                Some(other_tags),
-                Some(last_tx),
-                (0, 0),
-                true,
-            )
+            .create_bundle_file_transaction(bundle_file, leaves, Vec::new(), (100, 10))
             .await?;
+        assert!(transaction.data.0.is_empty());
+        assert_eq!(transaction.data_root, in_memory.data_root);
+        assert_eq!(transaction.data_size, in_memory.data_size);
+        assert!(in_memory.chunks.len() > 1);
+        for i in 0..in_memory.chunks.len() {
+            assert_eq!(transaction.read_chunk(i).await?, in_memory.get_chunk(i)?);
+        }
 
-        let error = arweave.post_transaction(&transaction).await.unwrap_err();
-        assert_matches!(error, Error::UnsignedTransaction);
+        let mut loaded = transaction.clone();
+        loaded.load_data().await?;
+        assert_eq!(loaded.data, in_memory.data);
 
+        // The file is removed once the last transaction referring to it is dropped.
+        drop(transaction);
+        assert!(path.exists());
+        drop(loaded);
+        assert!(!path.exists());
         Ok(())
     }
 
     #[tokio::test]
-    async fn test_create_write_read_status() -> Result<(), Error> {
+    async fn test_recompute_chunks() -> Result<(), Error> {
         let arweave = Arweave::from_keypair_path(
-            PathBuf::from(
-                "tests/fixtures/arweave-key-7eV1qae4qVNqsNChg3Scdi-DpOLJPCogct4ixoq1WNg.json",
-            ),
+            PathBuf::from("tests/fixtures/test_key0.json"),
             Url::from_str("http://url.com").unwrap(),
         )
         .await?;
+        let data = fs::read("tests/fixtures/1mb.bin").await?;
+        let transaction = arweave.sign_transaction(
+            arweave
+                .create_transaction(data, None, Some(Base64(vec![0; 32])), (0, 0), false)
+                .await?,
+        )?;
+        let chunk_bodies = |transaction: &Transaction| -> Result<Vec<String>, Error> {
+            (0..transaction.chunk_count())
+                .map(|i| Ok(serde_json::to_string(&transaction.get_chunk(i)?)?))
+                .collect()
+        };
+        let chunks = chunk_bodies(&transaction)?;
+        assert!(chunks.len() > 1);
 
-        let file_path = PathBuf::from("tests/fixtures/0.png");
-        let last_tx = Base64::from_str("LCwsLCwsLA")?;
-        let other_tags = vec![Tag::<Base64>::from_utf8_strs("key2", "value2")?];
-        let transaction = arweave
-            .create_transaction_from_file_path(
-                file_path.clone(),
-                Some(other_tags),
-                Some(last_tx),
-                (0, 0),
-                true,
-            )
-            .await?;
+        // Chunks and proofs aren't serialized, so they have to be generated again.
+        let json = serde_json::to_string(&transaction)?;
+        let mut loaded: Transaction = serde_json::from_str(&json)?;
+        assert_eq!(loaded.chunk_count(), 0);
+        loaded.recompute_chunks(&arweave.crypto.hasher)?;
+        assert_eq!(chunk_bodies(&loaded)?, chunks);
 
-        let signed_transaction = arweave.sign_transaction(transaction)?;
+        // From the data file for transactions without their data.
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let path = temp_dir.0.join("data.bin");
+        fs::copy("tests/fixtures/1mb.bin", &path).await?;
+        let mut without_data = loaded.clone_with_no_data()?;
+        without_data.data_file = Some(Arc::new(BundleFile::open(path)?));
+        without_data.recompute_chunks(&arweave.crypto.hasher)?;
+        assert_eq!(chunk_bodies(&without_data)?, chunks);
+
+        let mut corrupted: Transaction = serde_json::from_str(&json)?;
+        corrupted.data.0[0] ^= 1;
+        assert_matches!(
+            corrupted.recompute_chunks(&arweave.crypto.hasher),
+            Err(Error::DataRootMismatch(id)) if id == transaction.id.to_string()
+        );
+        assert_eq!(corrupted.chunk_count(), 0);
+        Ok(())
+    }
 
-        let status = Status {
-            id: signed_transaction.id.clone(),
-            reward: signed_transaction.reward,
-            file_path: Some(file_path.clone()),
-            ..Default::default()
+    #[tokio::test]
+    async fn test_deep_hash_preview() -> Result<(), Error> {
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key0.json"),
+            Url::from_str("http://url.com").unwrap(),
+        )
+        .await?;
+        let file_path = PathBuf::from("tests/fixtures/0.png");
+        let user_agent = format!("arloader/{}", env!("CARGO_PKG_VERSION"));
+        let hex = |deep_hash: [u8; 48]| {
+            deep_hash
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>()
         };
 
-        let temp_log_dir = TempDir::from_str("./tests/").await?;
-        let log_dir = temp_log_dir.0.clone();
+        // The User-Agent tag includes the arloader version, so the golden hashes are of the
+        // previews with it pinned to 0.0.0.
+        let preview = arweave
+            .deep_hash_preview(file_path.clone(), Vec::new(), false, (1000, 100))
+            .await?;
+        assert_eq!(preview.deep_hash_item.to_json()[0], "Mg");
+        assert_eq!(preview.unsigned["last_tx"], "");
+        assert_eq!(preview.unsigned["reward"], "1000");
+        let mut transaction: Transaction = serde_json::from_value(preview.unsigned)?;
+        assert_eq!(transaction.tags[0].value.to_utf8_string()?, user_agent);
+        assert_eq!(
+            arweave.crypto.deep_hash(transaction.to_deep_hash_item()?)?,
+            preview.deep_hash
+        );
+        transaction.tags[0] = Tag::from_utf8_strs("User-Agent", "arloader/0.0.0")?;
+        assert_eq!(
+            hex(arweave.crypto.deep_hash(transaction.to_deep_hash_item()?)?),
+            "933f3eeb7fc6cd9400e33ccfb15df1ee4134baf8928141dc38b95ba4a6b1898be7cdc3bea9e85f4984f3cf86333aff9b"
+        );
 
-        arweave
-            .write_status(status.clone(), log_dir.clone(), None)
+        let preview = arweave
+            .deep_hash_preview(file_path.clone(), Vec::new(), true, (0, 0))
             .await?;
+        assert_eq!(preview.deep_hash_item.to_json()[0], "ZGF0YWl0ZW0");
+        let mut data_item: DataItem = serde_json::from_value(preview.unsigned)?;
+        assert!(data_item.anchor.0.is_empty());
+        assert_eq!(data_item.tags[1].value, user_agent);
+        assert_eq!(
+            arweave.crypto.deep_hash(data_item.to_deep_hash_item()?)?,
+            preview.deep_hash
+        );
+        data_item.tags[1] = Tag::from_utf8_strs("User-Agent", "arloader/0.0.0")?;
+        assert_eq!(
+            hex(arweave.crypto.deep_hash(data_item.to_deep_hash_item()?)?),
+            "33d00348f0a19f560b60112f3e726336bd0cab0ec67a0c3c0287fa5af6657af01f607dc3675b932bf496cee87e65f6e4"
+        );
 
-        let read_status = arweave.read_status(file_path, log_dir).await?;
+        // Tags are added as when uploading, with a Content-Type tag replacing the detected one,
+        // moved after the other tags of a data item and left in place in a transaction.
+        let tags_in = vec![
+            Tag::<String>::from_utf8_strs("Content-Type", "text/plain")?,
+            Tag::<String>::from_utf8_strs("key", "value")?,
+        ];
+        let preview = arweave
+            .deep_hash_preview(file_path.clone(), tags_in.clone(), true, (0, 0))
+            .await?;
+        let data_item: DataItem = serde_json::from_value(preview.unsigned)?;
+        let tags: Vec<(String, String)> = data_item
+            .tags
+            .into_iter()
+            .map(|t| (t.name, t.value))
+            .collect();
+        assert_eq!(
+            tags,
+            vec![
+                ("key".to_string(), "value".to_string()),
+                ("Content-Type".to_string(), "text/plain".to_string()),
+                ("User-Agent".to_string(), user_agent.clone()),
+            ]
+        );
+        let preview = arweave
+            .deep_hash_preview(file_path, tags_in, false, (1000, 100))
+            .await?;
+        let transaction: Transaction = serde_json::from_value(preview.unsigned)?;
+        let tags: Vec<(String, String)> = transaction
+            .tags
+            .iter()
+            .map(|t| Ok((t.name.to_utf8_string()?, t.value.to_utf8_string()?)))
+            .collect::<Result<_, Error>>()?;
+        assert_eq!(
+            tags,
+            vec![
+                ("User-Agent".to_string(), user_agent),
+                ("Content-Type".to_string(), "text/plain".to_string()),
+                ("key".to_string(), "value".to_string()),
+            ]
+        );
+        Ok(())
+    }
 
-        assert_eq!(status, read_status);
+    #[tokio::test]
+    async fn test_create_transaction_from_file_path_streamed() -> Result<(), Error> {
+        let (base_url, requests) = mock_server::serve(|_| ("200 OK", "OK".to_string()));
+        let arweave =
+            Arweave::from_keypair_path(PathBuf::from("tests/fixtures/test_key0.json"), base_url)
+                .await?;
+        let last_tx = Base64::from_str("LCwsLCwsLA")?;
+        let tags = vec![Tag::<Base64>::from_utf8_strs("App-Name", "arloader")?];
+
+        for file_path in ["tests/fixtures/1mb.bin", "tests/fixtures/0.png"] {
+            let file_path = PathBuf::from(file_path);
+            let in_memory = arweave
+                .create_transaction_from_file_path(
+                    file_path.clone(),
+                    Some(tags.clone()),
+                    Some(last_tx.clone()),
+                    (100, 10),
+                    true,
+                )
+                .await?;
+            let streamed = arweave
+                .create_transaction_from_file_path_streamed(
+                    file_path.clone(),
+                    Some(tags.clone()),
+                    Some(last_tx.clone()),
+                    (100, 10),
+                    true,
+                )
+                .await?;
+            assert!(streamed.data.0.is_empty());
+            assert_eq!(streamed.data_root, in_memory.data_root);
+            assert_eq!(streamed.data_size, in_memory.data_size);
+            assert_eq!(streamed.tags, in_memory.tags);
+            assert_eq!(streamed.reward, in_memory.reward);
+            for i in 0..in_memory.chunks.len() {
+                assert_eq!(streamed.get_chunk(i)?, in_memory.get_chunk(i)?);
+                assert_eq!(streamed.read_chunk(i).await?, in_memory.get_chunk(i)?);
+            }
 
+            // Chunks are posted from the file, which is left in place.
+            let chunks_total = streamed.chunks.len();
+            let signed_transaction = arweave.sign_transaction(streamed)?;
+            arweave
+                .post_transaction_chunks(signed_transaction, 10, None)
+                .await?;
+            let paths: Vec<String> = (0..chunks_total + 1)
+                .map(|_| requests.recv_timeout(Duration::from_secs(5)).unwrap().path)
+                .collect();
+            assert_eq!(paths[0], "/tx");
+            assert!(paths[1..].iter().all(|p| p == "/chunk"));
+            assert!(file_path.exists());
+        }
         Ok(())
     }
 
@@ -1899,7 +8872,7 @@ mod tests {
         );
 
         let start = Instant::now();
-        let (bundle, _) = arweave.create_bundle_from_data_items(pre_data_items.clone())?;
+        let (bundle, _, _) = arweave.create_bundle_from_data_items(pre_data_items.clone())?;
         let duration = start.elapsed();
         println!("Time elapsed to create bundle: {} ms", duration.as_millis());
 
@@ -1911,6 +8884,50 @@ mod tests {
             duration.as_millis()
         );
 
+        // Peak memory isn't measured since allocations aren't instrumented in tests. What can be
+        // observed is that each data item is handed to the callback as soon as it's verified,
+        // before the next one is read, so that they don't have to be held at once.
+        let start = Instant::now();
+        let item_len = fs::metadata(&file_path).await?.len() as usize;
+        let mut ids = Vec::new();
+        arweave.deserialize_bundle_streaming(&bundle, |data_item| {
+            assert_eq!(data_item.data.0.len(), item_len);
+            ids.push(data_item.id);
+            Ok(())
+        })?;
+        let duration = start.elapsed();
+        println!(
+            "Time elapsed to deserialize streaming: {} ms",
+            duration.as_millis()
+        );
+        assert_eq!(
+            ids,
+            pre_data_items
+                .iter()
+                .map(|(d, _)| d.id.clone())
+                .collect::<Vec<_>>()
+        );
+
+        let mut calls = 0;
+        let stopped = arweave.deserialize_bundle_streaming(&bundle, |_| {
+            calls += 1;
+            Err(Error::BundleVerificationFailed(1))
+        });
+        assert_matches!(stopped, Err(Error::BundleVerificationFailed(1)));
+        assert_eq!(calls, 1);
+
+        // With the id in the last header changed, every data item before it has already been
+        // handed over when it fails to verify.
+        let mut tampered = bundle.clone();
+        tampered[BUNDLE_HEADER_LEN + 100 * BUNDLE_ITEM_HEADER_LEN - 1] ^= 1;
+        let mut calls = 0;
+        let stopped = arweave.deserialize_bundle_streaming(&tampered, |_| {
+            calls += 1;
+            Ok(())
+        });
+        assert_matches!(stopped, Err(Error::InvalidDataItemId));
+        assert_eq!(calls, 99);
+
         let start = Instant::now();
         let post_data_items = arweave.deserialize_bundle(bundle)?;
         let duration = start.elapsed();
@@ -1959,6 +8976,23 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_total_chunks() -> Result<(), Error> {
+        let arweave = Arweave::default();
+        let rebar3 = fs::read("tests/fixtures/rebar3").await?;
+        let one_mb = fs::read("tests/fixtures/1mb.bin").await?;
+
+        for data in [vec![1; 42], vec![0; 256 * 1024 + 1], rebar3, one_mb] {
+            let transaction = arweave.merklize(data)?;
+            assert_eq!(transaction.total_chunks(), transaction.chunks.len());
+            assert_eq!(
+                transaction.clone_with_no_data()?.total_chunks(),
+                transaction.chunks.len()
+            );
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_mime_types() -> Result<(), Error> {
         let file_paths = vec![
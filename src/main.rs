@@ -1,145 +1,401 @@
 use arloader::{
+    audit::AuditSince,
+    bundle::{check_bundle_tag_template, MAX_TAGS},
     commands::*,
-    status::{OutputFormat, StatusCode},
-    transaction::{Base64, FromUtf8Strs, Tag},
-    Arweave,
+    error::Error,
+    plan::UploadPlan,
+    status::{OutputFormat, SortKey, StatusCode},
+    transaction::{Base64, FromUtf8Strs, Tag, Tip},
+    utils::{
+        filter_paths_excluded, filter_paths_newer_than, last_run_time, NewerThan,
+        NFT_ASSET_EXCLUDES,
+    },
+    Arweave, FilesLink, ImageLink, NetworkParams, RetryPolicy, BYTES_PER_MB,
 };
 use clap::{
-    self, crate_description, crate_name, crate_version, value_t, App, AppSettings, Arg, ArgGroup,
-    SubCommand, Values,
+    self, crate_description, crate_name, crate_version, App, AppSettings, Arg, ArgGroup,
+    ArgMatches, SubCommand, Values,
 };
-use std::{fmt::Display, path::PathBuf, str::FromStr};
+use glob::Pattern;
+use std::{env, fmt::Display, path::PathBuf, process::ExitCode, str::FromStr};
+use tokio::fs;
 use url::Url;
+
+/// Environment variable that turns on `--read-only` when set to `1`.
+const READ_ONLY_ENV: &str = "ARLOADER_READ_ONLY";
+
 #[tokio::main]
-async fn main() -> CommandResult {
+async fn main() -> ExitCode {
     env_logger::init();
-    let app_matches = get_app().get_matches();
-    let base_url = app_matches
-        .value_of("base_url")
-        .map(|s| Url::from_str(&s.add_trailing_slash()))
-        .unwrap()
-        .unwrap();
-    let output_format = app_matches
-        .value_of("output_format")
-        .map(get_output_format)
-        .unwrap();
-
-    let (sub_command, arg_matches) = app_matches.subcommand();
-
-    match (sub_command, arg_matches) {
+    match run(&get_app().get_matches()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("Error: {:?}", error);
+            match error {
+                Error::MaxRuntimeExceeded(_) => ExitCode::from(PARTIAL_FAILURE_EXIT_CODE),
+                _ => ExitCode::FAILURE,
+            }
+        }
+    }
+}
+
+/// Runs the subcommand in `app_matches`. Arguments the subcommand needs that are missing or
+/// can't be parsed, which happens if its configuration and this function drift apart, are
+/// returned as errors rather than panicking.
+async fn run(app_matches: &ArgMatches<'_>) -> CommandResult {
+    let base_url = get_base_url(app_matches)?;
+    let output_format = get_output_format(value_of(app_matches, "output_format")?);
+    let pretty_status = app_matches.is_present("pretty_status");
+    let gzip_requests = app_matches.is_present("gzip_requests");
+    let network_params = get_network_params(app_matches)?;
+    let read_only = is_read_only(app_matches);
+
+    match app_matches.subcommand() {
+        ("audit", Some(sub_arg_matches)) => {
+            let arweave = if let Some(ar_keypair_path) = sub_arg_matches.value_of("ar_keypair_path")
+            {
+                Arweave::from_keypair_path(PathBuf::from(ar_keypair_path.expand_tilde()), base_url)
+                    .await?
+            } else {
+                get_arweave(app_matches)?
+            };
+            let wallet_address = sub_arg_matches
+                .value_of("wallet_address")
+                .map(|v| v.to_string());
+            let since = parse_value::<AuditSince>(sub_arg_matches, "since")?;
+            command_audit(
+                &arweave,
+                wallet_address,
+                since.0,
+                sub_arg_matches.is_present("csv"),
+                &output_format,
+            )
+            .await
+        }
         ("balance", Some(sub_arg_matches)) => {
             let arweave = if let Some(ar_keypair_path) = sub_arg_matches.value_of("ar_keypair_path")
             {
                 Arweave::from_keypair_path(PathBuf::from(ar_keypair_path.expand_tilde()), base_url)
-                    .await
-                    .unwrap()
+                    .await?
             } else {
-                Arweave::default()
+                get_arweave(app_matches)?
             };
             let wallet_address = sub_arg_matches
                 .value_of("wallet_address")
                 .map(|v| v.to_string());
             command_wallet_balance(&arweave, wallet_address).await
         }
+        ("bundle-items", Some(sub_arg_matches)) => {
+            let mut arweave = if let Some(ar_keypair_path) =
+                sub_arg_matches.value_of("ar_keypair_path")
+            {
+                Arweave::from_keypair_path(PathBuf::from(ar_keypair_path.expand_tilde()), base_url)
+                    .await?
+            } else {
+                Arweave::from_default_keypair(base_url)?
+            };
+            arweave.pretty_status = pretty_status;
+            arweave.read_only = read_only;
+            arweave.gzip_requests = gzip_requests;
+            arweave.network_params = network_params;
+            let log_dir = sub_arg_matches
+                .value_of("log_dir")
+                .map(|s| s.expand_tilde().add_trailing_slash())
+                .map(PathBuf::from);
+            let retry_policy =
+                RetryPolicy::with_retries(parse_value::<u32>(sub_arg_matches, "retries")?);
+            command_bundle_items(
+                &arweave,
+                paths_of(sub_arg_matches, "file_paths")?,
+                sub_arg_matches.is_present("post"),
+                log_dir,
+                parse_value::<f32>(sub_arg_matches, "reward_multiplier")?,
+                &output_format,
+                parse_value::<usize>(sub_arg_matches, "buffer")?,
+                retry_policy,
+                sub_arg_matches.is_present("skip_balance_check"),
+            )
+            .await
+        }
+        ("bundler-balance", Some(sub_arg_matches)) => {
+            let arweave = if let Some(ar_keypair_path) = sub_arg_matches.value_of("ar_keypair_path")
+            {
+                Arweave::from_keypair_path(PathBuf::from(ar_keypair_path.expand_tilde()), base_url)
+                    .await?
+            } else {
+                get_arweave(app_matches)?
+            };
+            let bundler_url =
+                Url::from_str(&value_of(sub_arg_matches, "bundler_url")?.add_trailing_slash())?;
+            let wallet_address = sub_arg_matches
+                .value_of("wallet_address")
+                .map(|v| v.to_string());
+            command_bundler_balance(&arweave, &bundler_url, wallet_address).await
+        }
+        ("check-local", Some(sub_arg_matches)) => {
+            let log_dir = &value_of(sub_arg_matches, "log_dir")?
+                .expand_tilde()
+                .add_trailing_slash();
+            command_check_local(&get_arweave(app_matches)?, log_dir, &output_format).await
+        }
+        ("debug-deep-hash", Some(sub_arg_matches)) => {
+            let mut arweave = if let Some(ar_keypair_path) =
+                sub_arg_matches.value_of("ar_keypair_path")
+            {
+                Arweave::from_keypair_path(PathBuf::from(ar_keypair_path.expand_tilde()), base_url)
+                    .await?
+            } else {
+                Arweave::from_default_keypair(base_url)?
+            };
+            arweave.network_params = network_params;
+            arweave.tip = sub_arg_matches
+                .value_of("tip")
+                .map(Tip::from_str)
+                .transpose()?;
+            let file_path = PathBuf::from(value_of(sub_arg_matches, "file_path")?.expand_tilde());
+            let reward_mult = parse_value::<f32>(sub_arg_matches, "reward_multiplier")?;
+            let out = sub_arg_matches
+                .value_of("out")
+                .map(|s| PathBuf::from(s.expand_tilde()));
+            command_debug_deep_hash(
+                &arweave,
+                file_path,
+                sub_arg_matches
+                    .values_of("tags")
+                    .map(get_tags_vec)
+                    .unwrap_or_default(),
+                sub_arg_matches.is_present("as_data_item"),
+                reward_mult,
+                out.as_deref(),
+            )
+            .await
+        }
+        ("dedupe-statuses", Some(sub_arg_matches)) => {
+            let log_dir = &value_of(sub_arg_matches, "log_dir")?
+                .expand_tilde()
+                .add_trailing_slash();
+            let apply = sub_arg_matches.is_present("apply");
+            let arweave = get_arweave(app_matches)?;
+            command_dedupe_statuses(&arweave, log_dir, apply).await
+        }
         ("estimate", Some(sub_arg_matches)) => {
-            let paths_iter = sub_arg_matches
-                .values_of("file_paths")
-                .map(|v| v.into_iter().map(PathBuf::from))
-                .unwrap();
-            let reward_mult = value_t!(sub_arg_matches.value_of("reward_multiplier"), f32).unwrap();
+            let reward_mult = parse_value::<f32>(sub_arg_matches, "reward_multiplier")?;
             let with_sol = sub_arg_matches.is_present("with_sol");
-            let bundle_size = (value_t!(sub_arg_matches.value_of("bundle_size"), f64).unwrap()
-                * 1_000_000.0) as u64;
+            if let Some(log_dir) = sub_arg_matches.value_of("from_log_dir") {
+                return command_estimate_from_log_dir(
+                    &get_arweave(app_matches)?,
+                    &log_dir.expand_tilde().add_trailing_slash(),
+                    reward_mult,
+                    with_sol,
+                    &output_format,
+                )
+                .await;
+            }
+            let paths_iter = get_paths_vec(sub_arg_matches, None, &[]).await?.into_iter();
+            let bundle_size = get_bundle_size(sub_arg_matches, &network_params)?;
             let no_bundle = sub_arg_matches.is_present("no_bundle");
             command_get_cost(
-                &Arweave::default(),
+                &get_arweave(app_matches)?,
                 paths_iter,
                 reward_mult,
                 with_sol,
                 bundle_size,
                 no_bundle,
+                &output_format,
             )
             .await
         }
         ("get-status", Some(sub_arg_matches)) => {
-            let id = sub_arg_matches.value_of("id").unwrap();
-            command_get_status(&Arweave::default(), id, &output_format).await
+            let id = value_of(sub_arg_matches, "id")?;
+            command_get_status(&get_arweave(app_matches)?, id, &output_format).await
         }
         ("get-transaction", Some(sub_arg_matches)) => {
-            let id = sub_arg_matches.value_of("id").unwrap();
-            command_get_transaction(&Arweave::default(), id).await
+            let id = value_of(sub_arg_matches, "id")?;
+            command_get_transaction(&get_arweave(app_matches)?, id).await
         }
         ("list-status", Some(sub_arg_matches)) => {
-            let log_dir = &sub_arg_matches
-                .value_of("log_dir")
-                .unwrap()
+            let log_dir = &value_of(sub_arg_matches, "log_dir")?
                 .expand_tilde()
                 .add_trailing_slash();
-            let paths_iter = sub_arg_matches
-                .values_of("file_paths")
-                .map(|v| v.into_iter().map(PathBuf::from));
             let no_bundle = sub_arg_matches.is_present("no_bundle");
 
             let statuses = sub_arg_matches
                 .values_of("statuses")
                 .map(get_status_codes_vec);
 
-            let max_confirms = value_t!(sub_arg_matches.value_of("max_confirms"), u64).ok();
+            let max_confirms = parse_optional::<u64>(sub_arg_matches, "max_confirms")?;
+            let sort_by = sub_arg_matches
+                .value_of("sort_by")
+                .map(SortKey::from_str)
+                .transpose()?;
+            let desc = sub_arg_matches.is_present("desc");
+            let limit = parse_optional::<usize>(sub_arg_matches, "limit")?;
             if no_bundle {
                 command_list_statuses(
-                    &Arweave::default(),
-                    paths_iter.unwrap(),
+                    &get_arweave(app_matches)?,
+                    paths_of(sub_arg_matches, "file_paths")?,
                     log_dir,
                     statuses,
                     max_confirms,
+                    sort_by,
+                    desc,
+                    limit,
                     &output_format,
                 )
                 .await
             } else {
                 command_list_bundle_statuses(
-                    &Arweave::default(),
+                    &get_arweave(app_matches)?,
                     log_dir,
                     statuses,
                     max_confirms,
+                    sort_by,
+                    desc,
+                    limit,
                     &output_format,
                 )
                 .await
             }
         }
-        ("pending", Some(_)) => command_get_pending_count(&Arweave::default()).await,
+        ("missing", Some(sub_arg_matches)) => {
+            let paths_iter = paths_of(sub_arg_matches, "file_paths")?;
+            let log_dir = PathBuf::from(
+                &value_of(sub_arg_matches, "log_dir")?
+                    .expand_tilde()
+                    .add_trailing_slash(),
+            );
+            command_missing(
+                &get_arweave(app_matches)?,
+                paths_iter,
+                log_dir,
+                &output_format,
+            )
+            .await
+        }
+        ("network-info", Some(_)) => {
+            let arweave = get_arweave(app_matches)?;
+            command_network_info(&arweave, &output_format).await
+        }
+        ("pending", Some(_)) => command_get_pending_count(&get_arweave(app_matches)?).await,
+        ("plan", Some(sub_arg_matches)) => {
+            let paths_vec = get_paths_vec(sub_arg_matches, None, &[]).await?;
+            let bundle_size = get_bundle_size(sub_arg_matches, &network_params)?;
+            let reward_mult = parse_value::<f32>(sub_arg_matches, "reward_multiplier")?;
+            let out = sub_arg_matches
+                .value_of("out")
+                .map(|s| PathBuf::from(s.expand_tilde()));
+            command_plan(
+                &get_arweave(app_matches)?,
+                paths_vec.into_iter(),
+                bundle_size,
+                reward_mult,
+                out.as_deref(),
+            )
+            .await
+        }
+        ("queue", Some(sub_arg_matches)) => match sub_arg_matches.subcommand() {
+            ("add", Some(queue_matches)) => {
+                let paths_vec = get_paths_vec(queue_matches, None, &[]).await?;
+                command_queue_add(&get_queue_dir(queue_matches)?, paths_vec.into_iter()).await
+            }
+            ("retry-failed", Some(queue_matches)) => {
+                command_queue_retry_failed(&get_queue_dir(queue_matches)?).await
+            }
+            ("run", Some(queue_matches)) => {
+                let mut arweave =
+                    if let Some(ar_keypair_path) = queue_matches.value_of("ar_keypair_path") {
+                        Arweave::from_keypair_path(
+                            PathBuf::from(ar_keypair_path.expand_tilde()),
+                            base_url,
+                        )
+                        .await?
+                    } else {
+                        Arweave::from_default_keypair(base_url)?
+                    };
+                arweave.pretty_status = pretty_status;
+                arweave.read_only = read_only;
+                arweave.gzip_requests = gzip_requests;
+                arweave.network_params = network_params;
+                let retry_policy =
+                    RetryPolicy::with_retries(parse_value::<u32>(queue_matches, "retries")?);
+                command_queue_run(
+                    &arweave,
+                    &get_queue_dir(queue_matches)?,
+                    queue_matches.values_of("tags").map(get_tags_vec),
+                    get_bundle_size(queue_matches, &network_params)?,
+                    parse_value::<f32>(queue_matches, "reward_multiplier")?,
+                    &output_format,
+                    parse_value::<usize>(queue_matches, "buffer")?,
+                    retry_policy,
+                    parse_optional::<u64>(queue_matches, "max_runtime")?,
+                )
+                .await
+                .map(|_| ())
+            }
+            ("status", Some(queue_matches)) => {
+                command_queue_status(&get_queue_dir(queue_matches)?, &output_format).await
+            }
+            (sub_command, _) => Err(Error::UnknownSubcommand(format!("queue {}", sub_command))),
+        },
+        ("repost", Some(sub_arg_matches)) => {
+            let log_dir = PathBuf::from(
+                &value_of(sub_arg_matches, "log_dir")?
+                    .expand_tilde()
+                    .add_trailing_slash(),
+            );
+            let buffer = parse_value::<usize>(sub_arg_matches, "buffer")?;
+            let arweave = get_arweave(app_matches)?;
+            command_repost(&arweave, &log_dir, buffer).await
+        }
         ("reupload", Some(sub_arg_matches)) => {
-            let arweave = if let Some(ar_keypair_path) = sub_arg_matches.value_of("ar_keypair_path")
+            let mut arweave = if let Some(ar_keypair_path) =
+                sub_arg_matches.value_of("ar_keypair_path")
             {
                 Arweave::from_keypair_path(PathBuf::from(ar_keypair_path.expand_tilde()), base_url)
-                    .await
-                    .unwrap()
+                    .await?
             } else {
-                Arweave::default()
+                Arweave::from_default_keypair(base_url)?
+            };
+            arweave.pretty_status = pretty_status;
+            arweave.read_only = read_only;
+            arweave.gzip_requests = gzip_requests;
+            arweave.network_params = network_params;
+            arweave.item_cache = sub_arg_matches
+                .value_of("cache_items")
+                .map(|s| PathBuf::from(s.expand_tilde()));
+            let verify_failures = sub_arg_matches
+                .value_of("from_failures")
+                .map(|s| PathBuf::from(s.expand_tilde()));
+            let paths_iter = match verify_failures {
+                Some(_) => Vec::new().into_iter(),
+                None => paths_of(sub_arg_matches, "file_paths")?,
             };
-            let paths_iter = sub_arg_matches
-                .values_of("file_paths")
-                .map(|v| v.into_iter().map(PathBuf::from))
-                .unwrap();
             let log_dir = PathBuf::from(
-                &sub_arg_matches
-                    .value_of("log_dir")
-                    .unwrap()
+                &value_of(sub_arg_matches, "log_dir")?
                     .expand_tilde()
                     .add_trailing_slash(),
             );
 
-            let reward_mult = value_t!(sub_arg_matches.value_of("reward_multiplier"), f32).unwrap();
+            let reward_mult = parse_value::<f32>(sub_arg_matches, "reward_multiplier")?;
             let no_bundle = sub_arg_matches.is_present("no_bundle");
-            let bundle_size = (value_t!(sub_arg_matches.value_of("bundle_size"), f64).unwrap()
-                * 1_000_000.0) as u64;
+            let bundle_size = get_bundle_size(sub_arg_matches, &network_params)?;
             let statuses = sub_arg_matches
                 .values_of("statuses")
                 .map(get_status_codes_vec);
-            let max_confirms = value_t!(sub_arg_matches.value_of("max_confirms"), u64).ok();
-            let buffer = value_t!(sub_arg_matches.value_of("buffer"), usize).unwrap();
+            let max_confirms = parse_optional::<u64>(sub_arg_matches, "max_confirms")?;
+            let buffer = parse_value::<usize>(sub_arg_matches, "buffer")?;
+            if !no_bundle {
+                check_upload_memory(
+                    &SystemMemory,
+                    bundle_size,
+                    buffer,
+                    sub_arg_matches.is_present("ignore_memory_check"),
+                )?;
+            }
             let sol_keypair_path = sub_arg_matches
                 .value_of("sol_keypair_path")
-                .map(PathBuf::from);
+                .map(|s| PathBuf::from(s.expand_tilde()));
+            let max_runtime = parse_optional::<u64>(sub_arg_matches, "max_runtime")?;
 
             if no_bundle {
                 command_reupload(
@@ -153,6 +409,8 @@ async fn main() -> CommandResult {
                     &output_format,
                     buffer,
                     sol_keypair_path,
+                    verify_failures,
+                    max_runtime,
                 )
                 .await
             } else {
@@ -168,127 +426,182 @@ async fn main() -> CommandResult {
                     output_format,
                     buffer,
                     sol_keypair_path,
+                    sub_arg_matches.is_present("purge_superseded"),
+                    verify_failures,
+                    max_runtime,
                 )
                 .await
             }
         }
         ("status-report", Some(sub_arg_matches)) => {
-            let log_dir = &sub_arg_matches
-                .value_of("log_dir")
-                .unwrap()
+            let log_dir = &value_of(sub_arg_matches, "log_dir")?
                 .expand_tilde()
                 .add_trailing_slash();
-            let paths_iter = sub_arg_matches
-                .values_of("file_paths")
-                .map(|v| v.into_iter().map(PathBuf::from));
             let no_bundle = sub_arg_matches.is_present("no_bundle");
 
             if no_bundle {
-                command_status_report(&Arweave::default(), paths_iter.unwrap(), log_dir).await
+                command_status_report(
+                    &get_arweave(app_matches)?,
+                    paths_of(sub_arg_matches, "file_paths")?,
+                    log_dir,
+                )
+                .await
             } else {
-                println!("Status report not implemented for bundles yet.");
-                Ok(())
+                command_bundle_status_report(&get_arweave(app_matches)?, log_dir, &output_format)
+                    .await
             }
         }
         ("update-metadata", Some(sub_arg_matches)) => {
-            let paths_iter = sub_arg_matches
-                .values_of("file_paths")
-                .map(|v| v.into_iter().map(PathBuf::from))
-                .unwrap();
-            let manifest_path = sub_arg_matches
-                .value_of("manifest_path")
-                .map(|s| s.expand_tilde())
-                .map(PathBuf::from)
-                .unwrap();
-            let link_file = sub_arg_matches.is_present("link_file");
+            let paths_iter = paths_of(sub_arg_matches, "file_paths")?;
+            let manifest_path =
+                PathBuf::from(value_of(sub_arg_matches, "manifest_path")?.expand_tilde());
+            let image_link = parse_value::<ImageLink>(sub_arg_matches, "image_link")?;
+            let files_link = parse_value::<FilesLink>(sub_arg_matches, "files_link")?;
             let update_image = sub_arg_matches.is_present("update_image");
             let update_animation_url = sub_arg_matches.is_present("update_animation_url");
             command_update_metadata(
-                &Arweave::default(),
+                &get_arweave(app_matches)?,
                 paths_iter,
                 manifest_path,
-                link_file,
+                image_link,
+                files_link,
                 update_image,
                 update_animation_url,
             )
             .await
         }
         ("update-nft-status", Some(sub_arg_matches)) => {
-            let log_dir = &sub_arg_matches
-                .value_of("log_dir")
-                .unwrap()
+            let log_dir = &value_of(sub_arg_matches, "log_dir")?
                 .expand_tilde()
                 .add_trailing_slash();
-            let buffer = value_t!(sub_arg_matches.value_of("buffer"), usize).unwrap();
-            command_update_nft_statuses(&Arweave::default(), log_dir, &output_format, buffer).await
+            let buffer = parse_value::<usize>(sub_arg_matches, "buffer")?;
+            let arweave = get_arweave(app_matches)?;
+            command_update_nft_statuses(&arweave, log_dir, &output_format, buffer).await
         }
         ("update-status", Some(sub_arg_matches)) => {
             let log_dir = PathBuf::from(
-                &sub_arg_matches
-                    .value_of("log_dir")
-                    .unwrap()
+                &value_of(sub_arg_matches, "log_dir")?
                     .expand_tilde()
                     .add_trailing_slash(),
             );
             let no_bundle = sub_arg_matches.is_present("no_bundle");
-            let buffer = value_t!(sub_arg_matches.value_of("buffer"), usize).unwrap();
+            let buffer = parse_value::<usize>(sub_arg_matches, "buffer")?;
+            let min_confirms = Some(parse_value::<u64>(sub_arg_matches, "min_confirms")?);
+            let arweave = get_arweave(app_matches)?;
 
             match no_bundle {
                 true => {
-                    let paths_iter = sub_arg_matches
-                        .values_of("file_paths")
-                        .map(|v| v.into_iter().map(PathBuf::from))
-                        .unwrap();
+                    let paths_iter = paths_of(sub_arg_matches, "file_paths")?;
                     command_update_statuses(
-                        &Arweave::default(),
+                        &arweave,
                         paths_iter,
                         log_dir,
                         &output_format,
                         buffer,
+                        min_confirms,
                     )
                     .await
                 }
                 false => {
+                    let index_hint_url = match sub_arg_matches.is_present("send_hints") {
+                        true => sub_arg_matches
+                            .value_of("index_hint_url")
+                            .map(Url::from_str)
+                            .transpose()?,
+                        false => None,
+                    };
                     command_update_bundle_statuses(
-                        &Arweave::default(),
+                        &arweave,
                         log_dir,
                         &output_format,
                         buffer,
+                        index_hint_url,
+                        min_confirms,
                     )
                     .await
                 }
             }
         }
         ("upload", Some(sub_arg_matches)) => {
-            let arweave = if let Some(ar_keypair_path) = sub_arg_matches.value_of("ar_keypair_path")
+            let mut arweave = if let Some(ar_keypair_path) =
+                sub_arg_matches.value_of("ar_keypair_path")
             {
                 Arweave::from_keypair_path(PathBuf::from(ar_keypair_path.expand_tilde()), base_url)
-                    .await
-                    .unwrap()
+                    .await?
             } else {
-                Arweave::default()
+                Arweave::from_default_keypair(base_url)?
             };
-            let paths_iter = sub_arg_matches
-                .values_of("file_paths")
-                .map(|v| v.into_iter().map(PathBuf::from))
-                .unwrap();
+            arweave.pretty_status = pretty_status;
+            arweave.read_only = read_only;
+            arweave.gzip_requests = gzip_requests;
+            arweave.network_params = network_params;
+            arweave.item_cache = sub_arg_matches
+                .value_of("cache_items")
+                .map(|s| PathBuf::from(s.expand_tilde()));
+            arweave.tip = sub_arg_matches
+                .value_of("tip")
+                .map(Tip::from_str)
+                .transpose()?;
+            arweave.strict_reward = sub_arg_matches.is_present("strict_reward");
+            arweave.anchor_from_hash = sub_arg_matches.is_present("anchor_from_hash");
+            arweave.bundle_tags = sub_arg_matches
+                .values_of("bundle_tags")
+                .map(get_tags_vec)
+                .unwrap_or_default();
             let log_dir = sub_arg_matches
                 .value_of("log_dir")
                 .map(|s| s.expand_tilde().add_trailing_slash())
                 .map(PathBuf::from);
-            let reward_mult = value_t!(sub_arg_matches.value_of("reward_multiplier"), f32).unwrap();
-            let bundle_size = (value_t!(sub_arg_matches.value_of("bundle_size"), f64).unwrap()
-                * 1_000_000.0) as u64;
+            let plan = match sub_arg_matches.value_of("plan") {
+                Some(plan_path) => Some(UploadPlan::from_str(
+                    &fs::read_to_string(plan_path.expand_tilde()).await?,
+                )?),
+                None => None,
+            };
+            let paths_vec = if plan.is_some() {
+                Vec::new()
+            } else {
+                get_paths_vec(sub_arg_matches, log_dir.as_ref(), &[]).await?
+            };
+            if plan.is_none() && paths_vec.is_empty() {
+                print_note(
+                    &output_format,
+                    "No files left to upload after applying --exclude and --newer-than.",
+                );
+                return Ok(());
+            }
+            let paths_iter = paths_vec.into_iter();
+            let reward_mult = parse_value::<f32>(sub_arg_matches, "reward_multiplier")?;
+            let bundle_size = match &plan {
+                Some(plan) => plan.bundle_size,
+                None => get_bundle_size(sub_arg_matches, &network_params)?,
+            };
             let with_sol = sub_arg_matches.is_present("with_sol");
             let no_bundle = sub_arg_matches.is_present("no_bundle");
-            let buffer = value_t!(sub_arg_matches.value_of("buffer"), usize).unwrap();
+            let buffer = parse_value::<usize>(sub_arg_matches, "buffer")?;
+            if !no_bundle {
+                check_upload_memory(
+                    &SystemMemory,
+                    bundle_size,
+                    buffer,
+                    sub_arg_matches.is_present("ignore_memory_check"),
+                )?;
+            }
             let sol_keypair_path = sub_arg_matches
                 .value_of("sol_keypair_path")
-                .map(PathBuf::from);
+                .map(|s| PathBuf::from(s.expand_tilde()));
+            let max_runtime = parse_optional::<u64>(sub_arg_matches, "max_runtime")?;
+            let retry_policy =
+                RetryPolicy::with_retries(parse_value::<u32>(sub_arg_matches, "retries")?);
+            let skip_balance_check = sub_arg_matches.is_present("skip_balance_check");
+            let bundle_manifest = sub_arg_matches.is_present("bundle_manifest");
 
             match (with_sol, no_bundle) {
                 (false, false) => {
-                    let path_chunks = arweave.chunk_file_paths(paths_iter, bundle_size)?;
+                    let path_chunks = match &plan {
+                        Some(plan) => plan.path_chunks().await?,
+                        None => arweave.chunk_file_paths(paths_iter, bundle_size)?,
+                    };
                     command_upload_bundles(
                         &arweave,
                         path_chunks,
@@ -297,23 +610,31 @@ async fn main() -> CommandResult {
                         reward_mult,
                         &output_format,
                         buffer,
+                        retry_policy,
+                        skip_balance_check,
+                        max_runtime,
+                        bundle_manifest,
                     )
                     .await
+                    .map(|_| ())
                 }
-                (false, true) => {
-                    command_upload(
-                        &arweave,
-                        paths_iter,
-                        log_dir,
-                        sub_arg_matches.values_of("tags").map(get_tags_vec),
-                        reward_mult,
-                        &output_format,
-                        buffer,
-                    )
-                    .await
-                }
+                (false, true) => command_upload(
+                    &arweave,
+                    paths_iter,
+                    log_dir,
+                    sub_arg_matches.values_of("tags").map(get_tags_vec),
+                    reward_mult,
+                    &output_format,
+                    buffer,
+                    max_runtime,
+                )
+                .await
+                .map(|_| ()),
                 (true, false) => {
-                    let path_chunks = arweave.chunk_file_paths(paths_iter, bundle_size)?;
+                    let path_chunks = match &plan {
+                        Some(plan) => plan.path_chunks().await?,
+                        None => arweave.chunk_file_paths(paths_iter, bundle_size)?,
+                    };
                     command_upload_bundles_with_sol(
                         &arweave,
                         path_chunks,
@@ -322,50 +643,78 @@ async fn main() -> CommandResult {
                         reward_mult,
                         &output_format,
                         buffer,
-                        sol_keypair_path.unwrap(),
-                    )
-                    .await
-                }
-                (true, true) => {
-                    command_upload_with_sol(
-                        &arweave,
-                        paths_iter,
-                        log_dir,
-                        sub_arg_matches.values_of("tags").map(get_tags_vec),
-                        reward_mult,
-                        &output_format,
-                        buffer,
-                        sol_keypair_path.unwrap(),
+                        retry_policy,
+                        skip_balance_check,
+                        sol_keypair_path.ok_or_else(|| missing("sol_keypair_path"))?,
+                        max_runtime,
+                        bundle_manifest,
                     )
                     .await
+                    .map(|_| ())
                 }
+                (true, true) => command_upload_with_sol(
+                    &arweave,
+                    paths_iter,
+                    log_dir,
+                    sub_arg_matches.values_of("tags").map(get_tags_vec),
+                    reward_mult,
+                    &output_format,
+                    buffer,
+                    sol_keypair_path.ok_or_else(|| missing("sol_keypair_path"))?,
+                    max_runtime,
+                )
+                .await
+                .map(|_| ()),
             }
         }
         ("upload-nfts", Some(sub_arg_matches)) => {
-            let arweave = if let Some(ar_keypair_path) = sub_arg_matches.value_of("ar_keypair_path")
+            let mut arweave = if let Some(ar_keypair_path) =
+                sub_arg_matches.value_of("ar_keypair_path")
             {
                 Arweave::from_keypair_path(PathBuf::from(ar_keypair_path.expand_tilde()), base_url)
-                    .await
-                    .unwrap()
+                    .await?
             } else {
-                Arweave::default()
+                Arweave::from_default_keypair(base_url)?
             };
-            let paths_iter = sub_arg_matches
-                .values_of("file_paths")
-                .map(|v| v.into_iter().map(PathBuf::from))
-                .unwrap();
+            arweave.pretty_status = pretty_status;
+            arweave.read_only = read_only;
+            arweave.gzip_requests = gzip_requests;
+            arweave.network_params = network_params;
+            arweave.strict_paths = sub_arg_matches.is_present("strict_paths");
+            arweave.manifest_base_dir = sub_arg_matches
+                .value_of("manifest_base_dir")
+                .map(|s| PathBuf::from(s.expand_tilde()));
+            arweave.strict_reward = sub_arg_matches.is_present("strict_reward");
+            arweave.anchor_from_hash = sub_arg_matches.is_present("anchor_from_hash");
+            arweave.bundle_tags = sub_arg_matches
+                .values_of("bundle_tags")
+                .map(get_tags_vec)
+                .unwrap_or_default();
             let log_dir = sub_arg_matches
                 .value_of("log_dir")
                 .map(|s| s.expand_tilde().add_trailing_slash())
                 .map(PathBuf::from);
-            let reward_mult = value_t!(sub_arg_matches.value_of("reward_multiplier"), f32).unwrap();
-            let bundle_size = (value_t!(sub_arg_matches.value_of("bundle_size"), f64).unwrap()
-                * 1_000_000.0) as u64;
-            let buffer = value_t!(sub_arg_matches.value_of("buffer"), usize).unwrap();
+            let paths_iter = get_paths_vec(sub_arg_matches, log_dir.as_ref(), &NFT_ASSET_EXCLUDES)
+                .await?
+                .into_iter();
+            let reward_mult = parse_value::<f32>(sub_arg_matches, "reward_multiplier")?;
+            let bundle_size = get_bundle_size(sub_arg_matches, &network_params)?;
+            let buffer = parse_value::<usize>(sub_arg_matches, "buffer")?;
             let link_file = sub_arg_matches.is_present("link_file");
             let sol_keypair_path = sub_arg_matches
                 .value_of("sol_keypair_path")
-                .map(PathBuf::from);
+                .map(|s| PathBuf::from(s.expand_tilde()));
+            let no_bundle = sub_arg_matches.is_present("no_bundle");
+            let skip_balance_check = sub_arg_matches.is_present("skip_balance_check");
+            let truncate_names = sub_arg_matches.is_present("truncate_names");
+            if !no_bundle {
+                check_upload_memory(
+                    &SystemMemory,
+                    bundle_size,
+                    buffer,
+                    sub_arg_matches.is_present("ignore_memory_check"),
+                )?;
+            }
 
             command_upload_nfts(
                 &arweave,
@@ -377,45 +726,142 @@ async fn main() -> CommandResult {
                 buffer,
                 sol_keypair_path,
                 link_file,
+                no_bundle,
+                skip_balance_check,
+                truncate_names,
+                parse_optional::<u64>(sub_arg_matches, "max_runtime")?,
             )
             .await
         }
-        ("upload-manifest", Some(sub_arg_matches)) => {
-            let arweave = if let Some(ar_keypair_path) = sub_arg_matches.value_of("ar_keypair_path")
+        ("upload-one", Some(sub_arg_matches)) => {
+            let mut arweave = if let Some(ar_keypair_path) =
+                sub_arg_matches.value_of("ar_keypair_path")
             {
                 Arweave::from_keypair_path(PathBuf::from(ar_keypair_path.expand_tilde()), base_url)
-                    .await
-                    .unwrap()
+                    .await?
             } else {
-                Arweave::default()
+                Arweave::from_default_keypair(base_url)?
             };
-            let log_dir = &sub_arg_matches
+            arweave.pretty_status = pretty_status;
+            arweave.read_only = read_only;
+            arweave.gzip_requests = gzip_requests;
+            arweave.network_params = network_params;
+            arweave.tip = sub_arg_matches
+                .value_of("tip")
+                .map(Tip::from_str)
+                .transpose()?;
+            let file_path = PathBuf::from(value_of(sub_arg_matches, "file_path")?.expand_tilde());
+            let log_dir = sub_arg_matches
                 .value_of("log_dir")
-                .unwrap()
+                .map(|s| s.expand_tilde().add_trailing_slash())
+                .map(PathBuf::from);
+            let reward_mult = parse_value::<f32>(sub_arg_matches, "reward_multiplier")?;
+            let sol_keypair_path = match sub_arg_matches.is_present("with_sol") {
+                true => sub_arg_matches
+                    .value_of("sol_keypair_path")
+                    .map(|s| PathBuf::from(s.expand_tilde())),
+                false => None,
+            };
+            command_upload_one(
+                &arweave,
+                file_path,
+                log_dir,
+                sub_arg_matches
+                    .values_of("tags")
+                    .map(get_tags_vec)
+                    .unwrap_or_default(),
+                reward_mult,
+                sub_arg_matches.is_present("no_bundle"),
+                sol_keypair_path,
+            )
+            .await
+        }
+        ("upload-manifest", Some(sub_arg_matches)) => {
+            let mut arweave = if let Some(ar_keypair_path) =
+                sub_arg_matches.value_of("ar_keypair_path")
+            {
+                Arweave::from_keypair_path(PathBuf::from(ar_keypair_path.expand_tilde()), base_url)
+                    .await?
+            } else {
+                Arweave::from_default_keypair(base_url)?
+            };
+            arweave.pretty_status = pretty_status;
+            arweave.read_only = read_only;
+            arweave.gzip_requests = gzip_requests;
+            arweave.network_params = network_params;
+            arweave.strict_paths = sub_arg_matches.is_present("strict_paths");
+            arweave.manifest_base_dir = sub_arg_matches
+                .value_of("manifest_base_dir")
+                .map(|s| PathBuf::from(s.expand_tilde()));
+            arweave.manifest_fallback = sub_arg_matches.value_of("fallback").map(String::from);
+            arweave.max_manifest_paths =
+                parse_value::<usize>(sub_arg_matches, "max_manifest_paths")?;
+            arweave.split_manifests = sub_arg_matches.is_present("split_manifests");
+            let log_dir = &value_of(sub_arg_matches, "log_dir")?
                 .expand_tilde()
                 .add_trailing_slash();
-            let reward_mult = value_t!(sub_arg_matches.value_of("reward_multiplier"), f32).unwrap();
+            let reward_mult = parse_value::<f32>(sub_arg_matches, "reward_multiplier")?;
             let sol_key_pair_path = sub_arg_matches
                 .value_of("sol_keypair_path")
                 .map(|s| s.expand_tilde());
 
-            command_upload_manifest(&arweave, log_dir, reward_mult, sol_key_pair_path).await
+            command_upload_manifest(
+                &arweave,
+                log_dir,
+                reward_mult,
+                sol_key_pair_path,
+                sub_arg_matches.value_of("index"),
+                sub_arg_matches.is_present("no_bundle"),
+            )
+            .await
+        }
+        ("verify-bundle-status", Some(sub_arg_matches)) => {
+            let path = PathBuf::from(value_of(sub_arg_matches, "path")?.expand_tilde());
+            let arweave = get_arweave(app_matches)?;
+            command_verify_bundle_status(&arweave, path, &output_format).await
+        }
+        ("verify-uploads", Some(sub_arg_matches)) => {
+            let paths_iter = paths_of(sub_arg_matches, "file_paths")?;
+            let log_dir = &value_of(sub_arg_matches, "log_dir")?
+                .expand_tilde()
+                .add_trailing_slash();
+            let buffer = parse_value::<usize>(sub_arg_matches, "buffer")?;
+            command_verify_uploads(
+                &get_arweave(app_matches)?,
+                paths_iter,
+                log_dir,
+                buffer,
+                &output_format,
+            )
+            .await
+        }
+        ("wallet-generate", Some(sub_arg_matches)) => {
+            let output_path =
+                PathBuf::from(value_of(sub_arg_matches, "output_path")?.expand_tilde());
+            command_wallet_generate(&output_path).await
         }
         ("write-metaplex-items", Some(sub_arg_matches)) => {
-            let paths_iter = sub_arg_matches
-                .values_of("file_paths")
-                .map(|v| v.into_iter().map(PathBuf::from))
-                .unwrap();
-            let manifest_path = sub_arg_matches
-                .value_of("manifest_path")
-                .map(|s| s.expand_tilde())
-                .map(PathBuf::from)
-                .unwrap();
+            let paths_iter = paths_of(sub_arg_matches, "file_paths")?;
+            let manifest_path =
+                PathBuf::from(value_of(sub_arg_matches, "manifest_path")?.expand_tilde());
             let link_file = sub_arg_matches.is_present("link_file");
-            command_write_metaplex_items(&Arweave::default(), paths_iter, manifest_path, link_file)
-                .await
+            let truncate_names = sub_arg_matches.is_present("truncate_names");
+            let ignore_missing = sub_arg_matches.is_present("ignore_missing");
+            command_write_metaplex_items(
+                &get_arweave(app_matches)?,
+                paths_iter,
+                manifest_path,
+                link_file,
+                truncate_names,
+                ignore_missing,
+            )
+            .await
+        }
+        (sub_command, _) => {
+            let _ = get_app().print_help();
+            println!();
+            Err(Error::UnknownSubcommand(sub_command.to_string()))
         }
-        _ => unreachable!(),
     }
 }
 
@@ -440,10 +886,103 @@ fn get_app() -> App<'static, 'static> {
                 .value_name("FORMAT")
                 .global(true)
                 .takes_value(true)
-                .possible_values(&["quiet", "display", "verbose", "json", "json-compact"])
+                .possible_values(&[
+                    "quiet",
+                    "display",
+                    "verbose",
+                    "json",
+                    "json-compact",
+                    "json-lines",
+                ])
                 .default_value("display")
                 .help("Specify output format."),
         )
+        .arg(
+            Arg::with_name("gzip_requests")
+                .long("gzip-requests")
+                .global(true)
+                .takes_value(false)
+                .help("Gzip encodes transactions and chunks posted to the gateway."),
+        )
+        .arg(
+            Arg::with_name("block_size")
+                .long("block-size")
+                .value_name("BYTES")
+                .global(true)
+                .takes_value(true)
+                .validator(is_valid_block_size)
+                .help("Overrides the bytes of data priced per block, 262144 by default."),
+        )
+        .arg(
+            Arg::with_name("max_tx_body")
+                .long("max-tx-body")
+                .value_name("BYTES")
+                .global(true)
+                .takes_value(true)
+                .validator(is_valid_max_tx_body)
+                .help(
+                    "Overrides the largest data in bytes posted whole rather than in chunks, \
+                    10000000 by default.",
+                ),
+        )
+        .arg(
+            Arg::with_name("max_bundle_size")
+                .long("max-bundle-size")
+                .value_name("MB")
+                .global(true)
+                .takes_value(true)
+                .validator(is_valid_bundle_size)
+                .help("Overrides the largest bundle size in megabytes, 200 by default."),
+        )
+        .arg(
+            Arg::with_name("pretty_status")
+                .long("pretty-status")
+                .global(true)
+                .takes_value(false)
+                .help("Write status files as pretty printed json."),
+        )
+        .arg(
+            Arg::with_name("read_only")
+                .long("read-only")
+                .global(true)
+                .takes_value(false)
+                .help("Refuses to post anything to the network. Also set with ARLOADER_READ_ONLY=1."),
+        )
+        .subcommand(
+            SubCommand::with_name("audit")
+                .about("Prints the transactions posted by an Arweave wallet since a date with their rewards summed by upload run.")
+                .arg(
+                    Arg::with_name("wallet_address")
+                        .value_name("WALLET_ADDRESS")
+                        .takes_value(true)
+                        .validator(is_parsable::<Base64>)
+                        .required_unless("ar_keypair_path")
+                        .help(
+                            "Specify the address of the wallet. \
+                            Defaults to <AR_KEYPAIR_PATH>.",
+                        ),
+                )
+                .arg(ar_keypair_path_arg())
+                .arg(
+                    Arg::with_name("since")
+                        .long("since")
+                        .value_name("YYYY-MM-DD|RFC3339")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(is_parsable::<AuditSince>)
+                        .help("Specify the date or time to list transactions from."),
+                )
+                .arg(
+                    Arg::with_name("csv")
+                        .long("csv")
+                        .takes_value(false)
+                        .help("Prints each transaction as csv instead of the summary."),
+                )
+                .after_help(
+                    "NOTES:\n- Transactions are grouped by their Arloader-Run-Id tag, or their User-Agent tag if they don't have one.\n- Rewards are in winstons in csv and json output.
+                    ",
+                ),
+        )
         .subcommand(
             SubCommand::with_name("balance")
                 .about("Prints the balance of an Arweave wallet.")
@@ -460,17 +999,119 @@ fn get_app() -> App<'static, 'static> {
                 )
                 .arg(ar_keypair_path_arg()),
         )
+        .subcommand(
+            SubCommand::with_name("bundle-items")
+                .about("Verifies data items signed by other tools and posts them in a bundle.")
+                .arg(file_paths_arg().required(true))
+                .arg(
+                    Arg::with_name("post")
+                        .long("post")
+                        .takes_value(false)
+                        .help("Bundles and posts the data items once they're all verified."),
+                )
+                .arg(ar_keypair_path_arg())
+                .arg(log_dir_arg_write().long("log-dir"))
+                .arg(reward_multiplier_arg())
+                .arg(buffer_arg("5"))
+                .arg(retries_arg())
+                .arg(skip_balance_check_arg())
+                .after_help(
+                    "EXAMPLES:\n- arloader bundle-items items/*.bin --post\n\nNOTES:\n- Files are serialized ANS-104 data items, e.g., from arbundles, signed with Arweave or ed25519 keys.\n- Nothing is posted if any data item fails to deserialize or verify, or is included more than once.\n- Before posting, checks that the wallet balance covers the estimated reward unless --skip-balance-check is passed.\n- Data items are recorded in the bundle status under their File-Name tag, or their id if they don't have one.
+                    ",
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("bundler-balance")
+                .about("Prints the prepaid balance of an Arweave wallet with a bundler node.")
+                .arg(bundler_url_arg().required(true))
+                .arg(
+                    Arg::with_name("wallet_address")
+                        .value_name("WALLET_ADDRESS")
+                        .takes_value(true)
+                        .validator(is_parsable::<Base64>)
+                        .required_unless("ar_keypair_path")
+                        .help(
+                            "Specify the address of the wallet. \
+                            Defaults to <AR_KEYPAIR_PATH>.",
+                        ),
+                )
+                .arg(ar_keypair_path_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("check-local")
+                .about("Checks that files referenced by statuses are unchanged locally.")
+                .arg(log_dir_arg_read())
+                .after_help(
+                    "NOTES:\n- Compares the size and BLAKE3 hash of each file with those recorded when it was uploaded.\n- Files in statuses written before hashes were recorded are reported as unverified.\n- Prints modified and missing files as they're checked, or every file with --output verbose.
+                    ",
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("debug-deep-hash")
+                .about("Prints the deep hash that uploading a file would sign, for debugging signature mismatches.")
+                .arg(
+                    Arg::with_name("file_path")
+                        .long("file")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(is_valid_file_path)
+                        .help("Specify the path of the file to hash."),
+                )
+                .arg(
+                    Arg::with_name("as_data_item")
+                        .long("as-data-item")
+                        .help("Builds a data item as included in a bundle instead of a transaction."),
+                )
+                .arg(tags_arg())
+                .arg(tip_value_arg().conflicts_with("as_data_item"))
+                .arg(
+                    Arg::with_name("out")
+                        .long("out")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .help("Specify where to write the unsigned transaction or data item as json."),
+                )
+                .arg(reward_multiplier_arg())
+                .arg(ar_keypair_path_arg())
+                .after_help(
+                    "NOTES:\n- Uses the default AR keypair, as when funding with SOL, unless --ar-keypair-path is provided.\n- Prints the hex of the deep hash followed by the tree it's computed over, with blobs as base64url strings.\n- Pass the same --tags, and --tip for transactions, as the upload being debugged.\n- The anchor is left empty, so the hash differs from an upload only by the anchor the gateway provides.
+                    ",
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("dedupe-statuses")
+                .about("Merges bundle statuses that include the same files.")
+                .arg(log_dir_arg_read())
+                .arg(apply_arg())
+                .after_help(
+                    "NOTES:\n- Keeps the status furthest along for each set of files, recording the others in its previous history.\n- Only prints the statuses that would be merged unless --apply is provided.
+                    ",
+                ),
+        )
         .subcommand(
             SubCommand::with_name("estimate")
                 .about("Prints the estimated cost of uploading files.")
                 .arg(file_paths_arg())
+                .arg(exclude_arg())
+                .arg(newer_than_arg())
                 .arg(reward_multiplier_arg())
                 .arg(with_sol_arg())
                 .arg(bundle_size_arg())
                 .arg(no_bundle_arg())
+                .arg(
+                    Arg::with_name("from_log_dir")
+                        .long("from-log-dir")
+                        .value_name("DIR")
+                        .takes_value(true)
+                        .validator(is_valid_dir)
+                        .conflicts_with_all(&["file_paths", "exclude", "newer_than", "no_bundle"])
+                        .help("Estimates the cost of uploading the data recorded in the statuses in DIR again instead of local files."),
+                )
                 .after_help(
                     "EXAMPLES:\nTo get an estimate of the cost in AR and USD to upload all the pngs in some/directory:\n\n\tarloader estimate some/directory/*.png \
                     \n\nTo get an estimate of the cost in SOL and USD to upload all the pngs in some/directory:\n\n\tarloader estimate some/directory/*.png --with-sol \
+                    \n\nTo compare the rewards paid for the statuses in some/directory/status with the cost of uploading the same data now:\n\n\tarloader estimate --from-log-dir some/directory/status \
                     \n\nNOTES:\n- Estimates assume the default bundle size of 10 MB. Pass the `--bundle-size` arg to change the bundle size.
                     " ,
                 ),
@@ -493,22 +1134,108 @@ fn get_app() -> App<'static, 'static> {
                 .arg(no_bundle_arg().requires("file_paths"))
                 .arg(statuses_arg())
                 .arg(max_confirms_arg())
+                .arg(sort_by_arg())
+                .arg(desc_arg())
+                .arg(limit_arg())
                 .after_help(
                     "EXAMPLES:\nTo list bundle statuses written to where/my/files/at/status:\n\n\tarloader list-status where/my/files/at/status \
                     \n\nTo list individual transaction statuses written to where/my/files/at/status for pngs uploaded from where/my/files/at:\n\n\tarloader list-status where/my/files/at/status --file-paths where/my/files/at/*.png --no-bundle \
                     \n\nTo list bundle statuses written to where/my/files/at/status that have a status of NotFound or Pending:\n\n\tarloader list-status where/my/files/at/status --statuses NotFound Pending \
                     \n\nTo list individual transaction statuses written to where/my/files/at/status for pngs uploaded from where/my/files/at that have fewer than 25 confirmations:\n\n\tarloader list-status where/my/files/at/status --file-paths where/my/files/at/*.png --max-confirms 25 --no-bundle \
+                    \n\nTo list the ten least confirmed bundle statuses written to where/my/files/at/status:\n\n\tarloader list-status where/my/files/at/status --sort-by confirms --limit 10 \
                     \n\nNOTES:\n- Make sure NOT to include quotes around <FILE_PATHS>.\n- Make sure <FILE_PATHS> matches the files you uploaded, not the json status files.\n- The primary reason for NotFound is insufficient reward. Try setting a higher <REWARD_MULT>.
                     ",
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("missing")
+                .about("Prints files that don't appear in any status.")
+                .arg(file_paths_arg().required(true))
+                .arg(log_dir_arg_read().long("log-dir").required(true))
+                .after_help(
+                    "EXAMPLES:\nTo print the pngs in where/my/files/at that aren't included in any of the statuses written to where/my/files/at/status:\n\n\tarloader missing where/my/files/at/*.png --log-dir where/my/files/at/status \
+                    \n\nNOTES:\n- Checks both individual transaction and bundle statuses.\n- Doesn't upload anything. Use `reupload` to upload the files.
+                    ",
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("network-info")
+                .about("Prints current network height, block hash and counts of blocks and peers."),
+        )
         .subcommand(
             SubCommand::with_name("pending").about("Prints count of pending network transactions."),
         )
+        .subcommand(
+            SubCommand::with_name("plan")
+                .about("Writes the bundles files would be uploaded in to a plan file to review before uploading.")
+                .arg(file_paths_arg().required(true))
+                .arg(exclude_arg())
+                .arg(newer_than_arg())
+                .arg(bundle_size_arg())
+                .arg(reward_multiplier_arg())
+                .arg(
+                    Arg::with_name("out")
+                        .long("out")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .help("Specify where to write the plan json file. Printed if not provided."),
+                )
+                .after_help(
+                    "EXAMPLES:\nTo plan 50 MB bundles of the files in assets/ and then upload them exactly as planned:\n\n\tarloader plan assets/* --bundle-size 50 --out plan.json\n\tarloader upload --plan plan.json --ar-keypair-path ~/arweave.json \
+                    \n\nNOTES:\n- Rewards in the plan are estimates, the reward paid is calculated from prices at upload.\n- Uploading a plan fails if any file in it changed since it was made.
+                    ",
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("queue")
+                .about("Uploads files from a queue that persists across runs, for uploads that take a long time.")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("add")
+                        .about("Adds files to the queue, creating it if it doesn't exist.")
+                        .arg(file_paths_arg().required(true))
+                        .arg(exclude_arg())
+                        .arg(newer_than_arg())
+                        .arg(queue_dir_arg()),
+                )
+                .subcommand(
+                    SubCommand::with_name("retry-failed")
+                        .about("Queues files that failed to upload to be uploaded again.")
+                        .arg(queue_dir_arg()),
+                )
+                .subcommand(
+                    SubCommand::with_name("run")
+                        .about("Uploads the pending files in the queue in bundles.")
+                        .arg(queue_dir_arg())
+                        .arg(ar_keypair_path_arg())
+                        .arg(tags_arg())
+                        .arg(bundle_size_arg())
+                        .arg(reward_multiplier_arg())
+                        .arg(buffer_arg("5"))
+                        .arg(retries_arg())
+                        .arg(max_runtime_arg()),
+                )
+                .subcommand(
+                    SubCommand::with_name("status")
+                        .about("Prints the number of pending, done and failed files in the queue.")
+                        .arg(queue_dir_arg()),
+                )
+                .after_help(
+                    "EXAMPLES:\nTo queue the pngs in where/my/files/at and upload them, picking up where the last run left off if it is interrupted:\n\n\tarloader queue add where/my/files/at/*.png --queue-dir my/queue\n\tarloader queue run --queue-dir my/queue --ar-keypair-path path/to/my/ar_keypair.json \
+                    \n\nNOTES:\n- Files are recorded in the journal.ndjson file of <QUEUE_DIR> when they are added, uploaded or fail, so runs don't scan <FILE_PATHS> again.\n- Bundle statuses are written to the status sub directory of <QUEUE_DIR>, to be updated with `arloader update-status`.\n- Bundles are recorded with their id before they are posted, so a run that was interrupted while posting one checks whether it was posted before uploading its files again.\n- Files are queued by their canonical paths, so the queue can be run from any directory.
+                    ",
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("repost")
+                .about("Posts transactions paid for with SOL that failed to post, without paying again.")
+                .arg(log_dir_arg_read())
+                .arg(buffer_arg("5")),
+        )
         .subcommand(
             SubCommand::with_name("reupload")
                 .about("Reuploads files.")
-                .arg(file_paths_arg().required(true))
+                .arg(file_paths_arg().required_unless("from_failures"))
                 .arg(log_dir_arg_read().long("log-dir").required(true))
                 .arg(no_bundle_arg())
                 .arg(tags_arg())
@@ -521,6 +1248,24 @@ fn get_app() -> App<'static, 'static> {
                 .arg(sol_keypair_path_arg())
                 .arg(buffer_arg("5"))
                 .arg(bundle_size_arg())
+                .arg(ignore_memory_check_arg())
+                .arg(cache_items_arg().conflicts_with("no_bundle"))
+                .arg(max_runtime_arg())
+                .arg(
+                    Arg::with_name("purge_superseded")
+                        .long("purge-superseded")
+                        .takes_value(false)
+                        .conflicts_with("no_bundle")
+                        .help("Deletes bundle statuses superseded by this or earlier reuploads instead of keeping them in the superseded sub directory of <LOG_DIR>."),
+                )
+                .arg(
+                    Arg::with_name("from_failures")
+                        .long("from-failures")
+                        .value_name("FAILURES_PATH")
+                        .takes_value(true)
+                        .conflicts_with_all(&["file_paths", "statuses", "max_confirms"])
+                        .help("Reuploads the files that failed `verify-uploads`, read from the verify_failures.json file it wrote to <LOG_DIR>."),
+                )
                 .group(
                     ArgGroup::with_name("ar_keypair")
                         .args(&["ar_keypair_path", "ar_default_keypair"])
@@ -529,7 +1274,8 @@ fn get_app() -> App<'static, 'static> {
                     "EXAMPLES:\nTo re-upload pngs previously uploaded from where/my/files/at in bundles with statuses written to where/my/files/at/status with a status of NotFound using an AR keypair with a path of path/to/my/ar_keypair.json:\n\n\tarloader reupload where/my/files/at/*.png --log-dir where/my/files/at/status --statuses NotFound --ar_keypair path path/to/my/ar_keypair.json\
                     \n\nTo re-upload pngs previously uploaded from where/my/files/at as individual transactions with statuses with fewer than 25 confirmations previously written to where/my/files/at/status using a SOL keypair with a path of path/to/my/sol_keypair.json and the default AR keypair:\n\n\tarloader reupload where/my/files/at/*.png --log-dir where/my/files/at/status --max-confirms 25 --no-bundle --with-sol --sol-keypair_path path/to/my/sol_keypair.json --ar-default-keypair\
                     \n\nTo re-upload pngs previously uploaded from where/my/files/at in bundles with statuses written to where/my/files/at/status with statuses of NotFound and Pending with a new bundle size of 100 MB and a reward multiplier of 3.0 using at AR keypair with the path the AR_KEYPAIR_PATH environment variable:\n\n\tarloader reupload where/my/files/at/*.png --log-dir where/my/files/at/status --statuses NotFound Pending --bundle-size 100 --reward-multiplier 3\
-                    \n\nNOTES:\n- Also uploads any files in <FILE_PATHS> not included in statuses.\n- Make sure NOT to include quotes around <FILE_PATHS>.\n- Make sure <FILE_PATHS> matches the files you uploaded, not the json status files.\n- Add paths to your keypair files to the AR_KEYPAIR_PATH and SOL_KEYPAIR_PATH environment variables instead of providing them as arguments.
+                    \n\nTo re-upload the files that failed `verify-uploads`, as listed in where/my/files/at/status/verify_failures.json:\n\n\tarloader reupload --from-failures where/my/files/at/status/verify_failures.json --log-dir where/my/files/at/status\
+                    \n\nNOTES:\n- Also uploads any files in <FILE_PATHS> not included in statuses.\n- Bundle statuses that are replaced are moved to the superseded sub directory of <LOG_DIR> once their files are uploaded again.\n- Make sure NOT to include quotes around <FILE_PATHS>.\n- Make sure <FILE_PATHS> matches the files you uploaded, not the json status files.\n- Add paths to your keypair files to the AR_KEYPAIR_PATH and SOL_KEYPAIR_PATH environment variables instead of providing them as arguments.
                     ",
                 ),
         )
@@ -540,8 +1286,9 @@ fn get_app() -> App<'static, 'static> {
                 .arg(file_paths_arg().long("file-paths").requires("no_bundle"))
                 .arg(no_bundle_arg().requires("file_paths"))
                 .after_help(
-                    "EXAMPLES:\nTo print a report of the individual transaction statuses previously written to some/directory/status for pngs previously uploaded from where/my/files/at:\n\n\tarloader status-report some/directory/status --file-paths where/my/files/at/*.png --no-bundle \
-                    \n\nNOTES:\n- Not yet implemented for bundle transactions.\n- Make sure <FILE_PATHS> matches the files you uploaded, not the json status files.
+                    "EXAMPLES:\nTo print a count of the bundles, files and KB by status for bundle statuses previously written to some/directory/status:\n\n\tarloader status-report some/directory/status \
+                    \n\nTo print a report of the individual transaction statuses previously written to some/directory/status for pngs previously uploaded from where/my/files/at:\n\n\tarloader status-report some/directory/status --file-paths where/my/files/at/*.png --no-bundle \
+                    \n\nNOTES:\n- Use `--output json` for a json summary of bundle statuses.\n- Make sure <FILE_PATHS> matches the files you uploaded, not the json status files.
                     " ,
                 ),
         )
@@ -556,7 +1303,8 @@ fn get_app() -> App<'static, 'static> {
                 .about("Updates NFT metadata files with links to uploaded asset files.")
                 .arg(file_paths_arg())
                 .arg(manifest_path_arg())
-                .arg(link_file_arg())
+                .arg(image_link_arg())
+                .arg(files_link_arg())
                 .arg(update_image_arg())
                 .arg(update_animation_url_arg())
         )
@@ -567,19 +1315,25 @@ fn get_app() -> App<'static, 'static> {
                 .arg(file_paths_arg().long("file-paths").requires("no_bundle"))
                 .arg(no_bundle_arg().requires("file_paths"))
                 .arg(buffer_arg("10"))
+                .arg(send_hints_arg().conflicts_with("no_bundle"))
+                .arg(index_hint_url_arg())
+                .arg(min_confirms_arg())
                 .after_help(
                     "EXAMPLES:\nTo update bundle statuses written to some/directory/status:\n\n\tarloader update-status some/directory/status \
+                    \n\nTo also post index hints for newly confirmed bundles:\n\n\tarloader update-status some/directory/status --send-hints --index-hint-url https://example.com/hints \
                     \n\nTo update individual transaction statuses for files with an extension of *.png written to some/directory/status:\n\n\tarloader update-status some/directory/status --file-paths *.png --no-bundle \
-                    \n\nNOTES:\n- Make sure NOT to include quotes around <FILE_PATHS>.\n- Make sure <FILE_PATHS> matches the files you uploaded, not the json status files.
+                    \n\nNOTES:\n- Statuses already confirmed with at least <MIN_CONFIRMS> confirmations are skipped without querying the network.\n- Make sure NOT to include quotes around <FILE_PATHS>.\n- Make sure <FILE_PATHS> matches the files you uploaded, not the json status files.
                     " ,
                 ),
         )
         .subcommand(
             SubCommand::with_name("upload")
                 .about("Uploads files.")
-                .arg(file_paths_arg().required(true))
+                .arg(file_paths_arg().required_unless("plan"))
+                .arg(exclude_arg())
                 .arg(log_dir_arg_write().long("log-dir"))
                 .arg(no_bundle_arg())
+                .arg(plan_arg())
                 .arg(tags_arg())
                 .arg(reward_multiplier_arg())
                 .arg(ar_keypair_path_arg().required_unless("with_sol"))
@@ -588,6 +1342,17 @@ fn get_app() -> App<'static, 'static> {
                 .arg(sol_keypair_path_arg())
                 .arg(buffer_arg("5"))
                 .arg(bundle_size_arg())
+                .arg(retries_arg())
+                .arg(ignore_memory_check_arg())
+                .arg(skip_balance_check_arg())
+                .arg(cache_items_arg().conflicts_with("no_bundle"))
+                .arg(anchor_from_hash_arg().conflicts_with("no_bundle"))
+                .arg(bundle_tags_arg().conflicts_with("no_bundle"))
+                .arg(bundle_manifest_arg().conflicts_with("no_bundle"))
+                .arg(strict_reward_arg())
+                .arg(tip_arg())
+                .arg(max_runtime_arg())
+                .arg(newer_than_arg().requires_if("@last-run", "log_dir"))
                 .group(
                     ArgGroup::with_name("ar_keypair")
                         .args(&["ar_keypair_path", "ar_default_keypair"])
@@ -596,13 +1361,44 @@ fn get_app() -> App<'static, 'static> {
         )
         .subcommand(
             SubCommand::with_name("upload-manifest")
-                .about("Uploads a manifest for uploaded files. Only currently implemented bundles.")
+                .about("Uploads a manifest for uploaded files.")
                 .arg(log_dir_arg_read().required(true))
+                .arg(no_bundle_arg().help("Reads the statuses of files uploaded individually instead of bundle statuses."))
+                .arg(manifest_base_dir_arg())
+                .arg(
+                    Arg::with_name("index")
+                        .long("index")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .help("Specify the path, relative to the manifest, to serve when the manifest itself is requested, e.g., index.html."),
+                )
+                .arg(
+                    Arg::with_name("fallback")
+                        .long("fallback")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .help("Specify the path, relative to the manifest, to serve for paths that aren't in the manifest, e.g., 404.html."),
+                )
+                .arg(
+                    Arg::with_name("max_manifest_paths")
+                        .long("max-manifest-paths")
+                        .value_name("MAX_PATHS")
+                        .takes_value(true)
+                        .default_value("100000")
+                        .validator(is_parsable::<usize>)
+                        .help("Specify the most paths a manifest can have."),
+                )
+                .arg(
+                    Arg::with_name("split_manifests")
+                        .long("split-manifests")
+                        .help("Uploads manifests with more than MAX_PATHS paths as several manifests, split by directory."),
+                )
                 .arg(reward_multiplier_arg())
                 .arg(ar_keypair_path_arg().required_unless("with_sol"))
                 .arg(ar_default_keypair())
                 .arg(with_sol_arg().requires("sol_keypair_path"))
                 .arg(sol_keypair_path_arg())
+                .arg(strict_paths_arg())
                 .group(
                     ArgGroup::with_name("ar_keypair")
                         .args(&["ar_keypair_path", "ar_default_keypair"])
@@ -613,6 +1409,8 @@ fn get_app() -> App<'static, 'static> {
             SubCommand::with_name("upload-nfts")
                 .about("Uploads a directory with pairs of asset and metadata files.")
                 .arg(file_paths_arg().required(true))
+                .arg(exclude_arg())
+                .arg(newer_than_arg().requires_if("@last-run", "log_dir"))
                 .arg(log_dir_arg_write().long("log-dir"))
                 .arg(tags_arg())
                 .arg(reward_multiplier_arg())
@@ -622,19 +1420,98 @@ fn get_app() -> App<'static, 'static> {
                 .arg(sol_keypair_path_arg())
                 .arg(buffer_arg("5"))
                 .arg(bundle_size_arg())
+                .arg(ignore_memory_check_arg())
                 .arg(link_file_arg())
+                .arg(manifest_base_dir_arg())
+                .arg(max_runtime_arg())
+                .arg(no_bundle_arg())
+                .arg(anchor_from_hash_arg().conflicts_with("no_bundle"))
+                .arg(bundle_tags_arg().conflicts_with("no_bundle"))
+                .arg(skip_balance_check_arg())
+                .arg(strict_paths_arg())
+                .arg(strict_reward_arg())
+                .arg(truncate_names_arg())
                 .group(
                     ArgGroup::with_name("ar_keypair")
                         .args(&["ar_keypair_path", "ar_default_keypair"])
                         .required(true),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("upload-one")
+                .about("Uploads a single file and prints a link to it once it's pending.")
+                .arg(
+                    Arg::with_name("file_path")
+                        .value_name("FILE_PATH")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(is_valid_file_path)
+                        .help("Specify the path of the file to upload."),
+                )
+                .arg(log_dir_arg_write().long("log-dir"))
+                .arg(no_bundle_arg())
+                .arg(tags_arg())
+                .arg(reward_multiplier_arg())
+                .arg(ar_keypair_path_arg().required_unless("with_sol"))
+                .arg(ar_default_keypair())
+                .arg(with_sol_arg().requires("sol_keypair_path"))
+                .arg(sol_keypair_path_arg())
+                .arg(tip_value_arg())
+                .group(
+                    ArgGroup::with_name("ar_keypair")
+                        .args(&["ar_keypair_path", "ar_default_keypair"])
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("verify-bundle-status")
+                .about("Verifies the data items in an uploaded bundle against its status.")
+                .arg(
+                    Arg::with_name("path")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(is_json_file_path)
+                        .help("Specify the path of the bundle status file."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("verify-uploads")
+                .about("Downloads uploaded files and compares them with the local files.")
+                .arg(file_paths_arg().required(true))
+                .arg(log_dir_arg_read().long("log-dir").required(true))
+                .arg(buffer_arg("10"))
+                .after_help(
+                    "EXAMPLES:\nTo verify the pngs in where/my/files/at against the statuses written to where/my/files/at/status:\n\n\tarloader verify-uploads where/my/files/at/*.png --log-dir where/my/files/at/status \
+                    \n\nNOTES:\n- Files that fail are written to verify_failures.json in the log directory.\n- Doesn't upload anything. Use `reupload --from-failures` with verify_failures.json to upload the files again.
+                    ",
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("wallet-generate")
+                .about("Generates a new Arweave wallet and prints its address.")
+                .arg(
+                    Arg::with_name("output_path")
+                        .long("output-path")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Specify where to write the wallet keypair json file."),
+                )
+                .after_help(
+                    "EXAMPLES:\nTo generate a new wallet and write it to ~/wallets/arweave.json:\n\n\tarloader wallet-generate --output-path ~/wallets/arweave.json \
+                    \n\nNOTES:\n- The file is only readable by you and is never overwritten if it already exists.\n- `--output-path` is used because `--output` sets the output format for every command.
+                    ",
+                ),
+        )
         .subcommand(
             SubCommand::with_name("write-metaplex-items")
                 .about("Writes metaplex items to file.")
                 .arg(file_paths_arg().required(true).validator(is_json_file_path))
                 .arg(manifest_path_arg())
                 .arg(link_file_arg())
+                .arg(truncate_names_arg())
+                .arg(ignore_missing_arg())
                 .after_help(
                     "EXAMPLES:\nTo write the metaplex items json file for metadata json files in the current directory with a manifest path of arloader_I-D4AkMq4rs/metadata/manifest__k5SQMAVPxhS-GAsbZbbTV9469qZj7oH-_SM3H45nTk.json:\n\n\tarloader write-metaplex-items *.json --manifest_path arloader_I-D4AkMq4rs/metadata/manifest__k5SQMAVPxhS-GAsbZbbTV9469qZj7oH-_SM3H45nTk.json \
                     \n\nNOTES:\n- Make sure NOT to include quotes around <FILE_PATHS>.\n- Make sure <FILE_PATHS> matches your json metadata files, not your asset files.
@@ -648,6 +1525,22 @@ fn get_app() -> App<'static, 'static> {
 // Arguments
 // ====================
 
+fn anchor_from_hash_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("anchor_from_hash")
+        .long("anchor-from-hash")
+        .required(false)
+        .takes_value(false)
+        .help("Sets the anchor of each data item to a hash of its content, so identical content gets identical anchors.")
+}
+
+fn apply_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("apply")
+        .long("apply")
+        .required(false)
+        .takes_value(false)
+        .help("Merges duplicate statuses and deletes the redundant files instead of just printing them.")
+}
+
 fn ar_default_keypair<'a, 'b>() -> Arg<'a, 'b> {
     Arg::with_name("ar_default_keypair")
         .long("ar-default-keypair")
@@ -676,6 +1569,13 @@ fn buffer_arg<'a, 'b>(default: &'a str) -> Arg<'a, 'b> {
         .help("Specify the maximum number of concurrent network requests.")
 }
 
+fn bundle_manifest_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("bundle_manifest")
+        .long("bundle-manifest")
+        .takes_value(false)
+        .help("Uploads a manifest of the files as a data item in the last bundle, after all of the other bundles are uploaded.")
+}
+
 fn bundle_size_arg<'a, 'b>() -> Arg<'a, 'b> {
     Arg::with_name("bundle_size")
         .long("bundle-size")
@@ -686,6 +1586,58 @@ fn bundle_size_arg<'a, 'b>() -> Arg<'a, 'b> {
         .help("Specify the bundle size in megabytes.")
 }
 
+fn bundle_tags_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("bundle_tags")
+        .long("bundle-tag")
+        .value_name("NAME:VALUE")
+        .multiple(true)
+        .number_of_values(1)
+        .takes_value(true)
+        .validator(is_valid_bundle_tag)
+        .help(
+            "Specify a tag for each bundle transaction as <NAME>:<VALUE>, with :: for a colon in <NAME>. Can be repeated. \
+        {count}, {bytes}, {items_hash}, {first_path} and {last_path} are replaced with \
+        the number of files in the bundle, their total size, a BLAKE3 hash of the data item \
+        ids and the first and last file paths. Use {{ and }} for literal braces.",
+        )
+}
+
+fn bundler_url_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("bundler_url")
+        .long("bundler-url")
+        .value_name("BUNDLER_URL")
+        .takes_value(true)
+        .validator(is_parsable::<Url>)
+        .help("Base url of the bundler node.")
+}
+
+fn cache_items_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("cache_items")
+        .long("cache-items")
+        .value_name("DIR")
+        .takes_value(true)
+        .help("Caches signed data items in this directory to reuse them for unchanged files on later runs.")
+}
+
+fn desc_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("desc")
+        .long("desc")
+        .required(false)
+        .takes_value(false)
+        .requires("sort_by")
+        .help("Sorts in descending order.")
+}
+
+fn exclude_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("exclude")
+        .long("exclude")
+        .value_name("GLOB")
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1)
+        .help("Leaves out files matching the pattern, by relative path or file name. Can be repeated.")
+}
+
 fn file_paths_arg<'a, 'b>() -> Arg<'a, 'b> {
     Arg::with_name("file_paths")
         .value_name("FILE_PATHS")
@@ -698,6 +1650,18 @@ fn file_paths_arg<'a, 'b>() -> Arg<'a, 'b> {
         )
 }
 
+fn files_link_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("files_link")
+        .long("files-link")
+        .value_name("FILES_LINK")
+        .takes_value(true)
+        .possible_values(&["id", "file", "both"])
+        .default_value("both")
+        .help(
+            "Specify whether links in the properties.files array are id based, file based or both.",
+        )
+}
+
 fn id_arg<'a, 'b>() -> Arg<'a, 'b> {
     Arg::with_name("id")
         .value_name("ID")
@@ -707,6 +1671,50 @@ fn id_arg<'a, 'b>() -> Arg<'a, 'b> {
         .help("Specify the transaction id.")
 }
 
+fn ignore_memory_check_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("ignore_memory_check")
+        .long("ignore-memory-check")
+        .required(false)
+        .takes_value(false)
+        .help("Warns instead of failing when <BUNDLE_SIZE> x <BUFFER> may not fit in available memory.")
+}
+
+fn ignore_missing_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("ignore_missing")
+        .long("ignore-missing")
+        .required(false)
+        .takes_value(false)
+        .help("Skips metadata files that aren't in the manifest instead of failing.")
+}
+
+fn image_link_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("image_link")
+        .long("image-link")
+        .value_name("IMAGE_LINK")
+        .takes_value(true)
+        .possible_values(&["id", "file"])
+        .default_value("id")
+        .help("Specify whether the image link is id based or file based.")
+}
+
+fn index_hint_url_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("index_hint_url")
+        .long("index-hint-url")
+        .value_name("URL")
+        .takes_value(true)
+        .validator(is_parsable::<Url>)
+        .help("Endpoint to post the data item ids of newly confirmed bundles to.")
+}
+
+fn limit_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("limit")
+        .long("limit")
+        .value_name("LIMIT")
+        .takes_value(true)
+        .validator(is_parsable::<usize>)
+        .help("Specify the maximum number of statuses to print.")
+}
+
 fn link_file_arg<'a, 'b>() -> Arg<'a, 'b> {
     Arg::with_name("link_file")
         .long("link-file")
@@ -719,9 +1727,9 @@ fn link_file_arg<'a, 'b>() -> Arg<'a, 'b> {
 fn log_dir_arg_write<'a, 'b>() -> Arg<'a, 'b> {
     Arg::with_name("log_dir")
         .value_name("LOG_DIR")
-        .validator(is_valid_dir)
+        .validator(is_valid_write_dir)
         .takes_value(true)
-        .help("Specify a directory to write status updates to.")
+        .help("Specify a directory to write status updates to, created if it doesn't exist.")
 }
 
 fn log_dir_arg_read<'a, 'b>() -> Arg<'a, 'b> {
@@ -730,7 +1738,19 @@ fn log_dir_arg_read<'a, 'b>() -> Arg<'a, 'b> {
         .value_name("LOG_DIR")
         .validator(is_valid_dir)
         .takes_value(true)
-        .help("Specify the directory that statuses have been written to.")
+        .help("Specify the directory that statuses have been written to.")
+}
+
+fn manifest_base_dir_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("manifest_base_dir")
+        .long("manifest-base-dir")
+        .value_name("MANIFEST_BASE_DIR")
+        .takes_value(true)
+        .validator(is_parsable::<PathBuf>)
+        .help(
+            "Specify the directory that manifest paths are relative to. \
+            Defaults to the common parent directory of the uploaded files.",
+        )
 }
 
 fn manifest_path_arg<'a, 'b>() -> Arg<'a, 'b> {
@@ -750,6 +1770,34 @@ fn max_confirms_arg<'a, 'b>() -> Arg<'a, 'b> {
         .help("Specify maximum number of confirmations to filter statuses by.")
 }
 
+fn max_runtime_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("max_runtime")
+        .long("max-runtime")
+        .value_name("SECS")
+        .takes_value(true)
+        .validator(is_parsable::<u64>)
+        .help("Stops starting new uploads after this many seconds, allowing uploads in progress a grace period to finish.")
+}
+
+fn min_confirms_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("min_confirms")
+        .long("min-confirms")
+        .value_name("MIN_CONFIRMS")
+        .takes_value(true)
+        .validator(is_parsable::<u64>)
+        .default_value("25")
+        .help("Skips statuses already confirmed with at least this many confirmations.")
+}
+
+fn newer_than_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("newer_than")
+        .long("newer-than")
+        .value_name("RFC3339|@last-run")
+        .takes_value(true)
+        .validator(is_parsable::<NewerThan>)
+        .help("Only includes files modified after this time, or after the newest status in --log-dir if @last-run, which needs --log-dir.")
+}
+
 fn no_bundle_arg<'a, 'b>() -> Arg<'a, 'b> {
     Arg::with_name("no_bundle")
         .long("no-bundle")
@@ -760,6 +1808,34 @@ fn no_bundle_arg<'a, 'b>() -> Arg<'a, 'b> {
         .help("Individual transaction for each file without bundling.")
 }
 
+fn plan_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("plan")
+        .long("plan")
+        .value_name("PLAN_PATH")
+        .takes_value(true)
+        .conflicts_with_all(&["file_paths", "no_bundle", "newer_than", "exclude"])
+        .help("Uploads the bundles in a plan file written by `arloader plan`, with its bundle size, instead of <FILE_PATHS>.")
+}
+
+fn queue_dir_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("queue_dir")
+        .long("queue-dir")
+        .value_name("QUEUE_DIR")
+        .takes_value(true)
+        .required(true)
+        .help("Specify the directory of the upload queue.")
+}
+
+fn retries_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("retries")
+        .long("retries")
+        .value_name("RETRIES")
+        .takes_value(true)
+        .validator(is_parsable::<u32>)
+        .default_value("3")
+        .help("Specify the number of times to retry posting a bundle after network errors or 429 and 5xx responses.")
+}
+
 fn reward_multiplier_arg<'a, 'b>() -> Arg<'a, 'b> {
     Arg::with_name("reward_multiplier")
         .long("reward-multiplier")
@@ -770,6 +1846,14 @@ fn reward_multiplier_arg<'a, 'b>() -> Arg<'a, 'b> {
         .help("Specify a factor between 0.0 and 10.0 to increase the reward by.")
 }
 
+fn skip_balance_check_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("skip_balance_check")
+        .long("skip-balance-check")
+        .required(false)
+        .takes_value(false)
+        .help("Starts uploading bundles without checking that the wallet balance covers the estimated cost.")
+}
+
 fn sol_keypair_path_arg<'a, 'b>() -> Arg<'a, 'b> {
     Arg::with_name("sol_keypair_path")
         .long("sol-keypair-path")
@@ -779,6 +1863,23 @@ fn sol_keypair_path_arg<'a, 'b>() -> Arg<'a, 'b> {
         .help("Specify path of keypair file to use for funding transactions.")
 }
 
+fn send_hints_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("send_hints")
+        .long("send-hints")
+        .takes_value(false)
+        .requires("index_hint_url")
+        .help("Posts index hints for bundles that are newly confirmed. Failures are reported but not fatal.")
+}
+
+fn sort_by_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("sort_by")
+        .long("sort-by")
+        .value_name("SORT_BY")
+        .takes_value(true)
+        .possible_values(&["confirms", "status", "size", "path"])
+        .help("Specify the column to sort statuses by. Size only applies to bundles.")
+}
+
 fn statuses_arg<'a, 'b>() -> Arg<'a, 'b> {
     Arg::with_name("statuses")
         .long("statuses")
@@ -798,21 +1899,70 @@ fn status_log_dir_arg<'a, 'b>() -> Arg<'a, 'b> {
         .help("Parent status directory that contains `assets/` and `metadata/` sub-folders.")
 }
 
+fn strict_paths_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("strict_paths")
+        .long("strict-paths")
+        .required(false)
+        .takes_value(false)
+        .help(
+            "Fails on file paths with characters other than ASCII letters, digits, \
+        -, ., _, ~ and / instead of percent-encoding them in the manifest.",
+        )
+}
+
+fn strict_reward_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("strict_reward")
+        .long("strict-reward")
+        .required(false)
+        .takes_value(false)
+        .help("Fails on bundles whose reward is less than the network price of their serialized size instead of raising it.")
+}
+
 fn tags_arg<'a, 'b>() -> Arg<'a, 'b> {
     Arg::with_name("tags")
         .long("tags")
         .value_name("TAGS")
         .multiple(true)
         .takes_value(true)
+        // Leaves room for the User-Agent and Content-Type tags added to each data item.
+        .max_values((MAX_TAGS - 2) as u64)
         .validator(is_valid_tag)
         .help(
             "Specify additional tags for uploaded files as \
-        <NAME>:<VALUE>, separated by spaces. Content-Type tag \
+        <NAME>:<VALUE>, separated by spaces. <VALUE> may contain colons, \
+        use :: for a colon in <NAME>. Content-Type tag \
         is inferred automatically so not necessary to \
-        specify. Applied to each uploaded file.",
+        specify. If one is given, its value is used as the \
+        Content-Type of every uploaded file in place of the \
+        inferred one. Applied to each uploaded file.",
+        )
+}
+
+fn tip_arg<'a, 'b>() -> Arg<'a, 'b> {
+    tip_value_arg()
+        .requires("no_bundle")
+        .conflicts_with("with_sol")
+}
+
+fn tip_value_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("tip")
+        .long("tip")
+        .value_name("ADDRESS:AR")
+        .takes_value(true)
+        .validator(is_parsable::<Tip>)
+        .help(
+            "Transfers AR to ADDRESS with each uploaded transaction, specified as <ADDRESS>:<AR>.",
         )
 }
 
+fn truncate_names_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("truncate_names")
+        .long("truncate-names")
+        .required(false)
+        .takes_value(false)
+        .help("Truncates NFT names longer than the 32 bytes allowed by candy machine instead of failing.")
+}
+
 fn update_animation_url_arg<'a, 'b>() -> Arg<'a, 'b> {
     Arg::with_name("update_animation_url")
         .long("update-animation-url")
@@ -869,11 +2019,21 @@ fn is_valid_tag<T>(tag: T) -> Result<(), String>
 where
     T: AsRef<str> + Display,
 {
-    let split: Vec<_> = tag.as_ref().split(":").collect();
-    match Tag::<Base64>::from_utf8_strs(split[0], split[1]) {
-        Ok(_) => Ok(()),
-        Err(_) => Err(format!("Not a valid tag.")),
-    }
+    let (name, value) = split_tag(tag.as_ref()).ok_or_else(|| {
+        "Not a valid tag, expected <NAME>:<VALUE> with `::` for colons in <NAME>.".to_string()
+    })?;
+    Tag::<String>::from_utf8_strs(&name, &value)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn is_valid_bundle_tag(tag: String) -> Result<(), String> {
+    is_valid_tag(&tag)?;
+    let (name, value) = split_tag(&tag).unwrap();
+    [name, value]
+        .iter()
+        .try_for_each(|t| check_bundle_tag_template(t))
+        .map_err(|e| e.to_string())
 }
 
 fn is_valid_reward_multiplier(reward_mult: String) -> Result<(), String> {
@@ -889,21 +2049,37 @@ fn is_valid_reward_multiplier(reward_mult: String) -> Result<(), String> {
     }
 }
 
+/// Checks that a bundle size is a positive number of MB. Bundle sizes are checked against
+/// `--max-bundle-size` once it has been parsed, see [`get_bundle_size`].
 fn is_valid_bundle_size(bundle_size: String) -> Result<(), String> {
     match bundle_size.parse::<f64>() {
         Ok(n) => {
-            if n > 0.0 && n <= 200.0 {
+            if n > 0.0 {
                 Ok(())
             } else {
-                Err(format!(
-                    "Bundle size must be at least 1 MB and not greater than 200 MB."
-                ))
+                Err(format!("Bundle size must be greater than 0 MB."))
             }
         }
         Err(_) => Err(format!("Not a valid bundle size.")),
     }
 }
 
+fn is_valid_block_size(block_size: String) -> Result<(), String> {
+    match block_size.parse::<u64>() {
+        Ok(n) if n > 0 => Ok(()),
+        _ => Err(format!(
+            "Block size must be a whole number of bytes greater than 0."
+        )),
+    }
+}
+
+fn is_valid_max_tx_body(max_tx_body: String) -> Result<(), String> {
+    match max_tx_body.parse::<u64>() {
+        Ok(_) => Ok(()),
+        Err(_) => Err("Max tx body must be a whole number of bytes.".to_string()),
+    }
+}
+
 fn is_valid_dir(dir_str: String) -> Result<(), String> {
     match dir_str.parse::<PathBuf>() {
         Ok(p) => {
@@ -921,6 +2097,19 @@ fn is_valid_dir(dir_str: String) -> Result<(), String> {
     }
 }
 
+fn is_valid_write_dir(dir_str: String) -> Result<(), String> {
+    match dir_str.parse::<PathBuf>() {
+        Ok(p) => {
+            if p.exists() && !p.is_dir() {
+                Err(format!("Path is not a directory."))
+            } else {
+                Ok(())
+            }
+        }
+        Err(_) => Err(format!("Not a valid path.")),
+    }
+}
+
 fn is_valid_file_path(path_str: String) -> Result<(), String> {
     match path_str.parse::<PathBuf>() {
         Ok(p) => {
@@ -942,7 +2131,7 @@ fn is_json_file_path(path_str: String) -> Result<(), String> {
     match path_str.parse::<PathBuf>() {
         Ok(p) => {
             if p.is_file() {
-                if p.extension().unwrap() == "json" {
+                if p.extension().map_or(false, |e| e == "json") {
                     Ok(())
                 } else {
                     Err(format!("Path does not have a json extension."))
@@ -980,6 +2169,164 @@ fn is_json_file_path(path_str: String) -> Result<(), String> {
 // Helpers
 // ====================
 
+fn missing(name: &str) -> Error {
+    Error::MissingArgument(name.to_string())
+}
+
+/// Value of argument `name`, or [`Error::MissingArgument`] if it wasn't provided and has no
+/// default.
+fn value_of<'a>(matches: &'a ArgMatches, name: &str) -> Result<&'a str, Error> {
+    matches.value_of(name).ok_or_else(|| missing(name))
+}
+
+/// Values of argument `name` as paths.
+fn paths_of(matches: &ArgMatches, name: &str) -> Result<std::vec::IntoIter<PathBuf>, Error> {
+    let values = matches.values_of(name).ok_or_else(|| missing(name))?;
+    Ok(values.map(PathBuf::from).collect::<Vec<_>>().into_iter())
+}
+
+/// Parses argument `name` if it was provided, returning [`Error::InvalidArgument`] if it can't be
+/// parsed.
+fn parse_optional<T: FromStr>(matches: &ArgMatches, name: &str) -> Result<Option<T>, Error> {
+    matches
+        .value_of(name)
+        .map(|value| {
+            value.parse::<T>().map_err(|_| Error::InvalidArgument {
+                name: name.to_string(),
+                value: value.to_string(),
+            })
+        })
+        .transpose()
+}
+
+/// Like [`parse_optional`], but returns [`Error::MissingArgument`] if `name` wasn't provided.
+fn parse_value<T: FromStr>(matches: &ArgMatches, name: &str) -> Result<T, Error> {
+    parse_optional(matches, name)?.ok_or_else(|| missing(name))
+}
+
+/// Arweave for subcommands that don't sign with a keypair of their own, with requests made to
+/// `--base-url` and the global status and request flags applied.
+fn get_arweave(app_matches: &ArgMatches) -> Result<Arweave, Error> {
+    Ok(Arweave {
+        pretty_status: app_matches.is_present("pretty_status"),
+        read_only: is_read_only(app_matches),
+        gzip_requests: app_matches.is_present("gzip_requests"),
+        network_params: get_network_params(app_matches)?,
+        ..Arweave::try_default_with_url(get_base_url(app_matches)?)?
+    })
+}
+
+/// Default [`NetworkParams`] with `--block-size`, `--max-tx-body` and `--max-bundle-size` applied.
+fn get_network_params(app_matches: &ArgMatches) -> Result<NetworkParams, Error> {
+    let defaults = NetworkParams::default();
+    Ok(NetworkParams {
+        block_size: parse_optional::<u64>(app_matches, "block_size")?
+            .unwrap_or(defaults.block_size),
+        max_tx_body: parse_optional::<u64>(app_matches, "max_tx_body")?
+            .unwrap_or(defaults.max_tx_body),
+        max_bundle_size: parse_optional::<f64>(app_matches, "max_bundle_size")?
+            .map_or(defaults.max_bundle_size, |mb| {
+                (mb * BYTES_PER_MB as f64) as u64
+            }),
+        ..defaults
+    })
+}
+
+/// `--bundle-size` in bytes, which can't be larger than `network_params.max_bundle_size`.
+fn get_bundle_size(
+    sub_arg_matches: &ArgMatches,
+    network_params: &NetworkParams,
+) -> Result<u64, Error> {
+    let bundle_size =
+        (parse_value::<f64>(sub_arg_matches, "bundle_size")? * BYTES_PER_MB as f64) as u64;
+    if bundle_size > network_params.max_bundle_size {
+        return Err(Error::InvalidArgument {
+            name: "bundle_size".to_string(),
+            value: format!(
+                "{} MB, more than the maximum of {} MB",
+                bundle_size as f64 / BYTES_PER_MB as f64,
+                network_params.max_bundle_size as f64 / BYTES_PER_MB as f64
+            ),
+        });
+    }
+    Ok(bundle_size)
+}
+
+/// `--queue-dir` with `~` expanded.
+fn get_queue_dir(sub_arg_matches: &ArgMatches) -> Result<PathBuf, Error> {
+    Ok(PathBuf::from(
+        value_of(sub_arg_matches, "queue_dir")?.expand_tilde(),
+    ))
+}
+
+fn get_base_url(app_matches: &ArgMatches) -> Result<Url, Error> {
+    Ok(Url::from_str(
+        &value_of(app_matches, "base_url")?.add_trailing_slash(),
+    )?)
+}
+
+/// Returns the `--exclude` patterns along with `defaults`.
+fn get_exclude_patterns(
+    sub_arg_matches: &ArgMatches<'_>,
+    defaults: &[&str],
+) -> Result<Vec<Pattern>, Error> {
+    defaults
+        .iter()
+        .copied()
+        .chain(sub_arg_matches.values_of("exclude").into_iter().flatten())
+        .map(|p| Pattern::new(p).map_err(Error::from))
+        .collect()
+}
+
+/// Collects `<FILE_PATHS>`, leaving out files matching `--exclude` or `default_excludes` and
+/// keeping only files modified after `--newer-than` if it was provided.
+async fn get_paths_vec(
+    sub_arg_matches: &ArgMatches<'_>,
+    log_dir: Option<&PathBuf>,
+    default_excludes: &[&str],
+) -> Result<Vec<PathBuf>, Error> {
+    let paths_iter = filter_paths_excluded(
+        paths_of(sub_arg_matches, "file_paths")?,
+        get_exclude_patterns(sub_arg_matches, default_excludes)?,
+    );
+    let newer_than = match sub_arg_matches
+        .value_of("newer_than")
+        .map(NewerThan::from_str)
+    {
+        Some(Ok(NewerThan::Time(t))) => Some(t),
+        Some(Ok(NewerThan::LastRun)) => {
+            last_run_time(log_dir.ok_or_else(|| missing("log_dir"))?).await?
+        }
+        Some(Err(e)) => return Err(e),
+        None => None,
+    };
+    match newer_than {
+        Some(t) => Ok(filter_paths_newer_than(paths_iter, t).collect()),
+        None => Ok(paths_iter.collect()),
+    }
+}
+
+/// Splits `<NAME>:<VALUE>` at the first colon that isn't part of a `::`, which escapes a colon
+/// in the name, so that values can contain colons, e.g., urls. Returns `None` without a
+/// separating colon or with an empty name.
+fn split_tag(tag: &str) -> Option<(String, String)> {
+    let mut name = String::new();
+    let mut chars = tag.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            ':' if chars.peek().map(|(_, c)| *c) == Some(':') => {
+                chars.next();
+                name.push(':');
+            }
+            ':' if !name.is_empty() => return Some((name, tag[i + 1..].to_string())),
+            ':' => return None,
+            c => name.push(c),
+        }
+    }
+    None
+}
+
+/// Parses tags already checked by [`is_valid_tag`].
 fn get_tags_vec<T>(values: Values) -> Vec<T>
 where
     T: FromUtf8Strs<T>,
@@ -987,10 +2334,9 @@ where
     values
         .into_iter()
         .map(|t| {
-            let split: Vec<&str> = t.split(":").collect();
-            T::from_utf8_strs(split[0], split[1])
+            let (name, value) = split_tag(t).unwrap();
+            T::from_utf8_strs(&name, &value).unwrap()
         })
-        .flat_map(Result::ok)
         .collect()
 }
 
@@ -1007,13 +2353,20 @@ fn get_status_codes_vec(values: Values) -> Vec<StatusCode> {
         .collect()
 }
 
+/// Whether `--read-only` is passed or [`READ_ONLY_ENV`] is set to `1` or `true`.
+fn is_read_only(app_matches: &ArgMatches) -> bool {
+    app_matches.is_present("read_only")
+        || env::var(READ_ONLY_ENV).map_or(false, |v| v == "1" || v.eq_ignore_ascii_case("true"))
+}
+
 /// Maps cli string argument to output format.
 pub fn get_output_format(output: &str) -> OutputFormat {
     match output {
         "quiet" => OutputFormat::DisplayQuiet,
         "verbose" => OutputFormat::DisplayVerbose,
         "json" => OutputFormat::Json,
-        "json_compact" => OutputFormat::JsonCompact,
+        "json-compact" => OutputFormat::JsonCompact,
+        "json-lines" => OutputFormat::JsonLines,
         _ => OutputFormat::Display,
     }
 }
@@ -1024,49 +2377,52 @@ pub trait CleanPaths {
 }
 
 // This gets applied to all directories to both expand the tilde for the home directory
-// and to make sure that there is a trailing slash.
+// and to make sure that there is a trailing slash. Only a leading `~` on its own or followed by a
+// separator is expanded, other tildes are part of the name. Empty strings are left unchanged.
 impl CleanPaths for &str {
     fn expand_tilde(&self) -> String {
-        if self.chars().next().unwrap() == '~' {
-            self.replace("~", &dirs_next::home_dir().unwrap().display().to_string())
-        } else {
-            self.to_string()
+        match (self.strip_prefix('~'), dirs_next::home_dir()) {
+            (Some(rest), Some(home))
+                if rest.is_empty() || rest.starts_with('/') || rest.starts_with('\\') =>
+            {
+                format!("{}{}", home.display(), rest)
+            }
+            _ => self.to_string(),
         }
     }
     fn add_trailing_slash(&self) -> String {
-        let last_char = self.chars().last().unwrap();
-        if !(last_char == '/' || last_char == '\\') {
-            format!("{}/", self)
-        } else {
-            self.to_string()
+        match self.chars().last() {
+            Some(last_char) if !(last_char == '/' || last_char == '\\') => format!("{}/", self),
+            _ => self.to_string(),
         }
     }
 }
 
 impl CleanPaths for String {
     fn expand_tilde(&self) -> String {
-        if self.chars().next().unwrap() == '~' {
-            self.replace("~", &dirs_next::home_dir().unwrap().display().to_string())
-        } else {
-            self.to_string()
-        }
+        self.as_str().expand_tilde()
     }
     fn add_trailing_slash(&self) -> String {
-        let last_char = self.chars().last().unwrap();
-        if !(last_char == '/' || last_char == '\\') {
-            format!("{}/", self)
-        } else {
-            self.to_string()
-        }
+        self.as_str().add_trailing_slash()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::get_app;
+    use super::{
+        get_app, get_arweave, get_bundle_size, get_exclude_patterns, get_network_params,
+        get_paths_vec, get_tags_vec, is_valid_tag, run, split_tag,
+    };
     use crate::CleanPaths;
-    use arloader::error::Error;
-    use clap::{value_t, ErrorKind};
+    use arloader::{
+        error::Error,
+        transaction::Tag,
+        utils::{filter_paths_excluded, NFT_ASSET_EXCLUDES},
+        FilesLink, ImageLink,
+    };
+    use clap::{value_t, App, ErrorKind, SubCommand};
+    use matches::assert_matches;
+    use std::path::PathBuf;
 
     #[test]
     fn estimate() {
@@ -1098,6 +2454,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn network_params() -> Result<(), Error> {
+        let m = get_app().get_matches_from(vec![
+            "arloader",
+            "--block-size",
+            "1000",
+            "--max-tx-body",
+            "500000",
+            "--max-bundle-size",
+            "50",
+            "estimate",
+            "tests/fixtures/0.png",
+        ]);
+        let network_params = get_network_params(&m)?;
+        assert_eq!(network_params.block_size, 1000);
+        assert_eq!(network_params.max_tx_body, 500_000);
+        assert_eq!(network_params.max_bundle_size, 50_000_000);
+        assert_eq!(get_arweave(&m)?.network_params, network_params);
+
+        // The default bundle size of 100 MB is larger than the maximum.
+        let sub_m = m.subcommand_matches("estimate").unwrap();
+        assert_matches!(
+            get_bundle_size(sub_m, &network_params),
+            Err(Error::InvalidArgument { .. })
+        );
+
+        let m = get_app().get_matches_from(vec!["arloader", "estimate", "tests/fixtures/0.png"]);
+        let network_params = get_network_params(&m)?;
+        assert_eq!(network_params, NetworkParams::default());
+        let sub_m = m.subcommand_matches("estimate").unwrap();
+        assert_eq!(get_bundle_size(sub_m, &network_params)?, 100_000_000);
+
+        let resp = get_app().get_matches_from_safe(vec![
+            "arloader",
+            "--block-size",
+            "0",
+            "estimate",
+            "tests/fixtures/0.png",
+        ]);
+        assert_eq!(resp.unwrap_err().kind, ErrorKind::ValueValidation);
+        Ok(())
+    }
+
     #[test]
     fn upload() -> Result<(), Error> {
         std::env::remove_var("AR_KEYPAIR_PATH");
@@ -1192,6 +2591,24 @@ mod tests {
             "tests/fixtures/solana_test.json"
         );
 
+        // upload-one takes a tip when paying with SOL, too.
+        let m = get_app().get_matches_from(vec![
+            "arloader",
+            "upload-one",
+            "tests/fixtures/0.png",
+            "--with-sol",
+            "--sol-keypair-path",
+            "tests/fixtures/solana_test.json",
+            "--ar-default-keypair",
+            "--tip",
+            "MlV6DeOtRmakDOf6vgOBlif795tcWimgyPsYYNQ8q1Y:0.01",
+        ]);
+        let sub_m = m.subcommand_matches("upload-one").unwrap();
+        assert_eq!(
+            sub_m.value_of("tip").unwrap(),
+            "MlV6DeOtRmakDOf6vgOBlif795tcWimgyPsYYNQ8q1Y:0.01"
+        );
+
         Ok(())
     }
 
@@ -1303,6 +2720,68 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn bundle_tags() {
+        let m = get_app().get_matches_from(vec![
+            "arloader",
+            "upload",
+            "tests/fixtures/0.png",
+            "--ar-default-keypair",
+            "--bundle-tag",
+            "File-Count:{count}",
+            "--bundle-tag",
+            "Content-Hash:{items_hash}",
+        ]);
+        let sub_m = m.subcommand_matches("upload").unwrap();
+        assert_eq!(
+            sub_m.values_of("bundle_tags").unwrap().collect::<Vec<_>>(),
+            vec!["File-Count:{count}", "Content-Hash:{items_hash}"]
+        );
+
+        for args in [
+            vec!["--bundle-tag", "File-Size:{size}"],
+            vec!["--bundle-tag", "File-Count:{count}", "--no-bundle"],
+        ] {
+            let resp = get_app().get_matches_from_safe(
+                [
+                    "arloader",
+                    "upload",
+                    "tests/fixtures/0.png",
+                    "--ar-default-keypair",
+                ]
+                .into_iter()
+                .chain(args),
+            );
+            assert!(resp.is_err());
+        }
+    }
+
+    #[test]
+    fn update_metadata_links() {
+        let links = |args: &[&str]| {
+            let m = get_app().get_matches_from(
+                [
+                    "arloader",
+                    "update-metadata",
+                    "--manifest-path",
+                    "manifest.json",
+                ]
+                .iter()
+                .chain(args),
+            );
+            let sub_m = m.subcommand_matches("update-metadata").unwrap();
+            (
+                value_t!(sub_m, "image_link", ImageLink).unwrap(),
+                value_t!(sub_m, "files_link", FilesLink).unwrap(),
+            )
+        };
+        assert_eq!(links(&[]), (ImageLink::Id, FilesLink::Both));
+        assert_eq!(
+            links(&["--image-link", "file", "--files-link", "id"]),
+            (ImageLink::File, FilesLink::Id)
+        );
+    }
+
     #[test]
     fn update_status() {
         let m = get_app().get_matches_from(vec!["arloader", "update-status", "tests/"]);
@@ -1311,11 +2790,281 @@ mod tests {
         assert_eq!(sub_m.value_of("log_dir").unwrap(), "tests/");
     }
 
+    #[test]
+    fn base_url_arweave() {
+        for args in [
+            vec!["get-status", "LCwsLCwsLA"],
+            vec!["update-status", "tests/"],
+        ] {
+            let m = get_app().get_matches_from(
+                ["arloader", "--base-url", "http://localhost:1984"]
+                    .into_iter()
+                    .chain(args.clone()),
+            );
+            assert_eq!(
+                get_arweave(&m).unwrap().base_url.as_str(),
+                "http://localhost:1984/"
+            );
+
+            let m = get_app().get_matches_from(["arloader"].into_iter().chain(args));
+            assert_eq!(
+                get_arweave(&m).unwrap().base_url.as_str(),
+                "https://arweave.net/"
+            );
+        }
+    }
+
     #[test]
     fn tilde_expansion() {
+        let home = dirs_next::home_dir().unwrap();
         assert_eq!(
-            dirs_next::home_dir().unwrap().join("tests/"),
+            home.join("tests/"),
             std::path::PathBuf::from("~/tests/".expand_tilde())
         );
+        assert_eq!("~".expand_tilde(), home.display().to_string());
+        assert_eq!(PathBuf::from("~/x".expand_tilde()), home.join("x"));
+        assert_eq!(String::from("~/x").expand_tilde(), "~/x".expand_tilde());
+        for unchanged in [
+            "a~b",
+            "./my~file/",
+            "data/~backup",
+            "~backup",
+            "",
+            "C:\\Users\\x",
+        ] {
+            assert_eq!(unchanged.expand_tilde(), unchanged);
+        }
+    }
+
+    #[test]
+    fn trailing_slash() {
+        assert_eq!("".add_trailing_slash(), "");
+        assert_eq!("a~b".add_trailing_slash(), "a~b/");
+        assert_eq!("tests/".add_trailing_slash(), "tests/");
+        assert_eq!("C:\\Users\\x\\".add_trailing_slash(), "C:\\Users\\x\\");
+        assert_eq!(String::from("tests").add_trailing_slash(), "tests/");
+    }
+
+    #[test]
+    fn exclude() -> Result<(), Error> {
+        let paths: Vec<PathBuf> = ["0.png", "0.json", ".DS_Store"]
+            .iter()
+            .map(|p| PathBuf::from("tests/fixtures").join(p))
+            .collect();
+
+        let m = get_app().get_matches_from(vec![
+            "arloader",
+            "estimate",
+            "tests/fixtures/0.png",
+            "--exclude",
+            "*.DS_Store",
+            "--exclude",
+            "*.JSON",
+        ]);
+        let sub_m = m.subcommand_matches("estimate").unwrap();
+        let patterns = get_exclude_patterns(sub_m, &[])?;
+        assert_eq!(patterns.len(), 2);
+        assert_eq!(
+            filter_paths_excluded(paths.clone(), patterns).collect::<Vec<PathBuf>>(),
+            paths[..2].to_vec()
+        );
+        assert_eq!(
+            sub_m
+                .values_of("file_paths")
+                .unwrap()
+                .collect::<Vec<&str>>(),
+            vec!["tests/fixtures/0.png"]
+        );
+
+        // Metadata files are left out of nft assets without --exclude.
+        let m = get_app().get_matches_from(vec![
+            "arloader",
+            "upload-nfts",
+            "tests/fixtures/0.png",
+            "--with-sol",
+            "--sol-keypair-path",
+            "tests/fixtures/solana_test.json",
+            "--ar-default-keypair",
+        ]);
+        let sub_m = m.subcommand_matches("upload-nfts").unwrap();
+        let patterns = get_exclude_patterns(sub_m, &NFT_ASSET_EXCLUDES)?;
+        assert_eq!(
+            filter_paths_excluded(paths.clone(), patterns).collect::<Vec<PathBuf>>(),
+            vec![paths[0].clone(), paths[2].clone()]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn newer_than() -> Result<(), Error> {
+        // Files are collected the same way for every subcommand that takes <FILE_PATHS>.
+        for args in [
+            vec!["estimate"],
+            vec!["plan"],
+            vec!["queue", "add", "--queue-dir", "tests/fixtures/"],
+            vec![
+                "upload",
+                "--ar-default-keypair",
+                "--log-dir",
+                "tests/fixtures/",
+            ],
+            vec![
+                "upload-nfts",
+                "--ar-default-keypair",
+                "--log-dir",
+                "tests/fixtures/",
+            ],
+        ] {
+            for (newer_than, expected) in [("1970-01-01T00:00:00Z", 1), ("2999-01-01T00:00:00Z", 0)]
+            {
+                let m = get_app().get_matches_from(
+                    [vec!["arloader"], args.clone()]
+                        .concat()
+                        .into_iter()
+                        .chain(["tests/fixtures/0.png", "--newer-than", newer_than]),
+                );
+                let (_, sub_m) = m.subcommand();
+                let sub_m = match sub_m.unwrap().subcommand() {
+                    ("add", Some(add_m)) => add_m,
+                    _ => sub_m.unwrap(),
+                };
+                assert_eq!(get_paths_vec(sub_m, None, &[]).await?.len(), expected);
+            }
+        }
+
+        // @last-run needs a log dir, which plan doesn't have.
+        let m = get_app().get_matches_from(vec![
+            "arloader",
+            "plan",
+            "tests/fixtures/0.png",
+            "--newer-than",
+            "@last-run",
+        ]);
+        let sub_m = m.subcommand_matches("plan").unwrap();
+        assert_matches!(
+            get_paths_vec(sub_m, None, &[]).await,
+            Err(Error::MissingArgument(name)) if name == "log_dir"
+        );
+
+        let resp = get_app().get_matches_from_safe(vec![
+            "arloader",
+            "upload-nfts",
+            "tests/fixtures/0.png",
+            "--ar-default-keypair",
+            "--newer-than",
+            "@last-run",
+        ]);
+        assert_eq!(resp.unwrap_err().kind, ErrorKind::MissingRequiredArgument);
+        Ok(())
+    }
+
+    #[test]
+    fn log_dir_write() {
+        let upload = |log_dir: &str| {
+            get_app().get_matches_from_safe(vec![
+                "arloader",
+                "upload",
+                "tests/fixtures/0.png",
+                "--ar-keypair-path",
+                "tests/fixtures/arweave-keyfile-MlV6DeOtRmakDOf6vgOBlif795tcWimgyPsYYNQ8q1Y.json",
+                "--log-dir",
+                log_dir,
+            ])
+        };
+
+        // Created by the command if it doesn't exist.
+        let m = upload("tests/does/not/exist/").unwrap();
+        let sub_m = m.subcommand_matches("upload").unwrap();
+        assert_eq!(sub_m.value_of("log_dir").unwrap(), "tests/does/not/exist/");
+
+        assert!(upload("tests/fixtures/").is_ok());
+        assert_eq!(
+            upload("tests/fixtures/0.png").unwrap_err().kind,
+            ErrorKind::ValueValidation
+        );
+
+        // Read mode still requires an existing directory.
+        let resp = get_app().get_matches_from_safe(vec![
+            "arloader",
+            "update-status",
+            "tests/does/not/exist/",
+        ]);
+        assert_eq!(resp.unwrap_err().kind, ErrorKind::ValueValidation);
+    }
+
+    #[test]
+    fn tags() {
+        assert!(is_valid_tag("App-Name:arloader").is_ok());
+        assert!(is_valid_tag("App-Name").is_err());
+        assert!(is_valid_tag(format!("Name:{}", "v".repeat(3073)))
+            .unwrap_err()
+            .contains("tag value of \"Name\" is 3073 bytes"));
+
+        let upload = |n: usize| {
+            let tags: Vec<String> = (0..n).map(|i| format!("Index:{}", i)).collect();
+            let mut args = vec![
+                "arloader",
+                "upload",
+                "tests/fixtures/0.png",
+                "--ar-keypair-path",
+                "tests/fixtures/arweave-keyfile-MlV6DeOtRmakDOf6vgOBlif795tcWimgyPsYYNQ8q1Y.json",
+                "--tags",
+            ];
+            args.extend(tags.iter().map(String::as_str));
+            get_app().get_matches_from_safe(args)
+        };
+        assert!(upload(126).is_ok());
+        assert!(upload(127).is_err());
+    }
+
+    #[test]
+    fn tags_with_colons() {
+        let pair = |n: &str, v: &str| Some((n.to_string(), v.to_string()));
+        assert_eq!(split_tag("Key:https://x"), pair("Key", "https://x"));
+        assert_eq!(split_tag("Key:"), pair("Key", ""));
+        assert_eq!(split_tag("Ns::Key:a:b"), pair("Ns:Key", "a:b"));
+        assert_eq!(split_tag("NoColon"), None);
+        assert_eq!(split_tag("Ns::Key"), None);
+        assert_eq!(split_tag(":value"), None);
+
+        let upload = |tag: &str| {
+            get_app().get_matches_from_safe(vec![
+                "arloader",
+                "upload",
+                "tests/fixtures/0.png",
+                "--ar-keypair-path",
+                "tests/fixtures/arweave-keyfile-MlV6DeOtRmakDOf6vgOBlif795tcWimgyPsYYNQ8q1Y.json",
+                "--tags",
+                tag,
+            ])
+        };
+        let m = upload("External-Url:https://example.com").unwrap();
+        let sub_m = m.subcommand_matches("upload").unwrap();
+        let tags: Vec<Tag<String>> = get_tags_vec(sub_m.values_of("tags").unwrap());
+        assert_eq!(tags[0].name, "External-Url");
+        assert_eq!(tags[0].value, "https://example.com");
+        assert!(upload("Key:").is_ok());
+        assert_eq!(
+            upload("NoColon").unwrap_err().kind,
+            ErrorKind::ValueValidation
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_subcommand() {
+        let resp = get_app().get_matches_from_safe(vec!["arloader", "bogus"]);
+        assert_eq!(resp.unwrap_err().kind, ErrorKind::UnrecognizedSubcommand);
+
+        // A subcommand the parser accepts but isn't handled.
+        let m = get_app()
+            .subcommand(SubCommand::with_name("bogus"))
+            .get_matches_from(vec!["arloader", "bogus"]);
+        assert_matches!(run(&m).await, Err(Error::UnknownSubcommand(s)) if s == "bogus");
+
+        // A handled subcommand whose arguments aren't configured.
+        let m = App::new("arloader")
+            .subcommand(SubCommand::with_name("get-status"))
+            .get_matches_from(vec!["arloader", "get-status"]);
+        assert_matches!(run(&m).await, Err(Error::MissingArgument(_)));
     }
 }
@@ -1,7 +1,8 @@
 //! Functionality for chunking file data and calculating and verifying root ids.
 
-use crate::{crypto::Provider, error::Error};
+use crate::{crypto::Hasher, error::Error};
 use borsh::BorshDeserialize;
+use std::io::{self, BufReader, Read, Write};
 
 /// Single struct used for original data chunks (Leaves) and branch nodes (hashes of pairs of child nodes).
 #[derive(Debug, PartialEq, Clone)]
@@ -70,6 +71,12 @@ pub const MIN_CHUNK_SIZE: usize = 32 * 1024;
 pub const HASH_SIZE: usize = 32;
 const NOTE_SIZE: usize = 32;
 
+/// Number of chunks that `data_len` bytes of data are split into for upload.
+pub fn chunks_len(data_len: u64) -> u64 {
+    let max_chunk_size = MAX_CHUNK_SIZE as u64;
+    data_len / max_chunk_size + (data_len % max_chunk_size != 0) as u64
+}
+
 /// Includes a function to convert a number to a Vec of 32 bytes per the Arweave spec.
 pub trait Helpers<T> {
     fn to_note_vec(&self) -> Vec<u8>;
@@ -83,48 +90,136 @@ impl Helpers<usize> for usize {
     }
 }
 
+/// Byte ranges of the chunks that `data_len` bytes of data are split into. Chunks are
+/// [`MAX_CHUNK_SIZE`] bytes, except that a last chunk smaller than [`MIN_CHUNK_SIZE`] is evened
+/// out with the one before it, and a zero length chunk is added after a full last chunk.
+pub fn chunk_ranges(data_len: usize) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = (0..data_len)
+        .step_by(MAX_CHUNK_SIZE)
+        .map(|start| (start, data_len.min(start + MAX_CHUNK_SIZE)))
+        .collect();
+
+    if ranges.len() > 1 && ranges.last().map_or(0, |(s, e)| e - s) < MIN_CHUNK_SIZE {
+        ranges.pop();
+        let (start, _) = ranges.pop().unwrap();
+        let last_two_len = data_len - start;
+        let chunk_size = last_two_len / 2 + (last_two_len % 2 != 0) as usize;
+        ranges.push((start, start + chunk_size));
+        ranges.push((start + chunk_size, data_len));
+    }
+
+    match ranges.last() {
+        Some((start, end)) if end - start < MAX_CHUNK_SIZE => {}
+        _ => ranges.push((data_len, data_len)),
+    }
+    ranges
+}
+
+/// Hashes a single leaf node for `chunk`, which starts at `min_byte_range` in the data.
+pub fn hash_leaf(chunk: &[u8], min_byte_range: usize, hasher: &Hasher) -> Result<Node, Error> {
+    let data_hash = hasher.hash_sha256(chunk)?;
+    let max_byte_range = min_byte_range + chunk.len();
+    let offset = max_byte_range.to_note_vec();
+    let id = hasher.hash_all_sha256(vec![&data_hash, &offset])?;
+
+    Ok(Node {
+        id,
+        data_hash: Some(data_hash),
+        min_byte_range,
+        max_byte_range,
+        left_child: None,
+        right_child: None,
+    })
+}
+
 /// Generates data chunks from which the calculation of root id starts.
-pub fn generate_leaves(data: Vec<u8>, crypto: &Provider) -> Result<Vec<Node>, Error> {
-    let mut data_chunks: Vec<&[u8]> = data.chunks(MAX_CHUNK_SIZE).collect();
+pub fn generate_leaves(data: Vec<u8>, hasher: &Hasher) -> Result<Vec<Node>, Error> {
+    chunk_ranges(data.len())
+        .into_iter()
+        .map(|(start, end)| hash_leaf(&data[start..end], start, hasher))
+        .collect()
+}
+
+/// Same as [`generate_leaves`] for `data_len` bytes read from `reader`, e.g., a file, reading
+/// [`MAX_CHUNK_SIZE`] bytes at a time so that the data doesn't have to be held in memory.
+pub fn generate_leaves_from_reader<R: Read>(
+    reader: R,
+    data_len: usize,
+    hasher: &Hasher,
+) -> Result<Vec<Node>, Error> {
+    let mut reader = BufReader::with_capacity(MAX_CHUNK_SIZE, reader.take(data_len as u64));
+    let mut writer = LeavesWriter::new(io::sink(), data_len, hasher);
+    io::copy(&mut reader, &mut writer)?;
+    let (_, leaves) = writer.finish()?;
+    Ok(leaves)
+}
 
-    #[allow(unused_assignments)]
-    let mut last_two = Vec::new();
+/// Passes data through to `inner`, generating the same leaves as [`generate_leaves`] as it is
+/// written, so that data written to a file doesn't also have to be held in memory to be chunked.
+/// The length of the data has to be known in advance, since it determines the size of the last
+/// two chunks.
+pub struct LeavesWriter<'a, W> {
+    inner: W,
+    hasher: &'a Hasher,
+    ranges: std::vec::IntoIter<(usize, usize)>,
+    next_range: Option<(usize, usize)>,
+    buf: Vec<u8>,
+    leaves: Vec<Node>,
+}
 
-    if data_chunks.len() > 1 && data_chunks.last().unwrap().len() < MIN_CHUNK_SIZE {
-        last_two = data_chunks.split_off(data_chunks.len() - 2).concat();
-        let chunk_size = last_two.len() / 2 + (last_two.len() % 2 != 0) as usize;
-        data_chunks.append(&mut last_two.chunks(chunk_size).collect::<Vec<&[u8]>>());
+impl<'a, W: Write> LeavesWriter<'a, W> {
+    pub fn new(inner: W, data_len: usize, hasher: &'a Hasher) -> Self {
+        let mut ranges = chunk_ranges(data_len).into_iter();
+        let next_range = ranges.next();
+        Self {
+            inner,
+            hasher,
+            ranges,
+            next_range,
+            buf: Vec::with_capacity(MAX_CHUNK_SIZE),
+            leaves: Vec::new(),
+        }
     }
 
-    if data_chunks.last().unwrap().len() == MAX_CHUNK_SIZE {
-        data_chunks.push(&[]);
+    /// Flushes `inner` and returns it with the leaves, or [`Error::InvalidDataLength`] if fewer
+    /// bytes were written than the data length given to [`LeavesWriter::new`].
+    pub fn finish(mut self) -> Result<(W, Vec<Node>), Error> {
+        // Hashes a trailing zero length leaf that no write has reached yet, e.g., for empty data.
+        self.write(&[])?;
+        self.inner.flush()?;
+        if self.next_range.is_some() {
+            return Err(Error::InvalidDataLength);
+        }
+        Ok((self.inner, self.leaves))
     }
+}
 
-    let mut leaves = Vec::<Node>::new();
-    let mut min_byte_range = 0;
-    for chunk in data_chunks.into_iter() {
-        let data_hash = crypto.hash_sha256(chunk)?;
-        let max_byte_range = min_byte_range + &chunk.len();
-        let offset = max_byte_range.to_note_vec();
-        let id = crypto.hash_all_sha256(vec![&data_hash, &offset])?;
+impl<'a, W: Write> Write for LeavesWriter<'a, W> {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        self.inner.write_all(data)?;
+        self.buf.extend_from_slice(data);
+        while let Some((start, end)) = self.next_range {
+            if self.buf.len() < end - start {
+                break;
+            }
+            let leaf = hash_leaf(&self.buf[..end - start], start, self.hasher)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+            self.leaves.push(leaf);
+            self.buf.drain(..end - start);
+            self.next_range = self.ranges.next();
+        }
+        Ok(data.len())
+    }
 
-        leaves.push(Node {
-            id,
-            data_hash: Some(data_hash),
-            min_byte_range,
-            max_byte_range,
-            left_child: None,
-            right_child: None,
-        });
-        min_byte_range = min_byte_range + &chunk.len();
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
     }
-    Ok(leaves)
 }
 
 /// Hashes together a single branch node from a pair of child nodes.
-pub fn hash_branch(left: Node, right: Node, crypto: &Provider) -> Result<Node, Error> {
+pub fn hash_branch(left: Node, right: Node, hasher: &Hasher) -> Result<Node, Error> {
     let max_byte_range = left.max_byte_range.to_note_vec();
-    let id = crypto.hash_all_sha256(vec![&left.id, &right.id, &max_byte_range])?;
+    let id = hasher.hash_all_sha256(vec![&left.id, &right.id, &max_byte_range])?;
     Ok(Node {
         id,
         data_hash: None,
@@ -136,12 +231,12 @@ pub fn hash_branch(left: Node, right: Node, crypto: &Provider) -> Result<Node, E
 }
 
 /// Builds one layer of branch nodes from a layer of child nodes.
-pub fn build_layer<'a>(nodes: Vec<Node>, crypto: &Provider) -> Result<Vec<Node>, Error> {
+pub fn build_layer<'a>(nodes: Vec<Node>, hasher: &Hasher) -> Result<Vec<Node>, Error> {
     let mut layer = Vec::<Node>::with_capacity(nodes.len() / 2 + (nodes.len() % 2 != 0) as usize);
     let mut nodes_iter = nodes.into_iter();
     while let Some(left) = nodes_iter.next() {
         if let Some(right) = nodes_iter.next() {
-            layer.push(hash_branch(left, right, &crypto).unwrap());
+            layer.push(hash_branch(left, right, &hasher).unwrap());
         } else {
             layer.push(left);
         }
@@ -150,14 +245,30 @@ pub fn build_layer<'a>(nodes: Vec<Node>, crypto: &Provider) -> Result<Vec<Node>,
 }
 
 /// Builds all layers from leaves up to single root node.
-pub fn generate_data_root(mut nodes: Vec<Node>, crypto: &Provider) -> Result<Node, Error> {
+pub fn generate_data_root(mut nodes: Vec<Node>, hasher: &Hasher) -> Result<Node, Error> {
     while nodes.len() > 1 {
-        nodes = build_layer(nodes, &crypto)?;
+        nodes = build_layer(nodes, &hasher)?;
     }
     let root = nodes.pop().unwrap();
     Ok(root)
 }
 
+/// Root of `leaves` along with the leaves and their proofs that are uploaded as chunks, leaving
+/// out the last leaf and its proof if it is zero length.
+pub fn chunks_and_proofs(
+    mut leaves: Vec<Node>,
+    hasher: &Hasher,
+) -> Result<(Node, Vec<Node>, Vec<Proof>), Error> {
+    let root = generate_data_root(leaves.clone(), hasher)?;
+    let mut proofs = resolve_proofs(root.clone(), None)?;
+    let last_leaf = leaves.last().unwrap();
+    if last_leaf.max_byte_range == last_leaf.min_byte_range {
+        leaves.pop();
+        proofs.pop();
+    }
+    Ok((root, leaves, proofs))
+}
+
 /// Calculates [`Proof`] for each data chunk contained in root [`Node`].
 pub fn resolve_proofs(node: Node, proof: Option<Proof>) -> Result<Vec<Proof>, Error> {
     let mut proof = if let Some(proof) = proof {
@@ -208,7 +319,7 @@ pub fn validate_chunk(
     mut root_id: [u8; HASH_SIZE],
     chunk: Node,
     proof: Proof,
-    crypto: &Provider,
+    hasher: &Hasher,
 ) -> Result<(), Error> {
     match chunk {
         Node {
@@ -232,7 +343,7 @@ pub fn validate_chunk(
             // Validate branches.
             for branch_proof in branch_proofs.iter() {
                 // Calculate the id from the proof.
-                let id = crypto.hash_all_sha256(vec![
+                let id = hasher.hash_all_sha256(vec![
                     &branch_proof.left_id,
                     &branch_proof.right_id,
                     &branch_proof.offset().to_note_vec(),
@@ -252,7 +363,7 @@ pub fn validate_chunk(
             }
 
             // Validate leaf: both id and data_hash are correct.
-            let id = crypto.hash_all_sha256(vec![&data_hash, &max_byte_range.to_note_vec()])?;
+            let id = hasher.hash_all_sha256(vec![&data_hash, &max_byte_range.to_note_vec()])?;
             if !(id == root_id) & !(data_hash == leaf_proof.data_hash) {
                 return Err(Error::InvalidProof.into());
             }
@@ -267,18 +378,15 @@ pub fn validate_chunk(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::transaction::Base64;
-    use std::{path::PathBuf, str::FromStr};
+    use crate::{crypto::Provider, transaction::Base64};
+    use std::str::FromStr;
     use tokio::fs;
 
     #[tokio::test]
     async fn test_generate_leaves() -> Result<(), Error> {
-        let crypto = Provider::from_keypair_path(PathBuf::from(
-            "tests/fixtures/arweave-key-7eV1qae4qVNqsNChg3Scdi-DpOLJPCogct4ixoq1WNg.json",
-        ))
-        .await?;
+        let hasher = Hasher::new();
         let data = fs::read("tests/fixtures/1mb.bin").await?;
-        let leaves: Vec<Node> = generate_leaves(data, &crypto)?;
+        let leaves: Vec<Node> = generate_leaves(data, &hasher)?;
         assert_eq!(
             leaves[1],
             Node {
@@ -301,20 +409,17 @@ mod tests {
 
     #[tokio::test]
     async fn test_hash_branch() -> Result<(), Error> {
-        let crypto = Provider::from_keypair_path(PathBuf::from(
-            "tests/fixtures/arweave-key-7eV1qae4qVNqsNChg3Scdi-DpOLJPCogct4ixoq1WNg.json",
-        ))
-        .await?;
+        let hasher = Hasher::new();
 
         let data = fs::read("tests/fixtures/1mb.bin").await?;
-        let leaves: Vec<Node> = generate_leaves(data, &crypto)?;
+        let leaves: Vec<Node> = generate_leaves(data, &hasher)?;
         let mut nodes_iter = leaves.into_iter();
         let left = nodes_iter.next().unwrap();
         let right = nodes_iter.next().unwrap();
         let left_clone = left.clone();
         let right_clone = right.clone();
 
-        let branch = hash_branch(left, right, &crypto)?;
+        let branch = hash_branch(left, right, &hasher)?;
         assert_eq!(
             branch,
             Node {
@@ -333,13 +438,10 @@ mod tests {
     }
     #[tokio::test]
     async fn test_build_layer() -> Result<(), Error> {
-        let crypto = Provider::from_keypair_path(PathBuf::from(
-            "tests/fixtures/arweave-key-7eV1qae4qVNqsNChg3Scdi-DpOLJPCogct4ixoq1WNg.json",
-        ))
-        .await?;
+        let hasher = Hasher::new();
         let data = fs::read("tests/fixtures/1mb.bin").await?;
-        let leaves: Vec<Node> = generate_leaves(data, &crypto)?;
-        let layer = build_layer(leaves, &crypto)?;
+        let leaves: Vec<Node> = generate_leaves(data, &hasher)?;
+        let layer = build_layer(leaves, &hasher)?;
         assert_eq!(
             layer[0].id,
             [
@@ -354,23 +456,23 @@ mod tests {
 
     #[tokio::test]
     async fn test_generate_data_root_even_chunks() -> Result<(), Error> {
-        let crypto = Provider::default();
+        let hasher = Hasher::new();
         let data = fs::read("tests/fixtures/1mb.bin").await?;
         // root id as calculate by arweave-js
         let root_actual = Base64::from_str("o1tTTjbC7hIZN6KbUUYjlkQoDl2k8VXNuBDcGIs52Hc")?;
-        let leaves: Vec<Node> = generate_leaves(data, &crypto)?;
-        let root = generate_data_root(leaves, &crypto)?;
+        let leaves: Vec<Node> = generate_leaves(data, &hasher)?;
+        let root = generate_data_root(leaves, &hasher)?;
         assert_eq!(root.id, root_actual.0.as_ref());
         Ok(())
     }
 
     #[tokio::test]
     async fn test_generate_proof() -> Result<(), Error> {
-        let crypto = Provider::default();
+        let hasher = Hasher::new();
         let proof_actual = Base64::from_str("7EAC9FsACQRwe4oIzu7Mza9KjgWKT4toYxDYGjWrCdp0QgsrYS6AueMJ_rM6ZEGslGqjUekzD3WSe7B5_fwipgAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAACAAAnH6dASdQCigcL43lp0QclqBaSncF4TspuvxoFbn2L18EXpQrP1wkbwdIjSSWQQRt_F31yNvxtc09KkPFtzMKAwAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABAAAIHiHU9QwOImFzjqSlfxkJJCtSbAox6TbbFhQvlEapSgAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAQAAA")?;
         let data = fs::read("tests/fixtures/rebar3").await?;
-        let leaves: Vec<Node> = generate_leaves(data, &crypto)?;
-        let root = generate_data_root(leaves, &crypto)?;
+        let leaves: Vec<Node> = generate_leaves(data, &hasher)?;
+        let root = generate_data_root(leaves, &hasher)?;
 
         let proofs = resolve_proofs(root, None)?;
         assert_eq!(
@@ -384,62 +486,80 @@ mod tests {
     }
     #[tokio::test]
     async fn test_validate_chunks() -> Result<(), Error> {
-        let crypto = Provider::default();
+        let hasher = Hasher::new();
         let data = fs::read("tests/fixtures/1mb.bin").await?;
-        let leaves: Vec<Node> = generate_leaves(data, &crypto)?;
-        let root = generate_data_root(leaves.clone(), &crypto)?;
+        let leaves: Vec<Node> = generate_leaves(data, &hasher)?;
+        let root = generate_data_root(leaves.clone(), &hasher)?;
         let root_id = root.id.clone();
         let proofs = resolve_proofs(root, None)?;
         println!("proofs_len: {}", proofs.len());
         assert_eq!(leaves.len(), proofs.len());
 
         for (chunk, proof) in leaves.into_iter().zip(proofs.into_iter()) {
-            assert_eq!((), validate_chunk(root_id.clone(), chunk, proof, &crypto)?);
+            assert_eq!((), validate_chunk(root_id.clone(), chunk, proof, &hasher)?);
         }
         Ok(())
     }
 
     #[tokio::test]
     async fn test_valid_root() -> Result<(), Error> {
-        let crypto = Provider::default();
+        let hasher = Hasher::new();
         let data_root_actual = Base64::from_str("t-GCOnjPWxdox950JsrFMu3nzOE4RktXpMcIlkqSUTw")?;
         let data = fs::read("tests/fixtures/rebar3").await?;
-        let leaves: Vec<Node> = generate_leaves(data, &crypto)?;
-        let root = generate_data_root(leaves.clone(), &crypto)?;
+        let leaves: Vec<Node> = generate_leaves(data, &hasher)?;
+        let root = generate_data_root(leaves.clone(), &hasher)?;
         assert_eq!(root.id.to_vec(), data_root_actual.0);
         Ok(())
     }
 
     #[tokio::test]
     async fn test_valid_root_even_chunks() -> Result<(), Error> {
-        let crypto = Provider::default();
+        let hasher = Hasher::new();
         let data = fs::read("tests/fixtures/1mb.bin").await?;
         // root id as calculate by arweave-js
         let root_actual = Base64::from_str("o1tTTjbC7hIZN6KbUUYjlkQoDl2k8VXNuBDcGIs52Hc")?;
-        let leaves: Vec<Node> = generate_leaves(data, &crypto)?;
-        let root = generate_data_root(leaves, &crypto)?;
+        let leaves: Vec<Node> = generate_leaves(data, &hasher)?;
+        let root = generate_data_root(leaves, &hasher)?;
         assert_eq!(root.id, root_actual.0.as_ref());
         Ok(())
     }
 
     #[test]
     fn test_valid_root_small_last_chunk() -> Result<(), Error> {
-        let crypto = Provider::default();
+        let hasher = Hasher::new();
         let data = vec![0; 256 * 1024 + 1];
         // root id as calculate by arweave-js
         let root_actual = Base64::from_str("br1Vtl3TS_NGWdHmYqBh3-MxrlckoluHCZGmUZk-dJc")?;
-        let leaves: Vec<Node> = generate_leaves(data, &crypto)?;
-        let root = generate_data_root(leaves, &crypto)?;
+        let leaves: Vec<Node> = generate_leaves(data, &hasher)?;
+        let root = generate_data_root(leaves, &hasher)?;
         println!("{}", Base64(root.id.to_vec()));
         assert_eq!(root.id, root_actual.0.as_ref());
         Ok(())
     }
 
     #[tokio::test]
-    async fn test_even_chunks() -> Result<(), Error> {
+    async fn test_merkle_without_keypair() -> Result<(), Error> {
+        let data = fs::read("tests/fixtures/rebar3").await?;
+        let hasher = Hasher::new();
+        let leaves: Vec<Node> = generate_leaves(data.clone(), &hasher)?;
+        let root = generate_data_root(leaves.clone(), &hasher)?;
+        let root_id = root.id;
+        for (chunk, proof) in leaves.into_iter().zip(resolve_proofs(root, None)?) {
+            validate_chunk(root_id, chunk, proof, &hasher)?;
+        }
+
+        // Provider derefs to Hasher, giving the same root.
         let crypto = Provider::default();
-        let data = fs::read("tests/fixtures/1mb.bin").await?;
         let leaves: Vec<Node> = generate_leaves(data, &crypto)?;
+        assert_eq!(generate_data_root(leaves, &crypto)?.id, root_id);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_even_chunks() -> Result<(), Error> {
+        let hasher = Hasher::new();
+        let data = fs::read("tests/fixtures/1mb.bin").await?;
+        let leaves: Vec<Node> = generate_leaves(data, &hasher)?;
         println!("{:?}", leaves[4]);
         assert_eq!(leaves.len(), 5);
         Ok(())
@@ -447,11 +567,72 @@ mod tests {
 
     #[test]
     fn test_small_last_chunk() -> Result<(), Error> {
-        let crypto = Provider::default();
+        let hasher = Hasher::new();
         let data = vec![0; 256 * 1024 + 1];
-        let leaves: Vec<Node> = generate_leaves(data, &crypto)?;
+        let leaves: Vec<Node> = generate_leaves(data, &hasher)?;
         assert_eq!(131073, leaves[0].max_byte_range);
         assert_eq!(131072, leaves[1].max_byte_range - leaves[1].min_byte_range);
         Ok(())
     }
+
+    #[test]
+    fn test_chunk_ranges() {
+        for data_len in [
+            0,
+            1,
+            MIN_CHUNK_SIZE,
+            MAX_CHUNK_SIZE,
+            MAX_CHUNK_SIZE + 1,
+            MAX_CHUNK_SIZE + MIN_CHUNK_SIZE,
+            3 * MAX_CHUNK_SIZE + 7,
+        ] {
+            let ranges = chunk_ranges(data_len);
+            assert_eq!(ranges.first().unwrap().0, 0);
+            assert_eq!(ranges.last().unwrap().1, data_len);
+            assert!(ranges.windows(2).all(|w| w[0].1 == w[1].0));
+            assert!(ranges.iter().all(|(s, e)| e - s <= MAX_CHUNK_SIZE));
+        }
+        assert_eq!(chunk_ranges(0), vec![(0, 0)]);
+        assert_eq!(
+            chunk_ranges(MAX_CHUNK_SIZE),
+            vec![(0, MAX_CHUNK_SIZE), (MAX_CHUNK_SIZE, MAX_CHUNK_SIZE)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_leaves_writer() -> Result<(), Error> {
+        let hasher = Hasher::new();
+        let data = fs::read("tests/fixtures/rebar3").await?;
+        for piece_len in [1000, MAX_CHUNK_SIZE, data.len()] {
+            let mut writer = LeavesWriter::new(Vec::new(), data.len(), &hasher);
+            for piece in data.chunks(piece_len) {
+                writer.write_all(piece)?;
+            }
+            let (written, leaves) = writer.finish()?;
+            assert_eq!(written, data);
+            assert_eq!(leaves, generate_leaves(data.clone(), &hasher)?);
+        }
+
+        let mut writer = LeavesWriter::new(Vec::new(), data.len(), &hasher);
+        writer.write_all(&data[1..])?;
+        assert!(matches!(writer.finish(), Err(Error::InvalidDataLength)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_generate_leaves_from_reader() -> Result<(), Error> {
+        let hasher = Hasher::new();
+        let data = fs::read("tests/fixtures/rebar3").await?;
+        for len in [0, 1, MAX_CHUNK_SIZE, MAX_CHUNK_SIZE + 1, data.len()] {
+            assert_eq!(
+                generate_leaves_from_reader(&data[..len], len, &hasher)?,
+                generate_leaves(data[..len].to_vec(), &hasher)?
+            );
+        }
+        assert!(matches!(
+            generate_leaves_from_reader(&data[1..], data.len(), &hasher),
+            Err(Error::InvalidDataLength)
+        ));
+        Ok(())
+    }
 }
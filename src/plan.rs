@@ -0,0 +1,195 @@
+//! Upload plans, the bundles that files will be uploaded in, written out ahead of an upload so
+//! that they can be reviewed and then uploaded exactly as planned.
+
+use crate::{error::Error, status::data_hash, NetworkParams, PathsChunk, VERSION};
+use serde::{Deserialize, Serialize};
+use std::{fmt, path::PathBuf, str::FromStr};
+use tokio::fs;
+
+/// File in a [`PlannedBundle`] with its size and hash when the plan was made.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlannedFile {
+    pub file_path: PathBuf,
+    pub data_size: u64,
+    pub data_hash: String,
+}
+
+/// Bundle transaction of an [`UploadPlan`] with its files in the order they'll be bundled.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlannedBundle {
+    pub index: usize,
+    pub data_size: u64,
+    /// Reward in winstons estimated from the price terms of the plan. The reward paid is
+    /// calculated from the price when the plan is uploaded.
+    pub reward: u64,
+    pub files: Vec<PlannedFile>,
+}
+
+/// Bundles that files will be uploaded in, created with `arloader plan` and uploaded with
+/// `arloader upload --plan`. Displayed and parsed as json.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UploadPlan {
+    pub arloader_version: String,
+    /// Bundle size in bytes the files were chunked with.
+    pub bundle_size: u64,
+    pub price_terms: (u64, u64),
+    pub bundles: Vec<PlannedBundle>,
+}
+
+impl UploadPlan {
+    /// Records `path_chunks`, typically from [`crate::Arweave::chunk_file_paths`], along with the
+    /// size and hash of each file and the reward of each bundle with `network_params`.
+    pub async fn new(
+        path_chunks: &[PathsChunk],
+        bundle_size: u64,
+        price_terms: (u64, u64),
+        network_params: &NetworkParams,
+    ) -> Result<Self, Error> {
+        let mut bundles = Vec::with_capacity(path_chunks.len());
+        for (index, PathsChunk(paths, data_size)) in path_chunks.iter().enumerate() {
+            let mut files = Vec::with_capacity(paths.len());
+            for file_path in paths {
+                let data = fs::read(file_path).await?;
+                files.push(PlannedFile {
+                    file_path: file_path.clone(),
+                    data_size: data.len() as u64,
+                    data_hash: data_hash(&data),
+                });
+            }
+            bundles.push(PlannedBundle {
+                index,
+                data_size: *data_size,
+                reward: network_params.transaction_reward(*data_size, price_terms),
+                files,
+            });
+        }
+        Ok(Self {
+            arloader_version: VERSION.to_string(),
+            bundle_size,
+            price_terms,
+            bundles,
+        })
+    }
+
+    pub fn num_files(&self) -> usize {
+        self.bundles.iter().map(|b| b.files.len()).sum()
+    }
+
+    pub fn data_size(&self) -> u64 {
+        self.bundles.iter().map(|b| b.data_size).sum()
+    }
+
+    pub fn reward(&self) -> u64 {
+        self.bundles.iter().map(|b| b.reward).sum()
+    }
+
+    /// Checks that every file still has the size and hash recorded in the plan, returning the
+    /// bundles to upload in the same order, or [`Error::UploadPlanFileChanged`] for the first
+    /// file that doesn't.
+    pub async fn path_chunks(&self) -> Result<Vec<PathsChunk>, Error> {
+        let mut path_chunks = Vec::with_capacity(self.bundles.len());
+        for bundle in &self.bundles {
+            let mut paths = Vec::with_capacity(bundle.files.len());
+            for file in &bundle.files {
+                let changed = || Error::UploadPlanFileChanged(file.file_path.clone());
+                let metadata = fs::metadata(&file.file_path).await.map_err(|_| changed())?;
+                if metadata.len() != file.data_size
+                    || data_hash(&fs::read(&file.file_path).await?) != file.data_hash
+                {
+                    return Err(changed());
+                }
+                paths.push(file.file_path.clone());
+            }
+            path_chunks.push(PathsChunk(paths, bundle.data_size));
+        }
+        Ok(path_chunks)
+    }
+}
+
+impl fmt::Display for UploadPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json = serde_json::to_string_pretty(self).map_err(|_| fmt::Error)?;
+        writeln!(f, "{}", json)
+    }
+}
+
+impl FromStr for UploadPlan {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(serde_json::from_str(s)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UploadPlan;
+    use crate::{error::Error, transaction_reward, utils::TempDir, Arweave};
+    use matches::assert_matches;
+    use std::{path::PathBuf, str::FromStr};
+    use tokio::fs;
+    use url::Url;
+
+    #[tokio::test]
+    async fn test_upload_plan() -> Result<(), Error> {
+        let arweave = Arweave::from_keypair_path(
+            PathBuf::from("tests/fixtures/test_key0.json"),
+            Url::from_str("http://url.com").unwrap(),
+        )
+        .await?;
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let mut paths = Vec::new();
+        for i in 0..5 {
+            let path = temp_dir.0.join(format!("{}.png", i));
+            fs::copy(format!("tests/fixtures/{}.png", i), &path).await?;
+            paths.push(path);
+        }
+        let bundle_size =
+            arweave.data_item_overhead()? as u64 * 3 + fs::metadata(&paths[0]).await?.len() * 2;
+        let path_chunks = arweave.chunk_file_paths(paths.clone().into_iter(), bundle_size)?;
+        assert!(path_chunks.len() > 1);
+
+        let plan = UploadPlan::new(
+            &path_chunks,
+            bundle_size,
+            (1000, 100),
+            &arweave.network_params,
+        )
+        .await?;
+        assert_eq!(plan.num_files(), 5);
+        assert_eq!(plan.data_size(), 1796 + 1882 * 3 + 1537);
+        assert!(plan.bundles.iter().enumerate().all(|(i, b)| b.index == i
+            && b.reward
+                == arweave
+                    .network_params
+                    .transaction_reward(b.data_size, (1000, 100))));
+        let plan = UploadPlan::from_str(&plan.to_string())?;
+
+        let planned_chunks = plan.path_chunks().await?;
+        assert_eq!(planned_chunks.len(), path_chunks.len());
+        for (planned, chunk) in planned_chunks.iter().zip(&path_chunks) {
+            assert_eq!(planned.0, chunk.0);
+            assert_eq!(planned.1, chunk.1);
+        }
+
+        let mut data = fs::read(&paths[3]).await?;
+        data[100] ^= 1;
+        fs::write(&paths[3], &data).await?;
+        assert_matches!(
+            plan.path_chunks().await,
+            Err(Error::UploadPlanFileChanged(p)) if p == paths[3]
+        );
+
+        fs::write(&paths[3], b"shorter").await?;
+        assert_matches!(
+            plan.path_chunks().await,
+            Err(Error::UploadPlanFileChanged(p)) if p == paths[3]
+        );
+
+        fs::remove_file(&paths[1]).await?;
+        assert_matches!(
+            plan.path_chunks().await,
+            Err(Error::UploadPlanFileChanged(p)) if p == paths[1]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,524 @@
+//! Durable upload queue for long uploads over unreliable connections. Files are added to a
+//! journal in the queue directory with `arloader queue add` and uploaded in bundles with
+//! `arloader queue run`, which records each bundle in the journal before and as soon as it posts,
+//! so that a run that is interrupted, or a process that is restarted, resumes where it left off.
+
+use crate::{error::Error, transaction::Base64};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    path::{Path, PathBuf},
+};
+use tokio::{fs, io::AsyncWriteExt};
+
+/// Journal in the queue directory, with one [`JournalEntry`] per line.
+pub const QUEUE_JOURNAL_FILE: &str = "journal.ndjson";
+
+/// Sub directory of the queue directory that bundle statuses are written to, so that they can
+/// be updated with `arloader update-status`.
+pub const QUEUE_STATUS_DIR: &str = "status";
+
+/// Entry of the queue journal. The state of the queue is the result of replaying the entries in
+/// order.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum JournalEntry {
+    Add {
+        paths: Vec<PathBuf>,
+    },
+    /// Recorded before a bundle is posted, with the id it was signed with, so that a run that is
+    /// interrupted while posting it can check whether it was posted.
+    Posting {
+        paths: Vec<PathBuf>,
+        bundle_id: String,
+    },
+    Done {
+        paths: Vec<PathBuf>,
+        bundle_id: String,
+    },
+    Failed {
+        paths: Vec<PathBuf>,
+        error: String,
+    },
+    Retry {
+        paths: Vec<PathBuf>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueItemState {
+    Pending,
+    Done,
+    Failed,
+}
+
+/// File in an [`UploadQueue`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct QueueItem {
+    pub path: PathBuf,
+    pub state: QueueItemState,
+    /// Id of the bundle the file was uploaded in, once it's done, or is being posted in while
+    /// it's pending.
+    pub bundle_id: Option<String>,
+    /// Error of the last upload of the file, while it's failed.
+    pub error: Option<String>,
+}
+
+/// Number of files in an [`UploadQueue`] by state.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct QueueCounts {
+    pub pending: usize,
+    pub done: usize,
+    pub failed: usize,
+}
+
+/// Files to upload, replayed from the journal in a queue directory. Changes are appended to the
+/// journal before they are applied, and the journal is synced to disk before each change
+/// returns, so that a run that is dropped after a bundle posts can't lose the record of it. The
+/// journal is written with [`tokio::fs`], so that it doesn't block the runtime while bundles
+/// are posted.
+#[derive(Debug)]
+pub struct UploadQueue {
+    journal_path: PathBuf,
+    items: Vec<QueueItem>,
+    index: HashMap<PathBuf, usize>,
+    /// Entries in the journal, compared with the entries of a compacted journal to decide
+    /// whether compacting is worth it.
+    entries: usize,
+}
+
+impl UploadQueue {
+    /// Opens the queue in `queue_dir`, creating the directory if it doesn't exist. A partial
+    /// last line, left by a process killed while appending to the journal, is removed, while
+    /// any other line that can't be parsed is an [`Error::QueueJournalCorrupt`].
+    pub async fn open(queue_dir: &Path) -> Result<Self, Error> {
+        fs::create_dir_all(queue_dir).await?;
+        let mut queue = Self {
+            journal_path: queue_dir.join(QUEUE_JOURNAL_FILE),
+            items: Vec::new(),
+            index: HashMap::new(),
+            entries: 0,
+        };
+        let journal = match fs::read_to_string(&queue.journal_path).await {
+            Ok(journal) => journal,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(queue),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut offset = 0;
+        for (i, line) in journal.split_inclusive('\n').enumerate() {
+            match serde_json::from_str::<JournalEntry>(line) {
+                Ok(entry) => {
+                    queue.apply(entry);
+                    queue.entries += 1;
+                    if !line.ends_with('\n') {
+                        let mut file = fs::OpenOptions::new()
+                            .append(true)
+                            .open(&queue.journal_path)
+                            .await?;
+                        file.write_all(b"\n").await?;
+                        file.flush().await?;
+                    }
+                }
+                Err(_) if line.trim().is_empty() => (),
+                Err(_) if !line.ends_with('\n') => {
+                    fs::OpenOptions::new()
+                        .write(true)
+                        .open(&queue.journal_path)
+                        .await?
+                        .set_len(offset as u64)
+                        .await?;
+                }
+                Err(_) => {
+                    return Err(Error::QueueJournalCorrupt {
+                        path: queue.journal_path,
+                        line: i + 1,
+                    })
+                }
+            }
+            offset += line.len();
+        }
+        Ok(queue)
+    }
+
+    pub fn items(&self) -> &[QueueItem] {
+        &self.items
+    }
+
+    /// Pending files in the order they were added.
+    pub fn pending(&self) -> Vec<PathBuf> {
+        self.items
+            .iter()
+            .filter(|i| i.state == QueueItemState::Pending)
+            .map(|i| i.path.clone())
+            .collect()
+    }
+
+    /// Pending files that were being posted in a bundle, grouped by the id of the bundle.
+    pub fn posting(&self) -> Vec<(String, Vec<PathBuf>)> {
+        let mut posting: Vec<(String, Vec<PathBuf>)> = Vec::new();
+        for item in &self.items {
+            let bundle_id = match (item.state, &item.bundle_id) {
+                (QueueItemState::Pending, Some(bundle_id)) => bundle_id,
+                _ => continue,
+            };
+            match posting.iter_mut().find(|(id, _)| id == bundle_id) {
+                Some((_, paths)) => paths.push(item.path.clone()),
+                None => posting.push((bundle_id.clone(), vec![item.path.clone()])),
+            }
+        }
+        posting
+    }
+
+    pub fn counts(&self) -> QueueCounts {
+        self.items
+            .iter()
+            .fold(QueueCounts::default(), |mut counts, item| {
+                match item.state {
+                    QueueItemState::Pending => counts.pending += 1,
+                    QueueItemState::Done => counts.done += 1,
+                    QueueItemState::Failed => counts.failed += 1,
+                }
+                counts
+            })
+    }
+
+    /// Adds the files in `paths_iter` that aren't queued yet, returning how many were added.
+    pub async fn add<IP>(&mut self, paths_iter: IP) -> Result<usize, Error>
+    where
+        IP: Iterator<Item = PathBuf>,
+    {
+        let mut seen = HashSet::new();
+        let paths: Vec<PathBuf> = paths_iter
+            .filter(|p| !self.index.contains_key(p) && seen.insert(p.clone()))
+            .collect();
+        let added = paths.len();
+        if added > 0 {
+            self.append(JournalEntry::Add { paths }).await?;
+        }
+        Ok(added)
+    }
+
+    /// Records that `paths` are about to be posted in the bundle with `bundle_id`.
+    pub async fn mark_posting(
+        &mut self,
+        paths: &[PathBuf],
+        bundle_id: &Base64,
+    ) -> Result<(), Error> {
+        self.append(JournalEntry::Posting {
+            paths: paths.to_vec(),
+            bundle_id: bundle_id.to_string(),
+        })
+        .await
+    }
+
+    /// Makes `paths` pending again, e.g., if the bundle they were being posted in wasn't posted.
+    pub async fn mark_pending(&mut self, paths: &[PathBuf]) -> Result<(), Error> {
+        self.append(JournalEntry::Retry {
+            paths: paths.to_vec(),
+        })
+        .await
+    }
+
+    /// Records that `paths` were uploaded in the bundle with `bundle_id`.
+    pub async fn mark_done(&mut self, paths: &[PathBuf], bundle_id: &Base64) -> Result<(), Error> {
+        self.append(JournalEntry::Done {
+            paths: paths.to_vec(),
+            bundle_id: bundle_id.to_string(),
+        })
+        .await
+    }
+
+    /// Records that the upload of `paths` failed with `error`. Failed files aren't uploaded
+    /// again until they are queued again with [`UploadQueue::retry_failed`].
+    pub async fn mark_failed(&mut self, paths: &[PathBuf], error: &str) -> Result<(), Error> {
+        self.append(JournalEntry::Failed {
+            paths: paths.to_vec(),
+            error: error.to_string(),
+        })
+        .await
+    }
+
+    /// Makes failed files pending again, returning how many there were.
+    pub async fn retry_failed(&mut self) -> Result<usize, Error> {
+        let paths: Vec<PathBuf> = self
+            .items
+            .iter()
+            .filter(|i| i.state == QueueItemState::Failed)
+            .map(|i| i.path.clone())
+            .collect();
+        let retried = paths.len();
+        if retried > 0 {
+            self.append(JournalEntry::Retry { paths }).await?;
+        }
+        Ok(retried)
+    }
+
+    /// Rewrites the journal with the fewest entries that replay to the current state, if that
+    /// would make it shorter. The compacted journal is written to a temporary file and renamed
+    /// over the journal, so the journal is never left partly written.
+    pub async fn compact(&mut self) -> Result<(), Error> {
+        let entries = self.compacted_entries();
+        if entries.len() >= self.entries {
+            return Ok(());
+        }
+        let mut journal = String::new();
+        for entry in &entries {
+            journal.push_str(&serde_json::to_string(entry)?);
+            journal.push('\n');
+        }
+        let temp_path = self.journal_path.with_extension("ndjson.tmp");
+        let mut file = fs::File::create(&temp_path).await?;
+        file.write_all(journal.as_bytes()).await?;
+        file.sync_data().await?;
+        fs::rename(&temp_path, &self.journal_path).await?;
+        self.entries = entries.len();
+        Ok(())
+    }
+
+    fn compacted_entries(&self) -> Vec<JournalEntry> {
+        let mut entries = Vec::new();
+        if self.items.is_empty() {
+            return entries;
+        }
+        entries.push(JournalEntry::Add {
+            paths: self.items.iter().map(|i| i.path.clone()).collect(),
+        });
+
+        let mut posting: Vec<(&str, Vec<PathBuf>)> = Vec::new();
+        let mut done: Vec<(&str, Vec<PathBuf>)> = Vec::new();
+        let mut failed: Vec<(&str, Vec<PathBuf>)> = Vec::new();
+        for item in &self.items {
+            let (groups, key) = match (item.state, &item.bundle_id, &item.error) {
+                (QueueItemState::Pending, Some(bundle_id), _) => (&mut posting, bundle_id.as_str()),
+                (QueueItemState::Done, Some(bundle_id), _) => (&mut done, bundle_id.as_str()),
+                (QueueItemState::Failed, _, Some(error)) => (&mut failed, error.as_str()),
+                _ => continue,
+            };
+            match groups.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, paths)) => paths.push(item.path.clone()),
+                None => groups.push((key, vec![item.path.clone()])),
+            }
+        }
+        entries.extend(
+            posting
+                .into_iter()
+                .map(|(bundle_id, paths)| JournalEntry::Posting {
+                    paths,
+                    bundle_id: bundle_id.to_string(),
+                }),
+        );
+        entries.extend(
+            done.into_iter()
+                .map(|(bundle_id, paths)| JournalEntry::Done {
+                    paths,
+                    bundle_id: bundle_id.to_string(),
+                }),
+        );
+        entries.extend(
+            failed
+                .into_iter()
+                .map(|(error, paths)| JournalEntry::Failed {
+                    paths,
+                    error: error.to_string(),
+                }),
+        );
+        entries
+    }
+
+    /// Appends `entry` to the journal and applies it.
+    async fn append(&mut self, entry: JournalEntry) -> Result<(), Error> {
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.journal_path)
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+        file.sync_data().await?;
+        self.apply(entry);
+        self.entries += 1;
+        Ok(())
+    }
+
+    fn apply(&mut self, entry: JournalEntry) {
+        match entry {
+            JournalEntry::Add { paths } => {
+                for path in paths {
+                    if !self.index.contains_key(&path) {
+                        self.index.insert(path.clone(), self.items.len());
+                        self.items.push(QueueItem {
+                            path,
+                            state: QueueItemState::Pending,
+                            bundle_id: None,
+                            error: None,
+                        });
+                    }
+                }
+            }
+            JournalEntry::Posting { paths, bundle_id } => {
+                for i in self.indices(&paths) {
+                    self.items[i].bundle_id = Some(bundle_id.clone());
+                }
+            }
+            JournalEntry::Done { paths, bundle_id } => {
+                for i in self.indices(&paths) {
+                    let item = &mut self.items[i];
+                    item.state = QueueItemState::Done;
+                    item.bundle_id = Some(bundle_id.clone());
+                    item.error = None;
+                }
+            }
+            JournalEntry::Failed { paths, error } => {
+                for i in self.indices(&paths) {
+                    let item = &mut self.items[i];
+                    item.state = QueueItemState::Failed;
+                    item.bundle_id = None;
+                    item.error = Some(error.clone());
+                }
+            }
+            JournalEntry::Retry { paths } => {
+                for i in self.indices(&paths) {
+                    let item = &mut self.items[i];
+                    item.state = QueueItemState::Pending;
+                    item.bundle_id = None;
+                    item.error = None;
+                }
+            }
+        }
+    }
+
+    /// Indices of the queued files in `paths`, ignoring any that aren't queued.
+    fn indices(&self, paths: &[PathBuf]) -> Vec<usize> {
+        paths
+            .iter()
+            .filter_map(|p| self.index.get(p))
+            .copied()
+            .collect()
+    }
+
+    /// Counts with the failed files and their errors.
+    pub fn to_json(&self) -> serde_json::Value {
+        let counts = self.counts();
+        json!({
+            "pending": counts.pending,
+            "done": counts.done,
+            "failed": counts.failed,
+            "failed_items": self
+                .items
+                .iter()
+                .filter(|i| i.state == QueueItemState::Failed)
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+impl fmt::Display for UploadQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let counts = self.counts();
+        writeln!(f, " {:<15}  {:>10}", "state", "files")?;
+        writeln!(f, "{:-<29}", "")?;
+        writeln!(f, " {:<16} {:>10}", "pending", counts.pending)?;
+        writeln!(f, " {:<16} {:>10}", "done", counts.done)?;
+        writeln!(f, " {:<16} {:>10}", "failed", counts.failed)?;
+        if counts.failed > 0 {
+            writeln!(f, "\nFailed files:")?;
+        }
+        for item in self
+            .items
+            .iter()
+            .filter(|i| i.state == QueueItemState::Failed)
+        {
+            writeln!(
+                f,
+                " {}: {}",
+                item.path.display(),
+                item.error.as_deref().unwrap_or_default()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{QueueCounts, QueueItemState, UploadQueue, QUEUE_JOURNAL_FILE};
+    use crate::{error::Error, transaction::Base64, utils::TempDir};
+    use matches::assert_matches;
+    use std::{fs, io::Write, path::PathBuf, str::FromStr};
+
+    #[tokio::test]
+    async fn test_upload_queue_journal() -> Result<(), Error> {
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let queue_dir = temp_dir.0.join("queue");
+        let journal_path = queue_dir.join(QUEUE_JOURNAL_FILE);
+        let paths: Vec<PathBuf> = (0..4)
+            .map(|i| PathBuf::from(format!("{}.png", i)))
+            .collect();
+
+        let mut queue = UploadQueue::open(&queue_dir).await?;
+        assert_eq!(queue.add(paths[..3].iter().cloned()).await?, 3);
+        assert_eq!(queue.add(paths.iter().chain(&paths).cloned()).await?, 1);
+        queue.mark_done(&paths[..2], &Base64(vec![1; 32])).await?;
+        queue.mark_failed(&paths[2..3], "post rejected").await?;
+        assert_eq!(
+            queue.counts(),
+            QueueCounts {
+                pending: 1,
+                done: 2,
+                failed: 1
+            }
+        );
+
+        // A partial line from a process killed while appending is dropped.
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&journal_path)?
+            .write_all(br#"{"op":"done","paths":["3.p"#)?;
+        let mut queue = UploadQueue::open(&queue_dir).await?;
+        assert_eq!(queue.pending(), vec![paths[3].clone()]);
+        assert_eq!(
+            queue.items()[0].bundle_id,
+            Some(Base64(vec![1; 32]).to_string())
+        );
+        assert_eq!(queue.items()[2].error.as_deref(), Some("post rejected"));
+
+        assert_eq!(queue.retry_failed().await?, 1);
+        assert_eq!(queue.pending(), vec![paths[2].clone(), paths[3].clone()]);
+        queue.mark_failed(&paths[3..], "file not found").await?;
+
+        // Files being posted stay pending with the id of their bundle until it's resolved.
+        let bundle_id = Base64(vec![2; 32]);
+        queue.mark_posting(&paths[2..3], &bundle_id).await?;
+        assert_eq!(queue.pending(), vec![paths[2].clone()]);
+        assert_eq!(
+            queue.posting(),
+            vec![(bundle_id.to_string(), vec![paths[2].clone()])]
+        );
+        let items = queue.items().to_vec();
+
+        // Compacting replaces the seven entries with one add, one posting, one done and one
+        // failed entry.
+        assert_eq!(fs::read_to_string(&journal_path)?.lines().count(), 7);
+        queue.compact().await?;
+        assert_eq!(fs::read_to_string(&journal_path)?.lines().count(), 4);
+        let mut queue = UploadQueue::open(&queue_dir).await?;
+        assert_eq!(queue.items(), items);
+        assert_eq!(queue.items()[3].state, QueueItemState::Failed);
+
+        queue.mark_pending(&paths[2..3]).await?;
+        assert!(queue.posting().is_empty());
+        assert_eq!(queue.pending(), vec![paths[2].clone()]);
+
+        fs::write(&journal_path, "{\"op\":\"add\",\"paths\":[]}\nnot json\n")?;
+        assert_matches!(
+            UploadQueue::open(&queue_dir).await,
+            Err(Error::QueueJournalCorrupt { line: 2, .. })
+        );
+        Ok(())
+    }
+}
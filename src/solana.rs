@@ -6,9 +6,15 @@ use futures::future::try_join;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use solana_sdk::{
-    hash::Hash, pubkey::Pubkey, signature::Signer, signer::keypair, system_transaction,
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::Signer,
+    signer::keypair,
+    system_instruction,
+    transaction::Transaction,
 };
-use std::str::FromStr;
+use std::{fs, path::Path, str::FromStr};
 
 /// Solana address to which SOL payments are made.
 pub const SOL_AR_PUBKEY: &str = "6AaM5L2SeA7ciwDNaYLhKqQzsDVaQM9CRqXVDdWPeAQ9";
@@ -22,12 +28,79 @@ pub const SOLANA_DEV_URL: &str = "https://api.devnet.solana.com";
 /// Uri of Solana payment api.
 pub const SOL_AR_BASE_URL: &str = "https://arloader.io/";
 
+/// Program used to attach the payment reference to SOL transfers.
+pub const MEMO_PROGRAM_ID: &str = "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr";
+
+/// Number of recent signatures searched for an existing payment.
+pub const PAYMENT_LOOKUP_LIMIT: usize = 100;
+
 /// Winstons per lamports exchange rate for calculating SOL payment amounts.
 pub const RATE: u64 = 2500;
 
 /// Minimum SOL transaction amount.
 pub const FLOOR: u64 = 10000;
 
+/// Lamports paid in fees for each SOL transaction.
+pub const SOL_TX_FEE: u64 = 5000;
+
+/// Loads a Solana keypair from `path`, accepting the JSON array of 64 secret key bytes written by
+/// `solana-keygen`, a base58 encoded secret key as exported by wallets like Phantom, or a JSON
+/// object with the secret key in a `secretKey` field. Errors describe which format was found and
+/// what's wrong with it.
+pub fn load_keypair<P: AsRef<Path>>(path: P) -> Result<keypair::Keypair, Error> {
+    let path = path.as_ref();
+    let error = |reason: String| Error::SolanaKeypair {
+        path: path.to_path_buf(),
+        reason,
+    };
+    let contents = fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+    let contents = contents.trim();
+
+    let bytes =
+        if contents.starts_with('[') {
+            serde_json::from_str::<Vec<u8>>(contents).map_err(|e| {
+                error(format!(
+                    "expected a JSON array of secret key bytes like solana-keygen writes: {}",
+                    e
+                ))
+            })?
+        } else if contents.starts_with('{') {
+            let value: Value = serde_json::from_str(contents)
+                .map_err(|e| error(format!("invalid JSON: {}", e)))?;
+            match value.get("secretKey") {
+                Some(Value::String(secret)) => decode_base58_secret(secret).map_err(error)?,
+                Some(secret @ Value::Array(_)) => serde_json::from_value(secret.clone())
+                    .map_err(|e| error(format!("invalid \"secretKey\" array: {}", e)))?,
+                _ => return Err(error(
+                    "JSON object has no \"secretKey\", expected a JSON array of secret key bytes \
+                    like solana-keygen writes"
+                        .to_string(),
+                )),
+            }
+        } else {
+            decode_base58_secret(contents.trim_matches('"')).map_err(error)?
+        };
+
+    if bytes.len() != 64 {
+        return Err(error(format!(
+            "secret key has {} bytes, expected 64, check that the file holds the secret key and \
+            not the public address",
+            bytes.len()
+        )));
+    }
+    keypair::Keypair::from_bytes(&bytes).map_err(|e| error(e.to_string()))
+}
+
+fn decode_base58_secret(secret: &str) -> Result<Vec<u8>, String> {
+    bs58::decode(secret).into_vec().map_err(|e| {
+        format!(
+            "not a JSON array of secret key bytes, a JSON object with \"secretKey\" or a base58 \
+            secret key: {}",
+            e
+        )
+    })
+}
+
 /// Returns recent blockhash neeed to create transaction.
 pub async fn get_recent_blockhash(base_url: url::Url) -> Result<Hash, Error> {
     let client = reqwest::Client::new();
@@ -104,11 +177,57 @@ pub async fn request_airdrop(base_url: url::Url, keypair: &keypair::Keypair) ->
     Ok(())
 }
 
-/// Creates Solana transaction.
+/// Returns the payment reference for an AR transaction from `payment_key`, a hash of what's
+/// being paid for that doesn't change between attempts, e.g., of its data and tags but not its
+/// anchor, so that every attempt to pay for the same data carries the same reference.
+pub fn payment_reference(payment_key: &[u8]) -> String {
+    format!("arloader:{}", bs58::encode(payment_key).into_string())
+}
+
+/// Returns the signature that identifies the serialized Solana transaction `sol_tx` on chain.
+pub fn sol_tx_signature(sol_tx: &str) -> Result<String, Error> {
+    let serialized = bs58::decode(sol_tx)
+        .into_vec()
+        .map_err(|_| Error::SolanaNetworkError)?;
+    let transaction: Transaction = bincode::deserialize(&serialized)?;
+    transaction
+        .signatures
+        .first()
+        .map(|s| s.to_string())
+        .ok_or(Error::SolanaNetworkError)
+}
+
+/// Returns whether the transaction with `signature` was processed and failed. Transactions that
+/// haven't been seen yet, e.g., because they're still pending, haven't failed, and can be sent
+/// again as they are without being executed twice.
+pub async fn sol_transaction_failed(base_url: url::Url, signature: &str) -> Result<bool, Error> {
+    let post_object = PostObject {
+        method: String::from("getSignatureStatuses"),
+        params: vec![
+            json!([signature]),
+            json!({ "searchTransactionHistory": true }),
+        ],
+        ..Default::default()
+    };
+
+    let result: Value = reqwest::Client::new()
+        .post(base_url)
+        .json(&post_object)
+        .send()
+        .await?
+        .json()
+        .await?;
+
+    let status = &result["result"]["value"][0];
+    Ok(!status.is_null() && !status["err"].is_null())
+}
+
+/// Creates Solana transaction, attaching `reference` as a memo if provided.
 pub async fn create_sol_transaction(
     base_url: url::Url,
     from_keypair: &keypair::Keypair,
     lamports: u64,
+    reference: Option<&str>,
 ) -> Result<String, Error> {
     let (recent_blockhash, balance) = try_join(
         get_recent_blockhash(base_url.clone()),
@@ -120,10 +239,23 @@ pub async fn create_sol_transaction(
         return Err(Error::InsufficientSolFunds);
     }
 
-    let transaction = system_transaction::transfer(
-        from_keypair,
+    let from_pubkey = from_keypair.pubkey();
+    let mut instructions = vec![system_instruction::transfer(
+        &from_pubkey,
         &Pubkey::from_str(SOL_AR_PUBKEY).unwrap(),
         lamports,
+    )];
+    if let Some(reference) = reference {
+        instructions.push(Instruction {
+            program_id: Pubkey::from_str(MEMO_PROGRAM_ID).unwrap(),
+            accounts: vec![AccountMeta::new_readonly(from_pubkey, true)],
+            data: reference.as_bytes().to_vec(),
+        });
+    }
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&from_pubkey),
+        &[from_keypair],
         recent_blockhash,
     );
     let serialized = bincode::serialize(&transaction)?;
@@ -131,6 +263,89 @@ pub async fn create_sol_transaction(
     Ok(bs58::encode(serialized).into_string())
 }
 
+/// Looks through the recent signatures of `pubkey` for a successful transfer carrying `reference`
+/// and returns the serialized transaction if one is found.
+pub async fn find_sol_payment(
+    base_url: url::Url,
+    pubkey: &Pubkey,
+    reference: &str,
+) -> Result<Option<String>, Error> {
+    let client = reqwest::Client::new();
+
+    let post_object = PostObject {
+        method: String::from("getSignaturesForAddress"),
+        params: vec![
+            json!(pubkey.to_string()),
+            json!({"limit": PAYMENT_LOOKUP_LIMIT, "commitment": "confirmed"}),
+        ],
+        ..Default::default()
+    };
+
+    let result: Value = client
+        .post(base_url.clone())
+        .json(&post_object)
+        .send()
+        .await?
+        .json()
+        .await?;
+
+    // Memos are returned prefixed with their length, e.g. "[52] arloader:...".
+    let signature = result["result"].as_array().and_then(|sigs| {
+        sigs.iter()
+            .filter(|s| s["err"].is_null())
+            .find(|s| {
+                s["memo"]
+                    .as_str()
+                    .map(|m| m.contains(reference))
+                    .unwrap_or(false)
+            })
+            .and_then(|s| s["signature"].as_str())
+    });
+    let signature = match signature {
+        Some(signature) => signature.to_string(),
+        None => return Ok(None),
+    };
+
+    let post_object = PostObject {
+        method: String::from("getTransaction"),
+        params: vec![
+            json!(signature),
+            json!({"encoding": "base64", "commitment": "confirmed"}),
+        ],
+        ..Default::default()
+    };
+
+    let result: Value = client
+        .post(base_url)
+        .json(&post_object)
+        .send()
+        .await?
+        .json()
+        .await?;
+
+    match result["result"]["transaction"][0].as_str() {
+        Some(encoded) => {
+            let serialized = base64::decode(encoded).map_err(|_| Error::SolanaNetworkError)?;
+            Ok(Some(bs58::encode(serialized).into_string()))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Returns an existing payment carrying `reference` if there is one, otherwise creates a new one,
+/// so that retrying a payment for the same AR transaction doesn't transfer SOL twice.
+pub async fn get_or_create_sol_transaction(
+    base_url: url::Url,
+    from_keypair: &keypair::Keypair,
+    lamports: u64,
+    reference: &str,
+) -> Result<String, Error> {
+    match find_sol_payment(base_url.clone(), &from_keypair.pubkey(), reference).await? {
+        Some(sol_tx) => Ok(sol_tx),
+        None => create_sol_transaction(base_url, from_keypair, lamports, Some(reference)).await,
+    }
+}
+
 /// Submits Solana transaction and required transaction elements and gets back signed AR transaction.
 pub async fn get_sol_ar_signature(
     base_url: url::Url,
@@ -195,9 +410,65 @@ pub struct SigResponse {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::error::Error;
+    use crate::{error::Error, utils::TempDir};
+    use matches::assert_matches;
     use solana_sdk::signer::keypair::{self, Keypair};
 
+    #[tokio::test]
+    async fn test_load_keypair() -> Result<(), Error> {
+        let fixture = "tests/fixtures/solana_test.json";
+        let expected = keypair::read_keypair_file(fixture)?;
+        let secret = expected.to_bytes();
+        let base58 = bs58::encode(&secret).into_string();
+        let temp_dir = TempDir::from_str("./tests/").await?;
+
+        let contents = [
+            ("array.json", fs::read_to_string(fixture)?),
+            ("base58.txt", format!("{}\n", base58)),
+            (
+                "object.json",
+                json!({ "publicKey": expected.pubkey().to_string(), "secretKey": base58 })
+                    .to_string(),
+            ),
+            (
+                "object_array.json",
+                json!({ "secretKey": secret.to_vec() }).to_string(),
+            ),
+        ];
+        for (name, contents) in contents {
+            let path = temp_dir.0.join(name);
+            fs::write(&path, contents)?;
+            assert_eq!(load_keypair(&path)?.pubkey(), expected.pubkey(), "{}", name);
+        }
+
+        let invalid = [
+            ("public.txt", expected.pubkey().to_string(), "32 bytes"),
+            ("short.json", json!(secret[..32]).to_string(), "32 bytes"),
+            (
+                "no_secret.json",
+                json!({ "publicKey": "x" }).to_string(),
+                "secretKey",
+            ),
+            ("bad_array.json", "[1, 2, 256]".to_string(), "JSON array"),
+            ("not_base58.txt", "not base58 0OIl".to_string(), "base58"),
+        ];
+        for (name, contents, reason_contains) in invalid {
+            let path = temp_dir.0.join(name);
+            fs::write(&path, contents)?;
+            assert_matches!(
+                load_keypair(&path),
+                Err(Error::SolanaKeypair { path: p, reason })
+                    if p == path && reason.contains(reason_contains)
+            );
+        }
+
+        assert_matches!(
+            load_keypair(temp_dir.0.join("missing.json")),
+            Err(Error::SolanaKeypair { .. })
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_get_recent_blockhash() -> Result<(), Error> {
         let base_url = SOLANA_DEV_URL.parse::<url::Url>().unwrap();
@@ -213,8 +484,68 @@ mod tests {
         let keypair = keypair::read_keypair_file("tests/fixtures/solana_test.json")?;
         request_airdrop(base_url.clone(), &keypair).await?;
 
-        let result = create_sol_transaction(base_url, &keypair, 42).await?;
-        println!("{}", result);
+        let result = create_sol_transaction(base_url, &keypair, 42, Some("arloader:test")).await?;
+        let transaction: Transaction =
+            bincode::deserialize(&bs58::decode(result).into_vec().unwrap())?;
+        assert_eq!(transaction.message.instructions.len(), 2);
+        assert_eq!(transaction.message.instructions[1].data, b"arloader:test");
+        Ok(())
+    }
+
+    async fn send_sol_transaction(base_url: url::Url, sol_tx: &str) -> Result<String, Error> {
+        let serialized = bs58::decode(sol_tx).into_vec().unwrap();
+        let post_object = PostObject {
+            method: String::from("sendTransaction"),
+            params: vec![
+                json!(base64::encode(serialized)),
+                json!({"encoding": "base64"}),
+            ],
+            ..Default::default()
+        };
+        let result: Value = reqwest::Client::new()
+            .post(base_url)
+            .json(&post_object)
+            .send()
+            .await?
+            .json()
+            .await?;
+        Ok(result["result"].as_str().unwrap_or_default().to_string())
+    }
+
+    #[tokio::test]
+    async fn test_get_or_create_sol_transaction() -> Result<(), Error> {
+        let base_url = SOLANA_DEV_URL.parse::<url::Url>().unwrap();
+        let keypair = keypair::read_keypair_file("tests/fixtures/solana_test.json")?;
+        request_airdrop(base_url.clone(), &keypair).await?;
+
+        let payment_key: Vec<u8> = (0..32).map(|_| rand::random::<u8>()).collect();
+        let reference = payment_reference(&payment_key);
+        assert_eq!(reference, payment_reference(&payment_key));
+
+        let first =
+            get_or_create_sol_transaction(base_url.clone(), &keypair, FLOOR, &reference).await?;
+        let signature = send_sol_transaction(base_url.clone(), &first).await?;
+
+        // Wait for the first payment to be confirmed before paying again.
+        let mut second = None;
+        for _ in 0..30 {
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            if let Some(sol_tx) =
+                find_sol_payment(base_url.clone(), &keypair.pubkey(), &reference).await?
+            {
+                second = Some(sol_tx);
+                break;
+            }
+        }
+        let second = second.expect("first payment was not confirmed");
+        assert_eq!(
+            get_or_create_sol_transaction(base_url.clone(), &keypair, FLOOR, &reference).await?,
+            second
+        );
+
+        let transaction: Transaction =
+            bincode::deserialize(&bs58::decode(second).into_vec().unwrap())?;
+        assert_eq!(transaction.signatures[0].to_string(), signature);
         Ok(())
     }
 
@@ -1,22 +1,259 @@
 //! Data structures for reporting transaction statuses.
 
+use crate::error::Error;
 use crate::solana::SigResponse;
-use crate::transaction::Base64;
+use crate::transaction::{Base64, Tip};
+use crate::{ConnectionStats, FailedUpload, VERSION, WINSTONS_PER_AR};
 
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, SubsecRound, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::{cmp::Eq, fmt, hash::Hash, path::PathBuf};
+use std::{
+    cmp::Eq,
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    hash::Hash,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration as StdDuration, Instant},
+};
 
 const STRFTIME: &str = "%Y-%m-%d %H:%M:%S";
 
+/// Environment variable that sets the width of status tables instead of the terminal width.
+pub const TABLE_WIDTH_ENV: &str = "ARLOADER_TABLE_WIDTH";
+
+/// Width of status tables if it can't be determined from the terminal.
+pub const DEFAULT_TABLE_WIDTH: usize = 100;
+
+/// Width of the id, status and confirms columns of [`Status`] tables, including separators.
+const STATUS_FIXED_WIDTH: usize = 67;
+
+/// Path columns are never narrower than this, even if the table overflows the terminal.
+const MIN_PATH_WIDTH: usize = 12;
+
+/// Widths of the bundle txid, items, KB, status and confirms columns of [`BundleStatus`] tables.
+const BUNDLE_COLUMN_WIDTHS: [usize; 5] = [43, 6, 6, 11, 8];
+
+/// Width of [`BundleStatus`] tables, spanned by the rules above and below their rows: the
+/// columns, a leading space and two spaces between columns.
+fn bundle_table_width() -> usize {
+    1 + BUNDLE_COLUMN_WIDTHS.iter().sum::<usize>() + 2 * (BUNDLE_COLUMN_WIDTHS.len() - 1)
+}
+
+/// Lays out the cells of a [`BundleStatus`] table row in [`BUNDLE_COLUMN_WIDTHS`].
+fn bundle_table_row(cells: [&str; 5]) -> String {
+    let [id, items, kb, status, confirms] = BUNDLE_COLUMN_WIDTHS;
+    format!(
+        " {:<id$}  {:>items$}  {:>kb$}  {:<status$}  {:>confirms$}",
+        cells[0],
+        cells[1],
+        cells[2],
+        cells[3],
+        cells[4],
+        id = id,
+        items = items,
+        kb = kb,
+        status = status,
+        confirms = confirms,
+    )
+}
+
+/// Returns the width set with [`TABLE_WIDTH_ENV`], or else the width of the terminal, or else
+/// [`DEFAULT_TABLE_WIDTH`].
+pub fn table_width() -> usize {
+    std::env::var(TABLE_WIDTH_ENV)
+        .ok()
+        .and_then(|w| w.parse().ok())
+        .or_else(|| terminal_size::terminal_size().map(|(w, _)| w.0 as usize))
+        .unwrap_or(DEFAULT_TABLE_WIDTH)
+}
+
+/// Shortens `s` to `width` characters by replacing the middle with `...`, keeping the start and
+/// end of paths, which are usually the most informative parts.
+pub fn truncate_middle(s: &str, width: usize) -> String {
+    let chars: Vec<char> = s.chars().collect();
+    if chars.len() <= width {
+        return s.to_string();
+    }
+    if width <= 3 {
+        return chars[..width].iter().collect();
+    }
+    let tail = (width - 3) / 2;
+    let head = width - 3 - tail;
+    format!(
+        "{}...{}",
+        chars[..head].iter().collect::<String>(),
+        chars[chars.len() - tail..].iter().collect::<String>()
+    )
+}
+
+/// Column layout of [`Status`] tables, giving the path column whatever width is left over from
+/// the fixed width columns.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatusTable {
+    pub width: usize,
+}
+
+impl StatusTable {
+    /// Sized with [`table_width`].
+    pub fn new() -> Self {
+        Self {
+            width: table_width(),
+        }
+    }
+
+    fn path_width(&self) -> usize {
+        self.width
+            .saturating_sub(STATUS_FIXED_WIDTH)
+            .max(MIN_PATH_WIDTH)
+    }
+
+    pub fn header(&self) -> String {
+        let path_width = self.path_width();
+        format!(
+            " {:<path_width$}  {:<43}  {:<9}  {}\n{:-<rule$}",
+            "path",
+            "id",
+            "status",
+            "confirms",
+            "",
+            path_width = path_width,
+            rule = path_width + STATUS_FIXED_WIDTH,
+        )
+    }
+
+    pub fn row(&self, status: &Status) -> String {
+        let path = status
+            .file_path
+            .as_ref()
+            .map(|f| f.display().to_string())
+            .unwrap_or_default();
+        format!(
+            " {:<path_width$}  {:<43}  {:<9}  {:>8}\n",
+            truncate_middle(&path, self.path_width()),
+            status.id,
+            status.status.to_string(),
+            status.confirms(),
+            path_width = self.path_width(),
+        )
+    }
+}
+
+/// Version of the status json written by this version of arloader, recorded as `schema_version`
+/// in every status. Statuses without one are read as the current version.
+pub const STATUS_SCHEMA_VERSION: u32 = 1;
+
+fn current_schema_version() -> u32 {
+    STATUS_SCHEMA_VERSION
+}
+
+/// Parses a status read from `path`, returning [`Error::UnsupportedStatusVersion`] if it was
+/// written by a newer version of arloader with a schema this version may not understand.
+pub fn parse_status<T: DeserializeOwned>(data: &str, path: &Path) -> Result<T, Error> {
+    let value: Value = serde_json::from_str(data)?;
+    let version = value["schema_version"]
+        .as_u64()
+        .unwrap_or(STATUS_SCHEMA_VERSION as u64);
+    if version > STATUS_SCHEMA_VERSION as u64 {
+        return Err(Error::UnsupportedStatusVersion {
+            path: path.to_path_buf(),
+            version,
+        });
+    }
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Serializes datetimes as RFC 3339 to the millisecond, so that logs don't vary with clock
+/// precision while statuses written in the same second can still be ordered. Any RFC 3339
+/// datetime is accepted when deserializing.
+pub mod datetime_millis {
+    use chrono::{DateTime, SecondsFormat, Utc};
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(dt: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&dt.to_rfc3339_opts(SecondsFormat::Millis, true))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DateTime<Utc>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        DateTime::parse_from_rfc3339(&s)
+            .map(|dt| dt.with_timezone(&Utc))
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Serializes a status to json with object keys sorted at every level, so that statuses that are
+/// semantically equal are written identically.
+pub fn to_status_json<T: Serialize>(status: &T, pretty: bool) -> serde_json::Result<String> {
+    let value = sort_keys(serde_json::to_value(status)?);
+    match pretty {
+        true => serde_json::to_string_pretty(&value),
+        false => serde_json::to_string(&value),
+    }
+}
+
+fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let sorted: BTreeMap<String, Value> =
+                map.into_iter().map(|(k, v)| (k, sort_keys(v))).collect();
+            Value::Object(sorted.into_iter().collect())
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sort_keys).collect()),
+        value => value,
+    }
+}
+
 /// Status as reported directly from the network.
 #[allow(dead_code)]
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct RawStatus {
     pub block_height: u64,
     pub block_indep_hash: Base64,
+    #[serde(default)]
     pub number_of_confirmations: u64,
+    /// Where `number_of_confirmations` came from, see [`RawStatus::reconcile_confirmations`].
+    #[serde(default)]
+    pub confirmations_source: ConfirmationsSource,
+}
+
+impl RawStatus {
+    /// Replaces `number_of_confirmations` with the count implied by the network's
+    /// `current_height` if the status endpoint omitted or understated it.
+    pub fn reconcile_confirmations(&mut self, current_height: u64) {
+        let from_height = (current_height + 1).saturating_sub(self.block_height);
+        if from_height > self.number_of_confirmations {
+            self.number_of_confirmations = from_height;
+            self.confirmations_source = ConfirmationsSource::Height;
+        }
+    }
+}
+
+/// Source of the number of confirmations recorded in a [`RawStatus`].
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+pub enum ConfirmationsSource {
+    /// As reported by the `tx/{id}/status` endpoint.
+    Node,
+    /// Computed from the current network height.
+    Height,
+}
+
+impl Default for ConfirmationsSource {
+    fn default() -> Self {
+        ConfirmationsSource::Node
+    }
+}
+
+impl fmt::Display for ConfirmationsSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfirmationsSource::Node => write!(f, "node"),
+            ConfirmationsSource::Height => write!(f, "height"),
+        }
+    }
 }
 
 /// Indicates transaction status on the network, from Submitted to Confirmed.
@@ -44,24 +281,153 @@ impl std::fmt::Display for StatusCode {
         }
     }
 }
+
+impl StatusCode {
+    /// Orders statuses by how far along they are, from NotFound up to Confirmed.
+    pub fn rank(&self) -> u8 {
+        match self {
+            StatusCode::NotFound => 0,
+            StatusCode::Submitted => 1,
+            StatusCode::Pending => 2,
+            StatusCode::Confirmed => 3,
+        }
+    }
+}
+
+/// Source of the current time used when updating statuses.
+pub trait Clock {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// [`Clock`] backed by the system clock.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Returns the current time from `clock`, or one millisecond after `previous` if the clock has
+/// moved backwards, so that `last_modified` always increases with each update.
+pub fn next_last_modified<C: Clock>(clock: &C, previous: DateTime<Utc>) -> DateTime<Utc> {
+    std::cmp::max(clock.now(), previous + Duration::milliseconds(1))
+}
+
 pub struct FilterElements<'a> {
     pub raw_status: &'a Option<RawStatus>,
     pub status: &'a StatusCode,
 }
 pub trait Filterable {
     fn get_filter_elements(&self) -> FilterElements;
+
+    fn confirms(&self) -> u64 {
+        self.get_filter_elements()
+            .raw_status
+            .as_ref()
+            .map(|s| s.number_of_confirmations)
+            .unwrap_or(0)
+    }
+
+    /// Whether the transaction is confirmed with at least `min_confirms` confirmations, after
+    /// which `update-status` skips it.
+    fn is_confirmed_with(&self, min_confirms: u64) -> bool {
+        *self.get_filter_elements().status == StatusCode::Confirmed
+            && self.confirms() >= min_confirms
+    }
+}
+
+/// Status yielded by [`crate::update_statuses_stream`] and
+/// [`crate::update_bundle_statuses_stream`], either updated from the network or skipped and
+/// left as it was read.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatusUpdate<T> {
+    Updated(T),
+    Skipped(T),
+}
+
+/// Column to sort statuses by before listing them, see [`sort_statuses`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Confirms,
+    Status,
+    /// Only applies to [`BundleStatus`]s.
+    Size,
+    Path,
+}
+
+impl FromStr for SortKey {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "confirms" => Ok(SortKey::Confirms),
+            "status" => Ok(SortKey::Status),
+            "size" => Ok(SortKey::Size),
+            "path" => Ok(SortKey::Path),
+            _ => Err(Error::InvalidSortKey(s.to_string())),
+        }
+    }
+}
+
+pub trait Sortable: Filterable {
+    /// Size of the data included, if recorded.
+    fn data_size(&self) -> Option<u64>;
+    /// File path used for sorting, and to break ties when sorting by other keys.
+    fn sort_path(&self) -> String;
+}
+
+/// Sorts `statuses` by `sort_by`, in descending order if `desc` is true. Statuses are sorted by
+/// [`StatusCode::rank`] rather than name when sorting by status.
+pub fn sort_statuses<S: Sortable>(
+    statuses: &mut [S],
+    sort_by: SortKey,
+    desc: bool,
+) -> Result<(), Error> {
+    if sort_by == SortKey::Size && statuses.iter().any(|s| s.data_size().is_none()) {
+        return Err(Error::InvalidSortKey(
+            "size only applies to bundle statuses".to_string(),
+        ));
+    }
+    statuses.sort_by_cached_key(|s| {
+        let key = match sort_by {
+            SortKey::Confirms => s.confirms(),
+            SortKey::Status => s.get_filter_elements().status.rank() as u64,
+            SortKey::Size => s.data_size().unwrap_or(0),
+            SortKey::Path => 0,
+        };
+        (key, s.sort_path())
+    });
+    if desc {
+        statuses.reverse();
+    }
+    Ok(())
 }
 
 /// Data structure for tracking transaction statuses.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct Status {
+    #[serde(default = "current_schema_version")]
+    pub schema_version: u32,
     pub id: Base64,
     pub status: StatusCode,
     pub file_path: Option<PathBuf>,
     pub content_type: String,
+    #[serde(with = "datetime_millis")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "datetime_millis")]
     pub last_modified: DateTime<Utc>,
     pub reward: u64,
+    #[serde(default)]
+    pub touched_count: u32,
+    /// Transfer included with the transaction, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tip: Option<Tip>,
+    /// Size of the uploaded file, see [`RecordedFile`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_size: Option<u64>,
+    /// Hex encoded BLAKE3 hash of the uploaded file, see [`RecordedFile`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_hash: Option<String>,
     #[serde(flatten)]
     pub raw_status: Option<RawStatus>,
     #[serde(flatten)]
@@ -71,6 +437,7 @@ pub struct Status {
 impl Default for Status {
     fn default() -> Self {
         Self {
+            schema_version: STATUS_SCHEMA_VERSION,
             id: Base64(vec![]),
             status: StatusCode::default(),
             file_path: None,
@@ -78,6 +445,10 @@ impl Default for Status {
             created_at: Utc::now(),
             last_modified: Utc::now(),
             reward: 0,
+            touched_count: 0,
+            tip: None,
+            data_size: None,
+            data_hash: None,
             raw_status: None,
             sol_sig: None,
         }
@@ -85,15 +456,59 @@ impl Default for Status {
 }
 
 impl Status {
-    pub fn header_string(&self, output_format: &OutputFormat) -> String {
+    /// Records an update, keeping `last_modified` increasing even if the clock moves backwards.
+    pub fn touch<C: Clock>(&mut self, clock: &C) {
+        self.last_modified = next_last_modified(clock, self.last_modified);
+        self.touched_count += 1;
+    }
+
+    /// Formats a single status, omitting the path column from [`OutputFormat::Display`] to
+    /// match [`OutputHeader::single_header_string`].
+    pub fn single_formatted_string(&self, output_format: &OutputFormat) -> String {
+        match output_format {
+            OutputFormat::Display => format!(
+                " {:<43}  {:<9}  {:>8}\n",
+                self.id,
+                self.status.to_string(),
+                self.raw_status
+                    .as_ref()
+                    .map(|f| f.number_of_confirmations)
+                    .unwrap_or(0)
+                    .to_string(),
+            ),
+            _ => output_format.formatted_string(self),
+        }
+    }
+}
+
+impl OutputHeader for Status {
+    fn header_string(output_format: &OutputFormat) -> String {
+        match output_format {
+            OutputFormat::Display => StatusTable::new().header(),
+            _ => String::new(),
+        }
+    }
+
+    fn table_header_string(table: &StatusTable, output_format: &OutputFormat) -> String {
+        match output_format {
+            OutputFormat::Display => table.header(),
+            _ => String::new(),
+        }
+    }
+
+    fn table_row_string(&self, table: &StatusTable) -> String {
+        table.row(self)
+    }
+
+    fn single_header_string(output_format: &OutputFormat) -> String {
         match output_format {
             OutputFormat::Display => {
                 format!(
-                    " {:<30}  {:<43}  {:<9}  {}\n{:-<97}",
-                    "path", "id", "status", "confirms", ""
+                    " {:<43}  {:<9}  {}\n{:-<65}",
+                    "id", "status", "confirms", ""
                 )
             }
-            _ => format!("{}", ""),
+            _ => String::new(),
         }
     }
 }
@@ -107,6 +522,19 @@ impl Filterable for Status {
     }
 }
 
+impl Sortable for Status {
+    fn data_size(&self) -> Option<u64> {
+        None
+    }
+
+    fn sort_path(&self) -> String {
+        self.file_path
+            .as_ref()
+            .map(|p| p.display().to_string())
+            .unwrap_or_default()
+    }
+}
+
 impl QuietDisplay for Status {
     fn write_str(&self, _w: &mut dyn fmt::Write) -> fmt::Result {
         Ok(())
@@ -115,21 +543,7 @@ impl QuietDisplay for Status {
 
 impl std::fmt::Display for Status {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        writeln!(
-            f,
-            " {:<30}  {:<43}  {:<9}  {:>8}",
-            self.file_path
-                .as_ref()
-                .map(|f| f.display().to_string())
-                .unwrap_or("".to_string()),
-            self.id,
-            self.status.to_string(),
-            self.raw_status
-                .as_ref()
-                .map(|f| f.number_of_confirmations)
-                .unwrap_or(0)
-                .to_string(),
-        )
+        write!(f, "{}", StatusTable::new().row(self))
     }
 }
 
@@ -162,8 +576,8 @@ impl VerboseDisplay for Status {
             writeln!(w, "{:<15} {}", "indep_hash:", raw_status.block_indep_hash)?;
             writeln!(
                 w,
-                "{:<15} {}",
-                "confirms:", raw_status.number_of_confirmations
+                "{:<15} {} ({})",
+                "confirms:", raw_status.number_of_confirmations, raw_status.confirmations_source
             )?;
         };
         writeln!(w, "")
@@ -173,49 +587,198 @@ impl VerboseDisplay for Status {
 /// Data structure for tracking bundle statuses.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct BundleStatus {
+    #[serde(default = "current_schema_version")]
+    pub schema_version: u32,
     pub id: Base64,
     pub status: StatusCode,
     pub file_paths: Value,
     pub number_of_files: u64,
     pub data_size: u64,
+    /// Bytes of avro encoded tags included in the bundle.
+    #[serde(default)]
+    pub tags_bytes: u64,
+    /// Bytes of the bundle in addition to `data_size`, see [`crate::bundle::BundleBreakdown`].
+    #[serde(default)]
+    pub overhead_bytes: u64,
+    #[serde(with = "datetime_millis")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "datetime_millis")]
     pub last_modified: DateTime<Utc>,
     pub reward: u64,
+    #[serde(default)]
+    pub touched_count: u32,
+    #[serde(default)]
+    pub owner_address: String,
     #[serde(flatten)]
     pub raw_status: Option<RawStatus>,
     #[serde(flatten)]
     pub sol_sig: Option<SigResponse>,
+    /// Statuses for the same files merged into this one, see [`merge_duplicate_statuses`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub previous: Vec<PreviousStatus>,
+    /// Only recorded for bundles posted during the current run, see [`Metrics`].
+    #[serde(skip)]
+    pub post_metrics: PostMetrics,
+    /// Chunks posted so far, only written to interim statuses while a bundle is being posted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upload_progress: Option<UploadProgress>,
+}
+
+/// Number of chunks of a transaction posted out of the total.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone, Copy)]
+pub struct UploadProgress {
+    pub chunks_posted: usize,
+    pub chunks_total: usize,
+}
+
+impl UploadProgress {
+    /// Whether chunks were left to post when the progress was recorded.
+    pub fn is_unfinished(&self) -> bool {
+        self.chunks_posted < self.chunks_total
+    }
+}
+
+impl std::fmt::Display for UploadProgress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}% posted",
+            self.chunks_posted * 100 / std::cmp::max(self.chunks_total, 1)
+        )
+    }
+}
+
+/// Bytes actually posted for a bundle and how long posting them took.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct PostMetrics {
+    pub bytes: u64,
+    pub latency: StdDuration,
+}
+
+/// Summary of a [`BundleStatus`] superseded by another one for the same files.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct PreviousStatus {
+    pub id: Base64,
+    pub status: StatusCode,
+    #[serde(with = "datetime_millis")]
+    pub last_modified: DateTime<Utc>,
+    pub reward: u64,
 }
 
 impl Default for BundleStatus {
     fn default() -> Self {
         Self {
+            schema_version: STATUS_SCHEMA_VERSION,
             id: Base64(vec![]),
             status: StatusCode::default(),
             file_paths: json!({}),
             number_of_files: 0,
             data_size: 0,
+            tags_bytes: 0,
+            overhead_bytes: 0,
             created_at: Utc::now(),
             last_modified: Utc::now(),
             reward: 0,
+            touched_count: 0,
+            owner_address: String::new(),
             raw_status: None,
             sol_sig: None,
+            previous: Vec::new(),
+            post_metrics: PostMetrics::default(),
+            upload_progress: None,
         }
     }
 }
 
 impl BundleStatus {
-    pub fn header_string(&self, output_format: &OutputFormat) -> String {
+    /// Whether this is an interim status written while the bundle was posted with chunks left to
+    /// post, e.g., by a run that stopped or failed before posting all of them. Its files aren't
+    /// uploaded yet.
+    pub fn is_posting(&self) -> bool {
+        self.upload_progress.map_or(false, |p| p.is_unfinished())
+    }
+
+    /// Sorted paths of the files included in the bundle.
+    pub fn file_path_set(&self) -> BTreeSet<String> {
+        self.file_paths
+            .as_object()
+            .map(|paths| paths.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Records an update, keeping `last_modified` increasing even if the clock moves backwards.
+    pub fn touch<C: Clock>(&mut self, clock: &C) {
+        self.last_modified = next_last_modified(clock, self.last_modified);
+        self.touched_count += 1;
+    }
+}
+
+/// Statuses that were merged into the status kept for the same set of files.
+#[derive(Debug)]
+pub struct MergedStatuses {
+    pub kept: BundleStatus,
+    pub removed: Vec<BundleStatus>,
+}
+
+/// Groups `statuses` by the set of files they include and, for each set with more than one
+/// status, keeps the one furthest along (Confirmed > Pending > Submitted > NotFound, then most
+/// confirmations, then most recently modified) with the others recorded in its `previous` history.
+pub fn merge_duplicate_statuses(statuses: Vec<BundleStatus>) -> Vec<MergedStatuses> {
+    let mut groups: BTreeMap<BTreeSet<String>, Vec<BundleStatus>> = BTreeMap::new();
+    for status in statuses {
+        groups
+            .entry(status.file_path_set())
+            .or_default()
+            .push(status);
+    }
+
+    groups
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .map(|mut group| {
+            group.sort_by(|a, b| {
+                (b.status.rank(), b.confirms(), b.last_modified).cmp(&(
+                    a.status.rank(),
+                    a.confirms(),
+                    a.last_modified,
+                ))
+            });
+            let mut kept = group.remove(0);
+            for removed in &group {
+                kept.previous.push(PreviousStatus {
+                    id: removed.id.clone(),
+                    status: removed.status.clone(),
+                    last_modified: removed.last_modified,
+                    reward: removed.reward,
+                });
+                kept.previous.extend(removed.previous.iter().cloned());
+            }
+            MergedStatuses {
+                kept,
+                removed: group,
+            }
+        })
+        .collect()
+}
+
+impl OutputHeader for BundleStatus {
+    fn header_string(output_format: &OutputFormat) -> String {
         match output_format {
             OutputFormat::Display => {
                 format!(
-                    " {:<43}  {:>6}  {:>6}  {:<11}  {}\n{:-<84}",
-                    "bundle txid", "items", "KB", "status", "confirms", ""
+                    "{}\n{:-<width$}",
+                    bundle_table_row(["bundle txid", "items", "KB", "status", "confirms"]),
+                    "",
+                    width = bundle_table_width(),
                 )
             }
-            _ => format!("{}", ""),
+            _ => String::new(),
         }
     }
+
+    fn single_header_string(output_format: &OutputFormat) -> String {
+        Self::header_string(output_format)
+    }
 }
 
 impl Filterable for BundleStatus {
@@ -227,6 +790,17 @@ impl Filterable for BundleStatus {
     }
 }
 
+impl Sortable for BundleStatus {
+    fn data_size(&self) -> Option<u64> {
+        Some(self.data_size)
+    }
+
+    /// First of the file paths included in the bundle.
+    fn sort_path(&self) -> String {
+        self.file_path_set().into_iter().next().unwrap_or_default()
+    }
+}
+
 impl QuietDisplay for BundleStatus {
     fn write_str(&self, _w: &mut dyn fmt::Write) -> fmt::Result {
         Ok(())
@@ -237,16 +811,22 @@ impl std::fmt::Display for BundleStatus {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(
             f,
-            " {:<43}  {:>6}  {:>6}  {:<11} {:>9}",
-            self.id,
-            self.number_of_files,
-            self.data_size / 1000,
-            self.status.to_string(),
-            self.raw_status
-                .as_ref()
-                .map(|f| f.number_of_confirmations)
-                .unwrap_or(0)
-                .to_string(),
+            "{}",
+            bundle_table_row([
+                &self.id.to_string(),
+                &self.number_of_files.to_string(),
+                &(self.data_size / 1000).to_string(),
+                &self
+                    .upload_progress
+                    .map(|p| p.to_string())
+                    .unwrap_or_else(|| self.status.to_string()),
+                &self
+                    .raw_status
+                    .as_ref()
+                    .map(|f| f.number_of_confirmations)
+                    .unwrap_or(0)
+                    .to_string(),
+            ])
         )
     }
 }
@@ -255,6 +835,28 @@ impl VerboseDisplay for BundleStatus {
     fn write_str(&self, w: &mut dyn fmt::Write) -> fmt::Result {
         writeln!(w, "{:<15} {}", "id:", self.id)?;
         writeln!(w, "{:<15} {:?}", "status:", self.status)?;
+        if let Some(progress) = &self.upload_progress {
+            writeln!(
+                w,
+                "{:<15} {}/{} chunks",
+                "progress:", progress.chunks_posted, progress.chunks_total
+            )?;
+        };
+        if !self.owner_address.is_empty() {
+            writeln!(w, "{:<15} {}", "owner_address:", self.owner_address)?;
+        };
+        if self.overhead_bytes > 0 {
+            let total_bytes = self.data_size + self.overhead_bytes;
+            writeln!(w, "{:<15} {} bytes", "data_size:", self.data_size)?;
+            writeln!(w, "{:<15} {} bytes", "tags_bytes:", self.tags_bytes)?;
+            writeln!(
+                w,
+                "{:<15} {} bytes ({:.2}% of bundle)",
+                "overhead:",
+                self.overhead_bytes,
+                self.overhead_bytes as f64 / total_bytes as f64 * 100.0
+            )?;
+        };
         writeln!(
             w,
             "{:<15} {}",
@@ -272,25 +874,345 @@ impl VerboseDisplay for BundleStatus {
             writeln!(w, "{:<15} {}", "indep_hash:", raw_status.block_indep_hash)?;
             writeln!(
                 w,
-                "{:<15} {}",
-                "confirms:", raw_status.number_of_confirmations
+                "{:<15} {} ({})",
+                "confirms:", raw_status.number_of_confirmations, raw_status.confirmations_source
             )?;
         };
         writeln!(w, "")
     }
 }
 
-/// Controls output format, including quiet, verbose and json formats.
+/// Totals over [`BundleStatus`]s, printed as a footer after bundle status tables so that the
+/// numbers reported by listing, updating and uploading commands match.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct SummaryAccumulator {
+    pub status_counts: BTreeMap<String, u64>,
+    pub number_of_bundles: u64,
+    pub number_of_files: u64,
+    pub data_size: u64,
+    pub reward: u64,
+}
+
+impl SummaryAccumulator {
+    pub fn add(&mut self, status: &BundleStatus) {
+        *self
+            .status_counts
+            .entry(status.status.to_string())
+            .or_insert(0) += 1;
+        self.number_of_bundles += 1;
+        self.number_of_files += status.number_of_files;
+        self.data_size += status.data_size;
+        self.reward += status.reward;
+    }
+
+    pub fn footer_string(&self, output_format: &OutputFormat) -> String {
+        match output_format {
+            OutputFormat::Display | OutputFormat::DisplayVerbose => {
+                let counts = vec![
+                    StatusCode::Submitted,
+                    StatusCode::Pending,
+                    StatusCode::Confirmed,
+                    StatusCode::NotFound,
+                ]
+                .into_iter()
+                .filter_map(|c| {
+                    self.status_counts
+                        .get(&c.to_string())
+                        .map(|n| format!("{}: {}", c, n))
+                })
+                .collect::<Vec<String>>()
+                .join(", ");
+                format!(
+                    "{:-<width$}\n totals: {} bundles ({})  {} files  {} KB  {:.6} AR\n",
+                    "",
+                    self.number_of_bundles,
+                    counts,
+                    self.number_of_files,
+                    self.data_size / 1000,
+                    self.reward as f64 / WINSTONS_PER_AR as f64,
+                    width = bundle_table_width(),
+                )
+            }
+            OutputFormat::DisplayQuiet => String::new(),
+            _ => output_format.json_string(&json!({ "summary": self })),
+        }
+    }
+}
+
+/// Throughput of an upload run, printed after the summary footer to help with tuning `--buffer`
+/// and `--bundle-size`.
+#[derive(Serialize, Debug, Clone)]
+pub struct Metrics {
+    #[serde(skip)]
+    started: Instant,
+    #[serde(skip)]
+    retries_at_start: usize,
+    #[serde(skip)]
+    total_post_latency: StdDuration,
+    #[serde(skip)]
+    connections_at_start: (u64, u64, StdDuration),
+    pub elapsed_secs: f64,
+    pub bundles: u64,
+    pub items: u64,
+    pub bytes_posted: u64,
+    pub retries: u64,
+    pub mb_per_sec: f64,
+    pub items_per_sec: f64,
+    pub bundles_per_min: f64,
+    pub avg_post_latency_secs: f64,
+    /// Connections opened to the gateway during the run, see [`ConnectionStats`].
+    pub connections_opened: u64,
+    /// Requests made during the run on connections that were already open.
+    pub connections_reused: u64,
+    /// Average time taken to open a connection during the run, including the TLS handshake.
+    pub avg_connect_secs: f64,
+}
+
+impl Metrics {
+    /// Starts timing a run, with `retries` being the current count of retried posts and
+    /// `connections` the stats of the client the run's requests are made with.
+    pub fn start(retries: usize, connections: &ConnectionStats) -> Self {
+        Self {
+            started: Instant::now(),
+            retries_at_start: retries,
+            total_post_latency: StdDuration::default(),
+            connections_at_start: (
+                connections.opened(),
+                connections.requests(),
+                connections.connect_time(),
+            ),
+            elapsed_secs: 0.0,
+            bundles: 0,
+            items: 0,
+            bytes_posted: 0,
+            retries: 0,
+            mb_per_sec: 0.0,
+            items_per_sec: 0.0,
+            bundles_per_min: 0.0,
+            avg_post_latency_secs: 0.0,
+            connections_opened: 0,
+            connections_reused: 0,
+            avg_connect_secs: 0.0,
+        }
+    }
+
+    pub fn add(&mut self, status: &BundleStatus) {
+        self.bundles += 1;
+        self.items += status.number_of_files;
+        self.bytes_posted += status.post_metrics.bytes;
+        self.total_post_latency += status.post_metrics.latency;
+    }
+
+    /// Stops timing the run and calculates rates, with `retries` being the current count of
+    /// retried posts.
+    pub fn finish(&mut self, retries: usize, connections: &ConnectionStats) {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        self.elapsed_secs = elapsed;
+        self.retries = retries.saturating_sub(self.retries_at_start) as u64;
+        let (opened_at_start, requests_at_start, connect_time_at_start) = self.connections_at_start;
+        self.connections_opened = connections.opened().saturating_sub(opened_at_start);
+        self.connections_reused = connections
+            .requests()
+            .saturating_sub(requests_at_start)
+            .saturating_sub(self.connections_opened);
+        if self.connections_opened > 0 {
+            self.avg_connect_secs = connections
+                .connect_time()
+                .saturating_sub(connect_time_at_start)
+                .as_secs_f64()
+                / self.connections_opened as f64;
+        }
+        if elapsed > 0.0 {
+            self.mb_per_sec = self.bytes_posted as f64 / 1_000_000.0 / elapsed;
+            self.items_per_sec = self.items as f64 / elapsed;
+            self.bundles_per_min = self.bundles as f64 * 60.0 / elapsed;
+        }
+        if self.bundles > 0 {
+            self.avg_post_latency_secs =
+                self.total_post_latency.as_secs_f64() / self.bundles as f64;
+        }
+    }
+
+    pub fn metrics_string(&self, output_format: &OutputFormat) -> String {
+        match output_format {
+            OutputFormat::Display | OutputFormat::DisplayVerbose => format!(
+                " elapsed: {:.1}s  {:.3} MB/s  {:.2} items/s  {:.2} bundles/min  avg post: {:.2}s  retries: {}  connections: {} opened ({:.3}s avg), {} reused\n",
+                self.elapsed_secs,
+                self.mb_per_sec,
+                self.items_per_sec,
+                self.bundles_per_min,
+                self.avg_post_latency_secs,
+                self.retries,
+                self.connections_opened,
+                self.avg_connect_secs,
+                self.connections_reused,
+            ),
+            OutputFormat::DisplayQuiet => String::new(),
+            _ => output_format.json_string(&json!({ "metrics": self })),
+        }
+    }
+}
+
+/// Transaction posted during an upload run, either a bundle or a single file, see [`Receipt`].
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct ReceiptTransaction {
+    pub id: Base64,
+    pub file_paths: Vec<PathBuf>,
+    pub data_size: u64,
+    pub reward: u64,
+    /// Paid for the transaction if it was paid for with SOL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lamports: Option<u64>,
+}
+
+/// Files that failed to upload during a run with the error they failed with.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct ReceiptFailure {
+    pub file_paths: Vec<PathBuf>,
+    pub error: String,
+}
+
+/// Record of the transactions posted by an upload run and the files in each, written to the log
+/// dir as [`Receipt::file_name`] when the run finishes so that it can be read without parsing
+/// the output of the command.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Receipt {
+    pub arloader_version: String,
+    #[serde(with = "datetime_millis")]
+    pub finished_at: DateTime<Utc>,
+    pub number_of_files: u64,
+    pub data_size: u64,
+    pub reward: u64,
+    /// Total paid with SOL, if any transactions were paid for with SOL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lamports: Option<u64>,
+    pub transactions: Vec<ReceiptTransaction>,
+    pub failures: Vec<ReceiptFailure>,
+}
+
+impl Default for Receipt {
+    fn default() -> Self {
+        Self {
+            arloader_version: VERSION.to_string(),
+            finished_at: Utc::now().trunc_subsecs(3),
+            number_of_files: 0,
+            data_size: 0,
+            reward: 0,
+            lamports: None,
+            transactions: Vec::new(),
+            failures: Vec::new(),
+        }
+    }
+}
+
+impl Receipt {
+    /// `receipt_<TIMESTAMP>.json`, with the unix time in milliseconds the run finished at, or
+    /// `receipt_<TIMESTAMP>_<n>.json` for `n` > 0 if runs finishing at the same time write to the
+    /// same log dir.
+    pub fn file_name(&self, n: u32) -> String {
+        match n {
+            0 => format!("receipt_{}.json", self.finished_at.timestamp_millis()),
+            n => format!("receipt_{}_{}.json", self.finished_at.timestamp_millis(), n),
+        }
+    }
+
+    pub fn add(&mut self, transaction: ReceiptTransaction) {
+        self.number_of_files += transaction.file_paths.len() as u64;
+        self.data_size += transaction.data_size;
+        self.reward += transaction.reward;
+        if let Some(lamports) = transaction.lamports {
+            *self.lamports.get_or_insert(0) += lamports;
+        }
+        self.transactions.push(transaction);
+    }
+
+    pub fn add_bundle(&mut self, status: &BundleStatus) {
+        self.add(ReceiptTransaction {
+            id: status.id.clone(),
+            file_paths: status
+                .file_path_set()
+                .into_iter()
+                .map(PathBuf::from)
+                .collect(),
+            data_size: status.data_size,
+            reward: status.reward,
+            lamports: status.sol_sig.as_ref().map(|s| s.lamports),
+        });
+    }
+
+    pub fn add_status(&mut self, status: &Status) {
+        self.add(ReceiptTransaction {
+            id: status.id.clone(),
+            file_paths: status.file_path.iter().cloned().collect(),
+            data_size: status.data_size.unwrap_or_default(),
+            reward: status.reward,
+            lamports: status.sol_sig.as_ref().map(|s| s.lamports),
+        });
+    }
+
+    pub fn add_failure(&mut self, failed: &FailedUpload) {
+        self.failures.push(ReceiptFailure {
+            file_paths: failed.file_paths.clone(),
+            error: failed.error.to_string(),
+        });
+    }
+
+    /// Adds an error that stopped the run, along with the files it left unprocessed if it's an
+    /// [`Error::NftUploadFailed`].
+    pub fn add_error(&mut self, error: &Error) {
+        let file_paths = match error {
+            Error::NftUploadFailed { file_paths, .. } => file_paths.clone(),
+            _ => Vec::new(),
+        };
+        self.failures.push(ReceiptFailure {
+            file_paths,
+            error: error.to_string(),
+        });
+    }
+
+    /// Adds the transactions and failures of `other`, e.g., from a stage of an NFT upload.
+    pub fn merge(&mut self, other: Receipt) {
+        other.transactions.into_iter().for_each(|t| self.add(t));
+        self.failures.extend(other.failures);
+    }
+
+    /// Marks the run as finished now, to the millisecond as it's written.
+    pub fn finish(&mut self) {
+        self.finished_at = Utc::now().trunc_subsecs(3);
+    }
+}
+
+/// Controls output format, including quiet, verbose and json formats. Records printed with
+/// [`RecordPrinter`] are written as a json array with `Json` and `JsonCompact`, and as one
+/// compact object per line with `JsonLines`.
 #[derive(Debug, Clone, Copy)]
 pub enum OutputFormat {
     Display,
     Json,
     JsonCompact,
+    JsonLines,
     DisplayQuiet,
     DisplayVerbose,
 }
 
 impl OutputFormat {
+    pub fn is_json(&self) -> bool {
+        matches!(
+            self,
+            OutputFormat::Json | OutputFormat::JsonCompact | OutputFormat::JsonLines
+        )
+    }
+
+    /// Formats `value` on a line of its own, pretty printed with `Json` and compact otherwise.
+    pub fn json_string<T: Serialize>(&self, value: &T) -> String {
+        let mut string = match self {
+            OutputFormat::Json => serde_json::to_string_pretty(value).unwrap(),
+            _ => serde_json::to_string(value).unwrap(),
+        };
+        string.push('\n');
+        string
+    }
+
     pub fn formatted_string<T>(&self, item: &T) -> String
     where
         T: Serialize + fmt::Display + QuietDisplay + VerboseDisplay,
@@ -307,25 +1229,297 @@ impl OutputFormat {
                 VerboseDisplay::write_str(item, &mut s).unwrap();
                 s
             }
-            OutputFormat::Json => {
-                let mut string = serde_json::to_string_pretty(item).unwrap();
-                ",\n".chars().for_each(|c| string.push(c));
-                string
+            OutputFormat::Json | OutputFormat::JsonCompact | OutputFormat::JsonLines => {
+                self.json_string(item)
             }
-            OutputFormat::JsonCompact => {
-                let mut string = serde_json::to_value(item).unwrap().to_string();
-                ",\n".chars().for_each(|c| string.push(c));
-                string
+        }
+    }
+}
+
+/// Prints a sequence of records in an [`OutputFormat`], with the header of `T` before the
+/// first record in display formats. With `Json` and `JsonCompact`, records are elements of an
+/// array that is opened before the first and closed by [`RecordPrinter::finish_string`], so
+/// that the output can be parsed as a whole.
+pub struct RecordPrinter {
+    output_format: OutputFormat,
+    table: StatusTable,
+    header: String,
+    count: usize,
+}
+
+impl RecordPrinter {
+    /// Sizes the table once, so that the header and every row share the same layout.
+    pub fn new<T: OutputHeader>(output_format: &OutputFormat) -> Self {
+        let table = StatusTable::new();
+        Self {
+            output_format: *output_format,
+            header: T::table_header_string(&table, output_format),
+            table,
+            count: 0,
+        }
+    }
+
+    /// Formats `item` as the next record, preceded by the header if it's the first one.
+    pub fn record_string<T>(&mut self, item: &T) -> String
+    where
+        T: Serialize + fmt::Display + QuietDisplay + VerboseDisplay + OutputHeader,
+    {
+        let mut string = String::new();
+        if self.count == 0 && !self.header.is_empty() {
+            string.push_str(&self.header);
+            string.push('\n');
+        }
+        let record = match self.output_format {
+            OutputFormat::Display => item.table_row_string(&self.table),
+            _ => self.output_format.formatted_string(item),
+        };
+        string.push_str(&self.next_string(record));
+        string
+    }
+
+    /// Adds a footer formatted for the output format, e.g., by
+    /// [`SummaryAccumulator::footer_string`], after the records. In json formats it's another
+    /// element of the output.
+    pub fn footer_string(&mut self, footer: String) -> String {
+        if footer.is_empty() {
+            return footer;
+        }
+        self.next_string(footer)
+    }
+
+    /// Closes the array of records in `Json` and `JsonCompact`, which is empty if no records
+    /// were printed.
+    pub fn finish_string(&self) -> String {
+        match (self.output_format, self.count) {
+            (OutputFormat::Json | OutputFormat::JsonCompact, 0) => "[]\n".to_string(),
+            (OutputFormat::Json | OutputFormat::JsonCompact, _) => "\n]\n".to_string(),
+            _ => String::new(),
+        }
+    }
+
+    fn next_string(&mut self, string: String) -> String {
+        self.count += 1;
+        match self.output_format {
+            OutputFormat::Json | OutputFormat::JsonCompact => {
+                let separator = if self.count == 1 { "[\n" } else { ",\n" };
+                format!("{}{}", separator, string.trim_end())
             }
+            _ => string,
+        }
+    }
+}
+
+/// Hex encoded BLAKE3 hash recorded for uploaded files.
+pub fn data_hash(data: &[u8]) -> String {
+    blake3::hash(data).to_hex().to_string()
+}
+
+/// Same as [`data_hash`] for the contents of `file_path`, read a block at a time.
+pub fn file_data_hash(file_path: &Path) -> std::io::Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    std::io::copy(&mut std::fs::File::open(file_path)?, &mut hasher)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// File referenced by a status, along with the size and hash recorded when it was uploaded, if
+/// any. Statuses written by earlier versions don't include them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedFile {
+    pub file_path: PathBuf,
+    /// Id of the transaction or data item the file was uploaded with.
+    pub id: Base64,
+    pub data_size: Option<u64>,
+    pub data_hash: Option<String>,
+}
+
+impl RecordedFile {
+    pub fn from_status(status: &Status) -> Option<Self> {
+        status.file_path.as_ref().map(|file_path| Self {
+            file_path: file_path.clone(),
+            id: status.id.clone(),
+            data_size: status.data_size,
+            data_hash: status.data_hash.clone(),
+        })
+    }
+
+    /// From one of the entries of [`BundleStatus::file_paths`].
+    pub fn from_manifest_entry(file_path: &str, entry: &Value) -> Self {
+        Self {
+            file_path: PathBuf::from(file_path),
+            id: entry["id"]
+                .as_str()
+                .and_then(|id| Base64::from_str(id).ok())
+                .unwrap_or_default(),
+            data_size: entry["data_size"].as_u64(),
+            data_hash: entry["data_hash"].as_str().map(String::from),
+        }
+    }
+
+    /// Compares the local file with what was recorded, only hashing it if its size matches.
+    pub fn check(&self) -> LocalFileState {
+        let metadata = match std::fs::metadata(&self.file_path) {
+            Ok(metadata) if metadata.is_file() => metadata,
+            _ => return LocalFileState::Missing,
+        };
+        if self.data_size.map_or(false, |size| size != metadata.len()) {
+            return LocalFileState::Modified;
+        }
+        let recorded_hash = match &self.data_hash {
+            Some(hash) => hash,
+            None => return LocalFileState::Unverified,
+        };
+        match file_data_hash(&self.file_path) {
+            Err(_) => LocalFileState::Missing,
+            Ok(hash) if &hash == recorded_hash => LocalFileState::Ok,
+            Ok(_) => LocalFileState::Modified,
+        }
+    }
+}
+
+/// Data size and reward recorded for a transaction, used to estimate the cost of uploading the
+/// same data again.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecordedSize {
+    pub id: Base64,
+    pub file_paths: Vec<PathBuf>,
+    pub data_size: u64,
+    /// Bytes of a bundle in addition to `data_size`, including its tags bytes, see
+    /// [`BundleStatus::overhead_bytes`]. Zero for transactions of single files, and for bundles
+    /// with statuses written before it was recorded.
+    pub overhead_bytes: u64,
+    /// Reward paid in winstons.
+    pub reward: u64,
+}
+
+impl RecordedSize {
+    /// Size of the data of the transaction the reward was paid for.
+    pub fn transaction_size(&self) -> u64 {
+        self.data_size + self.overhead_bytes
+    }
+
+    pub fn from_bundle_status(status: &BundleStatus) -> Self {
+        Self {
+            id: status.id.clone(),
+            file_paths: status
+                .file_paths
+                .as_object()
+                .map(|m| m.keys().map(PathBuf::from).collect())
+                .unwrap_or_default(),
+            data_size: status.data_size,
+            overhead_bytes: status.overhead_bytes,
+            reward: status.reward,
+        }
+    }
+
+    /// Returns `None` for statuses written before sizes were recorded.
+    pub fn from_status(status: &Status) -> Option<Self> {
+        status.data_size.map(|data_size| Self {
+            id: status.id.clone(),
+            file_paths: status.file_path.iter().cloned().collect(),
+            data_size,
+            overhead_bytes: 0,
+            reward: status.reward,
+        })
+    }
+}
+
+/// Result of [`RecordedFile::check`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum LocalFileState {
+    Ok,
+    Modified,
+    Missing,
+    /// The file exists but no hash was recorded to compare it to.
+    Unverified,
+}
+
+impl fmt::Display for LocalFileState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = match self {
+            LocalFileState::Ok => "ok",
+            LocalFileState::Modified => "modified",
+            LocalFileState::Missing => "missing",
+            LocalFileState::Unverified => "unverified",
+        };
+        f.pad(state)
+    }
+}
+
+/// Result of checking one data item of a bundle against its [`BundleStatus`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundleItemCheck {
+    pub id: Base64,
+    /// Path the id is recorded under in [`BundleStatus::file_paths`], if any.
+    pub file_path: Option<PathBuf>,
+    /// Number of times the id appears in the bundle.
+    pub occurrences: usize,
+    /// Why the signature or id of the data item didn't verify, if it didn't.
+    pub invalid: Option<String>,
+    /// Local file compared to the data item, if the file still exists.
+    pub local: Option<LocalFileState>,
+}
+
+impl BundleItemCheck {
+    /// Reasons the check failed, empty if it passed.
+    pub fn failures(&self) -> Vec<String> {
+        let mut failures = Vec::new();
+        if self.file_path.is_none() {
+            failures.push("not in file_paths".to_string());
+        }
+        match self.occurrences {
+            0 => failures.push("not in bundle".to_string()),
+            1 => {}
+            n => failures.push(format!("appears {} times", n)),
+        }
+        if let Some(invalid) = &self.invalid {
+            failures.push(format!("invalid: {}", invalid));
         }
+        if self.local == Some(LocalFileState::Modified) {
+            failures.push("local file differs".to_string());
+        }
+        failures
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.failures().is_empty()
     }
 }
 
-/// Implements header for output with multiple records.
-pub trait OutputHeader<T> {
-    fn header_string(output_format: &OutputFormat) -> String
+/// Result of comparing the data a gateway serves for an uploaded file with the local file, see
+/// [`crate::Arweave::verify_upload`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UploadCheck {
+    pub file_path: PathBuf,
+    /// Id the file was uploaded with, `None` if no status was found for it.
+    pub id: Option<Base64>,
+    /// Why the check failed, `None` if it passed.
+    pub failure: Option<String>,
+}
+
+impl UploadCheck {
+    pub fn is_ok(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// Implements headers for output of records.
+pub trait OutputHeader {
+    /// Header for a table with multiple records.
+    fn header_string(output_format: &OutputFormat) -> String;
+    /// Compact header for display of a single record.
+    fn single_header_string(output_format: &OutputFormat) -> String;
+    /// Header for a table laid out by `table`, which [`RecordPrinter`] builds once for all of
+    /// its records. Tables with fixed columns ignore it.
+    fn table_header_string(_table: &StatusTable, output_format: &OutputFormat) -> String {
+        Self::header_string(output_format)
+    }
+    /// Display row for a table laid out by `table`.
+    fn table_row_string(&self, _table: &StatusTable) -> String
     where
-        T: Serialize + fmt::Display + QuietDisplay + VerboseDisplay;
+        Self: fmt::Display,
+    {
+        self.to_string()
+    }
 }
 
 /// Implements output for quiet display output format.
@@ -341,3 +1535,579 @@ pub trait VerboseDisplay: fmt::Display {
         write!(w, "{}", self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        merge_duplicate_statuses, parse_status, sort_statuses, to_status_json, truncate_middle,
+        Base64, BundleStatus, Clock, ConfirmationsSource, OutputFormat, OutputHeader, RawStatus,
+        RecordPrinter, SortKey, Status, StatusCode, StatusTable, SummaryAccumulator,
+        STATUS_SCHEMA_VERSION,
+    };
+    use crate::error::Error;
+    use chrono::{DateTime, Duration, Utc};
+    use matches::assert_matches;
+    use serde_json::{json, Value};
+    use std::path::{Path, PathBuf};
+    use std::str::FromStr;
+
+    struct FixedClock(DateTime<Utc>);
+
+    impl Clock for FixedClock {
+        fn now(&self) -> DateTime<Utc> {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_parse_status_versions() {
+        let path = Path::new("status.json");
+        let mut value = serde_json::to_value(&Status::default()).unwrap();
+        assert_eq!(value["schema_version"], STATUS_SCHEMA_VERSION);
+
+        // Unknown fields are ignored and a missing version is read as the current one.
+        value["added_later"] = json!({"nested": true});
+        value.as_object_mut().unwrap().remove("schema_version");
+        let status: Status = parse_status(&value.to_string(), path).unwrap();
+        assert_eq!(status.schema_version, STATUS_SCHEMA_VERSION);
+
+        value["schema_version"] = json!(999);
+        value["status"] = json!("Archived");
+        assert_matches!(
+            parse_status::<Status>(&value.to_string(), path),
+            Err(Error::UnsupportedStatusVersion { version: 999, .. })
+        );
+    }
+
+    #[test]
+    fn test_reconcile_confirmations() {
+        let data = std::fs::read_to_string("tests/fixtures/tx_status.json").unwrap();
+        let mut raw_status: RawStatus = serde_json::from_str(&data).unwrap();
+        assert_eq!(raw_status.block_height, 882131);
+        assert_eq!(raw_status.confirmations_source, ConfirmationsSource::Node);
+
+        // A stale height never lowers the count reported by the node.
+        raw_status.reconcile_confirmations(882000);
+        assert_eq!(raw_status.number_of_confirmations, 3);
+        raw_status.reconcile_confirmations(882133);
+        assert_eq!(raw_status.number_of_confirmations, 3);
+        assert_eq!(raw_status.confirmations_source, ConfirmationsSource::Node);
+
+        raw_status.reconcile_confirmations(882140);
+        assert_eq!(raw_status.number_of_confirmations, 10);
+        assert_eq!(raw_status.confirmations_source, ConfirmationsSource::Height);
+
+        let mut value: Value = serde_json::from_str(&data).unwrap();
+        value
+            .as_object_mut()
+            .unwrap()
+            .remove("number_of_confirmations");
+        let mut raw_status: RawStatus = serde_json::from_value(value).unwrap();
+        assert_eq!(raw_status.number_of_confirmations, 0);
+        raw_status.reconcile_confirmations(882131);
+        assert_eq!(raw_status.number_of_confirmations, 1);
+        assert_eq!(raw_status.confirmations_source, ConfirmationsSource::Height);
+    }
+
+    #[test]
+    fn test_merge_duplicate_statuses() {
+        let bundle = |id: u8, status: StatusCode, paths: Value| BundleStatus {
+            id: Base64(vec![id; 32]),
+            status,
+            file_paths: paths,
+            ..BundleStatus::default()
+        };
+        let both = json!({"0.png": {"id": "a"}, "1.png": {"id": "b"}});
+        let both_reordered = json!({"1.png": {"id": "c"}, "0.png": {"id": "d"}});
+
+        let merged = merge_duplicate_statuses(vec![
+            bundle(1, StatusCode::NotFound, both.clone()),
+            bundle(2, StatusCode::Confirmed, both_reordered),
+            bundle(3, StatusCode::Pending, both),
+            bundle(4, StatusCode::Submitted, json!({"0.png": {"id": "e"}})),
+        ]);
+        assert_eq!(merged.len(), 1);
+        let kept = &merged[0].kept;
+        assert_eq!(kept.id, Base64(vec![2; 32]));
+        assert_eq!(
+            kept.previous
+                .iter()
+                .map(|p| (p.id.0[0], p.status.clone()))
+                .collect::<Vec<_>>(),
+            vec![(3, StatusCode::Pending), (1, StatusCode::NotFound)]
+        );
+        assert_eq!(merged[0].removed.len(), 2);
+
+        // Ties on status go to the most confirmations.
+        let mut more_confirmed = bundle(5, StatusCode::Confirmed, json!({"2.png": {}}));
+        more_confirmed.raw_status = Some(RawStatus {
+            block_height: 1,
+            block_indep_hash: Base64(vec![1; 48]),
+            number_of_confirmations: 20,
+            confirmations_source: ConfirmationsSource::Node,
+        });
+        let merged = merge_duplicate_statuses(vec![
+            bundle(6, StatusCode::Confirmed, json!({"2.png": {}})),
+            more_confirmed,
+        ]);
+        assert_eq!(merged[0].kept.id, Base64(vec![5; 32]));
+
+        let value = serde_json::to_value(&merged[0].kept).unwrap();
+        let status: BundleStatus = serde_json::from_value(value).unwrap();
+        assert_eq!(status.previous, merged[0].kept.previous);
+        assert!(serde_json::to_value(&BundleStatus::default()).unwrap()["previous"].is_null());
+    }
+
+    fn raw_status(number_of_confirmations: u64) -> Option<RawStatus> {
+        Some(RawStatus {
+            block_height: 1,
+            block_indep_hash: Base64(vec![1; 48]),
+            number_of_confirmations,
+            confirmations_source: ConfirmationsSource::Node,
+        })
+    }
+
+    #[test]
+    fn test_sort_bundle_statuses() {
+        // (id, status, confirms, data_size, file path)
+        let fixtures = [
+            (1, StatusCode::Confirmed, 30, 200, "b.png"),
+            (2, StatusCode::Pending, 0, 300, "d.png"),
+            (3, StatusCode::Confirmed, 5, 100, "a.png"),
+            (4, StatusCode::NotFound, 0, 400, "c.png"),
+        ];
+        let statuses: Vec<BundleStatus> = fixtures
+            .iter()
+            .map(|(id, status, confirms, data_size, path)| BundleStatus {
+                id: Base64(vec![*id; 32]),
+                status: status.clone(),
+                raw_status: if *confirms > 0 {
+                    raw_status(*confirms)
+                } else {
+                    None
+                },
+                data_size: *data_size,
+                file_paths: json!({ *path: {} }),
+                ..BundleStatus::default()
+            })
+            .collect();
+
+        let sorted_ids = |sort_by: &str, desc: bool| {
+            let mut sorted = statuses.clone();
+            sort_statuses(&mut sorted, SortKey::from_str(sort_by).unwrap(), desc).unwrap();
+            sorted.iter().map(|s| s.id.0[0]).collect::<Vec<u8>>()
+        };
+
+        // Ties are broken by path.
+        assert_eq!(sorted_ids("confirms", false), vec![4, 2, 3, 1]);
+        assert_eq!(sorted_ids("confirms", true), vec![1, 3, 2, 4]);
+        assert_eq!(sorted_ids("status", false), vec![4, 2, 3, 1]);
+        assert_eq!(sorted_ids("status", true), vec![1, 3, 2, 4]);
+        assert_eq!(sorted_ids("size", false), vec![3, 1, 2, 4]);
+        assert_eq!(sorted_ids("size", true), vec![4, 2, 1, 3]);
+        assert_eq!(sorted_ids("path", false), vec![3, 1, 4, 2]);
+        assert_eq!(sorted_ids("path", true), vec![2, 4, 1, 3]);
+    }
+
+    #[test]
+    fn test_sort_statuses() {
+        let statuses: Vec<Status> = [(1, 10, "c.png"), (2, 0, "a.png"), (3, 20, "b.png")]
+            .iter()
+            .map(|(id, confirms, path)| Status {
+                id: Base64(vec![*id; 32]),
+                status: if *confirms > 0 {
+                    StatusCode::Confirmed
+                } else {
+                    StatusCode::Submitted
+                },
+                raw_status: raw_status(*confirms),
+                file_path: Some(PathBuf::from(path)),
+                ..Status::default()
+            })
+            .collect();
+
+        let sorted_ids = |sort_by: SortKey, desc: bool| {
+            let mut sorted = statuses.clone();
+            sort_statuses(&mut sorted, sort_by, desc)
+                .map(|_| sorted.iter().map(|s| s.id.0[0]).collect::<Vec<u8>>())
+        };
+        assert_eq!(sorted_ids(SortKey::Confirms, false).unwrap(), vec![2, 1, 3]);
+        assert_eq!(sorted_ids(SortKey::Confirms, true).unwrap(), vec![3, 1, 2]);
+        assert_eq!(sorted_ids(SortKey::Status, false).unwrap(), vec![2, 3, 1]);
+        assert_eq!(sorted_ids(SortKey::Status, true).unwrap(), vec![1, 3, 2]);
+        assert_eq!(sorted_ids(SortKey::Path, false).unwrap(), vec![2, 3, 1]);
+        assert_eq!(sorted_ids(SortKey::Path, true).unwrap(), vec![1, 3, 2]);
+        assert_matches!(
+            sorted_ids(SortKey::Size, false),
+            Err(Error::InvalidSortKey(_))
+        );
+        assert_matches!(SortKey::from_str("age"), Err(Error::InvalidSortKey(_)));
+    }
+
+    #[test]
+    fn test_status_code_default() {
+        assert_eq!(StatusCode::default(), StatusCode::Submitted);
+        assert_eq!(Status::default().status, StatusCode::Submitted);
+        assert_eq!(BundleStatus::default().status, StatusCode::Submitted);
+    }
+
+    #[test]
+    fn test_touch_with_backwards_clock() {
+        let mut status = Status::default();
+        let created_at = status.created_at;
+        let backwards_clock = FixedClock(created_at - Duration::hours(1));
+
+        status.touch(&backwards_clock);
+        assert!(status.last_modified > created_at);
+        let previous = status.last_modified;
+        status.touch(&backwards_clock);
+        assert!(status.last_modified > previous);
+        assert_eq!(status.touched_count, 2);
+
+        let mut bundle_status = BundleStatus::default();
+        let created_at = bundle_status.created_at;
+        bundle_status.touch(&FixedClock(created_at - Duration::days(1)));
+        assert!(bundle_status.last_modified > created_at);
+        assert_eq!(bundle_status.touched_count, 1);
+    }
+
+    #[test]
+    fn test_touch_with_forward_clock() {
+        let mut status = Status::default();
+        let now = status.last_modified + Duration::hours(1);
+        status.touch(&FixedClock(now));
+        assert_eq!(status.last_modified, now);
+        assert_eq!(status.touched_count, 1);
+    }
+
+    #[test]
+    fn test_touched_count_defaults() {
+        let mut value = serde_json::to_value(&Status::default()).unwrap();
+        value.as_object_mut().unwrap().remove("touched_count");
+        let status: Status = serde_json::from_value(value).unwrap();
+        assert_eq!(status.touched_count, 0);
+    }
+
+    #[test]
+    fn test_owner_address_defaults() {
+        let mut value = serde_json::to_value(&BundleStatus::default()).unwrap();
+        value.as_object_mut().unwrap().remove("owner_address");
+        let status: BundleStatus = serde_json::from_value(value).unwrap();
+        assert_eq!(status.owner_address, "");
+    }
+
+    #[test]
+    fn test_status_json_deterministic() {
+        let created_at = "2022-01-01T00:00:00.123456789Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap();
+        let raw_status = Some(RawStatus {
+            block_height: 1,
+            block_indep_hash: Base64(vec![1; 32]),
+            number_of_confirmations: 2,
+            confirmations_source: ConfirmationsSource::Node,
+        });
+        let first = BundleStatus {
+            id: Base64(vec![2; 32]),
+            file_paths: json!({
+                "0.png": {"id": "a", "content_type": "image/png"},
+                "1.png": {"id": "b", "content_type": "image/png"},
+            }),
+            created_at,
+            last_modified: created_at + Duration::microseconds(10),
+            raw_status: raw_status.clone(),
+            ..BundleStatus::default()
+        };
+        let mut file_paths = serde_json::Map::new();
+        file_paths.insert(
+            "1.png".into(),
+            json!({"content_type": "image/png", "id": "b"}),
+        );
+        file_paths.insert(
+            "0.png".into(),
+            json!({"content_type": "image/png", "id": "a"}),
+        );
+        let second = BundleStatus {
+            raw_status,
+            last_modified: created_at + Duration::microseconds(20),
+            file_paths: Value::Object(file_paths),
+            created_at: created_at + Duration::microseconds(5),
+            id: Base64(vec![2; 32]),
+            ..BundleStatus::default()
+        };
+
+        for pretty in [false, true] {
+            assert_eq!(
+                to_status_json(&first, pretty).unwrap(),
+                to_status_json(&second, pretty).unwrap()
+            );
+        }
+        assert_eq!(
+            to_status_json(&first, false).unwrap(),
+            r#"{"block_height":1,"block_indep_hash":"AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE","created_at":"2022-01-01T00:00:00.123Z","data_size":0,"file_paths":{"0.png":{"content_type":"image/png","id":"a"},"1.png":{"content_type":"image/png","id":"b"}},"id":"AgICAgICAgICAgICAgICAgICAgICAgICAgICAgICAgI","last_modified":"2022-01-01T00:00:00.123Z","number_of_confirmations":2,"number_of_files":0,"owner_address":"","reward":0,"status":"Submitted","touched_count":0}"#
+        );
+
+        let status: BundleStatus =
+            serde_json::from_str(&to_status_json(&first, true).unwrap()).unwrap();
+        assert_eq!(
+            status.created_at,
+            created_at - Duration::nanoseconds(456789)
+        );
+    }
+
+    #[test]
+    fn test_summary_footer() {
+        let mut summary = SummaryAccumulator::default();
+        vec![
+            (StatusCode::Confirmed, 10, 1_200_000, 2_000_000_000),
+            (StatusCode::Confirmed, 5, 600_000, 1_000_000_000),
+            (StatusCode::Pending, 3, 300_000, 500_000_000),
+        ]
+        .into_iter()
+        .for_each(|(status, number_of_files, data_size, reward)| {
+            summary.add(&BundleStatus {
+                status,
+                number_of_files,
+                data_size,
+                reward,
+                ..BundleStatus::default()
+            })
+        });
+
+        assert_eq!(
+            summary.footer_string(&OutputFormat::Display),
+            format!(
+                "{:-<83}\n totals: 3 bundles (Pending: 1, Confirmed: 2)  18 files  2100 KB  0.003500 AR\n",
+                ""
+            )
+        );
+        assert_eq!(summary.footer_string(&OutputFormat::DisplayQuiet), "");
+        assert_eq!(
+            summary.footer_string(&OutputFormat::JsonCompact),
+            "{\"summary\":{\"status_counts\":{\"Confirmed\":2,\"Pending\":1},\"number_of_bundles\":3,\"number_of_files\":18,\"data_size\":2100000,\"reward\":3500000000}}\n"
+        );
+    }
+
+    #[test]
+    fn test_record_printer() {
+        let statuses: Vec<BundleStatus> = (0..3)
+            .map(|i| BundleStatus {
+                id: Base64(vec![i; 32]),
+                number_of_files: 2,
+                ..BundleStatus::default()
+            })
+            .collect();
+        let mut summary = SummaryAccumulator::default();
+        statuses.iter().for_each(|s| summary.add(s));
+        let print = |output_format: OutputFormat, statuses: &[BundleStatus]| {
+            let mut printer = RecordPrinter::new::<BundleStatus>(&output_format);
+            let mut output: String = statuses.iter().map(|s| printer.record_string(s)).collect();
+            if !statuses.is_empty() {
+                output.push_str(&printer.footer_string(summary.footer_string(&output_format)));
+            }
+            output.push_str(&printer.finish_string());
+            output
+        };
+
+        for output_format in [OutputFormat::Json, OutputFormat::JsonCompact] {
+            let values: Vec<Value> =
+                serde_json::from_str(&print(output_format, &statuses)).unwrap();
+            assert_eq!(values.len(), 4);
+            assert_eq!(values[2]["id"], statuses[2].id.to_string());
+            assert_eq!(values[3]["summary"]["number_of_files"], 6);
+            let empty: Vec<Value> = serde_json::from_str(&print(output_format, &[])).unwrap();
+            assert!(empty.is_empty());
+        }
+
+        let output = print(OutputFormat::JsonLines, &statuses);
+        let values: Vec<Value> = output
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(values.len(), 4);
+        assert_eq!(values[0]["id"], statuses[0].id.to_string());
+        assert_eq!(print(OutputFormat::JsonLines, &[]), "");
+
+        let output = print(OutputFormat::Display, &statuses);
+        assert!(output.starts_with(&BundleStatus::header_string(&OutputFormat::Display)));
+        assert_eq!(output.matches("bundle txid").count(), 1);
+    }
+
+    #[test]
+    fn test_truncate_middle() {
+        assert_eq!(truncate_middle("short.png", 12), "short.png");
+        assert_eq!(truncate_middle("abcdefghijklmnop", 12), "abcde...mnop");
+        assert_eq!(truncate_middle("ünïcödé/päth.png", 9), "ünï...png");
+        assert_eq!(truncate_middle("abcdef", 2), "ab");
+    }
+
+    #[test]
+    fn test_status_table_widths() {
+        let status = Status {
+            id: Base64(vec![1; 32]),
+            status: StatusCode::Confirmed,
+            file_path: Some(PathBuf::from(
+                "some/very/long/directory/structure/image.png",
+            )),
+            raw_status: raw_status(12),
+            ..Status::default()
+        };
+        let id = "AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE";
+
+        let table = StatusTable { width: 80 };
+        assert_eq!(
+            table.header(),
+            format!(
+                " path           id{}status     confirms\n{}",
+                " ".repeat(43),
+                "-".repeat(80)
+            )
+        );
+        assert_eq!(
+            table.row(&status),
+            format!(" some/...e.png  {}  Confirmed        12\n", id)
+        );
+
+        let table = StatusTable { width: 120 };
+        assert_eq!(
+            table.header(),
+            format!(
+                " path{}id{}status     confirms\n{}",
+                " ".repeat(51),
+                " ".repeat(43),
+                "-".repeat(120)
+            )
+        );
+        assert_eq!(
+            table.row(&status),
+            format!(
+                " some/very/long/directory/structure/image.png{}{}  Confirmed        12\n",
+                " ".repeat(11),
+                id
+            )
+        );
+
+        let table = StatusTable { width: 200 };
+        assert_eq!(table.header().lines().last().unwrap().len(), 200);
+        assert_eq!(
+            table.row(&status),
+            format!(
+                " some/very/long/directory/structure/image.png{}{}  Confirmed        12\n",
+                " ".repeat(91),
+                id
+            )
+        );
+        for width in [80, 120, 200] {
+            let table = StatusTable { width };
+            assert_eq!(table.row(&status).trim_end_matches('\n').len(), width);
+        }
+    }
+
+    #[test]
+    fn test_headers() {
+        assert_eq!(
+            Status::header_string(&OutputFormat::Display),
+            StatusTable::new().header()
+        );
+        assert_eq!(
+            Status::single_header_string(&OutputFormat::Display),
+            format!(" {:<43}  {:<9}  confirms\n{:-<65}", "id", "status", "")
+        );
+        assert_eq!(
+            BundleStatus::header_string(&OutputFormat::Display),
+            format!(
+                " {:<43}  {:>6}  {:>6}  {:<11}  {:>8}\n{:-<83}",
+                "bundle txid", "items", "KB", "status", "confirms", ""
+            )
+        );
+        for output_format in [
+            OutputFormat::Json,
+            OutputFormat::JsonCompact,
+            OutputFormat::JsonLines,
+            OutputFormat::DisplayQuiet,
+            OutputFormat::DisplayVerbose,
+        ] {
+            assert_eq!(Status::header_string(&output_format), "");
+            assert_eq!(Status::single_header_string(&output_format), "");
+            assert_eq!(BundleStatus::header_string(&output_format), "");
+        }
+    }
+
+    #[test]
+    fn test_table_rules_match_rows() {
+        let header = BundleStatus::header_string(&OutputFormat::Display);
+        let (columns, rule) = header.split_once('\n').unwrap();
+        let row = BundleStatus::default().to_string();
+        assert_eq!(rule.len(), columns.len());
+        assert_eq!(rule.len(), row.trim_end_matches('\n').len());
+
+        let footer = SummaryAccumulator::default().footer_string(&OutputFormat::Display);
+        assert_eq!(footer.lines().next().unwrap(), rule);
+
+        let table = StatusTable { width: 120 };
+        let mut printer = RecordPrinter {
+            output_format: OutputFormat::Display,
+            header: Status::table_header_string(&table, &OutputFormat::Display),
+            table,
+            count: 0,
+        };
+        let status = Status {
+            file_path: Some(PathBuf::from("some/file.png")),
+            ..Status::default()
+        };
+        assert_eq!(
+            printer.record_string(&status),
+            format!("{}\n{}", table.header(), table.row(&status))
+        );
+    }
+
+    #[test]
+    fn test_bundle_overhead_verbose() {
+        let mut status = BundleStatus {
+            data_size: 3000,
+            ..BundleStatus::default()
+        };
+        let output = OutputFormat::DisplayVerbose.formatted_string(&status);
+        assert!(!output.contains("overhead:"));
+
+        status.tags_bytes = 200;
+        status.overhead_bytes = 1000;
+        let output = OutputFormat::DisplayVerbose.formatted_string(&status);
+        assert!(output.contains(&format!("{:<15} 200 bytes", "tags_bytes:")));
+        assert!(output.contains(&format!(
+            "{:<15} 1000 bytes (25.00% of bundle)",
+            "overhead:"
+        )));
+
+        let value = serde_json::to_value(&status).unwrap();
+        assert_eq!(value["overhead_bytes"], 1000);
+    }
+
+    #[test]
+    fn test_single_status_all_formats() {
+        let status = Status {
+            id: Base64(vec![44; 32]),
+            ..Status::default()
+        };
+        for output_format in [
+            OutputFormat::Display,
+            OutputFormat::Json,
+            OutputFormat::JsonCompact,
+            OutputFormat::JsonLines,
+            OutputFormat::DisplayQuiet,
+            OutputFormat::DisplayVerbose,
+        ] {
+            let _ = Status::single_header_string(&output_format);
+            let output = status.single_formatted_string(&output_format);
+            if let OutputFormat::DisplayQuiet = output_format {
+                assert_eq!(output, "");
+            } else {
+                assert!(output.contains(&status.id.to_string()));
+            }
+        }
+        assert_eq!(
+            status.single_formatted_string(&OutputFormat::Display),
+            format!(" {:<43}  {:<9}  {:>8}\n", status.id, "Submitted", 0)
+        );
+    }
+}
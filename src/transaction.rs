@@ -1,14 +1,18 @@
 //! Data structures for serializing and deserializing [`Transaction`]s and [`Tag`]s.
 
 use crate::{
+    bundle::BundleFile,
+    crypto::Hasher,
     error::Error,
-    merkle::{Node, Proof},
+    merkle::{chunks_and_proofs, chunks_len, generate_leaves_from_reader, Node, Proof},
+    NetworkParams, WINSTONS_PER_AR,
 };
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::str::FromStr;
+use std::sync::Arc;
 
 /// Transaction data structure per [Arweave transaction spec](https://docs.arweave.org/developers/server/http-api#transaction-format).
-#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
 pub struct Transaction {
     pub format: u8,
     pub id: Base64,
@@ -29,6 +33,26 @@ pub struct Transaction {
     pub chunks: Vec<Node>,
     #[serde(skip)]
     pub proofs: Vec<Proof>,
+    /// Base and incremental winstons per block the reward was calculated from, set by
+    /// [`crate::Arweave::create_transaction`].
+    #[serde(skip)]
+    pub price_terms: Option<(u64, u64)>,
+    /// File the data is read from when it isn't held in `data`, e.g. a bundle created with
+    /// [`crate::Arweave::create_bundle_file_from_data_items`].
+    #[serde(skip)]
+    pub data_file: Option<Arc<BundleFile>>,
+}
+
+/// Reward of a [`Transaction`] calculated from the price terms it was created with, returned by
+/// [`Transaction::reward_breakdown`] and [`NetworkParams::reward_breakdown`].
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone, Copy)]
+pub struct RewardBreakdown {
+    /// Winstons for the first block.
+    pub base: u64,
+    /// Winstons for each additional block.
+    pub incremental: u64,
+    pub blocks: u64,
+    pub total: u64,
 }
 
 /// Chunk data structure per [Arweave chunk spec](https://docs.arweave.org/developers/server/http-api#upload-chunks).
@@ -43,6 +67,33 @@ pub struct Chunk {
     chunk: Base64,
 }
 
+/// Transfer of `quantity` winstons to `target` included with a data transaction, used to tip a
+/// wallet while uploading. Parsed from `<ADDRESS>:<AR>` strings.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Tip {
+    pub target: Base64,
+    #[serde(with = "stringify")]
+    pub quantity: u64,
+}
+
+impl FromStr for Tip {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidTip(s.to_string());
+        let (address, ar) = s.split_once(':').ok_or_else(invalid)?;
+        let target = Base64::from_str(address).map_err(|_| invalid())?;
+        if target.0.len() != 32 {
+            return Err(invalid());
+        }
+        let ar = ar.parse::<f64>().map_err(|_| invalid())?;
+        if !ar.is_finite() || ar <= 0.0 {
+            return Err(invalid());
+        }
+        let quantity = (ar * WINSTONS_PER_AR as f64).round() as u64;
+        Ok(Self { target, quantity })
+    }
+}
+
 /// Serializes and deserializes numbers represented as Strings. Used for `quantity`, `data_size`
 /// and `reward` [`Transaction`] fields so that they can be represented as numbers but be serialized
 /// to Strings as required by the Arweave spec.
@@ -86,24 +137,104 @@ impl Transaction {
             signature: self.signature.clone(),
             chunks: Vec::new(),
             proofs: Vec::new(),
+            price_terms: self.price_terms,
+            data_file: None,
         })
     }
+    /// Number of [`NetworkParams::block_size`] blocks the data is priced in.
+    pub fn num_blocks(&self, network_params: &NetworkParams) -> u64 {
+        network_params.blocks_len(self.data_size)
+    }
+    /// Number of chunks the transaction includes for upload, zero if it doesn't include its data.
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+    /// Reward calculated from the price terms the transaction was created with, `None` if it
+    /// wasn't created with [`crate::Arweave::create_transaction`], e.g. if it was deserialized.
+    /// `total` can be less than `reward` if the reward was raised afterwards, e.g. by
+    /// [`crate::Arweave::check_bundle_reward`].
+    pub fn reward_breakdown(&self, network_params: &NetworkParams) -> Option<RewardBreakdown> {
+        self.price_terms
+            .map(|price_terms| network_params.reward_breakdown(self.data_size, price_terms))
+    }
+    /// Number of chunks the transaction data is split into, computed from `data_size` so that it
+    /// is available whether or not the transaction includes its data.
+    pub fn total_chunks(&self) -> usize {
+        chunks_len(self.data_size) as usize
+    }
+    /// Returns the chunk at `idx` with its proof, read from `data_file` if the transaction
+    /// doesn't include its data.
     pub fn get_chunk(&self, idx: usize) -> Result<Chunk, Error> {
-        Ok(Chunk {
+        let (start, end) = (
+            self.chunks[idx].min_byte_range,
+            self.chunks[idx].max_byte_range,
+        );
+        let chunk = match &self.data_file {
+            Some(data_file) if self.data.0.is_empty() => data_file.read_range_sync(start, end)?,
+            _ => self.data.0[start..end].to_vec(),
+        };
+        Ok(self.chunk_with_data(idx, chunk))
+    }
+    /// Same as [`Transaction::get_chunk`], reading the chunk from `data_file` if it is set.
+    pub async fn read_chunk(&self, idx: usize) -> Result<Chunk, Error> {
+        match &self.data_file {
+            Some(data_file) => {
+                let chunk = data_file
+                    .read_range(
+                        self.chunks[idx].min_byte_range,
+                        self.chunks[idx].max_byte_range,
+                    )
+                    .await?;
+                Ok(self.chunk_with_data(idx, chunk))
+            }
+            None => self.get_chunk(idx),
+        }
+    }
+
+    /// Generates `chunks` and `proofs`, which aren't serialized, again from `data`, or from
+    /// `data_file` if the transaction doesn't include its data, e.g., to post the chunks of a
+    /// transaction loaded from json. They are the same as when the transaction was created, since
+    /// they only depend on the data. Returns [`Error::DataRootMismatch`] if the data doesn't
+    /// have the transaction's data root.
+    pub fn recompute_chunks(&mut self, hasher: &Hasher) -> Result<(), Error> {
+        let data_len = self.data_size as usize;
+        let leaves = match &self.data_file {
+            Some(data_file) if self.data.0.is_empty() => generate_leaves_from_reader(
+                std::fs::File::open(&data_file.path)?,
+                data_len,
+                hasher,
+            )?,
+            _ => generate_leaves_from_reader(&self.data.0[..], data_len, hasher)?,
+        };
+        let (root, chunks, proofs) = chunks_and_proofs(leaves, hasher)?;
+        if root.id[..] != self.data_root.0[..] {
+            return Err(Error::DataRootMismatch(self.id.to_string()));
+        }
+        self.chunks = chunks;
+        self.proofs = proofs;
+        Ok(())
+    }
+    /// Reads the data from `data_file` into `data`, if it is set and the data hasn't been read
+    /// yet, for posting the transaction with its data.
+    pub async fn load_data(&mut self) -> Result<(), Error> {
+        if let (Some(data_file), true) = (&self.data_file, self.data.0.is_empty()) {
+            self.data = Base64(data_file.read_all().await?);
+        }
+        Ok(())
+    }
+    fn chunk_with_data(&self, idx: usize, chunk: Vec<u8>) -> Chunk {
+        Chunk {
             data_root: self.data_root.clone(),
             data_size: self.data_size,
             data_path: Base64(self.proofs[idx].proof.clone()),
             offset: self.proofs[idx].offset,
-            chunk: Base64(
-                self.data.0[self.chunks[idx].min_byte_range..self.chunks[idx].max_byte_range]
-                    .to_vec(),
-            ),
-        })
+            chunk: Base64(chunk),
+        }
     }
 }
 
 /// Implemented on [`Transaction`] to create root [`DeepHashItem`]s used by
-/// [`crate::crypto::Provider::deep_hash`] in the creation of a transaction
+/// [`crate::crypto::Hasher::deep_hash`] in the creation of a transaction
 /// signatures.
 pub trait ToItems<'a, T> {
     fn to_deep_hash_item(&'a self) -> Result<DeepHashItem, Error>;
@@ -177,12 +308,16 @@ impl FromUtf8Strs<Tag<Base64>> for Tag<Base64> {
     }
 }
 
+/// Returns [`Error::TagLimitExceeded`] for names or values longer than data items allow, see
+/// [`crate::bundle::check_tag`].
 impl FromUtf8Strs<Tag<String>> for Tag<String> {
     fn from_utf8_strs(name: &str, value: &str) -> Result<Self, Error> {
         let name = String::from(name);
         let value = String::from(value);
 
-        Ok(Self { name, value })
+        let tag = Self { name, value };
+        crate::bundle::check_tag(&tag)?;
+        Ok(tag)
     }
 }
 
@@ -270,7 +405,7 @@ impl<'de> Deserialize<'de> for Base64 {
     }
 }
 
-/// Recursive data structure that facilitates [`crate::crypto::Provider::deep_hash`] accepting nested
+/// Recursive data structure that facilitates [`crate::crypto::Hasher::deep_hash`] accepting nested
 /// arrays of arbitrary depth as an argument with a single type.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum DeepHashItem {
@@ -285,6 +420,14 @@ impl DeepHashItem {
     pub fn from_children(children: Vec<DeepHashItem>) -> DeepHashItem {
         Self::List(children)
     }
+
+    /// Nested json arrays of the blobs as base64url strings.
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Self::Blob(blob) => serde_json::Value::String(Base64(blob.clone()).to_string()),
+            Self::List(list) => serde_json::Value::Array(list.iter().map(Self::to_json).collect()),
+        }
+    }
 }
 
 /// Not currently in use.
@@ -307,7 +450,11 @@ impl Uploader {
 
 #[cfg(test)]
 mod tests {
-    use super::{Base64, DeepHashItem, Error, FromUtf8Strs, Tag, ToItems};
+    use super::{
+        Base64, DeepHashItem, Error, FromUtf8Strs, RewardBreakdown, Tag, Tip, ToItems, Transaction,
+    };
+    use crate::{NetworkParams, BLOCK_SIZE};
+    use matches::assert_matches;
     use serde_json;
     use std::str::FromStr;
 
@@ -343,6 +490,25 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_tip_from_str() -> Result<(), Error> {
+        let address = "7eV1qae4qVNqsNChg3Scdi-DpOLJPCogct4ixoq1WNg";
+        let tip = Tip::from_str(&format!("{}:0.5", address))?;
+        assert_eq!(tip.target.to_string(), address);
+        assert_eq!(tip.quantity, 500_000_000_000);
+
+        for invalid in [
+            address.to_string(),
+            format!("{}:", address),
+            format!("{}:-1", address),
+            format!("{}:0", address),
+            "LCwsLCwsLA:1".to_string(),
+        ] {
+            assert_matches!(Tip::from_str(&invalid), Err(Error::InvalidTip(_)));
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_tags_deep_hash_item2() -> Result<(), Error> {
         let tags = vec![
@@ -371,4 +537,56 @@ mod tests {
         assert_eq!(deep_hash_item, deep_hash_item_actual);
         Ok(())
     }
+
+    #[test]
+    fn test_reward_breakdown() {
+        let network_params = NetworkParams::default();
+        for (data_size, blocks) in [
+            (0, 0),
+            (1, 1),
+            (BLOCK_SIZE - 1, 1),
+            (BLOCK_SIZE, 1),
+            (BLOCK_SIZE + 1, 2),
+            (3 * BLOCK_SIZE, 3),
+            (3 * BLOCK_SIZE + 1, 4),
+        ] {
+            let mut transaction = Transaction {
+                data_size,
+                ..Transaction::default()
+            };
+            assert_eq!(transaction.num_blocks(&network_params), blocks);
+            assert_eq!(transaction.chunk_count(), 0);
+            assert_eq!(transaction.reward_breakdown(&network_params), None);
+
+            transaction.price_terms = Some((100, 10));
+            assert_eq!(
+                transaction.reward_breakdown(&network_params),
+                Some(RewardBreakdown {
+                    base: 100,
+                    incremental: 10,
+                    blocks,
+                    total: 100 + 10 * blocks.saturating_sub(1),
+                })
+            );
+            assert_eq!(
+                transaction.clone_with_no_data().unwrap().price_terms,
+                Some((100, 10))
+            );
+        }
+
+        let network_params = NetworkParams {
+            block_size: 1000,
+            ..NetworkParams::default()
+        };
+        let transaction = Transaction {
+            data_size: 2500,
+            price_terms: Some((100, 10)),
+            ..Transaction::default()
+        };
+        assert_eq!(transaction.num_blocks(&network_params), 3);
+        assert_eq!(
+            transaction.reward_breakdown(&network_params).unwrap().total,
+            120
+        );
+    }
 }
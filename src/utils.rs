@@ -1,9 +1,21 @@
-//! Async [`TempDir`] for testing.
+//! Async [`TempDir`] for testing and helpers for filtering file paths, naming uploaded files and
+//! compressing requests.
 
 use crate::error::Error;
 use base64::{self, encode_config};
+use chrono::{DateTime, Utc};
+use flate2::{write::GzEncoder, Compression};
+use glob::{glob, Pattern};
 use ring::rand::{SecureRandom, SystemRandom};
-use std::{fs as fsstd, path::PathBuf};
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    env, fs as fsstd,
+    io::Write,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+    time::SystemTime,
+};
 use tokio::fs;
 
 /// Tuple struct with a [`PathBuf`] in it.
@@ -37,3 +49,576 @@ impl Drop for TempDir {
         }
     }
 }
+
+/// Cutoff for `--newer-than`, either a fixed time or the time of the previous run, resolved
+/// from the statuses in a log directory with [`last_run_time`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum NewerThan {
+    Time(DateTime<Utc>),
+    LastRun,
+}
+
+impl FromStr for NewerThan {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "@last-run" => Ok(Self::LastRun),
+            _ => DateTime::parse_from_rfc3339(s)
+                .map(|t| Self::Time(t.with_timezone(&Utc)))
+                .map_err(|_| Error::InvalidNewerThan(s.to_string())),
+        }
+    }
+}
+
+/// Filters `paths` to files modified after `t`. Paths without a readable modification time are
+/// kept so that the error surfaces when they are uploaded.
+pub fn filter_paths_newer_than<IP>(paths: IP, t: DateTime<Utc>) -> impl Iterator<Item = PathBuf>
+where
+    IP: IntoIterator<Item = PathBuf>,
+{
+    let t = SystemTime::from(t);
+    paths.into_iter().filter(
+        move |p| match fsstd::metadata(p).and_then(|m| m.modified()) {
+            Ok(modified) => modified > t,
+            Err(_) => true,
+        },
+    )
+}
+
+/// Patterns always excluded from the assets of NFT uploads, since metadata files are paired with
+/// assets by name and uploaded separately.
+pub const NFT_ASSET_EXCLUDES: [&str; 1] = ["*.json"];
+
+/// Filters out `paths` matching any of `patterns`, either as a path relative to the current
+/// directory or by file name alone. Matching is case sensitive.
+pub fn filter_paths_excluded<IP>(paths: IP, patterns: Vec<Pattern>) -> impl Iterator<Item = PathBuf>
+where
+    IP: IntoIterator<Item = PathBuf>,
+{
+    let current_dir = std::env::current_dir().ok();
+    paths.into_iter().filter(move |p| {
+        let relative = current_dir
+            .as_ref()
+            .and_then(|d| p.strip_prefix(d).ok())
+            .or_else(|| p.strip_prefix(".").ok())
+            .unwrap_or(p);
+        let file_name = p.file_name().map(|n| n.to_string_lossy());
+        !patterns.iter().any(|pattern| {
+            pattern.matches_path(relative)
+                || file_name.as_ref().map_or(false, |n| pattern.matches(n))
+        })
+    })
+}
+
+/// Returns the newest `created_at` of the statuses, individual or bundle, written to `log_dir`,
+/// or `None` if there aren't any.
+pub async fn last_run_time(log_dir: &Path) -> Result<Option<DateTime<Utc>>, Error> {
+    let mut last_run = None;
+    for path in glob(&log_dir.join("*.json").display().to_string())?.filter_map(Result::ok) {
+        let data = fs::read_to_string(path).await?;
+        let created_at = serde_json::from_str::<Value>(&data)
+            .ok()
+            .and_then(|v| serde_json::from_value::<DateTime<Utc>>(v["created_at"].clone()).ok());
+        last_run = last_run.max(created_at);
+    }
+    Ok(last_run)
+}
+
+/// Maximum length in bytes of NFT names accepted by the candy machine program.
+pub const MAX_METAPLEX_NAME_LEN: usize = 32;
+
+fn is_manifest_key_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~' | b'/')
+}
+
+/// Percent-encodes any characters in `path` other than unreserved characters and `/` separators,
+/// so that it can be used as a manifest key and in links through a manifest. Only ASCII letters
+/// and digits, `-`, `.`, `_`, `~` and `/` are kept. Every other byte, e.g. of spaces, `#`, `?`,
+/// `%` or non-ASCII characters, is encoded as `%` followed by two uppercase hex digits.
+pub fn encode_manifest_key(path: &str) -> String {
+    let mut key = String::with_capacity(path.len());
+    for b in path.bytes() {
+        if is_manifest_key_byte(b) {
+            key.push(b as char);
+        } else {
+            key.push_str(&format!("%{:02X}", b));
+        }
+    }
+    key
+}
+
+/// Returns the key for `path` in an Arweave path manifest, encoded with [`encode_manifest_key`].
+/// If `strict`, returns [`Error::InvalidManifestKey`] for paths that would need encoding instead.
+pub fn manifest_key(path: &str, strict: bool) -> Result<String, Error> {
+    if strict && !path.bytes().all(is_manifest_key_byte) {
+        return Err(Error::InvalidManifestKey(path.to_string()));
+    }
+    Ok(encode_manifest_key(path))
+}
+
+/// Replaces `\` separators with `/` and removes a leading `./`, so that paths from Windows and
+/// paths written either way compare equal as manifest paths.
+fn slash_path(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    path.strip_prefix("./").unwrap_or(&path).to_string()
+}
+
+/// Deepest directory containing every path in `paths`, which manifest paths are made relative to
+/// by default, see [`relative_manifest_path`]. Empty if they don't have one in common.
+pub fn common_parent<'a, I>(paths: I) -> PathBuf
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut common: Option<Vec<String>> = None;
+    for path in paths {
+        let path = slash_path(path);
+        let mut parent: Vec<&str> = path.split('/').collect();
+        parent.pop();
+        common = Some(match common {
+            None => parent.iter().map(|c| c.to_string()).collect(),
+            Some(common) => common
+                .into_iter()
+                .zip(parent)
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect(),
+        });
+    }
+    PathBuf::from(common.unwrap_or_default().join("/"))
+}
+
+/// Path of `file_path` in a manifest, relative to `base_dir` and with `/` separators, e.g.,
+/// `assets/0.png` for `/home/me/project/assets/0.png` with a `base_dir` of `/home/me/project`.
+/// Paths outside of `base_dir` are kept whole, without a leading `/`.
+pub fn relative_manifest_path(file_path: &str, base_dir: &Path) -> String {
+    let path = slash_path(file_path);
+    let base_dir = slash_path(&base_dir.display().to_string());
+    let base_dir = base_dir.trim_end_matches('/');
+    let relative = match path.strip_prefix(base_dir) {
+        Some(rest) if base_dir.is_empty() || rest.starts_with('/') => rest,
+        _ => &path,
+    };
+    relative.trim_start_matches('/').to_string()
+}
+
+/// Paths of one of the manifests that [`partition_manifest_paths`] splits paths into, along with
+/// the directory prefixes whose paths it holds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestPartition {
+    /// Directories ending in `/`, or empty for the top level, whose paths are in this manifest
+    /// unless they're under a longer prefix of another partition.
+    pub prefixes: Vec<String>,
+    pub paths: Vec<String>,
+}
+
+/// Splits relative manifest paths, see [`relative_manifest_path`], into partitions of at most
+/// `max_paths` paths, keeping the paths in each directory together. Directories with more than
+/// `max_paths` paths are split by subdirectory, with the files directly in them in a partition
+/// of their own, and consecutive partitions are then merged while they fit. A path is found in
+/// the partition with the longest prefix that matches it.
+///
+/// Returns [`Error::ManifestDirTooLarge`] if a directory directly contains more than
+/// `max_paths` files, since they can't be split by prefix.
+pub fn partition_manifest_paths(
+    mut paths: Vec<String>,
+    max_paths: usize,
+) -> Result<Vec<ManifestPartition>, Error> {
+    paths.sort();
+    let mut merged: Vec<ManifestPartition> = Vec::new();
+    for partition in partition_dir(paths, "", max_paths)? {
+        match merged.last_mut() {
+            Some(last) if last.paths.len() + partition.paths.len() <= max_paths => {
+                last.prefixes.extend(partition.prefixes);
+                last.paths.extend(partition.paths);
+            }
+            _ => merged.push(partition),
+        }
+    }
+    Ok(merged)
+}
+
+fn partition_dir(
+    paths: Vec<String>,
+    prefix: &str,
+    max_paths: usize,
+) -> Result<Vec<ManifestPartition>, Error> {
+    if paths.len() <= max_paths {
+        return Ok(vec![ManifestPartition {
+            prefixes: vec![prefix.to_string()],
+            paths,
+        }]);
+    }
+
+    let mut files = Vec::new();
+    let mut dirs: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for path in paths {
+        match path[prefix.len()..].split_once('/') {
+            Some((dir, _)) => dirs
+                .entry(format!("{}{}/", prefix, dir))
+                .or_default()
+                .push(path),
+            None => files.push(path),
+        }
+    }
+    if files.len() > max_paths {
+        return Err(Error::ManifestDirTooLarge {
+            prefix: prefix.to_string(),
+            count: files.len(),
+            max: max_paths,
+        });
+    }
+
+    let mut partitions = Vec::new();
+    if !files.is_empty() {
+        partitions.push(ManifestPartition {
+            prefixes: vec![prefix.to_string()],
+            paths: files,
+        });
+    }
+    for (dir, paths) in dirs {
+        partitions.extend(partition_dir(paths, &dir, max_paths)?);
+    }
+    Ok(partitions)
+}
+
+/// Maps each prefix of `partitions` to the id of the manifest its paths were uploaded in, where
+/// `manifest_ids` are in the same order as `partitions`.
+pub fn manifest_prefix_index(partitions: &[ManifestPartition], manifest_ids: &[String]) -> Value {
+    let index: serde_json::Map<String, Value> = partitions
+        .iter()
+        .zip(manifest_ids)
+        .flat_map(|(partition, id)| {
+            partition
+                .prefixes
+                .iter()
+                .map(move |prefix| (prefix.clone(), Value::String(id.clone())))
+        })
+        .collect();
+    Value::Object(index)
+}
+
+/// Normalizes `path` for matching against manifest keys, resolving `.` and `..` components and
+/// making absolute paths under the current directory relative to it, so that `./nfts/0.json`,
+/// `nfts/../nfts/0.json` and the absolute path of `nfts/0.json` all have the same key.
+pub fn path_key(path: &Path) -> PathBuf {
+    let path = match env::current_dir() {
+        Ok(dir) if path.is_absolute() => path.strip_prefix(dir).unwrap_or(path),
+        _ => path,
+    };
+    let mut components: Vec<Component> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir if matches!(components.last(), Some(Component::Normal(_))) => {
+                components.pop();
+            }
+            component => components.push(component),
+        }
+    }
+    components.iter().collect()
+}
+
+/// Checks that `name` fits within [`MAX_METAPLEX_NAME_LEN`], truncating it at a character
+/// boundary if `truncate` and returning [`Error::MetaplexNameTooLong`] otherwise.
+pub fn metaplex_name(name: &str, truncate: bool) -> Result<String, Error> {
+    if name.len() <= MAX_METAPLEX_NAME_LEN {
+        return Ok(name.to_string());
+    }
+    if !truncate {
+        return Err(Error::MetaplexNameTooLong(name.to_string()));
+    }
+    let end = (0..=MAX_METAPLEX_NAME_LEN)
+        .rev()
+        .find(|&i| name.is_char_boundary(i))
+        .unwrap_or(0);
+    Ok(name[..end].to_string())
+}
+
+/// Gzip compresses `data` for request bodies sent with `Content-Encoding: gzip`.
+pub fn gzip(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+#[cfg(any(test, feature = "test-util"))]
+pub mod mock_server;
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        common_parent, filter_paths_excluded, filter_paths_newer_than, last_run_time, manifest_key,
+        manifest_prefix_index, metaplex_name, partition_manifest_paths, path_key,
+        relative_manifest_path, ManifestPartition, NewerThan, TempDir, MAX_METAPLEX_NAME_LEN,
+    };
+    use crate::{
+        error::Error,
+        status::{BundleStatus, Status},
+    };
+    use chrono::{DateTime, Duration, Utc};
+    use glob::Pattern;
+    use matches::assert_matches;
+    use serde_json::json;
+    use std::{fs::File, path::PathBuf, time::SystemTime};
+    use tokio::fs;
+
+    fn touch(path: &PathBuf, modified: DateTime<Utc>) -> Result<(), Error> {
+        File::create(path)?.set_modified(SystemTime::from(modified))?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_manifest_key() -> Result<(), Error> {
+        assert_eq!(manifest_key("assets/0.png", false)?, "assets/0.png");
+        assert_eq!(manifest_key("my file.png", false)?, "my%20file.png");
+        assert_eq!(manifest_key("#1.png", false)?, "%231.png");
+        assert_eq!(manifest_key("café.png", false)?, "caf%C3%A9.png");
+        assert_eq!(
+            manifest_key("nfts/my file #1.png", false)?,
+            "nfts/my%20file%20%231.png"
+        );
+        assert_eq!(manifest_key("a?b%c.png", false)?, "a%3Fb%25c.png");
+        assert_eq!(manifest_key("A-z_0~9.png", false)?, "A-z_0~9.png");
+
+        assert_eq!(manifest_key("assets/0.png", true)?, "assets/0.png");
+        for path in ["my file.png", "#1.png", "café.png"] {
+            assert_matches!(
+                manifest_key(path, true),
+                Err(Error::InvalidManifestKey(p)) if p == path
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_path_key() -> Result<(), Error> {
+        let key = PathBuf::from("nfts/0.json");
+        for path in [
+            "nfts/0.json",
+            "./nfts/0.json",
+            "nfts/./0.json",
+            "nfts/../nfts/0.json",
+        ] {
+            assert_eq!(path_key(&PathBuf::from(path)), key);
+        }
+        assert_eq!(path_key(&std::env::current_dir()?.join("nfts/0.json")), key);
+        assert_eq!(
+            path_key(&PathBuf::from("../nfts/0.json")),
+            PathBuf::from("../nfts/0.json")
+        );
+        assert_eq!(
+            path_key(&PathBuf::from("/elsewhere/./nfts/0.json")),
+            PathBuf::from("/elsewhere/nfts/0.json")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_relative_manifest_path() {
+        let paths = [
+            "/home/me/project/assets/0.png",
+            "/home/me/project/assets/1.png",
+            "/home/me/project/metadata/0.json",
+        ];
+        let base_dir = common_parent(paths);
+        assert_eq!(base_dir, PathBuf::from("/home/me/project"));
+        let relative: Vec<String> = paths
+            .iter()
+            .map(|p| relative_manifest_path(p, &base_dir))
+            .collect();
+        assert_eq!(
+            relative,
+            vec!["assets/0.png", "assets/1.png", "metadata/0.json"]
+        );
+
+        let windows = ["assets\\0.png", "assets\\1.png"];
+        let base_dir = common_parent(windows);
+        assert_eq!(base_dir, PathBuf::from("assets"));
+        assert_eq!(relative_manifest_path(windows[0], &base_dir), "0.png");
+        assert_eq!(
+            relative_manifest_path(windows[0], &PathBuf::from("")),
+            "assets/0.png"
+        );
+
+        assert_eq!(common_parent(["0.png", "assets/1.png"]), PathBuf::from(""));
+        assert_eq!(
+            relative_manifest_path("./assets/0.png", &PathBuf::from("assets/")),
+            "0.png"
+        );
+        // Outside of the base dir, and not just sharing a prefix with it.
+        assert_eq!(
+            relative_manifest_path("/elsewhere/0.png", &PathBuf::from("/home/me")),
+            "elsewhere/0.png"
+        );
+        assert_eq!(
+            relative_manifest_path("/home/me2/0.png", &PathBuf::from("/home/me")),
+            "home/me2/0.png"
+        );
+    }
+
+    #[test]
+    fn test_partition_manifest_paths() -> Result<(), Error> {
+        let paths: Vec<String> = ["x.png", "b/c/2", "a/1", "b/1", "a/2", "b/c/1", "b/c/3"]
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let partition = |prefixes: &[&str], paths: &[&str]| ManifestPartition {
+            prefixes: prefixes.iter().map(|p| p.to_string()).collect(),
+            paths: paths.iter().map(|p| p.to_string()).collect(),
+        };
+
+        assert_eq!(
+            partition_manifest_paths(paths.clone(), 7)?,
+            vec![partition(
+                &[""],
+                &["a/1", "a/2", "b/1", "b/c/1", "b/c/2", "b/c/3", "x.png"]
+            )]
+        );
+
+        // `b/` is split by subdirectory, and the top level files are merged with `a/`.
+        let partitions = partition_manifest_paths(paths, 3)?;
+        assert_eq!(
+            partitions,
+            vec![
+                partition(&["", "a/"], &["x.png", "a/1", "a/2"]),
+                partition(&["b/"], &["b/1"]),
+                partition(&["b/c/"], &["b/c/1", "b/c/2", "b/c/3"]),
+            ]
+        );
+        assert_eq!(
+            manifest_prefix_index(
+                &partitions,
+                &["m0".to_string(), "m1".to_string(), "m2".to_string()]
+            ),
+            json!({"": "m0", "a/": "m0", "b/": "m1", "b/c/": "m2"})
+        );
+
+        let flat: Vec<String> = (0..4).map(|i| format!("dir/{}", i)).collect();
+        assert_matches!(
+            partition_manifest_paths(flat, 3),
+            Err(Error::ManifestDirTooLarge { prefix, count: 4, max: 3 }) if prefix == "dir/"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_metaplex_name() -> Result<(), Error> {
+        assert_eq!(metaplex_name("Arloader #1", false)?, "Arloader #1");
+
+        let long_name = "Arloader Limited Edition Collection #1";
+        assert_matches!(
+            metaplex_name(long_name, false),
+            Err(Error::MetaplexNameTooLong(_))
+        );
+        assert_eq!(
+            metaplex_name(long_name, true)?,
+            &long_name[..MAX_METAPLEX_NAME_LEN]
+        );
+
+        // Truncates at a character boundary.
+        let unicode_name = "é".repeat(20);
+        assert_eq!(metaplex_name(&unicode_name, true)?, "é".repeat(16));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_newer_than() {
+        assert_eq!(
+            "@last-run".parse::<NewerThan>().unwrap(),
+            NewerThan::LastRun
+        );
+        assert_eq!(
+            "2022-01-01T00:00:00-08:00".parse::<NewerThan>().unwrap(),
+            NewerThan::Time("2022-01-01T08:00:00Z".parse::<DateTime<Utc>>().unwrap())
+        );
+        assert_matches!(
+            "yesterday".parse::<NewerThan>(),
+            Err(Error::InvalidNewerThan(_))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_filter_paths_newer_than() -> Result<(), Error> {
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let cutoff = "2022-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        let old_path = temp_dir.0.join("old.bin");
+        let new_path = temp_dir.0.join("new.bin");
+        let missing_path = temp_dir.0.join("missing.bin");
+        touch(&old_path, cutoff - Duration::days(1))?;
+        touch(&new_path, cutoff + Duration::days(1))?;
+
+        let paths = vec![old_path, new_path.clone(), missing_path.clone()];
+        let filtered: Vec<PathBuf> = filter_paths_newer_than(paths, cutoff).collect();
+        assert_eq!(filtered, vec![new_path, missing_path]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_paths_excluded() -> Result<(), Error> {
+        let paths: Vec<PathBuf> = vec![
+            "dir/0.png",
+            "./dir/0.json",
+            "dir/.DS_Store",
+            "dir/Thumbs.db",
+            "dir/sub/1.PNG",
+        ]
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+        let filter = |patterns: &[&str]| -> Result<Vec<PathBuf>, Error> {
+            let patterns = patterns
+                .iter()
+                .map(|p| Pattern::new(p))
+                .collect::<Result<Vec<Pattern>, _>>()?;
+            Ok(filter_paths_excluded(paths.clone(), patterns).collect())
+        };
+
+        assert_eq!(filter(&[])?, paths);
+        assert_eq!(
+            filter(&["*.json", ".DS_Store", "Thumbs.db"])?,
+            vec![PathBuf::from("dir/0.png"), PathBuf::from("dir/sub/1.PNG")]
+        );
+        assert_eq!(filter(&["dir/sub/*"])?, paths[..4].to_vec(),);
+
+        // Case sensitive.
+        assert_eq!(filter(&["*.JSON", "thumbs.db"])?, paths);
+        assert_eq!(filter(&["*.png"])?.len(), 4);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_last_run_time() -> Result<(), Error> {
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        assert_eq!(last_run_time(&temp_dir.0).await?, None);
+
+        let older = "2022-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let newer = older + Duration::hours(1);
+        let status = Status {
+            created_at: older,
+            ..Status::default()
+        };
+        let bundle_status = BundleStatus {
+            created_at: newer,
+            ..BundleStatus::default()
+        };
+        fs::write(
+            temp_dir.0.join("status.json"),
+            serde_json::to_string(&status)?,
+        )
+        .await?;
+        fs::write(
+            temp_dir.0.join("bundle.json"),
+            serde_json::to_string(&bundle_status)?,
+        )
+        .await?;
+        fs::write(
+            temp_dir.0.join("manifest.json"),
+            r#"{"manifest": "arweave/paths"}"#,
+        )
+        .await?;
+
+        assert_eq!(last_run_time(&temp_dir.0).await?, Some(newer));
+        Ok(())
+    }
+}
@@ -0,0 +1,538 @@
+//! Minimal http server used in tests in place of network endpoints, [`MockGateway`], which
+//! answers the gateway endpoints used for uploads with canned responses, and [`MockSolana`],
+//! which does the same for paying with SOL. Also available to integration tests with the
+//! `test-util` feature.
+
+use crate::{
+    crypto::Provider,
+    solana::{SigResponse, TxData, MEMO_PROGRAM_ID},
+    transaction::Base64,
+};
+use flate2::read::GzDecoder;
+use serde_json::{json, Value};
+use solana_sdk::{
+    hash::Hash, pubkey::Pubkey, system_instruction::SystemInstruction, system_program,
+    transaction::Transaction,
+};
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    path::PathBuf,
+    str::FromStr,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+use url::Url;
+
+/// Request received by the mock server.
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    /// Index of the connection the request was received on, in the order connections were
+    /// accepted. Connections are kept alive, so clients can make several requests on one.
+    pub connection: usize,
+    pub method: String,
+    pub path: String,
+    /// Header names are lowercased.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl MockRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Parses the body as json, decompressing it first if it was sent with
+    /// `Content-Encoding: gzip`.
+    pub fn json(&self) -> Value {
+        let body = if self.header("content-encoding") == Some("gzip") {
+            let mut body = Vec::new();
+            GzDecoder::new(&self.body[..])
+                .read_to_end(&mut body)
+                .unwrap();
+            body
+        } else {
+            self.body.clone()
+        };
+        serde_json::from_slice(&body).unwrap()
+    }
+}
+
+/// Serves requests until the test exits, responding with the status line, e.g. `"200 OK"`, and
+/// body returned by `respond` for the request path, leaving out the body for HEAD requests.
+/// Returns the base url of the server and a receiver of the requests it has served.
+pub fn serve<F>(respond: F) -> (Url, mpsc::Receiver<MockRequest>)
+where
+    F: Fn(&str) -> (&'static str, String) + Send + Sync + 'static,
+{
+    serve_with_headers(move |path| {
+        let (status, body) = respond(path);
+        (status, Vec::new(), body)
+    })
+}
+
+/// Like [`serve`], but `respond` also returns headers to add to the response.
+pub fn serve_with_headers<F>(respond: F) -> (Url, mpsc::Receiver<MockRequest>)
+where
+    F: Fn(&str) -> (&'static str, Vec<(&'static str, String)>, String) + Send + Sync + 'static,
+{
+    serve_requests(move |request| respond(&request.path))
+}
+
+/// Like [`serve_with_headers`], but `respond` gets the whole request. Each request is sent to
+/// the receiver before it is responded to.
+pub fn serve_requests<F>(respond: F) -> (Url, mpsc::Receiver<MockRequest>)
+where
+    F: Fn(&MockRequest) -> (&'static str, Vec<(&'static str, String)>, String)
+        + Send
+        + Sync
+        + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = Url::from_str(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+    let (sender, receiver) = mpsc::channel();
+    let respond = Arc::new(respond);
+    thread::spawn(move || {
+        for (connection, socket) in listener.incoming().enumerate() {
+            let mut socket = socket.unwrap();
+            let (respond, sender) = (respond.clone(), sender.clone());
+            thread::spawn(move || {
+                while let Some(request) = read_request(&mut socket, connection) {
+                    let (status, headers, body) = respond(&request);
+                    let headers: String = headers
+                        .iter()
+                        .map(|(name, value)| format!("{}: {}\r\n", name, value))
+                        .collect();
+                    let response = format!(
+                        "HTTP/1.1 {}\r\n{}content-length: {}\r\n\r\n{}",
+                        status,
+                        headers,
+                        body.len(),
+                        if request.method == "HEAD" { "" } else { &body }
+                    );
+                    let _ = sender.send(request);
+                    if socket.write_all(response.as_bytes()).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+    });
+    (url, receiver)
+}
+
+/// Reads the next request from `socket`, or `None` once the client closes the connection.
+fn read_request(socket: &mut TcpStream, connection: usize) -> Option<MockRequest> {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    let body_start = loop {
+        let n = socket.read(&mut buf).ok().filter(|n| *n > 0)?;
+        request.extend_from_slice(&buf[..n]);
+        if let Some(i) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i + 4;
+        }
+    };
+    let head = String::from_utf8_lossy(&request[..body_start]).to_string();
+    let headers: Vec<(String, String)> = head
+        .lines()
+        .skip(1)
+        .filter_map(|l| {
+            let (name, value) = l.split_once(':')?;
+            Some((name.trim().to_lowercase(), value.trim().to_string()))
+        })
+        .collect();
+    let content_length = headers
+        .iter()
+        .find(|(n, _)| n == "content-length")
+        .map_or(0, |(_, v)| v.parse::<usize>().unwrap());
+    while request.len() < body_start + content_length {
+        let n = socket.read(&mut buf).ok().filter(|n| *n > 0)?;
+        request.extend_from_slice(&buf[..n]);
+    }
+    let mut request_line = head.lines().next().unwrap().split_whitespace();
+    Some(MockRequest {
+        connection,
+        method: request_line.next().unwrap().to_string(),
+        path: request_line.next().unwrap().to_string(),
+        headers,
+        body: request[body_start..].to_vec(),
+    })
+}
+
+/// Winstons charged per byte by [`MockGateway`] price requests.
+pub const MOCK_PRICE_PER_BYTE: u64 = 1;
+
+/// Wallet balance returned by [`MockGateway`] until changed with [`MockGateway::set_balance`].
+pub const MOCK_BALANCE: u64 = 1_000_000_000_000_000;
+
+/// Gateway that answers `info`, `price/<BYTES>`, `tx_anchor`, `tx/pending`, `tx`, `tx/<ID>`,
+/// `tx/<ID>/status`, `chunk`, `wallet/<ADDRESS>/balance` and `graphql` with canned responses,
+/// recording every request. Posted transactions are pending until [`MockGateway::mine`] is
+/// called, failures can be queued with [`MockGateway::fail_next`] to exercise retries and
+/// responses delayed with [`MockGateway::delay_next`] to interrupt requests in flight.
+pub struct MockGateway {
+    pub url: Url,
+    state: Arc<Mutex<GatewayState>>,
+}
+
+struct GatewayState {
+    requests: Vec<MockRequest>,
+    /// Posted transactions with the height they were mined at.
+    transactions: Vec<(Value, Option<u64>)>,
+    height: u64,
+    failures: Vec<(String, &'static str, usize)>,
+    delays: Vec<(String, Duration)>,
+    balance: u64,
+    graphql: Value,
+}
+
+impl MockGateway {
+    pub fn serve() -> Self {
+        let state = Arc::new(Mutex::new(GatewayState {
+            requests: Vec::new(),
+            transactions: Vec::new(),
+            height: 0,
+            failures: Vec::new(),
+            delays: Vec::new(),
+            balance: MOCK_BALANCE,
+            graphql: json!({ "data": { "transactions": { "edges": [] } } }),
+        }));
+        let responder = state.clone();
+        let (url, _) = serve_requests(move |request| {
+            let (response, delay) = {
+                let mut state = responder.lock().unwrap();
+                (state.respond(request), state.take_delay(request))
+            };
+            // Requests are recorded before the delay, so that they can be seen while it lasts.
+            if let Some(delay) = delay {
+                thread::sleep(delay);
+            }
+            response
+        });
+        Self { url, state }
+    }
+
+    /// Confirms the pending transactions in a new block.
+    pub fn mine(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.height += 1;
+        let height = state.height;
+        for (_, mined) in state.transactions.iter_mut().filter(|(_, m)| m.is_none()) {
+            *mined = Some(height);
+        }
+    }
+
+    /// Responds to the next `times` requests whose path starts with `path` with `status`, e.g.,
+    /// `"503 Service Unavailable"`, and an empty body.
+    pub fn fail_next(&self, path: &str, status: &'static str, times: usize) {
+        self.state
+            .lock()
+            .unwrap()
+            .failures
+            .push((path.to_string(), status, times));
+    }
+
+    /// Delays the response to the next request whose path is `path` by `delay`, after it has
+    /// been handled.
+    pub fn delay_next(&self, path: &str, delay: Duration) {
+        self.state
+            .lock()
+            .unwrap()
+            .delays
+            .push((path.to_string(), delay));
+    }
+
+    pub fn set_balance(&self, winstons: u64) {
+        self.state.lock().unwrap().balance = winstons;
+    }
+
+    pub fn set_graphql_response(&self, response: Value) {
+        self.state.lock().unwrap().graphql = response;
+    }
+
+    /// Requests received so far, in order.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Requests received so far with `method` and `path`, e.g., `("POST", "/chunk")`.
+    pub fn requests_to(&self, method: &str, path: &str) -> Vec<MockRequest> {
+        self.requests()
+            .into_iter()
+            .filter(|r| r.method == method && r.path == path)
+            .collect()
+    }
+
+    /// Bodies of the transactions accepted by the gateway, in the order they were posted.
+    pub fn posted_transactions(&self) -> Vec<Value> {
+        let state = self.state.lock().unwrap();
+        state.transactions.iter().map(|(t, _)| t.clone()).collect()
+    }
+
+    /// Bodies of the chunks posted to the gateway, including rejected ones.
+    pub fn posted_chunks(&self) -> Vec<Value> {
+        self.requests_to("POST", "/chunk")
+            .iter()
+            .map(MockRequest::json)
+            .collect()
+    }
+}
+
+impl GatewayState {
+    fn respond(
+        &mut self,
+        request: &MockRequest,
+    ) -> (&'static str, Vec<(&'static str, String)>, String) {
+        self.requests.push(request.clone());
+        if let Some(failure) = self
+            .failures
+            .iter_mut()
+            .find(|(path, _, times)| *times > 0 && request.path.starts_with(path.as_str()))
+        {
+            failure.2 -= 1;
+            return (failure.1, Vec::new(), String::new());
+        }
+
+        let ok = |body: String| ("200 OK", Vec::new(), body);
+        let not_found = ("404 Not Found", Vec::new(), "Not Found".to_string());
+        let segments: Vec<&str> = request.path.trim_start_matches('/').split('/').collect();
+        match (request.method.as_str(), segments.as_slice()) {
+            ("GET", ["info"]) => ok(json!({
+                "network": "arweave.mock",
+                "version": 5,
+                "release": 53,
+                "height": self.height,
+                "current": Base64(vec![self.height as u8; 48]).to_string(),
+                "blocks": self.height + 1,
+                "peers": 0,
+                "queue_length": 0,
+                "node_state_latency": 0,
+            })
+            .to_string()),
+            ("GET", ["price", bytes]) => match bytes.parse::<u64>() {
+                Ok(bytes) => ok((bytes * MOCK_PRICE_PER_BYTE).to_string()),
+                Err(_) => ("400 Bad Request", Vec::new(), String::new()),
+            },
+            ("GET", ["tx_anchor"]) => ok(Base64(vec![self.height as u8; 48]).to_string()),
+            ("GET", ["tx", "pending"]) => ok(json!(self
+                .transactions
+                .iter()
+                .filter(|(_, mined)| mined.is_none())
+                .map(|(t, _)| t["id"].clone())
+                .collect::<Vec<_>>())
+            .to_string()),
+            ("POST", ["tx"]) => {
+                self.transactions.push((request.json(), None));
+                ok("OK".to_string())
+            }
+            ("GET", ["tx", id]) => match self.transaction(id) {
+                Some((transaction, _)) => ok(transaction.to_string()),
+                None => not_found,
+            },
+            ("GET", ["tx", id, "status"]) => match self.transaction(id) {
+                Some((_, Some(block_height))) => ok(json!({
+                    "block_height": block_height,
+                    "block_indep_hash": Base64(vec![*block_height as u8; 48]).to_string(),
+                    "number_of_confirmations": self.height + 1 - block_height,
+                })
+                .to_string()),
+                Some((_, None)) => ("202 Accepted", Vec::new(), "Pending".to_string()),
+                None => not_found,
+            },
+            ("POST", ["chunk"]) => ok("OK".to_string()),
+            ("GET", ["wallet", _, "balance"]) => ok(self.balance.to_string()),
+            ("POST", ["graphql"]) => ok(self.graphql.to_string()),
+            _ => not_found,
+        }
+    }
+
+    fn take_delay(&mut self, request: &MockRequest) -> Option<Duration> {
+        let i = self
+            .delays
+            .iter()
+            .position(|(path, _)| *path == request.path)?;
+        Some(self.delays.remove(i).1)
+    }
+
+    fn transaction(&self, id: &str) -> Option<&(Value, Option<u64>)> {
+        self.transactions.iter().find(|(t, _)| t["id"] == id)
+    }
+}
+
+/// Lamports balance of every wallet on [`MockSolana`].
+pub const MOCK_SOL_BALANCE: u64 = 1_000_000_000_000;
+
+/// SOL transfer sent to [`MockSolana`] with a payment.
+#[derive(Debug, Clone)]
+pub struct MockTransfer {
+    pub signature: String,
+    /// Memo the transfer was sent with, its payment reference.
+    pub memo: String,
+    pub lamports: u64,
+    /// Serialized transaction.
+    pub transaction: Vec<u8>,
+}
+
+#[derive(Default)]
+struct MockSolanaState {
+    requests: Vec<MockRequest>,
+    transfers: Vec<MockTransfer>,
+    pending: bool,
+    fail_next: usize,
+}
+
+/// Solana rpc node and sol_ar signing service answering the requests made when paying for a
+/// transaction with SOL. Each distinct SOL transaction sent to the service is recorded as a
+/// transfer and, unless [`MockSolana::set_pending`] is on, is found by later lookups for its
+/// memo. Transactions are signed with the key at the path passed to [`MockSolana::serve`] in
+/// place of the key of the service.
+pub struct MockSolana {
+    /// Url of the Solana rpc node.
+    pub url: Url,
+    /// Url of the sol_ar signing service.
+    pub sol_ar_url: Url,
+    state: Arc<Mutex<MockSolanaState>>,
+}
+
+impl MockSolana {
+    pub fn serve(keypair_path: PathBuf) -> Self {
+        let provider = Provider::from_keypair_path_sync(keypair_path).unwrap();
+        let state = Arc::new(Mutex::new(MockSolanaState::default()));
+        let shared = state.clone();
+        let (url, _) = serve_requests(move |request| {
+            let mut state = shared.lock().unwrap();
+            state.requests.push(request.clone());
+            let ok = |body: Value| ("200 OK", Vec::new(), body.to_string());
+            if request.path == "/sol" {
+                let tx_data: TxData = serde_json::from_value(request.json()).unwrap();
+                let transfer = mock_transfer(&tx_data.sol_tx);
+                if !state
+                    .transfers
+                    .iter()
+                    .any(|t| t.signature == transfer.signature)
+                {
+                    state.transfers.push(transfer);
+                }
+                if state.fail_next > 0 {
+                    state.fail_next -= 1;
+                    return ("500 Internal Server Error", Vec::new(), "Error".to_string());
+                }
+                let deep_hash = provider.deep_hash(tx_data.deep_hash_item).unwrap();
+                let signature = provider.sign(&deep_hash).unwrap();
+                return ok(json!(SigResponse {
+                    ar_tx_id: Base64(provider.hash_sha256(&signature).unwrap().to_vec()),
+                    ar_tx_sig: Base64(signature),
+                    ar_tx_owner: provider.keypair_modulus().unwrap(),
+                    sol_tx_sig: String::new(),
+                    lamports: 0,
+                }));
+            }
+            // Pending transfers aren't found by any lookup.
+            let confirmed: Vec<&MockTransfer> = if state.pending {
+                Vec::new()
+            } else {
+                state.transfers.iter().collect()
+            };
+            let params = request.json()["params"].clone();
+            let result = match request.json()["method"].as_str() {
+                Some("getSignaturesForAddress") => confirmed
+                    .iter()
+                    .map(|t| {
+                        json!({
+                            "signature": t.signature,
+                            "memo": format!("[{}] {}", t.memo.len(), t.memo),
+                            "err": null,
+                        })
+                    })
+                    .collect(),
+                Some("getTransaction") => confirmed
+                    .iter()
+                    .find(|t| params[0] == t.signature)
+                    .map(|t| json!({ "transaction": [base64::encode(&t.transaction), "base64"] }))
+                    .unwrap_or(Value::Null),
+                Some("getSignatureStatuses") => {
+                    let status = confirmed
+                        .iter()
+                        .find(|t| params[0][0] == t.signature)
+                        .map(|_| json!({ "err": null, "confirmationStatus": "confirmed" }))
+                        .unwrap_or(Value::Null);
+                    json!({ "value": [status] })
+                }
+                Some("getRecentBlockhash") => {
+                    json!({ "value": { "blockhash": Hash::new_unique().to_string() } })
+                }
+                Some("getBalance") => json!({ "value": MOCK_SOL_BALANCE }),
+                _ => Value::Null,
+            };
+            ok(json!({ "jsonrpc": "2.0", "id": 1, "result": result }))
+        });
+        Self {
+            sol_ar_url: url.join("sol").unwrap(),
+            url,
+            state,
+        }
+    }
+
+    /// Requests made to the sol_ar service for signatures, one for each payment.
+    pub fn payments(&self) -> Vec<MockRequest> {
+        self.state
+            .lock()
+            .unwrap()
+            .requests
+            .iter()
+            .filter(|r| r.path == "/sol")
+            .cloned()
+            .collect()
+    }
+
+    /// Distinct SOL transactions sent to the sol_ar service, in the order they were first sent.
+    pub fn transfers(&self) -> Vec<MockTransfer> {
+        self.state.lock().unwrap().transfers.clone()
+    }
+
+    /// Keeps transfers pending, so they aren't found by lookups for earlier payments or by
+    /// signature status, until turned off again.
+    pub fn set_pending(&self, pending: bool) {
+        self.state.lock().unwrap().pending = pending;
+    }
+
+    /// Makes the next `n` requests to the sol_ar service fail after their transfer is recorded.
+    pub fn fail_next(&self, n: usize) {
+        self.state.lock().unwrap().fail_next = n;
+    }
+}
+
+/// Decodes the serialized SOL transaction `sol_tx` into the transfer it makes.
+fn mock_transfer(sol_tx: &str) -> MockTransfer {
+    let serialized = bs58::decode(sol_tx).into_vec().unwrap();
+    let transaction: Transaction = bincode::deserialize(&serialized).unwrap();
+    let memo_program = Pubkey::from_str(MEMO_PROGRAM_ID).unwrap();
+    let keys = &transaction.message.account_keys;
+    let memo = transaction
+        .message
+        .instructions
+        .iter()
+        .find(|i| keys[i.program_id_index as usize] == memo_program)
+        .map(|i| String::from_utf8(i.data.clone()).unwrap())
+        .unwrap_or_default();
+    let lamports = transaction
+        .message
+        .instructions
+        .iter()
+        .filter(|i| keys[i.program_id_index as usize] == system_program::id())
+        .find_map(|i| match bincode::deserialize(&i.data) {
+            Ok(SystemInstruction::Transfer { lamports }) => Some(lamports),
+            _ => None,
+        })
+        .unwrap_or_default();
+    MockTransfer {
+        signature: transaction.signatures[0].to_string(),
+        memo,
+        lamports,
+        transaction: serialized,
+    }
+}
@@ -1,18 +1,25 @@
 use arloader::{
+    commands::{command_upload_nfts, command_upload_one},
     crypto::Provider,
     error::Error,
-    solana::SOL_AR_BASE_URL,
-    status::{OutputFormat, Status, StatusCode},
-    transaction::{Base64, Tag},
-    upload_files_stream,
-    utils::TempDir,
-    Arweave,
+    solana::{FLOOR, RATE, SOL_AR_BASE_URL},
+    status::{
+        BundleStatus, LocalFileState, Metrics, OutputFormat, RecordPrinter, Status, StatusCode,
+    },
+    transaction::{Base64, FromUtf8Strs, Tag, Tip},
+    upload_bundles_stream, upload_files_stream,
+    utils::{
+        mock_server::{MockGateway, MockSolana},
+        TempDir,
+    },
+    Arweave, NetworkParams, NftPairOptions, NftUploadOptions, PathsChunk, RetryPolicy, BLOCK_SIZE,
+    MANIFEST_CONTENT_TYPE,
 };
 use futures::{future::try_join_all, StreamExt};
 use glob::glob;
 use solana_sdk::signer::keypair;
-use std::{iter, path::PathBuf, str::FromStr, time::Duration};
-use tokio::time::sleep;
+use std::{iter, path::PathBuf, str::FromStr, sync::atomic::Ordering, time::Duration};
+use tokio::{fs, time::sleep};
 use url::Url;
 
 async fn get_arweave() -> Result<Arweave, Error> {
@@ -23,6 +30,13 @@ async fn get_arweave() -> Result<Arweave, Error> {
     Ok(arweave)
 }
 
+/// Arweave with the same keypair as [`get_arweave`] making requests to `gateway`.
+async fn get_mock_arweave(gateway: &MockGateway) -> Result<Arweave, Error> {
+    let mut arweave = get_arweave().await?;
+    arweave.base_url = gateway.url.clone();
+    Ok(arweave)
+}
+
 async fn mine(arweave: &Arweave) -> Result<(), Error> {
     let url = arweave.base_url.join("mine")?;
     let resp = reqwest::get(url).await?.text().await?;
@@ -44,43 +58,93 @@ async fn airdrop(arweave: &Arweave) -> Result<(), Error> {
 
 #[tokio::test]
 async fn test_post_transaction() -> Result<(), Error> {
-    let arweave = get_arweave().await?;
-    // Don't run if test server is not running.
-    if let Err(_) = reqwest::get(arweave.base_url.join("info")?).await {
-        println!("Test server not running.");
-        return Ok(());
-    } else {
-    }
+    let gateway = MockGateway::serve();
+    let arweave = get_mock_arweave(&gateway).await?;
 
-    airdrop(&arweave).await?;
     let file_path = PathBuf::from("tests/fixtures/0.png");
     let transaction = arweave
-        .create_transaction_from_file_path(file_path, None, None, (0, 0), true)
+        .create_transaction_from_file_path(file_path.clone(), None, None, (0, 0), true)
         .await?;
 
     let signed_transaction = arweave.sign_transaction(transaction)?;
-    println!("signed_transaction: {:?}", &signed_transaction);
     arweave.post_transaction(&signed_transaction).await?;
 
-    let url = arweave.base_url.join("mine")?;
-    let resp = reqwest::get(url).await?.text().await?;
-    println!("mine: {}", resp);
+    let posted = gateway.posted_transactions();
+    assert_eq!(posted.len(), 1);
+    assert_eq!(posted[0]["id"], signed_transaction.id.to_string());
+    assert_eq!(
+        Base64::from_str(posted[0]["data"].as_str().unwrap())?.0,
+        fs::read(file_path).await?
+    );
 
     let status = arweave.get_status(&signed_transaction.id).await?;
-    println!("{:?}", status);
+    assert_eq!(status.status, StatusCode::Pending);
+    gateway.mine();
+    let status = arweave.get_status(&signed_transaction.id).await?;
+    assert_eq!(status.status, StatusCode::Confirmed);
+    assert_eq!(status.raw_status.unwrap().number_of_confirmations, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_post_transaction_retries() -> Result<(), Error> {
+    let gateway = MockGateway::serve();
+    let arweave = get_mock_arweave(&gateway).await?;
+    gateway.fail_next("/tx", "503 Service Unavailable", 2);
+
+    let transaction = arweave
+        .create_transaction(b"retried".to_vec(), None, None, (0, 0), true)
+        .await?;
+    let signed_transaction = arweave.sign_transaction(transaction)?;
+    arweave.post_transaction(&signed_transaction).await?;
+
+    assert_eq!(gateway.requests_to("POST", "/tx").len(), 3);
+    assert_eq!(gateway.posted_transactions().len(), 1);
+    assert_eq!(arweave.post_retries.load(Ordering::Relaxed), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_post_transaction_chunks() -> Result<(), Error> {
+    let gateway = MockGateway::serve();
+    let arweave = get_mock_arweave(&gateway).await?;
+    gateway.fail_next("/chunk", "500 Internal Server Error", 1);
+
+    let file_path = PathBuf::from("tests/fixtures/1mb.bin");
+    let transaction = arweave
+        .create_transaction_from_file_path(file_path.clone(), None, None, (0, 0), true)
+        .await?;
+    let signed_transaction = arweave.sign_transaction(transaction)?;
+    let chunks_total = signed_transaction.chunks.len();
+    assert!(chunks_total > 1);
+    arweave
+        .post_transaction_chunks(signed_transaction, 2, None)
+        .await?;
+
+    let posted = gateway.posted_transactions();
+    assert_eq!(posted.len(), 1);
+    assert_eq!(posted[0]["data"], "");
+
+    // The rejected chunk is posted again.
+    let mut chunks = gateway.posted_chunks();
+    assert_eq!(chunks.len(), chunks_total + 1);
+    assert_eq!(arweave.post_retries.load(Ordering::Relaxed), 1);
+    chunks.sort_by_key(|c| c["offset"].as_str().unwrap().parse::<usize>().unwrap());
+    chunks.dedup();
+    let data: Vec<u8> = chunks
+        .iter()
+        .map(|c| Base64::from_str(c["chunk"].as_str().unwrap()).map(|b| b.0))
+        .collect::<Result<Vec<_>, _>>()?
+        .concat();
+    assert_eq!(data, fs::read(file_path).await?);
     Ok(())
 }
 
 #[tokio::test]
 async fn test_upload_file_from_path() -> Result<(), Error> {
-    let arweave = get_arweave().await?;
-    // Don't run if test server is not running.
-    if let Err(_) = reqwest::get(arweave.base_url.join("info")?).await {
-        println!("Test server not running.");
-        return Ok(());
-    }
+    let gateway = MockGateway::serve();
+    let arweave = get_mock_arweave(&gateway).await?;
 
-    airdrop(&arweave).await?;
     let file_path = PathBuf::from("tests/fixtures/0.png");
     let temp_log_dir = TempDir::from_str("./tests/").await?;
     let log_dir = temp_log_dir.0.clone();
@@ -88,15 +152,389 @@ async fn test_upload_file_from_path() -> Result<(), Error> {
     let status = arweave
         .upload_file_from_path(file_path.clone(), Some(log_dir.clone()), None, None, (0, 0))
         .await?;
+    assert_eq!(
+        gateway.posted_transactions()[0]["id"],
+        status.id.to_string()
+    );
 
     let read_status = arweave.read_status(file_path, log_dir.clone()).await?;
-    println!("{:?}", &read_status);
     assert_eq!(status, read_status);
     Ok(())
 }
 
 #[tokio::test]
-async fn test_update_status() -> Result<(), Error> {
+async fn test_command_upload_one() -> Result<(), Error> {
+    let gateway = MockGateway::serve();
+    let arweave = get_mock_arweave(&gateway).await?;
+    let temp_log_dir = TempDir::from_str("./tests/").await?;
+    let log_dir = temp_log_dir.0.clone();
+    let tags = vec![Tag::<String>::from_utf8_strs("App-Name", "arloader")?];
+
+    command_upload_one(
+        &arweave,
+        PathBuf::from("tests/fixtures/0.png"),
+        Some(log_dir.clone()),
+        tags.clone(),
+        1.0,
+        false,
+        None,
+    )
+    .await?;
+    let posted = gateway.posted_transactions();
+    assert_eq!(posted.len(), 1);
+    let status = arweave
+        .read_bundle_status(
+            log_dir
+                .join(posted[0]["id"].as_str().unwrap())
+                .with_extension("json"),
+        )
+        .await?;
+    assert_eq!(status.number_of_files, 1);
+
+    // Errors checking the status are retried instead of failing the upload.
+    gateway.fail_next("/tx/", "503 Service Unavailable", 1);
+    command_upload_one(
+        &arweave,
+        PathBuf::from("tests/fixtures/1.png"),
+        None,
+        tags,
+        1.0,
+        true,
+        None,
+    )
+    .await?;
+    let posted = gateway.posted_transactions();
+    assert_eq!(posted.len(), 2);
+    let status_path = format!("/tx/{}/status", posted[1]["id"].as_str().unwrap());
+    assert_eq!(gateway.requests_to("GET", &status_path).len(), 2);
+    let name = Base64::from_utf8_str("App-Name")?.to_string();
+    assert!(posted[1]["tags"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|t| t["name"] == name.as_str()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_network_params() -> Result<(), Error> {
+    let gateway = MockGateway::serve();
+    let mut arweave = get_mock_arweave(&gateway).await?;
+    let file_path = PathBuf::from("tests/fixtures/0.png");
+    let data_size = fs::metadata(&file_path).await?.len();
+
+    assert_eq!(
+        arweave.get_price_terms(1.0).await?,
+        (BLOCK_SIZE, BLOCK_SIZE)
+    );
+    let status = arweave
+        .upload_file_from_path(file_path.clone(), None, None, None, (100, 10))
+        .await?;
+    assert_eq!(status.reward, 100);
+    assert_ne!(gateway.posted_transactions()[0]["data"], "");
+    assert!(gateway.posted_chunks().is_empty());
+
+    // Smaller blocks are priced and fetched, and the file is posted in chunks.
+    arweave.network_params = NetworkParams {
+        block_size: 500,
+        max_tx_body: 1000,
+        ..NetworkParams::default()
+    };
+    assert_eq!(arweave.get_price_terms(1.0).await?, (500, 500));
+    let status = arweave
+        .upload_file_from_path(file_path, None, None, None, (100, 10))
+        .await?;
+    assert_eq!(
+        status.reward,
+        100 + 10 * (arweave.network_params.blocks_len(data_size) - 1)
+    );
+    assert!(status.reward > 100);
+    assert_eq!(gateway.posted_transactions()[1]["data"], "");
+    assert!(!gateway.posted_chunks().is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upload_nft_pair_bundled() -> Result<(), Error> {
+    let gateway = MockGateway::serve();
+    let arweave = get_mock_arweave(&gateway).await?;
+    let image = fs::read("tests/fixtures/0.png").await?;
+    let metadata = serde_json::json!({"name": "0", "properties": {"files": []}});
+
+    let (metadata_id, image_id) = arweave
+        .upload_nft_pair(
+            image.clone(),
+            "image/png",
+            metadata,
+            NftPairOptions::default(),
+        )
+        .await?;
+
+    let posted = gateway.posted_transactions();
+    assert_eq!(posted.len(), 2);
+    let items: Vec<_> = posted
+        .iter()
+        .map(|t| {
+            let bundle = Base64::from_str(t["data"].as_str().unwrap()).unwrap();
+            arweave.deserialize_bundle(bundle.0).unwrap().remove(0)
+        })
+        .collect();
+    assert_eq!(items[0].id, image_id);
+    assert_eq!(items[0].data.0, image);
+    assert_eq!(items[1].id, metadata_id);
+    assert!(items[1].tags.contains(&Tag::<String>::from_utf8_strs(
+        "Content-Type",
+        "application/json"
+    )?));
+
+    let metadata: serde_json::Value = serde_json::from_slice(&items[1].data.0)?;
+    let image_link = format!("https://arweave.net/{}", image_id);
+    assert_eq!(metadata["name"], "0");
+    assert_eq!(metadata["image"], image_link);
+    assert_eq!(
+        metadata["properties"]["files"],
+        serde_json::json!([{"uri": image_link, "type": "image/png"}])
+    );
+    Ok(())
+}
+
+/// Uploads `tests/fixtures/0.png` and `1.png` in a bundle, returning it with a chunk of
+/// `tests/fixtures/2.png` to upload in the last bundle along with the manifest.
+async fn manifest_bundle_chunks(
+    arweave: &Arweave,
+    log_dir: PathBuf,
+) -> Result<(BundleStatus, PathsChunk), Error> {
+    let mut chunks = Vec::new();
+    for indexes in [vec![0, 1], vec![2]] {
+        let mut paths = Vec::new();
+        let mut data_size = 0;
+        for i in indexes {
+            let path = PathBuf::from(format!("tests/fixtures/{}.png", i));
+            data_size += fs::metadata(&path).await?.len();
+            paths.push(path);
+        }
+        chunks.push(PathsChunk(paths, data_size));
+    }
+    let last_chunk = chunks.pop().unwrap();
+    let bundle_status = arweave
+        .post_bundle_transaction_from_file_paths(
+            chunks.remove(0),
+            Vec::new(),
+            Some(log_dir),
+            (0, 0),
+            1,
+            RetryPolicy::default(),
+        )
+        .await?;
+    Ok((bundle_status, last_chunk))
+}
+
+/// Checks that the last bundle posted to `gateway` has the data item of `tests/fixtures/2.png`
+/// followed by the manifest of all three files, which the consolidated manifest file in `log_dir`
+/// links through.
+async fn check_manifest_bundle(
+    arweave: &Arweave,
+    gateway: &MockGateway,
+    manifest_id: &Base64,
+    status: BundleStatus,
+    log_dir: PathBuf,
+) -> Result<(), Error> {
+    let posted = gateway.posted_transactions();
+    assert_eq!(posted.len(), 2);
+    assert_eq!(posted[1]["id"], status.id.to_string());
+    let bundle = Base64::from_str(posted[1]["data"].as_str().unwrap()).unwrap();
+    let items = arweave.deserialize_bundle(bundle.0)?;
+    assert_eq!(items.len(), 2);
+    assert_eq!(
+        items[0].id.to_string(),
+        status.file_paths["tests/fixtures/2.png"]["id"]
+    );
+    assert_eq!(&items[1].id, manifest_id);
+    assert!(items[1].tags.contains(&Tag::<String>::from_utf8_strs(
+        "Content-Type",
+        MANIFEST_CONTENT_TYPE
+    )?));
+    let posted_manifest: serde_json::Value = serde_json::from_slice(&items[1].data.0)?;
+    let mut keys: Vec<&String> = posted_manifest["paths"]
+        .as_object()
+        .unwrap()
+        .keys()
+        .collect();
+    keys.sort();
+    assert_eq!(keys, vec!["0.png", "1.png", "2.png"]);
+    assert!(posted_manifest["paths"]
+        .as_object()
+        .unwrap()
+        .values()
+        .all(|entry| entry.as_object().unwrap().keys().eq(["id"].iter())));
+
+    // The consolidated manifest file links through the bundled manifest and is recorded in the
+    // status of its bundle, which is left out of later manifests.
+    let manifest_path = log_dir.join(format!("manifest_{}.json", manifest_id));
+    let links: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&manifest_path).await?)?;
+    assert_eq!(
+        links["tests/fixtures/1.png"]["files"][1]["uri"],
+        format!("https://arweave.net/{}/1.png", manifest_id)
+    );
+    assert_eq!(status.number_of_files, 2);
+    assert_eq!(
+        status.file_paths[manifest_path.to_str().unwrap()]["id"],
+        manifest_id.to_string()
+    );
+    let manifest = arweave.create_manifest_from_bundle_statuses(vec![status], None)?;
+    let keys: Vec<&String> = manifest["paths"].as_object().unwrap().keys().collect();
+    assert_eq!(keys, vec!["tests/fixtures/2.png"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_post_manifest_bundle() -> Result<(), Error> {
+    let gateway = MockGateway::serve();
+    let arweave = get_mock_arweave(&gateway).await?;
+    let temp_dir = TempDir::from_str("./tests/").await?;
+    let (bundle_status, last_chunk) = manifest_bundle_chunks(&arweave, temp_dir.0.clone()).await?;
+
+    let (manifest_id, status) = arweave
+        .post_manifest_bundle(
+            last_chunk,
+            Vec::new(),
+            vec![bundle_status],
+            temp_dir.0.clone(),
+            (0, 0),
+            1,
+            RetryPolicy::default(),
+        )
+        .await?;
+    check_manifest_bundle(&arweave, &gateway, &manifest_id, status, temp_dir.0.clone()).await
+}
+
+#[tokio::test]
+async fn test_post_manifest_bundle_with_sol() -> Result<(), Error> {
+    let gateway = MockGateway::serve();
+    let solana = MockSolana::serve(PathBuf::from("tests/fixtures/arweave_dev.json"));
+    let arweave = get_mock_arweave(&gateway).await?;
+    let temp_dir = TempDir::from_str("./tests/").await?;
+    let (bundle_status, last_chunk) = manifest_bundle_chunks(&arweave, temp_dir.0.clone()).await?;
+
+    let (manifest_id, status) = arweave
+        .post_manifest_bundle_with_sol(
+            last_chunk,
+            Vec::new(),
+            vec![bundle_status],
+            temp_dir.0.clone(),
+            (0, 0),
+            1,
+            RetryPolicy::default(),
+            solana.url.clone(),
+            solana.sol_ar_url.clone(),
+            &keypair::read_keypair_file("tests/fixtures/solana_test.json")?,
+        )
+        .await?;
+
+    // The bundle with the manifest is paid for once and signed by the sol_ar service.
+    assert_eq!(solana.payments().len(), 1);
+    let signer = Provider::from_keypair_path(PathBuf::from("tests/fixtures/arweave_dev.json"))
+        .await?
+        .keypair_modulus()?;
+    assert_eq!(
+        gateway.posted_transactions()[1]["owner"],
+        signer.to_string()
+    );
+    assert!(status.sol_sig.is_some());
+    assert!(glob(&format!("{}/unposted/*", temp_dir.0.display()))?
+        .next()
+        .is_none());
+    check_manifest_bundle(&arweave, &gateway, &manifest_id, status, temp_dir.0.clone()).await
+}
+
+async fn sign_with_sol(
+    arweave: &Arweave,
+    solana: &MockSolana,
+    from_keypair: &keypair::Keypair,
+    data: &[u8],
+    anchor: u8,
+) -> Result<(), Error> {
+    let transaction = arweave
+        .create_transaction(
+            data.to_vec(),
+            None,
+            Some(Base64(vec![anchor; 32])),
+            (0, 0),
+            true,
+        )
+        .await?;
+    arweave
+        .sign_transaction_with_sol(
+            transaction,
+            solana.url.clone(),
+            solana.sol_ar_url.clone(),
+            from_keypair,
+        )
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sol_payment_not_repeated() -> Result<(), Error> {
+    let gateway = MockGateway::serve();
+    let solana = MockSolana::serve(PathBuf::from("tests/fixtures/arweave_dev.json"));
+    let arweave = get_mock_arweave(&gateway).await?;
+    let from_keypair = keypair::read_keypair_file("tests/fixtures/solana_test.json")?;
+
+    // The transfer is still pending when the service fails, so the same one is sent again.
+    solana.set_pending(true);
+    solana.fail_next(1);
+    sign_with_sol(&arweave, &solana, &from_keypair, b"data", 0).await?;
+    assert_eq!(solana.payments().len(), 2);
+    assert_eq!(solana.transfers().len(), 1);
+
+    // Once confirmed, it's found for the same data even with a new anchor.
+    solana.set_pending(false);
+    sign_with_sol(&arweave, &solana, &from_keypair, b"data", 1).await?;
+    assert_eq!(solana.payments().len(), 3);
+    assert_eq!(solana.transfers().len(), 1);
+
+    sign_with_sol(&arweave, &solana, &from_keypair, b"other data", 1).await?;
+    let transfers = solana.transfers();
+    assert_eq!(transfers.len(), 2);
+    assert_ne!(transfers[0].memo, transfers[1].memo);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sol_payment_includes_tip() -> Result<(), Error> {
+    let gateway = MockGateway::serve();
+    let solana = MockSolana::serve(PathBuf::from("tests/fixtures/arweave_dev.json"));
+    let mut arweave = get_mock_arweave(&gateway).await?;
+    let from_keypair = keypair::read_keypair_file("tests/fixtures/solana_test.json")?;
+    let tip = Tip::from_str("MlV6DeOtRmakDOf6vgOBlif795tcWimgyPsYYNQ8q1Y:0.01")?;
+    arweave.tip = Some(tip.clone());
+
+    let status = arweave
+        .upload_file_from_path_with_sol(
+            PathBuf::from("tests/fixtures/0.png"),
+            None,
+            None,
+            None,
+            arweave.get_price_terms(1.0).await?,
+            solana.url.clone(),
+            solana.sol_ar_url.clone(),
+            &from_keypair,
+        )
+        .await?;
+    let posted = gateway.posted_transactions();
+    assert_eq!(posted[0]["target"], tip.target.to_string());
+    assert_eq!(posted[0]["quantity"], tip.quantity.to_string());
+    assert_eq!(
+        solana.transfers()[0].lamports,
+        std::cmp::max((status.reward + tip.quantity) / RATE, FLOOR)
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upload_nft_pair() -> Result<(), Error> {
     let arweave = get_arweave().await?;
     // Don't run if test server is not running.
     if let Err(_) = reqwest::get(arweave.base_url.join("info")?).await {
@@ -105,6 +543,73 @@ async fn test_update_status() -> Result<(), Error> {
     }
 
     airdrop(&arweave).await?;
+    mine(&arweave).await?;
+    let (metadata_id, image_id) = arweave
+        .upload_nft_pair(
+            fs::read("tests/fixtures/0.png").await?,
+            "image/png",
+            serde_json::json!({"name": "0"}),
+            NftPairOptions {
+                no_bundle: true,
+                ..NftPairOptions::default()
+            },
+        )
+        .await?;
+    mine(&arweave).await?;
+
+    let metadata: serde_json::Value =
+        serde_json::from_slice(&arweave.download_data(&metadata_id).await?)?;
+    assert_eq!(
+        metadata["image"],
+        format!("https://arweave.net/{}", image_id)
+    );
+    assert_eq!(metadata["properties"]["files"][0]["uri"], metadata["image"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upload_file_with_tip() -> Result<(), Error> {
+    let mut arweave = get_arweave().await?;
+    // Don't run if test server is not running.
+    if let Err(_) = reqwest::get(arweave.base_url.join("info")?).await {
+        println!("Test server not running.");
+        return Ok(());
+    }
+
+    airdrop(&arweave).await?;
+    mine(&arweave).await?;
+    let target = "7eV1qae4qVNqsNChg3Scdi-DpOLJPCogct4ixoq1WNg";
+    arweave.tip = Some(Tip::from_str(&format!("{}:0.5", target))?);
+    let target_balance = arweave.get_wallet_balance(Some(target.to_string())).await?;
+    let owner_balance = arweave.get_wallet_balance(None).await?;
+
+    let file_path = PathBuf::from("tests/fixtures/0.png");
+    arweave
+        .check_wallet_balance(&[file_path.clone()], (0, 0))
+        .await?;
+    let status = arweave
+        .upload_file_from_path(file_path, None, None, None, (0, 0))
+        .await?;
+    assert_eq!(status.tip, arweave.tip);
+
+    let transaction = arweave.get_transaction(&status.id).await?;
+    assert_eq!(transaction.target.to_string(), target);
+    assert_eq!(transaction.quantity, 500_000_000_000);
+
+    mine(&arweave).await?;
+    assert_eq!(
+        arweave.get_wallet_balance(Some(target.to_string())).await?,
+        target_balance + 500_000_000_000u64
+    );
+    assert!(arweave.get_wallet_balance(None).await? <= owner_balance - 500_000_000_000u64);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_update_status() -> Result<(), Error> {
+    let gateway = MockGateway::serve();
+    let arweave = get_mock_arweave(&gateway).await?;
+
     let file_path = PathBuf::from("tests/fixtures/0.png");
     let temp_log_dir = TempDir::from_str("./tests/").await?;
     let log_dir = temp_log_dir.0.clone();
@@ -118,17 +623,45 @@ async fn test_update_status() -> Result<(), Error> {
         .await?;
     assert_eq!(read_status.status, StatusCode::Submitted);
 
-    let url = arweave.base_url.join("mine")?;
-    let resp = reqwest::get(url).await?.text().await?;
-    println!("mine resp: {}", resp);
-
+    gateway.mine();
     let updated_status = arweave.update_status(file_path, log_dir.clone()).await?;
-    println!("{:?}", &updated_status);
     assert_eq!(updated_status.status, StatusCode::Confirmed);
     assert!(updated_status.last_modified > read_status.last_modified);
     Ok(())
 }
 
+#[tokio::test]
+async fn test_confirmations_match_network_height() -> Result<(), Error> {
+    let arweave = get_arweave().await?;
+    // Don't run if test server is not running.
+    if let Err(_) = reqwest::get(arweave.base_url.join("info")?).await {
+        println!("Test server not running.");
+        return Ok(());
+    }
+
+    airdrop(&arweave).await?;
+    let file_path = PathBuf::from("tests/fixtures/0.png");
+    let temp_log_dir = TempDir::from_str("./tests/").await?;
+    let log_dir = temp_log_dir.0.clone();
+
+    let status = arweave
+        .upload_file_from_path(file_path, Some(log_dir), None, None, (0, 0))
+        .await?;
+    for _ in 0..4 {
+        mine(&arweave).await?;
+    }
+
+    let updated_status = arweave.get_status(&status.id).await?;
+    let raw_status = updated_status.raw_status.unwrap();
+    let info = arweave.get_network_info().await?;
+    assert_eq!(
+        raw_status.number_of_confirmations,
+        info.height - raw_status.block_height + 1
+    );
+    assert!(raw_status.number_of_confirmations >= 4);
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_upload_files_from_paths_without_tags() -> Result<(), Error> {
     let arweave = get_arweave().await?;
@@ -341,15 +874,146 @@ async fn test_upload_files_stream() -> Result<(), Error> {
 
     let mut stream = upload_files_stream(&arweave, paths_iter, None, None, None, (0, 0), 3);
 
-    let output_format = OutputFormat::JsonCompact;
+    let mut printer = RecordPrinter::new::<Status>(&OutputFormat::JsonCompact);
+    let mut output = String::new();
+    let mut ids = Vec::new();
+    while let Some(result) = stream.next().await {
+        let status = result.map_err(|e| e.error)?;
+        output.push_str(&printer.record_string(&status));
+        ids.push(status.id.to_string());
+    }
+    output.push_str(&printer.finish_string());
+    print!("{}", output);
+
+    let printed: Vec<serde_json::Value> = serde_json::from_str(&output)?;
+    assert_eq!(
+        printed.iter().map(|s| s["id"].as_str()).collect::<Vec<_>>(),
+        ids.iter().map(|id| Some(id.as_str())).collect::<Vec<_>>()
+    );
+    Ok(())
+}
 
-    let mut counter = 0;
-    while let Some(Ok(status)) = stream.next().await {
-        if counter == 0 {
-            println!("{}", status.header_string(&output_format));
+/// Runs the arloader binary with `args`, returning what it printed to stdout.
+fn run_arloader(args: &[&str]) -> String {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_arloader"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[tokio::test]
+async fn test_upload_json_output() -> Result<(), Error> {
+    let gateway = MockGateway::serve();
+    for (output_format, no_bundle) in [
+        ("json", false),
+        ("json-compact", true),
+        ("json-lines", false),
+    ] {
+        let temp_dir = TempDir::from_str("./tests/").await?;
+        let log_dir = temp_dir.0.display().to_string();
+        let mut args = vec![
+            "upload",
+            "tests/fixtures/0.png",
+            "tests/fixtures/1.png",
+            "--ar-keypair-path",
+            "tests/fixtures/test_key0.json",
+            "--base-url",
+            gateway.url.as_str(),
+            "--log-dir",
+            &log_dir,
+            "--output",
+            output_format,
+        ];
+        if no_bundle {
+            args.push("--no-bundle");
+        }
+        let stdout = run_arloader(&args);
+
+        // The whole of stdout is the records, followed by the summary and metrics of bundles.
+        let records: Vec<serde_json::Value> = if output_format == "json-lines" {
+            stdout
+                .lines()
+                .map(serde_json::from_str)
+                .collect::<Result<_, _>>()?
+        } else {
+            serde_json::from_str(&stdout)?
+        };
+        let ids: Vec<&str> = records.iter().filter_map(|r| r["id"].as_str()).collect();
+        if no_bundle {
+            assert_eq!(records.len(), 2);
+            assert_eq!(ids.len(), 2);
+        } else {
+            assert_eq!(records.len(), 3);
+            assert_eq!(ids.len(), 1);
+            assert_eq!(records[1]["summary"]["number_of_files"], 2);
+            assert!(records[2]["metrics"].is_object());
         }
-        print!("{}", output_format.formatted_string(&status));
-        counter += 1;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_update_nft_status_json_output() -> Result<(), Error> {
+    let gateway = MockGateway::serve();
+    let temp_dir = TempDir::from_str("./tests/").await?;
+    for (stage, id) in [("assets", 1), ("metadata", 2)] {
+        let stage_dir = temp_dir.0.join(stage);
+        fs::create_dir(&stage_dir).await?;
+        let status = BundleStatus {
+            id: Base64(vec![id; 32]),
+            ..BundleStatus::default()
+        };
+        fs::write(
+            stage_dir.join(format!("{}.json", status.id)),
+            serde_json::to_string(&status)?,
+        )
+        .await?;
+    }
+    let manifest_id = Base64(vec![3; 32]);
+    fs::write(
+        temp_dir
+            .0
+            .join("assets")
+            .join(format!("manifest_{}.json", manifest_id)),
+        "{}",
+    )
+    .await?;
+
+    let log_dir = temp_dir.0.display().to_string();
+    for output_format in ["json", "json-compact", "json-lines"] {
+        let stdout = run_arloader(&[
+            "update-nft-status",
+            &log_dir,
+            "--base-url",
+            gateway.url.as_str(),
+            "--output",
+            output_format,
+        ]);
+
+        // Both stages and the manifest are in one array, or on lines of their own.
+        let records: Vec<serde_json::Value> = if output_format == "json-lines" {
+            stdout
+                .lines()
+                .map(serde_json::from_str)
+                .collect::<Result<_, _>>()?
+        } else {
+            serde_json::from_str(&stdout)?
+        };
+        let ids: Vec<&str> = records.iter().filter_map(|r| r["id"].as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                Base64(vec![1; 32]).to_string(),
+                Base64(vec![2; 32]).to_string(),
+                manifest_id.to_string(),
+            ]
+        );
     }
     Ok(())
 }
@@ -404,6 +1068,7 @@ async fn test_upload_file_from_path_with_sol() -> Result<(), Error> {
         .await?;
 
     println!("{:?}", status);
+    assert!(status.sol_sig.is_some());
 
     let read_status = arweave.read_status(file_path, log_dir.clone()).await?;
     println!("{:?}", &read_status);
@@ -411,6 +1076,55 @@ async fn test_upload_file_from_path_with_sol() -> Result<(), Error> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_sol_sig_on_returned_statuses() -> Result<(), Error> {
+    let gateway = MockGateway::serve();
+    let solana = MockSolana::serve(PathBuf::from("tests/fixtures/arweave_dev.json"));
+    let arweave = get_mock_arweave(&gateway).await?;
+    let from_keypair = keypair::read_keypair_file("tests/fixtures/solana_test.json")?;
+    let price_terms = arweave.get_price_terms(1.0).await?;
+    let temp_dir = TempDir::from_str("./tests/").await?;
+
+    // The payment is on the returned status whether or not it's also written to a log dir.
+    for log_dir in [None, Some(temp_dir.0.clone())] {
+        let status = arweave
+            .upload_file_from_path_with_sol(
+                PathBuf::from("tests/fixtures/0.png"),
+                log_dir.clone(),
+                None,
+                None,
+                price_terms,
+                solana.url.clone(),
+                solana.sol_ar_url.clone(),
+                &from_keypair,
+            )
+            .await?;
+        assert_eq!(status.sol_sig.map(|s| s.ar_tx_id), Some(status.id.clone()));
+
+        let paths = vec![PathBuf::from("tests/fixtures/1.png")];
+        let data_size = std::fs::metadata(&paths[0])?.len();
+        let bundle_status = arweave
+            .post_bundle_transaction_from_file_paths_with_sol(
+                PathsChunk(paths, data_size),
+                Vec::new(),
+                log_dir,
+                price_terms,
+                100,
+                RetryPolicy::default(),
+                solana.url.clone(),
+                solana.sol_ar_url.clone(),
+                &from_keypair,
+            )
+            .await?;
+        assert_eq!(
+            bundle_status.sol_sig.map(|s| s.ar_tx_id),
+            Some(bundle_status.id.clone())
+        );
+    }
+    assert_eq!(gateway.posted_transactions().len(), 4);
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_upload_bundle_from_file_paths() -> Result<(), Error> {
     let arweave = get_arweave().await?;
@@ -426,9 +1140,392 @@ async fn test_upload_bundle_from_file_paths() -> Result<(), Error> {
     let paths_chunks = arweave.chunk_file_paths(paths_iter, 2000000)?;
     println!("{:?}", paths_chunks);
     let status = arweave
-        .post_bundle_transaction_from_file_paths(paths_chunks[0].clone(), Vec::new(), (0, 0), 1)
+        .post_bundle_transaction_from_file_paths(
+            paths_chunks[0].clone(),
+            Vec::new(),
+            None,
+            (0, 0),
+            1,
+            RetryPolicy::default(),
+        )
         .await?;
 
     println!("{:?}", status);
     Ok(())
 }
+
+#[tokio::test]
+async fn test_upload_bundle_with_bundle_tags() -> Result<(), Error> {
+    let mut arweave = get_arweave().await?;
+
+    // Don't run if test server is not running.
+    if let Err(_) = reqwest::get(arweave.base_url.join("info")?).await {
+        println!("Test server not running.");
+        return Ok(());
+    }
+
+    airdrop(&arweave).await?;
+    arweave.bundle_tags = vec![
+        Tag::<String>::from_utf8_strs("File-Count", "{count}")?,
+        Tag::<String>::from_utf8_strs("First-Path", "{first_path}")?,
+    ];
+    let paths_iter = glob("tests/fixtures/[0-4].png")?.filter_map(Result::ok);
+    let paths_chunks = arweave.chunk_file_paths(paths_iter, 2000000)?;
+    let first_path = paths_chunks[0].0[0].display().to_string();
+    let status = arweave
+        .post_bundle_transaction_from_file_paths(
+            paths_chunks[0].clone(),
+            Vec::new(),
+            None,
+            (0, 0),
+            1,
+            RetryPolicy::default(),
+        )
+        .await?;
+    mine(&arweave).await?;
+
+    let transaction = arweave.get_transaction(&status.id).await?;
+    let tags: Vec<(String, String)> = transaction
+        .tags
+        .iter()
+        .map(|t| {
+            (
+                t.name.to_utf8_string().unwrap(),
+                t.value.to_utf8_string().unwrap(),
+            )
+        })
+        .collect();
+    assert!(tags.contains(&("File-Count".to_string(), "5".to_string())));
+    assert!(tags.contains(&("First-Path".to_string(), first_path)));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upload_bundle_progress() -> Result<(), Error> {
+    let arweave = get_arweave().await?;
+
+    // Don't run if test server is not running.
+    if let Err(_) = reqwest::get(arweave.base_url.join("info")?).await {
+        println!("Test server not running.");
+        return Ok(());
+    }
+
+    airdrop(&arweave).await?;
+    let temp_dir = TempDir::from_str("./tests/").await?;
+    let log_dir = temp_dir.0.join("status/");
+    fs::create_dir_all(&log_dir).await?;
+    let file_path = temp_dir.0.join("12mb.bin");
+    let data: Vec<u8> = (0..12_000_000).map(|_| rand::random::<u8>()).collect();
+    fs::write(&file_path, data).await?;
+    let paths_chunks = arweave.chunk_file_paths(iter::once(file_path), 20_000_000)?;
+
+    // Records the progress written to disk while the bundle is posted.
+    let watch_dir = log_dir.clone();
+    let watcher = tokio::spawn(async move {
+        let mut seen: Vec<usize> = Vec::new();
+        loop {
+            for path in glob(&format!("{}*.json", watch_dir.display()))
+                .unwrap()
+                .filter_map(Result::ok)
+            {
+                let status = fs::read_to_string(path)
+                    .await
+                    .ok()
+                    .and_then(|s| serde_json::from_str::<BundleStatus>(&s).ok());
+                if let Some(progress) = status.and_then(|s| s.upload_progress) {
+                    if seen.last() != Some(&progress.chunks_posted) {
+                        seen.push(progress.chunks_posted);
+                    }
+                    if progress.chunks_posted == progress.chunks_total {
+                        return seen;
+                    }
+                }
+            }
+            sleep(Duration::from_millis(5)).await;
+        }
+    });
+
+    let status = arweave
+        .post_bundle_transaction_from_file_paths(
+            paths_chunks[0].clone(),
+            Vec::new(),
+            Some(log_dir.clone()),
+            (0, 0),
+            1,
+            RetryPolicy::default(),
+        )
+        .await?;
+    let seen = watcher.await.unwrap();
+    println!("{:?}", seen);
+
+    let on_disk: BundleStatus = serde_json::from_str(
+        &fs::read_to_string(log_dir.join(status.id.to_string()).with_extension("json")).await?,
+    )?;
+    let progress = on_disk.upload_progress.unwrap();
+    assert!(progress.chunks_total > 40);
+    assert_eq!(progress.chunks_posted, progress.chunks_total);
+    assert!(seen.len() > 1);
+    assert!(seen
+        .iter()
+        .all(|&n| n % 10 == 0 || n == progress.chunks_total));
+    Ok(())
+}
+
+/// Posts a bundle of copies of some fixtures in `temp_dir` so they can be changed afterwards.
+async fn post_bundle_of_copies(
+    arweave: &Arweave,
+    temp_dir: &TempDir,
+) -> Result<BundleStatus, Error> {
+    let mut paths = Vec::new();
+    for path in glob("tests/fixtures/[0-2].png")?.filter_map(Result::ok) {
+        let copy = temp_dir.0.join(path.file_name().unwrap());
+        fs::copy(&path, &copy).await?;
+        paths.push(copy);
+    }
+    let paths_chunks = arweave.chunk_file_paths(paths.into_iter(), 2_000_000)?;
+    let status = arweave
+        .post_bundle_transaction_from_file_paths(
+            paths_chunks[0].clone(),
+            Vec::new(),
+            None,
+            (0, 0),
+            1,
+            RetryPolicy::default(),
+        )
+        .await?;
+    mine(arweave).await?;
+    Ok(status)
+}
+
+#[tokio::test]
+async fn test_verify_bundle_status() -> Result<(), Error> {
+    let arweave = get_arweave().await?;
+
+    // Don't run if test server is not running.
+    if let Err(_) = reqwest::get(arweave.base_url.join("info")?).await {
+        println!("Test server not running.");
+        return Ok(());
+    }
+
+    airdrop(&arweave).await?;
+    let temp_dir = TempDir::from_str("./tests/").await?;
+    let status = post_bundle_of_copies(&arweave, &temp_dir).await?;
+
+    let checks = arweave.verify_bundle_status(&status).await?;
+    assert_eq!(checks.len(), 3);
+    assert!(checks.iter().all(|c| c.is_ok()));
+    assert!(checks
+        .iter()
+        .all(|c| c.local == Some(LocalFileState::Ok) && c.file_path.is_some()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_verify_bundle_status_local_file_changed() -> Result<(), Error> {
+    let arweave = get_arweave().await?;
+
+    // Don't run if test server is not running.
+    if let Err(_) = reqwest::get(arweave.base_url.join("info")?).await {
+        println!("Test server not running.");
+        return Ok(());
+    }
+
+    airdrop(&arweave).await?;
+    let temp_dir = TempDir::from_str("./tests/").await?;
+    let status = post_bundle_of_copies(&arweave, &temp_dir).await?;
+    let changed = temp_dir.0.join("1.png");
+    let mut data = fs::read(&changed).await?;
+    data[0] ^= 0xff;
+    fs::write(&changed, data).await?;
+
+    let checks = arweave.verify_bundle_status(&status).await?;
+    let failed: Vec<_> = checks.iter().filter(|c| !c.is_ok()).collect();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].file_path, Some(changed));
+    assert_eq!(failed[0].local, Some(LocalFileState::Modified));
+    assert_eq!(failed[0].invalid, None);
+    assert_eq!(failed[0].occurrences, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upload_bundles_metrics() -> Result<(), Error> {
+    let arweave = get_arweave().await?;
+
+    // Don't run if test server is not running.
+    if let Err(_) = reqwest::get(arweave.base_url.join("info")?).await {
+        println!("Test server not running.");
+        return Ok(());
+    }
+
+    airdrop(&arweave).await?;
+    let paths: Vec<PathBuf> = glob("tests/fixtures/[0-4].png")?
+        .filter_map(Result::ok)
+        .collect();
+    let mut data_size = 0;
+    for path in &paths {
+        data_size += fs::metadata(path).await?.len();
+    }
+    let paths_chunks = arweave.chunk_file_paths(paths.into_iter(), 100_000)?;
+
+    let mut metrics = Metrics::start(
+        arweave.post_retries.load(Ordering::Relaxed),
+        &arweave.connection_stats,
+    );
+    let mut stream = upload_bundles_stream(
+        &arweave,
+        paths_chunks.clone(),
+        Vec::new(),
+        None,
+        (0, 0),
+        2,
+        RetryPolicy::default(),
+    );
+    while let Some(result) = stream.next().await {
+        metrics.add(&result.unwrap());
+    }
+    metrics.finish(
+        arweave.post_retries.load(Ordering::Relaxed),
+        &arweave.connection_stats,
+    );
+    println!("{}", metrics.metrics_string(&OutputFormat::Display));
+
+    assert_eq!(metrics.bundles, paths_chunks.len() as u64);
+    assert_eq!(metrics.items, 5);
+    assert!(metrics.bytes_posted > data_size);
+    assert!(metrics.elapsed_secs > 0.0 && metrics.mb_per_sec > 0.0);
+    assert!(metrics.avg_post_latency_secs > 0.0);
+    assert!(metrics.avg_post_latency_secs <= metrics.elapsed_secs);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upload_nfts_no_bundle() -> Result<(), Error> {
+    let arweave = get_arweave().await?;
+
+    // Don't run if test server is not running.
+    if let Err(_) = reqwest::get(arweave.base_url.join("info")?).await {
+        println!("Test server not running.");
+        return Ok(());
+    }
+
+    airdrop(&arweave).await?;
+    let temp_dir = TempDir::from_str("./tests/").await?;
+    for i in 0..3 {
+        for ext in ["png", "json"] {
+            let file_name = format!("{}.{}", i, ext);
+            fs::copy(
+                PathBuf::from("tests/fixtures").join(&file_name),
+                temp_dir.0.join(&file_name),
+            )
+            .await?;
+        }
+    }
+    let paths_iter = (0..3).map(|i| temp_dir.0.join(format!("{}.png", i)));
+    let log_dir = temp_dir.0.join("status/");
+
+    command_upload_nfts(
+        &arweave,
+        paths_iter,
+        Some(log_dir.clone()),
+        10_000_000,
+        1.0,
+        &OutputFormat::Display,
+        5,
+        None,
+        false,
+        true,
+        false,
+        false,
+        None,
+    )
+    .await?;
+    mine(&arweave).await?;
+
+    for (sub_dir, ext) in [("assets", "png"), ("metadata", "json")] {
+        let sub_log_dir = log_dir.join(sub_dir);
+        let statuses = arweave
+            .read_statuses(
+                (0..3).map(|i| temp_dir.0.join(format!("{}.{}", i, ext))),
+                sub_log_dir.clone(),
+            )
+            .await?;
+        assert_eq!(statuses.len(), 3);
+
+        let manifest_path = glob(&format!("{}/manifest_*.json", sub_log_dir.display()))?
+            .filter_map(Result::ok)
+            .nth(0)
+            .unwrap();
+        let manifest: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(manifest_path).await?)?;
+        assert_eq!(manifest.as_object().unwrap().len(), 3);
+        for status in statuses {
+            let key = status.file_path.unwrap().display().to_string();
+            assert_eq!(manifest[&key]["id"], status.id.to_string());
+        }
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upload_nft_collection() -> Result<(), Error> {
+    let arweave = get_arweave().await?;
+
+    // Don't run if test server is not running.
+    if let Err(_) = reqwest::get(arweave.base_url.join("info")?).await {
+        println!("Test server not running.");
+        return Ok(());
+    }
+
+    airdrop(&arweave).await?;
+    let temp_dir = TempDir::from_str("./tests/").await?;
+    for i in 0..3 {
+        for ext in ["png", "json"] {
+            let file_name = format!("{}.{}", i, ext);
+            fs::copy(
+                PathBuf::from("tests/fixtures").join(&file_name),
+                temp_dir.0.join(&file_name),
+            )
+            .await?;
+        }
+    }
+    let assets: Vec<PathBuf> = (0..3)
+        .map(|i| temp_dir.0.join(format!("{}.png", i)))
+        .collect();
+
+    let result = arweave
+        .upload_nft_collection(
+            assets.clone(),
+            NftUploadOptions {
+                log_dir: Some(temp_dir.0.join("status/")),
+                no_bundle: true,
+                ..NftUploadOptions::default()
+            },
+        )
+        .await?;
+    mine(&arweave).await?;
+
+    assert_eq!(result.tokens.len(), 3);
+    assert!(result.metaplex_items_path.exists());
+    let mut ids = vec![
+        result.asset_manifest_id.clone(),
+        result.metadata_manifest_id.clone(),
+    ];
+    for (token, asset) in result.tokens.iter().zip(&assets) {
+        assert_eq!(&token.asset_path, asset);
+        assert_eq!(
+            token.image_link,
+            format!("https://arweave.net/{}", token.image_id)
+        );
+        let metadata: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(asset.with_extension("json")).await?)?;
+        assert_eq!(metadata["image"], token.image_link);
+        ids.push(token.metadata_id.clone());
+    }
+
+    for id in ids {
+        let status = arweave.get_status(&id).await?;
+        assert_eq!(status.status, StatusCode::Confirmed);
+    }
+    Ok(())
+}